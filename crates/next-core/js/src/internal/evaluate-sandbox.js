@@ -0,0 +1,260 @@
+// Evaluates config files (e. g. postcss.config.js) in a sandbox and sends
+// their serialized result back to turbopack.
+//
+// This file is executed by node directly, without being processed by
+// turbopack, so it must only use CommonJS and node builtins.
+//
+// The config file and everything it requires are loaded by a separate module
+// loader, which only allows files within the project directory and a small set
+// of side effect free builtins. All loaded files are reported back, so
+// turbopack can re-evaluate the config when one of them changes.
+
+const net = require("node:net");
+const fs = require("node:fs");
+const path = require("node:path");
+const vm = require("node:vm");
+const { builtinModules, createRequire } = require("node:module");
+
+const ALLOWED_BUILTINS = new Set([
+  "assert",
+  "buffer",
+  "events",
+  "path",
+  "querystring",
+  "string_decoder",
+  "url",
+  "util",
+]);
+
+function createIpc(port) {
+  const socket = net.createConnection(port, "127.0.0.1");
+  const packets = [];
+  const waiting = [];
+  let buffer = Buffer.alloc(0);
+
+  socket.on("data", (chunk) => {
+    buffer = Buffer.concat([buffer, chunk]);
+    while (buffer.length >= 4) {
+      const length = buffer.readUInt32BE(0);
+      if (buffer.length < 4 + length) {
+        break;
+      }
+      const message = JSON.parse(buffer.toString("utf8", 4, 4 + length));
+      buffer = buffer.subarray(4 + length);
+      const resolve = waiting.shift();
+      if (resolve != null) {
+        resolve(message);
+      } else {
+        packets.push(message);
+      }
+    }
+  });
+
+  return {
+    recv() {
+      if (packets.length > 0) {
+        return Promise.resolve(packets.shift());
+      }
+      return new Promise((resolve) => waiting.push(resolve));
+    },
+    send(message) {
+      const packet = Buffer.from(JSON.stringify(message), "utf8");
+      const length = Buffer.alloc(4);
+      length.writeUInt32BE(packet.length);
+      socket.write(length);
+      return new Promise((resolve, reject) => {
+        socket.write(packet, (err) => (err != null ? reject(err) : resolve()));
+      });
+    },
+  };
+}
+
+class SandboxError extends Error {
+  constructor(message) {
+    super(message);
+    this.name = "SandboxError";
+  }
+}
+
+function isBuiltin(request) {
+  const name = request.startsWith("node:") ? request.slice(5) : request;
+  return builtinModules.includes(name) ? name.split("/")[0] : null;
+}
+
+// Loads the config file and its imports with a fresh module cache, so every
+// evaluation sees the current content of the files.
+function createLoader(root) {
+  const cache = new Map();
+  const dependencies = new Set();
+
+  function load(filename) {
+    const cached = cache.get(filename);
+    if (cached != null) {
+      return cached.exports;
+    }
+    if (!filename.startsWith(root + path.sep)) {
+      throw new SandboxError(
+        `${filename} is outside of the project directory and can't be loaded while evaluating a config`
+      );
+    }
+    dependencies.add(filename);
+
+    const module = { exports: {}, filename };
+    cache.set(filename, module);
+    const source = fs.readFileSync(filename, "utf8");
+    if (path.extname(filename) === ".json") {
+      module.exports = JSON.parse(source);
+      return module.exports;
+    }
+    if (path.extname(filename) === ".mjs") {
+      throw new SandboxError(
+        `${filename} is an ES module, only CommonJS is supported while evaluating a config`
+      );
+    }
+
+    const fn = vm.compileFunction(
+      source,
+      ["exports", "require", "module", "__filename", "__dirname"],
+      { filename }
+    );
+    fn.call(
+      module.exports,
+      module.exports,
+      createSandboxRequire(filename),
+      module,
+      filename,
+      path.dirname(filename)
+    );
+    return module.exports;
+  }
+
+  function createSandboxRequire(parent) {
+    const resolver = createRequire(parent);
+    const sandboxRequire = (request) => {
+      const builtin = isBuiltin(request);
+      if (builtin != null) {
+        if (!ALLOWED_BUILTINS.has(builtin)) {
+          throw new SandboxError(
+            `the builtin module "${request}" can't be used while evaluating a config`
+          );
+        }
+        return require(`node:${builtin}`);
+      }
+      return load(resolver.resolve(request));
+    };
+    sandboxRequire.resolve = (request) => resolver.resolve(request);
+    return sandboxRequire;
+  }
+
+  return { load, dependencies };
+}
+
+// Only plain JSON values can be sent back. Everything else is an error instead
+// of being dropped silently by JSON.stringify.
+//
+// In lossy mode, unsupported values are replaced by a description instead:
+// `{ $unsupported: "function", name }` for functions and
+// `{ $unsupported: className, properties }` for instances of classes. This
+// allows to read the plain parts of configs which can contain class instances,
+// e. g. plugins in a webpack config.
+function serialize(value, keyPath, lossy, seen = new Set()) {
+  if (
+    value === null ||
+    typeof value === "string" ||
+    typeof value === "boolean"
+  ) {
+    return value;
+  }
+  if (typeof value === "number") {
+    if (!Number.isFinite(value)) {
+      if (lossy) {
+        return { $unsupported: "number", value: String(value) };
+      }
+      throw new SandboxError(`${keyPath} is not a finite number`);
+    }
+    return value;
+  }
+  if (typeof value === "function" && lossy) {
+    return { $unsupported: "function", name: value.name };
+  }
+  if (typeof value === "object") {
+    if (seen.has(value)) {
+      throw new SandboxError(`${keyPath} is a circular reference`);
+    }
+    seen.add(value);
+    try {
+      if (Array.isArray(value)) {
+        return value.map((item, i) =>
+          item === undefined && lossy
+            ? null
+            : serialize(item, `${keyPath}[${i}]`, lossy, seen)
+        );
+      }
+      const result = {};
+      for (const [key, item] of Object.entries(value)) {
+        if (item !== undefined) {
+          result[key] = serialize(item, `${keyPath}.${key}`, lossy, seen);
+        }
+      }
+      const prototype = Object.getPrototypeOf(value);
+      if (prototype !== Object.prototype && prototype !== null) {
+        const className = value.constructor?.name ?? "a class";
+        if (lossy) {
+          return { $unsupported: className, properties: result };
+        }
+        throw new SandboxError(
+          `${keyPath} is an instance of ${className}, only plain objects can be returned from a config`
+        );
+      }
+      return result;
+    } finally {
+      seen.delete(value);
+    }
+  }
+  throw new SandboxError(
+    `${keyPath} is a ${typeof value}, which can't be returned from a config`
+  );
+}
+
+async function evaluate({ root, file, lossy }) {
+  const loader = createLoader(root);
+  try {
+    let exports = loader.load(file);
+    if (exports != null && exports.__esModule && "default" in exports) {
+      exports = exports.default;
+    }
+    if (typeof exports === "function") {
+      exports = await exports({});
+    }
+    return {
+      type: "value",
+      value: serialize(await exports, "config", lossy),
+      dependencies: [...loader.dependencies],
+    };
+  } catch (err) {
+    return {
+      type: "error",
+      name: err?.name ?? "Error",
+      message: err?.message ?? String(err),
+      stack: typeof err?.stack === "string" ? err.stack : "",
+      dependencies: [...loader.dependencies],
+    };
+  }
+}
+
+(async () => {
+  const ipc = createIpc(parseInt(process.argv[2], 10));
+  while (true) {
+    const message = await ipc.recv();
+    switch (message.type) {
+      case "evaluate": {
+        await ipc.send(await evaluate(message));
+        break;
+      }
+      default: {
+        console.error("unexpected message type", message.type);
+        process.exit(1);
+      }
+    }
+  }
+})();
@@ -0,0 +1,117 @@
+// Transforms CSS files with PostCSS and sends the result back to turbopack.
+//
+// This file is executed by node directly, without being processed by
+// turbopack, so it must only use CommonJS and node builtins.
+//
+// The plugins are taken from the postcss config evaluated by
+// evaluate-sandbox.js. PostCSS and the plugins are required from the project
+// directory. The files and directories plugins report in `dependency` and
+// `dir-dependency` messages are sent back, so turbopack can transform the CSS
+// again when one of them changes.
+
+const net = require("node:net");
+const path = require("node:path");
+const { createRequire } = require("node:module");
+
+// Uses the same protocol as evaluate-sandbox.js.
+function createIpc(port) {
+  const socket = net.createConnection(port, "127.0.0.1");
+  const packets = [];
+  const waiting = [];
+  let buffer = Buffer.alloc(0);
+
+  socket.on("data", (chunk) => {
+    buffer = Buffer.concat([buffer, chunk]);
+    while (buffer.length >= 4) {
+      const length = buffer.readUInt32BE(0);
+      if (buffer.length < 4 + length) {
+        break;
+      }
+      const message = JSON.parse(buffer.toString("utf8", 4, 4 + length));
+      buffer = buffer.subarray(4 + length);
+      const resolve = waiting.shift();
+      if (resolve != null) {
+        resolve(message);
+      } else {
+        packets.push(message);
+      }
+    }
+  });
+
+  return {
+    recv() {
+      if (packets.length > 0) {
+        return Promise.resolve(packets.shift());
+      }
+      return new Promise((resolve) => waiting.push(resolve));
+    },
+    send(message) {
+      const packet = Buffer.from(JSON.stringify(message), "utf8");
+      const length = Buffer.alloc(4);
+      length.writeUInt32BE(packet.length);
+      socket.write(length);
+      return new Promise((resolve, reject) => {
+        socket.write(packet, (err) => (err != null ? reject(err) : resolve()));
+      });
+    },
+  };
+}
+
+function loadPlugin(projectRequire, name, options) {
+  let plugin = projectRequire(name);
+  if (plugin != null && plugin.__esModule && "default" in plugin) {
+    plugin = plugin.default;
+  }
+  return options == null || options === true ? plugin() : plugin(options);
+}
+
+async function transform({ cwd, file, css, plugins }) {
+  const dependencies = new Set();
+  const dirDependencies = [];
+  try {
+    const projectRequire = createRequire(path.join(cwd, "package.json"));
+    const postcss = projectRequire("postcss");
+    const result = await postcss(
+      plugins.map(([name, options]) => loadPlugin(projectRequire, name, options))
+    ).process(css, { from: file, to: file, map: false });
+    for (const message of result.messages) {
+      if (message.type === "dependency") {
+        dependencies.add(message.file);
+      } else if (message.type === "dir-dependency") {
+        dirDependencies.push({ dir: message.dir, glob: message.glob ?? "**" });
+      }
+    }
+    return {
+      type: "css",
+      css: result.css,
+      dependencies: [...dependencies],
+      dirDependencies,
+    };
+  } catch (err) {
+    return {
+      type: "error",
+      name: err?.name ?? "Error",
+      message: err?.message ?? String(err),
+      stack: typeof err?.stack === "string" ? err.stack : "",
+      dependencies: [...dependencies],
+      dirDependencies,
+    };
+  }
+}
+
+(async () => {
+  const ipc = createIpc(parseInt(process.argv[2], 10));
+  while (true) {
+    const message = await ipc.recv();
+    switch (message.type) {
+      case "transform": {
+        await ipc.send(await transform(message));
+        break;
+      }
+      default: {
+        console.error("unexpected message type", message.type);
+        process.exit(1);
+      }
+    }
+  }
+})();
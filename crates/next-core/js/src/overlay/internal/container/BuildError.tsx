@@ -32,7 +32,30 @@ export const BuildError: React.FC<BuildErrorProps> = function BuildError({
             </h4>
           </DialogHeader>
           <DialogBody className="nextjs-container-build-error-body">
-            <Terminal content={issue.formatted} />
+            {issue.source == null ? (
+              <Terminal content={issue.formatted} />
+            ) : (
+              <>
+                <p className="nextjs-container-build-error-location">
+                  {issue.source.asset.path}:{issue.source.start.line + 1}:
+                  {issue.source.start.column + 1}
+                </p>
+                <h5>{issue.title}</h5>
+                <Terminal content={issue.source.code_frame} />
+                {issue.description ? <p>{issue.description}</p> : null}
+                {issue.documentation_link ? (
+                  <p>
+                    <a
+                      href={issue.documentation_link}
+                      target="_blank"
+                      rel="noopener noreferrer"
+                    >
+                      Learn more
+                    </a>
+                  </p>
+                ) : null}
+              </>
+            )}
             <footer>
               <p id="nextjs__container_build_error_desc">
                 <small>
@@ -55,6 +78,11 @@ export const styles = css`
     padding: 0;
   }
 
+  .nextjs-container-build-error-location {
+    color: var(--color-ansi-red);
+    margin: 0;
+  }
+
   .nextjs-container-build-error-body footer {
     margin-top: var(--size-gap);
   }
@@ -11,7 +11,7 @@ use turbo_tasks_fs::{
     FileSystemPathVc,
 };
 use turbopack::{
-    ecmascript::EcmascriptInputTransform,
+    ecmascript::{EcmascriptInputTransform, JsxRuntime},
     transition::{TransitionVc, TransitionsByNameVc},
     ModuleAssetContextVc,
 };
@@ -39,7 +39,9 @@ use crate::{
         next_layout_entry_transition::NextLayoutEntryTransition, LayoutSegment, LayoutSegmentsVc,
     },
     embed_js::{next_js_file, wrap_with_next_js_fs},
+    env::filter_for_client,
     fallback::get_fallback_page,
+    issue_codes,
     next_client::{
         context::{
             get_client_chunking_context, get_client_environment, get_client_module_options_context,
@@ -59,6 +61,7 @@ use crate::{
     nodejs::{
         create_node_rendered_source,
         node_entry::{NodeRenderingEntry, NodeRenderingEntryVc},
+        pool::NodeJsPoolOptionsVc,
         NodeEntry, NodeEntryVc,
     },
     util::regular_expression_for_path,
@@ -69,18 +72,25 @@ fn next_client_chunks_transition(
     project_root: FileSystemPathVc,
     app_dir: FileSystemPathVc,
     server_root: FileSystemPathVc,
+    env: ProcessEnvVc,
     browserslist_query: &str,
+    hermetic: bool,
 ) -> TransitionVc {
     let ty = Value::new(ContextType::App { app_dir });
     let client_chunking_context = get_client_chunking_context(project_root, server_root, ty);
-    let client_environment = get_client_environment(browserslist_query);
+    let client_environment =
+        get_client_environment(browserslist_query).with_defined_env(filter_for_client(env));
 
     let client_module_options_context =
-        get_client_module_options_context(project_root, client_environment, ty);
+        get_client_module_options_context(project_root, client_environment, ty, hermetic);
     NextClientChunksTransition {
         client_chunking_context,
         client_module_options_context,
-        client_resolve_options_context: get_client_resolve_options_context(project_root, ty),
+        client_resolve_options_context: get_client_resolve_options_context(
+            project_root,
+            ty,
+            hermetic,
+        ),
         client_environment,
         server_root,
     }
@@ -95,14 +105,17 @@ async fn next_client_transition(
     app_dir: FileSystemPathVc,
     env: ProcessEnvVc,
     browserslist_query: &str,
+    hermetic: bool,
 ) -> Result<TransitionVc> {
     let ty = Value::new(ContextType::App { app_dir });
     let client_chunking_context = get_client_chunking_context(project_root, server_root, ty);
-    let client_environment = get_client_environment(browserslist_query);
+    let client_environment =
+        get_client_environment(browserslist_query).with_defined_env(filter_for_client(env));
     let client_module_options_context =
-        get_client_module_options_context(project_root, client_environment, ty);
-    let client_runtime_entries = get_client_runtime_entries(project_root, env, ty);
-    let client_resolve_options_context = get_client_resolve_options_context(project_root, ty);
+        get_client_module_options_context(project_root, client_environment, ty, hermetic);
+    let client_runtime_entries = get_client_runtime_entries(project_root, env, ty, hermetic);
+    let client_resolve_options_context =
+        get_client_resolve_options_context(project_root, ty, hermetic);
 
     Ok(NextClientTransition {
         is_app: true,
@@ -123,6 +136,7 @@ fn next_ssr_client_module_transition(
     app_dir: FileSystemPathVc,
     process_env: ProcessEnvVc,
     externals: StringsVc,
+    hermetic: bool,
 ) -> TransitionVc {
     let ty = Value::new(ServerContextType::AppSSR { app_dir });
     NextSSRClientModuleTransition {
@@ -131,6 +145,7 @@ fn next_ssr_client_module_transition(
             project_root,
             ty,
             externals,
+            hermetic,
         ),
         ssr_environment: get_server_environment(ty, process_env),
     }
@@ -145,11 +160,12 @@ fn next_layout_entry_transition(
     server_root: FileSystemPathVc,
     process_env: ProcessEnvVc,
     externals: StringsVc,
+    hermetic: bool,
 ) -> TransitionVc {
     let ty = Value::new(ServerContextType::AppRSC { app_dir });
     let rsc_environment = get_server_environment(ty, process_env);
     let rsc_resolve_options_context =
-        get_server_resolve_options_context(project_root, ty, externals);
+        get_server_resolve_options_context(project_root, ty, externals, hermetic);
     let rsc_module_options_context = get_server_module_options_context(ty);
 
     NextLayoutEntryTransition {
@@ -171,13 +187,14 @@ fn app_context(
     browserslist_query: &str,
     ssr: bool,
     externals: StringsVc,
+    hermetic: bool,
 ) -> AssetContextVc {
     let next_server_to_client_transition = NextServerToClientTransition { ssr }.cell().into();
 
     let mut transitions = HashMap::new();
     transitions.insert(
         "next-layout-entry".to_string(),
-        next_layout_entry_transition(project_root, app_dir, server_root, env, externals),
+        next_layout_entry_transition(project_root, app_dir, server_root, env, externals, hermetic),
     );
     transitions.insert(
         "server-to-client".to_string(),
@@ -185,15 +202,29 @@ fn app_context(
     );
     transitions.insert(
         "next-client".to_string(),
-        next_client_transition(project_root, server_root, app_dir, env, browserslist_query),
+        next_client_transition(
+            project_root,
+            server_root,
+            app_dir,
+            env,
+            browserslist_query,
+            hermetic,
+        ),
     );
     transitions.insert(
         "next-client-chunks".to_string(),
-        next_client_chunks_transition(project_root, app_dir, server_root, browserslist_query),
+        next_client_chunks_transition(
+            project_root,
+            app_dir,
+            server_root,
+            env,
+            browserslist_query,
+            hermetic,
+        ),
     );
     transitions.insert(
         "next-ssr-client-module".to_string(),
-        next_ssr_client_module_transition(project_root, app_dir, env, externals),
+        next_ssr_client_module_transition(project_root, app_dir, env, externals, hermetic),
     );
 
     let ssr_ty = Value::new(ServerContextType::AppSSR { app_dir });
@@ -201,7 +232,7 @@ fn app_context(
         TransitionsByNameVc::cell(transitions),
         get_server_environment(ssr_ty, env),
         get_server_module_options_context(ssr_ty),
-        get_server_resolve_options_context(project_root, ssr_ty, externals),
+        get_server_resolve_options_context(project_root, ssr_ty, externals, hermetic),
     )
     .into()
 }
@@ -216,6 +247,8 @@ pub async fn create_app_source(
     env: ProcessEnvVc,
     browserslist_query: &str,
     externals: StringsVc,
+    pool_options: NodeJsPoolOptionsVc,
+    hermetic: bool,
 ) -> Result<ContentSourceVc> {
     let project_root = wrap_with_next_js_fs(project_root);
 
@@ -237,6 +270,7 @@ pub async fn create_app_source(
         browserslist_query,
         true,
         externals,
+        hermetic,
     );
     let context = app_context(
         project_root,
@@ -246,12 +280,13 @@ pub async fn create_app_source(
         browserslist_query,
         false,
         externals,
+        hermetic,
     );
 
     let server_runtime_entries =
         vec![ProcessEnvAssetVc::new(project_root, env).as_ecmascript_chunk_placeable()];
 
-    let fallback_page = get_fallback_page(project_root, server_root, browserslist_query);
+    let fallback_page = get_fallback_page(project_root, server_root, browserslist_query, hermetic);
 
     Ok(create_app_source_for_directory(
         context_ssr,
@@ -264,6 +299,7 @@ pub async fn create_app_source(
         server_root,
         LayoutSegmentsVc::cell(Vec::new()),
         output_path,
+        pool_options,
     )
     .into())
 }
@@ -280,6 +316,7 @@ async fn create_app_source_for_directory(
     target: FileSystemPathVc,
     layouts: LayoutSegmentsVc,
     intermediate_output_path: FileSystemPathVc,
+    pool_options: NodeJsPoolOptionsVc,
 ) -> Result<CombinedContentSourceVc> {
     let mut layouts = layouts;
     let mut sources = Vec::new();
@@ -370,6 +407,7 @@ async fn create_app_source_for_directory(
                     target,
                     project_root,
                     intermediate_output_path,
+                    pool_options,
                 }
                 .cell()
                 .into(),
@@ -399,6 +437,7 @@ async fn create_app_source_for_directory(
                         new_target,
                         layouts,
                         intermediate_output_path,
+                        pool_options,
                     )
                     .into(),
                 );
@@ -418,6 +457,7 @@ struct AppRenderer {
     target: FileSystemPathVc,
     project_root: FileSystemPathVc,
     intermediate_output_path: FileSystemPathVc,
+    pool_options: NodeJsPoolOptionsVc,
 }
 
 #[turbo_tasks::value_impl]
@@ -559,13 +599,18 @@ import BOOTSTRAP from {};
                 context,
                 Value::new(EcmascriptModuleAssetType::Typescript),
                 EcmascriptInputTransformsVc::cell(vec![
-                    EcmascriptInputTransform::React { refresh: false },
+                    EcmascriptInputTransform::React {
+                        refresh: false,
+                        runtime: JsxRuntime::Automatic,
+                        import_source: None,
+                    },
                     EcmascriptInputTransform::TypeScript,
                 ]),
                 context.environment(),
             ),
             chunking_context,
             intermediate_output_path,
+            pool_options: self.pool_options,
         }
         .cell())
     }
@@ -597,6 +642,11 @@ impl Issue for AppSourceIssue {
         StringVc::cell("next app".to_string())
     }
 
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(issue_codes::APP_SOURCE.to_string())
+    }
+
     #[turbo_tasks::function]
     fn context(&self) -> FileSystemPathVc {
         self.path
@@ -28,11 +28,13 @@ pub async fn get_fallback_page(
     project_root: FileSystemPathVc,
     dev_server_root: FileSystemPathVc,
     browserslist_query: &str,
+    hermetic: bool,
 ) -> Result<DevHtmlAssetVc> {
     let ty = Value::new(ContextType::Other);
     let environment = get_client_environment(browserslist_query);
-    let resolve_options_context = get_client_resolve_options_context(project_root, ty);
-    let module_options_context = get_client_module_options_context(project_root, environment, ty);
+    let resolve_options_context = get_client_resolve_options_context(project_root, ty, hermetic);
+    let module_options_context =
+        get_client_module_options_context(project_root, environment, ty, hermetic);
     let chunking_context = get_client_chunking_context(project_root, dev_server_root, ty);
 
     let mut import_map = ImportMap::empty();
@@ -0,0 +1,18 @@
+//! Stable codes of the issues emitted by next-core, within the `TP9xxx` range
+//! reserved for integrations. See [turbopack_core::issue::codes] for the codes
+//! of turbopack itself.
+
+/// The app couldn't be prepared, e. g. because of an invalid entry.
+pub const APP_SOURCE: &str = "TP9001";
+/// A page or API route failed while rendering in Node.js.
+pub const RENDERING: &str = "TP9002";
+/// A config file failed while being evaluated.
+pub const CONFIG_EVALUATION: &str = "TP9003";
+/// The React Refresh runtime couldn't be resolved.
+pub const REACT_REFRESH: &str = "TP9004";
+/// Multiple files map to the same route.
+pub const CONFLICTING_ROUTES: &str = "TP9005";
+/// A webpack config contains options which are ignored.
+pub const UNSUPPORTED_WEBPACK_CONFIG: &str = "TP9006";
+/// PostCSS failed to transform a CSS file.
+pub const POSTCSS: &str = "TP9007";
@@ -6,6 +6,7 @@ mod app_source;
 mod embed_js;
 pub mod env;
 mod fallback;
+pub mod issue_codes;
 pub mod next_client;
 mod next_client_component;
 mod next_import_map;
@@ -13,13 +14,19 @@ pub mod next_server;
 mod nodejs;
 mod path_regex;
 pub mod react_refresh;
+pub mod route_discovery;
 mod runtime;
 mod server_rendered_source;
 pub mod source_map;
 mod util;
 mod web_entry_source;
+pub mod webpack_config;
 
 pub use app_source::create_app_source;
+pub use nodejs::{
+    evaluate::{evaluate_config, evaluate_config_lossy, EvaluatedConfig, EvaluatedConfigVc},
+    pool::{NodeJsPoolOptions, NodeJsPoolOptionsVc},
+};
 pub use server_rendered_source::create_server_rendered_source;
 pub use web_entry_source::create_web_entry_source;
 
@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use anyhow::Result;
 use turbo_tasks::Value;
 use turbo_tasks_env::ProcessEnvVc;
-use turbo_tasks_fs::FileSystemPathVc;
+use turbo_tasks_fs::{FileContent, FileSystemPathVc};
 use turbopack::{
     module_options::{
         module_options_context::{ModuleOptionsContext, ModuleOptionsContextVc},
@@ -15,10 +15,16 @@ use turbopack::{
     ModuleAssetContextVc,
 };
 use turbopack_core::{
-    chunk::{dev::DevChunkingContextVc, ChunkingContextVc},
+    cache::{cache_key, RemoteCacheVc},
+    chunk::{
+        dev::{DevChunkingContextBuilder, DevChunkingContextVc},
+        vendor::VendorChunkGroupsVc,
+        ChunkingContextVc,
+    },
     context::AssetContextVc,
     environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
     resolve::{parse::RequestVc, pattern::Pattern},
+    source_transform::SourceTransformsVc,
 };
 use turbopack_ecmascript::{EcmascriptInputTransform, EcmascriptInputTransformsVc};
 use turbopack_env::ProcessEnvAssetVc;
@@ -31,6 +37,7 @@ use crate::{
         get_next_client_fallback_import_map, get_next_client_import_map,
         get_next_client_resolved_map,
     },
+    nodejs::postcss::PostCssTransformVc,
     react_refresh::assert_can_resolve_react_refresh,
 };
 
@@ -62,6 +69,7 @@ pub enum ContextType {
 pub fn get_client_resolve_options_context(
     project_root: FileSystemPathVc,
     ty: Value<ContextType>,
+    hermetic: bool,
 ) -> ResolveOptionsContextVc {
     let next_client_import_map = get_next_client_import_map(project_root, ty);
     let next_client_fallback_import_map = get_next_client_fallback_import_map(ty);
@@ -76,6 +84,7 @@ pub fn get_client_resolve_options_context(
         resolved_map: Some(next_client_resolved_map),
         browser: true,
         module: true,
+        hermetic,
         ..Default::default()
     }
     .cell()
@@ -86,8 +95,9 @@ pub async fn get_client_module_options_context(
     project_root: FileSystemPathVc,
     env: EnvironmentVc,
     ty: Value<ContextType>,
+    hermetic: bool,
 ) -> Result<ModuleOptionsContextVc> {
-    let resolve_options_context = get_client_resolve_options_context(project_root, ty);
+    let resolve_options_context = get_client_resolve_options_context(project_root, ty, hermetic);
     let enable_react_refresh =
         assert_can_resolve_react_refresh(project_root, resolve_options_context)
             .await?
@@ -133,15 +143,47 @@ pub async fn add_next_transforms_to_pages(
     Ok(module_options_context.cell())
 }
 
+/// Transforms CSS files with the plugins of the `postcss.config.js` in
+/// `project_path`, when there is one. With a `remote_cache`, the transformed
+/// files are stored in it, keyed by their source and the config.
+#[turbo_tasks::function]
+pub async fn add_postcss_transform(
+    module_options_context: ModuleOptionsContextVc,
+    project_path: FileSystemPathVc,
+    intermediate_output_path: FileSystemPathVc,
+    remote_cache: Option<RemoteCacheVc>,
+) -> Result<ModuleOptionsContextVc> {
+    let config_path = project_path.join("postcss.config.js");
+    let config = match &*config_path.read().await? {
+        FileContent::Content(config) => cache_key("postcss.config.js", &[config.content()]),
+        FileContent::NotFound => return Ok(module_options_context),
+    };
+    let mut module_options_context = module_options_context.await?.clone_value();
+    module_options_context.remote_cache = remote_cache.map(|cache| cache.scoped(config));
+    module_options_context.custom_rules.push(ModuleRule::new(
+        ModuleRuleCondition::ResourcePathEndsWith(".css".to_string()),
+        vec![ModuleRuleEffect::SourceTransforms(
+            SourceTransformsVc::cell(vec![PostCssTransformVc::new(
+                config_path,
+                intermediate_output_path,
+            )
+            .into()]),
+        )],
+    ));
+    Ok(module_options_context.cell())
+}
+
 #[turbo_tasks::function]
 pub fn get_client_asset_context(
     project_root: FileSystemPathVc,
     browserslist_query: &str,
     ty: Value<ContextType>,
+    hermetic: bool,
 ) -> AssetContextVc {
     let environment = get_client_environment(browserslist_query);
-    let resolve_options_context = get_client_resolve_options_context(project_root, ty);
-    let module_options_context = get_client_module_options_context(project_root, environment, ty);
+    let resolve_options_context = get_client_resolve_options_context(project_root, ty, hermetic);
+    let module_options_context =
+        get_client_module_options_context(project_root, environment, ty, hermetic);
 
     let context: AssetContextVc = ModuleAssetContextVc::new(
         TransitionsByNameVc::cell(HashMap::new()),
@@ -154,12 +196,11 @@ pub fn get_client_asset_context(
     context
 }
 
-#[turbo_tasks::function]
-pub fn get_client_chunking_context(
+fn client_chunking_context_builder(
     project_root: FileSystemPathVc,
     server_root: FileSystemPathVc,
     ty: Value<ContextType>,
-) -> ChunkingContextVc {
+) -> DevChunkingContextBuilder {
     DevChunkingContextVc::builder(
         project_root,
         server_root,
@@ -172,7 +213,30 @@ pub fn get_client_chunking_context(
         get_client_assets_path(server_root, ty),
     )
     .hot_module_replacement()
-    .build()
+    .error_tolerant_emit()
+}
+
+#[turbo_tasks::function]
+pub fn get_client_chunking_context(
+    project_root: FileSystemPathVc,
+    server_root: FileSystemPathVc,
+    ty: Value<ContextType>,
+) -> ChunkingContextVc {
+    client_chunking_context_builder(project_root, server_root, ty).build()
+}
+
+/// Like [get_client_chunking_context], but every package in `node_modules`
+/// is split into a vendor chunk of its own. The chunks of a package stay the
+/// same while the app code changes, so they are neither rebuilt nor reloaded.
+#[turbo_tasks::function]
+pub fn get_client_vendor_chunking_context(
+    project_root: FileSystemPathVc,
+    server_root: FileSystemPathVc,
+    ty: Value<ContextType>,
+) -> ChunkingContextVc {
+    client_chunking_context_builder(project_root, server_root, ty)
+        .vendor_chunk_groups(VendorChunkGroupsVc::node_modules())
+        .build()
 }
 
 #[turbo_tasks::function]
@@ -193,8 +257,9 @@ pub async fn get_client_runtime_entries(
     project_root: FileSystemPathVc,
     env: ProcessEnvVc,
     ty: Value<ContextType>,
+    hermetic: bool,
 ) -> Result<RuntimeEntriesVc> {
-    let resolve_options_context = get_client_resolve_options_context(project_root, ty);
+    let resolve_options_context = get_client_resolve_options_context(project_root, ty, hermetic);
     let enable_react_refresh =
         assert_can_resolve_react_refresh(project_root, resolve_options_context)
             .await?
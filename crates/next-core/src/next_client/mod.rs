@@ -5,7 +5,11 @@ use anyhow::{bail, Result};
 use turbo_tasks::ValueToString;
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack::{
-    ecmascript::chunk_group_files_asset::ChunkGroupFilesAsset,
+    ecmascript::{
+        chunk::{EcmascriptChunkPlaceableVc, EcmascriptChunkPlaceablesVc},
+        chunk_group_files_asset::ChunkGroupFilesAsset,
+        env_reads::check_undefined_env_reads,
+    },
     module_options::ModuleOptionsContextVc,
     resolve_options_context::ResolveOptionsContextVc,
     transition::{Transition, TransitionVc},
@@ -88,6 +92,12 @@ impl Transition for NextClientTransition {
             None => bail!("asset {} is not chunkable", asset.path().to_string().await?),
         };
 
+        // The client environment defines the `NEXT_PUBLIC_` variables, which
+        // are `undefined` in the browser when they are missing
+        if let Some(placeable) = EcmascriptChunkPlaceableVc::resolve_from(asset).await? {
+            check_undefined_env_reads(EcmascriptChunkPlaceablesVc::cell(vec![placeable])).await?;
+        }
+
         let runtime_entries = self.runtime_entries.resolve_entries(context.into());
 
         let asset = ChunkGroupFilesAsset {
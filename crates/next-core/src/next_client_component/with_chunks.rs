@@ -11,7 +11,7 @@ use turbopack::ecmascript::{
     utils::stringify_module_id,
 };
 use turbopack_core::{
-    asset::{Asset, AssetContentVc, AssetVc},
+    asset::{Asset, AssetContentVc, AssetOptionVc, AssetVc},
     chunk::{
         ChunkGroupVc, ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetReference,
         ChunkableAssetReferenceVc, ChunkableAssetVc, ChunkingContextVc, ChunkingType,
@@ -152,6 +152,11 @@ impl ChunkItem for WithChunksChunkItem {
         .cell()
         .into()]))
     }
+
+    #[turbo_tasks::function]
+    fn asset(&self) -> AssetOptionVc {
+        AssetOptionVc::cell(Some(self.inner.into()))
+    }
 }
 
 #[turbo_tasks::value]
@@ -28,6 +28,7 @@ pub fn get_server_resolve_options_context(
     project_path: FileSystemPathVc,
     ty: Value<ServerContextType>,
     externals: StringsVc,
+    hermetic: bool,
 ) -> ResolveOptionsContextVc {
     let next_server_import_map = get_next_server_import_map(project_path, ty, externals);
     match ty.into_value() {
@@ -41,6 +42,7 @@ pub fn get_server_resolve_options_context(
                 custom_conditions: vec!["development".to_string()],
                 import_map: Some(next_server_import_map),
                 module: true,
+                hermetic,
                 ..Default::default()
             }
         }
@@ -53,6 +55,7 @@ pub fn get_server_resolve_options_context(
             custom_conditions: vec!["development".to_string(), "react-server".to_string()],
             import_map: Some(next_server_import_map),
             module: true,
+            hermetic,
             ..Default::default()
         },
     }
@@ -74,6 +77,7 @@ pub fn get_server_environment(
             ServerContextType::AppRSC { .. } => Value::new(EnvironmentIntention::ServerRendering),
         },
     )
+    .with_defined_env(process_env)
 }
 
 #[turbo_tasks::function]
@@ -0,0 +1,314 @@
+//! Evaluation of JavaScript config files (e. g. `postcss.config.js`) in a
+//! sandboxed Node.js process.
+//!
+//! The config file is loaded with a restricted module loader: It can only
+//! require files within the root of its filesystem and a few side effect free
+//! builtins. The exported value, or the value returned by an exported
+//! function, is sent back as JSON. Configs which can contain values that
+//! can't be represented as JSON can be evaluated with [evaluate_config_lossy].
+//!
+//! Evaluations are cached by a hash of the content of all files loaded while
+//! evaluating, and the config is re-evaluated when one of these files changes.
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use turbo_tasks::{primitives::StringVc, ValueToString};
+use turbo_tasks_fs::{to_sys_path, FileContent, FileSystemPathVc};
+use turbo_tasks_hash::{encode_hex, DeterministicHasher, Xxh3Hash64Hasher};
+use turbopack_core::virtual_asset::VirtualAssetVc;
+
+use super::{
+    emit,
+    issue::EvaluationIssue,
+    pool::{NodeJsPool, NodeJsPoolOptions, NodeJsPoolVc},
+};
+use crate::embed_js::next_js_file;
+
+/// The loaded files can change while the config is evaluated. Evaluating
+/// stops when they still differ after this many evaluations.
+const MAX_EVALUATIONS: usize = 10;
+
+/// The result of evaluating a config file.
+#[turbo_tasks::value(shared, serialization = "none")]
+pub enum EvaluatedConfig {
+    /// The value exported by the config, or returned by its exported
+    /// function.
+    Value(JsonValue),
+    /// The config file doesn't exist.
+    NotFound,
+    /// The config couldn't be evaluated. An issue has been emitted.
+    Error,
+}
+
+/// The result of a sandbox process. `dependencies` are the files loaded while
+/// evaluating, relative to the root of the filesystem.
+#[turbo_tasks::value(shared, serialization = "none")]
+enum SandboxEvaluation {
+    Value {
+        value: JsonValue,
+        dependencies: Vec<String>,
+    },
+    Error {
+        name: String,
+        message: String,
+        stack: String,
+        dependencies: Vec<String>,
+    },
+}
+
+impl SandboxEvaluation {
+    fn dependencies(&self) -> &Vec<String> {
+        match self {
+            SandboxEvaluation::Value { dependencies, .. }
+            | SandboxEvaluation::Error { dependencies, .. } => dependencies,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum EvaluationOutgoingMessage<'a> {
+    Evaluate {
+        root: &'a Path,
+        file: &'a Path,
+        lossy: bool,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum EvaluationIncomingMessage {
+    Value {
+        value: JsonValue,
+        dependencies: Vec<String>,
+    },
+    Error {
+        name: String,
+        message: String,
+        stack: String,
+        dependencies: Vec<String>,
+    },
+}
+
+/// Creates a pool of sandbox processes, which evaluate config files in `cwd`.
+#[turbo_tasks::function]
+async fn get_evaluation_pool(
+    cwd: FileSystemPathVc,
+    intermediate_output_path: FileSystemPathVc,
+) -> Result<NodeJsPoolVc> {
+    // The sandbox is executed by node directly. It's a .cjs file, so it's not
+    // affected by the package.json of the output directory.
+    let entrypoint = intermediate_output_path.join("evaluate-sandbox.cjs");
+    emit(
+        VirtualAssetVc::new(
+            entrypoint,
+            next_js_file("internal/evaluate-sandbox.js").into(),
+        )
+        .into(),
+        intermediate_output_path,
+    )
+    .await?;
+
+    let cwd = to_sys_path(cwd)
+        .await?
+        .context("can only evaluate configs from a disk filesystem")?;
+    let entrypoint = to_sys_path(entrypoint)
+        .await?
+        .context("can only evaluate configs with a disk output filesystem")?;
+    Ok(NodeJsPool::new(
+        cwd,
+        entrypoint,
+        HashMap::new(),
+        NodeJsPoolOptions::default(),
+    )
+    .cell())
+}
+
+/// Evaluates the config file at `path` in a sandbox process. The process reads
+/// the files from disk, so `sources_hash` has to identify the content of the
+/// files it will load.
+#[turbo_tasks::function]
+async fn evaluate_in_sandbox(
+    path: FileSystemPathVc,
+    intermediate_output_path: FileSystemPathVc,
+    lossy: bool,
+    // Only used as cache key
+    _sources_hash: String,
+) -> Result<SandboxEvaluationVc> {
+    let root = to_sys_path(path.root())
+        .await?
+        .context("can only evaluate configs from a disk filesystem")?;
+    let file = to_sys_path(path)
+        .await?
+        .context("can only evaluate configs from a disk filesystem")?;
+    let pool = get_evaluation_pool(path.parent(), intermediate_output_path)
+        .strongly_consistent()
+        .await?;
+    let mut operation = pool.operation().await?;
+    operation
+        .send(EvaluationOutgoingMessage::Evaluate {
+            root: &root,
+            file: &file,
+            lossy,
+        })
+        .await
+        .context("sending config to the sandbox process")?;
+    let relative_paths = |dependencies: Vec<String>| -> Vec<String> {
+        dependencies
+            .iter()
+            .filter_map(|dependency| relative_unix_path(&root, Path::new(dependency)))
+            .collect()
+    };
+    Ok(match operation
+        .recv()
+        .await
+        .context("receiving evaluated config from the sandbox process")?
+    {
+        EvaluationIncomingMessage::Value {
+            value,
+            dependencies,
+        } => SandboxEvaluation::Value {
+            value,
+            dependencies: relative_paths(dependencies),
+        },
+        EvaluationIncomingMessage::Error {
+            name,
+            message,
+            stack,
+            dependencies,
+        } => SandboxEvaluation::Error {
+            name,
+            message,
+            stack,
+            dependencies: relative_paths(dependencies),
+        },
+    }
+    .cell())
+}
+
+/// Converts a path within `root` to a path relative to `root` with `/`
+/// separators.
+pub(crate) fn relative_unix_path(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let segments: Option<Vec<_>> = relative
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect();
+    Some(segments?.join("/"))
+}
+
+/// Hashes the content of `sources`. Reading them makes the calling task depend
+/// on them, so it's re-executed when one of them changes.
+pub(super) async fn sources_hash(root: FileSystemPathVc, sources: &[String]) -> Result<String> {
+    let mut hasher = Xxh3Hash64Hasher::new();
+    for source in sources {
+        hasher.write_ref(source);
+        match &*root.join(source).read().await? {
+            FileContent::Content(file) => {
+                hasher.write_u8(1);
+                hasher.write_usize(file.content().len());
+                hasher.write_bytes(file.content());
+            }
+            FileContent::NotFound => hasher.write_u8(0),
+        }
+    }
+    Ok(encode_hex(hasher.finish()))
+}
+
+/// Evaluates the JavaScript config file at `path` in a sandboxed Node.js
+/// process, which is started from the files emitted to
+/// `intermediate_output_path`.
+///
+/// The result is re-evaluated when the file or one of the files it requires
+/// changes, and reused as long as their content stays the same.
+#[turbo_tasks::function]
+pub async fn evaluate_config(
+    path: FileSystemPathVc,
+    intermediate_output_path: FileSystemPathVc,
+) -> Result<EvaluatedConfigVc> {
+    evaluate(path, intermediate_output_path, false).await
+}
+
+/// Like [evaluate_config], but values which can't be represented as JSON
+/// don't fail the evaluation. Functions are replaced by
+/// `{ "$unsupported": "function", "name": ... }` and instances of classes by
+/// `{ "$unsupported": "<class name>", "properties": { ... } }`.
+#[turbo_tasks::function]
+pub async fn evaluate_config_lossy(
+    path: FileSystemPathVc,
+    intermediate_output_path: FileSystemPathVc,
+) -> Result<EvaluatedConfigVc> {
+    evaluate(path, intermediate_output_path, true).await
+}
+
+async fn evaluate(
+    path: FileSystemPathVc,
+    intermediate_output_path: FileSystemPathVc,
+    lossy: bool,
+) -> Result<EvaluatedConfigVc> {
+    if let FileContent::NotFound = &*path.read().await? {
+        return Ok(EvaluatedConfig::NotFound.cell());
+    }
+    let root = path.root();
+    // The files loaded by the config are only known after evaluating it. It's
+    // evaluated again when they differ from the files hashed for the cache key.
+    let mut sources = vec![path.await?.path.clone()];
+    for _ in 0..MAX_EVALUATIONS {
+        let hash = sources_hash(root, &sources).await?;
+        let evaluation = evaluate_in_sandbox(path, intermediate_output_path, lossy, hash).await?;
+        if *evaluation.dependencies() != sources {
+            sources = evaluation.dependencies().clone();
+            continue;
+        }
+        return Ok(match &*evaluation {
+            SandboxEvaluation::Value { value, .. } => EvaluatedConfig::Value(value.clone()).cell(),
+            SandboxEvaluation::Error {
+                name,
+                message,
+                stack,
+                ..
+            } => {
+                EvaluationIssue {
+                    path,
+                    message: StringVc::cell(format!("{name}: {message}")),
+                    stack: StringVc::cell(stack.clone()),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                EvaluatedConfig::Error.cell()
+            }
+        });
+    }
+    bail!(
+        "the files required by {} kept changing while evaluating it",
+        path.to_string().await?
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::relative_unix_path;
+
+    #[test]
+    fn relative_paths() {
+        let root = Path::new("/project");
+        assert_eq!(
+            relative_unix_path(root, Path::new("/project/postcss.config.js")),
+            Some("postcss.config.js".to_string())
+        );
+        assert_eq!(
+            relative_unix_path(root, Path::new("/project/config/plugins.js")),
+            Some("config/plugins.js".to_string())
+        );
+        assert_eq!(
+            relative_unix_path(root, Path::new("/other/postcss.config.js")),
+            None
+        );
+    }
+}
@@ -3,6 +3,8 @@ use turbo_tasks::primitives::StringVc;
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::issue::{Issue, IssueVc};
 
+use crate::issue_codes;
+
 #[turbo_tasks::value(shared)]
 #[derive(Copy, Clone)]
 pub(super) struct RenderingIssue {
@@ -23,6 +25,11 @@ impl Issue for RenderingIssue {
         StringVc::cell("rendering".to_string())
     }
 
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(issue_codes::RENDERING.to_string())
+    }
+
     #[turbo_tasks::function]
     fn context(&self) -> FileSystemPathVc {
         self.context
@@ -46,3 +53,85 @@ impl Issue for RenderingIssue {
 
     // TODO parse stack trace into source location
 }
+
+#[turbo_tasks::value(shared)]
+#[derive(Copy, Clone)]
+pub(super) struct EvaluationIssue {
+    pub path: FileSystemPathVc,
+    pub message: StringVc,
+    pub stack: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for EvaluationIssue {
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Error evaluating config".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("config".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(issue_codes::CONFIG_EVALUATION.to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.message
+    }
+
+    #[turbo_tasks::function]
+    fn detail(&self) -> StringVc {
+        self.stack
+    }
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Copy, Clone)]
+pub(super) struct PostCssIssue {
+    pub path: FileSystemPathVc,
+    pub message: StringVc,
+    pub stack: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for PostCssIssue {
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Error transforming CSS with PostCSS".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("css".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(issue_codes::POSTCSS.to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.message
+    }
+
+    #[turbo_tasks::function]
+    fn detail(&self) -> StringVc {
+        self.stack
+    }
+}
@@ -29,16 +29,18 @@ use turbopack_ecmascript::{chunk::EcmascriptChunkPlaceablesVc, EcmascriptModuleA
 use self::{
     bootstrap::NodeJsBootstrapAsset,
     issue::RenderingIssue,
-    pool::{NodeJsOperation, NodeJsPool, NodeJsPoolVc},
+    pool::{NodeJsOperation, NodeJsPool, NodeJsPoolOptionsVc, NodeJsPoolVc},
 };
 use crate::source_map::{SourceMapTraceVc, StackFrame, TraceResult};
 
 pub(crate) mod bootstrap;
+pub mod evaluate;
 pub(crate) mod issue;
 pub(crate) mod node_api_source;
 pub(crate) mod node_entry;
 pub(crate) mod node_rendered_source;
-pub(crate) mod pool;
+pub mod pool;
+pub mod postcss;
 
 #[turbo_tasks::function]
 async fn emit(
@@ -182,6 +184,7 @@ async fn separate_assets(
 async fn get_renderer_pool(
     intermediate_asset: AssetVc,
     intermediate_output_path: FileSystemPathVc,
+    pool_options: NodeJsPoolOptionsVc,
 ) -> Result<NodeJsPoolVc> {
     // Emit a basic package.json that sets the type of the package to commonjs.
     // Currently code generated for Node is CommonJS, while authored code may be
@@ -203,7 +206,12 @@ async fn get_renderer_pool(
 
     if let Some(dir) = to_sys_path(intermediate_output_path).await? {
         let entrypoint = dir.join("index.js");
-        let pool = NodeJsPool::new(dir, entrypoint, HashMap::new(), 4);
+        let pool = NodeJsPool::new(
+            dir,
+            entrypoint,
+            HashMap::new(),
+            pool_options.await?.clone_value(),
+        );
         Ok(pool.cell())
     } else {
         Err(anyhow!("can only render from a disk filesystem"))
@@ -271,6 +279,7 @@ async fn render_static(
     fallback_page: DevHtmlAssetVc,
     chunking_context: ChunkingContextVc,
     intermediate_output_path: FileSystemPathVc,
+    pool_options: NodeJsPoolOptionsVc,
     data: RenderDataVc,
 ) -> Result<AssetContentVc> {
     let intermediate_asset = get_intermediate_asset(
@@ -279,7 +288,8 @@ async fn render_static(
         chunking_context,
         intermediate_output_path,
     );
-    let renderer_pool = get_renderer_pool(intermediate_asset, intermediate_output_path);
+    let renderer_pool =
+        get_renderer_pool(intermediate_asset, intermediate_output_path, pool_options);
     // Read this strongly consistent, since we don't want to run inconsistent
     // node.js code.
     let pool = renderer_pool.strongly_consistent().await?;
@@ -487,6 +497,7 @@ async fn render_proxy(
     runtime_entries: EcmascriptChunkPlaceablesVc,
     chunking_context: ChunkingContextVc,
     intermediate_output_path: FileSystemPathVc,
+    pool_options: NodeJsPoolOptionsVc,
     data: RenderDataVc,
     body: BodyVc,
 ) -> Result<ProxyResultVc> {
@@ -496,7 +507,8 @@ async fn render_proxy(
         chunking_context,
         intermediate_output_path,
     );
-    let renderer_pool = get_renderer_pool(intermediate_asset, intermediate_output_path);
+    let renderer_pool =
+        get_renderer_pool(intermediate_asset, intermediate_output_path, pool_options);
     let pool = renderer_pool.await?;
     let mut operation = match pool.operation().await {
         Ok(operation) => operation,
@@ -88,6 +88,7 @@ impl ContentSource for NodeApiContentSource {
                         this.runtime_entries,
                         entry.chunking_context,
                         entry.intermediate_output_path,
+                        entry.pool_options,
                         RenderData {
                             params,
                             method: method.clone(),
@@ -5,11 +5,14 @@ use turbopack_core::chunk::ChunkingContextVc;
 use turbopack_dev_server::source::ContentSourceData;
 use turbopack_ecmascript::EcmascriptModuleAssetVc;
 
+use super::pool::NodeJsPoolOptionsVc;
+
 #[turbo_tasks::value(shared)]
 pub struct NodeRenderingEntry {
     pub module: EcmascriptModuleAssetVc,
     pub chunking_context: ChunkingContextVc,
     pub intermediate_output_path: FileSystemPathVc,
+    pub pool_options: NodeJsPoolOptionsVc,
 }
 
 #[turbo_tasks::value(transparent)]
@@ -159,6 +159,7 @@ impl ContentSource for NodeRenderContentSource {
                                         this.fallback_page,
                                         entry.chunking_context,
                                         entry.intermediate_output_path,
+                                        entry.pool_options,
                                         RenderData {
                                             params,
                                             method: data.method.clone().ok_or_else(|| {
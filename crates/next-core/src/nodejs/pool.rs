@@ -14,7 +14,7 @@ use tokio::{
     process::{Child, Command},
     select,
     sync::{OwnedSemaphorePermit, Semaphore},
-    time::sleep,
+    time::{sleep, timeout},
 };
 
 enum NodeJsPoolProcess {
@@ -54,6 +54,31 @@ impl Drop for RunningNodeJsPoolProcess {
 
 const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// Configures the lifecycle of the processes of a [NodeJsPool].
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Clone, Debug, Hash, PartialOrd, Ord)]
+pub struct NodeJsPoolOptions {
+    /// The maximum number of processes running operations at the same time.
+    pub concurrency: usize,
+    /// The maximum number of idle processes which are kept for later
+    /// operations. Further processes are killed once their operation has
+    /// finished.
+    pub max_idle_processes: usize,
+    /// How long an operation waits for a message from its process. The process
+    /// is killed when it doesn't respond in time. `None` waits indefinitely.
+    pub operation_timeout: Option<Duration>,
+}
+
+impl Default for NodeJsPoolOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            max_idle_processes: 4,
+            operation_timeout: None,
+        }
+    }
+}
+
 impl NodeJsPoolProcess {
     async fn new(cwd: &Path, env: &HashMap<String, String>, entrypoint: &Path) -> Result<Self> {
         let listener = TcpListener::bind("127.0.0.1:0")
@@ -78,6 +103,9 @@ impl NodeJsPoolProcess {
         cmd.envs(env);
         cmd.stderr(Stdio::inherit());
         cmd.stdout(Stdio::inherit());
+        // Make sure the process doesn't outlive the pool, even when it can't be
+        // killed asynchronously
+        cmd.kill_on_drop(true);
 
         let child = cmd.spawn().context("spawning node pooled process")?;
 
@@ -113,6 +141,19 @@ impl NodeJsPoolProcess {
             NodeJsPoolProcess::Running(running) => running,
         })
     }
+
+    /// Whether the process has exited while it was idle. Exited processes are
+    /// reaped, so they don't linger as zombies.
+    fn has_exited(&mut self) -> bool {
+        let child = match self {
+            NodeJsPoolProcess::Spawned(spawned) => &mut spawned.child,
+            NodeJsPoolProcess::Running(running) => &mut running.child,
+        };
+        match child {
+            Some(child) => !matches!(child.try_wait(), Ok(None)),
+            None => true,
+        }
+    }
 }
 
 impl RunningNodeJsPoolProcess {
@@ -159,11 +200,17 @@ impl RunningNodeJsPoolProcess {
 ///
 /// The worker will *not* use the env of the parent process by default. All env
 /// vars need to be provided to make the execution as pure as possible.
+///
+/// The lifecycle of the processes is configured with [NodeJsPoolOptions]:
+/// Processes which don't respond within the operation timeout, and processes
+/// of operations which are dropped before they finished, are killed instead of
+/// being reused.
 #[turbo_tasks::value(into = "new", cell = "new", serialization = "none", eq = "manual")]
 pub(super) struct NodeJsPool {
     cwd: PathBuf,
     entrypoint: PathBuf,
     env: HashMap<String, String>,
+    options: NodeJsPoolOptions,
     #[turbo_tasks(trace_ignore, debug_ignore)]
     processes: Arc<Mutex<Vec<NodeJsPoolProcess>>>,
     #[turbo_tasks(trace_ignore, debug_ignore)]
@@ -175,14 +222,16 @@ impl NodeJsPool {
         cwd: PathBuf,
         entrypoint: PathBuf,
         env: HashMap<String, String>,
-        concurrency: usize,
+        options: NodeJsPoolOptions,
     ) -> Self {
+        let semaphore = Arc::new(Semaphore::new(options.concurrency));
         Self {
             cwd,
             entrypoint,
             env,
+            options,
             processes: Arc::new(Mutex::new(Vec::new())),
-            semaphore: Arc::new(Semaphore::new(concurrency)),
+            semaphore,
         }
     }
 
@@ -191,6 +240,8 @@ impl NodeJsPool {
 
         let popped = {
             let mut processes = self.processes.lock().unwrap();
+            // Processes might have crashed while they were idle
+            processes.retain_mut(|process| !process.has_exited());
             processes.pop()
         };
         let process = match popped {
@@ -211,6 +262,9 @@ impl NodeJsPool {
             process: Some(process.run().await?),
             permit,
             processes: self.processes.clone(),
+            max_idle_processes: self.options.max_idle_processes,
+            timeout: self.options.operation_timeout,
+            pending: false,
         })
     }
 }
@@ -221,6 +275,12 @@ pub struct NodeJsOperation {
     #[allow(dead_code)]
     permit: OwnedSemaphorePermit,
     processes: Arc<Mutex<Vec<NodeJsPoolProcess>>>,
+    max_idle_processes: usize,
+    timeout: Option<Duration>,
+    /// Whether a message was sent to the process and its answer wasn't
+    /// received yet. The process can't be reused in that case, as the answer
+    /// would be received by the next operation.
+    pending: bool,
 }
 
 impl NodeJsOperation {
@@ -234,11 +294,29 @@ impl NodeJsOperation {
     where
         M: DeserializeOwned,
     {
-        let message = self
-            .process_mut()?
-            .recv()
-            .await
-            .context("receiving message")?;
+        // A message which is only partially received when the operation is
+        // cancelled would be received by the next operation
+        self.pending = true;
+        let timeout_duration = self.timeout;
+        let process = self.process_mut()?;
+        let message = match timeout_duration {
+            Some(duration) => match timeout(duration, process.recv()).await {
+                Ok(message) => message,
+                Err(_) => {
+                    // The process is stuck. It's not reused as the operation is still pending.
+                    if let Some(child) = &mut process.child {
+                        let _ = child.start_kill();
+                    }
+                    bail!(
+                        "timed out waiting for the Node.js process to respond ({:?} timeout)",
+                        duration
+                    );
+                }
+            },
+            None => process.recv().await,
+        }
+        .context("receiving message")?;
+        self.pending = false;
         serde_json::from_slice(&message).context("deserializing message")
     }
 
@@ -246,6 +324,7 @@ impl NodeJsOperation {
     where
         M: Serialize,
     {
+        self.pending = true;
         self.process_mut()?
             .send(serde_json::to_vec(&message).context("serializing message")?)
             .await
@@ -274,10 +353,149 @@ impl NodeJsOperation {
 impl Drop for NodeJsOperation {
     fn drop(&mut self) {
         if let Some(process) = self.process.take() {
-            self.processes
-                .lock()
-                .unwrap()
-                .push(NodeJsPoolProcess::Running(process));
+            // When the operation was cancelled while waiting for an answer, the
+            // process is dropped and killed instead of being reused
+            if self.pending {
+                return;
+            }
+            let mut processes = self.processes.lock().unwrap();
+            if processes.len() < self.max_idle_processes {
+                processes.push(NodeJsPoolProcess::Running(process));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, env::temp_dir, fs, time::Duration};
+
+    use super::{NodeJsOperation, NodeJsPool, NodeJsPoolOptions};
+
+    /// Answers every message with the same message, except for `"hang"`,
+    /// which is never answered.
+    const ECHO: &str = r#"
+const net = require("net");
+const socket = net.connect(Number(process.argv[2]), "127.0.0.1");
+let buffer = Buffer.alloc(0);
+socket.on("data", (data) => {
+  buffer = Buffer.concat([buffer, data]);
+  while (buffer.length >= 4 && buffer.length >= 4 + buffer.readUInt32BE(0)) {
+    const packet = buffer.subarray(4, 4 + buffer.readUInt32BE(0));
+    buffer = buffer.subarray(4 + packet.length);
+    if (JSON.parse(packet.toString()) !== "hang") {
+      const length = Buffer.alloc(4);
+      length.writeUInt32BE(packet.length);
+      socket.write(Buffer.concat([length, packet]));
+    }
+  }
+});
+"#;
+
+    fn echo_pool(name: &str, options: NodeJsPoolOptions) -> NodeJsPool {
+        let cwd = temp_dir().join(format!("next-core-pool-{name}-{}", std::process::id()));
+        fs::create_dir_all(&cwd).unwrap();
+        let entrypoint = cwd.join("echo.js");
+        fs::write(&entrypoint, ECHO).unwrap();
+        NodeJsPool::new(cwd, entrypoint, HashMap::new(), options)
+    }
+
+    async fn echo(operation: &mut NodeJsOperation, message: &str) -> anyhow::Result<String> {
+        operation.send(message).await?;
+        operation.recv().await
+    }
+
+    fn idle_processes(pool: &NodeJsPool) -> usize {
+        pool.processes.lock().unwrap().len()
+    }
+
+    fn process_id(operation: &NodeJsOperation) -> u32 {
+        let process = operation.process.as_ref().unwrap();
+        process.child.as_ref().unwrap().id().unwrap()
+    }
+
+    /// Waits until the process with the id `pid` has exited and was reaped.
+    #[cfg(target_os = "linux")]
+    async fn wait_for_exit(pid: u32) {
+        let path = std::path::PathBuf::from(format!("/proc/{pid}"));
+        for _ in 0..500 {
+            if !path.exists() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
         }
+        panic!("process {pid} is still running");
+    }
+
+    #[tokio::test]
+    async fn reuses_processes_of_finished_operations() {
+        let pool = echo_pool("reuse", NodeJsPoolOptions::default());
+
+        let mut operation = pool.operation().await.unwrap();
+        assert_eq!(echo(&mut operation, "a").await.unwrap(), "a");
+        let pid = process_id(&operation);
+        drop(operation);
+        assert_eq!(idle_processes(&pool), 1);
+
+        let mut operation = pool.operation().await.unwrap();
+        assert_eq!(process_id(&operation), pid);
+        assert_eq!(echo(&mut operation, "b").await.unwrap(), "b");
+    }
+
+    #[tokio::test]
+    async fn keeps_at_most_max_idle_processes() {
+        let pool = echo_pool(
+            "max-idle",
+            NodeJsPoolOptions {
+                concurrency: 2,
+                max_idle_processes: 1,
+                operation_timeout: None,
+            },
+        );
+
+        let mut first = pool.operation().await.unwrap();
+        let mut second = pool.operation().await.unwrap();
+        assert_eq!(echo(&mut first, "a").await.unwrap(), "a");
+        assert_eq!(echo(&mut second, "b").await.unwrap(), "b");
+        drop(first);
+        drop(second);
+        assert_eq!(idle_processes(&pool), 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn kills_processes_which_time_out() {
+        let pool = echo_pool(
+            "timeout",
+            NodeJsPoolOptions {
+                operation_timeout: Some(Duration::from_millis(200)),
+                ..Default::default()
+            },
+        );
+
+        let mut operation = pool.operation().await.unwrap();
+        assert_eq!(echo(&mut operation, "a").await.unwrap(), "a");
+        let pid = process_id(&operation);
+        let err = echo(&mut operation, "hang").await.unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err:?}");
+        drop(operation);
+
+        assert_eq!(idle_processes(&pool), 0);
+        wait_for_exit(pid).await;
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn kills_processes_of_dropped_operations() {
+        let pool = echo_pool("drop", NodeJsPoolOptions::default());
+
+        let mut operation = pool.operation().await.unwrap();
+        let pid = process_id(&operation);
+        // The answer would be received by the next operation of the process
+        operation.send("a").await.unwrap();
+        drop(operation);
+
+        assert_eq!(idle_processes(&pool), 0);
+        wait_for_exit(pid).await;
     }
 }
@@ -0,0 +1,466 @@
+//! Transformation of CSS files with PostCSS, so the plugins configured in a
+//! `postcss.config.js` (e. g. autoprefixer or tailwindcss) are applied.
+//!
+//! The config is evaluated with [evaluate_config], so its plugins have to be
+//! listed by their package name, either as an object mapping names to options
+//! or as an array of names and `[name, options]` pairs. The CSS is transformed
+//! in a Node.js process, which reports the files and directories the plugins
+//! depend on. The CSS is transformed again when one of them changes.
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use turbo_tasks::{primitives::StringVc, ValueToString};
+use turbo_tasks_fs::{
+    glob::GlobVc, to_sys_path, DirectoryEntry, File, FileContent, FileSystemPathVc,
+};
+use turbo_tasks_hash::{encode_hex, Xxh3Hash64Hasher};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    reference::AssetReferencesVc,
+    source_transform::{SourceTransform, SourceTransformVc},
+    virtual_asset::VirtualAssetVc,
+};
+
+use super::{
+    emit,
+    evaluate::{evaluate_config, relative_unix_path, sources_hash, EvaluatedConfig},
+    issue::PostCssIssue,
+    pool::{NodeJsPool, NodeJsPoolOptions, NodeJsPoolVc},
+};
+use crate::embed_js::next_js_file;
+
+/// The dependencies can change while the CSS is transformed. Transforming
+/// stops when they still differ after this many transformations.
+const MAX_TRANSFORMS: usize = 10;
+
+/// A [SourceTransform] applying the plugins of the postcss config at
+/// `config_path`. The Node.js process is started from files emitted to
+/// `intermediate_output_path`.
+#[turbo_tasks::value]
+pub struct PostCssTransform {
+    config_path: FileSystemPathVc,
+    intermediate_output_path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl PostCssTransformVc {
+    #[turbo_tasks::function]
+    pub fn new(config_path: FileSystemPathVc, intermediate_output_path: FileSystemPathVc) -> Self {
+        Self::cell(PostCssTransform {
+            config_path,
+            intermediate_output_path,
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl SourceTransform for PostCssTransform {
+    #[turbo_tasks::function]
+    fn transform(&self, source: AssetVc) -> AssetVc {
+        PostCssTransformedAsset {
+            source,
+            config_path: self.config_path,
+            intermediate_output_path: self.intermediate_output_path,
+        }
+        .cell()
+        .into()
+    }
+}
+
+#[turbo_tasks::value]
+struct PostCssTransformedAsset {
+    source: AssetVc,
+    config_path: FileSystemPathVc,
+    intermediate_output_path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for PostCssTransformedAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.source.path()
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        postcss_transform(self.source, self.config_path, self.intermediate_output_path)
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        self.source.references()
+    }
+}
+
+/// The files and directories the plugins depend on, relative to the root of
+/// the filesystem. Directories are matched with a glob.
+#[derive(Default, PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+struct Dependencies {
+    files: Vec<String>,
+    dirs: Vec<(String, String)>,
+}
+
+/// The result of a transformation in the Node.js process.
+#[turbo_tasks::value(shared, serialization = "none")]
+enum PostCssResult {
+    Css {
+        css: String,
+        #[turbo_tasks(trace_ignore)]
+        dependencies: Dependencies,
+    },
+    Error {
+        name: String,
+        message: String,
+        stack: String,
+        #[turbo_tasks(trace_ignore)]
+        dependencies: Dependencies,
+    },
+}
+
+impl PostCssResult {
+    fn dependencies(&self) -> &Dependencies {
+        match self {
+            PostCssResult::Css { dependencies, .. } | PostCssResult::Error { dependencies, .. } => {
+                dependencies
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum PostCssOutgoingMessage<'a> {
+    Transform {
+        cwd: &'a Path,
+        file: &'a Path,
+        css: &'a str,
+        plugins: &'a JsonValue,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DirDependency {
+    dir: String,
+    glob: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum PostCssIncomingMessage {
+    #[serde(rename_all = "camelCase")]
+    Css {
+        css: String,
+        dependencies: Vec<String>,
+        dir_dependencies: Vec<DirDependency>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Error {
+        name: String,
+        message: String,
+        stack: String,
+        dependencies: Vec<String>,
+        dir_dependencies: Vec<DirDependency>,
+    },
+}
+
+/// Creates a pool of processes, which transform CSS with the packages in
+/// `cwd`.
+#[turbo_tasks::function]
+async fn get_postcss_pool(
+    cwd: FileSystemPathVc,
+    intermediate_output_path: FileSystemPathVc,
+) -> Result<NodeJsPoolVc> {
+    // The runner is executed by node directly. It's a .cjs file, so it's not
+    // affected by the package.json of the output directory.
+    let entrypoint = intermediate_output_path.join("postcss-transform.cjs");
+    emit(
+        VirtualAssetVc::new(
+            entrypoint,
+            next_js_file("internal/postcss-transform.js").into(),
+        )
+        .into(),
+        intermediate_output_path,
+    )
+    .await?;
+
+    let cwd = to_sys_path(cwd)
+        .await?
+        .context("can only transform CSS with PostCSS from a disk filesystem")?;
+    let entrypoint = to_sys_path(entrypoint)
+        .await?
+        .context("can only transform CSS with PostCSS with a disk output filesystem")?;
+    Ok(NodeJsPool::new(
+        cwd,
+        entrypoint,
+        HashMap::new(),
+        NodeJsPoolOptions::default(),
+    )
+    .cell())
+}
+
+/// Transforms `css`, the content of the file at `path`, in a Node.js process.
+/// The plugins can read other files from disk, so `dependencies_hash` has to
+/// identify the content of the files they depend on.
+#[turbo_tasks::function]
+async fn transform_in_process(
+    path: FileSystemPathVc,
+    css: String,
+    plugins: String,
+    config_path: FileSystemPathVc,
+    intermediate_output_path: FileSystemPathVc,
+    // Only used as cache key
+    _dependencies_hash: String,
+) -> Result<PostCssResultVc> {
+    let root = to_sys_path(path.root())
+        .await?
+        .context("can only transform CSS with PostCSS from a disk filesystem")?;
+    let file = to_sys_path(path)
+        .await?
+        .context("can only transform CSS with PostCSS from a disk filesystem")?;
+    let cwd = config_path.parent();
+    let pool = get_postcss_pool(cwd, intermediate_output_path)
+        .strongly_consistent()
+        .await?;
+    let cwd = to_sys_path(cwd)
+        .await?
+        .context("can only transform CSS with PostCSS from a disk filesystem")?;
+    let plugins: JsonValue = serde_json::from_str(&plugins)?;
+    let mut operation = pool.operation().await?;
+    operation
+        .send(PostCssOutgoingMessage::Transform {
+            cwd: &cwd,
+            file: &file,
+            css: &css,
+            plugins: &plugins,
+        })
+        .await
+        .context("sending CSS to the PostCSS process")?;
+    let dependencies = |files: Vec<String>, dirs: Vec<DirDependency>| Dependencies {
+        files: files
+            .iter()
+            .filter_map(|file| relative_unix_path(&root, Path::new(file)))
+            .collect(),
+        dirs: dirs
+            .into_iter()
+            .filter_map(|DirDependency { dir, glob }| {
+                relative_unix_path(&root, Path::new(&dir)).map(|dir| (dir, glob))
+            })
+            .collect(),
+    };
+    Ok(match operation
+        .recv()
+        .await
+        .context("receiving transformed CSS from the PostCSS process")?
+    {
+        PostCssIncomingMessage::Css {
+            css,
+            dependencies: files,
+            dir_dependencies,
+        } => PostCssResult::Css {
+            css,
+            dependencies: dependencies(files, dir_dependencies),
+        },
+        PostCssIncomingMessage::Error {
+            name,
+            message,
+            stack,
+            dependencies: files,
+            dir_dependencies,
+        } => PostCssResult::Error {
+            name,
+            message,
+            stack,
+            dependencies: dependencies(files, dir_dependencies),
+        },
+    }
+    .cell())
+}
+
+/// Hashes the content of the files in `dirs` matching their globs, which
+/// makes the calling task depend on them.
+async fn dirs_hash(root: FileSystemPathVc, dirs: &[(String, String)]) -> Result<String> {
+    let mut hasher = Xxh3Hash64Hasher::new();
+    for (dir, glob) in dirs {
+        hasher.write_ref(dir);
+        hasher.write_ref(glob);
+        let mut files = Vec::new();
+        let mut queue = vec![root.join(dir).read_glob(GlobVc::new(glob), false)];
+        while let Some(result) = queue.pop() {
+            let result = result.await?;
+            for entry in result.results.values() {
+                if let DirectoryEntry::File(file) = entry {
+                    files.push(file.await?.path.clone());
+                }
+            }
+            queue.extend(result.inner.values().copied());
+        }
+        files.sort();
+        hasher.write_ref(&sources_hash(root, &files).await?);
+    }
+    Ok(encode_hex(hasher.finish()))
+}
+
+/// Converts the plugins of a postcss config to a list of package names and
+/// their options, or `null` when they have none. Disabled plugins are
+/// omitted.
+fn plugins_from_config(config: &JsonValue) -> Result<Vec<(String, JsonValue)>> {
+    Ok(match config.get("plugins") {
+        None | Some(JsonValue::Null) => Vec::new(),
+        Some(JsonValue::Object(plugins)) => plugins
+            .iter()
+            .filter(|(_, options)| **options != JsonValue::Bool(false))
+            .map(|(name, options)| (name.clone(), options.clone()))
+            .collect(),
+        Some(JsonValue::Array(plugins)) => {
+            let mut result = Vec::new();
+            for plugin in plugins {
+                match plugin {
+                    JsonValue::String(name) => result.push((name.clone(), JsonValue::Null)),
+                    JsonValue::Array(pair) => match pair.as_slice() {
+                        [JsonValue::String(name)] => {
+                            result.push((name.clone(), JsonValue::Null));
+                        }
+                        [JsonValue::String(name), options] => {
+                            result.push((name.clone(), options.clone()));
+                        }
+                        _ => bail!("plugins must be a package name and its options"),
+                    },
+                    _ => bail!("plugins must be listed by their package name"),
+                }
+            }
+            result
+        }
+        Some(_) => bail!("plugins must be an object or an array"),
+    })
+}
+
+/// Transforms the CSS of `source` with the plugins of the postcss config at
+/// `config_path`. When the config doesn't exist, has no plugins or can't be
+/// evaluated, the content of `source` is returned unchanged.
+#[turbo_tasks::function]
+async fn postcss_transform(
+    source: AssetVc,
+    config_path: FileSystemPathVc,
+    intermediate_output_path: FileSystemPathVc,
+) -> Result<AssetContentVc> {
+    let path = source.path();
+    let config = evaluate_config(config_path, intermediate_output_path).await?;
+    let plugins = match &*config {
+        EvaluatedConfig::Value(config) => match plugins_from_config(config) {
+            Ok(plugins) => plugins,
+            Err(err) => {
+                PostCssIssue {
+                    path: config_path,
+                    message: StringVc::cell(format!("Invalid postcss config: {err}")),
+                    stack: StringVc::empty(),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                return Ok(source.content());
+            }
+        },
+        EvaluatedConfig::NotFound | EvaluatedConfig::Error => return Ok(source.content()),
+    };
+    if plugins.is_empty() {
+        return Ok(source.content());
+    }
+    let plugins = serde_json::to_string(&plugins)?;
+    let css = match &*source.content().await? {
+        AssetContent::File(file) => match &*file.await? {
+            FileContent::Content(file) => String::from_utf8_lossy(file.content()).into_owned(),
+            FileContent::NotFound => return Ok(source.content()),
+        },
+        AssetContent::Redirect { .. } => return Ok(source.content()),
+    };
+
+    // The files the plugins depend on are only known after transforming. It's
+    // transformed again when they differ from the files hashed for the cache
+    // key.
+    let root = path.root();
+    let mut dependencies = Dependencies::default();
+    for _ in 0..MAX_TRANSFORMS {
+        let hash = format!(
+            "{}{}",
+            sources_hash(root, &dependencies.files).await?,
+            dirs_hash(root, &dependencies.dirs).await?
+        );
+        let result = transform_in_process(
+            path,
+            css.clone(),
+            plugins.clone(),
+            config_path,
+            intermediate_output_path,
+            hash,
+        )
+        .await?;
+        if *result.dependencies() != dependencies {
+            dependencies = result.dependencies().clone();
+            continue;
+        }
+        return Ok(match &*result {
+            PostCssResult::Css { css, .. } => File::from(css.clone()).into(),
+            PostCssResult::Error {
+                name,
+                message,
+                stack,
+                ..
+            } => {
+                PostCssIssue {
+                    path,
+                    message: StringVc::cell(format!("{name}: {message}")),
+                    stack: StringVc::cell(stack.clone()),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                source.content()
+            }
+        });
+    }
+    bail!(
+        "the files PostCSS depends on kept changing while transforming {}",
+        path.to_string().await?
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value as JsonValue};
+
+    use super::plugins_from_config;
+
+    #[test]
+    fn plugins_of_configs() {
+        let plugins = plugins_from_config(&json!({
+            "plugins": { "tailwindcss": {}, "autoprefixer": true, "cssnano": false }
+        }))
+        .unwrap();
+        assert_eq!(
+            plugins,
+            vec![
+                ("tailwindcss".to_string(), json!({})),
+                ("autoprefixer".to_string(), json!(true)),
+            ]
+        );
+
+        let plugins = plugins_from_config(&json!({
+            "plugins": ["autoprefixer", ["postcss-preset-env", { "stage": 3 }]]
+        }))
+        .unwrap();
+        assert_eq!(
+            plugins,
+            vec![
+                ("autoprefixer".to_string(), JsonValue::Null),
+                ("postcss-preset-env".to_string(), json!({ "stage": 3 })),
+            ]
+        );
+
+        assert!(plugins_from_config(&json!({})).unwrap().is_empty());
+        assert!(plugins_from_config(&json!({ "plugins": [42] })).is_err());
+    }
+}
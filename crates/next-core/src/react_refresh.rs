@@ -13,6 +13,8 @@ use turbopack_core::{
     resolve::{origin::ResolveOriginVc, parse::RequestVc, ResolveResult},
 };
 
+use crate::issue_codes;
+
 #[turbo_tasks::function]
 fn react_refresh_request() -> RequestVc {
     RequestVc::parse_string("@next/react-refresh-utils/dist/runtime".to_string())
@@ -129,6 +131,11 @@ impl Issue for ReactRefreshResolvingIssue {
         StringVc::cell("other".to_string())
     }
 
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(issue_codes::REACT_REFRESH.to_string())
+    }
+
     #[turbo_tasks::function]
     fn context(&self) -> FileSystemPathVc {
         self.path
@@ -0,0 +1,240 @@
+//! Discovery of entries in a directory of routes, following the conventions
+//! of the `pages` and `app` directories of Next.js.
+//!
+//! The files matching the configured globs are read with
+//! [FileSystemPathVc::read_glob], so the discovered entries are recomputed
+//! when files are added to or removed from the directory.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::{glob::GlobVc, DirectoryEntry, FileSystemPathVc};
+use turbopack_core::issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+
+use crate::issue_codes;
+
+/// The extensions of route files in the default globs.
+const ROUTE_EXTENSIONS: [&str; 4] = ["js", "jsx", "ts", "tsx"];
+
+/// How the files in the routes directory map to routes.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord)]
+pub enum RouteConvention {
+    /// Every file is a route: `blog/[slug].js` is the route `/blog/[slug]`.
+    /// `index` files are the route of their directory. Files in the root
+    /// starting with `_` (e. g. `_app.js`) are not routes.
+    Pages,
+    /// Every file is the route of its directory: `blog/[slug]/page.js` is the
+    /// route `/blog/[slug]`. Route groups, like `(marketing)`, and parallel
+    /// routes, like `@modal`, are not part of the route. Directories starting
+    /// with `_` are private and don't contain routes.
+    App,
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct RouteDiscoveryOptions {
+    pub convention: RouteConvention,
+    /// Globs matching the entries, relative to the routes directory. Only `*`,
+    /// `**` and `?` are supported.
+    pub include: Vec<String>,
+    /// Globs matching files which are not entries, even when they are matched
+    /// by `include`.
+    pub exclude: Vec<String>,
+}
+
+impl RouteDiscoveryOptions {
+    /// Matches all JavaScript and TypeScript files, like the `pages`
+    /// directory.
+    pub fn pages() -> Self {
+        RouteDiscoveryOptions {
+            convention: RouteConvention::Pages,
+            include: ROUTE_EXTENSIONS
+                .iter()
+                .map(|extension| format!("**/*.{extension}"))
+                .collect(),
+            exclude: Vec::new(),
+        }
+    }
+
+    /// Matches the `page` files, like the `app` directory.
+    pub fn app() -> Self {
+        RouteDiscoveryOptions {
+            convention: RouteConvention::App,
+            include: ROUTE_EXTENSIONS
+                .iter()
+                .map(|extension| format!("**/page.{extension}"))
+                .collect(),
+            exclude: Vec::new(),
+        }
+    }
+}
+
+/// The entries of routes by their route, e. g. `/blog/[slug]`, sorted by
+/// route.
+#[turbo_tasks::value(transparent)]
+pub struct RouteEntries(IndexMap<String, FileSystemPathVc>);
+
+/// Discovers the entries of the routes in `routes_dir`. When multiple files
+/// map to the same route, the first one (ordered by path) is used and an issue
+/// is emitted.
+#[turbo_tasks::function]
+pub async fn discover_route_entries(
+    routes_dir: FileSystemPathVc,
+    options: RouteDiscoveryOptionsVc,
+) -> Result<RouteEntriesVc> {
+    let options = options.await?;
+    let mut exclude = Vec::new();
+    for glob in options.exclude.iter() {
+        exclude.push(GlobVc::new(glob).await?);
+    }
+
+    // The relative paths of all matching files
+    let mut files = BTreeMap::new();
+    for glob in options.include.iter() {
+        let mut queue = vec![routes_dir.read_glob(GlobVc::new(glob), false)];
+        while let Some(result) = queue.pop() {
+            let result = result.await?;
+            for (path, entry) in result.results.iter() {
+                if let DirectoryEntry::File(file) | DirectoryEntry::Symlink(file) = entry {
+                    if !exclude.iter().any(|glob| glob.execute(path)) {
+                        files.insert(path.clone(), *file);
+                    }
+                }
+            }
+            queue.extend(result.inner.values().copied());
+        }
+    }
+
+    let mut routes = BTreeMap::<String, FileSystemPathVc>::new();
+    for (path, file) in files {
+        let route = match route_for_path(options.convention, &path) {
+            Some(route) => route,
+            None => continue,
+        };
+        if let Some(&existing) = routes.get(&route) {
+            ConflictingRoutesIssue {
+                routes_dir,
+                route,
+                files: vec![existing, file],
+            }
+            .cell()
+            .as_issue()
+            .emit();
+            continue;
+        }
+        routes.insert(route, file);
+    }
+    Ok(RouteEntriesVc::cell(routes.into_iter().collect()))
+}
+
+/// Returns the route of the file at `path`, relative to the routes directory,
+/// or `None` when it isn't a route.
+fn route_for_path(convention: RouteConvention, path: &str) -> Option<String> {
+    let mut segments = path.split('/').collect::<Vec<_>>();
+    let file_name = segments.pop()?;
+    let stem = file_name
+        .rsplit_once('.')
+        .map_or(file_name, |(stem, _)| stem);
+    match convention {
+        RouteConvention::Pages => {
+            if segments.is_empty() && stem.starts_with('_') {
+                return None;
+            }
+            if stem != "index" {
+                segments.push(stem);
+            }
+        }
+        RouteConvention::App => {
+            if segments.iter().any(|segment| segment.starts_with('_')) {
+                return None;
+            }
+            segments.retain(|segment| {
+                let is_group = segment.starts_with('(') && segment.ends_with(')');
+                !is_group && !segment.starts_with('@')
+            });
+        }
+    }
+    Some(format!("/{}", segments.join("/")))
+}
+
+#[turbo_tasks::value(shared)]
+struct ConflictingRoutesIssue {
+    routes_dir: FileSystemPathVc,
+    route: String,
+    files: Vec<FileSystemPathVc>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for ConflictingRoutesIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!("Conflicting files for the route {}", self.route))
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("routes".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(issue_codes::CONFLICTING_ROUTES.to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.routes_dir
+    }
+
+    #[turbo_tasks::function]
+    async fn description(&self) -> Result<StringVc> {
+        let mut files = Vec::new();
+        for file in self.files.iter() {
+            files.push(file.await?.path.clone());
+        }
+        Ok(StringVc::cell(format!(
+            "The files {} map to the same route. Only the first one is used.",
+            files.join(" and ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{route_for_path, RouteConvention};
+
+    #[test]
+    fn pages_routes() {
+        let route = |path| route_for_path(RouteConvention::Pages, path);
+        assert_eq!(route("index.js"), Some("/".to_string()));
+        assert_eq!(route("about.tsx"), Some("/about".to_string()));
+        assert_eq!(route("blog/index.js"), Some("/blog".to_string()));
+        assert_eq!(route("blog/[slug].js"), Some("/blog/[slug]".to_string()));
+        assert_eq!(route("_app.js"), None);
+        assert_eq!(route("docs/_intro.js"), Some("/docs/_intro".to_string()));
+    }
+
+    #[test]
+    fn app_routes() {
+        let route = |path| route_for_path(RouteConvention::App, path);
+        assert_eq!(route("page.js"), Some("/".to_string()));
+        assert_eq!(
+            route("blog/[slug]/page.tsx"),
+            Some("/blog/[slug]".to_string())
+        );
+        assert_eq!(
+            route("(marketing)/about/page.js"),
+            Some("/about".to_string())
+        );
+        assert_eq!(route("@modal/login/page.js"), Some("/login".to_string()));
+        assert_eq!(route("_components/page.js"), None);
+    }
+}
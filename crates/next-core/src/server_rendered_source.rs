@@ -10,6 +10,7 @@ use turbo_tasks_fs::{DirectoryContent, DirectoryEntry, FileSystemEntryType, File
 use turbopack::{transition::TransitionsByNameVc, ModuleAssetContextVc};
 use turbopack_core::{
     asset::AssetVc,
+    cache::RemoteCacheVc,
     chunk::{dev::DevChunkingContextVc, ChunkingContextVc},
     context::AssetContextVc,
     source_asset::SourceAssetVc,
@@ -25,17 +26,18 @@ use turbopack_dev_server::{
 };
 use turbopack_ecmascript::{
     chunk::EcmascriptChunkPlaceablesVc, EcmascriptInputTransform, EcmascriptInputTransformsVc,
-    EcmascriptModuleAssetType, EcmascriptModuleAssetVc,
+    EcmascriptModuleAssetType, EcmascriptModuleAssetVc, JsxRuntime,
 };
 use turbopack_env::ProcessEnvAssetVc;
 
 use crate::{
     embed_js::{next_js_file, wrap_with_next_js_fs},
+    env::filter_for_client,
     fallback::get_fallback_page,
     next_client::{
         context::{
-            add_next_transforms_to_pages, get_client_assets_path, get_client_chunking_context,
-            get_client_environment, get_client_module_options_context,
+            add_next_transforms_to_pages, add_postcss_transform, get_client_assets_path,
+            get_client_chunking_context, get_client_environment, get_client_module_options_context,
             get_client_resolve_options_context, get_client_runtime_entries, ContextType,
         },
         NextClientTransition,
@@ -47,6 +49,7 @@ use crate::{
     nodejs::{
         create_node_api_source, create_node_rendered_source,
         node_entry::{NodeRenderingEntry, NodeRenderingEntryVc},
+        pool::NodeJsPoolOptionsVc,
         NodeEntry, NodeEntryVc,
     },
     util::regular_expression_for_path,
@@ -61,6 +64,9 @@ pub async fn create_server_rendered_source(
     server_root: FileSystemPathVc,
     env: ProcessEnvVc,
     browserslist_query: &str,
+    pool_options: NodeJsPoolOptionsVc,
+    hermetic: bool,
+    remote_cache: Option<RemoteCacheVc>,
 ) -> Result<ContentSourceVc> {
     let project_path = wrap_with_next_js_fs(project_root);
 
@@ -78,14 +84,22 @@ pub async fn create_server_rendered_source(
     let server_ty = Value::new(ServerContextType::Pages { pages_dir });
 
     let client_chunking_context = get_client_chunking_context(project_path, server_root, ty);
-    let client_environment = get_client_environment(browserslist_query);
+    let client_environment =
+        get_client_environment(browserslist_query).with_defined_env(filter_for_client(env));
     let client_module_options_context =
-        get_client_module_options_context(project_path, client_environment, ty);
+        get_client_module_options_context(project_path, client_environment, ty, hermetic);
     let client_module_options_context =
         add_next_transforms_to_pages(client_module_options_context, pages_dir);
-    let client_resolve_options_context = get_client_resolve_options_context(project_path, ty);
+    let client_module_options_context = add_postcss_transform(
+        client_module_options_context,
+        project_path,
+        output_path.join("postcss"),
+        remote_cache,
+    );
+    let client_resolve_options_context =
+        get_client_resolve_options_context(project_path, ty, hermetic);
 
-    let client_runtime_entries = get_client_runtime_entries(project_path, env, ty);
+    let client_runtime_entries = get_client_runtime_entries(project_path, env, ty, hermetic);
 
     let next_client_transition = NextClientTransition {
         is_app: false,
@@ -105,14 +119,14 @@ pub async fn create_server_rendered_source(
         TransitionsByNameVc::cell(transitions),
         get_server_environment(server_ty, env),
         get_server_module_options_context(server_ty),
-        get_server_resolve_options_context(project_path, server_ty, StringsVc::empty()),
+        get_server_resolve_options_context(project_path, server_ty, StringsVc::empty(), hermetic),
     )
     .into();
 
     let server_runtime_entries =
         vec![ProcessEnvAssetVc::new(project_path, env).as_ecmascript_chunk_placeable()];
 
-    let fallback_page = get_fallback_page(project_path, server_root, browserslist_query);
+    let fallback_page = get_fallback_page(project_path, server_root, browserslist_query, hermetic);
 
     let server_rendered_source = create_server_rendered_source_for_directory(
         project_path,
@@ -125,6 +139,7 @@ pub async fn create_server_rendered_source(
         server_root,
         server_root.join("api"),
         output_path,
+        pool_options,
     );
     let fallback_source =
         AssetGraphContentSourceVc::new_eager(server_root, fallback_page.as_asset());
@@ -149,6 +164,7 @@ async fn create_server_rendered_source_for_file(
     server_path: FileSystemPathVc,
     is_api_path: BoolVc,
     intermediate_output_path: FileSystemPathVc,
+    pool_options: NodeJsPoolOptionsVc,
 ) -> Result<ContentSourceVc> {
     let source_asset = SourceAssetVc::new(page_file).into();
     let entry_asset = context.process(source_asset);
@@ -171,6 +187,7 @@ async fn create_server_rendered_source_for_file(
                 is_api_path,
                 chunking_context,
                 intermediate_output_path,
+                pool_options,
             }
             .cell()
             .into(),
@@ -186,6 +203,7 @@ async fn create_server_rendered_source_for_file(
                 is_api_path,
                 chunking_context,
                 intermediate_output_path,
+                pool_options,
             }
             .cell()
             .into(),
@@ -210,6 +228,7 @@ async fn create_server_rendered_source_for_directory(
     server_path: FileSystemPathVc,
     server_api_path: FileSystemPathVc,
     intermediate_output_path: FileSystemPathVc,
+    pool_options: NodeJsPoolOptionsVc,
 ) -> Result<CombinedContentSourceVc> {
     let mut predefined_sources = vec![];
     let mut named_placeholder_sources = vec![];
@@ -253,6 +272,7 @@ async fn create_server_rendered_source_for_directory(
                                         dev_server_path,
                                         dev_server_path.is_inside(server_api_path),
                                         intermediate_output_path,
+                                        pool_options,
                                     ),
                                 ));
                             }
@@ -274,6 +294,7 @@ async fn create_server_rendered_source_for_directory(
                             server_path.join(name),
                             server_api_path,
                             intermediate_output_path.join(name),
+                            pool_options,
                         )
                         .into(),
                     ));
@@ -305,6 +326,7 @@ struct SsrEntry {
     is_api_path: BoolVc,
     chunking_context: ChunkingContextVc,
     intermediate_output_path: FileSystemPathVc,
+    pool_options: NodeJsPoolOptionsVc,
 }
 
 #[turbo_tasks::value_impl]
@@ -330,12 +352,17 @@ impl NodeEntry for SsrEntry {
                 Value::new(EcmascriptModuleAssetType::Typescript),
                 EcmascriptInputTransformsVc::cell(vec![
                     EcmascriptInputTransform::TypeScript,
-                    EcmascriptInputTransform::React { refresh: false },
+                    EcmascriptInputTransform::React {
+                        refresh: false,
+                        runtime: JsxRuntime::Automatic,
+                        import_source: None,
+                    },
                 ]),
                 self.context.environment(),
             ),
             chunking_context: self.chunking_context,
             intermediate_output_path: self.intermediate_output_path,
+            pool_options: self.pool_options,
         }
         .cell())
     }
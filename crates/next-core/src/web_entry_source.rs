@@ -2,9 +2,13 @@ use anyhow::{anyhow, Result};
 use turbo_tasks::{TryJoinIterExt, Value};
 use turbo_tasks_env::ProcessEnvVc;
 use turbo_tasks_fs::FileSystemPathVc;
-use turbopack::ecmascript::EcmascriptModuleAssetVc;
+use turbopack::{
+    ecmascript::EcmascriptModuleAssetVc, prebundle::PrebundledDependenciesVc, ModuleAssetContextVc,
+};
 use turbopack_core::{
+    asset::{AssetVc, AssetsVc},
     chunk::{ChunkGroupVc, ChunkableAssetVc},
+    context::AssetContextVc,
     resolve::{origin::PlainResolveOriginVc, parse::RequestVc},
 };
 use turbopack_dev_server::{
@@ -16,7 +20,7 @@ use crate::{
     embed_js::wrap_with_next_js_fs,
     next_client::context::{
         get_client_asset_context, get_client_chunking_context, get_client_runtime_entries,
-        ContextType,
+        get_client_vendor_chunking_context, ContextType,
     },
 };
 
@@ -27,33 +31,44 @@ pub async fn create_web_entry_source(
     server_root: FileSystemPathVc,
     env: ProcessEnvVc,
     eager_compile: bool,
+    vendor_chunks: bool,
+    prebundle_cache_dir: Option<FileSystemPathVc>,
     browserslist_query: &str,
+    hermetic: bool,
 ) -> Result<ContentSourceVc> {
     let project_root = wrap_with_next_js_fs(project_root);
 
     let ty = Value::new(ContextType::Other);
-    let context = get_client_asset_context(project_root, browserslist_query, ty);
-    let chunking_context = get_client_chunking_context(project_root, server_root, ty);
-    let entries = get_client_runtime_entries(project_root, env, ty);
+    let mut context = get_client_asset_context(project_root, browserslist_query, ty, hermetic);
+    if let Some(cache_dir) = prebundle_cache_dir {
+        if let Some(module_context) = ModuleAssetContextVc::resolve_from(context).await? {
+            let entries = resolve_entries(context, project_root, &entry_requests).await?;
+            let prebundled = PrebundledDependenciesVc::new(
+                module_context,
+                AssetsVc::cell(entries),
+                project_root,
+                cache_dir,
+            );
+            // Writes the files in the background, the app reads them from
+            // memory
+            let _ = prebundled.emit();
+            context = module_context
+                .with_extended_import_map(prebundled.import_map())
+                .into();
+        }
+    }
+    let chunking_context = if vendor_chunks {
+        get_client_vendor_chunking_context(project_root, server_root, ty)
+    } else {
+        get_client_chunking_context(project_root, server_root, ty)
+    };
+    let entries = get_client_runtime_entries(project_root, env, ty, hermetic);
 
     let runtime_entries = entries.resolve_entries(context);
 
-    let origin = PlainResolveOriginVc::new(context, project_root.join("_")).as_resolve_origin();
-    let entries = entry_requests
-        .into_iter()
-        .map(|request| async move {
-            Ok(origin
-                .resolve_asset(request, origin.resolve_options())
-                .primary_assets()
-                .await?
-                .first()
-                .copied())
-        })
-        .try_join()
-        .await?;
+    let entries = resolve_entries(context, project_root, &entry_requests).await?;
     let chunks: Vec<_> = entries
         .into_iter()
-        .flatten()
         .enumerate()
         .map(|(i, module)| async move {
             if let Some(ecmascript) = EcmascriptModuleAssetVc::resolve_from(module).await? {
@@ -88,3 +103,26 @@ pub async fn create_web_entry_source(
     .into();
     Ok(graph)
 }
+
+/// Resolves the modules of `entry_requests` from `project_root`. Requests
+/// which don't resolve are skipped.
+async fn resolve_entries(
+    context: AssetContextVc,
+    project_root: FileSystemPathVc,
+    entry_requests: &[RequestVc],
+) -> Result<Vec<AssetVc>> {
+    let origin = PlainResolveOriginVc::new(context, project_root.join("_")).as_resolve_origin();
+    let entries = entry_requests
+        .iter()
+        .map(|&request| async move {
+            Ok(origin
+                .resolve_asset(request, origin.resolve_options())
+                .primary_assets()
+                .await?
+                .first()
+                .copied())
+        })
+        .try_join()
+        .await?;
+    Ok(entries.into_iter().flatten().collect())
+}
@@ -0,0 +1,574 @@
+//! Best-effort import of a `webpack.config.js`, to ease the migration of
+//! existing applications.
+//!
+//! The config is evaluated in the config sandbox (see [evaluate_config_lossy])
+//! and the options which have an equivalent in turbopack are mapped onto it:
+//! `entry`, `resolve.alias`, `resolve.extensions`, `process.env` values of the
+//! `DefinePlugin` and `externals`. Everything else is reported as an issue.
+
+use std::path::Path;
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_env::{CustomProcessEnvVc, EnvMapVc, ProcessEnvVc};
+use turbo_tasks_fs::{to_sys_path, FileSystemPathVc};
+use turbopack::resolve_options_context::ResolveOptionsContextVc;
+use turbopack_core::{
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    resolve::options::{ImportMap, ImportMapVc, ImportMapping},
+};
+
+use crate::{
+    issue_codes,
+    nodejs::evaluate::{evaluate_config_lossy, relative_unix_path, EvaluatedConfig},
+};
+
+/// The name of the webpack config file in the project directory.
+pub const WEBPACK_CONFIG_FILE: &str = "webpack.config.js";
+
+/// The options of a webpack config which are supported by turbopack.
+#[turbo_tasks::value(shared)]
+pub struct WebpackConfig {
+    /// The requests of the entries by their name, relative to the project
+    /// directory.
+    pub entries: IndexMap<String, Vec<String>>,
+    /// The import map of `resolve.alias` and `externals`.
+    pub import_map: ImportMapVc,
+    /// The extensions of `resolve.extensions`.
+    pub extensions: Option<Vec<String>>,
+    /// The `process.env` values defined with the `DefinePlugin`.
+    pub defined_env: EnvMapVc,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionWebpackConfig(Option<WebpackConfigVc>);
+
+#[turbo_tasks::value_impl]
+impl WebpackConfigVc {
+    /// Applies the aliases, extensions and externals of the config to
+    /// `context`. Aliases take precedence over the import map of `context`.
+    #[turbo_tasks::function]
+    pub async fn apply_to_resolve_options_context(
+        self,
+        context: ResolveOptionsContextVc,
+    ) -> Result<ResolveOptionsContextVc> {
+        let this = self.await?;
+        let mut context = context
+            .with_extended_import_map(this.import_map)
+            .await?
+            .clone_value();
+        if let Some(extensions) = &this.extensions {
+            context.custom_extensions = Some(extensions.clone());
+        }
+        Ok(context.cell())
+    }
+
+    /// Extends `env` with the `process.env` values defined in the config.
+    #[turbo_tasks::function]
+    pub async fn process_env(self, env: ProcessEnvVc) -> Result<ProcessEnvVc> {
+        Ok(CustomProcessEnvVc::new(env, self.await?.defined_env).into())
+    }
+}
+
+/// Loads the [WEBPACK_CONFIG_FILE] in `project_path`. Returns `None` when
+/// there is no config or it couldn't be evaluated.
+#[turbo_tasks::function]
+pub async fn load_webpack_config(
+    project_path: FileSystemPathVc,
+    intermediate_output_path: FileSystemPathVc,
+) -> Result<OptionWebpackConfigVc> {
+    let path = project_path.join(WEBPACK_CONFIG_FILE);
+    let mut options = match &*evaluate_config_lossy(path, intermediate_output_path).await? {
+        EvaluatedConfig::Value(value) => read_webpack_options(value),
+        EvaluatedConfig::NotFound | EvaluatedConfig::Error => {
+            return Ok(OptionWebpackConfigVc::cell(None))
+        }
+    };
+
+    // Aliases usually point to absolute paths, like `path.resolve(__dirname,
+    // "src")`. They are resolved relative to the project directory instead.
+    let project_dir = to_sys_path(project_path).await?;
+    let mut import_map = ImportMap::empty();
+    for (alias, target) in options.aliases {
+        let requests = match target {
+            AliasTarget::Requests(requests) => requests,
+            AliasTarget::Ignore => {
+                import_map
+                    .insert_exact_alias(alias.trim_end_matches('$'), ImportMapping::Ignore.cell());
+                continue;
+            }
+        };
+        let mut relative_requests = Vec::new();
+        for request in requests {
+            if !Path::new(&request).is_absolute() {
+                relative_requests.push(request);
+                continue;
+            }
+            match project_dir
+                .as_deref()
+                .and_then(|project_dir| relative_unix_path(project_dir, Path::new(&request)))
+            {
+                Some(relative) => relative_requests.push(format!("./{relative}")),
+                None => options.unsupported.push((
+                    format!("resolve.alias.{alias}"),
+                    format!("points to {request}, which is outside of the project directory"),
+                )),
+            }
+        }
+        if relative_requests.is_empty() {
+            continue;
+        }
+        import_map.insert_user_alias(&alias, &relative_requests, Some(project_path));
+    }
+    for (request, target) in options.externals {
+        import_map.insert_exact_alias(request, ImportMapping::External(target).cell());
+    }
+
+    for (key_path, message) in options.unsupported {
+        UnsupportedWebpackConfigIssue {
+            path,
+            key_path,
+            message,
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    }
+
+    Ok(OptionWebpackConfigVc::cell(Some(
+        WebpackConfig {
+            entries: options.entries,
+            import_map: import_map.cell(),
+            extensions: options.extensions,
+            defined_env: EnvMapVc::cell(options.defined_env),
+        }
+        .cell(),
+    )))
+}
+
+#[derive(Debug, PartialEq)]
+enum AliasTarget {
+    Requests(Vec<String>),
+    /// `false`, which ignores the request.
+    Ignore,
+}
+
+/// The options read from the evaluated webpack config.
+#[derive(Debug, Default, PartialEq)]
+struct WebpackOptions {
+    entries: IndexMap<String, Vec<String>>,
+    aliases: Vec<(String, AliasTarget)>,
+    extensions: Option<Vec<String>>,
+    defined_env: IndexMap<String, String>,
+    externals: Vec<(String, Option<String>)>,
+    /// The key paths of unsupported options, with a message describing why
+    /// they are not supported.
+    unsupported: Vec<(String, String)>,
+}
+
+impl WebpackOptions {
+    fn unsupported(&mut self, key_path: impl Into<String>, message: impl Into<String>) {
+        self.unsupported.push((key_path.into(), message.into()));
+    }
+}
+
+/// Describes a value of the config for messages. Values which can't be
+/// represented as JSON are described by the sandbox.
+fn describe(value: &JsonValue) -> String {
+    match value {
+        JsonValue::Null => "null".to_string(),
+        JsonValue::Bool(_) => "a boolean".to_string(),
+        JsonValue::Number(_) => "a number".to_string(),
+        JsonValue::String(_) => "a string".to_string(),
+        JsonValue::Array(_) => "an array".to_string(),
+        JsonValue::Object(object) => match object.get("$unsupported") {
+            Some(JsonValue::String(ty)) if ty == "function" => "a function".to_string(),
+            Some(JsonValue::String(ty)) => format!("an instance of {ty}"),
+            _ => "an object".to_string(),
+        },
+    }
+}
+
+/// Reads a string or an array of strings.
+fn read_strings(value: &JsonValue) -> Option<Vec<String>> {
+    match value {
+        JsonValue::String(string) => Some(vec![string.clone()]),
+        JsonValue::Array(items) => items
+            .iter()
+            .map(|item| item.as_str().map(|item| item.to_string()))
+            .collect(),
+        _ => None,
+    }
+}
+
+fn read_webpack_options(config: &JsonValue) -> WebpackOptions {
+    let mut options = WebpackOptions::default();
+    let config = match config {
+        JsonValue::Array(configs) => {
+            if configs.len() > 1 {
+                options.unsupported(
+                    "config",
+                    "exports multiple configurations, only the first one is used",
+                );
+            }
+            match configs.first() {
+                Some(config) => config,
+                None => return options,
+            }
+        }
+        config => config,
+    };
+    let config = match config {
+        JsonValue::Object(config) => config,
+        config => {
+            options.unsupported("config", format!("is {}", describe(config)));
+            return options;
+        }
+    };
+    for (key, value) in config {
+        match key.as_str() {
+            "entry" => read_entry(&mut options, value),
+            "resolve" => read_resolve(&mut options, value),
+            "externals" => read_externals(&mut options, "externals", value),
+            "plugins" => read_plugins(&mut options, value),
+            // Turbopack decides these on its own
+            "mode" | "name" => {}
+            _ => options.unsupported(key.as_str(), "is not supported"),
+        }
+    }
+    options
+}
+
+fn read_entry(options: &mut WebpackOptions, entry: &JsonValue) {
+    if let Some(requests) = read_strings(entry) {
+        options.entries.insert("main".to_string(), requests);
+        return;
+    }
+    let entries = match entry {
+        JsonValue::Object(entries) if !entries.contains_key("$unsupported") => entries,
+        entry => {
+            options.unsupported("entry", format!("is {}", describe(entry)));
+            return;
+        }
+    };
+    for (name, entry) in entries {
+        let key_path = format!("entry.{name}");
+        let requests = match entry {
+            JsonValue::Object(descriptor) => {
+                for key in descriptor.keys().filter(|&key| key != "import") {
+                    options.unsupported(format!("{key_path}.{key}"), "is not supported");
+                }
+                descriptor.get("import").and_then(read_strings)
+            }
+            entry => read_strings(entry),
+        };
+        match requests {
+            Some(requests) => {
+                options.entries.insert(name.clone(), requests);
+            }
+            None => options.unsupported(key_path, "must be a string or an array of strings"),
+        }
+    }
+}
+
+fn read_resolve(options: &mut WebpackOptions, resolve: &JsonValue) {
+    let resolve = match resolve {
+        JsonValue::Object(resolve) => resolve,
+        resolve => {
+            options.unsupported("resolve", format!("is {}", describe(resolve)));
+            return;
+        }
+    };
+    for (key, value) in resolve {
+        match key.as_str() {
+            "alias" => read_aliases(options, value),
+            "extensions" => match read_strings(value) {
+                // "..." refers to the default extensions of webpack, which differ
+                // from the ones of turbopack
+                Some(extensions) if extensions.iter().any(|extension| extension == "...") => {
+                    options.unsupported("resolve.extensions", "can't contain \"...\"")
+                }
+                Some(extensions) => options.extensions = Some(extensions),
+                None => options.unsupported("resolve.extensions", "must be an array of strings"),
+            },
+            _ => options.unsupported(format!("resolve.{key}"), "is not supported"),
+        }
+    }
+}
+
+fn read_aliases(options: &mut WebpackOptions, aliases: &JsonValue) {
+    let aliases = match aliases {
+        JsonValue::Object(aliases) if !aliases.contains_key("$unsupported") => aliases,
+        aliases => {
+            options.unsupported(
+                "resolve.alias",
+                format!("is {}, only objects are supported", describe(aliases)),
+            );
+            return;
+        }
+    };
+    for (alias, target) in aliases {
+        let target = match target {
+            JsonValue::Bool(false) => AliasTarget::Ignore,
+            target => match read_strings(target) {
+                Some(requests) => AliasTarget::Requests(requests),
+                None => {
+                    options.unsupported(
+                        format!("resolve.alias.{alias}"),
+                        format!("is {}", describe(target)),
+                    );
+                    continue;
+                }
+            },
+        };
+        options.aliases.push((alias.clone(), target));
+    }
+}
+
+fn read_externals(options: &mut WebpackOptions, key_path: &str, externals: &JsonValue) {
+    match externals {
+        JsonValue::String(request) => options.externals.push((request.clone(), None)),
+        JsonValue::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                read_externals(options, &format!("{key_path}[{i}]"), item);
+            }
+        }
+        JsonValue::Object(externals) if !externals.contains_key("$unsupported") => {
+            for (request, target) in externals {
+                read_external(options, &format!("{key_path}.{request}"), request, target);
+            }
+        }
+        externals => options.unsupported(key_path, format!("is {}", describe(externals))),
+    }
+}
+
+/// The types of externals which are loaded as a module.
+const MODULE_EXTERNAL_TYPES: [&str; 5] =
+    ["commonjs", "commonjs2", "node-commonjs", "module", "import"];
+
+fn read_external(options: &mut WebpackOptions, key_path: &str, request: &str, target: &JsonValue) {
+    match target {
+        JsonValue::Bool(true) => options.externals.push((request.to_string(), None)),
+        JsonValue::String(target) => match target.split_once(' ') {
+            Some((ty, target)) if MODULE_EXTERNAL_TYPES.contains(&ty) => options
+                .externals
+                .push((request.to_string(), Some(target.to_string()))),
+            Some((ty, _)) => options.unsupported(
+                key_path,
+                format!("has the type {ty}, which is not supported"),
+            ),
+            None => options.unsupported(
+                key_path,
+                "refers to a global variable, only externals loaded as a module (e. g. \"commonjs \
+                 lodash\") are supported",
+            ),
+        },
+        target => options.unsupported(key_path, format!("is {}", describe(target))),
+    }
+}
+
+fn read_plugins(options: &mut WebpackOptions, plugins: &JsonValue) {
+    let plugins = match plugins {
+        JsonValue::Array(plugins) => plugins,
+        plugins => {
+            options.unsupported("plugins", format!("is {}", describe(plugins)));
+            return;
+        }
+    };
+    for (i, plugin) in plugins.iter().enumerate() {
+        let key_path = format!("plugins[{i}]");
+        let definitions = match plugin {
+            JsonValue::Object(plugin)
+                if plugin.get("$unsupported").and_then(JsonValue::as_str)
+                    == Some("DefinePlugin") =>
+            {
+                plugin
+                    .get("properties")
+                    .and_then(|properties| properties.get("definitions"))
+            }
+            JsonValue::Null | JsonValue::Bool(false) => continue,
+            plugin => {
+                options.unsupported(
+                    key_path,
+                    format!("is {}, which is not supported", describe(plugin)),
+                );
+                continue;
+            }
+        };
+        match definitions {
+            Some(JsonValue::Object(definitions)) => {
+                read_definitions(options, &format!("{key_path}.definitions"), definitions)
+            }
+            _ => options.unsupported(key_path, "has no definitions"),
+        }
+    }
+}
+
+fn read_definitions(
+    options: &mut WebpackOptions,
+    key_path: &str,
+    definitions: &JsonMap<String, JsonValue>,
+) {
+    for (key, value) in definitions {
+        let key_path = format!("{key_path}.{key}");
+        if let Some(name) = key.strip_prefix("process.env.") {
+            read_defined_env(options, &key_path, name, value);
+        } else if key == "process.env" {
+            match value {
+                JsonValue::Object(env) if !env.contains_key("$unsupported") => {
+                    for (name, value) in env {
+                        read_defined_env(options, &format!("{key_path}.{name}"), name, value);
+                    }
+                }
+                value => options.unsupported(key_path, format!("is {}", describe(value))),
+            }
+        } else {
+            options.unsupported(
+                key_path,
+                "is not supported, only `process.env` values can be defined",
+            );
+        }
+    }
+}
+
+/// The `DefinePlugin` replaces expressions with the code of their definition.
+/// Only string literals can be represented as `process.env` values.
+fn read_defined_env(options: &mut WebpackOptions, key_path: &str, name: &str, code: &JsonValue) {
+    match code
+        .as_str()
+        .and_then(|code| serde_json::from_str::<String>(code).ok())
+    {
+        Some(value) => {
+            options.defined_env.insert(name.to_string(), value);
+        }
+        None => options.unsupported(
+            key_path,
+            "must be a string literal, like JSON.stringify(\"value\")",
+        ),
+    }
+}
+
+#[turbo_tasks::value(shared)]
+struct UnsupportedWebpackConfigIssue {
+    path: FileSystemPathVc,
+    key_path: String,
+    message: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for UnsupportedWebpackConfigIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Unsupported option in webpack config".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("config".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(issue_codes::UNSUPPORTED_WEBPACK_CONFIG.to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(format!(
+            "`{}` {}. It's ignored by turbopack.",
+            self.key_path, self.message
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+    use serde_json::json;
+
+    use super::{read_webpack_options, AliasTarget, WebpackOptions};
+
+    #[test]
+    fn supported_options() {
+        let config = json!({
+            "mode": "development",
+            "entry": { "main": "./src/index.js", "admin": { "import": ["./src/admin.js"] } },
+            "resolve": {
+                "alias": { "fs": false, "utils$": "/project/src/utils.js" },
+                "extensions": [".ts", ".js"],
+            },
+            "externals": ["react", { "lodash": "commonjs lodash-es" }],
+            "plugins": [{
+                "$unsupported": "DefinePlugin",
+                "properties": { "definitions": { "process.env.API": "\"https://api\"" } },
+            }],
+        });
+        assert_eq!(
+            read_webpack_options(&config),
+            WebpackOptions {
+                entries: IndexMap::from_iter([
+                    ("main".to_string(), vec!["./src/index.js".to_string()]),
+                    ("admin".to_string(), vec!["./src/admin.js".to_string()]),
+                ]),
+                aliases: vec![
+                    ("fs".to_string(), AliasTarget::Ignore),
+                    (
+                        "utils$".to_string(),
+                        AliasTarget::Requests(vec!["/project/src/utils.js".to_string()])
+                    ),
+                ],
+                extensions: Some(vec![".ts".to_string(), ".js".to_string()]),
+                defined_env: IndexMap::from_iter([("API".to_string(), "https://api".to_string())]),
+                externals: vec![
+                    ("react".to_string(), None),
+                    ("lodash".to_string(), Some("lodash-es".to_string())),
+                ],
+                unsupported: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn unsupported_options() {
+        let config = json!({
+            "devtool": "source-map",
+            "externals": { "jquery": "jQuery" },
+            "plugins": [
+                { "$unsupported": "HtmlWebpackPlugin", "properties": {} },
+                {
+                    "$unsupported": "DefinePlugin",
+                    "properties": {
+                        "definitions": { "DEBUG": "true", "process.env.DEBUG": "true" },
+                    },
+                },
+            ],
+        });
+        let unsupported = read_webpack_options(&config)
+            .unsupported
+            .into_iter()
+            .map(|(key_path, _)| key_path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            unsupported,
+            vec![
+                "devtool",
+                "externals.jquery",
+                "plugins[0]",
+                "plugins[1].definitions.DEBUG",
+                "plugins[1].definitions.process.env.DEBUG",
+            ]
+        );
+    }
+}
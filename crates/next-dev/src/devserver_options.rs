@@ -2,7 +2,10 @@ use std::{net::IpAddr, path::PathBuf};
 
 #[cfg(feature = "cli")]
 use clap::Parser;
-use turbopack_cli_utils::issue::IssueSeverityCliOption;
+use turbopack_cli_utils::{
+    issue::{IssueCodeOverrideCliOption, IssuePathOverrideCliOption, IssueSeverityCliOption},
+    reporter::OutputFormat,
+};
 
 #[derive(Debug)]
 #[cfg_attr(feature = "cli", derive(Parser))]
@@ -45,6 +48,26 @@ pub struct DevServerOptions {
     #[cfg_attr(feature = "serializable", serde(default))]
     pub eager_compile: bool,
 
+    /// Split every package in node_modules into a chunk of its own, which
+    /// stays the same while the app code changes.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub vendor_chunks: bool,
+
+    /// Bundle every package in node_modules the app imports into an ES module
+    /// file in .next/cache/prebundle, and redirect the imports of the app to
+    /// the files.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub prebundle: bool,
+
+    /// Store the results of source transforms, e. g. PostCSS, in this
+    /// directory, and reuse them when the source and the config are unchanged.
+    /// The directory can be shared, e. g. on a network drive.
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub remote_cache_dir: Option<PathBuf>,
+
     /// Don't open the browser automatically when the dev server has started.
     #[cfg_attr(feature = "cli", clap(long))]
     #[cfg_attr(feature = "serializable", serde(default))]
@@ -55,6 +78,12 @@ pub struct DevServerOptions {
     /// Filter by issue severity.
     pub log_level: Option<IssueSeverityCliOption>,
 
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Fail requests when an issue with this severity, or a more severe one,
+    /// occurs. Defaults to `fatal`.
+    pub fail_level: Option<IssueSeverityCliOption>,
+
     #[cfg_attr(feature = "cli", clap(long))]
     #[cfg_attr(feature = "serializable", serde(default))]
     /// Show all log messages without limit.
@@ -65,6 +94,116 @@ pub struct DevServerOptions {
     /// Expand the log details.
     pub log_detail: bool,
 
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Suppress or change the severity of issues with a code, e. g.
+    /// `--issue-code TP1004=off` or `--issue-code TP2002=error`. Can be
+    /// passed multiple times.
+    pub issue_code: Vec<IssueCodeOverrideCliOption>,
+
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Suppress or change the severity of issues in files matching a glob,
+    /// optionally only those with a code, e. g. `--issue-path
+    /// node_modules/**=off` or `--issue-path src/**:TP1004=error`. Can be
+    /// passed multiple times, the first matching one is used.
+    pub issue_path: Vec<IssuePathOverrideCliOption>,
+
+    #[cfg_attr(feature = "cli", clap(long, value_enum))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// How issues are logged. Defaults to colored output, unless the NO_COLOR
+    /// environment variable is set or the output isn't a terminal.
+    pub log_format: Option<OutputFormat>,
+
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Print newline-delimited JSON events instead of messages for humans,
+    /// e. g. for wrapper tools. Issues are printed as JSON lines, too.
+    pub json: bool,
+
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// A JSON file mapping the ids of log messages to translated messages.
+    pub message_catalog: Option<PathBuf>,
+
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Keep all current issues in this file as JSON lines, regardless of the
+    /// log level, e. g. for editor integrations.
+    pub diagnostics_file: Option<PathBuf>,
+
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Print the cache hits and misses of each task function after every
+    /// compilation.
+    pub print_cache_stats: bool,
+
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// The number of seconds after which a Node.js process rendering a page or
+    /// API route is killed when it doesn't respond.
+    pub render_timeout: Option<u64>,
+
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// The estimated memory in megabytes which cached values may use. When
+    /// it's exceeded, values which can be recomputed, e. g. ASTs, are dropped
+    /// until they are needed again.
+    pub memory_limit: Option<usize>,
+
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Ignore changes of files matching a glob relative to the root
+    /// directory, e. g. `--watch-ignore 'node_modules/.cache/**'`. Can be
+    /// passed multiple times.
+    pub watch_ignore: Vec<String>,
+
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// The number of milliseconds file changes are collected before they are
+    /// applied.
+    pub watch_debounce: Option<u64>,
+
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Poll the files for changes instead of relying on events of the
+    /// operating system, e. g. for network file systems.
+    pub watch_poll: bool,
+
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Subscribe to file changes via Watchman when it's installed, e. g. for
+    /// very large repositories.
+    pub watchman: bool,
+
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Forbid network access and reading files outside of the root
+    /// directory, e. g. through symlinks, for reproducible builds. Telemetry
+    /// is disabled and the browser isn't opened.
+    pub hermetic: bool,
+
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Record the invalidations and task executions to a file, e. g. to
+    /// report a stale output or a missed invalidation.
+    pub record: Option<PathBuf>,
+
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Profile the task executions and write them to this file in the chrome
+    /// tracing format after every compilation, e. g. for chrome://tracing or
+    /// speedscope. The time spent per build phase and the slowest task
+    /// functions are printed, too.
+    pub profile: Option<PathBuf>,
+
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Replay the invalidations of a recording against this checkout and
+    /// print which tasks are executed differently. The replay itself is
+    /// recorded to the file passed with `--record`.
+    pub replay: Option<PathBuf>,
+
     // Inherited options from next-dev, need revisit later.
     // This is not supported by CLI yet.
     #[cfg_attr(feature = "serializable", serde(default))]
@@ -5,33 +5,52 @@ pub mod devserver_options;
 mod turbo_tasks_viz;
 
 use std::{
-    collections::HashSet,
+    cmp::max,
+    collections::{HashMap, HashSet},
     env::current_dir,
+    fmt::Write,
     future::join,
     net::IpAddr,
-    path::MAIN_SEPARATOR,
+    path::{Path, PathBuf, MAIN_SEPARATOR},
     sync::Arc,
     time::{Duration, Instant},
 };
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use devserver_options::DevServerOptions;
 use next_core::{
     create_app_source, create_server_rendered_source, create_web_entry_source, env::load_env,
-    source_map::NextSourceMapTraceContentSourceVc,
+    source_map::NextSourceMapTraceContentSourceVc, NodeJsPoolOptions,
 };
 use owo_colors::OwoColorize;
 use turbo_tasks::{
-    primitives::StringsVc, util::FormatDuration, RawVc, TransientInstance, TransientValue,
-    TurboTasks, Value,
+    primitives::StringsVc,
+    profiling::Profiler,
+    recording::{read_recording, Recorder},
+    registry,
+    util::FormatDuration,
+    RawVc, TransientInstance, TransientValue, TurboTasks, Value,
+};
+use turbo_tasks_fs::{DiskFileSystemVc, FileSystemVc, WatchOptions, WatchOptionsVc};
+use turbo_tasks_memory::{
+    stats::{Stats, TaskType},
+    viz, GcPolicy, MemoryBackend,
+};
+use turbopack_cli_utils::{
+    events::{millis, Event},
+    issue::{ConsoleUi, ConsoleUiVc, IssueCodeOverride, IssuePathOverride, LogOptions},
+    reporter::{terminal_width, MessageCatalog, OutputFormat},
+    telemetry::{BuildMetrics, Telemetry, TelemetryEvent},
+};
+use turbopack_core::{
+    cache::{CacheBackend, DiskCacheBackend, RemoteCacheVc},
+    issue::IssueSeverity,
+    resolve::parse::RequestVc,
 };
-use turbo_tasks_fs::{DiskFileSystemVc, FileSystemVc};
-use turbo_tasks_memory::MemoryBackend;
-use turbopack_cli_utils::issue::{ConsoleUi, ConsoleUiVc, LogOptions};
-use turbopack_core::{issue::IssueSeverity, resolve::parse::RequestVc};
 use turbopack_dev_server::{
     fs::DevServerFileSystemVc,
     introspect::IntrospectionSource,
+    module_graph::ModuleGraphSource,
     source::{
         combined::CombinedContentSource, router::RouterContentSource,
         static_assets::StaticAssetsContentSourceVc, ContentSourceVc,
@@ -46,12 +65,24 @@ pub struct NextDevServerBuilder {
     entry_requests: Vec<String>,
     server_component_externals: Vec<String>,
     eager_compile: bool,
+    vendor_chunks: bool,
+    prebundle: bool,
+    remote_cache_dir: Option<PathBuf>,
     hostname: Option<IpAddr>,
     port: Option<u16>,
     browserslist_query: String,
     log_level: IssueSeverity,
+    fail_level: IssueSeverity,
     show_all: bool,
     log_detail: bool,
+    issue_code_overrides: HashMap<String, IssueCodeOverride>,
+    issue_path_overrides: Vec<IssuePathOverride>,
+    log_format: OutputFormat,
+    message_catalog: MessageCatalog,
+    diagnostics_file: Option<PathBuf>,
+    node_pool_options: NodeJsPoolOptions,
+    hermetic: bool,
+    watch_options: WatchOptions,
 }
 
 impl NextDevServerBuilder {
@@ -67,14 +98,26 @@ impl NextDevServerBuilder {
             entry_requests: vec![],
             server_component_externals: vec![],
             eager_compile: false,
+            vendor_chunks: false,
+            prebundle: false,
+            remote_cache_dir: None,
             hostname: None,
             port: None,
             browserslist_query: "last 1 Chrome versions, last 1 Firefox versions, last 1 Safari \
                                  versions, last 1 Edge versions"
                 .to_owned(),
             log_level: IssueSeverity::Warning,
+            fail_level: IssueSeverity::Fatal,
             show_all: false,
             log_detail: false,
+            issue_code_overrides: HashMap::new(),
+            issue_path_overrides: vec![],
+            log_format: OutputFormat::from_env(),
+            message_catalog: MessageCatalog::default(),
+            diagnostics_file: None,
+            node_pool_options: NodeJsPoolOptions::default(),
+            hermetic: false,
+            watch_options: WatchOptions::default(),
         }
     }
 
@@ -93,6 +136,25 @@ impl NextDevServerBuilder {
         self
     }
 
+    /// Splits every package in node_modules into a vendor chunk of its own.
+    pub fn vendor_chunks(mut self, vendor_chunks: bool) -> NextDevServerBuilder {
+        self.vendor_chunks = vendor_chunks;
+        self
+    }
+
+    /// Pre-bundles the packages the entries import into ES module files.
+    pub fn prebundle(mut self, prebundle: bool) -> NextDevServerBuilder {
+        self.prebundle = prebundle;
+        self
+    }
+
+    /// Stores the results of source transforms in a directory, which can be
+    /// shared between checkouts and machines.
+    pub fn remote_cache_dir(mut self, remote_cache_dir: PathBuf) -> NextDevServerBuilder {
+        self.remote_cache_dir = Some(remote_cache_dir);
+        self
+    }
+
     pub fn hostname(mut self, hostname: IpAddr) -> NextDevServerBuilder {
         self.hostname = Some(hostname);
         self
@@ -113,6 +175,13 @@ impl NextDevServerBuilder {
         self
     }
 
+    /// Requests fail when an issue with this severity, or a more severe one,
+    /// occurs while handling them.
+    pub fn fail_level(mut self, fail_level: IssueSeverity) -> NextDevServerBuilder {
+        self.fail_level = fail_level;
+        self
+    }
+
     pub fn show_all(mut self, show_all: bool) -> NextDevServerBuilder {
         self.show_all = show_all;
         self
@@ -123,6 +192,63 @@ impl NextDevServerBuilder {
         self
     }
 
+    /// Suppresses or changes the severity of the issues with `code`.
+    pub fn issue_code_override(
+        mut self,
+        code: String,
+        code_override: IssueCodeOverride,
+    ) -> NextDevServerBuilder {
+        self.issue_code_overrides.insert(code, code_override);
+        self
+    }
+
+    /// Suppresses or changes the severity of the issues about files matching
+    /// a glob. Path overrides are matched in the order they are added, before
+    /// the code overrides.
+    pub fn issue_path_override(mut self, path_override: IssuePathOverride) -> NextDevServerBuilder {
+        self.issue_path_overrides.push(path_override);
+        self
+    }
+
+    pub fn log_format(mut self, log_format: OutputFormat) -> NextDevServerBuilder {
+        self.log_format = log_format;
+        self
+    }
+
+    /// Replaces the english messages of the log output.
+    pub fn message_catalog(mut self, message_catalog: MessageCatalog) -> NextDevServerBuilder {
+        self.message_catalog = message_catalog;
+        self
+    }
+
+    /// Writes all current issues to a file as JSON lines, e. g. for editors.
+    pub fn diagnostics_file(mut self, diagnostics_file: PathBuf) -> NextDevServerBuilder {
+        self.diagnostics_file = Some(diagnostics_file);
+        self
+    }
+
+    /// Configures the Node.js processes which render pages and API routes.
+    pub fn node_pool_options(
+        mut self,
+        node_pool_options: NodeJsPoolOptions,
+    ) -> NextDevServerBuilder {
+        self.node_pool_options = node_pool_options;
+        self
+    }
+
+    /// Forbids reading files outside of the root directory, e. g. through
+    /// symlinks.
+    pub fn hermetic(mut self, hermetic: bool) -> NextDevServerBuilder {
+        self.hermetic = hermetic;
+        self
+    }
+
+    /// Configures how the project files are watched for changes.
+    pub fn watch_options(mut self, watch_options: WatchOptions) -> NextDevServerBuilder {
+        self.watch_options = watch_options;
+        self
+    }
+
     pub async fn build(self) -> Result<DevServer> {
         let turbo_tasks = self.turbo_tasks;
 
@@ -131,14 +257,29 @@ impl NextDevServerBuilder {
         let entry_requests = self.entry_requests;
         let server_component_externals = self.server_component_externals;
         let eager_compile = self.eager_compile;
+        let vendor_chunks = self.vendor_chunks;
+        let prebundle = self.prebundle;
+        let remote_cache_dir = self
+            .remote_cache_dir
+            .map(|dir| dir.to_string_lossy().to_string());
         let show_all = self.show_all;
         let log_detail = self.log_detail;
         let browserslist_query = self.browserslist_query;
+        let node_pool_options = self.node_pool_options;
+        let hermetic = self.hermetic;
+        let watch_options = self.watch_options;
         let log_options = LogOptions {
             current_dir: current_dir().unwrap(),
             show_all,
             log_detail,
             log_level: self.log_level,
+            fail_level: self.fail_level,
+            code_overrides: self.issue_code_overrides,
+            path_overrides: self.issue_path_overrides,
+            format: self.log_format,
+            terminal_width: terminal_width(),
+            messages: self.message_catalog,
+            diagnostics_file: self.diagnostics_file,
         };
         let console_ui = Arc::new(ConsoleUi::new(log_options));
         let console_ui_to_dev_server = console_ui.clone();
@@ -151,10 +292,16 @@ impl NextDevServerBuilder {
                     project_dir.clone(),
                     entry_requests.clone(),
                     eager_compile,
+                    vendor_chunks,
+                    prebundle,
+                    remote_cache_dir.clone(),
                     turbo_tasks.clone().into(),
                     console_ui.clone().into(),
                     browserslist_query.clone(),
                     server_component_externals.clone(),
+                    Value::new(node_pool_options.clone()),
+                    hermetic,
+                    Value::new(watch_options.clone()),
                 )
             },
             (
@@ -182,10 +329,24 @@ async fn handle_issues<T: Into<RawVc>>(source: T, console_ui: ConsoleUiVc) -> Re
 }
 
 #[turbo_tasks::function]
-async fn project_fs(project_dir: &str, console_ui: ConsoleUiVc) -> Result<FileSystemVc> {
-    let disk_fs = DiskFileSystemVc::new("project".to_string(), project_dir.to_string());
+async fn project_fs(
+    project_dir: &str,
+    hermetic: bool,
+    watch_options: WatchOptionsVc,
+    console_ui: ConsoleUiVc,
+) -> Result<FileSystemVc> {
+    let disk_fs = if hermetic {
+        DiskFileSystemVc::new_hermetic("project".to_string(), project_dir.to_string())
+    } else {
+        DiskFileSystemVc::new("project".to_string(), project_dir.to_string())
+    };
     handle_issues(disk_fs, console_ui).await?;
-    disk_fs.await?.start_watching()?;
+    let disk_fs_value = disk_fs.await?;
+    // There is no persistent cache which restores the reads of a previous
+    // session, but files might have changed while the watcher is restarted,
+    // e. g. when the watch options change
+    disk_fs_value.invalidate();
+    disk_fs_value.start_watching_with_options(&*watch_options.await?)?;
     Ok(disk_fs.into())
 }
 
@@ -193,7 +354,10 @@ async fn project_fs(project_dir: &str, console_ui: ConsoleUiVc) -> Result<FileSy
 async fn output_fs(project_dir: &str, console_ui: ConsoleUiVc) -> Result<FileSystemVc> {
     let disk_fs = DiskFileSystemVc::new("output".to_string(), project_dir.to_string());
     handle_issues(disk_fs, console_ui).await?;
-    disk_fs.await?.start_watching()?;
+    let disk_fs_value = disk_fs.await?;
+    // Files written in a previous session might have been changed since
+    disk_fs_value.verify_emitted().await?;
+    disk_fs_value.start_watching()?;
     Ok(disk_fs.into())
 }
 
@@ -203,14 +367,25 @@ async fn source(
     project_dir: String,
     entry_requests: Vec<String>,
     eager_compile: bool,
+    vendor_chunks: bool,
+    prebundle: bool,
+    remote_cache_dir: Option<String>,
     turbo_tasks: TransientInstance<TurboTasks<MemoryBackend>>,
     console_ui: TransientInstance<ConsoleUi>,
     browserslist_query: String,
     server_component_externals: Vec<String>,
+    node_pool_options: Value<NodeJsPoolOptions>,
+    hermetic: bool,
+    watch_options: Value<WatchOptions>,
 ) -> Result<ContentSourceVc> {
     let console_ui = (*console_ui).clone().cell();
     let output_fs = output_fs(&project_dir, console_ui);
-    let fs = project_fs(&root_dir, console_ui);
+    let fs = project_fs(
+        &root_dir,
+        hermetic,
+        watch_options.into_value().cell(),
+        console_ui,
+    );
     let project_relative = project_dir.strip_prefix(&root_dir).unwrap();
     let project_relative = project_relative
         .strip_prefix(MAIN_SEPARATOR)
@@ -218,6 +393,14 @@ async fn source(
     let project_path = fs.root().join(project_relative);
 
     let env = load_env(project_path);
+    let node_pool_options = node_pool_options.into_value().cell();
+    let remote_cache = remote_cache_dir.map(|dir| {
+        let backend: Arc<dyn CacheBackend> = Arc::new(DiskCacheBackend::new(dir));
+        RemoteCacheVc::new(
+            TransientInstance::new(backend),
+            format!("next-dev@{}", env!("CARGO_PKG_VERSION")),
+        )
+    });
 
     let output_root = output_fs.root().join("/.next/server");
 
@@ -233,7 +416,10 @@ async fn source(
         dev_server_root,
         env,
         eager_compile,
+        vendor_chunks,
+        prebundle.then(|| output_fs.root().join("/.next/cache/prebundle")),
         &browserslist_query,
+        hermetic,
     );
     let rendered_source = create_server_rendered_source(
         project_path,
@@ -241,6 +427,9 @@ async fn source(
         dev_server_root,
         env,
         &browserslist_query,
+        node_pool_options,
+        hermetic,
+        remote_cache,
     );
     let app_source = create_app_source(
         project_path,
@@ -249,6 +438,8 @@ async fn source(
         env,
         &browserslist_query,
         StringsVc::cell(server_component_externals),
+        node_pool_options,
+        hermetic,
     );
     let viz = turbo_tasks_viz::TurboTasksSource {
         turbo_tasks: turbo_tasks.into(),
@@ -266,10 +457,16 @@ async fn source(
     }
     .cell()
     .into();
+    let module_graph = ModuleGraphSource {
+        roots: HashSet::from([main_source.into()]),
+    }
+    .cell()
+    .into();
     let source_map_trace = NextSourceMapTraceContentSourceVc::new(rendered_source).into();
     let source = RouterContentSource {
         routes: vec![
             ("__turbopack__/".to_string(), introspect),
+            ("__turbopack_module_graph__/".to_string(), module_graph),
             ("__turbo_tasks__/".to_string(), viz),
             (
                 "__nextjs_original-stack-frame".to_string(),
@@ -295,8 +492,21 @@ pub fn register() {
 
 /// Start a devserver with the given options.
 pub async fn start_server(options: &DevServerOptions) -> Result<()> {
+    start_server_with_telemetry(options, None).await
+}
+
+/// Start a devserver with the given options, which reports the metrics of
+/// the builds to `telemetry`.
+pub async fn start_server_with_telemetry(
+    options: &DevServerOptions,
+    telemetry: Option<Arc<dyn Telemetry>>,
+) -> Result<()> {
     let start = Instant::now();
 
+    if options.hermetic && telemetry.is_some() {
+        bail!("telemetry can't be enabled in hermetic mode, as it accesses the network");
+    }
+
     #[cfg(feature = "tokio_console")]
     console_subscriber::init();
     register();
@@ -321,22 +531,103 @@ pub async fn start_server(options: &DevServerOptions) -> Result<()> {
         dir.clone()
     };
 
-    let tt = TurboTasks::new(MemoryBackend::new());
+    let mut backend = MemoryBackend::new();
+    if let Some(memory_limit) = options.memory_limit {
+        backend = backend.with_gc_policy(GcPolicy::new(memory_limit * 1024 * 1024));
+    }
+    let tt = TurboTasks::new(backend);
+    // Files are watched, so executions outdated by a newer change can be
+    // aborted
+    tt.cancel_outdated_executions(true);
     let tt_clone = tt.clone();
 
+    let recorder = match options.record.as_ref() {
+        Some(path) => {
+            let recorder = Arc::new(Recorder::create(path)?);
+            tt.start_recording(recorder.clone());
+            Some(recorder)
+        }
+        None => None,
+    };
+    let profile = options.profile.clone().map(|path| {
+        let profiler = Arc::new(Profiler::new());
+        tt.start_profiling(profiler.clone());
+        (path, profiler)
+    });
+    let replay = match options.replay.as_ref() {
+        Some(path) => {
+            if recorder.is_none() {
+                bail!("replaying requires --record to record the replay");
+            }
+            Some(read_recording(path)?)
+        }
+        None => None,
+    };
+
     let mut server = NextDevServerBuilder::new(tt, dir, root_dir)
         .entry_request("src/index".into())
         .eager_compile(options.eager_compile)
+        .vendor_chunks(options.vendor_chunks)
+        .prebundle(options.prebundle)
         .hostname(options.hostname)
         .port(options.port)
         .log_detail(options.log_detail)
+        .node_pool_options(NodeJsPoolOptions {
+            operation_timeout: options.render_timeout.map(Duration::from_secs),
+            ..Default::default()
+        })
         .show_all(options.show_all)
+        .hermetic(options.hermetic)
+        .watch_options(WatchOptions {
+            ignore: options.watch_ignore.clone(),
+            debounce: options
+                .watch_debounce
+                .map_or_else(|| WatchOptions::default().debounce, Duration::from_millis),
+            poll: options.watch_poll,
+            watchman: options.watchman,
+        })
         .log_level(
             options
                 .log_level
                 .map_or_else(|| IssueSeverity::Warning, |l| l.0),
+        )
+        .fail_level(
+            options
+                .fail_level
+                .map_or_else(|| IssueSeverity::Fatal, |l| l.0),
         );
 
+    let json = options.json;
+    if json {
+        server = server.log_format(OutputFormat::JsonLines);
+    } else if let Some(log_format) = options.log_format {
+        server = server.log_format(log_format);
+    }
+
+    if let Some(path) = options.message_catalog.as_ref() {
+        let mut message_catalog = MessageCatalog::default();
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("message catalog {} can't be read", path.display()))?;
+        message_catalog.extend_from_json(&json)?;
+        server = server.message_catalog(message_catalog);
+    }
+
+    if let Some(path) = options.diagnostics_file.as_ref() {
+        server = server.diagnostics_file(path.clone());
+    }
+
+    if let Some(dir) = options.remote_cache_dir.as_ref() {
+        server = server.remote_cache_dir(dir.clone());
+    }
+
+    for option in options.issue_code.iter() {
+        server = server.issue_code_override(option.code.clone(), option.code_override);
+    }
+
+    for option in options.issue_path.iter() {
+        server = server.issue_path_override(option.path_override.clone());
+    }
+
     for package in options.server_components_external_packages.iter() {
         server = server.server_component_external(package.to_string());
     }
@@ -349,34 +640,123 @@ pub async fn start_server(options: &DevServerOptions) -> Result<()> {
         } else {
             format!("http://{}", server.addr)
         };
-        println!(
-            "{} - started server on {}:{}, url: {}",
-            "ready".green(),
-            server.addr.ip(),
-            server.addr.port(),
-            index_uri
-        );
-        if !options.no_open {
+        if json {
+            Event::ServerStarted {
+                address: server.addr.to_string(),
+                url: index_uri.clone(),
+            }
+            .print();
+        } else {
+            println!(
+                "{} - started server on {}:{}, url: {}",
+                "ready".green(),
+                server.addr.ip(),
+                server.addr.port(),
+                index_uri
+            );
+        }
+        if !options.no_open && !options.hermetic {
             let _ = webbrowser::open(&index_uri);
         }
     }
 
+    if let Some(events) = replay {
+        let tt = tt_clone.clone();
+        tokio::spawn(async move {
+            match tt.replay(events).await {
+                Ok(report) if report.matches() => {
+                    println!("{} - replay matches the recording", "event".purple())
+                }
+                Ok(report) => println!(
+                    "{} - replay differs from the recording\n{report}",
+                    "warn".yellow()
+                ),
+                Err(err) => println!("{} - replay failed: {err:?}", "error".red()),
+            }
+        });
+    }
+
+    let print_cache_stats = options.print_cache_stats;
+    let features = vec![
+        ("eager_compile", options.eager_compile),
+        ("vendor_chunks", options.vendor_chunks),
+        ("prebundle", options.prebundle),
+        ("remote_cache", options.remote_cache_dir.is_some()),
+        (
+            "server_components_external_packages",
+            !options.server_components_external_packages.is_empty(),
+        ),
+        ("render_timeout", options.render_timeout.is_some()),
+        ("message_catalog", options.message_catalog.is_some()),
+        ("issue_code_overrides", !options.issue_code.is_empty()),
+        ("issue_path_overrides", !options.issue_path.is_empty()),
+        ("fail_level", options.fail_level.is_some()),
+        ("diagnostics_file", options.diagnostics_file.is_some()),
+    ];
     let stats_future = async move {
-        println!(
-            "{event_type} - initial compilation {start}",
-            event_type = "event".purple(),
-            start = FormatDuration(start.elapsed()),
-        );
+        if let Some(telemetry) = &telemetry {
+            telemetry.record(&TelemetryEvent::Compiled(build_metrics(
+                &tt_clone,
+                start.elapsed(),
+                None,
+                &features,
+            )));
+        }
+        if json {
+            Event::Compiled {
+                duration_ms: millis(start.elapsed()),
+                task_execution_ms: None,
+                tasks: None,
+            }
+            .print();
+        } else {
+            println!(
+                "{event_type} - initial compilation {start}",
+                event_type = "event".purple(),
+                start = FormatDuration(start.elapsed()),
+            );
+        }
+        if print_cache_stats {
+            print_task_cache_stats(&tt_clone);
+        }
+        if let Some((path, profiler)) = &profile {
+            write_profile(path, profiler);
+        }
 
         loop {
-            let (elapsed, _count) = tt_clone
+            let (elapsed, count) = tt_clone
                 .get_or_wait_update_info(Duration::from_millis(100))
                 .await;
-            println!(
-                "{event_type} - updated in {elapsed}",
-                event_type = "event".purple(),
-                elapsed = FormatDuration(elapsed),
-            );
+            if let Some(telemetry) = &telemetry {
+                telemetry.record(&TelemetryEvent::Updated(build_metrics(
+                    &tt_clone,
+                    elapsed,
+                    Some(count),
+                    &features,
+                )));
+            }
+            if json {
+                Event::Updated {
+                    duration_ms: millis(elapsed),
+                    tasks: count,
+                }
+                .print();
+            } else {
+                println!(
+                    "{event_type} - updated in {elapsed}",
+                    event_type = "event".purple(),
+                    elapsed = FormatDuration(elapsed),
+                );
+            }
+            if print_cache_stats {
+                print_task_cache_stats(&tt_clone);
+            }
+            if let Some((path, profiler)) = &profile {
+                write_profile(path, profiler);
+            }
+            if let Some(recorder) = &recorder {
+                let _ = recorder.flush();
+            }
         }
     };
 
@@ -384,3 +764,128 @@ pub async fn start_server(options: &DevServerOptions) -> Result<()> {
 
     Ok(())
 }
+
+/// Prints the cache hits and misses of all task functions which were executed
+/// so far.
+fn print_task_cache_stats(tt: &TurboTasks<MemoryBackend>) {
+    let mut stats = Stats::new();
+    let backend = tt.backend();
+    backend.with_all_cached_tasks(|task| {
+        stats.add_id(backend, task);
+    });
+    println!(
+        "{event_type} - task cache stats\n{table}",
+        event_type = "event".purple(),
+        table = viz::cache::format_cache_stats(&stats.cache_stats()),
+    );
+}
+
+/// The number of task functions listed in the profile summary.
+const PROFILE_FUNCTIONS: usize = 10;
+
+/// Writes the profile to `path` and prints the time spent per build phase and
+/// in the slowest task functions.
+fn write_profile(path: &Path, profiler: &Profiler) {
+    if let Err(err) = std::fs::write(path, profiler.chrome_trace()) {
+        println!(
+            "{} - profile can't be written to {}: {err}",
+            "error".red(),
+            path.display()
+        );
+    }
+    let profiles = profiler.function_profiles();
+    let mut phases = Vec::<(&str, Duration)>::new();
+    for profile in profiles.iter() {
+        let phase = build_phase(&profile.name);
+        match phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, duration)) => *duration += profile.total_duration,
+            None => phases.push((phase, profile.total_duration)),
+        }
+    }
+    phases.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    let width = profiles
+        .iter()
+        .take(PROFILE_FUNCTIONS)
+        .map(|profile| profile.name.len())
+        .fold("function".len(), max);
+    let mut table = String::new();
+    for (phase, duration) in phases {
+        writeln!(table, "{phase:<width$}  {}", FormatDuration(duration)).unwrap();
+    }
+    writeln!(
+        table,
+        "\n{:<width$}  {:>10}  time",
+        "function", "executions"
+    )
+    .unwrap();
+    for profile in profiles.iter().take(PROFILE_FUNCTIONS) {
+        writeln!(
+            table,
+            "{:<width$}  {:>10}  {}",
+            profile.name,
+            profile.executions,
+            FormatDuration(profile.total_duration)
+        )
+        .unwrap();
+    }
+    println!(
+        "{event_type} - profile written to {path}\n{table}",
+        event_type = "event".purple(),
+        path = path.display(),
+    );
+}
+
+/// The phase of the build a task function belongs to, guessed from its name.
+/// Tasks resolving the arguments of a function belong to its phase.
+fn build_phase(function: &str) -> &'static str {
+    let function = function
+        .strip_prefix("resolve trait ")
+        .or_else(|| function.strip_prefix("resolve "))
+        .unwrap_or(function)
+        .to_lowercase();
+    let matches = |keywords: &[&str]| keywords.iter().any(|keyword| function.contains(keyword));
+    if matches(&["resolve"]) {
+        "resolve"
+    } else if matches(&["parse", "analyze", "analysis", "references"]) {
+        "analysis"
+    } else if matches(&["code_generation", "codegen", "chunk", "minify"]) {
+        "codegen"
+    } else {
+        "other"
+    }
+}
+
+/// Collects the metrics of a build for [Telemetry]. Modules are counted by
+/// their analysis tasks.
+fn build_metrics(
+    tt: &TurboTasks<MemoryBackend>,
+    duration: Duration,
+    tasks: Option<usize>,
+    features: &[(&'static str, bool)],
+) -> BuildMetrics {
+    let mut stats = Stats::new();
+    let backend = tt.backend();
+    backend.with_all_cached_tasks(|task| {
+        stats.add_id(backend, task);
+    });
+    let mut metrics = BuildMetrics {
+        duration,
+        tasks,
+        features: features.to_vec(),
+        ..Default::default()
+    };
+    for (ty, cache_stats) in stats.cache_stats() {
+        metrics.cache.hits += cache_stats.hits;
+        metrics.cache.misses += cache_stats.misses;
+        if let TaskType::Native(function) = ty {
+            if registry::get_function(function)
+                .name
+                .ends_with("ModuleAssetVc::analyze")
+            {
+                metrics.modules += cache_stats.misses;
+            }
+        }
+    }
+    metrics
+}
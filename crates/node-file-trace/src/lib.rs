@@ -12,7 +12,7 @@ use std::{
     time::{Duration, Instant},
 };
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 #[cfg(feature = "cli")]
 use clap::Parser;
 #[cfg(feature = "node-api")]
@@ -35,7 +35,14 @@ use turbopack::{
     emit, rebase::RebasedAssetVc, resolve_options_context::ResolveOptionsContext,
     transition::TransitionsByNameVc, ModuleAssetContextVc,
 };
-use turbopack_cli_utils::issue::{ConsoleUi, IssueSeverityCliOption, LogOptions};
+use turbopack_cli_utils::{
+    events::{millis, Event},
+    issue::{
+        ConsoleUi, IssueCodeOverrideCliOption, IssuePathOverrideCliOption, IssueSeverityCliOption,
+        LogOptions,
+    },
+    reporter::{terminal_width, OutputFormat},
+};
 use turbopack_core::{
     asset::{Asset, AssetVc, AssetsVc},
     context::AssetContextVc,
@@ -48,6 +55,11 @@ use turbopack_core::{
 
 use crate::nft_json::NftJsonAssetVc;
 
+/// Identifies the format of the persistent cache. A cache written by another
+/// version is discarded, as its tasks might not be compatible.
+#[cfg(feature = "persistent_cache")]
+const CACHE_FINGERPRINT: &str = concat!(env!("CARGO_PKG_NAME"), "@", env!("CARGO_PKG_VERSION"));
+
 #[cfg(feature = "persistent_cache")]
 #[cfg_attr(feature = "cli", derive(clap::Args))]
 #[cfg_attr(
@@ -109,6 +121,27 @@ pub struct CommonArgs {
     /// Filter by issue severity.
     log_level: Option<IssueSeverityCliOption>,
 
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "node-api", serde(default))]
+    /// Fail when an issue with this severity, or a more severe one, occurs.
+    /// Defaults to `fatal`.
+    fail_level: Option<IssueSeverityCliOption>,
+
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "node-api", serde(default))]
+    /// Suppress or change the severity of issues with a code, e. g.
+    /// `--issue-code TP1004=off` or `--issue-code TP2002=error`. Can be
+    /// passed multiple times.
+    issue_code: Vec<IssueCodeOverrideCliOption>,
+
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "node-api", serde(default))]
+    /// Suppress or change the severity of issues in files matching a glob,
+    /// optionally only those with a code, e. g. `--issue-path
+    /// node_modules/**=off` or `--issue-path src/**:TP1004=error`. Can be
+    /// passed multiple times, the first matching one is used.
+    issue_path: Vec<IssuePathOverrideCliOption>,
+
     #[cfg_attr(feature = "cli", clap(long))]
     #[cfg_attr(feature = "node-api", serde(default))]
     /// Show all log messages without limit.
@@ -119,6 +152,18 @@ pub struct CommonArgs {
     /// Expand the log details.
     log_detail: bool,
 
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "node-api", serde(default))]
+    /// Print newline-delimited JSON events instead of messages for humans,
+    /// e. g. for CI scripts. Issues are printed as JSON lines, too.
+    json: bool,
+
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "node-api", serde(default))]
+    /// Write all issues to this file as JSON lines, regardless of the log
+    /// level, e. g. for CI annotations.
+    diagnostics_file: Option<PathBuf>,
+
     /// Whether to skip the glob logic
     /// assume the provided input is not glob even if it contains `*` and `[]`
     #[cfg_attr(feature = "cli", clap(short, long))]
@@ -186,10 +231,12 @@ impl Args {
 
 async fn create_fs(name: &str, context: &str, watch: bool) -> Result<FileSystemVc> {
     let fs = DiskFileSystemVc::new(name.to_string(), context.to_string());
+    let fs_value = fs.await?;
+    // Files might have been changed since the tasks of a restored cache read
+    // them
+    fs_value.invalidate_changed().await?;
     if watch {
-        fs.await?.start_watching()?;
-    } else {
-        fs.await?.invalidate();
+        fs_value.start_watching()?;
     }
     Ok(fs.into())
 }
@@ -341,7 +388,7 @@ pub async fn start(args: Arc<Args>) -> Result<Vec<String>> {
             || {
                 let start = Instant::now();
                 let backend = MemoryBackendWithPersistedGraph::new(
-                    RocksDbPersistedGraph::new(cache).unwrap(),
+                    RocksDbPersistedGraph::new_with_fingerprint(cache, CACHE_FINGERPRINT).unwrap(),
                 );
                 let tt = TurboTasks::new(backend);
                 let elapsed = start.elapsed();
@@ -411,6 +458,11 @@ async fn run<B: Backend + 'static, F: Future<Output = ()>>(
         show_all,
         log_detail,
         log_level,
+        fail_level,
+        ref issue_code,
+        ref issue_path,
+        json,
+        ref diagnostics_file,
         ..
     } = args.common();
 
@@ -421,16 +473,33 @@ async fn run<B: Backend + 'static, F: Future<Output = ()>>(
                 println!("{}", e);
             }
             let (elapsed, count) = tt.get_or_wait_update_info(Duration::from_millis(100)).await;
-            println!(
-                "done in {} ({} task execution, {} tasks)",
-                FormatDuration(start.elapsed()),
-                FormatDuration(elapsed),
-                count
-            );
+            if json {
+                Event::Compiled {
+                    duration_ms: millis(start.elapsed()),
+                    task_execution_ms: Some(millis(elapsed)),
+                    tasks: Some(count),
+                }
+                .print();
+            } else {
+                println!(
+                    "done in {} ({} task execution, {} tasks)",
+                    FormatDuration(start.elapsed()),
+                    FormatDuration(elapsed),
+                    count
+                );
+            }
 
             loop {
                 let (elapsed, count) = tt.get_or_wait_update_info(Duration::from_millis(100)).await;
-                println!("updated {} tasks in {}", count, FormatDuration(elapsed));
+                if json {
+                    Event::Updated {
+                        duration_ms: millis(elapsed),
+                        tasks: count,
+                    }
+                    .print();
+                } else {
+                    println!("updated {} tasks in {}", count, FormatDuration(elapsed));
+                }
             }
         } else {
             let result = tt.wait_task_completion(root_task, true).await;
@@ -438,18 +507,36 @@ async fn run<B: Backend + 'static, F: Future<Output = ()>>(
             let (elapsed, count) = tt.get_or_wait_update_info(Duration::from_millis(100)).await;
             final_finish(tt, root_task, dur).await;
             let dur2 = start.elapsed();
-            println!(
-                "done in {} ({} compilation, {} task execution, {} tasks)",
-                FormatDuration(dur2),
-                FormatDuration(dur),
-                FormatDuration(elapsed),
-                count
-            );
+            if json {
+                Event::Compiled {
+                    duration_ms: millis(dur2),
+                    task_execution_ms: Some(millis(elapsed)),
+                    tasks: Some(count),
+                }
+                .print();
+            } else {
+                println!(
+                    "done in {} ({} compilation, {} task execution, {} tasks)",
+                    FormatDuration(dur2),
+                    FormatDuration(dur),
+                    FormatDuration(elapsed),
+                    count
+                );
+            }
             result
         }
     };
-    let has_return_value =
-        matches!(&*args, Args::Annotate { .. }) || matches!(&*args, Args::Print { .. });
+    let has_return_value = matches!(&*args, Args::Annotate { .. } | Args::Print { .. })
+        || json && matches!(&*args, Args::Build { .. });
+    let output_dir = match &*args {
+        Args::Build {
+            output_directory, ..
+        } => Some(process_context(
+            &current_dir().unwrap(),
+            Some(output_directory),
+        )?),
+        _ => None,
+    };
     let (sender, mut receiver) = channel(1);
     let dir = current_dir().unwrap();
     let tt = create_tt();
@@ -458,6 +545,23 @@ async fn run<B: Backend + 'static, F: Future<Output = ()>>(
         show_all,
         log_detail,
         log_level: log_level.map_or_else(|| IssueSeverity::Error, |l| l.0),
+        fail_level: fail_level.map_or_else(|| IssueSeverity::Fatal, |l| l.0),
+        code_overrides: issue_code
+            .iter()
+            .map(|option| (option.code.clone(), option.code_override))
+            .collect(),
+        path_overrides: issue_path
+            .iter()
+            .map(|option| option.path_override.clone())
+            .collect(),
+        format: if json {
+            OutputFormat::JsonLines
+        } else {
+            OutputFormat::from_env()
+        },
+        terminal_width: terminal_width(),
+        messages: Default::default(),
+        diagnostics_file: diagnostics_file.clone(),
     }));
     let task = tt.spawn_root_task(move || {
         let dir = dir.clone();
@@ -468,9 +572,12 @@ async fn run<B: Backend + 'static, F: Future<Output = ()>>(
             let output = main_operation(TransientValue::new(dir.clone()), args.clone().into());
 
             let console_ui = (*console_ui).clone().cell();
-            console_ui
+            let state = console_ui
                 .group_and_display_issues(TransientValue::new(output.into()))
                 .await?;
+            if state.has_fatal {
+                bail!("Issue(s) at or above the fail level occurred");
+            }
 
             if has_return_value {
                 let output_read_ref = output.await?;
@@ -487,7 +594,36 @@ async fn run<B: Backend + 'static, F: Future<Output = ()>>(
     } else {
         Vec::new()
     };
-    Ok(output)
+    match output_dir {
+        Some(output_dir) if json => {
+            print_emitted_assets(Path::new(&output_dir), &output)?;
+            Ok(Vec::new())
+        }
+        _ => Ok(output),
+    }
+}
+
+/// Prints an `assetEmitted` event for each of the `paths` written to
+/// `output_dir`, followed by their `stats`. Paths of assets without content
+/// are not written and skipped.
+fn print_emitted_assets(output_dir: &Path, paths: &[String]) -> Result<()> {
+    let mut assets = 0;
+    let mut total_size = 0;
+    for path in paths {
+        let size = match fs::metadata(output_dir.join(path)) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => continue,
+        };
+        assets += 1;
+        total_size += size;
+        Event::AssetEmitted {
+            path: path.clone(),
+            size,
+        }
+        .print();
+    }
+    Event::Stats { assets, total_size }.print();
+    Ok(())
 }
 
 #[turbo_tasks::function]
@@ -525,7 +661,7 @@ async fn main_operation(
                 }
             }
 
-            return Ok(StringsVc::cell(result.into_iter().collect::<Vec<_>>()));
+            Ok(StringsVc::cell(result.into_iter().collect::<Vec<_>>()))
         }
         Args::Annotate { common: _ } => {
             let input = process_input(&dir, &context, input).unwrap();
@@ -540,7 +676,7 @@ async fn main_operation(
                 output_nft_assets.push(path);
                 emit(nft_asset.into())
             }
-            return Ok(StringsVc::cell(output_nft_assets));
+            Ok(StringsVc::cell(output_nft_assets))
         }
         Args::Build {
             ref output_directory,
@@ -552,17 +688,21 @@ async fn main_operation(
             let out_fs = create_fs("output directory", &output, watch).await?;
             let input_dir = fs.root();
             let output_dir = out_fs.root();
+            let mut emitted = BTreeSet::new();
             for module in input_to_modules(fs, input, process_cwd, exact)
                 .await?
                 .iter()
             {
                 let rebased = RebasedAssetVc::new(*module, input_dir, output_dir).into();
                 emit(rebased);
+                for asset in all_assets(rebased).await?.iter() {
+                    emitted.insert(asset.path().await?.path.clone());
+                }
             }
+            Ok(StringsVc::cell(emitted.into_iter().collect()))
         }
         Args::Size { common: _ } => todo!(),
     }
-    Ok(StringsVc::cell(Vec::new()))
 }
 
 fn register() {
@@ -0,0 +1,45 @@
+use anyhow::Result;
+use turbo_tasks::primitives::OptionStringVc;
+
+use crate::{EnvMapVc, ProcessEnv, ProcessEnvVc};
+
+/// Extends a prior env with custom variables, which take precedence over the
+/// variables of the prior env.
+#[turbo_tasks::value]
+pub struct CustomProcessEnv {
+    prior: ProcessEnvVc,
+    custom: EnvMapVc,
+}
+
+#[turbo_tasks::value_impl]
+impl CustomProcessEnvVc {
+    #[turbo_tasks::function]
+    pub fn new(prior: ProcessEnvVc, custom: EnvMapVc) -> Self {
+        CustomProcessEnv { prior, custom }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ProcessEnv for CustomProcessEnv {
+    #[turbo_tasks::function]
+    async fn read_all(&self) -> Result<EnvMapVc> {
+        let prior = self.prior.read_all().await?;
+        let custom = self.custom.await?;
+
+        let mut extended = prior.clone_value();
+        extended.extend(custom.clone_value());
+        Ok(EnvMapVc::cell(extended))
+    }
+
+    #[turbo_tasks::function]
+    async fn read(&self, name: &str) -> Result<OptionStringVc> {
+        let custom = self.custom.await?;
+        match custom
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        {
+            Some((_, value)) => Ok(OptionStringVc::cell(Some(value.clone()))),
+            None => Ok(self.prior.read(name)),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 #![feature(min_specialization)]
 
 mod command_line;
+mod custom;
 mod dotenv;
 mod filter;
 
@@ -11,7 +12,8 @@ use indexmap::IndexMap;
 use turbo_tasks::primitives::OptionStringVc;
 
 pub use self::{
-    command_line::CommandLineProcessEnvVc, dotenv::DotenvProcessEnvVc, filter::FilterProcessEnvVc,
+    command_line::CommandLineProcessEnvVc, custom::CustomProcessEnvVc, dotenv::DotenvProcessEnvVc,
+    filter::FilterProcessEnvVc,
 };
 
 #[turbo_tasks::value(transparent)]
@@ -7,6 +7,8 @@ use crate::{embed::EmbeddedFileSystemVc, DiskFileSystemVc, FileSystemVc};
 #[turbo_tasks::function]
 pub async fn directory_from_relative_path(name: &str, path: String) -> Result<FileSystemVc> {
     let disk_fs = DiskFileSystemVc::new(name.to_string(), path);
+    // Without the `watch` feature, changes are not picked up until restarting
+    #[cfg(feature = "watch")]
     disk_fs.await?.start_watching()?;
 
     Ok(disk_fs.into())
@@ -17,6 +17,8 @@ pub async fn content_from_relative_path(package_path: &str, path: &str) -> Resul
         root_path.to_string_lossy().to_string(),
         root_path.to_string_lossy().to_string(),
     );
+    // Without the `watch` feature, changes are not picked up until restarting
+    #[cfg(feature = "watch")]
     disk_fs.await?.start_watching()?;
 
     let fs_path = disk_fs.root().join(path);
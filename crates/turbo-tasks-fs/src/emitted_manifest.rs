@@ -0,0 +1,30 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+
+/// The content hashes of the files a [DiskFileSystem](crate::DiskFileSystem)
+/// has written, keyed like the invalidator map. It's persisted together with
+/// the file system, so it describes what the cached tasks believe is on disk.
+#[derive(Default, Serialize, Deserialize)]
+pub struct EmittedManifest {
+    files: Mutex<HashMap<String, u64>>,
+}
+
+impl EmittedManifest {
+    pub fn record(&self, key: String, hash: u64) {
+        self.files.lock().unwrap().insert(key, hash);
+    }
+
+    pub fn remove(&self, key: &str) {
+        self.files.lock().unwrap().remove(key);
+    }
+
+    pub fn entries(&self) -> Vec<(String, u64)> {
+        self.files
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, hash)| (key.clone(), *hash))
+            .collect()
+    }
+}
@@ -4,38 +4,45 @@
 #![feature(iter_advance_by)]
 #![feature(io_error_more)]
 #![feature(main_separator_str)]
+#![feature(error_generic_member_access)]
+#![feature(provide_any)]
 
 pub mod attach;
 pub mod embed;
+mod emitted_manifest;
 pub mod glob;
 mod invalidator_map;
 mod read_glob;
+mod read_manifest;
 mod retry;
 pub mod util;
+pub mod virtual_fs;
+mod watcher;
+#[cfg(feature = "watch")]
+mod watchman;
 
 use std::{
-    collections::{HashMap, HashSet},
+    any::Demand,
+    collections::HashMap,
+    error::Error,
     fmt::{self, Display},
     fs::FileType,
     io::{self, ErrorKind},
     mem::take,
     path::{Path, PathBuf, MAIN_SEPARATOR},
-    sync::{
-        mpsc::{channel, RecvError, TryRecvError},
-        Arc, Mutex,
-    },
-    time::Duration,
+    sync::Arc,
 };
 
 use anyhow::{anyhow, bail, Context, Result};
 use bitflags::bitflags;
+use emitted_manifest::EmittedManifest;
 use glob::GlobVc;
 use invalidator_map::InvalidatorMap;
 use jsonc_parser::{parse_to_serde_value, ParseOptions};
 use mime::Mime;
-use notify::{watcher, DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
 use read_glob::read_glob;
 pub use read_glob::{ReadGlobResult, ReadGlobResultVc};
+use read_manifest::{hash_dir_entries, EntryKind, ReadManifest};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tokio::{
@@ -44,11 +51,13 @@ use tokio::{
 };
 use turbo_tasks::{
     primitives::{BoolVc, StringReadRef, StringVc},
-    spawn_thread,
     trace::TraceRawVcs,
-    CompletionVc, Invalidator, ValueToString, ValueToStringVc,
+    CompletionVc, HeapSize, ValueToString, ValueToStringVc,
 };
+use turbo_tasks_hash::hash_xxh3_hash64;
 use util::{join_path, normalize_path, sys_to_unix, unix_to_sys};
+use watcher::DiskWatcher;
+pub use watcher::{WatchOptions, WatchOptionsVc};
 
 use crate::retry::{retry_blocking, retry_future};
 #[cfg(target_family = "windows")]
@@ -72,13 +81,31 @@ pub trait FileSystem: ValueToString {
 pub struct DiskFileSystem {
     pub name: String,
     pub root: String,
+    /// Whether reading files outside of [root](Self::root) through symlinks
+    /// is forbidden.
+    pub hermetic: bool,
+    /// The canonicalized [root](Self::root), which reads are checked against
+    /// in hermetic mode.
+    canonical_root: PathBuf,
     #[turbo_tasks(debug_ignore, trace_ignore)]
     invalidator_map: Arc<InvalidatorMap>,
     #[turbo_tasks(debug_ignore, trace_ignore)]
     dir_invalidator_map: Arc<InvalidatorMap>,
+    /// Invalidators of reads of metadata, which are also invalidated by
+    /// changes of the permissions or timestamps, unlike reads of the content.
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    metadata_invalidator_map: Arc<InvalidatorMap>,
+    /// The files written by this file system, to detect when the disk
+    /// diverges from them while turbopack isn't running.
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    emitted: Arc<EmittedManifest>,
+    /// The files and directories read by this file system, to detect when
+    /// they are changed while turbopack isn't running.
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    read: Arc<ReadManifest>,
     #[turbo_tasks(debug_ignore, trace_ignore)]
     #[serde(skip)]
-    watcher: Mutex<Option<RecommendedWatcher>>,
+    watcher: DiskWatcher,
 }
 
 impl DiskFileSystem {
@@ -94,6 +121,26 @@ impl DiskFileSystem {
         }
     }
 
+    /// registers the real path of a path which is read through symlinks as
+    /// an invalidator for the current task, as the watcher reports changes of
+    /// the file at its real path, e. g. for packages linked from a pnpm
+    /// store. Only real paths inside of the root are watched.
+    async fn register_real_path_invalidator(&self, full_path: &Path) {
+        if let Ok(real_path) = fs::canonicalize(full_path).await {
+            if real_path != full_path && real_path.starts_with(&self.root) {
+                self.register_invalidator(real_path, true);
+            }
+        }
+    }
+
+    /// registers the path as an invalidator for reading the metadata in the
+    /// current task, has to be called within a turbo-tasks function
+    fn register_metadata_invalidator(&self, path: impl AsRef<Path>) {
+        let invalidator = turbo_tasks::get_invalidator();
+        self.metadata_invalidator_map
+            .insert(path_to_key(path), invalidator);
+    }
+
     pub fn invalidate(&self) {
         for (_, invalidators) in take(&mut *self.invalidator_map.lock().unwrap()).into_iter() {
             invalidators.into_iter().for_each(|i| i.invalidate());
@@ -101,157 +148,118 @@ impl DiskFileSystem {
         for (_, invalidators) in take(&mut *self.dir_invalidator_map.lock().unwrap()).into_iter() {
             invalidators.into_iter().for_each(|i| i.invalidate());
         }
+        for (_, invalidators) in
+            take(&mut *self.metadata_invalidator_map.lock().unwrap()).into_iter()
+        {
+            invalidators.into_iter().for_each(|i| i.invalidate());
+        }
     }
 
     pub fn start_watching(&self) -> Result<()> {
-        let mut watcher_guard = self.watcher.lock().unwrap();
-        if watcher_guard.is_some() {
-            return Ok(());
-        }
-        let invalidator_map = self.invalidator_map.clone();
-        let dir_invalidator_map = self.dir_invalidator_map.clone();
-        let root = self.root.clone();
-        // Create a channel to receive the events.
-        let (tx, rx) = channel();
-        // Create a watcher object, delivering debounced events.
-        // The notification back-end is selected based on the platform.
-        let mut watcher = watcher(tx, Duration::from_millis(1))?;
-        // Add a path to be watched. All files and directories at that path and
-        // below will be monitored for changes.
-        watcher.watch(&root, RecursiveMode::Recursive)?;
-
-        // We need to invalidate all reads that happened before watching
-        // Best is to start_watching before starting to read
-        for (_, invalidators) in take(&mut *invalidator_map.lock().unwrap()).into_iter() {
-            invalidators.into_iter().for_each(|i| i.invalidate());
-        }
-        for (_, invalidators) in take(&mut *dir_invalidator_map.lock().unwrap()).into_iter() {
-            invalidators.into_iter().for_each(|i| i.invalidate());
-        }
+        self.start_watching_with_options(&WatchOptions::default())
+    }
 
-        watcher_guard.replace(watcher);
-
-        spawn_thread(move || {
-            let mut batched_invalidate_path = HashSet::new();
-            let mut batched_invalidate_path_dir = HashSet::new();
-            let mut batched_invalidate_path_and_children = HashSet::new();
-            let mut batched_invalidate_path_and_children_dir = HashSet::new();
-
-            'outer: loop {
-                let mut event = rx.recv().map_err(|e| match e {
-                    RecvError => TryRecvError::Disconnected,
-                });
-                loop {
-                    match event {
-                        Ok(DebouncedEvent::Write(path)) => {
-                            batched_invalidate_path.insert(path);
-                        }
-                        Ok(DebouncedEvent::Create(path)) | Ok(DebouncedEvent::Remove(path)) => {
-                            batched_invalidate_path_and_children.insert(path.clone());
-                            batched_invalidate_path_and_children_dir.insert(path.clone());
-                            if let Some(parent) = path.parent() {
-                                batched_invalidate_path_dir.insert(PathBuf::from(parent));
-                            }
-                        }
-                        Ok(DebouncedEvent::Rename(source, destination)) => {
-                            batched_invalidate_path_and_children.insert(source.clone());
-                            if let Some(parent) = source.parent() {
-                                batched_invalidate_path_dir.insert(PathBuf::from(parent));
-                            }
-                            batched_invalidate_path_and_children.insert(destination.clone());
-                            if let Some(parent) = destination.parent() {
-                                batched_invalidate_path_dir.insert(PathBuf::from(parent));
-                            }
-                        }
-                        Ok(DebouncedEvent::Rescan) => {
-                            batched_invalidate_path_and_children.insert(PathBuf::from(&root));
-                            batched_invalidate_path_and_children_dir.insert(PathBuf::from(&root));
-                        }
-                        Ok(DebouncedEvent::Error(err, path)) => {
-                            println!("watch error ({:?}): {:?} ", path, err);
-                            match path {
-                                Some(path) => {
-                                    batched_invalidate_path_and_children.insert(path.clone());
-                                    batched_invalidate_path_and_children_dir.insert(path);
-                                }
-                                None => {
-                                    batched_invalidate_path_and_children
-                                        .insert(PathBuf::from(&root));
-                                    batched_invalidate_path_and_children_dir
-                                        .insert(PathBuf::from(&root));
-                                }
-                            }
-                        }
-                        Ok(DebouncedEvent::Chmod(_))
-                        | Ok(DebouncedEvent::NoticeRemove(_))
-                        | Ok(DebouncedEvent::NoticeWrite(_)) => {
-                            // ignored
-                        }
-                        Err(TryRecvError::Disconnected) => {
-                            // Sender has been disconnected
-                            // which means DiskFileSystem has been dropped
-                            // exit thread
-                            break 'outer;
-                        }
-                        Err(TryRecvError::Empty) => {
-                            break;
-                        }
-                    }
-                    event = rx.try_recv();
-                }
-                fn invalidate_path(
-                    invalidator_map: &mut HashMap<String, HashSet<Invalidator>>,
-                    paths: impl Iterator<Item = PathBuf>,
-                ) {
-                    for path in paths {
-                        let key = path_to_key(path);
-                        if let Some(invalidators) = invalidator_map.remove(&key) {
-                            invalidators.into_iter().for_each(|i| i.invalidate());
-                        }
-                    }
+    pub fn start_watching_with_options(&self, options: &WatchOptions) -> Result<()> {
+        self.watcher.start(
+            &self.root,
+            self.invalidator_map.clone(),
+            self.dir_invalidator_map.clone(),
+            self.metadata_invalidator_map.clone(),
+            options,
+        )
+    }
+
+    pub fn stop_watching(&self) {
+        self.watcher.stop();
+    }
+
+    /// Compares the files on disk with the files this file system wrote
+    /// before, e. g. in a previous session restored from a persistent cache.
+    /// Files which were changed or deleted by someone else invalidate the
+    /// tasks which read them, so they are written again when they are still
+    /// emitted. Returns the number of diverged files.
+    pub async fn verify_emitted(&self) -> Result<usize> {
+        let mut diverged = 0;
+        for (key, hash) in self.emitted.entries() {
+            let matches = match fs::read(&key).await {
+                Ok(content) => hash_xxh3_hash64(&content) == hash,
+                Err(e) if e.kind() == ErrorKind::NotFound => false,
+                Err(e) => return Err(anyhow!(e).context(format!("reading emitted file {key}"))),
+            };
+            if !matches {
+                diverged += 1;
+                self.emitted.remove(&key);
+                let invalidators = self.invalidator_map.lock().unwrap().remove(&key);
+                if let Some(invalidators) = invalidators {
+                    invalidators.into_iter().for_each(|i| i.invalidate());
                 }
-                fn invalidate_path_and_children_execute(
-                    invalidator_map: &mut HashMap<String, HashSet<Invalidator>>,
-                    paths: &mut HashSet<PathBuf>,
-                ) {
-                    for (_, invalidators) in invalidator_map.drain_filter(|key, _| {
-                        paths
-                            .iter()
-                            .any(|path_key| key.starts_with(&path_to_key(path_key)))
-                    }) {
+            }
+        }
+        Ok(diverged)
+    }
+
+    /// Compares the files and directories on disk with the ones this file
+    /// system read before, e. g. in a previous session restored from a
+    /// persistent cache. Only the tasks which read changed files or
+    /// directories are invalidated, the results of all other tasks are
+    /// reused. Returns the number of changed files and directories.
+    pub async fn invalidate_changed(&self) -> Result<usize> {
+        let mut changed = 0;
+        for (key, hash) in self.read.files() {
+            let unchanged = match fs::read(&key).await {
+                Ok(content) => hash == Some(hash_xxh3_hash64(&content)),
+                Err(e) if e.kind() == ErrorKind::NotFound => hash.is_none(),
+                Err(_) => false,
+            };
+            if !unchanged {
+                changed += 1;
+                self.read.remove_file(&key);
+                for map in [&self.invalidator_map, &self.metadata_invalidator_map] {
+                    let invalidators = map.lock().unwrap().remove(&key);
+                    if let Some(invalidators) = invalidators {
                         invalidators.into_iter().for_each(|i| i.invalidate());
                     }
-                    paths.clear()
                 }
-                {
-                    let mut invalidator_map = invalidator_map.lock().unwrap();
-                    invalidate_path(&mut invalidator_map, batched_invalidate_path.drain());
-                    invalidate_path_and_children_execute(
-                        &mut invalidator_map,
-                        &mut batched_invalidate_path_and_children,
-                    );
-                }
-                {
-                    let mut dir_invalidator_map = dir_invalidator_map.lock().unwrap();
-                    invalidate_path(
-                        &mut dir_invalidator_map,
-                        batched_invalidate_path_dir.drain(),
-                    );
-                    invalidate_path_and_children_execute(
-                        &mut dir_invalidator_map,
-                        &mut batched_invalidate_path_and_children_dir,
-                    );
+            }
+        }
+        for (key, hash) in self.read.dirs() {
+            let unchanged = match hash_dir(&key).await {
+                Ok(current) => hash == current,
+                Err(_) => false,
+            };
+            if !unchanged {
+                changed += 1;
+                self.read.remove_dir(&key);
+                let invalidators = self.dir_invalidator_map.lock().unwrap().remove(&key);
+                if let Some(invalidators) = invalidators {
+                    invalidators.into_iter().for_each(|i| i.invalidate());
                 }
             }
-        });
-        Ok(())
+        }
+        Ok(changed)
     }
 
-    pub fn stop_watching(&self) {
-        if let Some(watcher) = self.watcher.lock().unwrap().take() {
-            drop(watcher);
-            // thread will detect the stop because the channel is disconnected
+    /// Fails with a [HermeticViolation] when `full_path` resolves to a path
+    /// outside of the root in hermetic mode, e. g. because of a symlink.
+    async fn check_hermetic(&self, full_path: &Path) -> Result<()> {
+        if !self.hermetic {
+            return Ok(());
         }
+        let real_path = match fs::canonicalize(full_path).await {
+            Ok(real_path) => real_path,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(anyhow!(e).context(format!("resolving {}", full_path.display()))),
+        };
+        if !real_path.starts_with(&self.canonical_root) {
+            return Err(HermeticViolation {
+                fs_name: self.name.clone(),
+                path: full_path.to_path_buf(),
+                real_path,
+                root: self.canonical_root.clone(),
+            }
+            .into());
+        }
+        Ok(())
     }
 
     pub async fn to_sys_path(&self, fs_path: FileSystemPathVc) -> Result<PathBuf> {
@@ -264,25 +272,115 @@ pub fn path_to_key(path: impl AsRef<Path>) -> String {
     path.as_ref().to_string_lossy().to_string()
 }
 
+/// Hashes the entries of the directory at `path` like they are recorded by
+/// [FileSystem::read_dir] of a [DiskFileSystem], or `None` when it doesn't
+/// exist.
+async fn hash_dir(path: &str) -> io::Result<Option<u64>> {
+    let mut read_dir = match fs::read_dir(path).await {
+        Ok(dir) => dir,
+        Err(e)
+            if e.kind() == ErrorKind::NotFound
+                || e.kind() == ErrorKind::NotADirectory
+                || e.kind() == ErrorKind::InvalidFilename =>
+        {
+            return Ok(None)
+        }
+        Err(e) => return Err(e),
+    };
+    let mut entries = Vec::new();
+    while let Some(entry) = read_dir.next_entry().await? {
+        if let Some(file_name) = entry.file_name().to_str() {
+            entries.push((file_name.to_string(), entry_kind(entry.file_type().await?)));
+        }
+    }
+    Ok(Some(hash_dir_entries(
+        entries.iter().map(|(name, kind)| (name.as_str(), *kind)),
+    )))
+}
+
+fn entry_kind(file_type: FileType) -> EntryKind {
+    if file_type.is_file() {
+        EntryKind::File
+    } else if file_type.is_dir() {
+        EntryKind::Directory
+    } else if file_type.is_symlink() {
+        EntryKind::Symlink
+    } else {
+        EntryKind::Other
+    }
+}
+
 #[turbo_tasks::value_impl]
 impl DiskFileSystemVc {
     #[turbo_tasks::function]
     pub async fn new(name: String, root: String) -> Result<Self> {
+        Self::create(name, root, false).await
+    }
+
+    /// Creates a file system which fails to read files and directories
+    /// outside of `root`, which is otherwise possible through symlinks.
+    #[turbo_tasks::function]
+    pub async fn new_hermetic(name: String, root: String) -> Result<Self> {
+        Self::create(name, root, true).await
+    }
+}
+
+impl DiskFileSystemVc {
+    async fn create(name: String, root: String, hermetic: bool) -> Result<Self> {
         // create the directory for the filesystem on disk, if it doesn't exist
         fs::create_dir_all(&root).await?;
+        let canonical_root = fs::canonicalize(&root)
+            .await
+            .with_context(|| format!("resolving root {}", root))?;
 
         let instance = DiskFileSystem {
             name,
             root,
+            hermetic,
+            canonical_root,
             invalidator_map: Arc::new(InvalidatorMap::new()),
             dir_invalidator_map: Arc::new(InvalidatorMap::new()),
-            watcher: Mutex::new(None),
+            metadata_invalidator_map: Arc::new(InvalidatorMap::new()),
+            emitted: Arc::new(EmittedManifest::default()),
+            read: Arc::new(ReadManifest::default()),
+            watcher: DiskWatcher::default(),
         };
 
         Ok(Self::cell(instance))
     }
 }
 
+/// A read of a hermetic [DiskFileSystem] which resolves to a path outside of
+/// its root. It can be requested from the error of the read with
+/// `request_ref`, even after the error was passed between tasks.
+#[derive(Debug)]
+pub struct HermeticViolation {
+    pub fs_name: String,
+    pub path: PathBuf,
+    pub real_path: PathBuf,
+    pub root: PathBuf,
+}
+
+impl Display for HermeticViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "file system \"{}\" attempted to read {}, which resolves to {} outside of its root \
+             {}, in hermetic mode",
+            self.fs_name,
+            self.path.display(),
+            self.real_path.display(),
+            self.root.display()
+        )
+    }
+}
+
+impl Error for HermeticViolation {
+    fn provide<'a>(&'a self, demand: &mut Demand<'a>) {
+        demand.provide_ref(self);
+    }
+}
+
 impl fmt::Debug for DiskFileSystem {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "name: {}, root: {}", self.name, self.root)
@@ -295,6 +393,8 @@ impl FileSystem for DiskFileSystem {
     async fn read(&self, fs_path: FileSystemPathVc) -> Result<FileContentVc> {
         let full_path = self.to_sys_path(fs_path).await?;
         self.register_invalidator(&full_path, true);
+        self.register_real_path_invalidator(&full_path).await;
+        self.check_hermetic(&full_path).await?;
 
         let content = match retry_future(|| File::from_path(full_path.clone())).await {
             Ok(file) => FileContent::new(file),
@@ -303,6 +403,13 @@ impl FileSystem for DiskFileSystem {
                 bail!(anyhow!(e).context(format!("reading file {}", full_path.display())))
             }
         };
+        self.read.record_file(
+            path_to_key(&full_path),
+            match &content {
+                FileContent::Content(file) => Some(hash_xxh3_hash64(&file.content)),
+                FileContent::NotFound => None,
+            },
+        );
 
         Ok(content.cell())
     }
@@ -311,6 +418,7 @@ impl FileSystem for DiskFileSystem {
     async fn read_dir(&self, fs_path: FileSystemPathVc) -> Result<DirectoryContentVc> {
         let full_path = self.to_sys_path(fs_path).await?;
         self.register_invalidator(&full_path, false);
+        self.check_hermetic(&full_path).await?;
         let fs_path = fs_path.await?;
 
         // we use the sync std function here as it's a lot faster (600%) in
@@ -322,14 +430,15 @@ impl FileSystem for DiskFileSystem {
                     || e.kind() == ErrorKind::NotADirectory
                     || e.kind() == ErrorKind::InvalidFilename =>
             {
-                return Ok(DirectoryContentVc::not_found())
+                self.read.record_dir(path_to_key(&full_path), None);
+                return Ok(DirectoryContentVc::not_found());
             }
             Err(e) => {
                 bail!(anyhow!(e).context(format!("reading dir {}", full_path.display())))
             }
         };
 
-        let entries = read_dir
+        let entries: HashMap<String, DirectoryEntry> = read_dir
             .filter_map(|r| {
                 let e = match r {
                     Ok(e) => e,
@@ -357,6 +466,16 @@ impl FileSystem for DiskFileSystem {
             })
             .collect::<Result<_>>()
             .with_context(|| format!("reading directory item in {}", full_path.display()))?;
+        let hash = hash_dir_entries(entries.iter().map(|(name, entry)| {
+            let kind = match entry {
+                DirectoryEntry::File(_) => EntryKind::File,
+                DirectoryEntry::Directory(_) => EntryKind::Directory,
+                DirectoryEntry::Symlink(_) => EntryKind::Symlink,
+                DirectoryEntry::Other(_) | DirectoryEntry::Error => EntryKind::Other,
+            };
+            (name.as_str(), kind)
+        }));
+        self.read.record_dir(path_to_key(&full_path), Some(hash));
 
         Ok(DirectoryContentVc::new(entries))
     }
@@ -496,8 +615,11 @@ impl FileSystem for DiskFileSystem {
                 })
                 .await
                 .with_context(|| format!("failed to write to {}", full_path.display()))?;
+                self.emitted
+                    .record(path_to_key(&full_path), hash_xxh3_hash64(&file.content));
             }
             FileContent::NotFound => {
+                self.emitted.remove(&path_to_key(&full_path));
                 // println!("remove {}", full_path.display());
                 retry_future(|| fs::remove_file(full_path.clone()))
                     .await
@@ -594,7 +716,7 @@ impl FileSystem for DiskFileSystem {
     #[turbo_tasks::function]
     async fn metadata(&self, fs_path: FileSystemPathVc) -> Result<FileMetaVc> {
         let full_path = self.to_sys_path(fs_path).await?;
-        self.register_invalidator(&full_path, true);
+        self.register_metadata_invalidator(&full_path);
 
         let meta = retry_future(|| fs::metadata(full_path.clone()))
             .await
@@ -1129,13 +1251,22 @@ impl From<std::fs::Permissions> for Permissions {
     }
 }
 
-#[turbo_tasks::value(shared)]
+#[turbo_tasks::value(shared, heap_size)]
 #[derive(Clone)]
 pub enum FileContent {
     Content(File),
     NotFound,
 }
 
+impl HeapSize for FileContent {
+    fn heap_size(&self) -> usize {
+        match self {
+            FileContent::Content(file) => file.content.capacity(),
+            FileContent::NotFound => 0,
+        }
+    }
+}
+
 impl From<File> for FileContent {
     fn from(file: File) -> Self {
         FileContent::Content(file)
@@ -0,0 +1,72 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+use turbo_tasks_hash::hash_xxh3_hash64;
+
+/// The content hashes of the files and directories a
+/// [DiskFileSystem](crate::DiskFileSystem) has read, keyed like the
+/// invalidator maps. `None` is recorded for paths which were not found. It's
+/// persisted together with the file system, so changes made while turbopack
+/// isn't running can be detected when restoring a persistent cache.
+#[derive(Default, Serialize, Deserialize)]
+pub struct ReadManifest {
+    files: Mutex<HashMap<String, Option<u64>>>,
+    dirs: Mutex<HashMap<String, Option<u64>>>,
+}
+
+impl ReadManifest {
+    pub fn record_file(&self, key: String, hash: Option<u64>) {
+        self.files.lock().unwrap().insert(key, hash);
+    }
+
+    pub fn record_dir(&self, key: String, hash: Option<u64>) {
+        self.dirs.lock().unwrap().insert(key, hash);
+    }
+
+    pub fn remove_file(&self, key: &str) {
+        self.files.lock().unwrap().remove(key);
+    }
+
+    pub fn remove_dir(&self, key: &str) {
+        self.dirs.lock().unwrap().remove(key);
+    }
+
+    pub fn files(&self) -> Vec<(String, Option<u64>)> {
+        entries(&self.files)
+    }
+
+    pub fn dirs(&self) -> Vec<(String, Option<u64>)> {
+        entries(&self.dirs)
+    }
+}
+
+fn entries(map: &Mutex<HashMap<String, Option<u64>>>) -> Vec<(String, Option<u64>)> {
+    map.lock()
+        .unwrap()
+        .iter()
+        .map(|(key, hash)| (key.clone(), *hash))
+        .collect()
+}
+
+/// The kind of a directory entry, as part of the hash of a directory.
+#[derive(Clone, Copy)]
+pub enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+    Other,
+}
+
+/// Hashes the names and kinds of the entries of a directory, independent of
+/// their order.
+pub fn hash_dir_entries<'a>(entries: impl Iterator<Item = (&'a str, EntryKind)>) -> u64 {
+    let mut entries = entries.collect::<Vec<_>>();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut buffer = Vec::new();
+    for (name, kind) in entries {
+        buffer.extend_from_slice(name.as_bytes());
+        buffer.push(0);
+        buffer.push(kind as u8);
+    }
+    hash_xxh3_hash64(&buffer)
+}
@@ -0,0 +1,165 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    mem::take,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{bail, Result};
+use turbo_tasks::{primitives::StringVc, CompletionVc, ValueToString, ValueToStringVc};
+
+use crate::{
+    invalidator_map::InvalidatorMap, DirectoryContent, DirectoryContentVc, DirectoryEntry, File,
+    FileContent, FileContentVc, FileMetaVc, FileSystem, FileSystemPathVc, FileSystemVc,
+    LinkContent, LinkContentVc,
+};
+
+/// A file system which keeps its files in memory, e. g. for environments
+/// without a disk like wasm32 in the browser.
+///
+/// Files can be changed from outside of turbo-tasks functions with
+/// [VirtualFileSystem::set_file] and [VirtualFileSystem::remove_file], which
+/// invalidates all reads of them.
+#[turbo_tasks::value(cell = "new", eq = "manual", serialization = "none")]
+pub struct VirtualFileSystem {
+    name: String,
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    files: Mutex<BTreeMap<String, File>>,
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    invalidator_map: Arc<InvalidatorMap>,
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    dir_invalidator_map: Arc<InvalidatorMap>,
+}
+
+impl VirtualFileSystem {
+    /// Creates or replaces the file at `path`, relative to the root of the
+    /// file system.
+    pub fn set_file(&self, path: &str, file: File) {
+        let old = self
+            .files
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), file.clone());
+        if old.as_ref() != Some(&file) {
+            self.invalidate(path);
+        }
+    }
+
+    /// Removes the file at `path`, relative to the root of the file system.
+    pub fn remove_file(&self, path: &str) {
+        if self.files.lock().unwrap().remove(path).is_some() {
+            self.invalidate(path);
+        }
+    }
+
+    /// Invalidates reads of the file at `path`. Directories are invalidated
+    /// as a whole, as a file might add or remove any of its parents.
+    fn invalidate(&self, path: &str) {
+        if let Some(invalidators) = self.invalidator_map.lock().unwrap().remove(path) {
+            invalidators.into_iter().for_each(|i| i.invalidate());
+        }
+        for (_, invalidators) in take(&mut *self.dir_invalidator_map.lock().unwrap()).into_iter() {
+            invalidators.into_iter().for_each(|i| i.invalidate());
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl VirtualFileSystemVc {
+    #[turbo_tasks::function]
+    pub fn new(name: String) -> Self {
+        Self::cell(VirtualFileSystem {
+            name,
+            files: Mutex::new(BTreeMap::new()),
+            invalidator_map: Arc::new(InvalidatorMap::new()),
+            dir_invalidator_map: Arc::new(InvalidatorMap::new()),
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl FileSystem for VirtualFileSystem {
+    #[turbo_tasks::function]
+    async fn read(&self, fs_path: FileSystemPathVc) -> Result<FileContentVc> {
+        let path = &fs_path.await?.path;
+        self.invalidator_map
+            .insert(path.clone(), turbo_tasks::get_invalidator());
+        Ok(match self.files.lock().unwrap().get(path) {
+            Some(file) => FileContent::Content(file.clone()),
+            None => FileContent::NotFound,
+        }
+        .cell())
+    }
+
+    #[turbo_tasks::function]
+    fn read_link(&self, _fs_path: FileSystemPathVc) -> LinkContentVc {
+        LinkContent::NotFound.cell()
+    }
+
+    #[turbo_tasks::function]
+    async fn read_dir(&self, fs_path: FileSystemPathVc) -> Result<DirectoryContentVc> {
+        let path = &fs_path.await?.path;
+        self.dir_invalidator_map
+            .insert(path.clone(), turbo_tasks::get_invalidator());
+        let prefix = if path.is_empty() {
+            String::new()
+        } else {
+            format!("{path}/")
+        };
+        let mut entries = HashMap::new();
+        for file in self.files.lock().unwrap().keys() {
+            let relative = match file.strip_prefix(&prefix) {
+                Some(relative) => relative,
+                None => continue,
+            };
+            let entry = match relative.split_once('/') {
+                Some((dir, _)) => (dir, DirectoryEntry::Directory(fs_path.join(dir))),
+                None => (relative, DirectoryEntry::File(fs_path.join(relative))),
+            };
+            entries.entry(entry.0.to_string()).or_insert(entry.1);
+        }
+        if entries.is_empty() && !path.is_empty() {
+            return Ok(DirectoryContent::NotFound.cell());
+        }
+        Ok(DirectoryContentVc::new(entries))
+    }
+
+    #[turbo_tasks::function]
+    async fn write(
+        &self,
+        fs_path: FileSystemPathVc,
+        content: FileContentVc,
+    ) -> Result<CompletionVc> {
+        let path = &fs_path.await?.path;
+        match &*content.await? {
+            FileContent::Content(file) => self.set_file(path, file.clone()),
+            FileContent::NotFound => self.remove_file(path),
+        }
+        Ok(CompletionVc::new())
+    }
+
+    #[turbo_tasks::function]
+    fn write_link(
+        &self,
+        _fs_path: FileSystemPathVc,
+        _target: LinkContentVc,
+    ) -> Result<CompletionVc> {
+        bail!("Links are not supported by the virtual filesystem")
+    }
+
+    #[turbo_tasks::function]
+    async fn metadata(&self, fs_path: FileSystemPathVc) -> Result<FileMetaVc> {
+        let path = &fs_path.await?.path;
+        match self.files.lock().unwrap().get(path) {
+            Some(file) => Ok(file.meta.clone().cell()),
+            None => bail!("path not found, can't read metadata"),
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for VirtualFileSystem {
+    #[turbo_tasks::function]
+    fn to_string(&self) -> StringVc {
+        StringVc::cell(self.name.clone())
+    }
+}
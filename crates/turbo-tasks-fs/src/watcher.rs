@@ -0,0 +1,363 @@
+//! Watching of the directory of a [DiskFileSystem](crate::DiskFileSystem),
+//! which invalidates reads of files when they change.
+//!
+//! Changes are scoped by what was read: changes of permissions or timestamps
+//! only, e. g. by tools touching files, invalidate reads of the metadata but
+//! not of the content. Writes invalidate reads of the content, but the content
+//! is compared when it's read again, so tasks depending on it are only
+//! recomputed when it actually changed.
+//!
+//! Watching is only available with the `watch` feature, which is enabled by
+//! default. Without it, e. g. on wasm32 targets, the file system is read as is
+//! and reads are never invalidated.
+
+use std::time::Duration;
+
+#[cfg(feature = "watch")]
+pub(crate) use native::DiskWatcher;
+#[cfg(not(feature = "watch"))]
+pub(crate) use unsupported::DiskWatcher;
+
+/// Configures how a [DiskFileSystem](crate::DiskFileSystem) is watched.
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Clone, Debug, Hash, PartialOrd, Ord)]
+pub struct WatchOptions {
+    /// Globs of paths relative to the root whose changes are ignored, e. g.
+    /// output directories or `node_modules/.cache/**`. Reads of these paths
+    /// are not invalidated when they change.
+    pub ignore: Vec<String>,
+    /// How long changes are collected before the reads are invalidated, so
+    /// e. g. checking out a branch invalidates them at once.
+    pub debounce: Duration,
+    /// Polls the file system for changes instead of subscribing to the events
+    /// of the operating system, e. g. for network file systems which don't
+    /// emit events.
+    pub poll: bool,
+    /// Subscribes to the changes via Watchman when it's installed, which
+    /// doesn't need a watch per directory, e. g. for monorepos where these
+    /// exceed the limits of the operating system. The other options are used
+    /// when Watchman isn't available.
+    pub watchman: bool,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            ignore: Vec::new(),
+            debounce: Duration::from_millis(1),
+            poll: false,
+            watchman: false,
+        }
+    }
+}
+
+#[cfg(feature = "watch")]
+mod native {
+    use std::{
+        collections::{HashMap, HashSet},
+        mem::take,
+        path::{Path, PathBuf},
+        sync::{
+            mpsc::{channel, RecvError, TryRecvError},
+            Arc, Mutex,
+        },
+    };
+
+    use anyhow::{Context, Result};
+    use notify::{
+        watcher, DebouncedEvent, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher,
+    };
+    use turbo_tasks::{spawn_thread, Invalidator};
+
+    use super::WatchOptions;
+    use crate::{
+        glob::Glob,
+        invalidator_map::InvalidatorMap,
+        path_to_key,
+        util::sys_to_unix,
+        watchman::{self, WatchmanSubscription},
+    };
+
+    /// The watcher which is kept alive while watching.
+    enum NotifyWatcher {
+        Native(RecommendedWatcher),
+        Poll(PollWatcher),
+        Watchman(WatchmanSubscription),
+    }
+
+    #[derive(Default)]
+    pub(crate) struct DiskWatcher {
+        watcher: Mutex<Option<NotifyWatcher>>,
+    }
+
+    /// Whether the change of `path` is ignored by one of the `ignore` globs.
+    fn is_ignored(root: &str, ignore: &[Glob], path: &Path) -> bool {
+        if ignore.is_empty() {
+            return false;
+        }
+        match path.strip_prefix(root).ok().and_then(|path| path.to_str()) {
+            Some(path) => {
+                let path = sys_to_unix(path);
+                ignore.iter().any(|glob| glob.execute(&path))
+            }
+            None => false,
+        }
+    }
+
+    impl DiskWatcher {
+        pub fn start(
+            &self,
+            root: &str,
+            invalidator_map: Arc<InvalidatorMap>,
+            dir_invalidator_map: Arc<InvalidatorMap>,
+            metadata_invalidator_map: Arc<InvalidatorMap>,
+            options: &WatchOptions,
+        ) -> Result<()> {
+            let mut watcher_guard = self.watcher.lock().unwrap();
+            if watcher_guard.is_some() {
+                return Ok(());
+            }
+            let root = root.to_string();
+            let ignore = options
+                .ignore
+                .iter()
+                .map(|glob| {
+                    Glob::parse(glob).with_context(|| format!("invalid watch ignore glob {glob}"))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            // Create a channel to receive the events.
+            let (tx, rx) = channel();
+            // Create a watcher object, delivering debounced events. All files
+            // and directories in the root and below will be monitored for
+            // changes.
+            let subscription = options
+                .watchman
+                .then(|| watchman::subscribe(&root, tx.clone()));
+            let watcher = match subscription {
+                Some(Ok(subscription)) => NotifyWatcher::Watchman(subscription),
+                subscription => {
+                    if let Some(Err(err)) = subscription {
+                        println!(
+                            "watchman can't be used, falling back to the native watcher: {err:#}"
+                        );
+                    }
+                    if options.poll {
+                        let mut watcher = PollWatcher::new(tx, options.debounce)?;
+                        watcher.watch(&root, RecursiveMode::Recursive)?;
+                        NotifyWatcher::Poll(watcher)
+                    } else {
+                        // The notification back-end is selected based on the
+                        // platform.
+                        let mut watcher = watcher(tx, options.debounce)?;
+                        watcher.watch(&root, RecursiveMode::Recursive)?;
+                        NotifyWatcher::Native(watcher)
+                    }
+                }
+            };
+
+            // We need to invalidate all reads that happened before watching
+            // Best is to start_watching before starting to read
+            for (_, invalidators) in take(&mut *invalidator_map.lock().unwrap()).into_iter() {
+                invalidators.into_iter().for_each(|i| i.invalidate());
+            }
+            for (_, invalidators) in take(&mut *dir_invalidator_map.lock().unwrap()).into_iter() {
+                invalidators.into_iter().for_each(|i| i.invalidate());
+            }
+            for (_, invalidators) in
+                take(&mut *metadata_invalidator_map.lock().unwrap()).into_iter()
+            {
+                invalidators.into_iter().for_each(|i| i.invalidate());
+            }
+
+            watcher_guard.replace(watcher);
+
+            spawn_thread(move || {
+                let mut batched_invalidate_path = HashSet::new();
+                let mut batched_invalidate_path_dir = HashSet::new();
+                let mut batched_invalidate_path_metadata = HashSet::new();
+                let mut batched_invalidate_path_and_children = HashSet::new();
+                let mut batched_invalidate_path_and_children_dir = HashSet::new();
+
+                'outer: loop {
+                    let mut event = rx.recv().map_err(|e| match e {
+                        RecvError => TryRecvError::Disconnected,
+                    });
+                    loop {
+                        let ignored = match &event {
+                            Ok(DebouncedEvent::Write(path))
+                            | Ok(DebouncedEvent::Create(path))
+                            | Ok(DebouncedEvent::Remove(path))
+                            | Ok(DebouncedEvent::Chmod(path)) => is_ignored(&root, &ignore, path),
+                            Ok(DebouncedEvent::Rename(source, destination)) => {
+                                is_ignored(&root, &ignore, source)
+                                    && is_ignored(&root, &ignore, destination)
+                            }
+                            _ => false,
+                        };
+                        if ignored {
+                            event = rx.try_recv();
+                            continue;
+                        }
+                        match event {
+                            Ok(DebouncedEvent::Write(path)) => {
+                                batched_invalidate_path.insert(path);
+                            }
+                            Ok(DebouncedEvent::Create(path)) | Ok(DebouncedEvent::Remove(path)) => {
+                                batched_invalidate_path_and_children.insert(path.clone());
+                                batched_invalidate_path_and_children_dir.insert(path.clone());
+                                if let Some(parent) = path.parent() {
+                                    batched_invalidate_path_dir.insert(PathBuf::from(parent));
+                                }
+                            }
+                            Ok(DebouncedEvent::Rename(source, destination)) => {
+                                batched_invalidate_path_and_children.insert(source.clone());
+                                if let Some(parent) = source.parent() {
+                                    batched_invalidate_path_dir.insert(PathBuf::from(parent));
+                                }
+                                batched_invalidate_path_and_children.insert(destination.clone());
+                                if let Some(parent) = destination.parent() {
+                                    batched_invalidate_path_dir.insert(PathBuf::from(parent));
+                                }
+                            }
+                            Ok(DebouncedEvent::Rescan) => {
+                                batched_invalidate_path_and_children.insert(PathBuf::from(&root));
+                                batched_invalidate_path_and_children_dir
+                                    .insert(PathBuf::from(&root));
+                            }
+                            Ok(DebouncedEvent::Error(err, path)) => {
+                                println!("watch error ({:?}): {:?} ", path, err);
+                                match path {
+                                    Some(path) => {
+                                        batched_invalidate_path_and_children.insert(path.clone());
+                                        batched_invalidate_path_and_children_dir.insert(path);
+                                    }
+                                    None => {
+                                        batched_invalidate_path_and_children
+                                            .insert(PathBuf::from(&root));
+                                        batched_invalidate_path_and_children_dir
+                                            .insert(PathBuf::from(&root));
+                                    }
+                                }
+                            }
+                            Ok(DebouncedEvent::Chmod(path)) => {
+                                // only the metadata changed, the content is
+                                // still valid
+                                batched_invalidate_path_metadata.insert(path);
+                            }
+                            Ok(DebouncedEvent::NoticeRemove(_))
+                            | Ok(DebouncedEvent::NoticeWrite(_)) => {
+                                // ignored
+                            }
+                            Err(TryRecvError::Disconnected) => {
+                                // Sender has been disconnected
+                                // which means DiskFileSystem has been dropped
+                                // exit thread
+                                break 'outer;
+                            }
+                            Err(TryRecvError::Empty) => {
+                                break;
+                            }
+                        }
+                        event = rx.try_recv();
+                    }
+                    fn invalidate_path(
+                        invalidator_map: &mut HashMap<String, HashSet<Invalidator>>,
+                        paths: impl Iterator<Item = PathBuf>,
+                    ) {
+                        for path in paths {
+                            let key = path_to_key(path);
+                            if let Some(invalidators) = invalidator_map.remove(&key) {
+                                invalidators.into_iter().for_each(|i| i.invalidate());
+                            }
+                        }
+                    }
+                    fn invalidate_path_and_children_execute(
+                        invalidator_map: &mut HashMap<String, HashSet<Invalidator>>,
+                        paths: &HashSet<PathBuf>,
+                    ) {
+                        for (_, invalidators) in invalidator_map.drain_filter(|key, _| {
+                            paths
+                                .iter()
+                                .any(|path_key| key.starts_with(&path_to_key(path_key)))
+                        }) {
+                            invalidators.into_iter().for_each(|i| i.invalidate());
+                        }
+                    }
+                    {
+                        // every change of a file changes its metadata
+                        let mut metadata_invalidator_map = metadata_invalidator_map.lock().unwrap();
+                        invalidate_path(
+                            &mut metadata_invalidator_map,
+                            batched_invalidate_path
+                                .iter()
+                                .cloned()
+                                .chain(batched_invalidate_path_metadata.drain()),
+                        );
+                        invalidate_path_and_children_execute(
+                            &mut metadata_invalidator_map,
+                            &batched_invalidate_path_and_children,
+                        );
+                    }
+                    {
+                        let mut invalidator_map = invalidator_map.lock().unwrap();
+                        invalidate_path(&mut invalidator_map, batched_invalidate_path.drain());
+                        invalidate_path_and_children_execute(
+                            &mut invalidator_map,
+                            &batched_invalidate_path_and_children,
+                        );
+                        batched_invalidate_path_and_children.clear();
+                    }
+                    {
+                        let mut dir_invalidator_map = dir_invalidator_map.lock().unwrap();
+                        invalidate_path(
+                            &mut dir_invalidator_map,
+                            batched_invalidate_path_dir.drain(),
+                        );
+                        invalidate_path_and_children_execute(
+                            &mut dir_invalidator_map,
+                            &batched_invalidate_path_and_children_dir,
+                        );
+                        batched_invalidate_path_and_children_dir.clear();
+                    }
+                }
+            });
+            Ok(())
+        }
+
+        pub fn stop(&self) {
+            if let Some(watcher) = self.watcher.lock().unwrap().take() {
+                drop(watcher);
+                // thread will detect the stop because the channel is
+                // disconnected
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "watch"))]
+mod unsupported {
+    use std::sync::Arc;
+
+    use anyhow::{bail, Result};
+
+    use super::WatchOptions;
+    use crate::invalidator_map::InvalidatorMap;
+
+    #[derive(Default)]
+    pub(crate) struct DiskWatcher;
+
+    impl DiskWatcher {
+        pub fn start(
+            &self,
+            _root: &str,
+            _invalidator_map: Arc<InvalidatorMap>,
+            _dir_invalidator_map: Arc<InvalidatorMap>,
+            _metadata_invalidator_map: Arc<InvalidatorMap>,
+            _options: &WatchOptions,
+        ) -> Result<()> {
+            bail!("watching the file system requires the `watch` feature of turbo-tasks-fs")
+        }
+
+        pub fn stop(&self) {}
+    }
+}
@@ -0,0 +1,201 @@
+//! Watching via [Watchman](https://facebook.github.io/watchman/), which
+//! doesn't need an inotify watch per directory, so it also works in very large
+//! repositories where these exhaust the limits of the operating system.
+//!
+//! The `watchman` binary is used through its JSON command line interface. The
+//! changes of a subscription are translated to the events notify emits, so
+//! they are handled like these by the [DiskWatcher](crate::watcher).
+
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::mpsc::Sender,
+};
+
+use anyhow::{bail, Context, Result};
+use notify::DebouncedEvent;
+use serde::Deserialize;
+use serde_json::{json, Deserializer};
+use turbo_tasks::spawn_thread;
+
+const SUBSCRIPTION: &str = "turbopack";
+
+/// A subscription to the changes of a directory, which ends when it's
+/// dropped.
+pub(crate) struct WatchmanSubscription {
+    child: Child,
+}
+
+impl Drop for WatchmanSubscription {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[derive(Deserialize)]
+struct WatchProjectResponse {
+    #[serde(default)]
+    watch: String,
+    #[serde(default)]
+    relative_path: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SubscriptionResponse {
+    #[serde(default)]
+    subscription: Option<String>,
+    #[serde(default)]
+    is_fresh_instance: bool,
+    #[serde(default)]
+    files: Vec<ChangedFile>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChangedFile {
+    name: String,
+    exists: bool,
+    new: bool,
+}
+
+/// Subscribes to the changes of the files in `root` and sends them to `tx`.
+/// Fails when Watchman isn't installed or can't watch `root`.
+pub(crate) fn subscribe(root: &str, tx: Sender<DebouncedEvent>) -> Result<WatchmanSubscription> {
+    let output = Command::new("watchman")
+        .args(["--no-pretty", "watch-project", root])
+        .output()
+        .context("running watchman")?;
+    let project: WatchProjectResponse = serde_json::from_slice(&output.stdout)
+        .context("parsing the response of watchman watch-project")?;
+    if let Some(error) = project.error {
+        bail!("watchman can't watch {root}: {error}");
+    }
+    let mut query = json!({ "fields": ["name", "exists", "new"] });
+    // The watched project might be a parent directory of the root
+    if let Some(relative_path) = &project.relative_path {
+        query["relative_root"] = json!(relative_path);
+    }
+
+    let mut child = Command::new("watchman")
+        .args(["--no-pretty", "--persistent", "--json-command"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("running watchman")?;
+    // The command is read until the end of stdin, so it's closed afterwards
+    let mut stdin = child.stdin.take().context("watchman stdin is not piped")?;
+    serde_json::to_writer(
+        &mut stdin,
+        &json!(["subscribe", project.watch, SUBSCRIPTION, query]),
+    )?;
+    stdin.write_all(b"\n")?;
+    drop(stdin);
+    let stdout = child
+        .stdout
+        .take()
+        .context("watchman stdout is not piped")?;
+    let subscription = WatchmanSubscription { child };
+
+    let root = PathBuf::from(root);
+    spawn_thread(move || forward_changes(&root, stdout, &tx));
+    Ok(subscription)
+}
+
+/// Reads the responses of a subscription from `responses` and sends the
+/// changes of files in `root` to `tx`, until Watchman exits or `tx` is
+/// closed.
+fn forward_changes(root: &Path, responses: impl Read, tx: &Sender<DebouncedEvent>) {
+    // The first update lists all files, which are already up to date
+    let mut initial = true;
+    for response in Deserializer::from_reader(responses).into_iter::<SubscriptionResponse>() {
+        // Watchman exited, e. g. because the subscription was dropped
+        let response = match response {
+            Ok(response) => response,
+            Err(_) => break,
+        };
+        if let Some(error) = response.error {
+            println!("watchman error: {error}");
+            if tx.send(DebouncedEvent::Rescan).is_err() {
+                break;
+            }
+            continue;
+        }
+        // The response to the subscribe command
+        if response.subscription.is_none() {
+            continue;
+        }
+        if response.is_fresh_instance {
+            // Changes might have been missed, e. g. when Watchman was
+            // restarted
+            if !initial && tx.send(DebouncedEvent::Rescan).is_err() {
+                break;
+            }
+            initial = false;
+            continue;
+        }
+        for file in response.files {
+            let path = root.join(file.name);
+            let event = if !file.exists {
+                DebouncedEvent::Remove(path)
+            } else if file.new {
+                DebouncedEvent::Create(path)
+            } else {
+                DebouncedEvent::Write(path)
+            };
+            if tx.send(event).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::mpsc::channel};
+
+    use notify::DebouncedEvent;
+
+    use super::forward_changes;
+
+    #[test]
+    fn forwards_changes_after_the_initial_update() {
+        let responses = r#"
+            {"clock": "c:0:1", "subscribe": "turbopack"}
+            {"subscription": "turbopack", "is_fresh_instance": true, "files": [
+                {"name": "a.js", "exists": true, "new": true}
+            ]}
+            {"subscription": "turbopack", "files": [
+                {"name": "a.js", "exists": true, "new": false},
+                {"name": "dir/b.js", "exists": true, "new": true},
+                {"name": "c.js", "exists": false, "new": false}
+            ]}
+            {"subscription": "turbopack", "is_fresh_instance": true, "files": []}
+            {"error": "the watch was cancelled"}
+        "#;
+        let (tx, rx) = channel();
+        forward_changes(Path::new("/root"), responses.as_bytes(), &tx);
+        drop(tx);
+        // The events don't implement PartialEq
+        let events = rx
+            .iter()
+            .map(|event| format!("{event:?}"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                DebouncedEvent::Write("/root/a.js".into()),
+                DebouncedEvent::Create("/root/dir/b.js".into()),
+                DebouncedEvent::Remove("/root/c.js".into()),
+                // Changes might have been missed by the fresh instance
+                DebouncedEvent::Rescan,
+                DebouncedEvent::Rescan,
+            ]
+            .map(|event| format!("{event:?}"))
+        );
+    }
+}
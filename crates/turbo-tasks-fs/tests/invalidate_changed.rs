@@ -0,0 +1,68 @@
+use std::{env::temp_dir, fs, sync::Mutex};
+
+use turbo_tasks::{NothingVc, TaskId, TurboTasks};
+use turbo_tasks_fs::{register, DiskFileSystem, DiskFileSystemVc, FileContent, FileSystem};
+use turbo_tasks_memory::MemoryBackend;
+
+/// The contents read by the executions of the tasks spawned by [spawn_read].
+static READS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static CHANGED: Mutex<usize> = Mutex::new(0);
+
+/// Spawns a root task which reads `file` in the directory `root`.
+fn spawn_read(tt: &TurboTasks<MemoryBackend>, root: &str, file: &'static str) -> TaskId {
+    let root = root.to_string();
+    tt.spawn_root_task(move || {
+        let root = root.clone();
+        Box::pin(async move {
+            let fs = DiskFileSystemVc::new("test".to_string(), root);
+            if let FileContent::Content(file) = &*fs.root().join(file).read().await? {
+                READS
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8(file.content().to_vec())?);
+            }
+            Ok(NothingVc::new().into())
+        })
+    })
+}
+
+#[tokio::test]
+async fn invalidates_files_changed_since_the_file_system_was_persisted() {
+    register();
+
+    let dir = temp_dir().join(format!("turbo-tasks-fs-invalidate-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.txt"), "a").unwrap();
+    fs::write(dir.join("b.txt"), "b").unwrap();
+    let root = dir.to_string_lossy().to_string();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let read_a = spawn_read(&tt, &root, "a.txt");
+    let read_b = spawn_read(&tt, &root, "b.txt");
+    tt.wait_task_completion(read_a, true).await.unwrap();
+    tt.wait_task_completion(read_b, true).await.unwrap();
+    READS.lock().unwrap().sort();
+    assert_eq!(*READS.lock().unwrap(), vec!["a", "b"]);
+
+    // The file system is restored from its serialized form, like from a
+    // persistent cache, after a file was changed while nothing was watching
+    let changed_file = dir.join("a.txt");
+    let restore = tt.spawn_once_task(async move {
+        let disk_fs = DiskFileSystemVc::new("test".to_string(), root).await?;
+        let persisted = serde_json::to_string(&*disk_fs)?;
+        fs::write(changed_file, "changed").unwrap();
+        let restored: DiskFileSystem = serde_json::from_str(&persisted)?;
+        *CHANGED.lock().unwrap() = restored.invalidate_changed().await?;
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(restore, true).await.unwrap();
+    assert_eq!(*CHANGED.lock().unwrap(), 1);
+
+    // Only the task reading the changed file is executed again
+    tt.wait_task_completion(read_a, true).await.unwrap();
+    tt.wait_task_completion(read_b, true).await.unwrap();
+    assert_eq!(*READS.lock().unwrap(), vec!["a", "b", "changed"]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,144 @@
+use std::{env::temp_dir, fs, path::PathBuf, sync::Mutex, time::Duration};
+
+use turbo_tasks::{NothingVc, TaskId, TurboTasks};
+use turbo_tasks_fs::{register, DiskFileSystemVc, FileContent, FileSystem, WatchOptions};
+use turbo_tasks_memory::MemoryBackend;
+
+static IGNORE_READS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static POLL_READS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Spawns a root task which reads `file` in the directory `root` and pushes
+/// its content to `reads` on every execution.
+fn spawn_read(
+    tt: &TurboTasks<MemoryBackend>,
+    reads: &'static Mutex<Vec<String>>,
+    root: &str,
+    file: &'static str,
+) -> TaskId {
+    let root = root.to_string();
+    tt.spawn_root_task(move || {
+        let root = root.clone();
+        Box::pin(async move {
+            let fs = DiskFileSystemVc::new("test".to_string(), root);
+            if let FileContent::Content(file) = &*fs.root().join(file).read().await? {
+                reads
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8(file.content().to_vec())?);
+            }
+            Ok(NothingVc::new().into())
+        })
+    })
+}
+
+/// Starts watching the file system of the directory `root`.
+async fn start_watching(tt: &TurboTasks<MemoryBackend>, root: &str, options: WatchOptions) {
+    let root = root.to_string();
+    let task = tt.spawn_once_task(async move {
+        DiskFileSystemVc::new("test".to_string(), root)
+            .await?
+            .start_watching_with_options(&options)?;
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
+
+/// Creates an empty directory for a test with `files` in it.
+fn test_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = temp_dir().join(format!(
+        "turbo-tasks-fs-watch-{name}-{}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    for (path, content) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    dir
+}
+
+/// Waits until `reads` contains `content`, which is read again after the
+/// watcher invalidated the read.
+async fn wait_for_read(reads: &Mutex<Vec<String>>, content: &str) {
+    for _ in 0..100 {
+        if reads.lock().unwrap().iter().any(|read| read == content) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!(
+        "{content:?} was not read again: {:?}",
+        reads.lock().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn ignores_changes_of_ignored_files() {
+    register();
+
+    let dir = test_dir("ignore", &[("src.txt", "src"), ("cache/file.txt", "cache")]);
+    let root = dir.to_string_lossy().to_string();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    start_watching(
+        &tt,
+        &root,
+        WatchOptions {
+            ignore: vec!["cache/**/*".to_string()],
+            ..Default::default()
+        },
+    )
+    .await;
+    let read_src = spawn_read(&tt, &IGNORE_READS, &root, "src.txt");
+    let read_cache = spawn_read(&tt, &IGNORE_READS, &root, "cache/file.txt");
+    tt.wait_task_completion(read_src, true).await.unwrap();
+    tt.wait_task_completion(read_cache, true).await.unwrap();
+    IGNORE_READS.lock().unwrap().sort();
+    assert_eq!(*IGNORE_READS.lock().unwrap(), vec!["cache", "src"]);
+
+    // The ignored file is changed first, so its change has been handled when
+    // the change of the other file is
+    fs::write(dir.join("cache/file.txt"), "cache changed").unwrap();
+    fs::write(dir.join("src.txt"), "src changed").unwrap();
+    wait_for_read(&IGNORE_READS, "src changed").await;
+    tt.wait_task_completion(read_src, true).await.unwrap();
+    tt.wait_task_completion(read_cache, true).await.unwrap();
+    assert_eq!(
+        *IGNORE_READS.lock().unwrap(),
+        vec!["cache", "src", "src changed"]
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn polls_for_changes() {
+    register();
+
+    let dir = test_dir("poll", &[("file.txt", "file")]);
+    let root = dir.to_string_lossy().to_string();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    start_watching(
+        &tt,
+        &root,
+        WatchOptions {
+            debounce: Duration::from_millis(10),
+            poll: true,
+            ..Default::default()
+        },
+    )
+    .await;
+    let read = spawn_read(&tt, &POLL_READS, &root, "file.txt");
+    tt.wait_task_completion(read, true).await.unwrap();
+    assert_eq!(*POLL_READS.lock().unwrap(), vec!["file"]);
+
+    // The poll watcher compares modification times, which might have a
+    // resolution of a second
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    fs::write(dir.join("file.txt"), "file changed").unwrap();
+    wait_for_read(&POLL_READS, "file changed").await;
+
+    fs::remove_dir_all(&dir).unwrap();
+}
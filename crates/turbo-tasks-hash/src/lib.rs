@@ -8,6 +8,7 @@ mod base16;
 mod deterministic_hash;
 mod hex;
 mod md4;
+mod sha256;
 mod xxh3_hash64;
 
 pub use crate::{
@@ -15,5 +16,6 @@ pub use crate::{
     deterministic_hash::{DeterministicHash, DeterministicHasher},
     hex::{encode_hex, encode_hex_string},
     md4::hash_md4,
+    sha256::hash_sha256,
     xxh3_hash64::{hash_xxh3_hash64, Xxh3Hash64Hasher},
 };
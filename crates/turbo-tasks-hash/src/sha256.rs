@@ -0,0 +1,8 @@
+use sha2::Digest;
+
+/// Hash some content with the SHA-256 cryptographic hash function.
+///
+/// Returns a 32-byte hash digest.
+pub fn hash_sha256(content: &[u8]) -> [u8; 32] {
+    sha2::Sha256::digest(content).into()
+}
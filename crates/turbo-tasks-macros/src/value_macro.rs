@@ -129,6 +129,7 @@ struct ValueArguments {
     cell_mode: CellMode,
     manual_eq: bool,
     transparent: bool,
+    heap_size: bool,
 }
 
 impl Parse for ValueArguments {
@@ -139,6 +140,7 @@ impl Parse for ValueArguments {
             cell_mode: CellMode::Shared,
             manual_eq: false,
             transparent: false,
+            heap_size: false,
         };
         let punctuated: Punctuated<Meta, Token![,]> = input.parse_terminated(Meta::parse)?;
         for meta in punctuated {
@@ -193,12 +195,15 @@ impl Parse for ValueArguments {
                 ("transparent", Meta::Path(_)) => {
                     result.transparent = true;
                 }
+                ("heap_size", Meta::Path(_)) => {
+                    result.heap_size = true;
+                }
                 (_, meta) => {
                     return Err(Error::new_spanned(
                         &meta,
                         format!(
                             "unexpected {:?}, expected \"shared\", \"into\", \"serialization\", \
-                             \"cell\", \"eq\", \"transparent\"",
+                             \"cell\", \"eq\", \"transparent\", \"heap_size\"",
                             meta
                         ),
                     ))
@@ -218,6 +223,7 @@ pub fn value(args: TokenStream, input: TokenStream) -> TokenStream {
         cell_mode,
         manual_eq,
         transparent,
+        heap_size,
     } = parse_macro_input!(args as ValueArguments);
 
     let (vis, ident) = match &item {
@@ -410,6 +416,14 @@ pub fn value(args: TokenStream, input: TokenStream) -> TokenStream {
         }
     };
 
+    let register_heap_size = if heap_size {
+        quote! {
+            value.register_heap_size::<#ident>();
+        }
+    } else {
+        quote!()
+    };
+
     let for_input_marker = match serialization_mode {
         SerializationMode::None | SerializationMode::Auto | SerializationMode::Custom => quote! {},
         SerializationMode::AutoForInput | SerializationMode::CustomForInput => quote! {
@@ -591,6 +605,7 @@ pub fn value(args: TokenStream, input: TokenStream) -> TokenStream {
         ) {
             #value_type_init_ident.get_or_init(|| {
                 let mut value = #new_value_type;
+                #register_heap_size
                 f(&mut value);
                 value
             }).register(global_name);
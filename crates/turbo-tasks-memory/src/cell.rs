@@ -6,23 +6,37 @@ use turbo_tasks::{backend::CellContent, TaskId, TurboTasksBackendApi};
 pub struct Cell {
     content: CellContent,
     updates: u32,
+    /// The estimated memory usage of the content, see
+    /// [GcPolicy](crate::gc::GcPolicy).
+    size: usize,
+    eviction: Eviction,
     pub(crate) dependent_tasks: HashSet<TaskId>,
 }
 
+/// Whether the content of a [Cell] was evicted to free memory.
+#[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
+enum Eviction {
+    #[default]
+    None,
+    /// Executing the task again assigns the same content, so the dependent
+    /// tasks don't need to be notified.
+    Evicted,
+    /// The task was invalidated after the content was evicted, so the
+    /// recomputed content might be different.
+    EvictedAndInvalidated,
+}
+
 impl Cell {
     pub fn new() -> Self {
         Self {
             content: CellContent(None),
             updates: 0,
+            size: 0,
+            eviction: Eviction::None,
             dependent_tasks: HashSet::new(),
         }
     }
 
-    pub fn read_content(&mut self, reader: TaskId) -> CellContent {
-        self.dependent_tasks.insert(reader);
-        self.read_content_untracked()
-    }
-
     /// INVALIDATION: Be careful with this, it will not track dependencies, so
     /// using it could break cache invalidation.
     pub fn read_content_untracked(&self) -> CellContent {
@@ -33,11 +47,56 @@ impl Cell {
         self.dependent_tasks.insert(reader);
     }
 
-    pub fn assign(&mut self, content: CellContent, turbo_tasks: &dyn TurboTasksBackendApi) {
+    /// Assigns the content and returns the size of the previous content.
+    pub fn assign(
+        &mut self,
+        content: CellContent,
+        size: usize,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> usize {
         self.content = content;
         self.updates += 1;
+        let eviction = std::mem::take(&mut self.eviction);
         // notify
-        if !self.dependent_tasks.is_empty() {
+        if eviction != Eviction::Evicted && !self.dependent_tasks.is_empty() {
+            turbo_tasks.schedule_notify_tasks_set(&self.dependent_tasks);
+        }
+        std::mem::replace(&mut self.size, size)
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_evicted(&self) -> bool {
+        self.eviction != Eviction::None
+    }
+
+    /// Drops the content and returns its size. The task needs to be executed
+    /// again before the cell can be read.
+    pub fn evict(&mut self) -> usize {
+        if self.size == 0 {
+            return 0;
+        }
+        self.content = CellContent(None);
+        self.eviction = Eviction::Evicted;
+        std::mem::take(&mut self.size)
+    }
+
+    /// Called when the task is invalidated, as the content of an evicted cell
+    /// might be different when it's recomputed.
+    pub fn invalidate_evicted(&mut self) {
+        if self.eviction == Eviction::Evicted {
+            self.eviction = Eviction::EvictedAndInvalidated;
+        }
+    }
+
+    /// Called when an execution of the task has completed without assigning
+    /// the evicted cell, which is empty then.
+    pub fn reset_eviction(&mut self, turbo_tasks: &dyn TurboTasksBackendApi) {
+        if std::mem::take(&mut self.eviction) == Eviction::EvictedAndInvalidated
+            && !self.dependent_tasks.is_empty()
+        {
             turbo_tasks.schedule_notify_tasks_set(&self.dependent_tasks);
         }
     }
@@ -0,0 +1,81 @@
+use std::{
+    fmt,
+    mem::{size_of, size_of_val},
+    time::Duration,
+};
+
+use turbo_tasks::{backend::CellContent, registry, SharedReference};
+
+use crate::cell::Cell;
+
+/// Controls when and which cells are evicted by the
+/// [MemoryBackend](crate::MemoryBackend) to limit its memory usage, e. g. for
+/// long-running dev servers.
+///
+/// Only cells of tasks which can be executed again are evicted. Reading an
+/// evicted cell executes the task again to recompute its content, so values
+/// which are large compared to the time needed to recompute them, like ASTs or
+/// analysis results, are evicted first, while small values which are
+/// expensive to recompute are kept.
+#[derive(Clone)]
+pub struct GcPolicy {
+    /// The estimated memory usage of all cells in bytes above which cells are
+    /// evicted.
+    pub memory_limit: usize,
+    /// The estimated memory usage of all cells in bytes to which the eviction
+    /// reduces it, to avoid evicting again on the next update.
+    pub target: usize,
+    /// Estimates the memory usage of the content of a cell in bytes.
+    pub estimate_size: fn(&CellContent) -> usize,
+}
+
+impl fmt::Debug for GcPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GcPolicy")
+            .field("memory_limit", &self.memory_limit)
+            .field("target", &self.target)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GcPolicy {
+    /// Evicts cells when their estimated memory usage exceeds `memory_limit`
+    /// bytes, until it's reduced to 3/4 of the limit.
+    pub fn new(memory_limit: usize) -> Self {
+        Self {
+            memory_limit,
+            target: memory_limit / 4 * 3,
+            estimate_size: heap_size,
+        }
+    }
+
+    /// The order in which the cells of a task are evicted, the higher the
+    /// earlier.
+    pub(crate) fn priority(size: usize, duration: Duration) -> f64 {
+        // The duration of an execution which didn't take measurable time is
+        // still not free.
+        size as f64 / (duration.as_secs_f64() + 1e-6)
+    }
+}
+
+/// Estimates the memory usage of the content of a cell by the size of the
+/// cell and the value itself, without memory owned by the value, e. g. the
+/// items of a Vec.
+pub fn shallow_size(content: &CellContent) -> usize {
+    size_of::<Cell>()
+        + content
+            .0
+            .as_ref()
+            .map_or(0, |reference| size_of_val(&*reference.1))
+}
+
+/// Estimates the memory usage of the content of a cell like [shallow_size],
+/// plus the memory owned by the value when its type implements
+/// [HeapSize](turbo_tasks::HeapSize).
+pub fn heap_size(content: &CellContent) -> usize {
+    let owned = match &content.0 {
+        Some(SharedReference(Some(ty), value)) => registry::get_value_type(*ty).heap_size(&**value),
+        _ => None,
+    };
+    shallow_size(content) + owned.unwrap_or_default()
+}
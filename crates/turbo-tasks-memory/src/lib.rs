@@ -4,6 +4,7 @@
 
 mod cell;
 mod count_hash_set;
+pub mod gc;
 mod memory_backend;
 mod memory_backend_with_pg;
 mod output;
@@ -12,5 +13,6 @@ pub mod stats;
 mod task;
 pub mod viz;
 
+pub use gc::GcPolicy;
 pub use memory_backend::MemoryBackend;
 pub use memory_backend_with_pg::MemoryBackendWithPersistedGraph;
@@ -5,7 +5,7 @@ use std::{
     future::Future,
     hash::BuildHasherDefault,
     pin::Pin,
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
     time::Duration,
 };
 
@@ -24,6 +24,7 @@ use turbo_tasks::{
 };
 
 use crate::{
+    gc::GcPolicy,
     output::Output,
     scope::{TaskScope, TaskScopeId},
     task::{
@@ -41,6 +42,10 @@ pub struct MemoryBackend {
     backend_job_id_factory: IdFactory<BackendJobId>,
     task_cache: DashMap<PersistentTaskType, TaskId, BuildHasherDefault<FxHasher>>,
     scope_generation: AtomicUsize,
+    gc_policy: Option<GcPolicy>,
+    /// The estimated memory usage of all cells, see [GcPolicy].
+    cell_memory: AtomicUsize,
+    gc_scheduled: AtomicBool,
 }
 
 impl Default for MemoryBackend {
@@ -66,9 +71,52 @@ impl MemoryBackend {
             backend_job_id_factory: IdFactory::new(),
             task_cache: DashMap::default(),
             scope_generation: AtomicUsize::new(0),
+            gc_policy: None,
+            cell_memory: AtomicUsize::new(0),
+            gc_scheduled: AtomicBool::new(false),
         }
     }
 
+    /// Evicts cells according to the `policy` when their estimated memory
+    /// usage exceeds its limit.
+    pub fn with_gc_policy(mut self, policy: GcPolicy) -> Self {
+        self.gc_policy = Some(policy);
+        self
+    }
+
+    /// The estimated memory usage of all cells, see [GcPolicy]. It's only
+    /// tracked when a policy is set.
+    pub fn cell_memory_usage(&self) -> usize {
+        self.cell_memory.load(Ordering::Relaxed)
+    }
+
+    /// Evicts the cells of the tasks with the highest
+    /// [priority](GcPolicy::priority) until the memory usage is reduced to the
+    /// target of the policy. Returns the freed memory.
+    pub fn collect_garbage(&self) -> usize {
+        let policy = match &self.gc_policy {
+            Some(policy) => policy,
+            None => return 0,
+        };
+        let mut candidates = Vec::new();
+        self.with_all_cached_tasks(|id| {
+            if let Some((size, duration)) = self.with_task(id, |task| task.eviction_candidate()) {
+                candidates.push((GcPolicy::priority(size, duration), id));
+            }
+        });
+        candidates.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        let mut freed = 0;
+        for (_, id) in candidates {
+            if self.cell_memory_usage() <= policy.target {
+                break;
+            }
+            let size = self.with_task(id, |task| task.evict_cells());
+            self.cell_memory.fetch_sub(size, Ordering::Relaxed);
+            freed += size;
+        }
+        freed
+    }
+
     fn connect_task_child(
         &self,
         parent: TaskId,
@@ -325,7 +373,7 @@ impl Backend for MemoryBackend {
         task: TaskId,
         index: usize,
         reader: TaskId,
-        _turbo_tasks: &dyn TurboTasksBackendApi,
+        turbo_tasks: &dyn TurboTasksBackendApi,
     ) -> Result<Result<CellContent, EventListener>> {
         if task == reader {
             Ok(Ok(self.with_task(task, |task| {
@@ -333,9 +381,9 @@ impl Backend for MemoryBackend {
             })))
         } else {
             Task::add_dependency_to_current(TaskDependency::TaskCell(task, index));
-            Ok(Ok(self.with_task(task, |task| {
-                task.with_cell_mut(index, |cell| cell.read_content(reader))
-            })))
+            Ok(self.with_task(task, |task| {
+                task.read_cell(index, Some(reader), self, turbo_tasks)
+            }))
         }
     }
 
@@ -343,11 +391,9 @@ impl Backend for MemoryBackend {
         &self,
         task: TaskId,
         index: usize,
-        _turbo_tasks: &dyn TurboTasksBackendApi,
+        turbo_tasks: &dyn TurboTasksBackendApi,
     ) -> Result<Result<CellContent, EventListener>> {
-        Ok(Ok(self.with_task(task, |task| {
-            task.with_cell(index, |cell| cell.read_content_untracked())
-        })))
+        Ok(self.with_task(task, |task| task.read_cell(index, None, self, turbo_tasks)))
     }
 
     fn track_read_task_cell(
@@ -412,9 +458,23 @@ impl Backend for MemoryBackend {
         content: CellContent,
         turbo_tasks: &dyn TurboTasksBackendApi,
     ) {
-        self.with_task(task, |task| {
-            task.with_cell_mut(index, |cell| cell.assign(content, turbo_tasks))
-        })
+        let size = self
+            .gc_policy
+            .as_ref()
+            .map_or(0, |policy| (policy.estimate_size)(&content));
+        let previous_size = self.with_task(task, |task| {
+            task.with_cell_mut(index, |cell| cell.assign(content, size, turbo_tasks))
+        });
+        self.cell_memory.fetch_add(size, Ordering::Relaxed);
+        self.cell_memory.fetch_sub(previous_size, Ordering::Relaxed);
+        if let Some(policy) = &self.gc_policy {
+            if self.cell_memory_usage() > policy.memory_limit
+                && !self.gc_scheduled.swap(true, Ordering::AcqRel)
+            {
+                turbo_tasks
+                    .schedule_backend_background_job(self.create_backend_job(Job::CollectGarbage));
+            }
+        }
     }
 
     /// SAFETY: Must only called once with the same id
@@ -445,6 +505,7 @@ impl Backend for MemoryBackend {
     ) -> TaskId {
         let result = if let Some(task) = self.task_cache.get(&task_type).map(|task| *task) {
             // fast pass without creating a new task
+            self.with_task(task, |task| task.record_cache_hit());
             self.connect_task_child(parent_task, task, turbo_tasks);
 
             // TODO maybe force (background) scheduling to avoid inactive tasks hanging in
@@ -482,7 +543,9 @@ impl Backend for MemoryBackend {
                         self.memory_tasks.remove(*id);
                         turbo_tasks.reuse_task_id(id);
                     }
-                    *entry.get()
+                    let task = *entry.get();
+                    self.with_task(task, |task| task.record_cache_hit());
+                    task
                 }
             };
             self.connect_task_child(parent_task, result_task, turbo_tasks);
@@ -526,6 +589,9 @@ pub(crate) enum Job {
     /// Remove tasks from a scope. Scheduled by `run_remove_from_scope_queue` to
     /// split off work.
     RemoveFromScopeQueue(VecDeque<TaskId>, TaskScopeId),
+    /// Evict cells when the memory usage exceeds the limit of the
+    /// [GcPolicy].
+    CollectGarbage,
 }
 
 impl Job {
@@ -558,6 +624,10 @@ impl Job {
             Job::RemoveFromScopeQueue(queue, id) => {
                 run_remove_from_scope_queue(queue, id, backend, turbo_tasks);
             }
+            Job::CollectGarbage => {
+                backend.collect_garbage();
+                backend.gc_scheduled.store(false, Ordering::Release);
+            }
         }
     }
 }
@@ -55,6 +55,7 @@ pub struct TaskStats {
     pub count: usize,
     pub active_count: usize,
     pub executions: usize,
+    pub cache_hits: usize,
     pub roots: usize,
     pub scopes: usize,
     pub total_duration: Duration,
@@ -70,6 +71,7 @@ impl Default for TaskStats {
             count: 0,
             active_count: 0,
             executions: 0,
+            cache_hits: 0,
             roots: 0,
             scopes: 0,
             total_duration: Duration::ZERO,
@@ -81,6 +83,45 @@ impl Default for TaskStats {
     }
 }
 
+impl TaskStats {
+    /// Summarizes how well the task cache works for this kind of task.
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.cache_hits,
+            misses: self.count,
+            recomputations: self.executions.saturating_sub(self.count),
+            recompute_duration: self.total_update_duration,
+        }
+    }
+}
+
+/// Cache statistics of a task function.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct CacheStats {
+    /// How often an existing task was reused from the task cache.
+    pub hits: usize,
+    /// How often a task had to be created because it was not in the task
+    /// cache.
+    pub misses: usize,
+    /// How often tasks were executed again after being invalidated.
+    pub recomputations: usize,
+    /// The time spent in the latest recomputation of the tasks.
+    pub recompute_duration: Duration,
+}
+
+impl CacheStats {
+    /// The share of cache lookups which reused an existing task, between 0
+    /// and 1.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
 pub struct Stats {
     tasks: HashMap<TaskType, TaskStats>,
 }
@@ -117,6 +158,7 @@ impl Stats {
             total_duration,
             last_duration,
             executions,
+            cache_hits,
             root_scoped,
             child_scopes,
             active,
@@ -133,6 +175,7 @@ impl Stats {
         }
         stats.max_duration = max(stats.max_duration, last_duration);
         stats.executions += executions as usize;
+        stats.cache_hits += cache_hits as usize;
         if root_scoped {
             stats.roots += 1;
         }
@@ -166,6 +209,25 @@ impl Stats {
         });
     }
 
+    /// Returns the [CacheStats] of all cached task functions, with the most
+    /// frequently created ones first. Root and once tasks are not cached and
+    /// are skipped.
+    pub fn cache_stats(&self) -> Vec<(TaskType, CacheStats)> {
+        let mut cache_stats: Vec<_> = self
+            .tasks
+            .iter()
+            .filter(|(ty, _)| !matches!(ty, TaskType::Root(_) | TaskType::Once(_)))
+            .map(|(ty, stats)| (ty.clone(), stats.cache_stats()))
+            .collect();
+        cache_stats.sort_by(|(a_ty, a), (b_ty, b)| {
+            b.misses
+                .cmp(&a.misses)
+                .then_with(|| b.hits.cmp(&a.hits))
+                .then_with(|| a_ty.to_string().cmp(&b_ty.to_string()))
+        });
+        cache_stats
+    }
+
     pub fn merge_resolve(&mut self) {
         self.merge(|ty, _stats| match ty {
             TaskType::Root(_) | TaskType::Once(_) | TaskType::Native(_) => false,
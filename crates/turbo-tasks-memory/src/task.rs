@@ -8,7 +8,10 @@ use std::{
     hash::Hash,
     mem::{replace, take},
     pin::Pin,
-    sync::Mutex,
+    sync::{
+        atomic::{AtomicU32, Ordering as AtomicOrdering},
+        Mutex,
+    },
     time::Duration,
 };
 
@@ -17,7 +20,7 @@ use event_listener::{Event, EventListener};
 use parking_lot::{RwLock, RwLockWriteGuard};
 use tokio::task_local;
 use turbo_tasks::{
-    backend::{CellMappings, PersistentTaskType},
+    backend::{CellContent, CellMappings, PersistentTaskType},
     get_invalidator, registry, FunctionId, Invalidator, RawVc, TaskId, TaskInput, TraitTypeId,
     TurboTasksBackendApi,
 };
@@ -118,6 +121,9 @@ pub struct Task {
     /// It will only be accessed from the task execution, which happens
     /// non-concurrently.
     execution_data: Mutex<TaskExecutionData>,
+    /// The number of times the task was looked up in the task cache and
+    /// reused instead of being created again. It's only used for stats.
+    cache_hits: AtomicU32,
 }
 
 /// Task data that is only modified during task execution.
@@ -272,6 +278,7 @@ impl Task {
             ty: TaskType::Native(native_fn, bound_fn),
             state: Default::default(),
             execution_data: Default::default(),
+            cache_hits: AtomicU32::new(0),
         }
     }
 
@@ -286,6 +293,7 @@ impl Task {
             ty: TaskType::ResolveNative(native_fn),
             state: Default::default(),
             execution_data: Default::default(),
+            cache_hits: AtomicU32::new(0),
         }
     }
 
@@ -301,6 +309,7 @@ impl Task {
             ty: TaskType::ResolveTrait(trait_type, trait_fn_name),
             state: Default::default(),
             execution_data: Default::default(),
+            cache_hits: AtomicU32::new(0),
         }
     }
 
@@ -319,6 +328,7 @@ impl Task {
                 ..Default::default()
             }),
             execution_data: Default::default(),
+            cache_hits: AtomicU32::new(0),
         }
     }
 
@@ -337,6 +347,7 @@ impl Task {
                 ..Default::default()
             }),
             execution_data: Default::default(),
+            cache_hits: AtomicU32::new(0),
         }
     }
 
@@ -557,6 +568,9 @@ impl Task {
             match state.state_type {
                 InProgress => {
                     state.state_type = Done;
+                    for cell in state.created_cells.iter_mut() {
+                        cell.reset_eviction(turbo_tasks);
+                    }
                     for scope in state.scopes.iter() {
                         backend.with_scope(scope, |scope| {
                             scope.decrement_unfinished_tasks(backend);
@@ -607,6 +621,9 @@ impl Task {
         self.clear_dependencies(backend);
 
         let mut state = self.state.write();
+        for cell in state.created_cells.iter_mut() {
+            cell.invalidate_evicted();
+        }
         match state.state_type {
             Dirty | Scheduled | InProgressDirty => {
                 // already dirty
@@ -637,6 +654,10 @@ impl Task {
             }
             InProgress => {
                 state.state_type = InProgressDirty;
+                drop(state);
+                // The result of the execution is discarded, so it doesn't
+                // need to complete
+                turbo_tasks.cancel_task_execution(self.id);
             }
         }
     }
@@ -1121,10 +1142,70 @@ impl Task {
         func(&mut state.created_cells[index])
     }
 
-    /// Access to a cell.
-    pub(crate) fn with_cell<T>(&self, index: usize, func: impl FnOnce(&Cell) -> T) -> T {
+    /// Reads a cell, tracking the read when a `reader` is passed. When the
+    /// content of the cell was evicted, the task is scheduled to recompute it
+    /// and a listener for the completion is returned instead.
+    pub(crate) fn read_cell(
+        &self,
+        index: usize,
+        reader: Option<TaskId>,
+        backend: &MemoryBackend,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Result<CellContent, EventListener> {
+        let mut state = self.state.write();
+        let cell = &mut state.created_cells[index];
+        if let Some(reader) = reader {
+            cell.track_read(reader);
+        }
+        if !cell.is_evicted() {
+            return Ok(cell.read_content_untracked());
+        }
+        let listener = state.event.listen();
+        match state.state_type {
+            Done => {
+                for scope in state.scopes.iter() {
+                    backend.with_scope(scope, |scope| scope.increment_unfinished_tasks());
+                }
+                state.state_type = Scheduled;
+                drop(state);
+                turbo_tasks.schedule(self.id);
+            }
+            Dirty => {
+                state.state_type = Scheduled;
+                drop(state);
+                turbo_tasks.schedule(self.id);
+            }
+            Scheduled | InProgress | InProgressDirty => {}
+        }
+        Err(listener)
+    }
+
+    /// The estimated memory usage of the cells and the duration of the last
+    /// execution, when the cells can be evicted.
+    pub(crate) fn eviction_candidate(&self) -> Option<(usize, Duration)> {
+        if matches!(self.ty, TaskType::Root(_) | TaskType::Once(_)) {
+            return None;
+        }
         let state = self.state.read();
-        func(&state.created_cells[index])
+        if state.state_type != Done {
+            return None;
+        }
+        let size = state.created_cells.iter().map(|cell| cell.size()).sum();
+        (size > 0).then_some((size, state.last_duration))
+    }
+
+    /// Drops the content of the cells, when the task is not executing, and
+    /// returns the freed memory.
+    pub(crate) fn evict_cells(&self) -> usize {
+        let mut state = self.state.write();
+        if state.state_type != Done {
+            return 0;
+        }
+        state
+            .created_cells
+            .iter_mut()
+            .map(|cell| cell.evict())
+            .sum()
     }
 
     /// For testing purposes
@@ -1145,6 +1226,11 @@ impl Task {
         state.executions = 0;
         state.total_duration = Duration::ZERO;
         state.last_duration = Duration::ZERO;
+        self.cache_hits.store(0, AtomicOrdering::Relaxed);
+    }
+
+    pub(crate) fn record_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, AtomicOrdering::Relaxed);
     }
 
     pub fn get_stats_info(&self, backend: &MemoryBackend) -> TaskStatsInfo {
@@ -1153,6 +1239,7 @@ impl Task {
             total_duration: state.total_duration,
             last_duration: state.last_duration,
             executions: state.executions,
+            cache_hits: self.cache_hits.load(AtomicOrdering::Relaxed),
             root_scoped: matches!(state.scopes, TaskScopes::Root(_)),
             child_scopes: match state.scopes {
                 TaskScopes::Root(_) => 1,
@@ -1525,6 +1612,7 @@ pub struct TaskStatsInfo {
     pub total_duration: Duration,
     pub last_duration: Duration,
     pub executions: u32,
+    pub cache_hits: u32,
     pub root_scoped: bool,
     pub child_scopes: usize,
     pub active: bool,
@@ -0,0 +1,53 @@
+use turbo_tasks::util::FormatDuration;
+
+use super::*;
+use crate::stats::CacheStats;
+
+/// Formats the cache statistics of task functions as a plain text table, e. g.
+/// for printing to the terminal.
+pub fn format_cache_stats(cache_stats: &[(TaskType, CacheStats)]) -> String {
+    let rows: Vec<_> = cache_stats
+        .iter()
+        .map(|(ty, stats)| {
+            [
+                ty.to_string(),
+                stats.hits.to_string(),
+                stats.misses.to_string(),
+                format!("{:.1}%", stats.hit_rate() * 100.0),
+                stats.recomputations.to_string(),
+                FormatDuration(stats.recompute_duration).to_string(),
+            ]
+        })
+        .collect();
+    let header = [
+        "function",
+        "hits",
+        "misses",
+        "hit rate",
+        "recomputations",
+        "recompute time",
+    ];
+    let mut widths = header.map(|title| title.len());
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = max(*width, cell.len());
+        }
+    }
+
+    let mut out = String::new();
+    let mut write_row = |cells: &mut dyn Iterator<Item = &str>| {
+        for (i, (cell, width)) in cells.zip(widths.iter()).enumerate() {
+            if i == 0 {
+                write!(out, "{cell:<width$}").unwrap();
+            } else {
+                write!(out, "  {cell:>width$}").unwrap();
+            }
+        }
+        out.push('\n');
+    };
+    write_row(&mut header.into_iter());
+    for row in rows.iter() {
+        write_row(&mut row.iter().map(|cell| cell.as_str()));
+    }
+    out
+}
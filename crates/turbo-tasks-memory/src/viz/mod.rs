@@ -1,3 +1,4 @@
+pub mod cache;
 pub mod graph;
 pub mod table;
 
@@ -37,6 +38,7 @@ struct MaxValues {
     pub count: usize,
     pub active_count: usize,
     pub updates: usize,
+    pub cache_hits: usize,
     pub roots: usize,
     /// stored as scopes * 100
     pub scopes: usize,
@@ -69,6 +71,7 @@ fn get_max_values_internal(depth: u32, node: &GroupTree) -> MaxValues {
     let mut max_count = 0;
     let mut max_active_count = 0;
     let mut max_updates = 0;
+    let mut max_cache_hits = 0;
     let mut max_roots = 0;
     let mut max_scopes = 0;
     let mut max_dependencies = 0;
@@ -82,6 +85,7 @@ fn get_max_values_internal(depth: u32, node: &GroupTree) -> MaxValues {
         max_count = max(max_count, s.count);
         max_active_count = max(max_active_count, s.active_count);
         max_updates = max(max_updates, s.executions.saturating_sub(s.count));
+        max_cache_hits = max(max_cache_hits, s.cache_hits);
         max_roots = max(max_roots, s.roots);
         max_scopes = max(max_scopes, 100 * s.scopes / s.count);
         max_dependencies = max(max_dependencies, get_avg_dependencies_count_times_100(s));
@@ -104,6 +108,7 @@ fn get_max_values_internal(depth: u32, node: &GroupTree) -> MaxValues {
             count,
             active_count,
             updates,
+            cache_hits,
             roots,
             scopes,
             dependencies,
@@ -117,6 +122,7 @@ fn get_max_values_internal(depth: u32, node: &GroupTree) -> MaxValues {
         max_count = max(max_count, count);
         max_active_count = max(max_active_count, active_count);
         max_updates = max(max_updates, updates);
+        max_cache_hits = max(max_cache_hits, cache_hits);
         max_roots = max(max_roots, roots);
         max_scopes = max(max_scopes, scopes);
         max_dependencies = max(max_dependencies, dependencies);
@@ -131,6 +137,7 @@ fn get_max_values_internal(depth: u32, node: &GroupTree) -> MaxValues {
         count: max_count,
         active_count: max_active_count,
         updates: max_updates,
+        cache_hits: max_cache_hits,
         roots: max_roots,
         scopes: max_scopes,
         dependencies: max_dependencies,
@@ -31,6 +31,7 @@ pub fn create_table(root: GroupTree) -> String {
     out += r#"<th>initial executions</th>"#;
     out += r#"<th>active</th>"#;
     out += r#"<th>reexecutions</th>"#;
+    out += r#"<th>cache hits</th>"#;
     out += r#"<th>total duration</th>"#;
     out += r#"<th>total current duration</th>"#;
     out += r#"<th>total update duration</th>"#;
@@ -81,6 +82,12 @@ pub fn create_table(root: GroupTree) -> String {
             ),
             stats.executions.saturating_sub(stats.count)
         )?;
+        write!(
+            out,
+            "<td bgcolor=\"{}\">{}</td>",
+            as_frac_color(stats.cache_hits, max_values.cache_hits),
+            stats.cache_hits
+        )?;
         write!(
             out,
             "<td bgcolor=\"{}\" data-sort=\"{}\">{}</td>",
@@ -0,0 +1,82 @@
+#![feature(min_specialization)]
+
+use std::{sync::Mutex, time::Duration};
+
+use anyhow::Result;
+use tokio::time::{sleep, timeout};
+use turbo_tasks::{get_invalidator, Invalidator, NothingVc, TurboTasks};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+
+register!();
+
+static INPUT: Mutex<(u32, Option<Invalidator>)> = Mutex::new((0, None));
+/// The inputs of the started and of the completed executions of `double`.
+static STARTED: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+static COMPLETED: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+static PRODUCT: Mutex<u32> = Mutex::new(0);
+
+fn set_input(value: u32) {
+    let mut input = INPUT.lock().unwrap();
+    input.0 = value;
+    if let Some(invalidator) = input.1.take() {
+        invalidator.invalidate();
+    }
+}
+
+#[tokio::test]
+async fn cancels_executions_of_invalidated_tasks() {
+    *REGISTER;
+    set_input(1);
+    let tt = TurboTasks::new(MemoryBackend::new());
+    tt.cancel_outdated_executions(true);
+    let task = tt.spawn_root_task(|| {
+        Box::pin(async {
+            let product = double("input").await?;
+            *PRODUCT.lock().unwrap() = *product;
+            Ok(NothingVc::new().into())
+        })
+    });
+
+    // The execution with the first input never completes on its own
+    timeout(Duration::from_secs(10), async {
+        while !STARTED.lock().unwrap().contains(&1) {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    set_input(2);
+    timeout(Duration::from_secs(10), tt.wait_task_completion(task, true))
+        .await
+        .expect("the outdated execution is cancelled")
+        .unwrap();
+
+    // The task is executed again with the new input, and the cancelled
+    // execution neither completed nor set the output
+    assert_eq!(*STARTED.lock().unwrap(), vec![1, 2]);
+    assert_eq!(*COMPLETED.lock().unwrap(), vec![2]);
+    assert_eq!(*PRODUCT.lock().unwrap(), 4);
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::function]
+fn read_input(_name: &str) -> NumberVc {
+    let mut input = INPUT.lock().unwrap();
+    input.1 = Some(get_invalidator());
+    NumberVc::cell(input.0)
+}
+
+#[turbo_tasks::function]
+async fn double(name: &str) -> Result<NumberVc> {
+    let input = *read_input(name).await?;
+    STARTED.lock().unwrap().push(input);
+    // Waits until the execution is cancelled
+    while input == 1 {
+        sleep(Duration::from_millis(10)).await;
+    }
+    COMPLETED.lock().unwrap().push(input);
+    Ok(NumberVc::cell(input * 2))
+}
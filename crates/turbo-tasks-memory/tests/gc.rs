@@ -0,0 +1,103 @@
+#![feature(min_specialization)]
+
+use std::sync::Mutex;
+
+use anyhow::Result;
+use turbo_tasks::{get_invalidator, HeapSize, Invalidator, NothingVc, TurboTasks};
+use turbo_tasks_memory::{gc, GcPolicy, MemoryBackend};
+use turbo_tasks_testing::register;
+
+register!();
+
+static INPUT: Mutex<(String, Option<Invalidator>)> = Mutex::new((String::new(), None));
+/// The inputs of the executions of `text`.
+static TEXT_EXECUTIONS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+/// The number of executions of `length`, which depends on `text`.
+static LENGTH_EXECUTIONS: Mutex<u32> = Mutex::new(0);
+static LENGTH: Mutex<usize> = Mutex::new(0);
+
+fn set_input(value: &str) {
+    let mut input = INPUT.lock().unwrap();
+    input.0 = value.to_string();
+    if let Some(invalidator) = input.1.take() {
+        invalidator.invalidate();
+    }
+}
+
+#[tokio::test]
+async fn evicts_and_recomputes_cells() {
+    *REGISTER;
+    set_input("a");
+    // Cells are only evicted when garbage is collected explicitly
+    let tt = TurboTasks::new(MemoryBackend::new().with_gc_policy(GcPolicy {
+        memory_limit: usize::MAX,
+        target: 0,
+        estimate_size: gc::heap_size,
+    }));
+    let task = tt.spawn_root_task(|| {
+        Box::pin(async {
+            *LENGTH.lock().unwrap() = *length().await?;
+            Ok(NothingVc::new().into())
+        })
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+    assert_eq!(*LENGTH.lock().unwrap(), 1000);
+
+    // The memory owned by the text is part of the estimate
+    assert!(tt.backend().cell_memory_usage() > 1000);
+    assert!(tt.backend().collect_garbage() > 1000);
+    assert!(tt.backend().cell_memory_usage() < 1000);
+
+    // Reading the evicted cell executes the task again, which assigns the
+    // same content, so the dependent task is not executed again
+    let read = tt.spawn_once_task(async {
+        assert_eq!(text().await?.len(), 1000);
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(read, true).await.unwrap();
+    assert_eq!(*TEXT_EXECUTIONS.lock().unwrap(), vec!["a", "a"]);
+    assert_eq!(*LENGTH_EXECUTIONS.lock().unwrap(), 1);
+    assert!(tt.backend().cell_memory_usage() > 1000);
+
+    // A cell which was evicted again and changed while evicted notifies the
+    // dependent task
+    tt.backend().collect_garbage();
+    set_input("bb");
+    tt.wait_task_completion(task, true).await.unwrap();
+    assert_eq!(*TEXT_EXECUTIONS.lock().unwrap(), vec!["a", "a", "bb"]);
+    assert_eq!(*LENGTH_EXECUTIONS.lock().unwrap(), 2);
+    assert_eq!(*LENGTH.lock().unwrap(), 2000);
+}
+
+#[turbo_tasks::value(transparent, heap_size)]
+struct Text(String);
+
+impl HeapSize for Text {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size()
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+struct Length(usize);
+
+#[turbo_tasks::function]
+fn read_input() -> TextVc {
+    let mut input = INPUT.lock().unwrap();
+    input.1 = Some(get_invalidator());
+    TextVc::cell(input.0.clone())
+}
+
+#[turbo_tasks::function]
+async fn text() -> Result<TextVc> {
+    let input = read_input().await?;
+    TEXT_EXECUTIONS.lock().unwrap().push((*input).clone());
+    Ok(TextVc::cell(input.repeat(1000)))
+}
+
+#[turbo_tasks::function]
+async fn length() -> Result<LengthVc> {
+    let text = text().await?;
+    *LENGTH_EXECUTIONS.lock().unwrap() += 1;
+    Ok(LengthVc::cell(text.len()))
+}
@@ -0,0 +1,109 @@
+#![feature(min_specialization)]
+
+use std::{
+    env::temp_dir,
+    path::PathBuf,
+    process,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use turbo_tasks::{
+    get_invalidator,
+    recording::{read_recording, RecordedEvent, Recorder},
+    Invalidator, NothingVc, TaskId, TurboTasks, Value,
+};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+
+register!();
+
+static INPUT: Mutex<(u32, Option<Invalidator>)> = Mutex::new((0, None));
+static PRODUCT: Mutex<u32> = Mutex::new(0);
+
+/// Changes the input. The invalidation is left to a replay when `invalidate`
+/// is false.
+fn set_input(value: u32, invalidate: bool) {
+    let mut input = INPUT.lock().unwrap();
+    input.0 = value;
+    if invalidate {
+        if let Some(invalidator) = input.1.take() {
+            invalidator.invalidate();
+        }
+    }
+}
+
+fn recording_path(name: &str) -> PathBuf {
+    temp_dir().join(format!("turbo-tasks-{name}-{}.jsonl", process::id()))
+}
+
+/// Starts a build which keeps the product of the input up to date, so
+/// invalidated tasks are executed again.
+async fn start_build(tt: &TurboTasks<MemoryBackend>) -> TaskId {
+    let task = tt.spawn_root_task(|| {
+        Box::pin(async {
+            let product = multiply("input", Value::new(Factor(3))).await?;
+            *PRODUCT.lock().unwrap() = *product;
+            Ok(NothingVc::new().into())
+        })
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+    task
+}
+
+#[tokio::test]
+async fn replay_executes_the_recorded_tasks() {
+    *REGISTER;
+    let recording = recording_path("recording");
+
+    set_input(1, false);
+    let tt = TurboTasks::new(MemoryBackend::new());
+    tt.start_recording(Arc::new(Recorder::create(&recording).unwrap()));
+    let task = start_build(&tt).await;
+    assert_eq!(*PRODUCT.lock().unwrap(), 3);
+    set_input(2, true);
+    tt.wait_task_completion(task, true).await.unwrap();
+    assert_eq!(*PRODUCT.lock().unwrap(), 6);
+    tt.stop_recording().unwrap().flush().unwrap();
+
+    let events = read_recording(&recording).unwrap();
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, RecordedEvent::Invalidated { .. })));
+
+    // The replay starts from the initial build, the input changes without an
+    // invalidation. Invalidating the recorded task must execute the same tasks
+    // again.
+    set_input(1, false);
+    let tt = TurboTasks::new(MemoryBackend::new());
+    tt.start_recording(Arc::new(
+        Recorder::create(&recording_path("replay")).unwrap(),
+    ));
+    start_build(&tt).await;
+    assert_eq!(*PRODUCT.lock().unwrap(), 3);
+    set_input(2, false);
+    let report = tt.replay(events).await.unwrap();
+    assert_eq!(report.steps.len(), 2);
+    assert!(report.steps.iter().all(|step| step.unknown.is_empty()));
+    assert!(report.matches(), "{report}");
+    assert_eq!(*PRODUCT.lock().unwrap(), 6);
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Copy, Hash, PartialOrd, Ord)]
+struct Factor(u32);
+
+#[turbo_tasks::function]
+fn read_input(_name: &str) -> NumberVc {
+    let mut input = INPUT.lock().unwrap();
+    input.1 = Some(get_invalidator());
+    NumberVc::cell(input.0)
+}
+
+#[turbo_tasks::function]
+async fn multiply(name: &str, factor: Value<Factor>) -> Result<NumberVc> {
+    Ok(NumberVc::cell(*read_input(name).await? * factor.0))
+}
@@ -115,6 +115,27 @@ impl RocksDbPersistedGraph {
         })
     }
 
+    /// Opens the database at `path` like [RocksDbPersistedGraph::new], but
+    /// discards it when it was written with another `fingerprint`. The
+    /// fingerprint should cover everything which makes the persisted tasks
+    /// incompatible, e. g. the version of the application and options which
+    /// are not inputs of the tasks.
+    pub fn new_with_fingerprint<P: AsRef<Path>>(path: P, fingerprint: &str) -> Result<Self> {
+        let path = path.as_ref();
+        let fingerprint_path = path.join("FINGERPRINT");
+        match std::fs::read_to_string(&fingerprint_path) {
+            Ok(existing) if existing == fingerprint => {}
+            _ => {
+                if path.exists() {
+                    std::fs::remove_dir_all(path)?;
+                }
+                std::fs::create_dir_all(path)?;
+                std::fs::write(&fingerprint_path, fingerprint)?;
+            }
+        }
+        Self::new(path)
+    }
+
     fn with_task_id_mapping<T>(&self, api: &dyn PersistedGraphApi, func: impl FnOnce() -> T) -> T {
         with_task_id_mapping(&PgApiMapping::new(self, api), func)
     }
@@ -897,3 +918,41 @@ impl<'a> IdMapping<TaskId> for PgApiReadOnlyMapping<'a> {
         new_id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, fs};
+
+    use super::RocksDbPersistedGraph;
+
+    #[test]
+    fn discards_databases_with_another_fingerprint() {
+        let path = temp_dir().join(format!(
+            "turbo-tasks-rocksdb-fingerprint-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&path);
+        let marker = path.join("marker");
+
+        drop(RocksDbPersistedGraph::new_with_fingerprint(&path, "a").unwrap());
+        fs::write(&marker, "").unwrap();
+
+        // The same fingerprint keeps the database
+        drop(RocksDbPersistedGraph::new_with_fingerprint(&path, "a").unwrap());
+        assert!(marker.exists());
+
+        // Another fingerprint deletes it
+        drop(RocksDbPersistedGraph::new_with_fingerprint(&path, "b").unwrap());
+        assert!(!marker.exists());
+        assert_eq!(fs::read_to_string(path.join("FINGERPRINT")).unwrap(), "b");
+
+        // So does a missing fingerprint, e. g. of a database written before
+        // fingerprints were introduced
+        fs::write(&marker, "").unwrap();
+        fs::remove_file(path.join("FINGERPRINT")).unwrap();
+        drop(RocksDbPersistedGraph::new_with_fingerprint(&path, "b").unwrap());
+        assert!(!marker.exists());
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use pin_project_lite::pin_project;
+
+/// Signals that the result of a task execution is no longer needed, as the
+/// task was invalidated while it was executing, e. g. by a newer file change
+/// in watch mode. The execution is aborted at its next await point and the
+/// task is executed again with the new inputs.
+///
+/// Long running synchronous code can check
+/// [is_cancelled](CancellationToken::is_cancelled) of the
+/// [current token](crate::cancellation_token) to stop early.
+#[derive(Default)]
+pub struct CancellationToken {
+    cancelled: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+        if let Some(waker) = self.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
+
+pin_project! {
+    /// Completes with `None` as soon as the token is cancelled, without
+    /// polling the future again.
+    pub(crate) struct CancellableFuture<'a, F: Future> {
+        token: &'a CancellationToken,
+        #[pin]
+        future: F,
+    }
+}
+
+impl<'a, F: Future> CancellableFuture<'a, F> {
+    pub(crate) fn new(token: &'a CancellationToken, future: F) -> Self {
+        Self { token, future }
+    }
+}
+
+impl<'a, F: Future> Future for CancellableFuture<'a, F> {
+    type Output = Option<F::Output>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        if this.token.is_cancelled() {
+            return Poll::Ready(None);
+        }
+        *this.token.waker.lock().unwrap() = Some(cx.waker().clone());
+        // The token might have been cancelled before the waker was stored
+        if this.token.is_cancelled() {
+            return Poll::Ready(None);
+        }
+        this.future.poll(cx).map(Some)
+    }
+}
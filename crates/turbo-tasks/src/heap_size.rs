@@ -0,0 +1,162 @@
+use std::{
+    any::Any,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    mem::size_of,
+    path::PathBuf,
+    sync::Arc,
+};
+
+use indexmap::{IndexMap, IndexSet};
+
+use crate::RawVc;
+
+/// Trait to estimate the memory a value owns on the heap, in addition to its
+/// own size.
+///
+/// The memory usage of cells is tracked with it, e. g. to evict large values
+/// which can be recomputed when the memory usage of a backend exceeds a limit.
+/// Values opt in with `#[turbo_tasks::value(heap_size)]`, the estimate of other
+/// values is their own size.
+///
+/// The estimate doesn't need to be exact, but it should be cheap to compute,
+/// as it's computed whenever a cell is updated.
+pub trait HeapSize {
+    fn heap_size(&self) -> usize;
+}
+
+/// Returns the estimated heap size of a value stored in a cell, which must be
+/// a `T`. This is internally used by `#[turbo_tasks::value(heap_size)]`.
+pub(crate) fn any_heap_size<T: HeapSize + Any>(value: &(dyn Any + Send + Sync)) -> usize {
+    value.downcast_ref::<T>().map_or(0, HeapSize::heap_size)
+}
+
+macro_rules! no_heap {
+  ($ty:ty) => {
+    impl HeapSize for $ty {
+      fn heap_size(&self) -> usize {
+        0
+      }
+    }
+  };
+
+  ($ty:ty, $($tys:ty),+) => {
+    no_heap!($ty);
+    no_heap!($($tys),+);
+  }
+}
+
+no_heap!(i8, u8, i16, u16, i32, u32, i64, u64, char, bool, usize, f32, f64);
+no_heap!((), RawVc);
+
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl HeapSize for PathBuf {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<A: HeapSize, B: HeapSize> HeapSize for (A, B) {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size() + self.1.heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, HeapSize::heap_size)
+    }
+}
+
+impl<T: HeapSize> HeapSize for Box<T> {
+    fn heap_size(&self) -> usize {
+        size_of::<T>() + (**self).heap_size()
+    }
+}
+
+/// The pointee is counted for every reference, as it's not known whether the
+/// other references are tracked.
+impl<T: HeapSize> HeapSize for Arc<T> {
+    fn heap_size(&self) -> usize {
+        size_of::<T>() + (**self).heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+impl<T: HeapSize> HeapSize for HashSet<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+impl<T: HeapSize> HeapSize for IndexSet<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+impl<T: HeapSize> HeapSize for BTreeSet<T> {
+    fn heap_size(&self) -> usize {
+        self.len() * size_of::<T>() + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for HashMap<K, V> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<(K, V)>()
+            + self
+                .iter()
+                .map(|(key, value)| key.heap_size() + value.heap_size())
+                .sum::<usize>()
+    }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for IndexMap<K, V> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<(K, V)>()
+            + self
+                .iter()
+                .map(|(key, value)| key.heap_size() + value.heap_size())
+                .sum::<usize>()
+    }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for BTreeMap<K, V> {
+    fn heap_size(&self) -> usize {
+        self.len() * size_of::<(K, V)>()
+            + self
+                .iter()
+                .map(|(key, value)| key.heap_size() + value.heap_size())
+                .sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, mem::size_of};
+
+    use super::HeapSize;
+
+    #[test]
+    fn counts_owned_memory() {
+        assert_eq!(1u32.heap_size(), 0);
+        assert_eq!(String::with_capacity(10).heap_size(), 10);
+        let strings = vec!["a".repeat(3), "b".repeat(5)];
+        assert_eq!(
+            strings.heap_size(),
+            strings.capacity() * size_of::<String>() + 8
+        );
+        let map = HashMap::from([(1u32, "a".repeat(4))]);
+        assert!(map.heap_size() >= size_of::<(u32, String)>() + 4);
+        assert_eq!(Some(Box::new(7u64)).heap_size(), 8);
+    }
+}
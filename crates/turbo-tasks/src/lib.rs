@@ -35,10 +35,12 @@
 #![feature(arbitrary_enum_discriminant)]
 
 pub mod backend;
+mod cancellation;
 mod collectibles;
 mod completion;
 pub mod debug;
 mod display;
+mod heap_size;
 mod id;
 mod id_factory;
 mod infinite_vec;
@@ -51,8 +53,10 @@ mod nothing;
 mod once_map;
 pub mod persisted_graph;
 pub mod primitives;
+pub mod profiling;
 mod raw_vc;
 mod read_ref;
+pub mod recording;
 pub mod registry;
 mod task_input;
 mod timed_future;
@@ -62,16 +66,20 @@ mod value;
 mod value_type;
 
 pub use anyhow::{Error, Result};
+pub use cancellation::CancellationToken;
 pub use collectibles::CollectiblesSource;
 pub use completion::{Completion, CompletionVc, CompletionsVc};
 pub use display::{ValueToString, ValueToStringVc};
+pub use heap_size::HeapSize;
 pub use id::{
     with_task_id_mapping, without_task_id_mapping, FunctionId, IdMapping, TaskId, TraitTypeId,
     ValueTypeId,
 };
 pub use join_iter_ext::{JoinIterExt, TryJoinIterExt};
+#[cfg(feature = "native")]
+pub use manager::spawn_thread;
 pub use manager::{
-    dynamic_call, emit, get_invalidator, run_once, spawn_blocking, spawn_thread, trait_call,
+    cancellation_token, dynamic_call, emit, get_invalidator, run_once, spawn_blocking, trait_call,
     turbo_tasks, Invalidator, TaskIdProvider, TurboTasks, TurboTasksApi, TurboTasksBackendApi,
     TurboTasksCallApi,
 };
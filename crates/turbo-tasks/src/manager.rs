@@ -1,7 +1,7 @@
 use std::{
     borrow::Cow,
     cell::RefCell,
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fmt::Debug,
     future::Future,
     hash::Hash,
@@ -10,13 +10,12 @@ use std::{
     pin::Pin,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
-        Arc, Mutex, Weak,
+        Arc, Mutex, RwLock, Weak,
     },
-    thread,
     time::{Duration, Instant},
 };
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use event_listener::{Event, EventListener};
 use futures::FutureExt;
 use serde::{de::Visitor, Deserialize, Serialize};
@@ -24,9 +23,12 @@ use tokio::{runtime::Handle, select, task_local};
 
 use crate::{
     backend::{Backend, CellContent, CellMappings, PersistentTaskType, TransientTaskType},
+    cancellation::{CancellableFuture, CancellationToken},
     id::{BackendJobId, FunctionId, TraitTypeId},
     id_factory::IdFactory,
+    profiling::{ProfiledFuture, Profiler},
     raw_vc::RawVc,
+    recording::{split_steps, RecordedEvent, Recorder, ReplayReport, ReplayStep},
     task_input::{SharedReference, SharedValue, TaskInput},
     timed_future::{self, TimedFuture},
     trace::TraceRawVcs,
@@ -146,6 +148,15 @@ pub trait TurboTasksBackendApi: TaskIdProvider + TurboTasksCallApi + Sync + Send
     /// Enqueues tasks for notification of changed dependencies. This will
     /// eventually call `invalidate_tasks()` on all tasks.
     fn schedule_notify_tasks_set(&self, tasks: &HashSet<TaskId>);
+
+    /// Aborts the current execution of the task when cancellation of
+    /// outdated executions is enabled. The backend must only call this when
+    /// the result is discarded anyway, as the task was invalidated. The
+    /// aborted execution gets no `task_execution_result`, and
+    /// `task_execution_completed` gets the cell mappings the execution
+    /// started with. The dependencies read by the aborted execution need to
+    /// be dropped like the ones of any execution of an invalidated task.
+    fn cancel_task_execution(&self, task: TaskId);
 }
 
 impl TaskIdProvider for &dyn TurboTasksBackendApi {
@@ -182,6 +193,19 @@ pub struct TurboTasks<B: Backend + 'static> {
     event: Event,
     event_foreground: Event,
     event_background: Event,
+    /// Set while invalidations and task executions are recorded, see
+    /// [recording](crate::recording).
+    recorder: RwLock<Option<Arc<Recorder>>>,
+    recording: AtomicBool,
+    /// Set while task executions are profiled, see
+    /// [profiling](crate::profiling).
+    profiler: RwLock<Option<Arc<Profiler>>>,
+    profiling: AtomicBool,
+    /// Whether executions of invalidated tasks are aborted, see
+    /// [CancellationToken].
+    cancel_outdated: AtomicBool,
+    /// The tokens of the in-flight executions while `cancel_outdated` is set.
+    executions: Mutex<HashMap<TaskId, Arc<CancellationToken>>>,
 }
 
 // TODO implement our own thread pool and make these thread locals instead
@@ -193,6 +217,9 @@ task_local! {
 
     static CURRENT_TASK_ID: TaskId;
 
+    /// The [CancellationToken] of the current task execution
+    static CANCELLATION_TOKEN: Arc<CancellationToken>;
+
     /// Affected [Task]s, that are tracked during task execution
     /// These tasks will be invalidated when the execution finishes
     /// or before reading a cell value
@@ -222,6 +249,12 @@ impl<B: Backend> TurboTasks<B> {
             event: Event::new(),
             event_foreground: Event::new(),
             event_background: Event::new(),
+            recorder: RwLock::new(None),
+            recording: AtomicBool::new(false),
+            profiler: RwLock::new(None),
+            profiling: AtomicBool::new(false),
+            cancel_outdated: AtomicBool::new(false),
+            executions: Mutex::new(HashMap::new()),
         });
         this.backend.startup(&*this);
         this
@@ -242,6 +275,9 @@ impl<B: Backend> TurboTasks<B> {
         let id = self
             .backend
             .create_transient_task(TransientTaskType::Root(Box::new(functor)), self);
+        if let Some(profiler) = self.profiler() {
+            profiler.register_transient_task(id, "root");
+        }
         self.schedule(id);
         id
     }
@@ -256,6 +292,9 @@ impl<B: Backend> TurboTasks<B> {
         let id = self
             .backend
             .create_transient_task(TransientTaskType::Once(Box::pin(future)), self);
+        if let Some(profiler) = self.profiler() {
+            profiler.register_transient_task(id, "once");
+        }
         self.schedule(id);
         id
     }
@@ -278,14 +317,124 @@ impl<B: Backend> TurboTasks<B> {
         Ok(rx.await?)
     }
 
+    /// Records the invalidations and task executions to `recorder` from now
+    /// on. Tasks created before can't be identified in the recording.
+    pub fn start_recording(&self, recorder: Arc<Recorder>) {
+        *self.recorder.write().unwrap() = Some(recorder);
+        self.recording.store(true, Ordering::Release);
+    }
+
+    pub fn stop_recording(&self) -> Option<Arc<Recorder>> {
+        self.recording.store(false, Ordering::Release);
+        self.recorder.write().unwrap().take()
+    }
+
+    fn recorder(&self) -> Option<Arc<Recorder>> {
+        if !self.recording.load(Ordering::Acquire) {
+            return None;
+        }
+        self.recorder.read().unwrap().clone()
+    }
+
+    /// Profiles the task executions with `profiler` from now on. Tasks
+    /// created before are named "unknown" in the profile.
+    pub fn start_profiling(&self, profiler: Arc<Profiler>) {
+        *self.profiler.write().unwrap() = Some(profiler);
+        self.profiling.store(true, Ordering::Release);
+    }
+
+    pub fn stop_profiling(&self) -> Option<Arc<Profiler>> {
+        self.profiling.store(false, Ordering::Release);
+        self.profiler.write().unwrap().take()
+    }
+
+    /// Aborts in-flight executions of tasks when they are invalidated, e. g.
+    /// by a newer file change in watch mode, instead of completing them
+    /// before executing the task again. Code which isn't cancellation safe,
+    /// e. g. writing multiple files which need to be consistent, should not
+    /// await in between when this is enabled.
+    pub fn cancel_outdated_executions(&self, cancel: bool) {
+        self.cancel_outdated.store(cancel, Ordering::Release);
+    }
+
+    fn profiler(&self) -> Option<Arc<Profiler>> {
+        if !self.profiling.load(Ordering::Acquire) {
+            return None;
+        }
+        self.profiler.read().unwrap().clone()
+    }
+
+    fn get_or_create_persistent_task(&self, task_type: PersistentTaskType) -> TaskId {
+        let parent_task = current_task("turbo_function calls");
+        let recorder = self.recorder();
+        let profiler = self.profiler();
+        if recorder.is_none() && profiler.is_none() {
+            return self
+                .backend
+                .get_or_create_persistent_task(task_type, parent_task, self);
+        }
+        let id = self
+            .backend
+            .get_or_create_persistent_task(task_type.clone(), parent_task, self);
+        if let Some(recorder) = recorder {
+            recorder.register_task(id, &task_type);
+        }
+        if let Some(profiler) = profiler {
+            profiler.register_task(id, &task_type);
+        }
+        id
+    }
+
+    /// Replays the invalidations of a recording in the order they happened,
+    /// each batch after the tasks executed before have settled, and compares
+    /// which tasks are executed with the recording. Recording must have been
+    /// started before the build to identify the tasks.
+    pub async fn replay(&self, events: Vec<RecordedEvent>) -> Result<ReplayReport> {
+        let recorder = self
+            .recorder()
+            .context("replaying requires the replay to be recorded")?;
+        let mut report = ReplayReport::default();
+        for step in split_steps(events) {
+            let mut replayed = ReplayStep::default();
+            for task in step.invalidated {
+                let ids = recorder.task_ids(&task);
+                if ids.is_empty() {
+                    replayed.unknown.push(task.clone());
+                }
+                for id in ids {
+                    TurboTasksApi::invalidate(self, id);
+                }
+                replayed.invalidated.push(task);
+            }
+            self.wait_idle().await;
+            let executed = recorder.take_executed();
+            replayed.missing = step.executed.difference(&executed).cloned().collect();
+            replayed.missing.sort();
+            replayed.additional = executed.difference(&step.executed).cloned().collect();
+            replayed.additional.sort();
+            report.steps.push(replayed);
+        }
+        recorder.flush()?;
+        Ok(report)
+    }
+
+    /// Waits until no tasks are scheduled anymore.
+    async fn wait_idle(&self) {
+        loop {
+            let listener = self.event.listen();
+            if self.currently_scheduled_tasks.load(Ordering::Acquire) == 0 {
+                return;
+            }
+            listener.await;
+        }
+    }
+
     /// Call a native function with arguments.
     /// All inputs must be resolved.
     pub(crate) fn native_call(&self, func: FunctionId, inputs: Vec<TaskInput>) -> RawVc {
-        RawVc::TaskOutput(self.backend.get_or_create_persistent_task(
-            PersistentTaskType::Native(func, inputs),
-            current_task("turbo_function calls"),
-            self,
-        ))
+        RawVc::TaskOutput(
+            self.get_or_create_persistent_task(PersistentTaskType::Native(func, inputs)),
+        )
     }
 
     /// Calls a native function with arguments. Resolves arguments when needed
@@ -294,11 +443,9 @@ impl<B: Backend> TurboTasks<B> {
         if inputs.iter().all(|i| i.is_resolved() && !i.is_nothing()) {
             self.native_call(func, inputs)
         } else {
-            RawVc::TaskOutput(self.backend.get_or_create_persistent_task(
-                PersistentTaskType::ResolveNative(func, inputs),
-                current_task("turbo_function calls"),
-                self,
-            ))
+            RawVc::TaskOutput(
+                self.get_or_create_persistent_task(PersistentTaskType::ResolveNative(func, inputs)),
+            )
         }
     }
 
@@ -310,11 +457,13 @@ impl<B: Backend> TurboTasks<B> {
         trait_fn_name: Cow<'static, str>,
         inputs: Vec<TaskInput>,
     ) -> RawVc {
-        RawVc::TaskOutput(self.backend.get_or_create_persistent_task(
-            PersistentTaskType::ResolveTrait(trait_type, trait_fn_name, inputs),
-            current_task("turbo_function calls"),
-            self,
-        ))
+        RawVc::TaskOutput(
+            self.get_or_create_persistent_task(PersistentTaskType::ResolveTrait(
+                trait_type,
+                trait_fn_name,
+                inputs,
+            )),
+        )
     }
 
     pub(crate) fn schedule(&self, task_id: TaskId) {
@@ -331,15 +480,45 @@ impl<B: Backend> TurboTasks<B> {
                     break;
                 }
                 if let Some(execution) = this.backend.try_start_task_execution(task_id, &*this) {
+                    if let Some(recorder) = this.recorder() {
+                        if let Some(task) = recorder.task(task_id) {
+                            recorder.record(RecordedEvent::Executed { task });
+                        }
+                    }
                     // Setup thread locals
                     let has_cell_mappings = execution.cell_mappings.is_some();
 
                     let cell_mappings = RefCell::new(execution.cell_mappings.unwrap_or_default());
+                    let profiler = this.profiler();
+                    let token = Arc::new(CancellationToken::new());
+                    let cancellable = this.cancel_outdated.load(Ordering::Acquire);
+                    // The cells of the previous execution, which are kept when this one is
+                    // cancelled
+                    let mut previous_cell_mappings = None;
+                    if cancellable {
+                        this.executions
+                            .lock()
+                            .unwrap()
+                            .insert(task_id, token.clone());
+                        if has_cell_mappings {
+                            previous_cell_mappings = Some(cell_mappings.borrow().clone());
+                        }
+                    }
                     let (result, duration, cell_mappings) = PREVIOUS_CELLS
                         .scope(cell_mappings, async {
-                            let (result, duration) =
-                                TimedFuture::new(AssertUnwindSafe(execution.future).catch_unwind())
-                                    .await;
+                            let (result, duration) = CANCELLATION_TOKEN
+                                .scope(
+                                    token.clone(),
+                                    TimedFuture::new(ProfiledFuture::new(
+                                        profiler.clone(),
+                                        task_id,
+                                        CancellableFuture::new(
+                                            &token,
+                                            AssertUnwindSafe(execution.future).catch_unwind(),
+                                        ),
+                                    )),
+                                )
+                                .await;
                             let cell_mappings = if has_cell_mappings {
                                 Some(PREVIOUS_CELLS.with(|s| take(&mut *s.borrow_mut())))
                             } else {
@@ -355,14 +534,29 @@ impl<B: Backend> TurboTasks<B> {
                             FormatDuration(duration)
                         )
                     }
-                    let result = result.map_err(|any| match any.downcast::<String>() {
-                        Ok(owned) => Some(Cow::Owned(*owned)),
-                        Err(any) => match any.downcast::<&'static str>() {
-                            Ok(str) => Some(Cow::Borrowed(*str)),
-                            Err(_) => None,
-                        },
-                    });
-                    this.backend.task_execution_result(task_id, result, &*this);
+                    if let Some(profiler) = profiler {
+                        profiler.record_execution(task_id, duration);
+                    }
+                    if cancellable {
+                        this.executions.lock().unwrap().remove(&task_id);
+                    }
+                    let cell_mappings = match result {
+                        Some(result) => {
+                            let result = result.map_err(|any| match any.downcast::<String>() {
+                                Ok(owned) => Some(Cow::Owned(*owned)),
+                                Err(any) => match any.downcast::<&'static str>() {
+                                    Ok(str) => Some(Cow::Borrowed(*str)),
+                                    Err(_) => None,
+                                },
+                            });
+                            this.backend.task_execution_result(task_id, result, &*this);
+                            cell_mappings
+                        }
+                        // The cancelled execution stopped part of the way, so neither its
+                        // output nor the cells it allocated are kept. The task was
+                        // invalidated, so the backend executes it again.
+                        None => previous_cell_mappings,
+                    };
                     this.notify_scheduled_tasks_internal();
                     let reexecute = this.backend.task_execution_completed(
                         task_id,
@@ -640,6 +834,11 @@ impl<B: Backend> TurboTasksCallApi for TurboTasks<B> {
 
 impl<B: Backend> TurboTasksApi for TurboTasks<B> {
     fn invalidate(&self, task: TaskId) {
+        if let Some(recorder) = self.recorder() {
+            if let Some(recorded) = recorder.task(task) {
+                recorder.record(RecordedEvent::Invalidated { task: recorded });
+            }
+        }
         self.backend.invalidate_task(task, self);
     }
 
@@ -777,6 +976,15 @@ impl<B: Backend> TurboTasksBackendApi for TurboTasks<B> {
         })
     }
 
+    fn cancel_task_execution(&self, task: TaskId) {
+        if !self.cancel_outdated.load(Ordering::Acquire) {
+            return;
+        }
+        if let Some(token) = self.executions.lock().unwrap().get(&task) {
+            token.cancel();
+        }
+    }
+
     fn try_foreground_done(&self) -> Result<(), EventListener> {
         if self
             .currently_scheduled_foreground_jobs
@@ -963,6 +1171,12 @@ pub fn turbo_tasks() -> Arc<dyn TurboTasksApi> {
     TURBO_TASKS.with(|arc| arc.clone())
 }
 
+/// The [CancellationToken] of the current task execution. Panics when called
+/// outside of a task execution.
+pub fn cancellation_token() -> Arc<CancellationToken> {
+    CANCELLATION_TOKEN.with(|token| token.clone())
+}
+
 pub fn with_turbo_tasks<T>(func: impl FnOnce(&Arc<dyn TurboTasksApi>) -> T) -> T {
     TURBO_TASKS.with(|arc| func(arc))
 }
@@ -997,6 +1211,7 @@ pub fn emit<T: ValueTraitVc>(collectible: T) {
     with_turbo_tasks(|tt| tt.emit_collectible(T::get_trait_type_id(), collectible.into()))
 }
 
+#[cfg(feature = "native")]
 pub async fn spawn_blocking<T: Send + 'static>(func: impl FnOnce() -> T + Send + 'static) -> T {
     let (r, d) = tokio::task::spawn_blocking(|| {
         let start = Instant::now();
@@ -1009,9 +1224,20 @@ pub async fn spawn_blocking<T: Send + 'static>(func: impl FnOnce() -> T + Send +
     r
 }
 
+/// Without the `native` feature there are no threads for blocking tasks, e. g.
+/// on wasm32 targets, so `func` is called on the current one.
+#[cfg(not(feature = "native"))]
+pub async fn spawn_blocking<T: Send + 'static>(func: impl FnOnce() -> T + Send + 'static) -> T {
+    let start = Instant::now();
+    let r = func();
+    timed_future::add_duration(start.elapsed());
+    r
+}
+
+#[cfg(feature = "native")]
 pub fn spawn_thread(func: impl FnOnce() + Send + 'static) {
     let handle = Handle::current();
-    thread::spawn(move || {
+    std::thread::spawn(move || {
         let guard = handle.enter();
         func();
         drop(guard);
@@ -0,0 +1,217 @@
+//! Profiling of task executions, to find out which task functions dominate
+//! the build time.
+//!
+//! Every poll of a task execution is recorded as a span on the worker thread
+//! it happened on. As polls on a thread never overlap, the spans can be
+//! exported as a timeline in the chrome tracing format, which can be opened in
+//! chrome://tracing, Perfetto or speedscope. The executions are aggregated by
+//! task function in [Profiler::function_profiles].
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use pin_project_lite::pin_project;
+use serde_json::json;
+
+use crate::{backend::PersistentTaskType, registry, TaskId};
+
+/// A poll of a task execution.
+struct Span {
+    task: TaskId,
+    thread: usize,
+    start: Duration,
+    duration: Duration,
+}
+
+/// The aggregated executions of a task function.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FunctionProfile {
+    pub name: String,
+    pub executions: usize,
+    /// The time spent in the executions, without the time waiting for other
+    /// tasks.
+    pub total_duration: Duration,
+    pub max_duration: Duration,
+}
+
+/// Records the executions of tasks, see [profiling](crate::profiling).
+pub struct Profiler {
+    start: Instant,
+    tasks: Mutex<HashMap<TaskId, String>>,
+    spans: Mutex<Vec<Span>>,
+    executions: Mutex<Vec<(TaskId, Duration)>>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            tasks: Mutex::new(HashMap::new()),
+            spans: Mutex::new(Vec::new()),
+            executions: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn register_task(&self, id: TaskId, ty: &PersistentTaskType) {
+        let mut tasks = self.tasks.lock().unwrap();
+        if tasks.contains_key(&id) {
+            return;
+        }
+        let name = match ty {
+            PersistentTaskType::Native(function, _) => {
+                registry::get_function(*function).name.clone()
+            }
+            PersistentTaskType::ResolveNative(function, _) => {
+                format!("resolve {}", registry::get_function(*function).name)
+            }
+            PersistentTaskType::ResolveTrait(trait_type, name, _) => {
+                format!(
+                    "resolve trait {}::{}",
+                    registry::get_trait(*trait_type).name,
+                    name
+                )
+            }
+        };
+        tasks.insert(id, name);
+    }
+
+    pub(crate) fn register_transient_task(&self, id: TaskId, name: &str) {
+        self.tasks.lock().unwrap().insert(id, name.to_string());
+    }
+
+    fn name(tasks: &HashMap<TaskId, String>, task: TaskId) -> &str {
+        tasks.get(&task).map_or("unknown", |name| name.as_str())
+    }
+
+    fn record_span(&self, task: TaskId, start: Instant, duration: Duration) {
+        thread_local! {
+            static THREAD: usize = {
+                static NEXT_THREAD: AtomicUsize = AtomicUsize::new(0);
+                NEXT_THREAD.fetch_add(1, Ordering::Relaxed)
+            };
+        }
+        let span = Span {
+            task,
+            thread: THREAD.with(|thread| *thread),
+            start: start.saturating_duration_since(self.start),
+            duration,
+        };
+        self.spans.lock().unwrap().push(span);
+    }
+
+    pub(crate) fn record_execution(&self, task: TaskId, duration: Duration) {
+        self.executions.lock().unwrap().push((task, duration));
+    }
+
+    /// The executions aggregated by task function, the longest total
+    /// duration first.
+    pub fn function_profiles(&self) -> Vec<FunctionProfile> {
+        let tasks = self.tasks.lock().unwrap();
+        let mut profiles = HashMap::<&str, FunctionProfile>::new();
+        for &(task, duration) in self.executions.lock().unwrap().iter() {
+            let name = Self::name(&tasks, task);
+            let profile = profiles.entry(name).or_insert_with(|| FunctionProfile {
+                name: name.to_string(),
+                ..Default::default()
+            });
+            profile.executions += 1;
+            profile.total_duration += duration;
+            profile.max_duration = profile.max_duration.max(duration);
+        }
+        let mut profiles = profiles.into_values().collect::<Vec<_>>();
+        profiles.sort_by(|a, b| {
+            b.total_duration
+                .cmp(&a.total_duration)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        profiles
+    }
+
+    /// Exports the recorded polls in the chrome tracing format, with a track
+    /// for every worker thread.
+    pub fn chrome_trace(&self) -> String {
+        let tasks = self.tasks.lock().unwrap();
+        let spans = self.spans.lock().unwrap();
+        let mut threads = spans.iter().map(|span| span.thread).collect::<Vec<_>>();
+        threads.sort_unstable();
+        threads.dedup();
+        let thread_names = threads.into_iter().map(|thread| {
+            json!({
+                "name": "thread_name",
+                "ph": "M",
+                "pid": 1,
+                "tid": thread,
+                "args": { "name": format!("worker {thread}") },
+            })
+        });
+        let events = spans.iter().map(|span| {
+            json!({
+                "name": Self::name(&tasks, span.task),
+                "cat": "task",
+                "ph": "X",
+                "ts": span.start.as_secs_f64() * 1_000_000.0,
+                "dur": span.duration.as_secs_f64() * 1_000_000.0,
+                "pid": 1,
+                "tid": span.thread,
+                "args": { "task": *span.task },
+            })
+        });
+        json!({
+            "traceEvents": thread_names.chain(events).collect::<Vec<_>>(),
+            "displayTimeUnit": "ms",
+        })
+        .to_string()
+    }
+}
+
+pin_project! {
+    /// Records every poll of a task execution to a [Profiler], if any.
+    pub(crate) struct ProfiledFuture<F: Future> {
+        profiler: Option<Arc<Profiler>>,
+        task: TaskId,
+        #[pin]
+        future: F,
+    }
+}
+
+impl<F: Future> ProfiledFuture<F> {
+    pub(crate) fn new(profiler: Option<Arc<Profiler>>, task: TaskId, future: F) -> Self {
+        Self {
+            profiler,
+            task,
+            future,
+        }
+    }
+}
+
+impl<F: Future> Future for ProfiledFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        match this.profiler {
+            Some(profiler) => {
+                let start = Instant::now();
+                let result = this.future.poll(cx);
+                profiler.record_span(*this.task, start, start.elapsed());
+                result
+            }
+            None => this.future.poll(cx),
+        }
+    }
+}
@@ -0,0 +1,328 @@
+//! Recording of the invalidations and task executions of incremental builds,
+//! which can be replayed to reproduce bugs like stale outputs or missed
+//! invalidations.
+//!
+//! Tasks are identified by their function and a hash of their inputs, as the
+//! [TaskId]s differ between runs. Inputs which are Vcs hash the identity of
+//! the task they belong to instead, other inputs hash their serialization.
+//! Values which can't be serialized only hash their type, so tasks which only
+//! differ in such inputs can't be told apart.
+//!
+//! A recording is a file with one JSON [RecordedEvent] per line. Only
+//! invalidations coming from outside of turbo-tasks are recorded, e. g. by a
+//! file watcher, as the invalidations of dependent tasks follow from them.
+//! [TurboTasks::replay](crate::TurboTasks::replay) applies them to another
+//! run and compares which tasks are executed.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display},
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    mem::take,
+    path::Path,
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use turbo_tasks_hash::{DeterministicHasher, Xxh3Hash64Hasher};
+
+use crate::{backend::PersistentTaskType, registry, TaskId, TaskInput};
+
+/// The identity of a task, which is the same in every run.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct RecordedTask {
+    pub name: String,
+    pub inputs_hash: u64,
+}
+
+impl Display for RecordedTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:016x})", self.name, self.inputs_hash)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    /// The task was invalidated from outside of turbo-tasks.
+    Invalidated { task: RecordedTask },
+    /// The task was executed.
+    Executed { task: RecordedTask },
+}
+
+/// Writes the events of a run to a recording.
+pub struct Recorder {
+    tasks: Mutex<HashMap<TaskId, RecordedTask>>,
+    output: Mutex<BufWriter<File>>,
+    /// The tasks executed since [Recorder::take_executed] was called last.
+    executed: Mutex<HashSet<RecordedTask>>,
+}
+
+impl Recorder {
+    /// Creates a recorder writing to the file at `path`, which is truncated.
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("recording {} can't be created", path.display()))?;
+        Ok(Self {
+            tasks: Mutex::new(HashMap::new()),
+            output: Mutex::new(BufWriter::new(file)),
+            executed: Mutex::new(HashSet::new()),
+        })
+    }
+
+    pub(crate) fn register_task(&self, id: TaskId, ty: &PersistentTaskType) {
+        let mut tasks = self.tasks.lock().unwrap();
+        if tasks.contains_key(&id) {
+            return;
+        }
+        let (name, inputs) = match ty {
+            PersistentTaskType::Native(function, inputs) => (
+                registry::get_function_global_name(*function).to_string(),
+                inputs,
+            ),
+            PersistentTaskType::ResolveNative(function, inputs) => (
+                format!(
+                    "[resolve] {}",
+                    registry::get_function_global_name(*function)
+                ),
+                inputs,
+            ),
+            PersistentTaskType::ResolveTrait(trait_type, name, inputs) => (
+                format!(
+                    "[resolve trait] {} in trait {}",
+                    name,
+                    registry::get_trait(*trait_type).name
+                ),
+                inputs,
+            ),
+        };
+        let mut hasher = Xxh3Hash64Hasher::new();
+        for input in inputs.iter() {
+            hash_input(input, &tasks, &mut hasher);
+        }
+        let task = RecordedTask {
+            name,
+            inputs_hash: hasher.finish(),
+        };
+        tasks.insert(id, task);
+    }
+
+    pub(crate) fn task(&self, id: TaskId) -> Option<RecordedTask> {
+        self.tasks.lock().unwrap().get(&id).cloned()
+    }
+
+    /// The tasks with the identity of `task` in this run.
+    pub(crate) fn task_ids(&self, task: &RecordedTask) -> Vec<TaskId> {
+        self.tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, recorded)| *recorded == task)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    pub(crate) fn record(&self, event: RecordedEvent) {
+        if let RecordedEvent::Executed { task } = &event {
+            self.executed.lock().unwrap().insert(task.clone());
+        }
+        let mut output = self.output.lock().unwrap();
+        // Recording is best effort, a failing write must not fail the build
+        if serde_json::to_writer(&mut *output, &event).is_ok() {
+            let _ = output.write_all(b"\n");
+        }
+    }
+
+    pub(crate) fn take_executed(&self) -> HashSet<RecordedTask> {
+        take(&mut *self.executed.lock().unwrap())
+    }
+
+    pub fn flush(&self) -> Result<()> {
+        self.output.lock().unwrap().flush()?;
+        Ok(())
+    }
+}
+
+fn hash_input(
+    input: &TaskInput,
+    tasks: &HashMap<TaskId, RecordedTask>,
+    hasher: &mut Xxh3Hash64Hasher,
+) {
+    match input {
+        TaskInput::TaskOutput(id) => {
+            hasher.write_u8(0);
+            hash_task(tasks.get(id), hasher);
+        }
+        TaskInput::TaskCell(id, index) => {
+            hasher.write_u8(1);
+            hash_task(tasks.get(id), hasher);
+            hasher.write_usize(*index);
+        }
+        TaskInput::List(list) => {
+            hasher.write_u8(2);
+            hasher.write_usize(list.len());
+            for input in list.iter() {
+                hash_input(input, tasks, hasher);
+            }
+        }
+        input => {
+            hasher.write_u8(3);
+            match serde_json::to_vec(input) {
+                Ok(bytes) => hasher.write_bytes(&bytes),
+                Err(_) => hasher.write_bytes(input.to_string().as_bytes()),
+            }
+        }
+    }
+}
+
+fn hash_task(task: Option<&RecordedTask>, hasher: &mut Xxh3Hash64Hasher) {
+    match task {
+        Some(task) => {
+            hasher.write_u8(1);
+            hasher.write_bytes(task.name.as_bytes());
+            hasher.write_bytes(&task.inputs_hash.to_le_bytes());
+        }
+        None => hasher.write_u8(0),
+    }
+}
+
+/// Reads the events of a recording written by a [Recorder].
+pub fn read_recording(path: &Path) -> Result<Vec<RecordedEvent>> {
+    let file = File::open(path)
+        .with_context(|| format!("recording {} can't be opened", path.display()))?;
+    let mut events = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        events.push(serde_json::from_str(&line).with_context(|| {
+            format!(
+                "line {} of recording {} is not an event",
+                index + 1,
+                path.display()
+            )
+        })?);
+    }
+    Ok(events)
+}
+
+/// A batch of invalidations of a recording with the tasks executed because
+/// of them.
+pub(crate) struct RecordedStep {
+    pub invalidated: Vec<RecordedTask>,
+    pub executed: HashSet<RecordedTask>,
+}
+
+/// Splits the events into steps. The first step is the initial build, which
+/// isn't caused by invalidations.
+pub(crate) fn split_steps(events: Vec<RecordedEvent>) -> Vec<RecordedStep> {
+    let mut steps = vec![RecordedStep {
+        invalidated: Vec::new(),
+        executed: HashSet::new(),
+    }];
+    for event in events {
+        let step = steps.last_mut().unwrap();
+        match event {
+            RecordedEvent::Invalidated { task } => {
+                if step.executed.is_empty() && !step.invalidated.is_empty() {
+                    step.invalidated.push(task);
+                } else {
+                    steps.push(RecordedStep {
+                        invalidated: vec![task],
+                        executed: HashSet::new(),
+                    });
+                }
+            }
+            RecordedEvent::Executed { task } => {
+                step.executed.insert(task);
+            }
+        }
+    }
+    steps
+}
+
+/// How the executions of a replayed step differ from the recording.
+#[derive(Debug, Default)]
+pub struct ReplayStep {
+    pub invalidated: Vec<RecordedTask>,
+    /// Invalidated tasks which don't exist in the replay, e. g. because the
+    /// checkout differs.
+    pub unknown: Vec<RecordedTask>,
+    /// Tasks which were executed in the recording, but not in the replay.
+    pub missing: Vec<RecordedTask>,
+    /// Tasks which were executed in the replay, but not in the recording.
+    pub additional: Vec<RecordedTask>,
+}
+
+/// The result of [TurboTasks::replay](crate::TurboTasks::replay).
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    pub steps: Vec<ReplayStep>,
+}
+
+impl ReplayReport {
+    /// Whether the replay executed the same tasks as the recording.
+    pub fn matches(&self) -> bool {
+        self.steps
+            .iter()
+            .all(|step| step.missing.is_empty() && step.additional.is_empty())
+    }
+}
+
+impl Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, step) in self.steps.iter().enumerate() {
+            writeln!(
+                f,
+                "step {}: {} invalidated, {} unknown, {} missing, {} additional",
+                index + 1,
+                step.invalidated.len(),
+                step.unknown.len(),
+                step.missing.len(),
+                step.additional.len()
+            )?;
+            for task in step.unknown.iter() {
+                writeln!(f, "  unknown {task}")?;
+            }
+            for task in step.missing.iter() {
+                writeln!(f, "  missing {task}")?;
+            }
+            for task in step.additional.iter() {
+                writeln!(f, "  additional {task}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_steps, RecordedEvent, RecordedTask};
+
+    fn task(name: &str) -> RecordedTask {
+        RecordedTask {
+            name: name.to_string(),
+            inputs_hash: 0,
+        }
+    }
+
+    #[test]
+    fn splits_steps_at_invalidations() {
+        let steps = split_steps(vec![
+            RecordedEvent::Executed { task: task("a") },
+            RecordedEvent::Invalidated { task: task("b") },
+            RecordedEvent::Invalidated { task: task("c") },
+            RecordedEvent::Executed { task: task("b") },
+            RecordedEvent::Invalidated { task: task("d") },
+        ]);
+        assert_eq!(steps.len(), 3);
+        assert!(steps[0].invalidated.is_empty());
+        assert!(steps[0].executed.contains(&task("a")));
+        assert_eq!(steps[1].invalidated, vec![task("b"), task("c")]);
+        assert!(steps[1].executed.contains(&task("b")));
+        assert!(steps[2].executed.is_empty());
+    }
+}
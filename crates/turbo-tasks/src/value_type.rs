@@ -9,6 +9,7 @@ use std::{
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    heap_size::{any_heap_size, HeapSize},
     id::{FunctionId, TraitTypeId},
     magic_any::{AnyDeserializeSeed, MagicAny, MagicAnyDeserializeSeed},
     registry::{register_trait_type, register_value_type},
@@ -58,6 +59,7 @@ pub trait TypedForInput: Typed {}
 
 type MagicSerializationFn = fn(&dyn MagicAny) -> &dyn erased_serde::Serialize;
 type AnySerializationFn = fn(&(dyn Any + Sync + Send)) -> &dyn erased_serde::Serialize;
+type HeapSizeFn = fn(&(dyn Any + Sync + Send)) -> usize;
 
 // TODO this type need some refactoring when multiple languages are added to
 // turbo-task In this case a trait_method might be of a different function type.
@@ -79,6 +81,9 @@ pub struct ValueType {
     /// Functors for serialization
     magic_serialization: Option<(MagicSerializationFn, MagicAnyDeserializeSeed)>,
     any_serialization: Option<(AnySerializationFn, AnyDeserializeSeed)>,
+
+    /// Functor to estimate the heap size, see [HeapSize]
+    heap_size: Option<HeapSizeFn>,
 }
 
 impl Hash for ValueType {
@@ -138,6 +143,7 @@ impl ValueType {
             trait_methods: HashMap::new(),
             magic_serialization: None,
             any_serialization: None,
+            heap_size: None,
         }
     }
 
@@ -154,6 +160,7 @@ impl ValueType {
                 MagicAnyDeserializeSeed::new::<T>(),
             )),
             any_serialization: Some((any_as_serialize::<T>, AnyDeserializeSeed::new::<T>())),
+            heap_size: None,
         }
     }
 
@@ -167,15 +174,27 @@ impl ValueType {
             trait_methods: HashMap::new(),
             magic_serialization: None,
             any_serialization: Some((any_as_serialize::<T>, AnyDeserializeSeed::new::<T>())),
+            heap_size: None,
         }
     }
 
+    /// This is internally used by `#[turbo_tasks::value(heap_size)]`
+    pub fn register_heap_size<T: HeapSize + Any>(&mut self) {
+        self.heap_size = Some(any_heap_size::<T>);
+    }
+
+    /// The estimated heap size of a value of this type, when the type
+    /// implements [HeapSize].
+    pub fn heap_size(&self, value: &(dyn Any + Sync + Send)) -> Option<usize> {
+        self.heap_size.map(|heap_size| heap_size(value))
+    }
+
     pub fn magic_as_serializable<'a>(
         &self,
         arc: &'a Arc<dyn MagicAny>,
     ) -> Option<&'a dyn erased_serde::Serialize> {
         if let Some(s) = self.magic_serialization {
-            let r: &dyn MagicAny = arc;
+            let r: &dyn MagicAny = &**arc;
             Some((s.0)(r))
         } else {
             None
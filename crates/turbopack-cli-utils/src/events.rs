@@ -0,0 +1,87 @@
+//! Machine-readable output of commands, printed instead of the messages for
+//! humans when a command is run with `--json`.
+//!
+//! Every line of stdout is a JSON object. Its `event` field names the kind of
+//! the event, all other fields are camelCase and durations are milliseconds:
+//!
+//! * `serverStarted`: `address` and `url` of a started dev server.
+//! * `compiled`: the initial compilation finished after `durationMs`. Commands
+//!   which know it add the `taskExecutionMs` spent executing `tasks` tasks.
+//! * `updated`: a recompilation after a change finished after `durationMs`,
+//!   executing `tasks` tasks.
+//! * `assetEmitted`: a file with `size` bytes was written to `path`.
+//! * `stats`: the number of emitted `assets` and their `totalSize` in bytes,
+//!   printed after all `assetEmitted` events of a build.
+//! * `issue`: an issue, with the fields `severity`, `category`, `code`, `path`,
+//!   `title`, `description`, `documentationLink`, `source` (`start` and `end`
+//!   with a 1-based `line` and a 0-based `column`) and `related` (the issues
+//!   causing it, with the same fields except `event` and `documentationLink`),
+//!   and `detail` when run with `--log-detail`. The lines of the
+//!   `--diagnostics-file` are `issue` events, too, always with `detail`.
+//!
+//! Consumers should ignore events and fields they don't know, as new ones can
+//! be added.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// An event of the `--json` output, see the [module documentation](self).
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum Event {
+    #[serde(rename_all = "camelCase")]
+    ServerStarted { address: String, url: String },
+    #[serde(rename_all = "camelCase")]
+    Compiled {
+        duration_ms: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        task_execution_ms: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tasks: Option<usize>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Updated { duration_ms: u64, tasks: usize },
+    #[serde(rename_all = "camelCase")]
+    AssetEmitted { path: String, size: u64 },
+    #[serde(rename_all = "camelCase")]
+    Stats { assets: usize, total_size: u64 },
+}
+
+impl Event {
+    /// Prints the event as a single line of JSON to stdout.
+    pub fn print(&self) {
+        println!("{}", serde_json::to_string(self).unwrap());
+    }
+}
+
+/// Converts `duration` to the milliseconds used in events.
+pub fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Event;
+
+    #[test]
+    fn serializes_events() {
+        let event = Event::Compiled {
+            duration_ms: 1200,
+            task_execution_ms: Some(900),
+            tasks: Some(42),
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"compiled","durationMs":1200,"taskExecutionMs":900,"tasks":42}"#
+        );
+        let event = Event::AssetEmitted {
+            path: "dist/index.js".to_string(),
+            size: 10,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"assetEmitted","path":"dist/index.js","size":10}"#
+        );
+    }
+}
@@ -3,17 +3,17 @@ use std::{
     cmp::{min, Ordering},
     collections::{hash_map::Entry, HashMap, HashSet},
     fmt::Write as _,
-    path::PathBuf,
+    path::{Path, PathBuf},
     str::FromStr,
     sync::{Arc, Mutex},
 };
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use crossterm::style::{StyledContent, Stylize};
 use owo_colors::{OwoColorize as _, Style};
 use turbo_tasks::{RawVc, TransientValue, TryJoinIterExt, ValueToString};
 use turbo_tasks_fs::{
-    attach::AttachedFileSystemVc, to_sys_path, FileLinesContent, FileSystemPathVc,
+    attach::AttachedFileSystemVc, glob::Glob, to_sys_path, FileLinesContent, FileSystemPathVc,
 };
 use turbo_tasks_hash::Xxh3Hash64Hasher;
 use turbopack_core::issue::{
@@ -21,6 +21,8 @@ use turbopack_core::issue::{
     PlainIssueSource,
 };
 
+use crate::reporter::{strip_ansi, MessageCatalog, OutputFormat};
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct IssueSeverityCliOption(pub IssueSeverity);
 
@@ -65,6 +67,130 @@ impl FromStr for IssueSeverityCliOption {
     }
 }
 
+/// Overrides how issues with a specific
+/// [code](turbopack_core::issue::Issue::code) are reported.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Copy, Hash)]
+pub enum IssueCodeOverride {
+    /// Issues with the code are not reported.
+    Off,
+    /// Issues with the code are reported with this severity instead of their
+    /// own, e. g. to escalate warnings to errors.
+    Severity(IssueSeverity),
+}
+
+/// An [IssueCodeOverride] for a code, written as `<code>=off` or
+/// `<code>=<severity>`, e. g. `TP1004=off` or `TP2002=error`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct IssueCodeOverrideCliOption {
+    pub code: String,
+    pub code_override: IssueCodeOverride,
+}
+
+impl FromStr for IssueCodeOverrideCliOption {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (code, value) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected <code>=<off|severity>, got {}", s))?;
+        let code_override = if value.eq_ignore_ascii_case("off") {
+            IssueCodeOverride::Off
+        } else {
+            IssueCodeOverride::Severity(IssueSeverityCliOption::from_str(value)?.0)
+        };
+        Ok(IssueCodeOverrideCliOption {
+            code: code.trim().to_string(),
+            code_override,
+        })
+    }
+}
+
+impl serde::Serialize for IssueCodeOverrideCliOption {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let value = match self.code_override {
+            IssueCodeOverride::Off => "off",
+            IssueCodeOverride::Severity(severity) => severity.as_str(),
+        };
+        serializer.serialize_str(&format!("{}={}", self.code, value))
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for IssueCodeOverrideCliOption {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        IssueCodeOverrideCliOption::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Overrides how issues about files matching a glob are reported, e. g. to
+/// suppress the warnings of dependencies.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct IssuePathOverride {
+    /// Matched against the path of the issue's context, relative to the root
+    /// of its file system.
+    pub glob: Glob,
+    /// When set, only issues with this code are overridden.
+    pub code: Option<String>,
+    pub code_override: IssueCodeOverride,
+}
+
+impl IssuePathOverride {
+    fn matches(&self, path: &str, code: &str) -> bool {
+        self.code.as_deref().map_or(true, |c| c == code) && self.glob.execute(path)
+    }
+}
+
+/// An [IssuePathOverride], written as `<glob>=<off|severity>` or
+/// `<glob>:<code>=<off|severity>`, e. g. `node_modules/**=off` or
+/// `src/legacy/**:TP1004=warning`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct IssuePathOverrideCliOption {
+    source: String,
+    pub path_override: IssuePathOverride,
+}
+
+impl FromStr for IssuePathOverrideCliOption {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (matcher, value) = s
+            .rsplit_once('=')
+            .ok_or_else(|| anyhow!("expected <glob>[:<code>]=<off|severity>, got {}", s))?;
+        let (glob, code) = match matcher.rsplit_once(':') {
+            Some((glob, code)) => (glob, Some(code.trim().to_string())),
+            None => (matcher, None),
+        };
+        let code_override = if value.eq_ignore_ascii_case("off") {
+            IssueCodeOverride::Off
+        } else {
+            IssueCodeOverride::Severity(IssueSeverityCliOption::from_str(value)?.0)
+        };
+        Ok(IssuePathOverrideCliOption {
+            source: s.to_string(),
+            path_override: IssuePathOverride {
+                glob: Glob::parse(glob.trim())?,
+                code,
+                code_override,
+            },
+        })
+    }
+}
+
+impl serde::Serialize for IssuePathOverrideCliOption {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for IssuePathOverrideCliOption {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        IssuePathOverrideCliOption::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
 fn severity_to_style(severity: IssueSeverity) -> Style {
     match severity {
         IssueSeverity::Bug => Style::new().bright_red().underline(),
@@ -78,7 +204,29 @@ fn severity_to_style(severity: IssueSeverity) -> Style {
     }
 }
 
-fn format_source_content(source: &PlainIssueSource, formatted_issue: &mut String) {
+/// The maximum width of a line of source code when the width of the terminal
+/// is unknown.
+const DEFAULT_SOURCE_LINE_WIDTH: usize = 200;
+/// The width taken by the line numbers and markers in front of source code, and
+/// the indentation of issues.
+const SOURCE_LINE_GUTTER_WIDTH: usize = 16;
+/// Source code is never shortened to less than this width, even on very narrow
+/// terminals.
+const MIN_SOURCE_LINE_WIDTH: usize = 40;
+
+fn source_line_width(terminal_width: Option<usize>) -> usize {
+    terminal_width.map_or(DEFAULT_SOURCE_LINE_WIDTH, |width| {
+        width
+            .saturating_sub(SOURCE_LINE_GUTTER_WIDTH)
+            .max(MIN_SOURCE_LINE_WIDTH)
+    })
+}
+
+fn format_source_content(
+    source: &PlainIssueSource,
+    max_width: usize,
+    formatted_issue: &mut String,
+) {
     if let FileLinesContent::Lines(lines) = source.asset.content.lines() {
         let context_start = source.start.line.saturating_sub(4);
         let context_end = source.end.line + 4;
@@ -97,13 +245,17 @@ fn format_source_content(source: &PlainIssueSource, formatted_issue: &mut String
                     (s, "")
                 }
             }
-            fn limit_len(s: &str) -> Cow<'_, str> {
-                if s.len() < 200 {
+            /// Shortens `s` to `max_width` characters by replacing its middle
+            /// with `...`.
+            fn limit_len(s: &str, max_width: usize) -> Cow<'_, str> {
+                let len = s.chars().count();
+                if len <= max_width {
                     return Cow::Borrowed(s);
                 }
-                let (a, b) = s.split_at(s.floor_char_boundary(98));
-                let (_, c) = b.split_at(b.ceil_char_boundary(b.len() - 99));
-                Cow::Owned(format!("{}...{}", a, c))
+                let half = max_width.saturating_sub(3) / 2;
+                let start = s.chars().take(half).collect::<String>();
+                let end = s.chars().skip(len - half).collect::<String>();
+                Cow::Owned(format!("{start}...{end}"))
             }
             match (i.cmp(&source.start.line), i.cmp(&source.end.line)) {
                 // outside
@@ -112,7 +264,7 @@ fn format_source_content(source: &PlainIssueSource, formatted_issue: &mut String
                         formatted_issue,
                         "{:>6}   {}",
                         n.dimmed(),
-                        limit_len(l).dimmed()
+                        limit_len(l, max_width).dimmed()
                     )
                     .unwrap();
                 }
@@ -123,8 +275,8 @@ fn format_source_content(source: &PlainIssueSource, formatted_issue: &mut String
                         formatted_issue,
                         "{:>6} + {}{}",
                         n,
-                        limit_len(before).dimmed(),
-                        limit_len(marked).bold()
+                        limit_len(before, max_width).dimmed(),
+                        limit_len(marked, max_width).bold()
                     )
                     .unwrap();
                 }
@@ -137,9 +289,9 @@ fn format_source_content(source: &PlainIssueSource, formatted_issue: &mut String
                         formatted_issue,
                         "{:>6} > {}{}{}",
                         n,
-                        limit_len(before).dimmed(),
-                        limit_len(middle).bold(),
-                        limit_len(after).dimmed()
+                        limit_len(before, max_width).dimmed(),
+                        limit_len(middle, max_width).bold(),
+                        limit_len(after, max_width).dimmed()
                     )
                     .unwrap();
                 }
@@ -150,20 +302,33 @@ fn format_source_content(source: &PlainIssueSource, formatted_issue: &mut String
                         formatted_issue,
                         "{:>6} + {}{}",
                         n,
-                        limit_len(marked).bold(),
-                        limit_len(after).dimmed()
+                        limit_len(marked, max_width).bold(),
+                        limit_len(after, max_width).dimmed()
                     )
                     .unwrap();
                 }
                 // middle line
-                (Ordering::Greater, Ordering::Less) => {
-                    writeln!(formatted_issue, "{:>6} | {}", n, limit_len(l).bold()).unwrap()
-                }
+                (Ordering::Greater, Ordering::Less) => writeln!(
+                    formatted_issue,
+                    "{:>6} | {}",
+                    n,
+                    limit_len(l, max_width).bold()
+                )
+                .unwrap(),
             }
         }
     }
 }
 
+/// Formats the lines around the source of an issue, with the source
+/// highlighted, like they are shown in the terminal. Used by the error overlay
+/// of the dev server, which renders the ANSI styles.
+pub fn format_code_frame(source: &PlainIssueSource) -> String {
+    let mut code_frame = String::new();
+    format_source_content(source, DEFAULT_SOURCE_LINE_WIDTH, &mut code_frame);
+    code_frame
+}
+
 async fn format_optional_path(
     path: &OptionIssueProcessingPathItemsVc,
     formatted_issue: &mut String,
@@ -205,6 +370,8 @@ pub fn format_issue(
     let &LogOptions {
         ref current_dir,
         log_detail,
+        terminal_width,
+        ref messages,
         ..
     } = options;
 
@@ -218,7 +385,7 @@ pub fn format_issue(
         .replace("/./", "/")
         .replace("\\\\?\\", "");
     let category = &plain_issue.category;
-    let title = &plain_issue.title;
+    let title = title_with_code(plain_issue);
 
     let mut styled_issue = if let Some(source) = &plain_issue.source {
         let mut styled_issue = format!(
@@ -229,7 +396,7 @@ pub fn format_issue(
             title.bold()
         );
         styled_issue.push('\n');
-        format_source_content(source, &mut styled_issue);
+        format_source_content(source, source_line_width(terminal_width), &mut styled_issue);
         styled_issue
     } else {
         format!("{}", title.bold())
@@ -250,7 +417,9 @@ pub fn format_issue(
         }
         let documentation_link = &plain_issue.documentation_link;
         if !documentation_link.is_empty() {
-            writeln!(styled_issue, "\ndocumentation: {documentation_link}").unwrap();
+            let documentation =
+                messages.format("documentation", &[("link", documentation_link.as_str())]);
+            writeln!(styled_issue, "\n{documentation}").unwrap();
         }
         if let Some(path) = path {
             writeln!(styled_issue, "{}", path).unwrap();
@@ -273,6 +442,15 @@ pub fn format_issue(
     issue_text
 }
 
+/// Prefixes the title with the code of the issue, when it has one.
+fn title_with_code(plain_issue: &PlainIssue) -> Cow<'_, str> {
+    if plain_issue.code.is_empty() {
+        Cow::Borrowed(&plain_issue.title)
+    } else {
+        Cow::Owned(format!("{} {}", plain_issue.code, plain_issue.title))
+    }
+}
+
 pub type GroupedIssues = HashMap<IssueSeverity, HashMap<String, HashMap<String, Vec<String>>>>;
 
 const DEFAULT_SHOW_COUNT: usize = 3;
@@ -295,6 +473,36 @@ pub struct LogOptions {
     pub show_all: bool,
     pub log_detail: bool,
     pub log_level: IssueSeverity,
+    /// Issues with this severity, or a more severe one, fail the compilation.
+    /// They are still only printed when they pass the `log_level`.
+    pub fail_level: IssueSeverity,
+    /// Overrides of the reporting of issues by their code.
+    pub code_overrides: HashMap<String, IssueCodeOverride>,
+    /// Overrides of the reporting of issues by the path of their context.
+    /// The first matching override is used, before the `code_overrides`.
+    pub path_overrides: Vec<IssuePathOverride>,
+    pub format: OutputFormat,
+    /// The width of the terminal, which long lines of source code are
+    /// shortened to.
+    pub terminal_width: Option<usize>,
+    pub messages: MessageCatalog,
+    /// When set, all current issues are written to this file as JSON lines
+    /// after every pull, for editors and CI annotations. Unlike the console
+    /// output, it includes the issues of earlier pulls and ignores the
+    /// `log_level`.
+    pub diagnostics_file: Option<PathBuf>,
+}
+
+impl LogOptions {
+    /// Returns how an issue with `code` about the file at `path` is reported
+    /// instead of with its own severity, if at all.
+    fn issue_override(&self, path: &str, code: &str) -> Option<IssueCodeOverride> {
+        self.path_overrides
+            .iter()
+            .find(|path_override| path_override.matches(path, code))
+            .map(|path_override| path_override.code_override)
+            .or_else(|| self.code_overrides.get(code).copied())
+    }
 }
 
 /// Tracks the state of currently seen issues.
@@ -326,6 +534,10 @@ struct SeenIssues {
     /// "seen" and will not be relogged. Once the count reaches zero, the
     /// issue is removed and the next time its seen it will be considered new.
     issues_count: HashMap<u64, usize>,
+
+    /// The JSON lines of the seen issues for the
+    /// [diagnostics file](LogOptions::diagnostics_file).
+    diagnostics: HashMap<u64, String>,
 }
 
 impl SeenIssues {
@@ -386,6 +598,27 @@ impl SeenIssues {
         *old = issue_ids;
         difference
     }
+
+    /// Writes the diagnostics of all issues which are seen by any source to
+    /// `path`. `diagnostics` of the current pull replace the ones of earlier
+    /// pulls, so it must be called after [SeenIssues::new_ids].
+    fn write_diagnostics(&mut self, path: &Path, diagnostics: Vec<(u64, String)>) -> Result<()> {
+        self.diagnostics.extend(diagnostics);
+        let issues_count = &self.issues_count;
+        self.diagnostics
+            .retain(|id, _| issues_count.contains_key(id));
+
+        // Sorted, so the file doesn't change when the issues are pulled in a
+        // different order.
+        let mut lines = self.diagnostics.values().collect::<Vec<_>>();
+        lines.sort_unstable();
+        let mut content = String::new();
+        for line in lines {
+            writeln!(content, "{line}")?;
+        }
+        std::fs::write(path, content)
+            .with_context(|| format!("diagnostics can't be written to {}", path.display()))
+    }
 }
 
 /// Logs emitted issues to console logs, deduplicating issues between peeks of
@@ -417,6 +650,7 @@ impl ConsoleUi {
 
 #[turbo_tasks::value(transparent)]
 pub struct DisplayIssueState {
+    /// An issue with the `fail_level` or a more severe one was reported.
     pub has_fatal: bool,
     pub has_issues: bool,
     pub has_new_issues: bool,
@@ -434,6 +668,7 @@ async fn internal_hash(issue: IssueVc) -> Result<IssueHashVc> {
     hasher.write_value(issue.severity().await?);
     hasher.write_ref(&issue.context().await?.path);
     hasher.write_value(issue.category().await?);
+    hasher.write_value(issue.code().await?);
     hasher.write_value(issue.title().await?);
     hasher.write_value(issue.description().await?);
     hasher.write_value(issue.detail().await?);
@@ -462,43 +697,90 @@ impl ConsoleUiVc {
 
         let issues = IssueVc::peek_issues_with_path(source).await?;
         let issues = issues.await?;
+        let options = &this.options;
         let &LogOptions {
             ref current_dir,
             show_all,
             log_detail,
             log_level,
+            fail_level,
+            format,
+            terminal_width,
+            ref messages,
+            ref diagnostics_file,
             ..
-        } = &this.options;
+        } = options;
+        let mut out = String::new();
         let mut grouped_issues: GroupedIssues = HashMap::new();
 
         let issues = issues
             .iter_with_shortest_path()
             .map(async move |(issue, path)| {
                 let id = internal_hash(issue).await?;
-                Ok((issue, path, *id))
+                let code = issue.code().await?;
+                let context = issue.context().await?;
+                let severity = match options.issue_override(&context.path, &code) {
+                    Some(IssueCodeOverride::Off) => None,
+                    Some(IssueCodeOverride::Severity(severity)) => Some(severity),
+                    None => Some(*issue.severity().await?),
+                };
+                Ok(severity.map(|severity| (issue, path, *id, severity)))
             })
             .try_join()
-            .await?;
+            .await?
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
 
-        let issue_ids = issues.iter().map(|(_, _, id)| *id).collect::<HashSet<_>>();
+        let issue_ids = issues
+            .iter()
+            .map(|(_, _, id, _)| *id)
+            .collect::<HashSet<_>>();
         let mut new_ids = this.seen.lock().unwrap().new_ids(source, issue_ids);
 
-        let mut has_fatal = false;
+        if let Some(diagnostics_file) = diagnostics_file {
+            let diagnostics = issues
+                .iter()
+                .map(async move |&(issue, _, id, severity)| {
+                    let plain_issue = issue.into_plain().await?;
+                    let context_path = make_relative_to_cwd(issue.context(), current_dir).await?;
+                    let json = issue_to_json(&plain_issue, severity, &context_path, true);
+                    Ok((id, json.to_string()))
+                })
+                .try_join()
+                .await?;
+            this.seen
+                .lock()
+                .unwrap()
+                .write_diagnostics(diagnostics_file, diagnostics)?;
+        }
+
+        // Issues which were already reported by an earlier pull still fail it.
+        let has_fatal = issues
+            .iter()
+            .any(|(_, _, _, severity)| *severity <= fail_level);
         let has_issues = !issues.is_empty();
         let has_new_issues = !new_ids.is_empty();
 
-        for (issue, path, id) in issues {
+        for (issue, path, id, severity) in issues {
             if !new_ids.remove(&id) {
                 continue;
             }
 
             let plain_issue = issue.into_plain().await?;
 
-            let severity = plain_issue.severity;
             let context_path = make_relative_to_cwd(issue.context(), current_dir).await?;
             let category = &plain_issue.category;
-            let title = &plain_issue.title;
-            has_fatal = severity == IssueSeverity::Fatal;
+            let title = title_with_code(&plain_issue);
+
+            if format == OutputFormat::JsonLines {
+                if severity <= log_level {
+                    let json = issue_to_json(&plain_issue, severity, &context_path, log_detail);
+                    writeln!(out, "{json}")?;
+                }
+                continue;
+            }
+
             let severity_map = grouped_issues
                 .entry(severity)
                 .or_insert_with(Default::default);
@@ -518,7 +800,7 @@ impl ConsoleUiVc {
                     title.bold()
                 );
                 styled_issue.push('\n');
-                format_source_content(source, &mut styled_issue);
+                format_source_content(source, source_line_width(terminal_width), &mut styled_issue);
                 styled_issue
             } else {
                 format!("{}", title.bold())
@@ -539,7 +821,9 @@ impl ConsoleUiVc {
                 }
                 let documentation_link = issue.documentation_link().await?;
                 if !documentation_link.is_empty() {
-                    writeln!(&mut styled_issue, "\ndocumentation: {documentation_link}")?;
+                    let documentation =
+                        messages.format("documentation", &[("link", documentation_link.as_str())]);
+                    writeln!(&mut styled_issue, "\n{documentation}")?;
                 }
                 format_optional_path(&path, &mut styled_issue).await?;
             }
@@ -550,10 +834,10 @@ impl ConsoleUiVc {
             if let Some(severity_map) = grouped_issues.get_mut(&severity) {
                 let severity_map_size = severity_map.len();
                 let indent = if severity_map_size == 1 {
-                    print!("{} - ", severity.style(severity_to_style(severity)));
+                    write!(out, "{} - ", severity.style(severity_to_style(severity)))?;
                     ""
                 } else {
-                    println!("{} -", severity.style(severity_to_style(severity)));
+                    writeln!(out, "{} -", severity.style(severity_to_style(severity)))?;
                     "  "
                 };
                 let severity_map_take_count = if show_all {
@@ -567,10 +851,10 @@ impl ConsoleUiVc {
                     let category_issues = severity_map.get_mut(category).unwrap();
                     let category_issues_size = category_issues.len();
                     let indent = if category_issues_size == 1 && indent.is_empty() {
-                        print!("[{category}] ");
+                        write!(out, "[{category}] ")?;
                         "".to_string()
                     } else {
-                        println!("{indent}[{category}]");
+                        writeln!(out, "{indent}[{category}]")?;
                         format!("{indent}  ")
                     };
                     let (mut contextes, mut vendor_contextes): (Vec<_>, Vec<_>) = category_issues
@@ -589,7 +873,7 @@ impl ConsoleUiVc {
                     for (context, issues) in contextes.into_iter().take(category_issues_take_count)
                     {
                         issues.sort();
-                        println!("{indent}{}", context.bright_blue());
+                        writeln!(out, "{indent}{}", context.bright_blue())?;
                         let issues_size = issues.len();
                         let issues_take_count = if show_all {
                             issues_size
@@ -599,38 +883,50 @@ impl ConsoleUiVc {
                         for issue in issues.iter().take(issues_take_count) {
                             let mut i = 0;
                             for line in issue.lines() {
-                                println!("{indent}  {line}");
+                                writeln!(out, "{indent}  {line}")?;
                                 i += 1;
                             }
                             if i > 1 {
                                 // Spacing after multi line issues
-                                println!();
+                                writeln!(out)?;
                             }
                         }
                         if issues_size > issues_take_count {
-                            println!("{indent}  {}", show_all_message("issues", issues_size));
+                            writeln!(
+                                out,
+                                "{indent}  {}",
+                                show_all_message(messages, "label_issues", issues_size)
+                            )?;
                         }
                     }
                     if category_issues_size > category_issues_take_count {
-                        println!(
+                        writeln!(
+                            out,
                             "{indent}{}",
                             show_all_message_with_shown_count(
-                                "paths",
+                                messages,
+                                "label_paths",
                                 category_issues_size,
                                 category_issues_take_count
                             )
-                        );
+                        )?;
                     }
                 }
                 if severity_map_size > severity_map_take_count {
-                    println!(
+                    writeln!(
+                        out,
                         "{indent}{}",
-                        show_all_message("categories", severity_map_size)
-                    )
+                        show_all_message(messages, "label_categories", severity_map_size)
+                    )?
                 }
             }
         }
 
+        match format {
+            OutputFormat::Pretty | OutputFormat::JsonLines => print!("{out}"),
+            OutputFormat::Plain => print!("{}", strip_ansi(&out)),
+        }
+
         Ok(DisplayIssueState {
             has_fatal,
             has_issues,
@@ -658,28 +954,130 @@ async fn make_relative_to_cwd(path: FileSystemPathVc, cwd: &PathBuf) -> Result<S
     }
 }
 
-fn show_all_message(label: &str, size: usize) -> StyledContent<String> {
-    show_all_message_with_shown_count(label, size, DEFAULT_SHOW_COUNT)
+fn show_all_message(
+    messages: &MessageCatalog,
+    label_id: &str,
+    size: usize,
+) -> StyledContent<String> {
+    show_all_message_with_shown_count(messages, label_id, size, DEFAULT_SHOW_COUNT)
 }
 
 fn show_all_message_with_shown_count(
-    label: &str,
+    messages: &MessageCatalog,
+    label_id: &str,
     size: usize,
     shown: usize,
 ) -> StyledContent<String> {
+    let label = messages.format(label_id, &[]);
+    let flag = "--show-all".bright_green().to_string();
     if shown == 0 {
-        format!(
-            "... [{} {label}] are hidden, run with {} to show them",
-            size,
-            "--show-all".bright_green()
-        )
-        .bold()
+        messages
+            .format(
+                "hidden",
+                &[
+                    ("count", &size.to_string()),
+                    ("label", &label),
+                    ("flag", &flag),
+                ],
+            )
+            .bold()
     } else {
-        format!(
-            "... [{} more {label}] are hidden, run with {} to show all",
-            size - shown,
-            "--show-all".bright_green()
-        )
-        .bold()
+        messages
+            .format(
+                "hidden_more",
+                &[
+                    ("count", &(size - shown).to_string()),
+                    ("label", &label),
+                    ("flag", &flag),
+                ],
+            )
+            .bold()
+    }
+}
+
+/// Serializes an issue as a single line of JSON, for
+/// [OutputFormat::JsonLines]. The schema is documented as the `issue` event in
+/// [crate::events].
+fn issue_to_json(
+    plain_issue: &PlainIssue,
+    severity: IssueSeverity,
+    context_path: &str,
+    log_detail: bool,
+) -> serde_json::Value {
+    let mut json = serde_json::json!({
+        "event": "issue",
+        "severity": severity.as_str(),
+        "category": plain_issue.category,
+        "code": plain_issue.code,
+        "path": context_path,
+        "title": plain_issue.title,
+        "description": plain_issue.description,
+        "documentationLink": plain_issue.documentation_link,
+        "source": plain_issue.source.as_deref().map(source_to_json),
+        "related": plain_issue
+            .sub_issues
+            .iter()
+            .map(|sub_issue| related_issue_to_json(sub_issue))
+            .collect::<Vec<_>>(),
+    });
+    if log_detail {
+        json["detail"] = plain_issue.detail.clone().into();
+    }
+    json
+}
+
+/// Serializes a sub issue for the `related` field of [issue_to_json]. Sub
+/// issues are reported with their own severity and context.
+fn related_issue_to_json(plain_issue: &PlainIssue) -> serde_json::Value {
+    serde_json::json!({
+        "severity": plain_issue.severity.as_str(),
+        "category": plain_issue.category,
+        "code": plain_issue.code,
+        "path": plain_issue.context,
+        "title": plain_issue.title,
+        "description": plain_issue.description,
+        "source": plain_issue.source.as_deref().map(source_to_json),
+        "related": plain_issue
+            .sub_issues
+            .iter()
+            .map(|sub_issue| related_issue_to_json(sub_issue))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn source_to_json(source: &PlainIssueSource) -> serde_json::Value {
+    serde_json::json!({
+        "start": { "line": source.start.line + 1, "column": source.start.column },
+        "end": { "line": source.end.line + 1, "column": source.end.column },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use turbopack_core::issue::IssueSeverity;
+
+    use super::{IssueCodeOverride, IssuePathOverrideCliOption};
+
+    #[test]
+    fn parses_path_overrides() {
+        let option = IssuePathOverrideCliOption::from_str("node_modules/**=off").unwrap();
+        assert_eq!(option.path_override.code, None);
+        assert_eq!(option.path_override.code_override, IssueCodeOverride::Off);
+        assert!(option
+            .path_override
+            .matches("node_modules/react/index.js", "TP1004"));
+        assert!(!option.path_override.matches("src/index.js", "TP1004"));
+
+        let option = IssuePathOverrideCliOption::from_str("src/**:TP1004=error").unwrap();
+        assert_eq!(
+            option.path_override.code_override,
+            IssueCodeOverride::Severity(IssueSeverity::Error)
+        );
+        assert!(option.path_override.matches("src/index.js", "TP1004"));
+        assert!(!option.path_override.matches("src/index.js", "TP2002"));
+
+        assert!(IssuePathOverrideCliOption::from_str("src/**").is_err());
     }
 }
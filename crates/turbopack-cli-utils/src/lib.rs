@@ -2,7 +2,10 @@
 #![feature(min_specialization)]
 #![feature(round_char_boundary)]
 
+pub mod events;
 pub mod issue;
+pub mod reporter;
+pub mod telemetry;
 
 pub fn register() {
     turbo_tasks::register();
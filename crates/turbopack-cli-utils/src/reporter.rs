@@ -0,0 +1,163 @@
+use std::{borrow::Cow, collections::HashMap, io::stdout};
+
+use anyhow::{Context, Result};
+use crossterm::tty::IsTty;
+
+/// How issues are written to the console.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Copy, Hash, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// Colored output for humans.
+    Pretty,
+    /// Output for humans without ANSI escape sequences, for CI logs and
+    /// terminals which don't support colors.
+    Plain,
+    /// One JSON object per issue and line, for log collectors.
+    JsonLines,
+}
+
+impl OutputFormat {
+    /// Follows the [NO_COLOR](https://no-color.org) convention: colors are
+    /// disabled when the `NO_COLOR` environment variable is set to a non-empty
+    /// value, when the terminal is `dumb` or when stdout isn't a terminal.
+    pub fn from_env() -> Self {
+        let no_color = std::env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty());
+        let dumb = std::env::var_os("TERM").map_or(false, |term| term == "dumb");
+        if no_color || dumb || !stdout().is_tty() {
+            OutputFormat::Plain
+        } else {
+            OutputFormat::Pretty
+        }
+    }
+}
+
+/// Returns the width of the terminal in columns, or `None` when stdout isn't a
+/// terminal.
+pub fn terminal_width() -> Option<usize> {
+    if !stdout().is_tty() {
+        return None;
+    }
+    crossterm::terminal::size()
+        .ok()
+        .map(|(columns, _)| columns as usize)
+}
+
+/// Removes ANSI escape sequences, e. g. colors, from `text`.
+pub fn strip_ansi(text: &str) -> Cow<'_, str> {
+    if !text.contains('\x1b') {
+        return Cow::Borrowed(text);
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            result.push(c);
+            continue;
+        }
+        // Control sequences (`ESC [`) end with a byte in the range `@` to `~`,
+        // other escape sequences consist of a single character.
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    Cow::Owned(result)
+}
+
+/// The messages of the console output, by their id. Messages can contain
+/// placeholders like `{count}`, which are replaced when formatting them.
+///
+/// The default catalog contains english messages. Translations can replace
+/// some or all of them with [MessageCatalog::extend_from_json].
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct MessageCatalog {
+    messages: HashMap<String, String>,
+}
+
+const DEFAULT_MESSAGES: &[(&str, &str)] = &[
+    (
+        "hidden",
+        "... [{count} {label}] are hidden, run with {flag} to show them",
+    ),
+    (
+        "hidden_more",
+        "... [{count} more {label}] are hidden, run with {flag} to show all",
+    ),
+    ("label_issues", "issues"),
+    ("label_paths", "paths"),
+    ("label_categories", "categories"),
+    ("documentation", "documentation: {link}"),
+];
+
+impl Default for MessageCatalog {
+    fn default() -> Self {
+        MessageCatalog {
+            messages: DEFAULT_MESSAGES
+                .iter()
+                .map(|&(id, message)| (id.to_string(), message.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl MessageCatalog {
+    /// Replaces messages with the ones in `json`, an object mapping message
+    /// ids to messages. Messages missing in `json` are kept.
+    pub fn extend_from_json(&mut self, json: &str) -> Result<()> {
+        let messages: HashMap<String, String> =
+            serde_json::from_str(json).context("invalid message catalog")?;
+        self.messages.extend(messages);
+        Ok(())
+    }
+
+    /// Returns the message with the id `id`, with its placeholders replaced by
+    /// `args`. Unknown ids are returned as is.
+    pub fn format(&self, id: &str, args: &[(&str, &str)]) -> String {
+        let mut message = self
+            .messages
+            .get(id)
+            .map_or_else(|| id.to_string(), |message| message.clone());
+        for (name, value) in args {
+            message = message.replace(&format!("{{{name}}}"), value);
+        }
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{strip_ansi, MessageCatalog};
+
+    #[test]
+    fn strips_ansi_sequences() {
+        assert_eq!(strip_ansi("plain"), "plain");
+        assert_eq!(
+            strip_ansi("\x1b[1m\x1b[91merror\x1b[0m - [parse]"),
+            "error - [parse]"
+        );
+        assert_eq!(strip_ansi("a\x1bcb"), "ab");
+    }
+
+    #[test]
+    fn formats_messages() {
+        let mut catalog = MessageCatalog::default();
+        assert_eq!(
+            catalog.format("documentation", &[("link", "https://turbo.build")]),
+            "documentation: https://turbo.build"
+        );
+        catalog
+            .extend_from_json(r#"{ "documentation": "Dokumentation: {link}" }"#)
+            .unwrap();
+        assert_eq!(
+            catalog.format("documentation", &[("link", "https://turbo.build")]),
+            "Dokumentation: https://turbo.build"
+        );
+        assert_eq!(catalog.format("label_paths", &[]), "paths");
+        assert_eq!(catalog.format("unknown", &[]), "unknown");
+    }
+}
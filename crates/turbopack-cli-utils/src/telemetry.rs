@@ -0,0 +1,52 @@
+//! Hooks for opt-in telemetry of embedders.
+//!
+//! Products built on turbopack implement [Telemetry] to receive coarse,
+//! anonymous metrics of the builds, which they can collect when the user
+//! opted in. The metrics never contain paths, module names or source code.
+
+use std::time::Duration;
+
+/// Receives the metrics of builds. Implementations must not block, as they
+/// are called from the task reporting the builds.
+pub trait Telemetry: Send + Sync {
+    fn record(&self, event: &TelemetryEvent);
+}
+
+#[derive(Debug, Clone)]
+pub enum TelemetryEvent {
+    /// The initial compilation has finished.
+    Compiled(BuildMetrics),
+    /// An update after a change has finished.
+    Updated(BuildMetrics),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BuildMetrics {
+    pub duration: Duration,
+    /// The number of tasks executed for the build, if known.
+    pub tasks: Option<usize>,
+    /// The number of modules analyzed so far.
+    pub modules: usize,
+    pub cache: CacheMetrics,
+    /// Which optional features are enabled, by name.
+    pub features: Vec<(&'static str, bool)>,
+}
+
+/// How well the task cache worked so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheMetrics {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl CacheMetrics {
+    /// The share of task calls served from the cache, between 0 and 1.
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::{net::IpAddr, path::PathBuf};
+
+use clap::{Args, Parser};
+use turbopack::{build::ModuleIds, preset::Preset};
+use turbopack_cli_utils::issue::IssueSeverityCliOption;
+use turbopack_core::{chunk::ChunkFormat, environment::EcmascriptVersion};
+use turbopack_ecmascript::chunk::library::LibraryFormat;
+
+#[derive(Debug, Parser)]
+#[clap(author, version, about, long_about = None)]
+pub enum Arguments {
+    /// Builds the entries of the project into the output directory.
+    Build(BuildArguments),
+    /// Starts a dev server compiling the entries of the project on demand.
+    Dev(DevArguments),
+    /// Builds the entries of the project and reports which files make the
+    /// output large.
+    Analyze(BuildArguments),
+}
+
+#[derive(Debug, Args)]
+pub struct CommonArguments {
+    /// The entries of the project, relative to the project directory.
+    /// Defaults to the entries of the config file, or `src/index.js`.
+    #[clap(value_parser)]
+    pub entries: Vec<String>,
+
+    /// The directory of the project. Defaults to the current directory.
+    #[clap(short, long, value_parser)]
+    pub dir: Option<PathBuf>,
+
+    /// The config file of the project. Defaults to `turbopack.config.js`,
+    /// `turbopack.config.json` or `turbopack.config.toml` in the project
+    /// directory, when it exists.
+    #[clap(short, long, value_parser)]
+    pub config: Option<PathBuf>,
+
+    /// The preset of the project, e. g. `react-app` or `node-server`.
+    /// Overrides the preset of the config file.
+    #[clap(long, value_parser)]
+    pub preset: Option<Preset>,
+
+    /// Filter by issue severity.
+    #[clap(short, long)]
+    pub log_level: Option<IssueSeverityCliOption>,
+
+    /// Show all log messages without limit.
+    #[clap(long)]
+    pub show_all: bool,
+
+    /// Expand the log details.
+    #[clap(long)]
+    pub log_detail: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct BuildArguments {
+    #[clap(flatten)]
+    pub common: CommonArguments,
+
+    /// The directory the output is written to, relative to the project
+    /// directory. Overrides the output directory of the config file.
+    #[clap(short, long, value_parser)]
+    pub output_dir: Option<PathBuf>,
+
+    /// Minify the code of the chunks.
+    #[clap(long)]
+    pub minify: bool,
+
+    /// Emit the runtime as a chunk of its own, instead of including it in the
+    /// chunk of every entry.
+    #[clap(long)]
+    pub runtime_chunk: bool,
+
+    /// The URL prefix the output is served from, e. g. `/assets/`, or `auto`
+    /// to derive it from the URL of the loaded scripts at runtime. Overrides
+    /// the public path of the config file.
+    #[clap(long, value_parser)]
+    pub public_path: Option<String>,
+
+    /// How the chunks register themselves, `global`, or `systemjs` and `amd`
+    /// for hosts loading the entries with a module loader. Overrides the chunk
+    /// format of the config file.
+    #[clap(long, value_parser)]
+    pub chunk_format: Option<ChunkFormat>,
+
+    /// Downlevel the syntax of the output to this ES version, e. g. `es5` or
+    /// `es2017`. Overrides the ES version of the config file.
+    #[clap(long, value_parser)]
+    pub es_version: Option<EcmascriptVersion>,
+
+    /// Build the entries as libraries in this module format, `esm`, `cjs` or
+    /// `umd`, instead of building an app. Overrides the format of the config
+    /// file.
+    #[clap(long, value_parser)]
+    pub library: Option<LibraryFormat>,
+
+    /// Replace packages included in multiple versions with their highest
+    /// version.
+    #[clap(long)]
+    pub dedupe_packages: bool,
+
+    /// Omit modules from async chunks which the importing chunk contains.
+    #[clap(long)]
+    pub dedupe_async_chunks: bool,
+
+    /// Omit exports which no module imports from the code of the modules.
+    #[clap(long)]
+    pub tree_shaking: bool,
+
+    /// Concatenate modules only imported by a single other module into the
+    /// scope of their importer.
+    #[clap(long)]
+    pub concatenate_modules: bool,
+
+    /// How module ids are derived, `named`, `hashed` or `numeric`. Overrides
+    /// the module ids of the config file and the preset.
+    #[clap(long, value_parser)]
+    pub module_ids: Option<ModuleIds>,
+
+    /// Fail when an issue with this severity, or a more severe one, occurs.
+    /// Defaults to `error`.
+    #[clap(long)]
+    pub fail_level: Option<IssueSeverityCliOption>,
+}
+
+#[derive(Debug, Args)]
+pub struct DevArguments {
+    #[clap(flatten)]
+    pub common: CommonArguments,
+
+    /// The port number on which to start the dev server.
+    #[clap(short, long, value_parser, default_value_t = 3000)]
+    pub port: u16,
+
+    /// Hostname on which to start the dev server.
+    #[clap(short = 'H', long, value_parser, default_value = "0.0.0.0")]
+    pub hostname: IpAddr,
+
+    /// Don't open the browser automatically when the dev server has started.
+    #[clap(long)]
+    pub no_open: bool,
+}
@@ -0,0 +1,112 @@
+use anyhow::{bail, Result};
+use owo_colors::OwoColorize;
+use turbo_tasks::util::FormatDuration;
+use turbopack::build::{BuildResult, SIZE_REPORT_NAME};
+use turbopack_cli_utils::issue::format_issue;
+use turbopack_core::issue::IssueSeverity;
+
+use crate::{arguments::BuildArguments, config::Config, entries, log_options, project_dir};
+
+/// The number of assets listed by `turbopack analyze`.
+const ANALYZE_ASSETS: usize = 10;
+
+/// Builds the project and lists the emitted assets.
+pub async fn build(arguments: &BuildArguments) -> Result<()> {
+    let result = build_project(arguments, false).await?;
+    for asset in result.assets.iter() {
+        println!("  {:>10}  {}", format_size(asset.size), asset.path);
+    }
+    println!(
+        "{} - built {} assets in {}",
+        "event".purple(),
+        result.assets.len(),
+        FormatDuration(result.duration)
+    );
+    Ok(())
+}
+
+/// Builds the project with a size report and lists the largest assets.
+pub async fn analyze(arguments: &BuildArguments) -> Result<()> {
+    let result = build_project(arguments, true).await?;
+    let total = result.assets.iter().map(|asset| asset.size).sum::<usize>();
+    let mut assets = result.assets.iter().collect::<Vec<_>>();
+    assets.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    for asset in assets.iter().take(ANALYZE_ASSETS) {
+        println!(
+            "  {:>10}  {:>5.1}%  {}",
+            format_size(asset.size),
+            asset.size as f64 * 100.0 / total.max(1) as f64,
+            asset.path
+        );
+    }
+    println!(
+        "{} - {} assets with {} in total, see {SIZE_REPORT_NAME} in the output directory for the \
+         sizes by package and module",
+        "event".purple(),
+        result.assets.len(),
+        format_size(total)
+    );
+    Ok(())
+}
+
+async fn build_project(arguments: &BuildArguments, size_report: bool) -> Result<BuildResult> {
+    let common = &arguments.common;
+    let project_dir = project_dir(common)?;
+    let config = Config::load(&project_dir, common.config.as_deref()).await?;
+    let entries = entries(&common.entries, &config);
+
+    let mut options = config.build;
+    options.project_dir = project_dir.clone();
+    if let Some(output_dir) = &arguments.output_dir {
+        options.output_dir = output_dir.clone();
+    }
+    if let Some(preset) = common.preset {
+        options.preset = preset;
+    }
+    if let Some(public_path) = &arguments.public_path {
+        options.public_path = public_path.clone();
+    }
+    if let Some(chunk_format) = arguments.chunk_format {
+        options.chunk_format = chunk_format;
+    }
+    if let Some(es_version) = arguments.es_version {
+        options.es_version = Some(es_version);
+    }
+    if let Some(module_ids) = arguments.module_ids {
+        options.module_ids = Some(module_ids);
+    }
+    if let Some(format) = arguments.library {
+        options.library.get_or_insert_with(Default::default).format = format;
+    }
+    options.minify |= arguments.minify;
+    options.runtime_chunk |= arguments.runtime_chunk;
+    options.dedupe_packages |= arguments.dedupe_packages;
+    options.dedupe_async_chunks |= arguments.dedupe_async_chunks;
+    options.tree_shaking |= arguments.tree_shaking;
+    options.concatenate_modules |= arguments.concatenate_modules;
+    options.size_report |= size_report;
+
+    let result = turbopack::build::build(entries, options).await?;
+
+    let fail_level = arguments
+        .fail_level
+        .map_or_else(|| IssueSeverity::Error, |l| l.0);
+    let log_options = log_options(common, project_dir, fail_level);
+    for issue in result.issues.iter() {
+        if issue.severity <= log_options.log_level {
+            print!("{}", format_issue(issue, None, &log_options));
+        }
+    }
+    if result.has_issues_at(fail_level) {
+        bail!("Issue(s) at or above the fail level occurred");
+    }
+    Ok(result)
+}
+
+fn format_size(size: usize) -> String {
+    if size < 1024 {
+        format!("{size} B")
+    } else {
+        format!("{:.1} kB", size as f64 / 1024.0)
+    }
+}
@@ -0,0 +1,198 @@
+//! Loading of the config file of a project.
+//!
+//! The config file is `turbopack.config.js`, `turbopack.config.json` or
+//! `turbopack.config.toml` in the project directory, or the file passed with
+//! `--config`. JavaScript configs are evaluated in a sandboxed Node.js process
+//! and export the config object, or a function returning it.
+//!
+//! The config is loaded by turbo tasks, so the dev server picks up changes to
+//! the config file, or to the files a JavaScript config requires.
+
+use std::{
+    env::current_dir,
+    path::{Component, Path},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use next_core::{evaluate_config, EvaluatedConfig};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use turbo_tasks::TurboTasks;
+use turbo_tasks_fs::{DiskFileSystemVc, FileContent, FileSystemPathVc, FileSystemVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::build::{BuildEntry, BuildOptions};
+use turbopack_core::issue::IssueVc;
+
+/// The names of the config files which are looked up in the project directory
+/// when no other config file is passed, in order of precedence.
+pub const CONFIG_FILE_NAMES: [&str; 3] = [
+    "turbopack.config.js",
+    "turbopack.config.json",
+    "turbopack.config.toml",
+];
+
+/// The directory in the project directory JavaScript config files are
+/// evaluated from.
+pub const INTERMEDIATE_OUTPUT_DIR: &str = ".turbopack";
+
+/// The config file of a project.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Config {
+    /// The entries of the project. Entries can be the path of the module
+    /// relative to the project directory, or an object with the `request`, and
+    /// optionally the `name` and the `filename` template of the entry.
+    pub entries: Vec<BuildEntry>,
+    /// The options of builds. The dev server uses the preset and the
+    /// browserslist query. The project directory is the directory the
+    /// command runs in.
+    #[serde(flatten)]
+    pub build: BuildOptions,
+}
+
+impl Config {
+    /// Deserializes a config loaded by [load_config].
+    pub fn from_json(value: &JsonValue) -> Result<Config> {
+        Config::deserialize(value).context("config file is invalid")
+    }
+
+    /// Loads the config file at `path`, or the first of the
+    /// [CONFIG_FILE_NAMES] in `project_dir` which exists. Without a config
+    /// file, the defaults are used.
+    ///
+    /// The config is loaded once. The dev server loads it with
+    /// [load_config] instead, which picks up changes.
+    pub async fn load(project_dir: &Path, path: Option<&Path>) -> Result<Config> {
+        let path = config_path(project_dir, path)?;
+        let project_dir = project_dir
+            .to_str()
+            .context("project directory contains invalid characters")?
+            .to_string();
+
+        crate::register();
+        let tt = TurboTasks::new(MemoryBackend::new());
+        let result = tt
+            .run_once(async move {
+                let project_fs: FileSystemVc =
+                    DiskFileSystemVc::new("project".to_string(), project_dir.clone()).into();
+                let output_fs: FileSystemVc =
+                    DiskFileSystemVc::new("output".to_string(), project_dir).into();
+                let config = load_config(
+                    project_fs.root(),
+                    path,
+                    output_fs.root().join(INTERMEDIATE_OUTPUT_DIR),
+                );
+                if let Some(value) = &*config.strongly_consistent().await? {
+                    return Ok(value.clone());
+                }
+                // The reason is only known from the issues of the evaluation
+                let issues = IssueVc::peek_issues_with_path(config)
+                    .await?
+                    .strongly_consistent()
+                    .await?
+                    .get_plain_issues()
+                    .await?;
+                let messages = issues
+                    .iter()
+                    .map(|issue| format!("{}\n{}", issue.title, issue.description))
+                    .collect::<Vec<_>>();
+                Err(anyhow!(
+                    "config file can't be evaluated\n\n{}",
+                    messages.join("\n\n")
+                ))
+            })
+            .await;
+        tt.stop_and_wait().await;
+        Config::from_json(&result?)
+    }
+}
+
+/// The config file passed on the command line, relative to the project
+/// directory. The config is loaded by turbo tasks from the project directory,
+/// so it has to be within it.
+pub fn config_path(project_dir: &Path, path: Option<&Path>) -> Result<Option<String>> {
+    let path = match path {
+        Some(path) => current_dir()?.join(path),
+        None => return Ok(None),
+    };
+    let relative = path.strip_prefix(project_dir).with_context(|| {
+        format!(
+            "config file {} must be within the project directory {}",
+            path.display(),
+            project_dir.display()
+        )
+    })?;
+    let segments = relative
+        .components()
+        .map(|component| match component {
+            Component::Normal(segment) => segment.to_str(),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .with_context(|| format!("config file path {} is not supported", path.display()))?;
+    Ok(Some(segments.join("/")))
+}
+
+/// The JSON representation of a config file, which is an empty object when
+/// there is no config file. It's `None` when a JavaScript config can't be
+/// evaluated, the reason has been emitted as an issue then.
+#[turbo_tasks::value(transparent, serialization = "none")]
+pub struct ConfigJson(Option<JsonValue>);
+
+/// Loads the config file at `path`, which is relative to `project_root`, or
+/// the first of the [CONFIG_FILE_NAMES] which exists. JavaScript configs are
+/// evaluated from `intermediate_output_path`.
+#[turbo_tasks::function]
+pub async fn load_config(
+    project_root: FileSystemPathVc,
+    path: Option<String>,
+    intermediate_output_path: FileSystemPathVc,
+) -> Result<ConfigJsonVc> {
+    let path = match path {
+        Some(path) => project_root.join(&path),
+        None => {
+            let mut found = None;
+            for name in CONFIG_FILE_NAMES {
+                let path = project_root.join(name);
+                if let FileContent::Content(_) = &*path.read().await? {
+                    found = Some(path);
+                    break;
+                }
+            }
+            match found {
+                Some(path) => path,
+                None => {
+                    return Ok(ConfigJsonVc::cell(Some(JsonValue::Object(
+                        Default::default(),
+                    ))))
+                }
+            }
+        }
+    };
+    let path_value = path.await?;
+    let value = match path_value.extension() {
+        Some("js" | "cjs") => match &*evaluate_config(path, intermediate_output_path).await? {
+            EvaluatedConfig::Value(value) => value.clone(),
+            EvaluatedConfig::NotFound => bail!("config file {} doesn't exist", path_value.path),
+            EvaluatedConfig::Error => return Ok(ConfigJsonVc::cell(None)),
+        },
+        extension => {
+            let content = match &*path.read().await? {
+                FileContent::Content(file) => String::from_utf8(file.content().to_vec())
+                    .with_context(|| format!("config file {} is not UTF-8", path_value.path))?,
+                FileContent::NotFound => bail!("config file {} doesn't exist", path_value.path),
+            };
+            match extension {
+                Some("json") => serde_json::from_str(&content)
+                    .with_context(|| format!("config file {} is invalid", path_value.path))?,
+                Some("toml") => toml::from_str(&content)
+                    .with_context(|| format!("config file {} is invalid", path_value.path))?,
+                _ => bail!(
+                    "config file {} has an unsupported format, use .js, .json or .toml",
+                    path_value.path
+                ),
+            }
+        }
+    };
+    Ok(ConfigJsonVc::cell(Some(value)))
+}
@@ -0,0 +1,220 @@
+use std::{collections::HashMap, future::join, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, bail, Context, Result};
+use owo_colors::OwoColorize;
+use turbo_tasks::{util::FormatDuration, RawVc, TransientInstance, TransientValue, TurboTasks};
+use turbo_tasks_fs::{DiskFileSystemVc, FileSystemVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{preset::Preset, transition::TransitionsByNameVc, ModuleAssetContextVc};
+use turbopack_cli_utils::issue::{ConsoleUi, ConsoleUiVc};
+use turbopack_core::{
+    chunk::{ChunkGroupVc, ChunkableAssetVc},
+    context::AssetContextVc,
+    issue::IssueSeverity,
+    source_asset::SourceAssetVc,
+};
+use turbopack_dev_server::{
+    fs::DevServerFileSystemVc,
+    html::DevHtmlAssetVc,
+    source::{
+        asset_graph::AssetGraphContentSourceVc, combined::CombinedContentSource,
+        static_assets::StaticAssetsContentSourceVc, ContentSourceVc,
+    },
+    DevServer,
+};
+use turbopack_ecmascript::EcmascriptModuleAssetVc;
+
+use crate::{
+    arguments::DevArguments,
+    config::{config_path, load_config, Config, INTERMEDIATE_OUTPUT_DIR},
+    log_options, project_dir, register,
+};
+
+/// Starts a dev server for the project, which compiles the entries on demand
+/// and recompiles them when files change.
+pub async fn start_server(arguments: &DevArguments) -> Result<()> {
+    let common = &arguments.common;
+    let project_dir = project_dir(common)?;
+    let config_path = config_path(&project_dir, common.config.as_deref())?;
+    let entries = common.entries.clone();
+    let preset = common.preset.map(|preset| preset.to_string());
+    let log_options = log_options(common, project_dir.clone(), IssueSeverity::Fatal);
+    let project_dir = project_dir
+        .to_str()
+        .context("project directory contains invalid characters")?
+        .to_string();
+
+    register();
+    let tt = TurboTasks::new(MemoryBackend::new());
+    // Files are watched, so executions outdated by a newer change can be
+    // aborted
+    tt.cancel_outdated_executions(true);
+    let tt_clone = tt.clone();
+
+    let console_ui = Arc::new(ConsoleUi::new(log_options));
+    let console_ui_to_dev_server = console_ui.clone();
+    let server = DevServer::listen(
+        tt.clone(),
+        move || {
+            source(
+                project_dir.clone(),
+                config_path.clone(),
+                entries.clone(),
+                preset.clone(),
+                console_ui.clone().into(),
+            )
+        },
+        (arguments.hostname, arguments.port).into(),
+        console_ui_to_dev_server,
+    )?;
+
+    let index_uri = if server.addr.ip().is_loopback() || server.addr.ip().is_unspecified() {
+        format!("http://localhost:{}", server.addr.port())
+    } else {
+        format!("http://{}", server.addr)
+    };
+    println!(
+        "{} - started server on {}:{}, url: {}",
+        "ready".green(),
+        server.addr.ip(),
+        server.addr.port(),
+        index_uri
+    );
+    if !arguments.no_open {
+        let _ = webbrowser::open(&index_uri);
+    }
+
+    let stats_future = async move {
+        loop {
+            let (elapsed, _) = tt_clone
+                .get_or_wait_update_info(Duration::from_millis(100))
+                .await;
+            println!(
+                "{event_type} - updated in {elapsed}",
+                event_type = "event".purple(),
+                elapsed = FormatDuration(elapsed),
+            );
+        }
+    };
+
+    join!(stats_future, async { server.future.await.unwrap() }).await;
+
+    Ok(())
+}
+
+async fn handle_issues<T: Into<RawVc>>(source: T, console_ui: ConsoleUiVc) -> Result<()> {
+    let state = console_ui
+        .group_and_display_issues(TransientValue::new(source.into()))
+        .await?;
+
+    if state.has_fatal {
+        Err(anyhow!("Fatal issue(s) occurred"))
+    } else {
+        Ok(())
+    }
+}
+
+#[turbo_tasks::function]
+async fn project_fs(project_dir: &str, console_ui: ConsoleUiVc) -> Result<FileSystemVc> {
+    let disk_fs = DiskFileSystemVc::new("project".to_string(), project_dir.to_string());
+    handle_issues(disk_fs, console_ui).await?;
+    disk_fs.await?.start_watching()?;
+    Ok(disk_fs.into())
+}
+
+#[turbo_tasks::function]
+async fn output_fs(project_dir: &str, console_ui: ConsoleUiVc) -> Result<FileSystemVc> {
+    let disk_fs = DiskFileSystemVc::new("output".to_string(), project_dir.to_string());
+    handle_issues(disk_fs, console_ui).await?;
+    Ok(disk_fs.into())
+}
+
+#[turbo_tasks::function]
+async fn source(
+    project_dir: String,
+    config_path: Option<String>,
+    entries: Vec<String>,
+    preset: Option<String>,
+    console_ui: TransientInstance<ConsoleUi>,
+) -> Result<ContentSourceVc> {
+    let console_ui = (*console_ui).clone().cell();
+    let project_root = project_fs(&project_dir, console_ui).root();
+    let output_root = output_fs(&project_dir, console_ui).root();
+
+    // The config is loaded from the watched project directory, so the source
+    // is recreated when the config file changes
+    let config = load_config(
+        project_root,
+        config_path,
+        output_root.join(INTERMEDIATE_OUTPUT_DIR),
+    );
+    handle_issues(config, console_ui).await?;
+    let config = match &*config.await? {
+        Some(config) => Config::from_json(config)?,
+        None => bail!("config file can't be evaluated"),
+    };
+    let entries = crate::entries(&entries, &config);
+    let preset = match preset {
+        Some(preset) => preset.parse::<Preset>()?,
+        None => config.build.preset,
+    };
+    let browserslist_query = config.build.browserslist_query;
+
+    let dev_server_fs = DevServerFileSystemVc::new().as_file_system();
+    let dev_server_root = dev_server_fs.root();
+
+    let environment = preset.environment(&browserslist_query);
+    let mut resolve_options_context = preset.resolve_options_context(environment);
+    resolve_options_context.prewarm_lockfile_packages = true;
+    let context: AssetContextVc = ModuleAssetContextVc::new(
+        TransitionsByNameVc::cell(HashMap::new()),
+        environment,
+        preset
+            .module_options_context(environment, config.build.es_version)
+            .cell(),
+        resolve_options_context.cell(),
+    )
+    .into();
+    let runtime_entries = preset.runtime_entries(context, project_root);
+    let chunking_context = preset
+        .chunking_context(
+            project_root,
+            dev_server_root,
+            dev_server_root.join("_chunks"),
+            dev_server_root.join("_assets"),
+        )
+        .build();
+
+    let mut chunk_groups = Vec::new();
+    for entry in entries.iter() {
+        let source = SourceAssetVc::new(project_root.join(&entry.request));
+        let module = context.process(source.into());
+        let chunk = if let Some(ecmascript) = EcmascriptModuleAssetVc::resolve_from(module).await? {
+            ecmascript.as_evaluated_chunk(chunking_context, Some(runtime_entries))
+        } else if let Some(chunkable) = ChunkableAssetVc::resolve_from(module).await? {
+            chunkable.as_chunk(chunking_context)
+        } else {
+            bail!(
+                "entry {} is not chunkable, so it can't be served",
+                entry.request
+            );
+        };
+        chunk_groups.push(ChunkGroupVc::from_chunk(chunk));
+    }
+
+    let entry_asset = DevHtmlAssetVc::new(dev_server_root.join("index.html"), chunk_groups).into();
+    let entry_source: ContentSourceVc =
+        AssetGraphContentSourceVc::new_lazy(dev_server_root, entry_asset).into();
+    let static_source =
+        StaticAssetsContentSourceVc::new(String::new(), project_root.join("public")).into();
+    let source = CombinedContentSource {
+        sources: vec![static_source, entry_source],
+    }
+    .cell()
+    .into();
+
+    handle_issues(dev_server_fs, console_ui).await?;
+    handle_issues(entry_source, console_ui).await?;
+
+    Ok(source)
+}
@@ -0,0 +1,76 @@
+#![feature(future_join)]
+#![feature(min_specialization)]
+
+pub mod arguments;
+pub mod build;
+pub mod config;
+pub mod dev;
+
+use std::{collections::HashMap, env::current_dir, path::PathBuf};
+
+use anyhow::{Context, Result};
+use arguments::CommonArguments;
+use config::Config;
+use turbopack::build::BuildEntry;
+use turbopack_cli_utils::{
+    issue::LogOptions,
+    reporter::{terminal_width, OutputFormat},
+};
+use turbopack_core::issue::IssueSeverity;
+
+/// The entry which is used when neither the command line nor the config file
+/// has any.
+const DEFAULT_ENTRY: &str = "src/index.js";
+
+pub fn register() {
+    next_core::register();
+    turbopack::register();
+    turbopack_dev_server::register();
+    include!(concat!(env!("OUT_DIR"), "/register.rs"));
+}
+
+fn project_dir(arguments: &CommonArguments) -> Result<PathBuf> {
+    arguments
+        .dir
+        .as_ref()
+        .map(|dir| dir.canonicalize())
+        .unwrap_or_else(current_dir)
+        .context("project directory can't be found")
+}
+
+/// The entries passed on the command line, or else the entries of the config
+/// file.
+fn entries(entries: &[String], config: &Config) -> Vec<BuildEntry> {
+    if !entries.is_empty() {
+        entries
+            .iter()
+            .map(|request| BuildEntry::new(request.clone()))
+            .collect()
+    } else if !config.entries.is_empty() {
+        config.entries.clone()
+    } else {
+        vec![BuildEntry::new(DEFAULT_ENTRY.to_string())]
+    }
+}
+
+fn log_options(
+    arguments: &CommonArguments,
+    project_dir: PathBuf,
+    fail_level: IssueSeverity,
+) -> LogOptions {
+    LogOptions {
+        current_dir: project_dir,
+        show_all: arguments.show_all,
+        log_detail: arguments.log_detail,
+        log_level: arguments
+            .log_level
+            .map_or_else(|| IssueSeverity::Warning, |l| l.0),
+        fail_level,
+        code_overrides: HashMap::new(),
+        path_overrides: Vec::new(),
+        format: OutputFormat::from_env(),
+        terminal_width: terminal_width(),
+        messages: Default::default(),
+        diagnostics_file: None,
+    }
+}
@@ -0,0 +1,14 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use clap::Parser;
+use turbopack_cli::arguments::Arguments;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    match Arguments::parse() {
+        Arguments::Build(arguments) => turbopack_cli::build::build(&arguments).await,
+        Arguments::Dev(arguments) => turbopack_cli::dev::start_server(&arguments).await,
+        Arguments::Analyze(arguments) => turbopack_cli::build::analyze(&arguments).await,
+    }
+}
@@ -0,0 +1,67 @@
+use std::{
+    env::temp_dir,
+    fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+/// Runs `turbopack build` for the fixture `tests/fixtures/<fixture>` with a
+/// temporary output directory, which is returned with the output of the
+/// command.
+fn build_fixture(fixture: &str) -> (Output, PathBuf) {
+    let project_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(fixture);
+    let output_dir = temp_dir().join(format!("turbopack-cli-{fixture}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&output_dir);
+    let output = Command::new(env!("CARGO_BIN_EXE_turbopack"))
+        .arg("build")
+        .arg("--dir")
+        .arg(&project_dir)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .output()
+        .unwrap();
+    (output, output_dir)
+}
+
+#[test]
+fn builds_the_entries_of_the_config() {
+    let (output, output_dir) = build_fixture("app");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "turbopack build failed\n{stdout}\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let manifest = fs::read_to_string(output_dir.join("manifest.json")).unwrap();
+    let scripts = fs::read_dir(&output_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == "js")
+        })
+        .collect::<Vec<_>>();
+    assert!(!scripts.is_empty());
+    for script in scripts.iter() {
+        let file_name = script.file_name().unwrap().to_string_lossy();
+        assert!(
+            manifest.contains(&*file_name),
+            "{file_name} is in the manifest"
+        );
+        assert!(stdout.contains(&*file_name), "{file_name} is listed");
+    }
+    assert!(scripts.iter().any(|script| fs::read_to_string(script)
+        .unwrap()
+        .contains("The answer is")));
+}
+
+#[test]
+fn fails_on_errors() {
+    let (output, _) = build_fixture("resolve_error");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("fail level"));
+}
@@ -0,0 +1,136 @@
+use std::{env::temp_dir, fs, path::PathBuf};
+
+use turbopack::build::BuildEntry;
+use turbopack_cli::config::{config_path, Config};
+
+/// Creates an empty project directory for a test with `files` in it.
+fn project_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = temp_dir().join(format!(
+        "turbopack-cli-config-{name}-{}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (path, content) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    dir.canonicalize().unwrap()
+}
+
+#[tokio::test]
+async fn uses_the_defaults_without_a_config_file() {
+    let dir = project_dir("none", &[]);
+
+    assert_eq!(Config::load(&dir, None).await.unwrap(), Config::default());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn loads_toml_configs() {
+    let dir = project_dir(
+        "toml",
+        &[(
+            "turbopack.config.toml",
+            "entries = [\"src/index.js\"]\nminify = true\n",
+        )],
+    );
+
+    let config = Config::load(&dir, None).await.unwrap();
+    assert_eq!(
+        config.entries,
+        vec![BuildEntry::new("src/index.js".to_string())]
+    );
+    assert!(config.build.minify);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn prefers_javascript_configs() {
+    let dir = project_dir(
+        "js",
+        &[
+            (
+                "turbopack.config.js",
+                r#"module.exports = async () => ({ entries: require("./entries.js") });"#,
+            ),
+            (
+                "entries.js",
+                r#"module.exports = [{ name: "main", request: "src/app.js" }];"#,
+            ),
+            (
+                "turbopack.config.json",
+                r#"{ "entries": ["src/index.js"] }"#,
+            ),
+        ],
+    );
+
+    let config = Config::load(&dir, None).await.unwrap();
+    assert_eq!(
+        config.entries,
+        vec![BuildEntry {
+            name: "main".to_string(),
+            request: "src/app.js".to_string(),
+            filename: None,
+        }]
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn loads_the_passed_config_file() {
+    let dir = project_dir(
+        "passed",
+        &[
+            ("configs/custom.json", r#"{ "entries": ["src/custom.js"] }"#),
+            (
+                "turbopack.config.json",
+                r#"{ "entries": ["src/index.js"] }"#,
+            ),
+        ],
+    );
+
+    let config = Config::load(&dir, Some(&dir.join("configs/custom.json")))
+        .await
+        .unwrap();
+    assert_eq!(
+        config.entries,
+        vec![BuildEntry::new("src/custom.js".to_string())]
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn reports_errors_of_javascript_configs() {
+    let dir = project_dir(
+        "error",
+        &[(
+            "turbopack.config.js",
+            "throw new Error(\"the config is broken\");\n",
+        )],
+    );
+
+    let error = Config::load(&dir, None).await.unwrap_err();
+    assert!(
+        format!("{error:#}").contains("the config is broken"),
+        "{error:#}"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn requires_config_files_within_the_project_directory() {
+    let dir = temp_dir().join("turbopack-cli-config-project");
+
+    assert_eq!(
+        config_path(&dir, Some(&dir.join("configs/custom.json"))).unwrap(),
+        Some("configs/custom.json".to_string())
+    );
+    assert!(config_path(&dir, Some(&temp_dir().join("custom.json"))).is_err());
+}
@@ -0,0 +1,252 @@
+use std::{io::ErrorKind, path::PathBuf, sync::Arc};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use tokio::fs;
+use turbo_tasks::TransientInstance;
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbo_tasks_hash::{encode_hex_string, hash_sha256};
+
+use crate::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    reference::AssetReferencesVc,
+};
+
+/// A store of blobs which can be shared between machines, e. g. between CI
+/// runners, so outputs computed on one of them are reused by the others.
+/// Implementations can store the blobs in a directory, an HTTP cache, S3 or
+/// behind gRPC.
+///
+/// Keys are content-addressed, they are derived from all inputs of the
+/// cached value by [cache_key]. Backends don't need to verify the values,
+/// [RemoteCache] does.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    /// Returns the blob stored for `key`, or `None` when there is none.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Stores `value` for `key`, replacing an existing blob. Values are only
+    /// stored again when the stored one is corrupted.
+    async fn put(&self, key: &str, value: Vec<u8>) -> Result<()>;
+}
+
+/// Derives a content-addressed key from a namespace, which should identify
+/// the kind of value and the version of the code computing it, and all
+/// inputs which affect the value.
+pub fn cache_key(namespace: &str, inputs: &[&[u8]]) -> String {
+    let mut buffer = Vec::new();
+    for input in [namespace.as_bytes()].iter().chain(inputs.iter()) {
+        // The length keeps the boundaries between inputs unambiguous
+        buffer.extend_from_slice(&(input.len() as u64).to_le_bytes());
+        buffer.extend_from_slice(input);
+    }
+    encode_hex_string(&hash_sha256(&buffer))
+}
+
+/// The length of the SHA-256 digest stored in front of every value.
+const DIGEST_LENGTH: usize = 32;
+
+/// Stores values in a [CacheBackend] together with their digest and verifies
+/// them when they are read. Values which are corrupted, e. g. by a partial
+/// upload, are treated as missing, so they are computed and stored again.
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new")]
+pub struct RemoteCache {
+    #[turbo_tasks(trace_ignore, debug_ignore)]
+    backend: Arc<dyn CacheBackend>,
+    /// Identifies the version of the tools and their configuration, so values
+    /// computed by other versions are not used.
+    namespace: String,
+}
+
+#[turbo_tasks::value_impl]
+impl RemoteCacheVc {
+    #[turbo_tasks::function]
+    pub fn new(backend: TransientInstance<Arc<dyn CacheBackend>>, namespace: String) -> Self {
+        Self::cell(RemoteCache {
+            backend: (*backend).clone(),
+            namespace,
+        })
+    }
+
+    /// A cache storing the values in the same backend, whose keys are
+    /// separated from the keys of this cache by `scope`, e. g. the digest of a
+    /// config file the values depend on.
+    #[turbo_tasks::function]
+    pub async fn scoped(self, scope: String) -> Result<Self> {
+        let this = self.await?;
+        Ok(Self::cell(RemoteCache {
+            backend: this.backend.clone(),
+            namespace: cache_key(&this.namespace, &[scope.as_bytes()]),
+        }))
+    }
+
+    /// Returns the content stored for `key`, or `content` when there is none,
+    /// which is stored then. `content` is not computed when it's cached.
+    #[turbo_tasks::function]
+    pub async fn content(self, key: &str, content: AssetContentVc) -> Result<AssetContentVc> {
+        let this = self.await?;
+        if let Some(value) = this.get(key).await? {
+            return Ok(File::from(value).into());
+        }
+        if let AssetContent::File(file) = &*content.await? {
+            if let FileContent::Content(file) = &*file.await? {
+                this.put(key, file.content().to_vec()).await?;
+            }
+        }
+        Ok(content)
+    }
+}
+
+impl RemoteCache {
+    /// Returns the verified value stored for `key`.
+    pub async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .backend
+            .get(&self.backend_key(key))
+            .await?
+            .and_then(verify))
+    }
+
+    /// Stores `value` for `key` together with its digest.
+    pub async fn put(&self, key: &str, value: Vec<u8>) -> Result<()> {
+        self.backend
+            .put(&self.backend_key(key), with_digest(value))
+            .await
+    }
+
+    /// The key of the value for `key` in the namespace of the cache.
+    fn backend_key(&self, key: &str) -> String {
+        cache_key(&self.namespace, &[key.as_bytes()])
+    }
+}
+
+/// A source transformed by source transforms, whose content is stored in a
+/// [RemoteCache], keyed by the path and the content of the original source.
+/// The transforms only run when the cache has no content for the source.
+#[turbo_tasks::value]
+pub struct RemoteCachedSource {
+    source: AssetVc,
+    transformed: AssetVc,
+    cache: RemoteCacheVc,
+}
+
+#[turbo_tasks::value_impl]
+impl RemoteCachedSourceVc {
+    #[turbo_tasks::function]
+    pub fn new(source: AssetVc, transformed: AssetVc, cache: RemoteCacheVc) -> Self {
+        Self::cell(RemoteCachedSource {
+            source,
+            transformed,
+            cache,
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for RemoteCachedSource {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.transformed.path()
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        if let AssetContent::File(file) = &*self.source.content().await? {
+            if let FileContent::Content(file) = &*file.await? {
+                let path = self.source.path().await?;
+                let key = cache_key("source-transforms", &[path.path.as_bytes(), file.content()]);
+                return Ok(self.cache.content(&key, self.transformed.content()));
+            }
+        }
+        Ok(self.transformed.content())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        self.transformed.references()
+    }
+}
+
+fn with_digest(value: Vec<u8>) -> Vec<u8> {
+    let mut stored = hash_sha256(&value).to_vec();
+    stored.extend(value);
+    stored
+}
+
+/// Returns the value of a stored blob, or `None` when it doesn't match its
+/// digest.
+fn verify(mut stored: Vec<u8>) -> Option<Vec<u8>> {
+    if stored.len() < DIGEST_LENGTH {
+        return None;
+    }
+    let value = stored.split_off(DIGEST_LENGTH);
+    (stored[..] == hash_sha256(&value)[..]).then_some(value)
+}
+
+/// A [CacheBackend] storing the blobs as files in a directory, e. g. on a
+/// network share. Values are written to a temporary file first, so readers
+/// never see partially written values.
+pub struct DiskCacheBackend {
+    root: PathBuf,
+}
+
+impl DiskCacheBackend {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl CacheBackend for DiskCacheBackend {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match fs::read(self.root.join(key)).await {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("reading cache entry {key}")),
+        }
+    }
+
+    /// Replaces an existing file, which is only the case when it was
+    /// corrupted, as values are only stored when there is no valid one.
+    async fn put(&self, key: &str, value: Vec<u8>) -> Result<()> {
+        fs::create_dir_all(&self.root)
+            .await
+            .with_context(|| format!("creating cache directory {}", self.root.display()))?;
+        let temp_path = self.root.join(format!("{key}.{}.tmp", std::process::id()));
+        fs::write(&temp_path, value)
+            .await
+            .with_context(|| format!("writing cache entry {key}"))?;
+        fs::rename(&temp_path, self.root.join(key))
+            .await
+            .with_context(|| format!("writing cache entry {key}"))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cache_key, verify, with_digest};
+
+    #[test]
+    fn cache_keys_separate_inputs() {
+        assert_ne!(
+            cache_key("test", &[b"ab".as_slice(), b"c".as_slice()]),
+            cache_key("test", &[b"a".as_slice(), b"bc".as_slice()])
+        );
+        assert_eq!(
+            cache_key("test", &[b"a".as_slice()]),
+            cache_key("test", &[b"a".as_slice()])
+        );
+    }
+
+    #[test]
+    fn verifies_values() {
+        let stored = with_digest(b"value".to_vec());
+        assert_eq!(verify(stored.clone()), Some(b"value".to_vec()));
+
+        let mut corrupted = stored;
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert_eq!(verify(corrupted), None);
+        assert_eq!(verify(b"short".to_vec()), None);
+    }
+}
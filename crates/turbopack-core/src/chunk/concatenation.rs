@@ -0,0 +1,41 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use turbo_tasks_fs::FileSystemPathVc;
+
+use crate::asset::AssetVc;
+
+/// Describes whether a module shares its scope with other modules.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub enum ModuleConcatenation {
+    /// The module is emitted on its own.
+    None,
+    /// The listed modules are concatenated into the scope of the module, in
+    /// the order they are evaluated in. They are emitted before the code of
+    /// the module itself.
+    Root(Vec<AssetVc>),
+    /// The module is concatenated into the scope of the module importing it.
+    /// It is not emitted on its own and has no exports object.
+    Inner,
+}
+
+/// The [ModuleConcatenation] of all modules of a module graph, keyed by the
+/// path of the module.
+#[turbo_tasks::value(transparent)]
+pub struct ConcatenatedModules(IndexMap<FileSystemPathVc, ModuleConcatenation>);
+
+#[turbo_tasks::value_impl]
+impl ConcatenatedModulesVc {
+    /// Returns the concatenation of the module at `path`. Modules which are
+    /// not part of the analysed module graph are not concatenated.
+    #[turbo_tasks::function]
+    pub async fn concatenation(self, path: FileSystemPathVc) -> Result<ModuleConcatenationVc> {
+        let path = path.resolve().await?;
+        Ok(self
+            .await?
+            .get(&path)
+            .cloned()
+            .unwrap_or(ModuleConcatenation::None)
+            .cell())
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::{bail, Result};
+use indexmap::IndexMap;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
+
+use crate::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    reference::AssetReferencesVc,
+};
+
+/// The number of hex digits of the content hash in file names.
+pub const CONTENT_HASH_LENGTH: usize = 8;
+
+/// Computes a content hash of `content`, which stays the same as long as the
+/// content doesn't change.
+#[turbo_tasks::function]
+pub async fn content_hash(content: AssetContentVc) -> Result<StringVc> {
+    let hash = match &*content.await? {
+        AssetContent::File(file) => match &*file.await? {
+            FileContent::Content(file) => hash_xxh3_hash64(file.content()),
+            FileContent::NotFound => bail!("content hash of a missing file can't be computed"),
+        },
+        AssetContent::Redirect { target, .. } => hash_xxh3_hash64(target.as_bytes()),
+    };
+    Ok(StringVc::cell(
+        encode_hex(hash)[..CONTENT_HASH_LENGTH].to_string(),
+    ))
+}
+
+/// An [Asset] which is placed at `[name].[contenthash].[ext]` next to its
+/// source, so its file name changes whenever its content changes.
+///
+/// The content of the source refers to other chunks by their original paths,
+/// so the page has to map these paths to the emitted files (see
+/// [OutputManifestAsset]).
+#[turbo_tasks::value]
+pub struct ContentHashedAsset {
+    source: AssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ContentHashedAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(source: AssetVc) -> Self {
+        Self::cell(ContentHashedAsset { source })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for ContentHashedAsset {
+    #[turbo_tasks::function]
+    async fn path(&self) -> Result<FileSystemPathVc> {
+        let hash = content_hash(self.source.content()).await?;
+        Ok(self.source.path().append_to_stem(&format!(".{hash}")))
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        self.source.content()
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        self.source.references()
+    }
+}
+
+/// Maps the paths of chunks, relative to the output root, to the content
+/// hashed files they were emitted to.
+#[turbo_tasks::value(transparent)]
+pub struct OutputFiles(IndexMap<String, String>);
+
+/// A JSON manifest of [OutputFiles]. HTML generation can use it to refer to
+/// the emitted chunks, and pass it to the runtime as `TURBOPACK_CHUNK_FILES`
+/// so chunks loaded at runtime are requested from the emitted files.
+#[turbo_tasks::value]
+pub struct OutputManifestAsset {
+    path: FileSystemPathVc,
+    files: OutputFilesVc,
+}
+
+#[turbo_tasks::value_impl]
+impl OutputManifestAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(path: FileSystemPathVc, files: OutputFilesVc) -> Self {
+        Self::cell(OutputManifestAsset { path, files })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for OutputManifestAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let json = serde_json::to_string_pretty(&*self.files.await?)?;
+        Ok(File::from(json).into())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
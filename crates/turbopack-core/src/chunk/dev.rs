@@ -3,12 +3,24 @@ use turbo_tasks::{
     primitives::{BoolVc, StringVc},
     Value, ValueToString,
 };
-use turbo_tasks_fs::FileSystemPathVc;
+use turbo_tasks_fs::{FileSystemPathOptionVc, FileSystemPathVc};
 use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
 
-use super::{ChunkingContext, ChunkingContextVc};
-use crate::asset::AssetVc;
+use super::{
+    concatenation::{ConcatenatedModulesVc, ModuleConcatenation, ModuleConcatenationVc},
+    used_exports::{ExportUsage, ExportUsageVc, UsedExportsVc},
+    vendor::VendorChunkGroupsVc,
+    ChunkFormat, ChunkFormatVc, ChunkSizeLimits, ChunkSizeLimitsVc, ChunkingContext,
+    ChunkingContextVc, CommonChunkOptions, MinifyOptions, ModuleEvaluation, ModuleEvaluationVc,
+    ModuleIdStrategy, ModuleIdStrategyVc, OptionCommonChunkOptionsVc, OptionMinifyOptionsVc,
+    OptionPublicPathVc, PublicPath,
+};
+use crate::{
+    asset::AssetVc,
+    source_map::{SourceMapEmission, SourceMapEmissionVc, SourceMapFormat, SourceMapFormatVc},
+};
 
+#[derive(Clone)]
 pub struct DevChunkingContextBuilder {
     context: DevChunkingContext,
 }
@@ -19,6 +31,40 @@ impl DevChunkingContextBuilder {
         self
     }
 
+    pub fn async_chunk_deduplication(mut self) -> Self {
+        self.context.enable_async_chunk_deduplication = true;
+        self
+    }
+
+    /// Emits chunks with failing modules replaced by code throwing their
+    /// error, instead of failing the whole chunk.
+    pub fn error_tolerant_emit(mut self) -> Self {
+        self.context.enable_error_tolerant_emit = true;
+        self
+    }
+
+    /// Fails the build with fatal issues when references can't be generated,
+    /// instead of emitting code which throws at runtime.
+    pub fn strict_reference_validation(mut self) -> Self {
+        self.context.enable_strict_reference_validation = true;
+        self
+    }
+
+    /// Emits the runtime as a chunk of its own, which is shared by all chunk
+    /// groups, instead of including it in every chunk evaluating entries.
+    pub fn runtime_chunk(mut self) -> Self {
+        self.context.enable_runtime_chunk = true;
+        self
+    }
+
+    /// Places all modules into the chunks of their chunk group's entry, even
+    /// the modules imported with `import()`, so no chunks are loaded at
+    /// runtime. For runtimes like edge runtimes which can't load chunks.
+    pub fn single_file_output(mut self) -> Self {
+        self.context.enable_single_file_output = true;
+        self
+    }
+
     pub fn layer(mut self, layer: &str) -> Self {
         self.context.layer = (!layer.is_empty()).then(|| layer.to_string());
         self
@@ -29,6 +75,95 @@ impl DevChunkingContextBuilder {
         self
     }
 
+    /// Omits exports which are not used according to `used_exports` during
+    /// code generation.
+    pub fn used_exports(mut self, used_exports: UsedExportsVc) -> Self {
+        self.context.used_exports = Some(used_exports);
+        self
+    }
+
+    /// Concatenates modules into the scope of the modules importing them
+    /// according to `concatenated_modules` during code generation.
+    pub fn concatenated_modules(mut self, concatenated_modules: ConcatenatedModulesVc) -> Self {
+        self.context.concatenated_modules = Some(concatenated_modules);
+        self
+    }
+
+    /// Splits the modules matching `vendor_chunk_groups` into separate vendor
+    /// chunks.
+    pub fn vendor_chunk_groups(mut self, vendor_chunk_groups: VendorChunkGroupsVc) -> Self {
+        self.context.vendor_chunk_groups = Some(vendor_chunk_groups);
+        self
+    }
+
+    /// Extracts modules which are loaded by multiple async chunks of the same
+    /// chunk into common chunks.
+    pub fn common_chunks(mut self, options: CommonChunkOptions) -> Self {
+        self.context.common_chunks = Some(options);
+        self
+    }
+
+    /// Merges chunks with less than `size` bytes of code with other small
+    /// chunks of the same chunk group.
+    pub fn min_chunk_size(mut self, size: usize) -> Self {
+        self.context.chunk_size_limits.min_chunk_size = Some(size);
+        self
+    }
+
+    /// Splits chunks with more than `size` bytes of code along the module
+    /// graph.
+    pub fn max_chunk_size(mut self, size: usize) -> Self {
+        self.context.chunk_size_limits.max_chunk_size = Some(size);
+        self
+    }
+
+    /// Sets when the modules of evaluated chunk groups are evaluated. Defaults
+    /// to [ModuleEvaluation::Deferred].
+    pub fn module_evaluation(mut self, module_evaluation: ModuleEvaluation) -> Self {
+        self.context.module_evaluation = module_evaluation;
+        self
+    }
+
+    /// Sets how module ids are derived. Defaults to [ModuleIdStrategy::Named].
+    pub fn module_id_strategy(mut self, module_id_strategy: ModuleIdStrategy) -> Self {
+        self.context.module_id_strategy = module_id_strategy;
+        self
+    }
+
+    /// Sets how the source maps of chunks are emitted. Defaults to
+    /// [SourceMapFormat::Sectioned].
+    pub fn source_map_format(mut self, source_map_format: SourceMapFormat) -> Self {
+        self.context.source_map_format = source_map_format;
+        self
+    }
+
+    /// Sets where the source maps of chunks are emitted. Defaults to
+    /// [SourceMapEmission::External].
+    pub fn source_map_emission(mut self, source_map_emission: SourceMapEmission) -> Self {
+        self.context.source_map_emission = source_map_emission;
+        self
+    }
+
+    /// Minifies the code of chunks according to `options`.
+    pub fn minify(mut self, options: MinifyOptions) -> Self {
+        self.context.minify = Some(options);
+        self
+    }
+
+    /// Sets the URL prefix chunks and assets are loaded from. Defaults to the
+    /// root of the server.
+    pub fn public_path(mut self, public_path: PublicPath) -> Self {
+        self.context.public_path = Some(public_path);
+        self
+    }
+
+    /// Sets how chunks register themselves with the runtime. Defaults to
+    /// [ChunkFormat::Global].
+    pub fn chunk_format(mut self, chunk_format: ChunkFormat) -> Self {
+        self.context.chunk_format = chunk_format;
+        self
+    }
+
     pub fn build(self) -> ChunkingContextVc {
         DevChunkingContextVc::new(Value::new(self.context)).into()
     }
@@ -56,6 +191,41 @@ pub struct DevChunkingContext {
     layer: Option<String>,
     /// Enable HMR for this chunking
     enable_hot_module_replacement: bool,
+    /// Omit chunk items from async chunks which are loaded by the importing
+    /// chunk already
+    enable_async_chunk_deduplication: bool,
+    /// Report references which can't be generated as fatal issues
+    enable_strict_reference_validation: bool,
+    /// Replace failing modules with code throwing their error
+    enable_error_tolerant_emit: bool,
+    /// Emit the runtime as a chunk of its own
+    enable_runtime_chunk: bool,
+    /// Place all modules into the chunk of the entry
+    enable_single_file_output: bool,
+    /// Usage of module exports, used to omit unused exports
+    used_exports: Option<UsedExportsVc>,
+    /// Modules which are concatenated into a single scope
+    concatenated_modules: Option<ConcatenatedModulesVc>,
+    /// Modules which are split into vendor chunks
+    vendor_chunk_groups: Option<VendorChunkGroupsVc>,
+    /// Extraction of modules shared by async chunks into common chunks
+    common_chunks: Option<CommonChunkOptions>,
+    /// Limits for the size of chunks
+    chunk_size_limits: ChunkSizeLimits,
+    /// When modules of evaluated chunk groups are evaluated
+    module_evaluation: ModuleEvaluation,
+    /// How module ids are derived
+    module_id_strategy: ModuleIdStrategy,
+    /// How the source maps of chunks are emitted
+    source_map_format: SourceMapFormat,
+    /// Where the source maps of chunks are emitted
+    source_map_emission: SourceMapEmission,
+    /// Minification of the code of chunks
+    minify: Option<MinifyOptions>,
+    /// The URL prefix chunks and assets are loaded from
+    public_path: Option<PublicPath>,
+    /// How chunks register themselves with the runtime
+    chunk_format: ChunkFormat,
 }
 
 impl DevChunkingContextVc {
@@ -74,6 +244,23 @@ impl DevChunkingContextVc {
                 asset_root_path,
                 layer: None,
                 enable_hot_module_replacement: false,
+                enable_async_chunk_deduplication: false,
+                enable_strict_reference_validation: false,
+                enable_error_tolerant_emit: false,
+                enable_runtime_chunk: false,
+                enable_single_file_output: false,
+                used_exports: None,
+                concatenated_modules: None,
+                vendor_chunk_groups: None,
+                common_chunks: None,
+                chunk_size_limits: Default::default(),
+                module_evaluation: ModuleEvaluation::Deferred,
+                module_id_strategy: ModuleIdStrategy::Named,
+                source_map_format: SourceMapFormat::Sectioned,
+                source_map_emission: SourceMapEmission::External,
+                minify: None,
+                public_path: None,
+                chunk_format: ChunkFormat::Global,
             },
         }
     }
@@ -159,6 +346,15 @@ impl ChunkingContext for DevChunkingContext {
 
     #[turbo_tasks::function]
     async fn can_be_in_same_chunk(&self, asset_a: AssetVc, asset_b: AssetVc) -> Result<BoolVc> {
+        // Modules of different vendor chunks are always split
+        if let Some(vendor_chunk_groups) = self.vendor_chunk_groups {
+            let vendor_a = vendor_chunk_groups.vendor_chunk_name(asset_a.path());
+            let vendor_b = vendor_chunk_groups.vendor_chunk_name(asset_b.path());
+            if *vendor_a.await? != *vendor_b.await? {
+                return Ok(BoolVc::cell(false));
+            }
+        }
+
         let parent_dir = asset_a.path().parent().await?;
 
         let path = asset_b.path().await?;
@@ -182,11 +378,106 @@ impl ChunkingContext for DevChunkingContext {
         BoolVc::cell(self.enable_hot_module_replacement)
     }
 
+    #[turbo_tasks::function]
+    fn is_async_chunk_deduplication_enabled(&self) -> BoolVc {
+        BoolVc::cell(self.enable_async_chunk_deduplication)
+    }
+
+    #[turbo_tasks::function]
+    fn is_strict_reference_validation_enabled(&self) -> BoolVc {
+        BoolVc::cell(self.enable_strict_reference_validation)
+    }
+
+    #[turbo_tasks::function]
+    fn is_error_tolerant_emit_enabled(&self) -> BoolVc {
+        BoolVc::cell(self.enable_error_tolerant_emit)
+    }
+
+    #[turbo_tasks::function]
+    fn is_single_file_output_enabled(&self) -> BoolVc {
+        BoolVc::cell(self.enable_single_file_output)
+    }
+
+    #[turbo_tasks::function]
+    fn runtime_chunk_path(&self) -> FileSystemPathOptionVc {
+        FileSystemPathOptionVc::cell(
+            self.enable_runtime_chunk
+                .then(|| self.chunk_root_path.join("turbopack-runtime.js")),
+        )
+    }
+
     #[turbo_tasks::function]
     fn layer(&self) -> StringVc {
         StringVc::cell(self.layer.clone().unwrap_or_default())
     }
 
+    #[turbo_tasks::function]
+    fn export_usage(&self, path: FileSystemPathVc) -> ExportUsageVc {
+        match self.used_exports {
+            Some(used_exports) => used_exports.usage(path),
+            None => ExportUsage::All.cell(),
+        }
+    }
+
+    #[turbo_tasks::function]
+    fn module_concatenation(&self, path: FileSystemPathVc) -> ModuleConcatenationVc {
+        match self.concatenated_modules {
+            Some(concatenated_modules) => concatenated_modules.concatenation(path),
+            None => ModuleConcatenation::None.cell(),
+        }
+    }
+
+    #[turbo_tasks::function]
+    fn vendor_chunk_groups(&self) -> VendorChunkGroupsVc {
+        self.vendor_chunk_groups
+            .unwrap_or_else(|| VendorChunkGroupsVc::cell(Vec::new()))
+    }
+
+    #[turbo_tasks::function]
+    fn common_chunk_options(&self) -> OptionCommonChunkOptionsVc {
+        OptionCommonChunkOptionsVc::cell(self.common_chunks)
+    }
+
+    #[turbo_tasks::function]
+    fn chunk_size_limits(&self) -> ChunkSizeLimitsVc {
+        self.chunk_size_limits.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn module_evaluation(&self) -> ModuleEvaluationVc {
+        self.module_evaluation.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn module_id_strategy(&self) -> ModuleIdStrategyVc {
+        self.module_id_strategy.clone().cell()
+    }
+
+    #[turbo_tasks::function]
+    fn source_map_format(&self) -> SourceMapFormatVc {
+        self.source_map_format.clone().cell()
+    }
+
+    #[turbo_tasks::function]
+    fn source_map_emission(&self) -> SourceMapEmissionVc {
+        self.source_map_emission.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn minify_options(&self) -> OptionMinifyOptionsVc {
+        OptionMinifyOptionsVc::cell(self.minify)
+    }
+
+    #[turbo_tasks::function]
+    fn public_path(&self) -> OptionPublicPathVc {
+        OptionPublicPathVc::cell(self.public_path.clone())
+    }
+
+    #[turbo_tasks::function]
+    fn chunk_format(&self) -> ChunkFormatVc {
+        self.chunk_format.cell()
+    }
+
     #[turbo_tasks::function]
     async fn with_layer(self_vc: DevChunkingContextVc, layer: &str) -> Result<ChunkingContextVc> {
         let mut context = self_vc.await?.clone_value();
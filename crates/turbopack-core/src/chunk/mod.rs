@@ -1,10 +1,22 @@
+pub mod concatenation;
+pub mod content_hash;
 pub mod dev;
 pub mod optimize;
+pub mod resource_hints;
+pub mod size_report;
+pub mod stats;
+pub mod used_exports;
+pub mod vendor;
+
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    fmt::{Debug, Display},
+    str::FromStr,
+};
 
-use std::{collections::VecDeque, fmt::Debug};
-
-use anyhow::{anyhow, Result};
-use indexmap::IndexSet;
+use anyhow::{anyhow, bail, Result};
+use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 use turbo_tasks::{
     debug::ValueDebugFormat,
@@ -12,14 +24,21 @@ use turbo_tasks::{
     trace::TraceRawVcs,
     ValueToString, ValueToStringVc,
 };
-use turbo_tasks_fs::FileSystemPathVc;
+use turbo_tasks_fs::{FileSystemPathOptionVc, FileSystemPathVc};
 use turbo_tasks_hash::DeterministicHash;
 
-use self::optimize::optimize;
+use self::{
+    concatenation::{ModuleConcatenation, ModuleConcatenationVc},
+    optimize::optimize,
+    used_exports::{ExportUsage, ExportUsageVc},
+    vendor::VendorChunkGroupsVc,
+};
 use crate::{
-    asset::{Asset, AssetVc, AssetsVc},
+    asset::{Asset, AssetOptionVc, AssetVc, AssetsVc},
+    issue::{code_gen::CodeGenerationIssue, IssueSeverity},
     reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
     resolve::{ResolveResult, ResolveResultVc},
+    source_map::{SourceMapEmission, SourceMapEmissionVc, SourceMapFormat, SourceMapFormatVc},
 };
 
 /// A module id, which can be a number or string
@@ -35,6 +54,32 @@ pub enum ModuleId {
 #[turbo_tasks::value(transparent, shared)]
 pub struct ModuleIds(Vec<ModuleIdVc>);
 
+/// Controls how module ids are derived from the readable names of chunk
+/// items.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Hash, PartialOrd, Ord)]
+pub enum ModuleIdStrategy {
+    /// The readable names are used as ids. Useful during development.
+    Named,
+    /// Short hashes of the readable names are used as ids. They stay the same
+    /// across builds as long as the modules don't move, which is useful for
+    /// long-term caching. The ids of the map are used for the names it
+    /// contains, which lengthens the hashes of colliding names.
+    Hashed(Option<HashedModuleIdsVc>),
+    /// The numbers the readable names are mapped to are used as ids. Modules
+    /// missing in the map use their readable name.
+    Numeric(NumericModuleIdsVc),
+}
+
+/// Maps the readable names of chunk items to numeric module ids.
+#[turbo_tasks::value(transparent)]
+pub struct NumericModuleIds(IndexMap<String, u32>);
+
+/// Maps the readable names of chunk items to hashed module ids which don't
+/// collide with each other.
+#[turbo_tasks::value(transparent)]
+pub struct HashedModuleIds(IndexMap<String, String>);
+
 /// A context for the chunking that influences the way chunks are created
 #[turbo_tasks::value_trait]
 pub trait ChunkingContext {
@@ -55,6 +100,306 @@ pub trait ChunkingContext {
     }
 
     fn with_layer(&self, layer: &str) -> ChunkingContextVc;
+
+    /// Returns which exports of the module at `path` are used. Code generation
+    /// can omit exports which are not used.
+    fn export_usage(&self, _path: FileSystemPathVc) -> ExportUsageVc {
+        ExportUsage::All.cell()
+    }
+
+    fn module_evaluation(&self) -> ModuleEvaluationVc {
+        ModuleEvaluation::Deferred.cell()
+    }
+
+    fn module_id_strategy(&self) -> ModuleIdStrategyVc {
+        ModuleIdStrategy::Named.cell()
+    }
+
+    /// Returns how the source maps of chunks are emitted.
+    fn source_map_format(&self) -> SourceMapFormatVc {
+        SourceMapFormat::Sectioned.cell()
+    }
+
+    /// Returns where the source maps of chunks are emitted.
+    fn source_map_emission(&self) -> SourceMapEmissionVc {
+        SourceMapEmission::External.cell()
+    }
+
+    /// Returns how the code of chunks is minified. Chunks are emitted
+    /// unminified when this is `None`.
+    fn minify_options(&self) -> OptionMinifyOptionsVc {
+        OptionMinifyOptionsVc::cell(None)
+    }
+
+    /// Returns the URL prefix chunks and assets are loaded from. They are
+    /// loaded from the root of the server when this is `None`.
+    fn public_path(&self) -> OptionPublicPathVc {
+        OptionPublicPathVc::cell(None)
+    }
+
+    /// Returns how chunks register themselves with the runtime.
+    fn chunk_format(&self) -> ChunkFormatVc {
+        ChunkFormat::Global.cell()
+    }
+
+    /// Whether asynchronously loaded chunks omit the chunk items which are
+    /// already loaded by the chunk importing them. These modules are looked up
+    /// in the runtime registry instead of being duplicated.
+    fn is_async_chunk_deduplication_enabled(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// Whether references which can't be generated, e. g. unresolvable
+    /// requests, are reported as fatal issues instead of only throwing when
+    /// they are evaluated at runtime.
+    fn is_strict_reference_validation_enabled(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// Whether chunks are still emitted when some of their modules fail, e. g.
+    /// because of a resolve error or a crashing transform. The failing modules
+    /// are replaced with code throwing a descriptive error when they are
+    /// evaluated, so unrelated modules keep working. Useful in development.
+    fn is_error_tolerant_emit_enabled(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// Whether all modules are placed into the chunk of the entry, including
+    /// the ones loaded in parallel or imported asynchronously. Asynchronous
+    /// imports resolve without loading chunks then.
+    fn is_single_file_output_enabled(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    /// Returns the path of the chunk containing the runtime, when the runtime
+    /// is emitted as a chunk of its own. Otherwise, every chunk evaluating
+    /// entries includes the runtime.
+    fn runtime_chunk_path(&self) -> FileSystemPathOptionVc {
+        FileSystemPathOptionVc::cell(None)
+    }
+
+    /// Returns whether the module at `path` is concatenated with other modules
+    /// into a single scope during code generation.
+    fn module_concatenation(&self, _path: FileSystemPathVc) -> ModuleConcatenationVc {
+        ModuleConcatenation::None.cell()
+    }
+
+    /// Returns the groups of modules which are split into vendor chunks.
+    fn vendor_chunk_groups(&self) -> VendorChunkGroupsVc {
+        VendorChunkGroupsVc::cell(Vec::new())
+    }
+
+    /// Returns whether modules shared by multiple async chunks are extracted
+    /// into common chunks, and how.
+    fn common_chunk_options(&self) -> OptionCommonChunkOptionsVc {
+        OptionCommonChunkOptionsVc::cell(None)
+    }
+
+    /// Returns the limits for the size of chunks, which the chunk optimizer
+    /// enforces by merging and splitting chunks.
+    fn chunk_size_limits(&self) -> ChunkSizeLimitsVc {
+        ChunkSizeLimits::default().cell()
+    }
+}
+
+/// Limits for the size of chunks, in bytes of generated code.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Default, Copy, Clone, Hash, PartialOrd, Ord)]
+pub struct ChunkSizeLimits {
+    /// Chunks smaller than this are merged with other small chunks of the
+    /// same chunk group, to avoid many requests for tiny chunks.
+    pub min_chunk_size: Option<usize>,
+    /// Chunks larger than this are split along the module graph: modules
+    /// which are allowed to be placed in a parallel chunk are moved into
+    /// chunks of their own.
+    pub max_chunk_size: Option<usize>,
+}
+
+/// Controls the extraction of modules which are loaded by multiple async chunks
+/// of the same chunk into common chunks. The common chunks are loaded in
+/// parallel to the async chunks using them.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord)]
+pub struct CommonChunkOptions {
+    /// Modules are only extracted when at least this many async chunks load
+    /// them.
+    pub min_async_chunks: usize,
+    /// Common chunks with less code than this (in bytes) are not extracted,
+    /// their modules stay in the async chunks instead.
+    pub min_size: usize,
+    /// The maximum number of chunks requested for an async chunk: the async
+    /// chunk itself and its common chunks. The largest common chunks are
+    /// preferred when there are more.
+    pub max_parallel_requests: usize,
+}
+
+impl Default for CommonChunkOptions {
+    fn default() -> Self {
+        Self {
+            min_async_chunks: 2,
+            min_size: 20_000,
+            max_parallel_requests: 6,
+        }
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionCommonChunkOptions(Option<CommonChunkOptions>);
+
+/// Options for minifying the code of chunks, e. g. in production builds.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord)]
+pub struct MinifyOptions {
+    /// Whether local variables and functions are renamed to shorter names.
+    pub mangle: bool,
+    /// How often the compressor runs over the code. Later passes can find
+    /// optimizations enabled by earlier ones, at the cost of build time. The
+    /// code isn't compressed when this is 0.
+    pub compress_passes: usize,
+    /// Whether the names of classes are preserved, for code which relies on
+    /// `constructor.name`.
+    pub keep_classnames: bool,
+}
+
+impl Default for MinifyOptions {
+    fn default() -> Self {
+        Self {
+            mangle: true,
+            compress_passes: 1,
+            keep_classnames: false,
+        }
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionMinifyOptions(Option<MinifyOptions>);
+
+/// The URL prefix the output of a chunking context is served from. Chunks and
+/// assets are loaded from the prefix joined with their path relative to the
+/// output root.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Hash, PartialOrd, Ord)]
+pub enum PublicPath {
+    /// A prefix known at build time, e. g. `/assets/` or
+    /// `https://cdn.example.com/`.
+    Fixed(String),
+    /// The prefix is derived at runtime from the URL of the script containing
+    /// the runtime, so the output can be served from any location.
+    Auto,
+}
+
+impl PublicPath {
+    /// Returns the prefix of a [PublicPath::Fixed] ending with a `/`, so paths
+    /// relative to the output root can be appended.
+    pub fn fixed_prefix(&self) -> Option<String> {
+        match self {
+            PublicPath::Fixed(prefix) if prefix.is_empty() || prefix.ends_with('/') => {
+                Some(prefix.clone())
+            }
+            PublicPath::Fixed(prefix) => Some(format!("{prefix}/")),
+            PublicPath::Auto => None,
+        }
+    }
+}
+
+impl Display for PublicPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PublicPath::Fixed(prefix) => f.write_str(prefix),
+            PublicPath::Auto => f.write_str("auto"),
+        }
+    }
+}
+
+impl FromStr for PublicPath {
+    type Err = Infallible;
+
+    /// Parses `auto` as [PublicPath::Auto], everything else is a fixed prefix.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "auto" => PublicPath::Auto,
+            prefix => PublicPath::Fixed(prefix.to_string()),
+        })
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionPublicPath(Option<PublicPath>);
+
+/// Controls when the modules of an evaluated chunk group are evaluated.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord)]
+pub enum ModuleEvaluation {
+    /// Only the entries are evaluated once all chunks are loaded. All other
+    /// modules are evaluated when they are imported for the first time. This
+    /// keeps the startup time of large apps low.
+    Deferred,
+    /// All modules which are loaded with the chunk group are evaluated once
+    /// all chunks are loaded, dependencies before their dependents, followed by
+    /// the entries.
+    Eager,
+}
+
+/// How the chunks of a chunking context are registered with the runtime. The
+/// SystemJS and AMD formats allow hosts to load the chunks with their module
+/// loader, the evaluated chunks of chunk groups then export the exports of
+/// their last entry.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkFormat {
+    /// Chunks are loaded with `<script>` tags and push themselves onto the
+    /// global `TURBOPACK` list.
+    Global,
+    /// Chunks are `System.register` modules, which the runtime loads with
+    /// `System.import`.
+    SystemJs,
+    /// Chunks are anonymous AMD modules calling `define`, which the runtime
+    /// loads with `require`. As AMD modules can't be evaluated asynchronously,
+    /// the exports of evaluated chunks are a promise of the entry exports.
+    Amd,
+}
+
+impl ChunkFormat {
+    pub const ALL: [ChunkFormat; 3] =
+        [ChunkFormat::Global, ChunkFormat::SystemJs, ChunkFormat::Amd];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChunkFormat::Global => "global",
+            ChunkFormat::SystemJs => "systemjs",
+            ChunkFormat::Amd => "amd",
+        }
+    }
+}
+
+impl Default for ChunkFormat {
+    fn default() -> Self {
+        ChunkFormat::Global
+    }
+}
+
+impl Display for ChunkFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ChunkFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match ChunkFormat::ALL
+            .into_iter()
+            .find(|format| format.name() == s)
+        {
+            Some(format) => Ok(format),
+            None => bail!(
+                "unknown chunk format \"{s}\", expected one of {}",
+                ChunkFormat::ALL.map(|format| format.name()).join(", ")
+            ),
+        }
+    }
 }
 
 /// An [Asset] that can be converted into a [Chunk].
@@ -88,12 +433,15 @@ impl ChunkGroupVc {
     /// Lists all chunks that are in this chunk group.
     /// These chunks need to be loaded to fulfill that chunk group.
     /// All chunks should be loaded in parallel.
+    ///
+    /// Chunks are listed in the order they are referenced, starting with the
+    /// entry. Chunk types where the order matters, like CSS, rely on that.
     #[turbo_tasks::function]
     pub async fn chunks(self) -> Result<ChunksVc> {
         let mut chunks = IndexSet::new();
 
-        let mut queue = vec![self.await?.entry];
-        while let Some(chunk) = queue.pop() {
+        let mut queue = VecDeque::from([self.await?.entry]);
+        while let Some(chunk) = queue.pop_front() {
             let chunk = chunk.resolve().await?;
             if chunks.insert(chunk) {
                 for r in chunk.references().await?.iter() {
@@ -102,7 +450,7 @@ impl ChunkGroupVc {
                             let result = r.resolve_reference();
                             for a in result.primary_assets().await?.iter() {
                                 if let Some(chunk) = ChunkVc::resolve_from(a).await? {
-                                    queue.push(chunk);
+                                    queue.push_back(chunk);
                                 }
                             }
                         }
@@ -137,7 +485,25 @@ impl ValueToString for ChunkGroup {
 /// returns true, all referenced assets (if they are [Chunk]s) are placed in the
 /// same chunk group.
 #[turbo_tasks::value_trait]
-pub trait Chunk: Asset + ValueToString {}
+pub trait Chunk: Asset + ValueToString {
+    /// The chunk items placed into the chunk, e. g. to report the modules of
+    /// the chunk. Chunks which don't consist of chunk items, like runtime
+    /// chunks, don't have any.
+    fn chunk_items(&self) -> ChunkItemsVc {
+        ChunkItemsVc::cell(Vec::new())
+    }
+
+    /// The size of the code of each module of the chunk before minification,
+    /// e. g. for size reports.
+    fn module_sizes(&self) -> ModuleSizesVc {
+        ModuleSizesVc::cell(IndexMap::new())
+    }
+}
+
+/// Maps the modules of a chunk to the size of their code in the chunk, in
+/// bytes.
+#[turbo_tasks::value(transparent)]
+pub struct ModuleSizes(IndexMap<AssetVc, u64>);
 
 /// see [Chunk] for explanation
 #[turbo_tasks::value_trait]
@@ -291,9 +657,12 @@ pub struct ChunkContentResult<I> {
 #[async_trait::async_trait]
 pub trait FromChunkableAsset: ChunkItem + Sized + Debug {
     async fn from_asset(context: ChunkingContextVc, asset: AssetVc) -> Result<Option<Self>>;
+    /// Creates a loader item for `asset`, which is imported asynchronously
+    /// by the module `parent_entry`.
     async fn from_async_asset(
         context: ChunkingContextVc,
         asset: ChunkableAssetVc,
+        parent_entry: AssetVc,
     ) -> Result<Option<(Self, ChunkableAssetVc)>>;
 }
 
@@ -315,12 +684,18 @@ pub async fn chunk_content<I: FromChunkableAsset>(
     chunk_content_internal(context, entry, additional_entries, false).await
 }
 
+/// The `parent` of work items is the asset the references belong to, which
+/// is the parent entry of async loaders created for them.
 enum ChunkContentWorkItem {
-    AssetReferences(AssetReferencesVc),
+    AssetReferences {
+        references: AssetReferencesVc,
+        parent: AssetVc,
+    },
     Assets {
         assets: AssetsVc,
         reference: AssetReferenceVc,
         chunking_type: ChunkingType,
+        parent: AssetVc,
     },
 }
 
@@ -338,33 +713,50 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
     let mut queue = VecDeque::new();
 
     let chunk_item = I::from_asset(context, entry).await?.unwrap();
-    queue.push_back(ChunkContentWorkItem::AssetReferences(
-        chunk_item.references(),
-    ));
+    queue.push_back(ChunkContentWorkItem::AssetReferences {
+        references: chunk_item.references(),
+        parent: entry,
+    });
     chunk_items.push(chunk_item);
     processed_assets.insert(entry);
 
     if let Some(additional_entries) = additional_entries {
         for entry in &*additional_entries.await? {
             let chunk_item = I::from_asset(context, *entry).await?.unwrap();
-            queue.push_back(ChunkContentWorkItem::AssetReferences(
-                chunk_item.references(),
-            ));
+            queue.push_back(ChunkContentWorkItem::AssetReferences {
+                references: chunk_item.references(),
+                parent: *entry,
+            });
             chunk_items.push(chunk_item);
             processed_assets.insert(*entry);
         }
     }
 
+    let error_tolerant = *context.is_error_tolerant_emit_enabled().await?;
+    let single_file = *context.is_single_file_output_enabled().await?;
+    // Async chunks which omit modules only load the chunks of their manifest,
+    // the chunks of the whole asset would never be loaded
+    let async_chunks_omit_modules = *context.is_async_chunk_deduplication_enabled().await?
+        || context.common_chunk_options().await?.is_some();
+
     'outer: while let Some(item) = queue.pop_front() {
         match item {
-            ChunkContentWorkItem::AssetReferences(item) => {
-                for r in item.await?.iter() {
+            ChunkContentWorkItem::AssetReferences { references, parent } => {
+                let references = match references.await {
+                    Ok(references) => references,
+                    // The chunk item failing to compute its references fails to generate its
+                    // code too, which reports the error
+                    Err(_) if error_tolerant => continue,
+                    Err(err) => return Err(err),
+                };
+                for r in references.iter() {
                     if let Some(pc) = ChunkableAssetReferenceVc::resolve_from(r).await? {
                         if let Some(chunking_type) = *pc.chunking_type(context).await? {
                             queue.push_back(ChunkContentWorkItem::Assets {
                                 assets: r.resolve_reference().primary_assets(),
                                 reference: *r,
                                 chunking_type,
+                                parent,
                             });
                             continue;
                         }
@@ -376,6 +768,7 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                 assets,
                 reference,
                 chunking_type,
+                parent,
             } => {
                 // It's important to temporary store these results in these variables
                 // so that we can cancel to complete list of assets by that references together
@@ -392,8 +785,27 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                 // not loaded in parallel
                 let mut inner_chunk_groups = Vec::new();
 
+                let assets = match assets.await {
+                    Ok(assets) => assets,
+                    Err(err) if error_tolerant => {
+                        CodeGenerationIssue {
+                            severity: IssueSeverity::Error.into(),
+                            path: entry.path(),
+                            title: StringVc::cell(format!(
+                                "Failed to resolve {}",
+                                reference.to_string().await?
+                            )),
+                            message: StringVc::cell(format!("{err:?}")),
+                            source: *reference.issue_source().await?,
+                        }
+                        .cell()
+                        .as_issue()
+                        .emit();
+                        continue;
+                    }
+                    Err(err) => return Err(err),
+                };
                 for asset in assets
-                    .await?
                     .iter()
                     .filter(|asset| processed_assets.insert(**asset))
                 {
@@ -410,7 +822,7 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                     match chunking_type {
                         ChunkingType::Placed => {
                             if let Some(chunk_item) = I::from_asset(context, *asset).await? {
-                                inner_chunk_items.push(chunk_item);
+                                inner_chunk_items.push((chunk_item, *asset));
                             } else {
                                 return Err(anyhow!(
                                     "Asset {} was requested to be placed into the same chunk, but \
@@ -420,15 +832,23 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                             }
                         }
                         ChunkingType::Parallel => {
+                            if single_file {
+                                if let Some(chunk_item) = I::from_asset(context, *asset).await? {
+                                    inner_chunk_items.push((chunk_item, *asset));
+                                    continue;
+                                }
+                            }
                             let chunk = chunkable_asset.as_chunk(context);
                             inner_chunks.push(chunk);
                         }
                         ChunkingType::PlacedOrParallel => {
                             // heuristic for being in the same chunk
-                            if !split && *context.can_be_in_same_chunk(entry, *asset).await? {
+                            if single_file
+                                || !split && *context.can_be_in_same_chunk(entry, *asset).await?
+                            {
                                 // chunk item, chunk or other asset?
                                 if let Some(chunk_item) = I::from_asset(context, *asset).await? {
-                                    inner_chunk_items.push(chunk_item);
+                                    inner_chunk_items.push((chunk_item, *asset));
                                     continue;
                                 }
                             }
@@ -442,13 +862,23 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                         }
                         ChunkingType::SeparateAsync => {
                             if let Some((manifest_loader_item, manifest_chunk)) =
-                                I::from_async_asset(context, chunkable_asset).await?
+                                I::from_async_asset(context, chunkable_asset, parent).await?
                             {
-                                inner_chunk_items.push(manifest_loader_item);
+                                inner_chunk_items.push((manifest_loader_item, *asset));
+                                if single_file {
+                                    // The loader imports the asset from the same chunk
+                                    if let Some(chunk_item) = I::from_asset(context, *asset).await?
+                                    {
+                                        inner_chunk_items.push((chunk_item, *asset));
+                                        continue;
+                                    }
+                                }
                                 inner_chunk_groups
                                     .push(ChunkGroupVc::from_asset(manifest_chunk, context));
-                                inner_chunk_groups
-                                    .push(ChunkGroupVc::from_asset(chunkable_asset, context));
+                                if !async_chunks_omit_modules {
+                                    inner_chunk_groups
+                                        .push(ChunkGroupVc::from_asset(chunkable_asset, context));
+                                }
                             } else {
                                 external_asset_references.push(reference);
                                 continue 'outer;
@@ -459,10 +889,11 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
 
                 let prev_chunk_items = chunk_items.len();
 
-                for chunk_item in inner_chunk_items {
-                    queue.push_back(ChunkContentWorkItem::AssetReferences(
-                        chunk_item.references(),
-                    ));
+                for (chunk_item, asset) in inner_chunk_items {
+                    queue.push_back(ChunkContentWorkItem::AssetReferences {
+                        references: chunk_item.references(),
+                        parent: asset,
+                    });
                     chunk_items.push(chunk_item);
                 }
                 chunks.extend(inner_chunks);
@@ -472,6 +903,7 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                 // This will hurt performance in many aspects.
                 let chunk_items_count = chunk_items.len();
                 if !split
+                    && !single_file
                     && prev_chunk_items != chunk_items_count
                     && chunk_items_count > 5000
                     && prev_chunk_items > 1
@@ -499,6 +931,13 @@ pub trait ChunkItem {
     /// TODO(alexkirsz) This should have a default impl that returns empty
     /// references.
     fn references(&self) -> AssetReferencesVc;
+
+    /// The asset the [ChunkItem] was created from. Chunk items which aren't
+    /// created from an asset of the module graph, like loaders, don't have
+    /// one.
+    fn asset(&self) -> AssetOptionVc {
+        AssetOptionVc::cell(None)
+    }
 }
 
 #[turbo_tasks::value(transparent)]
@@ -0,0 +1,192 @@
+use anyhow::Result;
+use indexmap::{IndexMap, IndexSet};
+use serde::Serialize;
+use turbo_tasks_fs::{File, FileSystemPathVc};
+
+use super::{content_hash::OutputFilesVc, ChunkGroupVc};
+use crate::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    reference::AssetReferencesVc,
+};
+
+/// The chunk groups of the entries of an application by the name of the
+/// entry.
+#[turbo_tasks::value(transparent)]
+pub struct EntryChunkGroups(IndexMap<String, ChunkGroupVc>);
+
+/// A JSON manifest of resource hints for the entries of an application, so
+/// frameworks can emit `<link rel="preconnect">`, `<link rel="dns-prefetch">`
+/// and `<link rel="preload">` tags for them.
+///
+/// When the public path points at another origin, e. g. a CDN, the origin is
+/// listed in `preconnect` and `dnsPrefetch`. The chunks of each entry are
+/// listed in the order they should be preloaded: stylesheets before scripts,
+/// each in the order of the chunk group.
+#[turbo_tasks::value]
+pub struct ResourceHintsManifestAsset {
+    path: FileSystemPathVc,
+    output_root: FileSystemPathVc,
+    public_path: String,
+    entries: EntryChunkGroupsVc,
+    files: OutputFilesVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ResourceHintsManifestAssetVc {
+    /// Creates the manifest at `path`. Chunks are served from `public_path`
+    /// joined with their path relative to `output_root`, or with the content
+    /// hashed file they were emitted to according to `files`.
+    #[turbo_tasks::function]
+    pub fn new(
+        path: FileSystemPathVc,
+        output_root: FileSystemPathVc,
+        public_path: String,
+        entries: EntryChunkGroupsVc,
+        files: OutputFilesVc,
+    ) -> Self {
+        Self::cell(ResourceHintsManifestAsset {
+            path,
+            output_root,
+            public_path,
+            entries,
+            files,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceHints<'a> {
+    public_path: &'a str,
+    preconnect: Vec<String>,
+    dns_prefetch: Vec<String>,
+    entries: IndexMap<&'a str, Vec<PreloadHint>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PreloadHint {
+    href: String,
+    #[serde(rename = "as")]
+    destination: &'static str,
+    /// Cross-origin preloads need to be fetched in CORS mode to be reused by
+    /// the script and link tags loading them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cross_origin: Option<&'static str>,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for ResourceHintsManifestAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let output_root = self.output_root.await?;
+        let files = self.files.await?;
+        let origin = cross_origin(&self.public_path);
+        let cross_origin = origin.as_ref().map(|_| "anonymous");
+        let public_path = if self.public_path.is_empty() || self.public_path.ends_with('/') {
+            self.public_path.clone()
+        } else {
+            format!("{}/", self.public_path)
+        };
+
+        let chunk_groups = self.entries.await?;
+        let mut entries = IndexMap::new();
+        for (name, chunk_group) in chunk_groups.iter() {
+            let mut styles = IndexSet::new();
+            let mut scripts = IndexSet::new();
+            let mut other = IndexSet::new();
+            for chunk in chunk_group.chunks().await?.iter() {
+                let path = chunk.path().await?;
+                let relative = match output_root.get_path_to(&path) {
+                    Some(relative) => relative,
+                    None => continue,
+                };
+                let file = files.get(relative).map_or(relative, |file| file.as_str());
+                let href = format!("{public_path}{file}");
+                match path.extension() {
+                    Some("css") => styles.insert(href),
+                    Some("js" | "mjs") => scripts.insert(href),
+                    _ => other.insert(href),
+                };
+            }
+            let hints = [("style", styles), ("script", scripts), ("fetch", other)]
+                .into_iter()
+                .flat_map(|(destination, hrefs)| {
+                    hrefs.into_iter().map(move |href| PreloadHint {
+                        href,
+                        destination,
+                        cross_origin,
+                    })
+                })
+                .collect();
+            entries.insert(name.as_str(), hints);
+        }
+
+        let origins = origin.into_iter().collect::<Vec<_>>();
+        let json = serde_json::to_string_pretty(&ResourceHints {
+            public_path: &self.public_path,
+            preconnect: origins.clone(),
+            dns_prefetch: origins,
+            entries,
+        })?;
+        Ok(File::from(json).into())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+/// Returns the origin of `public_path` when it points at another origin, e. g.
+/// `https://cdn.example.com` for `https://cdn.example.com/assets/`.
+/// Protocol-relative paths keep their missing scheme.
+fn cross_origin(public_path: &str) -> Option<String> {
+    let (scheme, rest) = if let Some(rest) = public_path.strip_prefix("//") {
+        ("", rest)
+    } else {
+        let (scheme, rest) = public_path.split_once("://")?;
+        if !matches!(scheme, "http" | "https") {
+            return None;
+        }
+        (scheme, rest)
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if host.is_empty() {
+        return None;
+    }
+    Some(if scheme.is_empty() {
+        format!("//{host}")
+    } else {
+        format!("{scheme}://{host}")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cross_origin;
+
+    #[test]
+    fn origins_of_public_paths() {
+        assert_eq!(
+            cross_origin("https://cdn.example.com/assets/"),
+            Some("https://cdn.example.com".to_string())
+        );
+        assert_eq!(
+            cross_origin("http://localhost:4000"),
+            Some("http://localhost:4000".to_string())
+        );
+        assert_eq!(
+            cross_origin("//cdn.example.com/_next/"),
+            Some("//cdn.example.com".to_string())
+        );
+        assert_eq!(cross_origin("/_next/"), None);
+        assert_eq!(cross_origin(""), None);
+        assert_eq!(cross_origin("file:///tmp/"), None);
+    }
+}
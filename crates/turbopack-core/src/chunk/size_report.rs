@@ -0,0 +1,247 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::Serialize;
+use turbo_tasks::{Value, ValueToString};
+use turbo_tasks_fs::{File, FileSystemPathVc};
+
+use super::{
+    resource_hints::EntryChunkGroupsVc,
+    stats::{content_size, referenced_output_assets},
+    ChunkVc,
+};
+use crate::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    reference::AssetReferencesVc,
+    resolve::duplicates::package_root,
+};
+
+/// The format of a [SizeReportAsset].
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(PartialOrd, Ord, Debug, Clone, Copy, Hash)]
+pub enum SizeReportFormat {
+    /// The tree of sizes as JSON.
+    Json,
+    /// A self-contained HTML page showing the tree of sizes as treemap.
+    Html,
+}
+
+/// A report of the sizes of the chunks of an application, broken down by the
+/// packages and modules placed into them, to find out what makes the output
+/// large.
+///
+/// Every node lists the `size` of its part of the emitted files, i. e. after
+/// minification when it's enabled, and the `unminifiedSize` of its code. The
+/// minified code can't be attributed to modules, so the `size` of packages and
+/// modules is estimated from their share of the unminified code of the chunk.
+#[turbo_tasks::value]
+pub struct SizeReportAsset {
+    path: FileSystemPathVc,
+    output_root: FileSystemPathVc,
+    entries: EntryChunkGroupsVc,
+    format: SizeReportFormat,
+}
+
+#[turbo_tasks::value_impl]
+impl SizeReportAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        path: FileSystemPathVc,
+        output_root: FileSystemPathVc,
+        entries: EntryChunkGroupsVc,
+        format: Value<SizeReportFormat>,
+    ) -> Self {
+        Self::cell(SizeReportAsset {
+            path,
+            output_root,
+            entries,
+            format: format.into_value(),
+        })
+    }
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct SizeNode {
+    name: String,
+    size: u64,
+    unminified_size: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<SizeNode>,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for SizeReportAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let output_root = self.output_root.await?;
+        let mut root = SizeNode {
+            name: output_root.path.clone(),
+            ..Default::default()
+        };
+        for asset in referenced_output_assets(self.entries, self.output_root)
+            .await?
+            .iter()
+        {
+            let chunk = if let Some(chunk) = ChunkVc::resolve_from(asset).await? {
+                chunk
+            } else {
+                continue;
+            };
+            let path = asset.path().await?;
+            let name = match output_root.get_path_to(&path) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let size = *content_size(asset.content()).await?;
+
+            let module_sizes = chunk.module_sizes().await?;
+            let unminified_size = module_sizes.values().sum::<u64>();
+            let mut packages = IndexMap::<String, SizeNode>::new();
+            for (module, &module_unminified_size) in module_sizes.iter() {
+                let module_path = module.path().await?;
+                let package = package_name(&module_path.path).unwrap_or("(app)");
+                let module_size = if unminified_size == 0 {
+                    0
+                } else {
+                    size * module_unminified_size / unminified_size
+                };
+                let package = packages
+                    .entry(package.to_string())
+                    .or_insert_with(|| SizeNode {
+                        name: package.to_string(),
+                        ..Default::default()
+                    });
+                package.size += module_size;
+                package.unminified_size += module_unminified_size;
+                package.children.push(SizeNode {
+                    name: module.path().to_string().await?.clone_value(),
+                    size: module_size,
+                    unminified_size: module_unminified_size,
+                    children: Vec::new(),
+                });
+            }
+
+            let mut packages = packages
+                .into_iter()
+                .map(|(_, package)| package)
+                .collect::<Vec<_>>();
+            packages.sort_by(|a, b| b.size.cmp(&a.size));
+            for package in packages.iter_mut() {
+                package.children.sort_by(|a, b| b.size.cmp(&a.size));
+            }
+            root.size += size;
+            root.unminified_size += unminified_size;
+            root.children.push(SizeNode {
+                name,
+                size,
+                unminified_size,
+                children: packages,
+            });
+        }
+        root.children.sort_by(|a, b| b.size.cmp(&a.size));
+
+        let content = match self.format {
+            SizeReportFormat::Json => serde_json::to_string_pretty(&root)?,
+            SizeReportFormat::Html => {
+                // `</script>` in a module name must not end the script tag
+                let json = serde_json::to_string(&root)?.replace("</", "<\\/");
+                HTML_REPORT.replace("/*REPORT*/", &json)
+            }
+        };
+        Ok(File::from(content).into())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+/// Returns the name of the npm package `path` belongs to, e. g. `react` for
+/// `node_modules/react/index.js` or `@next/env` for
+/// `node_modules/@next/env/dist/index.js`. Nested packages belong to the
+/// innermost package.
+fn package_name(path: &str) -> Option<&str> {
+    package_root(path).map(|(_, name)| name)
+}
+
+const HTML_REPORT: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Bundle size report</title>
+<style>
+  body { margin: 0; font: 12px sans-serif; }
+  #treemap { position: absolute; inset: 0; }
+  .node { position: absolute; box-sizing: border-box; overflow: hidden; border: 1px solid #fff; padding: 2px; }
+</style>
+</head>
+<body>
+<div id="treemap"></div>
+<script>
+const report = /*REPORT*/;
+
+function formatSize(size) {
+  return size < 1024 ? size + " B" : (size / 1024).toFixed(1) + " kB";
+}
+
+// Lays out the children of a node in slices along the longer side of its
+// rectangle, recursively.
+function render(node, parent, x, y, width, height, depth) {
+  const element = document.createElement("div");
+  element.className = "node";
+  element.style.left = x + "%";
+  element.style.top = y + "%";
+  element.style.width = width + "%";
+  element.style.height = height + "%";
+  element.style.background = "hsl(" + ((depth * 67) % 360) + ", 60%, " + (85 - depth * 8) + "%)";
+  element.title = node.name + "\n" + formatSize(node.size) + " (" + formatSize(node.unminifiedSize) + " unminified)";
+  element.textContent = node.name;
+  parent.appendChild(element);
+
+  const total = (node.children || []).reduce((sum, child) => sum + child.size, 0);
+  if (total === 0) {
+    return;
+  }
+  const horizontal = element.clientWidth >= element.clientHeight;
+  let offset = 0;
+  for (const child of node.children) {
+    const share = (child.size / total) * 100;
+    if (horizontal) {
+      render(child, element, offset, 12, share, 88, depth + 1);
+    } else {
+      render(child, element, 0, 12 + offset * 0.88, 100, share * 0.88, depth + 1);
+    }
+    offset += share;
+  }
+}
+
+render(report, document.getElementById("treemap"), 0, 0, 100, 100, 0);
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::package_name;
+
+    #[test]
+    fn package_names_of_paths() {
+        assert_eq!(package_name("node_modules/react/index.js"), Some("react"));
+        assert_eq!(
+            package_name("node_modules/@next/env/dist/index.js"),
+            Some("@next/env")
+        );
+        assert_eq!(
+            package_name("node_modules/a/node_modules/b/lib/b.js"),
+            Some("b")
+        );
+        assert_eq!(package_name("src/index.js"), None);
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::Result;
+use indexmap::{map::Entry, IndexMap, IndexSet};
+use serde::Serialize;
+use turbo_tasks::{primitives::U64Vc, TryJoinIterExt, ValueToString};
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+
+use super::{resource_hints::EntryChunkGroupsVc, ChunkVc};
+use crate::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc, AssetsVc},
+    reference::AssetReferencesVc,
+};
+
+/// The size of `content` in bytes. Missing files and redirects have no size.
+#[turbo_tasks::function]
+pub async fn content_size(content: AssetContentVc) -> Result<U64Vc> {
+    let size = match &*content.await? {
+        AssetContent::File(file) => match &*file.await? {
+            FileContent::Content(file) => file.content().len() as u64,
+            FileContent::NotFound => 0,
+        },
+        AssetContent::Redirect { .. } => 0,
+    };
+    Ok(U64Vc::cell(size))
+}
+
+/// The assets in `output_root` which are referenced by the chunks of
+/// `entries`, including the chunks, in the order they are referenced. Assets
+/// outside of `output_root` are not followed, they aren't part of the output.
+#[turbo_tasks::function]
+pub async fn referenced_output_assets(
+    entries: EntryChunkGroupsVc,
+    output_root: FileSystemPathVc,
+) -> Result<AssetsVc> {
+    let output_root = output_root.await?;
+    let mut queue = VecDeque::new();
+    for chunk_group in entries.await?.values() {
+        for chunk in chunk_group.chunks().await?.iter() {
+            queue.push_back(chunk.as_asset());
+        }
+    }
+
+    let mut assets = IndexSet::new();
+    while let Some(asset) = queue.pop_front() {
+        let asset = asset.resolve().await?;
+        if !asset.path().await?.is_inside(&output_root) || !assets.insert(asset) {
+            continue;
+        }
+        for reference in asset.references().await?.iter() {
+            for asset in reference.resolve_reference().primary_assets().await?.iter() {
+                queue.push_back(*asset);
+            }
+        }
+    }
+    Ok(AssetsVc::cell(assets.into_iter().collect()))
+}
+
+/// A `stats.json` describing the output of a build in the format of webpack's
+/// stats, so tools of its ecosystem, like bundle analyzers, can be used.
+///
+/// It lists the assets in `output_root` which are referenced by the chunks of
+/// the entries, the chunks among them, the modules placed into the chunks and
+/// the entrypoints. A module lists the modules referencing it as `reasons`.
+/// Sizes of assets are the sizes of the emitted files, sizes of modules the
+/// sizes of their sources.
+#[turbo_tasks::value]
+pub struct StatsAsset {
+    path: FileSystemPathVc,
+    output_root: FileSystemPathVc,
+    entries: EntryChunkGroupsVc,
+}
+
+#[turbo_tasks::value_impl]
+impl StatsAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        path: FileSystemPathVc,
+        output_root: FileSystemPathVc,
+        entries: EntryChunkGroupsVc,
+    ) -> Self {
+        Self::cell(StatsAsset {
+            path,
+            output_root,
+            entries,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Stats {
+    output_path: String,
+    assets_by_chunk_name: IndexMap<String, Vec<String>>,
+    assets: Vec<StatsOutputAsset>,
+    chunks: Vec<StatsChunk>,
+    modules: Vec<StatsModule>,
+    entrypoints: IndexMap<String, StatsChunkGroup>,
+    named_chunk_groups: IndexMap<String, StatsChunkGroup>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StatsOutputAsset {
+    name: String,
+    size: u64,
+    chunks: Vec<String>,
+    chunk_names: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StatsChunk {
+    id: String,
+    names: Vec<String>,
+    files: Vec<String>,
+    /// The sum of the sizes of the chunk's modules, like webpack reports it.
+    size: u64,
+    entry: bool,
+    initial: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StatsModule {
+    id: String,
+    identifier: String,
+    name: String,
+    size: u64,
+    chunks: Vec<String>,
+    reasons: Vec<StatsReason>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StatsReason {
+    module_identifier: String,
+    module_name: String,
+    /// The description of the reference, e. g. `require ./foo`.
+    #[serde(rename = "type")]
+    ty: String,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct StatsChunkGroup {
+    name: String,
+    chunks: Vec<String>,
+    assets: Vec<StatsChunkGroupAsset>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct StatsChunkGroupAsset {
+    name: String,
+    size: u64,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for StatsAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let output_root = self.output_root.await?;
+
+        // Chunks of the entries are loaded initially, the first one of each
+        // entry is its entry chunk. All other chunks are loaded on demand.
+        let mut chunk_names = HashMap::<ChunkVc, Vec<String>>::new();
+        let mut entry_chunks = HashSet::new();
+        let mut entries = Vec::new();
+        let entry_chunk_groups = self.entries.await?;
+        for (name, chunk_group) in entry_chunk_groups.iter() {
+            let chunks = chunk_group
+                .chunks()
+                .await?
+                .iter()
+                .map(|chunk| chunk.resolve())
+                .try_join()
+                .await?;
+            if let Some(chunk) = chunks.first() {
+                entry_chunks.insert(*chunk);
+            }
+            for chunk in chunks.iter() {
+                chunk_names.entry(*chunk).or_default().push(name.clone());
+            }
+            entries.push((name, chunks));
+        }
+
+        let mut output_assets = IndexMap::new();
+        let mut chunks = IndexMap::new();
+        for asset in referenced_output_assets(self.entries, self.output_root)
+            .await?
+            .iter()
+        {
+            let path = asset.path().await?;
+            let name = match output_root.get_path_to(&path) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let size = *content_size(asset.content()).await?;
+            if let Some(chunk) = ChunkVc::resolve_from(asset).await? {
+                chunks.insert(chunk, name.clone());
+            }
+            output_assets.insert(*asset, (name, size));
+        }
+
+        let mut modules = IndexMap::<AssetVc, StatsModule>::new();
+        let mut stats_chunks = Vec::new();
+        for (&chunk, id) in chunks.iter() {
+            let mut size = 0;
+            for chunk_item in chunk.chunk_items().await?.iter() {
+                let module = if let Some(module) = *chunk_item.asset().await? {
+                    module.resolve().await?
+                } else {
+                    continue;
+                };
+                let stats_module = match modules.entry(module) {
+                    Entry::Occupied(e) => e.into_mut(),
+                    Entry::Vacant(e) => {
+                        let identifier = module.path().to_string().await?.clone_value();
+                        e.insert(StatsModule {
+                            id: identifier.clone(),
+                            identifier,
+                            name: format!("./{}", module.path().await?.path),
+                            size: *content_size(module.content()).await?,
+                            chunks: Vec::new(),
+                            reasons: Vec::new(),
+                        })
+                    }
+                };
+                size += stats_module.size;
+                stats_module.chunks.push(id.clone());
+            }
+            let names = chunk_names.get(&chunk).cloned().unwrap_or_default();
+            stats_chunks.push(StatsChunk {
+                id: id.clone(),
+                names,
+                files: vec![id.clone()],
+                size,
+                entry: entry_chunks.contains(&chunk),
+                initial: chunk_names.contains_key(&chunk),
+            });
+        }
+
+        // A module is included for the references of the modules referencing
+        // it.
+        let module_assets = modules.keys().copied().collect::<Vec<_>>();
+        for module in module_assets {
+            let module_identifier = modules[&module].identifier.clone();
+            let module_name = modules[&module].name.clone();
+            for reference in module.references().await?.iter() {
+                let ty = reference.to_string().await?;
+                for asset in reference.resolve_reference().primary_assets().await?.iter() {
+                    let asset = asset.resolve().await?;
+                    if let Some(referenced) = modules.get_mut(&asset) {
+                        referenced.reasons.push(StatsReason {
+                            module_identifier: module_identifier.clone(),
+                            module_name: module_name.clone(),
+                            ty: ty.clone_value(),
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut assets_by_chunk_name = IndexMap::new();
+        let mut entrypoints = IndexMap::new();
+        for (name, chunks_of_entry) in entries {
+            let mut chunk_ids = Vec::new();
+            let mut assets = Vec::new();
+            for chunk in chunks_of_entry.iter() {
+                if let Some(id) = chunks.get(chunk) {
+                    chunk_ids.push(id.clone());
+                }
+                if let Some((name, size)) = output_assets.get(&chunk.as_asset()) {
+                    assets.push(StatsChunkGroupAsset {
+                        name: name.clone(),
+                        size: *size,
+                    });
+                }
+            }
+            assets_by_chunk_name.insert(
+                name.clone(),
+                assets.iter().map(|asset| asset.name.clone()).collect(),
+            );
+            entrypoints.insert(
+                name.clone(),
+                StatsChunkGroup {
+                    name: name.clone(),
+                    chunks: chunk_ids,
+                    assets,
+                },
+            );
+        }
+
+        let mut stats_assets = Vec::new();
+        for (asset, (name, size)) in output_assets {
+            let mut chunk_ids = Vec::new();
+            let mut names = Vec::new();
+            if let Some(chunk) = ChunkVc::resolve_from(asset).await? {
+                chunk_ids.push(name.clone());
+                names = chunk_names.get(&chunk).cloned().unwrap_or_default();
+            }
+            stats_assets.push(StatsOutputAsset {
+                name,
+                size,
+                chunks: chunk_ids,
+                chunk_names: names,
+            });
+        }
+
+        let json = serde_json::to_string_pretty(&Stats {
+            output_path: output_root.path.clone(),
+            assets_by_chunk_name,
+            assets: stats_assets,
+            chunks: stats_chunks,
+            modules: modules.into_iter().map(|(_, module)| module).collect(),
+            named_chunk_groups: entrypoints.clone(),
+            entrypoints,
+        })?;
+        Ok(File::from(json).into())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use turbo_tasks_fs::FileSystemPathVc;
+
+/// Describes which exports of a module are used by the modules importing it.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub enum ExportUsage {
+    /// All exports might be used, e. g. because the module is an entry or is
+    /// imported as a namespace.
+    All,
+    /// Only the listed exports are used.
+    Named(BTreeSet<String>),
+}
+
+impl ExportUsage {
+    pub fn is_used(&self, name: &str) -> bool {
+        match self {
+            ExportUsage::All => true,
+            ExportUsage::Named(names) => names.contains(name),
+        }
+    }
+
+    /// Merges `other` into this usage. Returns true when the usage changed.
+    pub fn extend(&mut self, other: &ExportUsage) -> bool {
+        match (&mut *self, other) {
+            (ExportUsage::All, _) => false,
+            (_, ExportUsage::All) => {
+                *self = ExportUsage::All;
+                true
+            }
+            (ExportUsage::Named(names), ExportUsage::Named(other)) => {
+                let len = names.len();
+                names.extend(other.iter().cloned());
+                names.len() != len
+            }
+        }
+    }
+}
+
+/// The [ExportUsage] of all modules of a module graph, keyed by the path of the
+/// module.
+#[turbo_tasks::value(transparent)]
+pub struct UsedExports(IndexMap<FileSystemPathVc, ExportUsage>);
+
+#[turbo_tasks::value_impl]
+impl UsedExportsVc {
+    /// Returns the usage of the exports of the module at `path`. Modules
+    /// which are not part of the analysed module graph conservatively use
+    /// all exports.
+    #[turbo_tasks::function]
+    pub async fn usage(self, path: FileSystemPathVc) -> Result<ExportUsageVc> {
+        let path = path.resolve().await?;
+        Ok(self
+            .await?
+            .get(&path)
+            .cloned()
+            .unwrap_or(ExportUsage::All)
+            .cell())
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+use turbo_tasks::primitives::OptionStringVc;
+use turbo_tasks_fs::{glob::Glob, FileSystemPathVc};
+
+/// Selects the modules which are moved into the chunks of a
+/// [VendorChunkGroup].
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub enum VendorChunkTest {
+    /// Modules within a `node_modules` directory. Every package gets a chunk
+    /// of its own, so an update of one package doesn't invalidate the chunks
+    /// of the others.
+    NodeModules,
+    /// Modules whose path, relative to the root of the filesystem, matches the
+    /// glob. They are all placed in a single chunk.
+    Glob(Glob),
+}
+
+/// Modules matching `test` are split from the chunks of the application into
+/// separate vendor chunks. These only change when the vendored modules change,
+/// so they stay cached while the application code changes, and entries using
+/// the same vendored modules share them.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct VendorChunkGroup {
+    pub name: String,
+    pub test: VendorChunkTest,
+}
+
+/// A list of [VendorChunkGroup]s. A module belongs to the first group it
+/// matches.
+#[turbo_tasks::value(transparent)]
+pub struct VendorChunkGroups(Vec<VendorChunkGroup>);
+
+#[turbo_tasks::value_impl]
+impl VendorChunkGroupsVc {
+    /// Splits every package within `node_modules` into a vendor chunk.
+    #[turbo_tasks::function]
+    pub fn node_modules() -> Self {
+        Self::cell(vec![VendorChunkGroup {
+            name: "vendor".to_string(),
+            test: VendorChunkTest::NodeModules,
+        }])
+    }
+
+    /// Returns the name of the vendor chunk the module at `path` is placed in,
+    /// or `None` when it stays in the chunks of the application.
+    #[turbo_tasks::function]
+    pub async fn vendor_chunk_name(self, path: FileSystemPathVc) -> Result<OptionStringVc> {
+        let path = &path.await?.path;
+        for group in self.await?.iter() {
+            let name = match &group.test {
+                VendorChunkTest::NodeModules => {
+                    node_modules_package(path).map(|package| format!("{}~{package}", group.name))
+                }
+                VendorChunkTest::Glob(glob) => glob.execute(path).then(|| group.name.clone()),
+            };
+            if name.is_some() {
+                return Ok(OptionStringVc::cell(name));
+            }
+        }
+        Ok(OptionStringVc::cell(None))
+    }
+}
+
+/// Returns the name of the package containing `path`, when it's within a
+/// `node_modules` directory. Nested `node_modules` directories are resolved to
+/// the innermost package.
+pub fn node_modules_package(path: &str) -> Option<&str> {
+    let start = path
+        .match_indices("node_modules/")
+        .map(|(i, _)| i)
+        .filter(|&i| i == 0 || path[..i].ends_with('/'))
+        .last()?
+        + "node_modules/".len();
+    let rest = &path[start..];
+    let mut segments = rest.splitn(3, '/');
+    let first = segments.next()?;
+    let len = if first.starts_with('@') {
+        first.len() + 1 + segments.next()?.len()
+    } else {
+        first.len()
+    };
+    // A file directly within `node_modules` isn't part of a package
+    if len == rest.len() {
+        return None;
+    }
+    Some(&rest[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::node_modules_package;
+
+    #[test]
+    fn packages() {
+        assert_eq!(
+            node_modules_package("node_modules/react/index.js"),
+            Some("react")
+        );
+        assert_eq!(
+            node_modules_package("app/node_modules/@next/font/dist/index.js"),
+            Some("@next/font")
+        );
+        assert_eq!(
+            node_modules_package("node_modules/a/node_modules/b/lib/b.js"),
+            Some("b")
+        );
+        assert_eq!(node_modules_package("src/my_node_modules/a/index.js"), None);
+        assert_eq!(node_modules_package("src/index.js"), None);
+        assert_eq!(node_modules_package("node_modules/index.js"), None);
+    }
+}
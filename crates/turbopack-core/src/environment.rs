@@ -1,9 +1,12 @@
+#[cfg(feature = "process")]
+use std::process::{Command, Stdio};
 use std::{
-    process::{Command, Stdio},
+    fmt::{self, Display, Formatter},
     str::FromStr,
 };
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use indexmap::IndexMap;
 use swc_core::ecma::preset_env::{Version, Versions};
 use turbo_tasks::{
     primitives::{BoolVc, OptionStringVc, StringVc, StringsVc},
@@ -20,6 +23,9 @@ pub struct Environment {
     // members must be private to avoid leaking non-custom types
     execution: ExecutionEnvironment,
     intention: EnvironmentIntention,
+    defined_env: Option<ProcessEnvVc>,
+    provided_globals: Option<ProvidedGlobalsVc>,
+    es_version: Option<EcmascriptVersion>,
 }
 
 #[turbo_tasks::value_impl]
@@ -32,10 +38,209 @@ impl EnvironmentVc {
         Self::cell(Environment {
             execution: execution.into_value(),
             intention: intention.into_value(),
+            defined_env: None,
+            provided_globals: None,
+            es_version: None,
         })
     }
+
+    /// Returns a new environment in which `process.env` is known to contain
+    /// the variables of `env`.
+    #[turbo_tasks::function]
+    pub async fn with_defined_env(self, env: ProcessEnvVc) -> Result<Self> {
+        let this = self.await?;
+        Ok(Self::cell(Environment {
+            execution: this.execution,
+            intention: this.intention,
+            defined_env: Some(env),
+            provided_globals: this.provided_globals,
+            es_version: this.es_version,
+        }))
+    }
+
+    /// Returns a new environment in which the modules of `globals` provide
+    /// the globals the environment doesn't have, e. g. `Buffer` in browsers.
+    #[turbo_tasks::function]
+    pub async fn with_provided_globals(self, globals: ProvidedGlobalsVc) -> Result<Self> {
+        let this = self.await?;
+        Ok(Self::cell(Environment {
+            execution: this.execution,
+            intention: this.intention,
+            defined_env: this.defined_env,
+            provided_globals: Some(globals),
+            es_version: this.es_version,
+        }))
+    }
+
+    /// Returns a new environment which only supports the syntax of
+    /// `es_version`, in addition to the runtime versions of the execution
+    /// environment. Code is downleveled to whichever of them is older.
+    #[turbo_tasks::function]
+    pub async fn with_es_version(self, es_version: Value<EcmascriptVersion>) -> Result<Self> {
+        let this = self.await?;
+        Ok(Self::cell(Environment {
+            execution: this.execution,
+            intention: this.intention,
+            defined_env: this.defined_env,
+            provided_globals: this.provided_globals,
+            es_version: Some(es_version.into_value()),
+        }))
+    }
+}
+
+/// A version of the ECMAScript spec, which code is downleveled to when it's
+/// set explicitly for an environment.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(PartialOrd, Ord, Debug, Hash, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum EcmascriptVersion {
+    Es5,
+    Es2015,
+    Es2016,
+    Es2017,
+    Es2018,
+    Es2019,
+    Es2020,
+    Es2021,
+    Es2022,
+}
+
+impl EcmascriptVersion {
+    pub const ALL: [EcmascriptVersion; 9] = [
+        EcmascriptVersion::Es5,
+        EcmascriptVersion::Es2015,
+        EcmascriptVersion::Es2016,
+        EcmascriptVersion::Es2017,
+        EcmascriptVersion::Es2018,
+        EcmascriptVersion::Es2019,
+        EcmascriptVersion::Es2020,
+        EcmascriptVersion::Es2021,
+        EcmascriptVersion::Es2022,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EcmascriptVersion::Es5 => "es5",
+            EcmascriptVersion::Es2015 => "es2015",
+            EcmascriptVersion::Es2016 => "es2016",
+            EcmascriptVersion::Es2017 => "es2017",
+            EcmascriptVersion::Es2018 => "es2018",
+            EcmascriptVersion::Es2019 => "es2019",
+            EcmascriptVersion::Es2020 => "es2020",
+            EcmascriptVersion::Es2021 => "es2021",
+            EcmascriptVersion::Es2022 => "es2022",
+        }
+    }
+
+    /// The first versions of the major engines supporting all syntax of the
+    /// version. The syntax transforms are selected by the runtime versions
+    /// they're needed for, so the version is expressed as runtime versions.
+    /// ES5 is the syntax of Internet Explorer 11, which doesn't support any
+    /// syntax of ES2015.
+    fn runtime_versions(self) -> Versions {
+        let (chrome, edge, firefox, safari, opera, ios, node) = match self {
+            EcmascriptVersion::Es5 => {
+                return Versions {
+                    ie: Some(version(11, 0)),
+                    ..Default::default()
+                }
+            }
+            EcmascriptVersion::Es2015 => (51, 15, 54, (10, 0), 38, (10, 0), (6, 5)),
+            EcmascriptVersion::Es2016 => (52, 15, 54, (10, 1), 39, (10, 3), (7, 0)),
+            EcmascriptVersion::Es2017 => (58, 16, 54, (11, 0), 45, (11, 0), (8, 0)),
+            EcmascriptVersion::Es2018 => (64, 79, 78, (12, 0), 51, (12, 0), (10, 0)),
+            EcmascriptVersion::Es2019 => (66, 79, 78, (12, 0), 53, (12, 0), (10, 0)),
+            EcmascriptVersion::Es2020 => (80, 80, 80, (14, 0), 67, (14, 0), (14, 0)),
+            EcmascriptVersion::Es2021 => (85, 85, 80, (14, 0), 71, (14, 0), (15, 0)),
+            EcmascriptVersion::Es2022 => (94, 94, 93, (16, 4), 80, (16, 4), (16, 11)),
+        };
+        Versions {
+            chrome: Some(version(chrome, 0)),
+            edge: Some(version(edge, 0)),
+            firefox: Some(version(firefox, 0)),
+            safari: Some(version(safari.0, safari.1)),
+            opera: Some(version(opera, 0)),
+            ios: Some(version(ios.0, ios.1)),
+            node: Some(version(node.0, node.1)),
+            ..Default::default()
+        }
+    }
+}
+
+impl Display for EcmascriptVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
+impl FromStr for EcmascriptVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match EcmascriptVersion::ALL
+            .into_iter()
+            .find(|version| version.name() == s)
+        {
+            Some(version) => Ok(version),
+            None => bail!(
+                "unknown ECMAScript version \"{s}\", expected one of {}",
+                EcmascriptVersion::ALL
+                    .map(|version| version.name())
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+fn version(major: u32, minor: u32) -> Version {
+    Version {
+        major,
+        minor,
+        patch: 0,
+    }
+}
+
+/// Lowers the versions of `versions` to the versions of `other`, adding the
+/// runtimes only `other` has versions for. Code for both has to run in the
+/// oldest version of every runtime either of them targets.
+fn lower_versions(versions: &mut Versions, other: &Versions) {
+    fn lower(version: &mut Option<Version>, other: Option<Version>) {
+        if let Some(other) = other {
+            if version.map_or(true, |version| other < version) {
+                *version = Some(other);
+            }
+        }
+    }
+    lower(&mut versions.chrome, other.chrome);
+    lower(&mut versions.edge, other.edge);
+    lower(&mut versions.firefox, other.firefox);
+    lower(&mut versions.safari, other.safari);
+    lower(&mut versions.opera, other.opera);
+    lower(&mut versions.ios, other.ios);
+    lower(&mut versions.node, other.node);
+    lower(&mut versions.ie, other.ie);
+}
+
+/// A module providing a global which the environment doesn't have, like
+/// `ProvidePlugin` in webpack. Modules reading the global import the module
+/// instead.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct ProvidedGlobal {
+    /// The request of the module, e. g. `buffer`.
+    pub request: String,
+    /// The export of the module which is the global, e. g. `Buffer`. The
+    /// module itself is the global when `None`, e. g. for `process/browser`.
+    pub export: Option<String>,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct ProvidedGlobalOption(Option<ProvidedGlobal>);
+
+/// The globals provided by modules, by the name of the global.
+#[turbo_tasks::value(transparent)]
+pub struct ProvidedGlobals(IndexMap<String, ProvidedGlobal>);
+
 #[turbo_tasks::value(serialization = "auto_for_input")]
 #[derive(PartialOrd, Ord, Debug, Hash, Clone, Copy)]
 pub enum EnvironmentIntention {
@@ -62,7 +267,7 @@ pub enum EnvironmentIntention {
 pub enum ExecutionEnvironment {
     NodeJsBuildTime(NodeJsEnvironmentVc),
     NodeJsLambda(NodeJsEnvironmentVc),
-    EdgeFunction(NodeJsEnvironmentVc),
+    EdgeFunction(EdgeWorkerEnvironmentVc),
     Browser(BrowserEnvironmentVc),
     // TODO allow custom trait here
     Custom(u8),
@@ -70,33 +275,52 @@ pub enum ExecutionEnvironment {
 
 #[turbo_tasks::value_impl]
 impl EnvironmentVc {
+    #[turbo_tasks::function]
+    pub async fn execution(self) -> Result<ExecutionEnvironmentVc> {
+        Ok(self.await?.execution.cell())
+    }
+
     #[turbo_tasks::function]
     pub async fn compile_target(self) -> Result<CompileTargetVc> {
         let this = self.await?;
         Ok(match this.execution {
             ExecutionEnvironment::NodeJsBuildTime(node_env, ..)
             | ExecutionEnvironment::NodeJsLambda(node_env) => node_env.await?.compile_target,
-            ExecutionEnvironment::Browser(_) => CompileTargetVc::unknown(),
-            ExecutionEnvironment::EdgeFunction(_) => todo!(),
+            ExecutionEnvironment::Browser(_) | ExecutionEnvironment::EdgeFunction(_) => {
+                CompileTargetVc::unknown()
+            }
             ExecutionEnvironment::Custom(_) => todo!(),
         })
     }
 
+    /// The runtime versions the code has to run in. An ES version set for the
+    /// environment lowers them further, see [EnvironmentVc::with_es_version].
     #[turbo_tasks::function]
     pub async fn runtime_versions(self) -> Result<RuntimeVersionsVc> {
         let this = self.await?;
-        Ok(match this.execution {
+        let versions = match this.execution {
             ExecutionEnvironment::NodeJsBuildTime(node_env, ..)
-            | ExecutionEnvironment::NodeJsLambda(node_env) => node_env.runtime_versions(),
+            | ExecutionEnvironment::NodeJsLambda(node_env) => {
+                Some(*node_env.runtime_versions().await?)
+            }
             ExecutionEnvironment::Browser(browser_env) => {
-                RuntimeVersionsVc::cell(Versions::parse_versions(browserslist::resolve(
+                Some(Versions::parse_versions(browserslist::resolve(
                     browser_env.await?.browserslist_query.split(','),
                     &browserslist::Opts::new(),
                 )?)?)
             }
-            ExecutionEnvironment::EdgeFunction(_) => todo!(),
+            ExecutionEnvironment::EdgeFunction(_) => None,
             ExecutionEnvironment::Custom(_) => todo!(),
-        })
+        };
+        Ok(RuntimeVersionsVc::cell(match (versions, this.es_version) {
+            (Some(mut versions), Some(es_version)) => {
+                lower_versions(&mut versions, &es_version.runtime_versions());
+                versions
+            }
+            (Some(versions), None) => versions,
+            (None, Some(es_version)) => es_version.runtime_versions(),
+            (None, None) => bail!("the runtime versions of edge runtimes are not known"),
+        }))
     }
 
     #[turbo_tasks::function]
@@ -112,6 +336,37 @@ impl EnvironmentVc {
         })
     }
 
+    /// Node.js builtins which are available at runtime although the
+    /// environment doesn't have
+    /// [node_externals](EnvironmentVc::node_externals), e. g. in edge
+    /// runtimes with Node.js compatibility.
+    #[turbo_tasks::function]
+    pub async fn node_compat_builtins(self) -> Result<StringsVc> {
+        Ok(match self.await?.execution {
+            ExecutionEnvironment::EdgeFunction(edge_env) => {
+                StringsVc::cell(edge_env.await?.node_compat_builtins.clone())
+            }
+            _ => StringsVc::empty(),
+        })
+    }
+
+    /// Whether the Node.js globals like `Buffer` and the members of `process`
+    /// are available. Otherwise only `process.env` is, as the runtime provides
+    /// a stand-in `process` object.
+    #[turbo_tasks::function]
+    pub async fn node_globals(self) -> Result<BoolVc> {
+        let env = self.await?;
+        Ok(match env.execution {
+            ExecutionEnvironment::NodeJsBuildTime(..) | ExecutionEnvironment::NodeJsLambda(_) => {
+                BoolVc::cell(true)
+            }
+            ExecutionEnvironment::EdgeFunction(_) | ExecutionEnvironment::Browser(_) => {
+                BoolVc::cell(false)
+            }
+            ExecutionEnvironment::Custom(_) => todo!(),
+        })
+    }
+
     #[turbo_tasks::function]
     pub async fn resolve_extensions(self) -> Result<StringsVc> {
         let env = self.await?;
@@ -123,9 +378,10 @@ impl EnvironmentVc {
                     ".json".to_string(),
                 ])
             }
-            ExecutionEnvironment::EdgeFunction(_) | ExecutionEnvironment::Browser(_) => {
-                StringsVc::empty()
+            ExecutionEnvironment::EdgeFunction(_) => {
+                StringsVc::cell(vec![".js".to_string(), ".json".to_string()])
             }
+            ExecutionEnvironment::Browser(_) => StringsVc::empty(),
             ExecutionEnvironment::Custom(_) => todo!(),
         })
     }
@@ -134,12 +390,10 @@ impl EnvironmentVc {
     pub async fn resolve_node_modules(self) -> Result<BoolVc> {
         let env = self.await?;
         Ok(match env.execution {
-            ExecutionEnvironment::NodeJsBuildTime(..) | ExecutionEnvironment::NodeJsLambda(_) => {
-                BoolVc::cell(true)
-            }
-            ExecutionEnvironment::EdgeFunction(_) | ExecutionEnvironment::Browser(_) => {
-                BoolVc::cell(false)
-            }
+            ExecutionEnvironment::NodeJsBuildTime(..)
+            | ExecutionEnvironment::NodeJsLambda(_)
+            | ExecutionEnvironment::EdgeFunction(_) => BoolVc::cell(true),
+            ExecutionEnvironment::Browser(_) => BoolVc::cell(false),
             ExecutionEnvironment::Custom(_) => todo!(),
         })
     }
@@ -151,8 +405,53 @@ impl EnvironmentVc {
             ExecutionEnvironment::NodeJsBuildTime(..) | ExecutionEnvironment::NodeJsLambda(_) => {
                 StringsVc::cell(vec!["node".to_string()])
             }
+            ExecutionEnvironment::EdgeFunction(_) => StringsVc::cell(vec![
+                "edge-light".to_string(),
+                "worker".to_string(),
+                "browser".to_string(),
+            ]),
+            ExecutionEnvironment::Browser(_) => StringsVc::empty(),
+            ExecutionEnvironment::Custom(_) => todo!(),
+        })
+    }
+
+    /// Reads a variable which is known to be defined on `process.env` in this
+    /// environment.
+    #[turbo_tasks::function]
+    pub async fn read_defined_env(self, name: &str) -> Result<OptionStringVc> {
+        Ok(match self.await?.defined_env {
+            Some(env) => env.read(name),
+            None => OptionStringVc::cell(None),
+        })
+    }
+
+    /// The module providing the global `name`, when it's provided by a
+    /// module in this environment.
+    #[turbo_tasks::function]
+    pub async fn provided_global(self, name: &str) -> Result<ProvidedGlobalOptionVc> {
+        Ok(match self.await?.provided_globals {
+            Some(globals) => ProvidedGlobalOptionVc::cell(globals.await?.get(name).cloned()),
+            None => ProvidedGlobalOptionVc::cell(None),
+        })
+    }
+
+    /// Whether the variables on `process.env` are known for this environment.
+    #[turbo_tasks::function]
+    pub async fn defines_env(self) -> Result<BoolVc> {
+        Ok(BoolVc::cell(self.await?.defined_env.is_some()))
+    }
+
+    /// Whether the variables on `process.env` are only known at runtime, as
+    /// opposed to being inlined at build time.
+    #[turbo_tasks::function]
+    pub async fn runtime_env(self) -> Result<BoolVc> {
+        let env = self.await?;
+        Ok(match env.execution {
+            ExecutionEnvironment::NodeJsBuildTime(..) | ExecutionEnvironment::NodeJsLambda(_) => {
+                BoolVc::cell(true)
+            }
             ExecutionEnvironment::EdgeFunction(_) | ExecutionEnvironment::Browser(_) => {
-                StringsVc::empty()
+                BoolVc::cell(false)
             }
             ExecutionEnvironment::Custom(_) => todo!(),
         })
@@ -245,24 +544,85 @@ pub struct BrowserEnvironment {
     pub browserslist_query: String,
 }
 
+/// An edge runtime or a web worker, which only provides Web APIs. Node.js
+/// builtins are not available unless listed in `node_compat_builtins`, and
+/// neither are Node.js globals.
+#[turbo_tasks::value(shared)]
+#[derive(Default)]
+pub struct EdgeWorkerEnvironment {
+    /// Node.js builtins provided by the runtime, e. g. `buffer` with the
+    /// Node.js compatibility of some edge runtimes. They are external and
+    /// loaded with the `node:` prefix.
+    pub node_compat_builtins: Vec<String>,
+}
+
 #[turbo_tasks::value(transparent)]
 pub struct RuntimeVersions(#[turbo_tasks(trace_ignore)] pub Versions);
 
+/// Detects the version of the installed Node.js by running `node`. Without
+/// the `process` feature, e. g. on wasm32 targets, it fails.
 #[turbo_tasks::function]
 pub async fn get_current_nodejs_version(path_env: StringVc) -> Result<StringVc> {
+    Ok(StringVc::cell(nodejs_version(&path_env.await?)?))
+}
+
+#[cfg(feature = "process")]
+fn nodejs_version(path_env: &str) -> Result<String> {
     let mut cmd = Command::new("node");
     cmd.arg("--version");
     cmd.env_clear();
-    cmd.env("PATH", path_env.await?.clone());
+    cmd.env("PATH", path_env);
     cmd.stdin(Stdio::piped());
     cmd.stdout(Stdio::piped());
 
-    Ok(StringVc::cell(
-        String::from_utf8(cmd.output()?.stdout)?
-            .strip_prefix('v')
-            .context("Version must begin with v")?
-            .strip_suffix('\n')
-            .context("Version must end with \\n")?
-            .to_owned(),
-    ))
+    Ok(String::from_utf8(cmd.output()?.stdout)?
+        .strip_prefix('v')
+        .context("Version must begin with v")?
+        .strip_suffix('\n')
+        .context("Version must end with \\n")?
+        .to_owned())
+}
+
+#[cfg(not(feature = "process"))]
+fn nodejs_version(_path_env: &str) -> Result<String> {
+    bail!("detecting the Node.js version requires the `process` feature of turbopack-core")
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::ecma::preset_env::Versions;
+
+    use super::{lower_versions, version, EcmascriptVersion};
+
+    #[test]
+    fn parses_ecmascript_versions() {
+        for es_version in EcmascriptVersion::ALL {
+            assert_eq!(
+                es_version.name().parse::<EcmascriptVersion>().unwrap(),
+                es_version
+            );
+        }
+        assert!("es6".parse::<EcmascriptVersion>().is_err());
+    }
+
+    #[test]
+    fn lowers_runtime_versions_to_ecmascript_versions() {
+        let mut versions = Versions {
+            chrome: Some(version(108, 0)),
+            firefox: Some(version(50, 0)),
+            ..Default::default()
+        };
+        lower_versions(&mut versions, &EcmascriptVersion::Es2017.runtime_versions());
+        assert_eq!(versions.chrome, Some(version(58, 0)));
+        // Already older than ES2017
+        assert_eq!(versions.firefox, Some(version(50, 0)));
+        // Added by ES2017
+        assert_eq!(versions.safari, Some(version(11, 0)));
+        assert_eq!(versions.node, Some(version(8, 0)));
+        assert_eq!(versions.ie, None);
+
+        lower_versions(&mut versions, &EcmascriptVersion::Es5.runtime_versions());
+        assert_eq!(versions.chrome, Some(version(58, 0)));
+        assert_eq!(versions.ie, Some(version(11, 0)));
+    }
 }
@@ -21,6 +21,12 @@ impl IntrospectableAssetVc {
             .await?
             .unwrap_or_else(|| IntrospectableAsset(asset).cell().into()))
     }
+
+    /// The [AssetVc] which is introspected.
+    #[turbo_tasks::function]
+    pub async fn asset(self) -> Result<AssetVc> {
+        Ok(self.await?.0)
+    }
 }
 
 #[turbo_tasks::function]
@@ -23,12 +23,8 @@ impl Issue for AnalyzeIssue {
     }
 
     #[turbo_tasks::function]
-    async fn title(&self) -> Result<StringVc> {
-        Ok(if let Some(code) = self.code.as_ref() {
-            StringVc::cell(format!("{code} {}", self.title.await?))
-        } else {
-            self.title
-        })
+    fn title(&self) -> StringVc {
+        self.title
     }
 
     #[turbo_tasks::function]
@@ -36,6 +32,14 @@ impl Issue for AnalyzeIssue {
         self.category
     }
 
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        match &self.code {
+            Some(code) => StringVc::cell(code.clone()),
+            None => StringVc::empty(),
+        }
+    }
+
     #[turbo_tasks::function]
     fn context(&self) -> FileSystemPathVc {
         self.path
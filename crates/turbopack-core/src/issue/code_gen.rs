@@ -2,7 +2,9 @@ use anyhow::Result;
 use turbo_tasks::primitives::StringVc;
 use turbo_tasks_fs::FileSystemPathVc;
 
-use super::{Issue, IssueSeverityVc, IssueVc};
+use super::{
+    codes, Issue, IssueSeverity, IssueSeverityVc, IssueSourceVc, IssueVc, OptionIssueSourceVc,
+};
 
 #[turbo_tasks::value(shared)]
 pub struct CodeGenerationIssue {
@@ -10,6 +12,9 @@ pub struct CodeGenerationIssue {
     pub path: FileSystemPathVc,
     pub title: StringVc,
     pub message: StringVc,
+    /// The code the issue was found in, when it's known, so the issue can be
+    /// shown with a code frame.
+    pub source: Option<IssueSourceVc>,
 }
 
 #[turbo_tasks::value_impl]
@@ -29,6 +34,59 @@ impl Issue for CodeGenerationIssue {
         StringVc::cell("code generation".to_string())
     }
 
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::parse::CODE_GENERATION.to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.message
+    }
+
+    #[turbo_tasks::function]
+    fn source(&self) -> OptionIssueSourceVc {
+        OptionIssueSourceVc::cell(self.source)
+    }
+}
+
+/// A reference which can't be generated, e. g. because its request is
+/// unresolvable, and is replaced with code throwing when it's evaluated.
+/// Emitted when strict reference validation is enabled.
+#[turbo_tasks::value(shared)]
+pub struct InvalidReferenceIssue {
+    pub path: FileSystemPathVc,
+    pub message: StringVc,
+    pub source: Option<IssueSourceVc>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for InvalidReferenceIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Fatal.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Reference can't be generated".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("code generation".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::parse::INVALID_REFERENCE.to_string())
+    }
+
     #[turbo_tasks::function]
     fn context(&self) -> FileSystemPathVc {
         self.path
@@ -38,4 +96,9 @@ impl Issue for CodeGenerationIssue {
     fn description(&self) -> StringVc {
         self.message
     }
+
+    #[turbo_tasks::function]
+    fn source(&self) -> OptionIssueSourceVc {
+        OptionIssueSourceVc::cell(self.source)
+    }
 }
@@ -0,0 +1,64 @@
+//! Stable codes of the issues emitted by turbopack, returned by
+//! [Issue::code](super::Issue::code).
+//!
+//! Codes never change their meaning once assigned, so tooling can match on
+//! them and users can suppress or escalate issues by their code. New kinds of
+//! issues get a new code within the range of their category:
+//!
+//! * `TP1xxx`: analysis of modules, see `turbopack_ecmascript::errors`
+//! * `TP2xxx`: resolving of requests and modules
+//! * `TP3xxx`: parsing and code generation
+//! * `TP4xxx`: configuration and environment
+//! * `TP5xxx`: CSS and CSS modules
+//! * `TP9xxx`: reserved for integrations built on turbopack, e. g. next-core
+
+pub mod resolve {
+    /// A request couldn't be resolved.
+    pub const UNRESOLVABLE: &str = "TP2001";
+    /// A module is known to be unsupported.
+    pub const UNSUPPORTED_MODULE: &str = "TP2002";
+    /// The effects of a module rule can't be applied.
+    pub const MODULE_RULE: &str = "TP2003";
+    /// A subpath of a package isn't exported by its "exports" field.
+    pub const PACKAGE_PATH_NOT_EXPORTED: &str = "TP2004";
+    /// A Node.js builtin was replaced by a polyfill or an empty module.
+    pub const POLYFILL: &str = "TP2005";
+    /// A package is included in multiple versions.
+    pub const DUPLICATE_PACKAGE: &str = "TP2006";
+}
+
+pub mod parse {
+    /// A source file couldn't be parsed.
+    pub const PARSE: &str = "TP3001";
+    /// Code generation of a module failed.
+    pub const CODE_GENERATION: &str = "TP3002";
+    /// A JSON file couldn't be parsed.
+    pub const JSON: &str = "TP3003";
+    /// A package.json file couldn't be parsed.
+    pub const PACKAGE_JSON: &str = "TP3004";
+    /// A lockfile couldn't be parsed.
+    pub const LOCKFILE: &str = "TP3005";
+    /// A reference couldn't be generated and would throw at runtime.
+    pub const INVALID_REFERENCE: &str = "TP3006";
+    /// Processing a module panicked.
+    pub const PANIC: &str = "TP3007";
+}
+
+pub mod config {
+    /// A tsconfig.json file is invalid or couldn't be resolved.
+    pub const TSCONFIG: &str = "TP4001";
+    /// A dotenv file couldn't be loaded.
+    pub const PROCESS_ENV: &str = "TP4002";
+    /// An environment variable is read, but not defined.
+    pub const UNDEFINED_ENV: &str = "TP4003";
+    /// The network or files outside of the roots are accessed in hermetic
+    /// mode.
+    pub const HERMETIC: &str = "TP4004";
+}
+
+pub mod css {
+    /// Classes of a CSS module are not used by any module importing it.
+    pub const UNUSED_CSS_MODULE_CLASSES: &str = "TP5001";
+    /// A Sass stylesheet couldn't be compiled.
+    pub const SASS_COMPILE: &str = "TP5002";
+}
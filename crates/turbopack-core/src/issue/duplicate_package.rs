@@ -0,0 +1,95 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{primitives::StringVc, trace::TraceRawVcs};
+use turbo_tasks_fs::FileSystemPathVc;
+
+use super::{codes, Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+
+/// A copy of a package of a [DuplicatePackageIssue].
+#[derive(PartialEq, Eq, Debug, Clone, TraceRawVcs, Serialize, Deserialize)]
+pub struct DuplicatePackageCopy {
+    pub path: String,
+    pub version: String,
+    /// The sum of the sizes of the sources of its modules.
+    pub size: u64,
+    /// A chain of references which includes the copy, see
+    /// [ImportChains](crate::reference::graph::ImportChains).
+    pub import_chain: String,
+}
+
+/// A package of which multiple versions are part of the module graph, see
+/// [find_duplicate_packages](crate::resolve::duplicates::find_duplicate_packages).
+#[turbo_tasks::value(shared)]
+pub struct DuplicatePackageIssue {
+    pub context: FileSystemPathVc,
+    pub name: String,
+    /// The copies, the highest version first.
+    pub copies: Vec<DuplicatePackageCopy>,
+    /// All versions of the package the lockfile of the project installs,
+    /// which can include versions that are not part of the module graph.
+    pub lockfile_versions: Vec<String>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for DuplicatePackageIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!(
+            "Package `{}` is included in multiple versions",
+            self.name
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("resolve".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::resolve::DUPLICATE_PACKAGE.to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> Result<StringVc> {
+        // All copies but the highest version could be deduped.
+        let duplicated_size = self
+            .copies
+            .iter()
+            .skip(1)
+            .map(|copy| copy.size)
+            .sum::<u64>();
+        let mut description = format!(
+            "The additional copies of the package add {} bytes, which could be saved by using a \
+             single version.\n",
+            duplicated_size
+        );
+        for copy in self.copies.iter() {
+            write!(
+                description,
+                "\n{} at {} ({} bytes), included by:\n{}",
+                copy.version, copy.path, copy.size, copy.import_chain
+            )?;
+        }
+        if !self.lockfile_versions.is_empty() {
+            write!(
+                description,
+                "\nThe lockfile installs the versions {} of the package.",
+                self.lockfile_versions.join(", ")
+            )?;
+        }
+        Ok(StringVc::cell(description))
+    }
+}
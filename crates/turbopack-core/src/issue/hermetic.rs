@@ -0,0 +1,82 @@
+use anyhow::Error;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::{FileSystemPathVc, HermeticViolation};
+
+use super::{codes, Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+
+/// An access to the network or the system which is forbidden in hermetic
+/// mode, e. g. a remote module.
+#[turbo_tasks::value(shared)]
+pub struct HermeticIssue {
+    pub context: FileSystemPathVc,
+    /// The subsystem which attempted the access, e. g. "resolve".
+    pub subsystem: String,
+    /// What was accessed, e. g. the URL of a remote module.
+    pub access: String,
+}
+
+impl HermeticIssue {
+    /// Emits a [HermeticIssue] when `error` was caused by a read outside of
+    /// the root of a hermetic file system, and returns whether it was.
+    pub fn emit_for_violation(context: FileSystemPathVc, error: &Error) -> bool {
+        let violation = match (**error).request_ref::<HermeticViolation>() {
+            Some(violation) => violation,
+            None => return false,
+        };
+        HermeticIssue {
+            context,
+            subsystem: "file system".to_string(),
+            access: format!(
+                "{}, which resolves to {} outside of the root {}",
+                violation.path.display(),
+                violation.real_path.display(),
+                violation.root.display()
+            ),
+        }
+        .cell()
+        .as_issue()
+        .emit();
+        true
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for HermeticIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Fatal.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!(
+            "{} attempted to access {} in hermetic mode",
+            self.subsystem, self.access
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("hermetic".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::config::HERMETIC.to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(
+            "Hermetic mode forbids network access and reads outside of the declared roots, so \
+             builds only depend on their inputs. Vendor the accessed resource into the project or \
+             disable hermetic mode."
+                .to_string(),
+        )
+    }
+}
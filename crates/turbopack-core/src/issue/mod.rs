@@ -1,6 +1,12 @@
 pub mod analyze;
 pub mod code_gen;
+pub mod codes;
+pub mod duplicate_package;
+pub mod hermetic;
+pub mod lockfile;
 pub mod package_json;
+pub mod panic;
+pub mod polyfill;
 pub mod resolve;
 pub mod unsupported_module;
 
@@ -90,6 +96,14 @@ pub trait Issue {
         StringVc::empty()
     }
 
+    /// A stable, machine-readable code of the kind of issue (eg "TP1001"),
+    /// which allows tooling to match on issues and users to suppress or
+    /// escalate them. See [codes] for the assigned codes. Empty when the
+    /// issue has no code.
+    fn code(&self) -> StringVc {
+        StringVc::empty()
+    }
+
     /// The issue title should be descriptive of the issue, but should be a
     /// single line. This is displayed to the user directly under the issue
     /// header.
@@ -438,6 +452,7 @@ pub struct PlainIssue {
     pub severity: IssueSeverity,
     pub context: String,
     pub category: String,
+    pub code: String,
 
     pub title: String,
     pub description: String,
@@ -456,6 +471,7 @@ impl IssueVc {
             severity: *self.severity().await?,
             context: self.context().to_string().await?.clone_value(),
             category: self.category().await?.clone_value(),
+            code: self.code().await?.clone_value(),
             title: self.title().await?.clone_value(),
             description: self.description().await?.clone_value(),
             detail: self.detail().await?.clone_value(),
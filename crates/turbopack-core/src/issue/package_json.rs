@@ -2,7 +2,7 @@ use anyhow::Result;
 use turbo_tasks::primitives::StringVc;
 use turbo_tasks_fs::FileSystemPathVc;
 
-use super::{Issue, IssueVc};
+use super::{codes, Issue, IssueVc};
 
 #[turbo_tasks::value(shared)]
 pub struct PackageJsonIssue {
@@ -22,6 +22,11 @@ impl Issue for PackageJsonIssue {
         StringVc::cell("parse".to_string())
     }
 
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::parse::PACKAGE_JSON.to_string())
+    }
+
     #[turbo_tasks::function]
     fn context(&self) -> FileSystemPathVc {
         self.path
@@ -0,0 +1,156 @@
+use std::{
+    any::Any,
+    backtrace::Backtrace,
+    cell::{Cell, RefCell},
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    sync::Once,
+};
+
+use anyhow::Result;
+use futures::{future::poll_fn, FutureExt};
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::FileSystemPathVc;
+
+use super::{codes, Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+
+/// A panic in one of the per-module tasks, e. g. an analyzer bug triggered by
+/// the source of the module. The task continues without the result of the
+/// panicking stage.
+#[turbo_tasks::value(shared)]
+pub struct PanicIssue {
+    pub path: FileSystemPathVc,
+    /// The stage of processing the module which panicked, e. g. "parsing".
+    pub stage: String,
+    pub message: String,
+    pub backtrace: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for PanicIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!("Internal error while {} the module", self.stage))
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("panic".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::parse::PANIC.to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(format!(
+            "turbopack panicked while {} the module: {}\nThis is a bug in turbopack, please \
+             report it together with the module.",
+            self.stage, self.message
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn detail(&self) -> StringVc {
+        StringVc::cell(self.backtrace.clone())
+    }
+}
+
+thread_local! {
+    /// How many [catch_panic] futures are being polled on this thread.
+    static CATCHING: Cell<usize> = Cell::new(0);
+    /// The backtrace of the last panic caught by [catch_panic] on this thread.
+    static BACKTRACE: RefCell<Option<String>> = RefCell::new(None);
+}
+
+static INSTALL_HOOK: Once = Once::new();
+
+/// Installs a panic hook which captures the backtrace of panics caught by
+/// [catch_panic] instead of printing them. Other panics are passed to the
+/// previous hook.
+fn install_hook() {
+    INSTALL_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if CATCHING.with(|c| c.get()) > 0 {
+                let backtrace = Backtrace::force_capture().to_string();
+                BACKTRACE.with(|b| *b.borrow_mut() = Some(backtrace));
+            } else {
+                previous(info);
+            }
+        }));
+    });
+}
+
+struct CatchingGuard;
+
+impl CatchingGuard {
+    fn new() -> Self {
+        CATCHING.with(|c| c.set(c.get() + 1));
+        CatchingGuard
+    }
+}
+
+impl Drop for CatchingGuard {
+    fn drop(&mut self) {
+        CATCHING.with(|c| c.set(c.get() - 1));
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&'static str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
+
+/// Runs `future`, which processes the module at `path`. A panic in it is
+/// converted into a [PanicIssue] for the module with the panic message and
+/// backtrace, and `None` is returned instead of failing the whole task.
+pub async fn catch_panic<T>(
+    path: FileSystemPathVc,
+    stage: &str,
+    future: impl Future<Output = Result<T>>,
+) -> Result<Option<T>> {
+    install_hook();
+    let mut future = Box::pin(future);
+    let result = AssertUnwindSafe(poll_fn(|cx| {
+        let _guard = CatchingGuard::new();
+        future.as_mut().poll(cx)
+    }))
+    .catch_unwind()
+    .await;
+    match result {
+        Ok(result) => Ok(Some(result?)),
+        Err(payload) => {
+            let backtrace = BACKTRACE
+                .with(|b| b.borrow_mut().take())
+                .unwrap_or_default();
+            PanicIssue {
+                path,
+                stage: stage.to_string(),
+                message: panic_message(&*payload),
+                backtrace,
+            }
+            .cell()
+            .as_issue()
+            .emit();
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::FileSystemPathVc;
+
+use super::{codes, Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+
+/// A Node.js builtin which was replaced by its configured polyfill, see
+/// [Polyfills](crate::resolve::polyfills::Polyfills).
+#[turbo_tasks::value(shared)]
+pub struct PolyfillIssue {
+    pub context: FileSystemPathVc,
+    /// The builtin, e. g. `path`.
+    pub module: String,
+    /// The request replacing the builtin, or `None` for an empty module.
+    pub polyfill: Option<String>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for PolyfillIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Info.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(match &self.polyfill {
+            Some(polyfill) => format!(
+                "Node.js builtin `{}` is polyfilled with `{}`",
+                self.module, polyfill
+            ),
+            None => format!(
+                "Node.js builtin `{}` is replaced with an empty module",
+                self.module
+            ),
+        })
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("resolve".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::resolve::POLYFILL.to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(format!(
+            "The environment doesn't provide `{}`, so the configured polyfill is bundled instead. \
+             Polyfills can behave differently than Node.js.",
+            self.module
+        ))
+    }
+}
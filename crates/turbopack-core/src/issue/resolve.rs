@@ -2,7 +2,7 @@ use anyhow::Result;
 use turbo_tasks::{primitives::StringVc, ValueToString};
 use turbo_tasks_fs::FileSystemPathVc;
 
-use super::{Issue, IssueVc};
+use super::{codes, Issue, IssueVc};
 use crate::resolve::{options::ResolveOptionsVc, parse::RequestVc};
 
 #[turbo_tasks::value(shared)]
@@ -29,6 +29,11 @@ impl Issue for ResolvingIssue {
         StringVc::cell("resolve".to_string())
     }
 
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::resolve::UNRESOLVABLE.to_string())
+    }
+
     #[turbo_tasks::function]
     fn context(&self) -> FileSystemPathVc {
         self.context
@@ -72,3 +77,53 @@ Type of request: {request_type}",
     // TODO add sub_issue for a description of resolve_options
     // TODO add source link
 }
+
+/// A subpath of a package is requested, which its package.json "exports"
+/// field doesn't export for the active conditions.
+#[turbo_tasks::value(shared)]
+pub struct PackageSubpathNotExportedIssue {
+    pub package_json: FileSystemPathVc,
+    pub subpath: String,
+    /// The conditions which are set when resolving the subpath.
+    pub conditions: Vec<String>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for PackageSubpathNotExportedIssue {
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!(
+            "Package subpath '{}' is not exported",
+            self.subpath
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("resolve".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::resolve::PACKAGE_PATH_NOT_EXPORTED.to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.package_json
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(format!(
+            "The \"exports\" field of the package.json doesn't define the subpath '{}' for the \
+             conditions {}, so it can't be imported.",
+            self.subpath,
+            if self.conditions.is_empty() {
+                "(none)".to_string()
+            } else {
+                self.conditions.join(", ")
+            }
+        ))
+    }
+}
@@ -2,7 +2,7 @@ use anyhow::Result;
 use turbo_tasks::primitives::StringVc;
 use turbo_tasks_fs::FileSystemPathVc;
 
-use super::{Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+use super::{codes, Issue, IssueSeverity, IssueSeverityVc, IssueVc};
 
 #[turbo_tasks::value(shared)]
 pub struct UnsupportedModuleIssue {
@@ -23,6 +23,11 @@ impl Issue for UnsupportedModuleIssue {
         StringVc::cell("resolve".to_string())
     }
 
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::resolve::UNSUPPORTED_MODULE.to_string())
+    }
+
     #[turbo_tasks::function]
     fn title(&self) -> StringVc {
         StringVc::cell("Unsupported module".into())
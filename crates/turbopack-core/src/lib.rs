@@ -4,8 +4,10 @@
 #![feature(type_alias_impl_trait)]
 #![feature(assert_matches)]
 #![feature(lint_reasons)]
+#![feature(error_generic_member_access)]
 
 pub mod asset;
+pub mod cache;
 pub mod chunk;
 pub mod code_builder;
 pub mod context;
@@ -17,6 +19,7 @@ pub mod resolve;
 pub mod source_asset;
 pub mod source_map;
 pub mod source_pos;
+pub mod source_transform;
 pub mod target;
 mod utils;
 pub mod version;
@@ -0,0 +1,290 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Write,
+    iter::once,
+};
+
+use anyhow::Result;
+use indexmap::IndexSet;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{primitives::StringVc, trace::TraceRawVcs, Value, ValueToString};
+
+use super::AssetReferenceKind;
+use crate::{
+    asset::{AssetVc, AssetsVc},
+    chunk::ChunkVc,
+};
+
+/// The format of a rendered [ModuleGraph].
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(PartialOrd, Ord, Debug, Clone, Copy, Hash)]
+pub enum ModuleGraphFormat {
+    Json,
+    /// The graph in the DOT language of Graphviz.
+    Dot,
+}
+
+/// A reference between two modules of a [ModuleGraph].
+#[derive(PartialEq, Eq, Debug, Clone, TraceRawVcs, Serialize, Deserialize)]
+pub struct ModuleGraphReference {
+    pub from: AssetVc,
+    pub to: AssetVc,
+    pub kind: AssetReferenceKind,
+    /// The description of the reference, e. g. `import ./foo`.
+    pub description: String,
+}
+
+/// The maximum number of import chains returned for a module. The number of
+/// chains can grow exponentially with the size of the graph.
+const MAX_IMPORT_CHAINS: usize = 100;
+
+/// Chains of references from the roots of a [ModuleGraph] to a module, each
+/// starting at a root.
+#[turbo_tasks::value(transparent)]
+pub struct ImportChains(Vec<Vec<ModuleGraphReference>>);
+
+/// The resolved references between modules, starting from some roots. It's
+/// meant for debugging why a module ends up in the output.
+///
+/// Chunks are not part of the graph, the modules placed into them are roots
+/// instead, and the chunks they reference are followed.
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub struct ModuleGraph {
+    pub roots: IndexSet<AssetVc>,
+    /// All modules of the graph in the order they are found.
+    pub modules: IndexSet<AssetVc>,
+    pub references: Vec<ModuleGraphReference>,
+}
+
+#[turbo_tasks::value_impl]
+impl ModuleGraphVc {
+    /// Walks the references of `roots`, which can be modules or chunks.
+    #[turbo_tasks::function]
+    pub async fn from_roots(roots: AssetsVc) -> Result<Self> {
+        let mut graph = ModuleGraph {
+            roots: IndexSet::new(),
+            modules: IndexSet::new(),
+            references: Vec::new(),
+        };
+        let mut chunks = IndexSet::new();
+        let mut queue = VecDeque::new();
+        for &root in roots.await?.iter() {
+            queue.push_back((root.resolve().await?, true));
+        }
+        while let Some((asset, is_root)) = queue.pop_front() {
+            if let Some(chunk) = ChunkVc::resolve_from(asset).await? {
+                if !chunks.insert(chunk) {
+                    continue;
+                }
+                for chunk_item in chunk.chunk_items().await?.iter() {
+                    if let Some(module) = *chunk_item.asset().await? {
+                        queue.push_back((module.resolve().await?, true));
+                    }
+                }
+                for reference in asset.references().await?.iter() {
+                    for &asset in reference.resolve_reference().primary_assets().await?.iter() {
+                        queue.push_back((asset.resolve().await?, false));
+                    }
+                }
+                continue;
+            }
+            if is_root {
+                graph.roots.insert(asset);
+            }
+            if !graph.modules.insert(asset) {
+                continue;
+            }
+            for reference in asset.references().await?.iter() {
+                let kind = *reference.kind().await?;
+                let description = reference.to_string().await?;
+                for &referenced in reference.resolve_reference().primary_assets().await?.iter() {
+                    let referenced = referenced.resolve().await?;
+                    if ChunkVc::resolve_from(referenced).await?.is_none() {
+                        graph.references.push(ModuleGraphReference {
+                            from: asset,
+                            to: referenced,
+                            kind,
+                            description: description.clone_value(),
+                        });
+                    }
+                    queue.push_back((referenced, false));
+                }
+            }
+        }
+        Ok(graph.cell())
+    }
+
+    /// The modules of the graph with the given path, e. g. multiple modules
+    /// when a file is processed in different contexts.
+    #[turbo_tasks::function]
+    pub async fn modules_at_path(self, path: &str) -> Result<AssetsVc> {
+        let mut modules = Vec::new();
+        for &module in self.await?.modules.iter() {
+            if module.path().await?.path == path {
+                modules.push(module);
+            }
+        }
+        Ok(AssetsVc::cell(modules))
+    }
+
+    /// Walks the graph backwards from `module` to find out why it's included,
+    /// like the reasons of webpack. Returns all chains of references without
+    /// cycles from a root to `module`, but at most [MAX_IMPORT_CHAINS].
+    #[turbo_tasks::function]
+    pub async fn import_chains(self, module: AssetVc) -> Result<ImportChainsVc> {
+        let graph = self.await?;
+        let module = module.resolve().await?;
+        let mut referencing = HashMap::<AssetVc, Vec<&ModuleGraphReference>>::new();
+        for reference in graph.references.iter() {
+            referencing.entry(reference.to).or_default().push(reference);
+        }
+
+        let mut chains = Vec::new();
+        // Every entry is a chain from a module to `module`, in reverse order.
+        let mut stack = vec![(module, Vec::<&ModuleGraphReference>::new())];
+        while let Some((current, chain)) = stack.pop() {
+            if chains.len() >= MAX_IMPORT_CHAINS {
+                break;
+            }
+            if graph.roots.contains(&current) {
+                chains.push(chain.iter().rev().map(|&r| r.clone()).collect());
+                continue;
+            }
+            let in_chain = chain
+                .iter()
+                .map(|reference| reference.to)
+                .chain(once(current))
+                .collect::<HashSet<_>>();
+            for &reference in referencing.get(&current).into_iter().flatten() {
+                if in_chain.contains(&reference.from) {
+                    continue;
+                }
+                let mut chain = chain.clone();
+                chain.push(reference);
+                stack.push((reference.from, chain));
+            }
+        }
+        Ok(ImportChainsVc::cell(chains))
+    }
+
+    /// Renders the graph with the paths of the modules as their ids.
+    #[turbo_tasks::function]
+    pub async fn render(self, format: Value<ModuleGraphFormat>) -> Result<StringVc> {
+        let graph = self.await?;
+        let mut ids = Vec::with_capacity(graph.modules.len());
+        for module in graph.modules.iter() {
+            ids.push(module.path().to_string().await?);
+        }
+        let id_of = |module: &AssetVc| graph.modules.get_full(module).map(|(i, _)| &**ids[i]);
+
+        let content = match format.into_value() {
+            ModuleGraphFormat::Json => {
+                #[derive(Serialize)]
+                struct JsonModule<'a> {
+                    id: &'a str,
+                    root: bool,
+                }
+                #[derive(Serialize)]
+                struct JsonReference<'a> {
+                    from: &'a str,
+                    to: &'a str,
+                    kind: &'static str,
+                    description: &'a str,
+                }
+                #[derive(Serialize)]
+                struct JsonGraph<'a> {
+                    modules: Vec<JsonModule<'a>>,
+                    references: Vec<JsonReference<'a>>,
+                }
+
+                let json = JsonGraph {
+                    modules: graph
+                        .modules
+                        .iter()
+                        .zip(ids.iter())
+                        .map(|(module, id)| JsonModule {
+                            id: id.as_str(),
+                            root: graph.roots.contains(module),
+                        })
+                        .collect(),
+                    references: graph
+                        .references
+                        .iter()
+                        .filter_map(|reference| {
+                            Some(JsonReference {
+                                from: id_of(&reference.from)?,
+                                to: id_of(&reference.to)?,
+                                kind: reference.kind.as_str(),
+                                description: &reference.description,
+                            })
+                        })
+                        .collect(),
+                };
+                serde_json::to_string_pretty(&json)?
+            }
+            ModuleGraphFormat::Dot => {
+                let mut dot = "digraph modules {\n".to_string();
+                for (module, id) in graph.modules.iter().zip(ids.iter()) {
+                    if graph.roots.contains(module) {
+                        writeln!(dot, "  \"{}\" [style=bold];", dot_escape(id))?;
+                    } else {
+                        writeln!(dot, "  \"{}\";", dot_escape(id))?;
+                    }
+                }
+                for reference in graph.references.iter() {
+                    if let (Some(from), Some(to)) = (id_of(&reference.from), id_of(&reference.to)) {
+                        let style = match reference.kind {
+                            AssetReferenceKind::Async => ", style=dashed",
+                            _ => "",
+                        };
+                        writeln!(
+                            dot,
+                            "  \"{}\" -> \"{}\" [label=\"{}\", tooltip=\"{}\"{}];",
+                            dot_escape(from),
+                            dot_escape(to),
+                            reference.kind.as_str(),
+                            dot_escape(&reference.description),
+                            style
+                        )?;
+                    }
+                }
+                dot.push_str("}\n");
+                dot
+            }
+        };
+        Ok(StringVc::cell(content))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ImportChainsVc {
+    /// Renders the chains as text, one module per line with the reference
+    /// to the next module below it. Chains are separated by an empty line.
+    #[turbo_tasks::function]
+    pub async fn to_text(self) -> Result<StringVc> {
+        let mut text = String::new();
+        for chain in self.await?.iter() {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            for reference in chain.iter() {
+                writeln!(text, "{}", reference.from.path().to_string().await?)?;
+                writeln!(
+                    text,
+                    "  {} {}",
+                    reference.kind.as_str(),
+                    reference.description
+                )?;
+            }
+            if let Some(last) = chain.last() {
+                writeln!(text, "{}", last.to.path().to_string().await?)?;
+            }
+        }
+        Ok(StringVc::cell(text))
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
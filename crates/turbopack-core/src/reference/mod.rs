@@ -5,10 +5,11 @@ use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
 
 use crate::{
     asset::{AssetVc, AssetsVc},
-    issue::IssueVc,
+    issue::{IssueVc, OptionIssueSourceVc},
     resolve::{ResolveResult, ResolveResultVc},
 };
 
+pub mod graph;
 pub mod source_map;
 
 pub use source_map::SourceMapVc;
@@ -23,8 +24,43 @@ pub use source_map::SourceMapVc;
 #[turbo_tasks::value_trait]
 pub trait AssetReference: ValueToString {
     fn resolve_reference(&self) -> ResolveResultVc;
-    // TODO think about different types
-    // fn kind(&self) -> AssetReferenceTypeVc;
+
+    /// How the referenced assets are used by the referencing asset. It's only
+    /// informational, e. g. to show in the module graph.
+    fn kind(&self) -> AssetReferenceKindVc {
+        AssetReferenceKind::Asset.cell()
+    }
+
+    /// Where the reference is located in the code of the referencing asset,
+    /// so issues about it can be shown with a code frame.
+    fn issue_source(&self) -> OptionIssueSourceVc {
+        OptionIssueSourceVc::cell(None)
+    }
+}
+
+/// The kind of an [AssetReference].
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Copy, Hash)]
+pub enum AssetReferenceKind {
+    /// A static ESM import or export.
+    Esm,
+    /// A CommonJS `require` or `require.resolve`.
+    Cjs,
+    /// A reference which is loaded on demand, e. g. a dynamic `import()`.
+    Async,
+    /// Any other reference, e. g. to a file used by the asset.
+    Asset,
+}
+
+impl AssetReferenceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssetReferenceKind::Esm => "esm",
+            AssetReferenceKind::Cjs => "cjs",
+            AssetReferenceKind::Async => "async",
+            AssetReferenceKind::Asset => "asset",
+        }
+    }
 }
 
 /// Multiple [AssetReference]s
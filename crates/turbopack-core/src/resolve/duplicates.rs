@@ -0,0 +1,285 @@
+use std::cmp::Ordering;
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use turbo_tasks::{CompletionVc, ValueToString};
+use turbo_tasks_fs::{glob::GlobVc, FileJsonContent, FileSystemEntryType, FileSystemPathVc};
+
+use super::{
+    lockfile::find_lockfile,
+    options::{ImportMapping, ResolvedMap, ResolvedMapVc},
+    ResolveResult,
+};
+use crate::{
+    asset::AssetVc,
+    chunk::stats::content_size,
+    issue::duplicate_package::{DuplicatePackageCopy, DuplicatePackageIssue},
+    reference::graph::{ImportChainsVc, ModuleGraphVc},
+    source_asset::SourceAssetVc,
+};
+
+/// A copy of a package installed in `node_modules`.
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub struct PackageCopy {
+    /// The directory of the package.
+    pub root: FileSystemPathVc,
+    /// The version from its package.json.
+    pub version: String,
+    /// The modules of the package which are part of the module graph.
+    pub modules: Vec<AssetVc>,
+    /// The sum of the sizes of the sources of the modules.
+    pub size: u64,
+}
+
+/// A package which is included in multiple versions.
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub struct DuplicatePackage {
+    pub name: String,
+    /// The copies of the package, sorted by version, the highest first.
+    pub copies: Vec<PackageCopyVc>,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct DuplicatePackages(Vec<DuplicatePackageVc>);
+
+/// Finds the packages of which multiple versions are part of the module
+/// graph. Multiple copies of the same version are not reported, they are
+/// usually an intended result of the package manager's install layout.
+#[turbo_tasks::function]
+pub async fn find_duplicate_packages(graph: ModuleGraphVc) -> Result<DuplicatePackagesVc> {
+    // The modules of every copy, grouped by the name of the package.
+    let mut packages = IndexMap::<String, IndexMap<String, Vec<AssetVc>>>::new();
+    let mut roots = IndexMap::<String, FileSystemPathVc>::new();
+    for &module in graph.await?.modules.iter() {
+        let path = module.path();
+        if let Some((root, name)) = package_root(&path.await?.path) {
+            roots
+                .entry(root.to_string())
+                .or_insert_with(|| path.root().join(root));
+            packages
+                .entry(name.to_string())
+                .or_default()
+                .entry(root.to_string())
+                .or_default()
+                .push(module);
+        }
+    }
+
+    let mut duplicates = Vec::new();
+    for (name, package_copies) in packages {
+        if package_copies.len() < 2 {
+            continue;
+        }
+        let mut copies = Vec::new();
+        for (root, modules) in package_copies {
+            let root = roots[&root];
+            let version = match &*root.join("package.json").read_json().await? {
+                FileJsonContent::Content(package_json) => package_json["version"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                _ => String::new(),
+            };
+            let mut size = 0;
+            for module in modules.iter() {
+                size += *content_size(module.content()).await?;
+            }
+            copies.push(PackageCopy {
+                root,
+                version,
+                modules,
+                size,
+            });
+        }
+        copies.sort_by(|a, b| compare_versions(&b.version, &a.version));
+        if copies.first().map(|copy| &copy.version) == copies.last().map(|copy| &copy.version) {
+            continue;
+        }
+        duplicates.push(
+            DuplicatePackage {
+                name,
+                copies: copies.into_iter().map(|copy| copy.cell()).collect(),
+            }
+            .cell(),
+        );
+    }
+    Ok(DuplicatePackagesVc::cell(duplicates))
+}
+
+/// Emits an issue for every package of which multiple versions are part of
+/// the module graph, with the sizes of the copies, the import chains which
+/// include them and the versions the lockfile installs.
+#[turbo_tasks::function]
+pub async fn report_duplicate_packages(graph: ModuleGraphVc) -> Result<CompletionVc> {
+    for duplicate in find_duplicate_packages(graph).await?.iter() {
+        let duplicate = duplicate.await?;
+        let mut copies = Vec::new();
+        for copy in duplicate.copies.iter() {
+            let copy = copy.await?;
+            // The first module found in the graph is the one imported from
+            // outside of the package.
+            let import_chain = match copy.modules.first() {
+                Some(&module) => {
+                    let chains = graph.import_chains(module).await?;
+                    ImportChainsVc::cell(chains.iter().take(1).cloned().collect())
+                        .to_text()
+                        .await?
+                        .clone_value()
+                }
+                None => String::new(),
+            };
+            copies.push(DuplicatePackageCopy {
+                path: copy.root.to_string().await?.clone_value(),
+                version: copy.version.clone(),
+                size: copy.size,
+                import_chain,
+            });
+        }
+        if let Some(highest) = duplicate.copies.first() {
+            let context = highest.await?.root;
+            let lockfile_versions = match *find_lockfile(context).await? {
+                Some(lockfile) => lockfile.versions(&duplicate.name).await?.clone_value(),
+                None => Vec::new(),
+            };
+            DuplicatePackageIssue {
+                context,
+                name: duplicate.name.clone(),
+                copies,
+                lockfile_versions,
+            }
+            .cell()
+            .as_issue()
+            .emit();
+        }
+    }
+    Ok(CompletionVc::new())
+}
+
+#[turbo_tasks::value_impl]
+impl DuplicatePackagesVc {
+    /// A [ResolvedMap] which replaces the modules of all but the highest
+    /// version of a package with the same files of the highest version, when
+    /// they exist there. Using it for resolving dedupes the packages, which
+    /// may break packages relying on APIs of older versions.
+    #[turbo_tasks::function]
+    pub async fn dedupe_to_highest(self) -> Result<ResolvedMapVc> {
+        let mut by_glob = Vec::new();
+        for duplicate in self.await?.iter() {
+            let duplicate = duplicate.await?;
+            let (highest, lower) = match duplicate.copies.split_first() {
+                Some(split) => split,
+                None => continue,
+            };
+            let highest_root = highest.await?.root;
+            for copy in lower.iter() {
+                let copy = copy.await?;
+                let root = copy.root.await?;
+                for module in copy.modules.iter() {
+                    let path = module.path().await?;
+                    // Paths which can't be matched literally by a glob are
+                    // kept.
+                    let relative = match root.get_path_to(&path) {
+                        Some(relative) if !relative.contains(['*', '?', '[', '{', '\\']) => {
+                            relative
+                        }
+                        _ => continue,
+                    };
+                    let target = highest_root.join(relative);
+                    if !matches!(*target.get_type().await?, FileSystemEntryType::File) {
+                        continue;
+                    }
+                    by_glob.push((
+                        copy.root,
+                        GlobVc::new(relative),
+                        ImportMapping::Direct(
+                            ResolveResult::Single(SourceAssetVc::new(target).into(), Vec::new())
+                                .into(),
+                        )
+                        .into(),
+                    ));
+                }
+            }
+        }
+        Ok(ResolvedMap { by_glob }.cell())
+    }
+}
+
+/// Returns the directory and the name of the npm package `path` belongs to,
+/// e. g. `node_modules/@next/env` and `@next/env` for
+/// `node_modules/@next/env/dist/index.js`. Nested packages belong to the
+/// innermost package.
+pub(crate) fn package_root(path: &str) -> Option<(&str, &str)> {
+    let index = path.rfind("node_modules/")? + "node_modules/".len();
+    let rest = &path[index..];
+    let mut segments = rest.splitn(3, '/');
+    let first = segments.next().filter(|s| !s.is_empty())?;
+    let name = if first.starts_with('@') {
+        let second = segments.next()?;
+        &rest[..first.len() + 1 + second.len()]
+    } else {
+        first
+    };
+    Some((&path[..index + name.len()], name))
+}
+
+/// Compares two semver versions by their numeric parts. A version with a
+/// prerelease tag is lower than the same version without.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn split(version: &str) -> (Vec<u64>, Option<&str>) {
+        let version = version.split('+').next().unwrap_or_default();
+        let (numbers, prerelease) = match version.split_once('-') {
+            Some((numbers, prerelease)) => (numbers, Some(prerelease)),
+            None => (version, None),
+        };
+        (
+            numbers
+                .split('.')
+                .map(|part| part.parse().unwrap_or_default())
+                .collect(),
+            prerelease,
+        )
+    }
+    let (a_numbers, a_prerelease) = split(a);
+    let (b_numbers, b_prerelease) = split(b);
+    a_numbers
+        .cmp(&b_numbers)
+        .then_with(|| match (a_prerelease, b_prerelease) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(a), Some(b)) => a.cmp(b),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::{compare_versions, package_root};
+
+    #[test]
+    fn package_roots_of_paths() {
+        assert_eq!(
+            package_root("node_modules/react/index.js"),
+            Some(("node_modules/react", "react"))
+        );
+        assert_eq!(
+            package_root("app/node_modules/@next/env/dist/index.js"),
+            Some(("app/node_modules/@next/env", "@next/env"))
+        );
+        assert_eq!(
+            package_root("node_modules/a/node_modules/b/lib/b.js"),
+            Some(("node_modules/a/node_modules/b", "b"))
+        );
+        assert_eq!(package_root("src/index.js"), None);
+    }
+
+    #[test]
+    fn compares_versions() {
+        assert_eq!(compare_versions("1.10.0", "1.9.0"), Ordering::Greater);
+        assert_eq!(compare_versions("2.0.0-beta.1", "2.0.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.0.0+build", "1.0.0"), Ordering::Equal);
+    }
+}
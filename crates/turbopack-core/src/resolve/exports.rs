@@ -318,3 +318,45 @@ impl ExportsField {
         self.0.lookup(request)
     }
 }
+
+/// Content of an "imports" field in a package.json, which maps `#`-prefixed
+/// requests within the package to files of the package or to other packages.
+#[derive(PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportsField(AliasMap<ExportsValue>);
+
+impl TryFrom<&Value> for ImportsField {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &Value) -> Result<Self> {
+        // https://nodejs.org/api/packages.html#imports
+        let object = match value {
+            Value::Object(object) => object,
+            _ => bail!("\"imports\" field must be an object"),
+        };
+        let mut map = AliasMap::new();
+        for (key, value) in object.iter() {
+            if !key.starts_with('#') || key == "#" || key.starts_with("#/") {
+                bail!(
+                    "invalid key \"{}\" in the imports field, keys must start with \"#\"",
+                    key
+                );
+            }
+            let mut value: ExportsValue = value.try_into()?;
+            let pattern = if is_folder_shorthand(key) {
+                expand_folder_shorthand(key, &mut value)?
+            } else {
+                AliasPattern::parse(key)
+            };
+            map.insert(pattern, value);
+        }
+        Ok(Self(map))
+    }
+}
+
+impl ImportsField {
+    /// Looks up a `#`-prefixed request string in the "imports" field. Returns
+    /// an iterator of matching targets, like [ExportsField::lookup].
+    pub fn lookup<'a>(&'a self, request: &'a str) -> AliasMapLookupIterator<'a, ExportsValue> {
+        self.0.lookup(request)
+    }
+}
@@ -0,0 +1,268 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{
+    primitives::{BoolVc, Regex},
+    trace::TraceRawVcs,
+};
+use turbo_tasks_fs::FileSystemPathVc;
+
+use super::{
+    find_context_file,
+    options::{ConditionValue, ResolveIntoPackage, ResolveOptions, ResolveOptionsVc},
+    package_json_field,
+    parse::RequestVc,
+    resolve, FindContextFileResult, ResolveResult, ResolveResultOptionVc, ResolveResultVc,
+    SpecialType,
+};
+use crate::environment::{EnvironmentVc, ExecutionEnvironment};
+
+/// How an external module is loaded at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TraceRawVcs)]
+pub enum ExternalType {
+    /// Loaded with `require()`.
+    CommonJs,
+    /// Loaded as an ES module. Chunks are not emitted as ES modules yet, so
+    /// it's loaded like [ExternalType::Import].
+    Module,
+    /// Loaded with `import()`. Static imports and requires of it are loaded
+    /// like [ExternalType::CommonJs] for now.
+    Import,
+    /// Read from a global variable, e. g. `jQuery` provided by a script tag.
+    Global,
+}
+
+impl ExternalType {
+    /// The type of externals in `environment` when none is configured:
+    /// Node.js loads them with `require()`, edge functions with `import()`
+    /// and browsers read them from global variables.
+    pub async fn for_environment(environment: EnvironmentVc) -> Result<Self> {
+        Ok(match *environment.execution().await? {
+            ExecutionEnvironment::NodeJsBuildTime(_) | ExecutionEnvironment::NodeJsLambda(_) => {
+                ExternalType::CommonJs
+            }
+            ExecutionEnvironment::EdgeFunction(_) => ExternalType::Import,
+            ExecutionEnvironment::Browser(_) | ExecutionEnvironment::Custom(_) => {
+                ExternalType::Global
+            }
+        })
+    }
+}
+
+/// Which requests are external.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub enum ExternalsMatcher {
+    /// Requests which equal the name, e. g. `react`.
+    Exact(String),
+    /// Requests starting with the prefix, e. g. `@aws-sdk/`.
+    Prefix(String),
+    Regex(#[turbo_tasks(trace_ignore)] Regex),
+}
+
+impl ExternalsMatcher {
+    fn matches(&self, request: &str) -> bool {
+        match self {
+            ExternalsMatcher::Exact(name) => request == name,
+            ExternalsMatcher::Prefix(prefix) => request.starts_with(prefix),
+            ExternalsMatcher::Regex(regex) => regex.is_match(request),
+        }
+    }
+}
+
+/// The module an external request is replaced with.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct External {
+    /// The request or the name of the global variable loading the module at
+    /// runtime.
+    pub request: String,
+    /// Overrides the type of the [Externals].
+    pub ty: Option<ExternalType>,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct ExternalOption(Option<External>);
+
+/// Decides whether requests are external, for conditions which can't be
+/// expressed with an [ExternalsMatcher].
+#[turbo_tasks::value_trait]
+pub trait ExternalsCallback {
+    fn external(&self, request: &str) -> ExternalOptionVc;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+struct ExternalsEntry {
+    matcher: ExternalsMatcher,
+    target: Option<String>,
+    ty: Option<ExternalType>,
+}
+
+/// Requests which are not bundled, but loaded at runtime, e. g. from
+/// `node_modules` in Node.js or from a global variable in browsers. They are
+/// checked in the order they were added, before the callbacks.
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Default)]
+pub struct Externals {
+    entries: Vec<ExternalsEntry>,
+    callbacks: Vec<ExternalsCallbackVc>,
+    /// The type of externals which don't configure one, see
+    /// [ExternalsVc::with_environment] for the default.
+    external_type: Option<ExternalType>,
+}
+
+impl Externals {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes requests matching `matcher` external. They are loaded with the
+    /// request itself or with `target` at runtime. `ty` overrides the
+    /// type of the externals.
+    pub fn add(
+        &mut self,
+        matcher: ExternalsMatcher,
+        target: Option<String>,
+        ty: Option<ExternalType>,
+    ) {
+        self.entries.push(ExternalsEntry {
+            matcher,
+            target,
+            ty,
+        });
+    }
+
+    pub fn add_callback(&mut self, callback: ExternalsCallbackVc) {
+        self.callbacks.push(callback);
+    }
+
+    pub fn set_external_type(&mut self, ty: ExternalType) {
+        self.external_type = Some(ty);
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ExternalsVc {
+    /// Uses the type of externals of `environment` unless a type is set.
+    #[turbo_tasks::function]
+    pub async fn with_environment(self, environment: EnvironmentVc) -> Result<Self> {
+        let mut externals = self.await?.clone_value();
+        if externals.external_type.is_none() {
+            externals.external_type = Some(ExternalType::for_environment(environment).await?);
+        }
+        Ok(externals.cell())
+    }
+
+    #[turbo_tasks::function]
+    pub async fn lookup(self, request: RequestVc) -> Result<ResolveResultOptionVc> {
+        let this = self.await?;
+        let request = match request.await?.request() {
+            Some(request) => request,
+            None => return Ok(ResolveResultOptionVc::cell(None)),
+        };
+        let mut external = this
+            .entries
+            .iter()
+            .find(|entry| entry.matcher.matches(&request))
+            .map(|entry| External {
+                request: entry.target.clone().unwrap_or_else(|| request.clone()),
+                ty: entry.ty,
+            });
+        if external.is_none() {
+            for callback in this.callbacks.iter() {
+                if let Some(result) = &*callback.external(&request).await? {
+                    external = Some(result.clone());
+                    break;
+                }
+            }
+        }
+        let External { request, ty } = match external {
+            Some(external) => external,
+            None => return Ok(ResolveResultOptionVc::cell(None)),
+        };
+        let special = match ty.or(this.external_type).unwrap_or(ExternalType::CommonJs) {
+            ExternalType::CommonJs | ExternalType::Module | ExternalType::Import => {
+                SpecialType::OriginalReferenceTypeExternal(request)
+            }
+            ExternalType::Global => SpecialType::GlobalExternal(request),
+        };
+        Ok(ResolveResultOptionVc::cell(Some(
+            ResolveResult::Special(special, Vec::new()).cell(),
+        )))
+    }
+}
+
+/// Whether Node.js loads the file as an ES module: `.mjs` files and `.js`
+/// files in packages with `"type": "module"`.
+#[turbo_tasks::function]
+async fn is_esm_file(path: FileSystemPathVc) -> Result<BoolVc> {
+    Ok(BoolVc::cell(match path.await?.extension() {
+        Some("mjs") => true,
+        Some("js") => match &*find_context_file(path.parent(), "package.json").await? {
+            FindContextFileResult::Found(package_json, _) => {
+                package_json_field(package_json.read_json(), "type")
+                    .await?
+                    .as_str()
+                    == Some("module")
+            }
+            FindContextFileResult::NotFound(_) => false,
+        },
+        _ => false,
+    }))
+}
+
+/// Makes a request external which resolved to a file in node_modules, so
+/// Node.js loads the package from there at runtime. External modules are
+/// loaded with `require()` unless they are imported with `import()`, so
+/// packages which can only be loaded as ES modules stay bundled otherwise.
+pub(super) async fn externalize_node_module(
+    context: FileSystemPathVc,
+    request: RequestVc,
+    result: ResolveResultVc,
+    options: ResolveOptionsVc,
+    options_value: &ResolveOptions,
+) -> Result<Option<ResolveResultVc>> {
+    let asset = match &*result.await? {
+        ResolveResult::Single(asset, _) => *asset,
+        _ => return Ok(None),
+    };
+    let path = asset.path();
+    if !path
+        .await?
+        .path
+        .split('/')
+        .any(|segment| segment == "node_modules")
+    {
+        return Ok(None);
+    }
+    let request_string = match request.await?.request() {
+        Some(request) => request,
+        None => return Ok(None),
+    };
+    if *is_esm_file(path).await? && !options_value.async_import {
+        // The package might provide a CommonJS module for `require()` in
+        // addition to the ES module matching the "import" condition
+        let requires = options_value.into_package.iter().any(|item| {
+            matches!(
+                item,
+                ResolveIntoPackage::ExportsField { conditions, .. }
+                    if conditions.get("require") == Some(&ConditionValue::Set)
+            )
+        });
+        if requires {
+            return Ok(None);
+        }
+        let required = resolve(context, request, options.with_require_conditions());
+        return Ok(match &*required.await? {
+            ResolveResult::Special(SpecialType::OriginalReferenceTypeExternal(_), _) => {
+                Some(required)
+            }
+            _ => None,
+        });
+    }
+    Ok(Some(
+        ResolveResult::Special(
+            SpecialType::OriginalReferenceTypeExternal(request_string),
+            Vec::new(),
+        )
+        .cell(),
+    ))
+}
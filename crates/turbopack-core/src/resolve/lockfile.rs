@@ -0,0 +1,379 @@
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use turbo_tasks::{primitives::StringsVc, trace::TraceRawVcs, CompletionVc};
+use turbo_tasks_fs::{FileContent, FileSystemPathVc};
+
+use super::{find_context_file, FindContextFileResult};
+use crate::issue::lockfile::LockfileIssue;
+
+/// The package manager a lockfile was written by.
+#[derive(
+    Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TraceRawVcs,
+)]
+pub enum LockfileKind {
+    /// `package-lock.json`
+    Npm,
+    /// `pnpm-lock.yaml`
+    Pnpm,
+    /// `yarn.lock`
+    Yarn,
+}
+
+impl LockfileKind {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            LockfileKind::Npm => "package-lock.json",
+            LockfileKind::Pnpm => "pnpm-lock.yaml",
+            LockfileKind::Yarn => "yarn.lock",
+        }
+    }
+}
+
+/// A package installed according to a lockfile.
+#[derive(
+    Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TraceRawVcs,
+)]
+pub struct LockfilePackage {
+    pub name: String,
+    pub version: String,
+    /// The install location relative to the lockfile directory, e.g.
+    /// `node_modules/a/node_modules/b`. Only known for lockfiles that describe
+    /// the node_modules layout.
+    pub path: Option<String>,
+}
+
+/// The installed packages of a project, as described by its lockfile.
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub struct Lockfile {
+    pub path: FileSystemPathVc,
+    pub kind: LockfileKind,
+    pub packages: Vec<LockfilePackage>,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionLockfile(Option<LockfileVc>);
+
+#[turbo_tasks::value_impl]
+impl LockfileVc {
+    /// All versions of the package `name` that are installed.
+    #[turbo_tasks::function]
+    pub async fn versions(self, name: &str) -> Result<StringsVc> {
+        let this = self.await?;
+        let versions: BTreeSet<_> = this
+            .packages
+            .iter()
+            .filter(|package| package.name == name)
+            .map(|package| package.version.clone())
+            .collect();
+        Ok(StringsVc::cell(versions.into_iter().collect()))
+    }
+
+    /// Reads the package.json of all packages with a known install location,
+    /// so these reads are already cached when resolving into the packages.
+    #[turbo_tasks::function]
+    pub async fn prewarm(self) -> Result<CompletionVc> {
+        let this = self.await?;
+        let root = this.path.parent();
+        for path in this.packages.iter().filter_map(|p| p.path.as_deref()) {
+            // Reading is enough to populate the cache, the result is not needed here.
+            let _ = root.join(path).join("package.json").read_json();
+        }
+        Ok(CompletionVc::new())
+    }
+}
+
+/// Finds the closest lockfile of any supported package manager in `context` or
+/// its parents and reads the installed packages from it.
+#[turbo_tasks::function]
+pub async fn find_lockfile(context: FileSystemPathVc) -> Result<OptionLockfileVc> {
+    let mut closest: Option<(FileSystemPathVc, LockfileKind, usize)> = None;
+    for kind in [LockfileKind::Npm, LockfileKind::Pnpm, LockfileKind::Yarn] {
+        if let FindContextFileResult::Found(path, _) =
+            &*find_context_file(context, kind.file_name()).await?
+        {
+            let depth = path.await?.path.len() - kind.file_name().len();
+            if closest.map_or(true, |(_, _, closest_depth)| depth > closest_depth) {
+                closest = Some((*path, kind, depth));
+            }
+        }
+    }
+    let (path, kind) = match closest {
+        Some((path, kind, _)) => (path, kind),
+        None => return Ok(OptionLockfileVc::cell(None)),
+    };
+    let content = match &*path.read().await? {
+        FileContent::Content(file) => String::from_utf8_lossy(file.content()).into_owned(),
+        FileContent::NotFound => return Ok(OptionLockfileVc::cell(None)),
+    };
+    let packages = match kind {
+        LockfileKind::Npm => serde_json::from_str(&content)
+            .map_err(|err| anyhow!("{}", err))
+            .and_then(|json| parse_package_lock(&json)),
+        LockfileKind::Pnpm => parse_pnpm_lock(&content),
+        LockfileKind::Yarn => parse_yarn_lock(&content),
+    };
+    match packages {
+        Ok(mut packages) => {
+            packages.sort();
+            packages.dedup();
+            Ok(OptionLockfileVc::cell(Some(
+                Lockfile {
+                    path,
+                    kind,
+                    packages,
+                }
+                .cell(),
+            )))
+        }
+        Err(err) => {
+            LockfileIssue {
+                path,
+                error_message: err.to_string(),
+            }
+            .cell()
+            .as_issue()
+            .emit();
+            Ok(OptionLockfileVc::cell(None))
+        }
+    }
+}
+
+/// Returns the package name of a `node_modules/...` install location.
+fn package_name_from_path(path: &str) -> &str {
+    match path.rfind("node_modules/") {
+        Some(index) => &path[index + "node_modules/".len()..],
+        None => path,
+    }
+}
+
+fn parse_package_lock(json: &JsonValue) -> Result<Vec<LockfilePackage>> {
+    let mut packages = Vec::new();
+    if let Some(entries) = json.get("packages").and_then(|p| p.as_object()) {
+        // lockfileVersion 2 and 3
+        for (path, entry) in entries {
+            if path.is_empty() || entry.get("link").and_then(|l| l.as_bool()) == Some(true) {
+                continue;
+            }
+            if let Some(version) = entry.get("version").and_then(|v| v.as_str()) {
+                packages.push(LockfilePackage {
+                    name: entry
+                        .get("name")
+                        .and_then(|n| n.as_str())
+                        .unwrap_or_else(|| package_name_from_path(path))
+                        .to_string(),
+                    version: version.to_string(),
+                    path: Some(path.clone()),
+                });
+            }
+        }
+    } else if let Some(dependencies) = json.get("dependencies") {
+        // lockfileVersion 1
+        fn add_dependencies(
+            dependencies: &JsonValue,
+            parent: &str,
+            packages: &mut Vec<LockfilePackage>,
+        ) {
+            if let Some(dependencies) = dependencies.as_object() {
+                for (name, entry) in dependencies {
+                    let path = format!("{parent}node_modules/{name}");
+                    if let Some(version) = entry.get("version").and_then(|v| v.as_str()) {
+                        packages.push(LockfilePackage {
+                            name: name.clone(),
+                            version: version.to_string(),
+                            path: Some(path.clone()),
+                        });
+                    }
+                    if let Some(nested) = entry.get("dependencies") {
+                        add_dependencies(nested, &format!("{path}/"), packages);
+                    }
+                }
+            }
+        }
+        add_dependencies(dependencies, "", &mut packages);
+    } else {
+        return Err(anyhow!(
+            "package-lock.json contains neither \"packages\" nor \"dependencies\""
+        ));
+    }
+    Ok(packages)
+}
+
+fn unquote(s: &str) -> &str {
+    let s = s.trim();
+    s.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .or_else(|| s.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')))
+        .unwrap_or(s)
+}
+
+/// Parses the keys of the `packages` section of a pnpm lockfile, e.g.
+/// `/@scope/name/1.0.0_peer@1.0.0` (version 5) or
+/// `/@scope/name@1.0.0(peer@1.0.0)` (version 6).
+fn parse_pnpm_lock(content: &str) -> Result<Vec<LockfilePackage>> {
+    let mut lockfile_version = None;
+    let mut in_packages = false;
+    let mut packages = Vec::new();
+    for line in content.lines() {
+        if let Some(version) = line.strip_prefix("lockfileVersion:") {
+            lockfile_version = Some(unquote(version).to_string());
+            continue;
+        }
+        if !line.starts_with(' ') && !line.trim().is_empty() {
+            in_packages = line.trim_end() == "packages:";
+            continue;
+        }
+        if !in_packages || line.starts_with("   ") {
+            continue;
+        }
+        let key = match line.trim().strip_suffix(':') {
+            Some(key) => unquote(key),
+            None => continue,
+        };
+        let key = key.trim_start_matches('/');
+        let key = key.split('(').next().unwrap_or(key);
+        let name_and_version = if lockfile_version
+            .as_deref()
+            .map_or(false, |v| v.starts_with('5'))
+        {
+            key.rsplit_once('/')
+                .map(|(name, version)| (name, version.split('_').next().unwrap_or(version)))
+        } else {
+            key.rfind('@')
+                .filter(|index| *index > 0)
+                .map(|index| (&key[..index], &key[index + 1..]))
+        };
+        if let Some((name, version)) = name_and_version {
+            packages.push(LockfilePackage {
+                name: name.to_string(),
+                version: version.to_string(),
+                path: None,
+            });
+        }
+    }
+    if lockfile_version.is_none() {
+        return Err(anyhow!("pnpm-lock.yaml has no lockfileVersion"));
+    }
+    Ok(packages)
+}
+
+/// Parses the entries of a yarn lockfile, both the classic format
+/// (`version "1.0.0"`) and the yaml format of yarn 2+ (`version: 1.0.0`).
+fn parse_yarn_lock(content: &str) -> Result<Vec<LockfilePackage>> {
+    let mut packages = Vec::new();
+    let mut current_name: Option<String> = None;
+    for line in content.lines() {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with(' ') {
+            current_name = line.trim_end().strip_suffix(':').and_then(|descriptors| {
+                let descriptor = unquote(descriptors.split(", ").next()?);
+                let index = descriptor.get(1..)?.find('@')? + 1;
+                Some(descriptor[..index].to_string())
+            });
+            continue;
+        }
+        if let Some(name) = &current_name {
+            let line = line.trim();
+            if let Some(version) = line
+                .strip_prefix("version ")
+                .or_else(|| line.strip_prefix("version:"))
+            {
+                packages.push(LockfilePackage {
+                    name: name.clone(),
+                    version: unquote(version).to_string(),
+                    path: None,
+                });
+                current_name = None;
+            }
+        }
+    }
+    if packages.is_empty()
+        && content
+            .lines()
+            .any(|l| !l.starts_with('#') && !l.is_empty())
+    {
+        return Err(anyhow!("yarn.lock contains no package entries"));
+    }
+    Ok(packages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_package_lock, parse_pnpm_lock, parse_yarn_lock};
+
+    fn versions(packages: Vec<super::LockfilePackage>) -> Vec<(String, String)> {
+        let mut list: Vec<_> = packages.into_iter().map(|p| (p.name, p.version)).collect();
+        list.sort();
+        list
+    }
+
+    #[test]
+    fn package_lock() {
+        let json = serde_json::json!({
+            "lockfileVersion": 2,
+            "packages": {
+                "": { "name": "app" },
+                "node_modules/@scope/a": { "version": "1.0.0" },
+                "node_modules/b": { "version": "2.0.0" },
+                "node_modules/b/node_modules/@scope/a": { "version": "0.9.0" },
+                "node_modules/linked": { "resolved": "packages/linked", "link": true }
+            }
+        });
+        let packages = parse_package_lock(&json).unwrap();
+        assert_eq!(
+            packages
+                .iter()
+                .find(|p| p.version == "0.9.0")
+                .and_then(|p| p.path.as_deref()),
+            Some("node_modules/b/node_modules/@scope/a")
+        );
+        assert_eq!(
+            versions(packages),
+            vec![
+                ("@scope/a".to_string(), "0.9.0".to_string()),
+                ("@scope/a".to_string(), "1.0.0".to_string()),
+                ("b".to_string(), "2.0.0".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn pnpm_lock() {
+        let v5 = "lockfileVersion: 5.4\n\npackages:\n\n  /@scope/a/1.0.0_react@18.0.0:\n    \
+                  resolution: {integrity: sha512-x}\n\n  /b/2.0.0:\n    dev: false\n";
+        let v6 = "lockfileVersion: '6.0'\n\npackages:\n\n  /@scope/a@1.0.0(react@18.0.0):\n    \
+                  resolution: {integrity: sha512-x}\n\n  /b@2.0.0:\n    dev: false\n";
+        for content in [v5, v6] {
+            assert_eq!(
+                versions(parse_pnpm_lock(content).unwrap()),
+                vec![
+                    ("@scope/a".to_string(), "1.0.0".to_string()),
+                    ("b".to_string(), "2.0.0".to_string()),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn yarn_lock() {
+        let classic = "# yarn lockfile v1\n\n\"@scope/a@^1.0.0\", \"@scope/a@^1.0.1\":\n  \
+                       version \"1.0.1\"\n  resolved \"https://x\"\n\nb@^2.0.0:\n  version \
+                       \"2.0.0\"\n";
+        let berry = "__metadata:\n  version: 6\n\n\"@scope/a@npm:^1.0.0\":\n  version: \
+                     1.0.1\n\n\"b@npm:^2.0.0\":\n  version: 2.0.0\n";
+        for content in [classic, berry] {
+            assert_eq!(
+                versions(parse_yarn_lock(content).unwrap()),
+                vec![
+                    ("@scope/a".to_string(), "1.0.1".to_string()),
+                    ("b".to_string(), "2.0.0".to_string()),
+                ]
+            );
+        }
+    }
+}
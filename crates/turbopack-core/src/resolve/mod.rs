@@ -10,29 +10,34 @@ use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use turbo_tasks::{
-    primitives::{BoolVc, StringVc},
+    primitives::{BoolVc, JsonValueVc, StringVc},
     trace::TraceRawVcs,
     TryJoinIterExt, Value, ValueToString, ValueToStringVc,
 };
 use turbo_tasks_fs::{
     util::{normalize_path, normalize_request},
-    FileJsonContent, FileJsonContentVc, FileSystemEntryType, FileSystemPathVc, RealPathResult,
+    File, FileJsonContent, FileJsonContentVc, FileSystemEntryType, FileSystemPathVc,
+    RealPathResult,
 };
 
 use self::{
-    exports::{ExportsField, ExportsValue},
+    exports::{ExportsField, ExportsValue, ImportsField},
+    externals::externalize_node_module,
     options::{
         resolve_modules_options, ImportMapResult, ResolveInPackage, ResolveIntoPackage,
         ResolveModules, ResolveModulesOptionsVc, ResolveOptionsVc,
     },
     origin::ResolveOriginVc,
     parse::{Request, RequestVc},
+    polyfills::{is_node_prefixed, resolve_polyfill},
+    trace::{record, ResolveStep},
 };
 use crate::{
     asset::{AssetVc, AssetsVc},
     issue::{
+        hermetic::HermeticIssue,
         package_json::{PackageJsonIssue, PackageJsonIssueVc},
-        resolve::{ResolvingIssue, ResolvingIssueVc},
+        resolve::{PackageSubpathNotExportedIssue, ResolvingIssue, ResolvingIssueVc},
     },
     reference::{AssetReference, AssetReferenceVc},
     resolve::{
@@ -40,14 +45,22 @@ use crate::{
         pattern::{read_matches, Pattern, PatternMatch, PatternVc},
     },
     source_asset::SourceAssetVc,
+    virtual_asset::VirtualAssetVc,
 };
 
 mod alias_map;
+pub mod duplicates;
 mod exports;
+pub mod externals;
+pub mod lockfile;
 pub mod options;
 pub mod origin;
+pub mod overrides;
 pub mod parse;
 pub mod pattern;
+pub mod polyfills;
+pub mod trace;
+pub mod virtual_modules;
 
 pub use alias_map::{
     AliasMap, AliasMapIntoIter, AliasMapLookupIterator, AliasMatch, AliasPattern, AliasTemplate,
@@ -57,6 +70,8 @@ pub use alias_map::{
 pub enum SpecialType {
     OriginalReferenceExternal,
     OriginalReferenceTypeExternal(String),
+    /// Read from the global variable with the name at runtime.
+    GlobalExternal(String),
     Ignore,
     Empty,
     Custom(u8),
@@ -72,6 +87,9 @@ pub enum ResolveResult {
     Unresolveable(Vec<AssetReferenceVc>),
 }
 
+#[turbo_tasks::value(transparent)]
+pub struct ResolveResultOption(Option<ResolveResultVc>);
+
 impl Default for ResolveResult {
     fn default() -> Self {
         ResolveResult::Unresolveable(Vec::new())
@@ -349,6 +367,17 @@ async fn type_exists(
     })
 }
 
+/// Reads a single field of a package.json file. Resolving only depends on the
+/// fields it reads, so changes of other fields, e. g. "scripts" or "version",
+/// don't invalidate it.
+#[turbo_tasks::function]
+async fn package_json_field(package_json: FileJsonContentVc, field: &str) -> Result<JsonValueVc> {
+    Ok(JsonValueVc::cell(match &*package_json.await? {
+        FileJsonContent::Content(package_json) => package_json[field].clone(),
+        _ => JsonValue::Null,
+    }))
+}
+
 #[turbo_tasks::value(shared)]
 enum ExportsFieldResult {
     Some(#[turbo_tasks(debug_ignore, trace_ignore)] ExportsField),
@@ -384,6 +413,40 @@ async fn exports_field(
     }
 }
 
+#[turbo_tasks::value(shared)]
+enum ImportsFieldResult {
+    Some(#[turbo_tasks(debug_ignore, trace_ignore)] ImportsField),
+    None,
+}
+
+#[turbo_tasks::function]
+async fn imports_field(
+    package_json_path: FileSystemPathVc,
+    package_json: FileJsonContentVc,
+) -> Result<ImportsFieldResultVc> {
+    if let FileJsonContent::Content(package_json) = &*package_json.await? {
+        let field_value = &package_json["imports"];
+        if let serde_json::Value::Null = field_value {
+            return Ok(ImportsFieldResult::None.into());
+        }
+        let imports_field: Result<ImportsField> = field_value.try_into();
+        match imports_field {
+            Ok(imports_field) => Ok(ImportsFieldResult::Some(imports_field).into()),
+            Err(err) => {
+                let issue: PackageJsonIssueVc = PackageJsonIssue {
+                    path: package_json_path,
+                    error_message: err.to_string(),
+                }
+                .into();
+                issue.as_issue().emit();
+                Ok(ImportsFieldResult::None.into())
+            }
+        }
+    } else {
+        Ok(ImportsFieldResult::None.into())
+    }
+}
+
 #[turbo_tasks::value(shared)]
 pub enum FindContextFileResult {
     Found(FileSystemPathVc, Vec<AssetReferenceVc>),
@@ -438,6 +501,15 @@ async fn find_package(
     let mut packages = vec![];
     let mut references = vec![];
     let options = options.await?;
+    // Symlinked packages keep the path they were found at when symlinks are
+    // preserved
+    let package_path = |path: FileSystemPathVc, real_path: FileSystemPathVc| {
+        if options.preserve_symlinks {
+            path
+        } else {
+            real_path
+        }
+    };
     for resolve_modules in &options.modules {
         match resolve_modules {
             ResolveModules::Nested(root_vc, names) => {
@@ -448,10 +520,10 @@ async fn find_package(
                 while context_value.is_inside(root) {
                     for name in names.iter() {
                         let fs_path = context.join(name);
-                        if let Some(fs_path) = dir_exists(fs_path, &mut references).await? {
-                            let fs_path = fs_path.join(&package_name);
-                            if let Some(fs_path) = dir_exists(fs_path, &mut references).await? {
-                                packages.push(fs_path);
+                        if let Some(real_path) = dir_exists(fs_path, &mut references).await? {
+                            let fs_path = package_path(fs_path, real_path).join(&package_name);
+                            if let Some(real_path) = dir_exists(fs_path, &mut references).await? {
+                                packages.push(package_path(fs_path, real_path));
                             }
                         }
                     }
@@ -572,6 +644,38 @@ pub async fn resolve(
     #[allow(clippy::explicit_auto_deref)]
     let options_value: &ResolveOptions = &*options.await?;
 
+    // Module overrides take precedence over everything else
+    if let Some(overrides) = options_value.overrides {
+        if let Some(module) = *overrides.lookup_request(request).await? {
+            record(options_value, || ResolveStep::Aliased {
+                request,
+                by: "a module override".to_string(),
+            });
+            return Ok(ResolveResult::Single(module, Vec::new()).into());
+        }
+    }
+
+    if let Some(virtual_modules) = options_value.virtual_modules {
+        if let Some(module) = *virtual_modules.lookup(request).await? {
+            record(options_value, || ResolveStep::Aliased {
+                request,
+                by: "a virtual module".to_string(),
+            });
+            return Ok(ResolveResult::Single(module, Vec::new()).into());
+        }
+    }
+
+    // Externals are loaded at runtime instead of being bundled
+    if let Some(externals) = options_value.externals {
+        if let Some(result) = *externals.lookup(request).await? {
+            record(options_value, || ResolveStep::Aliased {
+                request,
+                by: "the externals".to_string(),
+            });
+            return Ok(result);
+        }
+    }
+
     // Apply import mappings if provided
     if let Some(import_map) = &options_value.import_map {
         let result_ref = import_map.lookup(request).await?;
@@ -586,12 +690,31 @@ pub async fn resolve(
             // Typescript resolution algorithm does in case an alias match
             // doesn't resolve to anything: fall back to resolving the request normally.
             if !*resolve_result_vc.is_unresolveable().await? {
+                record(options_value, || ResolveStep::Aliased {
+                    request,
+                    by: "the import map".to_string(),
+                });
                 return Ok(resolve_result_vc);
             }
         }
     }
 
     let request_value = request.await?;
+
+    // Builtins requested with the `node:` prefix never resolve, so they are
+    // replaced with their polyfills right away
+    if let Some(polyfills) = options_value.polyfills {
+        if is_node_prefixed(&request_value) {
+            if let Some(result) = resolve_polyfill(context, request, options, polyfills).await? {
+                record(options_value, || ResolveStep::Aliased {
+                    request,
+                    by: "the polyfills".to_string(),
+                });
+                return Ok(result);
+            }
+        }
+    }
+
     let result = match &*request_value {
         Request::Dynamic => ResolveResult::unresolveable().into(),
         Request::Alternatives { requests } => {
@@ -613,6 +736,12 @@ pub async fn resolve(
                 PatternVc::new(path.clone()),
             )
             .await?;
+            if matches.is_empty() {
+                record(options_value, || ResolveStep::NotFound {
+                    context,
+                    request: path.to_string(),
+                });
+            }
             for m in matches.iter() {
                 match m {
                     PatternMatch::File(_, path) => {
@@ -642,6 +771,11 @@ pub async fn resolve(
                 patterns.push(path);
             }
             let new_pat = Pattern::alternatives(patterns);
+            record(options_value, || ResolveStep::TriedExtensions {
+                context,
+                request: path.to_string(),
+                extensions: options_value.extensions.clone(),
+            });
 
             resolve(
                 context,
@@ -650,7 +784,26 @@ pub async fn resolve(
             )
         }
         Request::Module { module, path } => {
-            resolve_module_request(context, options, options_value, module, path).await?
+            if let Some(result) =
+                resolve_module_alias_field(context, options, options_value, module, path).await?
+            {
+                return Ok(result);
+            }
+            let result =
+                resolve_module_request(context, options, options_value, module, path).await?;
+            if options_value.externalize_node_modules {
+                if let Some(external) =
+                    externalize_node_module(context, request, result, options, options_value)
+                        .await?
+                {
+                    record(options_value, || ResolveStep::Aliased {
+                        request,
+                        by: "the node_modules externals".to_string(),
+                    });
+                    return Ok(external);
+                }
+            }
+            result
         }
         Request::ServerRelative { path } => {
             let mut new_pat = path.clone();
@@ -683,16 +836,36 @@ pub async fn resolve(
             ResolveResult::unresolveable().into()
         }
         Request::Empty => ResolveResult::unresolveable().into(),
-        Request::PackageInternal { path: _ } => {
-            let issue: ResolvingIssueVc = ResolvingIssue {
-                request_type: "package internal import: not implemented yet".to_string(),
-                request,
+        Request::PackageInternal { path } => {
+            if let Some(path) = path.clone().into_string() {
+                resolve_package_internal_request(context, options, options_value, &path).await?
+            } else {
+                let issue: ResolvingIssueVc = ResolvingIssue {
+                    request_type: "package internal import: not implemented yet".to_string(),
+                    request,
+                    context,
+                    resolve_options: options,
+                    error_message: Some(
+                        "dynamic package internal imports are not implemented yet".to_string(),
+                    ),
+                }
+                .into();
+                issue.as_issue().emit();
+                ResolveResult::unresolveable().into()
+            }
+        }
+        Request::Uri {
+            protocol,
+            remainder,
+        } if options_value.hermetic => {
+            HermeticIssue {
                 context,
-                resolve_options: options,
-                error_message: Some("package internal imports are not implemented yet".to_string()),
+                subsystem: "resolve".to_string(),
+                access: format!("the remote module {protocol}{remainder}"),
             }
-            .into();
-            issue.as_issue().emit();
+            .cell()
+            .as_issue()
+            .emit();
             ResolveResult::unresolveable().into()
         }
         Request::Uri {
@@ -724,6 +897,19 @@ pub async fn resolve(
         }
     };
 
+    // Replace unresolveable Node.js builtins with their polyfills
+    if let Some(polyfills) = options_value.polyfills {
+        if *result.is_unresolveable().await? {
+            if let Some(result) = resolve_polyfill(context, request, options, polyfills).await? {
+                record(options_value, || ResolveStep::Aliased {
+                    request,
+                    by: "the polyfills".to_string(),
+                });
+                return Ok(result);
+            }
+        }
+    }
+
     // Apply fallback import mappings if provided
     if let Some(import_map) = &options_value.fallback_import_map {
         if *result.is_unresolveable().await? {
@@ -732,6 +918,10 @@ pub async fn resolve(
             if !matches!(result, ImportMapResult::NoEntry) {
                 let resolve_result_vc =
                     resolve_import_map_result(result, context, context, request, options).await?;
+                record(options_value, || ResolveStep::Aliased {
+                    request,
+                    by: "the fallback import map".to_string(),
+                });
                 return Ok(resolve_result_vc);
             }
         }
@@ -761,21 +951,20 @@ async fn resolve_into_folder(
                 return Ok(resolve(package_path, request, options));
             }
             ResolveIntoPackage::MainField(name) => {
-                if let FileJsonContent::Content(package_json) = &*package_json.await? {
-                    if let Some(field_value) = package_json[name].as_str() {
-                        let request =
-                            RequestVc::parse(Value::new(normalize_request(field_value).into()));
-
-                        let result = &*resolve(package_path, request, options).await?;
-                        // we are not that strict when a main field fails to resolve
-                        // we continue to try other alternatives
-                        if !result.is_unresolveable() {
-                            let mut result = result.clone();
-                            result.add_reference(
-                                AffectingResolvingAssetReferenceVc::new(package_json_path).into(),
-                            );
-                            return Ok(result.into());
-                        }
+                let field_value = package_json_field(package_json, name).await?;
+                if let Some(field_value) = field_value.as_str() {
+                    let request =
+                        RequestVc::parse(Value::new(normalize_request(field_value).into()));
+
+                    let result = &*resolve(package_path, request, options).await?;
+                    // we are not that strict when a main field fails to resolve
+                    // we continue to try other alternatives
+                    if !result.is_unresolveable() {
+                        let mut result = result.clone();
+                        result.add_reference(
+                            AffectingResolvingAssetReferenceVc::new(package_json_path).into(),
+                        );
+                        return Ok(result.into());
                     }
                 }
             }
@@ -792,6 +981,7 @@ async fn resolve_into_folder(
                         package_path,
                         package_json_path,
                         options,
+                        &options_value,
                         exports_field,
                         ".",
                         conditions,
@@ -819,6 +1009,10 @@ async fn resolve_module_request(
     .await?;
 
     if result.packages.is_empty() {
+        record(options_value, || ResolveStep::PackageNotFound {
+            context,
+            module: module.to_string(),
+        });
         return Ok(ResolveResult::Unresolveable(result.references.clone()).into());
     }
 
@@ -841,6 +1035,7 @@ async fn resolve_module_request(
             );
         }
         if could_match_others {
+            let mut has_exports_field = false;
             for resolve_into_package in options_value.into_package.iter() {
                 match resolve_into_package {
                     ResolveIntoPackage::Default(_) | ResolveIntoPackage::MainField(_) => {
@@ -870,6 +1065,7 @@ async fn resolve_module_request(
                                     *package_path,
                                     package_json_path,
                                     options,
+                                    options_value,
                                     exports_field,
                                     &format!(".{path}"),
                                     conditions,
@@ -880,15 +1076,20 @@ async fn resolve_module_request(
                             }
                             // other options do not apply anymore when an exports
                             // field exist
+                            has_exports_field = true;
                             break;
                         }
                     }
                 }
             }
-            let mut new_pat = path.clone();
-            new_pat.push_front(".".to_string().into());
-            let relative = RequestVc::relative(Value::new(new_pat), true);
-            results.push(resolve(*package_path, relative, options));
+            // Subpaths not exported by the exports field must not be
+            // resolvable within the package
+            if !has_exports_field {
+                let mut new_pat = path.clone();
+                new_pat.push_front(".".to_string().into());
+                let relative = RequestVc::relative(Value::new(new_pat), true);
+                results.push(resolve(*package_path, relative, options));
+            }
         }
     }
 
@@ -952,6 +1153,36 @@ fn resolve_import_map_result_boxed<'a>(
     })
 }
 
+/// Looks up a file in an alias field object, e. g. the `browser` field. Keys
+/// may omit the leading `./` and the extension of the file.
+fn alias_field_lookup<'a>(
+    field_value: &'a serde_json::Map<String, JsonValue>,
+    rel_path: &str,
+) -> Option<&'a JsonValue> {
+    let rel_path = rel_path.strip_prefix("./").unwrap_or(rel_path);
+    let mut keys = vec![rel_path];
+    if let Some((stem, ext)) = rel_path.rsplit_once('.') {
+        if !ext.contains('/') {
+            keys.push(stem);
+        }
+    }
+    keys.into_iter().find_map(|key| {
+        field_value
+            .get(&format!("./{key}"))
+            .or_else(|| field_value.get(key))
+    })
+}
+
+/// An empty module substituted for files and modules excluded by an alias
+/// field.
+fn empty_module(package_path: FileSystemPathVc) -> AssetVc {
+    VirtualAssetVc::new(
+        package_path.join("__turbopack_empty_module__.js"),
+        File::from("").into(),
+    )
+    .into()
+}
+
 async fn resolve_alias_field_result(
     result: &JsonValue,
     refs: Vec<AssetReferenceVc>,
@@ -961,8 +1192,12 @@ async fn resolve_alias_field_result(
     issue_request: &str,
     field_name: &str,
 ) -> Result<ResolveResultVc> {
+    record(&*resolve_options.await?, || ResolveStep::Aliased {
+        request: RequestVc::parse(Value::new(Pattern::Constant(issue_request.to_string()))),
+        by: format!("the \"{field_name}\" field"),
+    });
     if result.as_bool() == Some(false) {
-        return Ok(ResolveResult::Special(SpecialType::Ignore, refs).cell());
+        return Ok(ResolveResult::Single(empty_module(package_path), refs).cell());
     }
     if let Some(value) = result.as_str() {
         return Ok(resolve(
@@ -984,42 +1219,108 @@ async fn resolve_alias_field_result(
     Ok(ResolveResult::Unresolveable(refs).cell())
 }
 
+/// Applies the module entries of the alias fields of the package containing
+/// `context`, e. g. `"fs": false` in the `browser` field.
+async fn resolve_module_alias_field(
+    context: FileSystemPathVc,
+    options: ResolveOptionsVc,
+    options_value: &ResolveOptions,
+    module: &str,
+    path: &Pattern,
+) -> Result<Option<ResolveResultVc>> {
+    let request = match path.clone().into_string() {
+        Some(path) => format!("{module}{path}"),
+        None => return Ok(None),
+    };
+    for resolve_in in options_value.in_package.iter() {
+        match resolve_in {
+            ResolveInPackage::AliasField(field) => {
+                if let FindContextFileResult::Found(package_json, refs) =
+                    &*find_context_file(context, "package.json").await?
+                {
+                    let field_value = package_json_field(package_json.read_json(), field).await?;
+                    if let Some(value) = field_value.get(&request) {
+                        // A module aliased to itself resolves normally
+                        if value.as_str() == Some(request.as_str()) {
+                            continue;
+                        }
+                        return Ok(Some(
+                            resolve_alias_field_result(
+                                value,
+                                refs.clone(),
+                                package_json.parent(),
+                                options,
+                                *package_json,
+                                &request,
+                                field,
+                            )
+                            .await?,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
 async fn resolved(
     fs_path: FileSystemPathVc,
     original_context: FileSystemPathVc,
     original_request: RequestVc,
-    ResolveOptions {
+    options_value: &ResolveOptions,
+    options: ResolveOptionsVc,
+) -> Result<ResolveResultVc> {
+    let ResolveOptions {
         resolved_map,
         in_package,
+        overrides,
+        preserve_symlinks,
         ..
-    }: &ResolveOptions,
-    options: ResolveOptionsVc,
-) -> Result<ResolveResultVc> {
-    let RealPathResult { path, symlinks } = &*fs_path.realpath_with_links().await?;
+    } = options_value;
+    let RealPathResult {
+        path: real_path,
+        symlinks,
+    } = &*fs_path.realpath_with_links().await?;
+    // The symlinks are referenced in both cases, so changing them invalidates
+    // the result
+    let path = if *preserve_symlinks {
+        &fs_path
+    } else {
+        real_path
+    };
+    if let Some(overrides) = overrides {
+        if let Some(module) = *overrides.lookup_path(*path).await? {
+            record(options_value, || ResolveStep::Aliased {
+                request: original_request,
+                by: "a module override".to_string(),
+            });
+            return Ok(ResolveResult::Single(module, Vec::new()).into());
+        }
+    }
     for resolve_in in in_package.iter() {
         match resolve_in {
             ResolveInPackage::AliasField(field) => {
                 if let FindContextFileResult::Found(package_json, refs) =
                     &*find_context_file(fs_path.parent(), "package.json").await?
                 {
-                    if let FileJsonContent::Content(package) = &*package_json.read_json().await? {
-                        if let Some(field_value) = package[field].as_object() {
-                            let package_path = package_json.parent();
-                            if let Some(rel_path) =
-                                package_path.await?.get_relative_path_to(&*fs_path.await?)
-                            {
-                                if let Some(value) = field_value.get(&rel_path) {
-                                    return resolve_alias_field_result(
-                                        value,
-                                        refs.clone(),
-                                        package_path,
-                                        options,
-                                        *package_json,
-                                        &rel_path,
-                                        field,
-                                    )
-                                    .await;
-                                }
+                    let field_value = package_json_field(package_json.read_json(), field).await?;
+                    if let Some(field_value) = field_value.as_object() {
+                        let package_path = package_json.parent();
+                        if let Some(rel_path) =
+                            package_path.await?.get_relative_path_to(&*fs_path.await?)
+                        {
+                            if let Some(value) = alias_field_lookup(field_value, &rel_path) {
+                                return resolve_alias_field_result(
+                                    value,
+                                    refs.clone(),
+                                    package_path,
+                                    options,
+                                    *package_json,
+                                    &rel_path,
+                                    field,
+                                )
+                                .await;
                             }
                         }
                     }
@@ -1030,6 +1331,10 @@ async fn resolved(
     if let Some(resolved_map) = resolved_map {
         let result = resolved_map.lookup(*path).await?;
         if !matches!(&*result, ImportMapResult::NoEntry) {
+            record(options_value, || ResolveStep::Aliased {
+                request: original_request,
+                by: "the resolved map".to_string(),
+            });
             return resolve_import_map_result(
                 &result,
                 path.parent(),
@@ -1040,6 +1345,7 @@ async fn resolved(
             .await;
         }
     }
+    record(options_value, || ResolveStep::Resolved { path: *path });
     Ok(ResolveResult::Single(
         SourceAssetVc::new(*path).into(),
         symlinks
@@ -1050,10 +1356,89 @@ async fn resolved(
     .into())
 }
 
+/// Resolves a `#`-prefixed request with the "imports" field of the package
+/// containing `context`. Targets starting with `./` are files of the package,
+/// other targets are requests to other packages, resolved from the package.
+async fn resolve_package_internal_request(
+    context: FileSystemPathVc,
+    options: ResolveOptionsVc,
+    options_value: &ResolveOptions,
+    path: &str,
+) -> Result<ResolveResultVc> {
+    let (package_json_path, references) = match &*find_context_file(context, "package.json").await?
+    {
+        FindContextFileResult::Found(package_json_path, references) => {
+            (*package_json_path, references.clone())
+        }
+        FindContextFileResult::NotFound(references) => {
+            return Ok(ResolveResult::Unresolveable(references.clone()).into());
+        }
+    };
+    let package_path = package_json_path.parent();
+    let package_json = package_json_path.read_json();
+    let imports_field = imports_field(package_json_path, package_json).await?;
+    let imports_field = match &*imports_field {
+        ImportsFieldResult::Some(imports_field) => imports_field,
+        ImportsFieldResult::None => {
+            return Ok(ResolveResult::Unresolveable(references).into());
+        }
+    };
+    // The imports field uses the same conditions as the exports field
+    let (conditions, unspecified_conditions) = options_value
+        .into_package
+        .iter()
+        .find_map(|into_package| match into_package {
+            ResolveIntoPackage::ExportsField {
+                conditions,
+                unspecified_conditions,
+                ..
+            } => Some((conditions.clone(), unspecified_conditions.clone())),
+            _ => None,
+        })
+        .unwrap_or_else(|| (BTreeMap::new(), ConditionValue::Unset));
+
+    let mut results = Vec::new();
+    let mut conditions_state = HashMap::new();
+    let values = imports_field
+        .lookup(path)
+        .map(AliasMatch::try_into_self)
+        .collect::<Result<Vec<Cow<'_, ExportsValue>>>>()?;
+    for value in values.iter() {
+        if value.add_results(
+            &conditions,
+            &unspecified_conditions,
+            &mut conditions_state,
+            &mut results,
+        ) {
+            break;
+        }
+    }
+    {
+        let mut duplicates_set = HashSet::new();
+        results.retain(|item| duplicates_set.insert(*item));
+    }
+    let mut resolved_results = Vec::new();
+    for target in results {
+        if target.starts_with("./") {
+            if let Some(target) = normalize_path(target) {
+                let request = RequestVc::parse(Value::new(format!("./{}", target).into()));
+                resolved_results.push(resolve(package_path, request, options));
+            }
+        } else if !target.starts_with('/') && !target.starts_with("../") {
+            let request = RequestVc::parse(Value::new(target.to_string().into()));
+            resolved_results.push(resolve(package_path, request, options));
+        }
+    }
+    let mut references = references;
+    references.push(AffectingResolvingAssetReferenceVc::new(package_json_path).into());
+    Ok(merge_results_with_references(resolved_results, references))
+}
+
 fn handle_exports_field(
     package_path: FileSystemPathVc,
     package_json: FileSystemPathVc,
     options: ResolveOptionsVc,
+    options_value: &ResolveOptions,
     exports_field: &ExportsField,
     path: &str,
     conditions: &BTreeMap<String, ConditionValue>,
@@ -1079,6 +1464,30 @@ fn handle_exports_field(
         let mut duplicates_set = HashSet::new();
         results.retain(|item| duplicates_set.insert(*item));
     }
+    record(options_value, || ResolveStep::ExportsField {
+        package_json,
+        subpath: path.to_string(),
+        conditions: conditions
+            .iter()
+            .filter(|(_, value)| matches!(value, ConditionValue::Set))
+            .map(|(condition, _)| condition.clone())
+            .collect(),
+        targets: results.iter().map(|target| target.to_string()).collect(),
+    });
+    if results.is_empty() {
+        PackageSubpathNotExportedIssue {
+            package_json,
+            subpath: path.to_string(),
+            conditions: conditions
+                .iter()
+                .filter(|(_, value)| matches!(value, ConditionValue::Set))
+                .map(|(condition, _)| condition.clone())
+                .collect(),
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    }
     let mut resolved_results = Vec::new();
     for path in results {
         if let Some(path) = normalize_path(path) {
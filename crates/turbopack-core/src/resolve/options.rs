@@ -10,6 +10,10 @@ use turbo_tasks_fs::{
 
 use super::{
     alias_map::{AliasMap, AliasTemplate},
+    externals::ExternalsVc,
+    overrides::ModuleOverridesVc,
+    polyfills::PolyfillsVc,
+    virtual_modules::VirtualModulesVc,
     AliasPattern, ResolveResult, ResolveResultVc, SpecialType,
 };
 use crate::resolve::parse::RequestVc;
@@ -75,7 +79,9 @@ pub enum ResolveIntoPackage {
 // The different ways to resolve a request withing a package
 #[derive(TraceRawVcs, Hash, PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
 pub enum ResolveInPackage {
-    /// Using a alias field which allows to map requests
+    /// Using a alias field which allows to map requests. Files of the package
+    /// and modules requested from it can be remapped, or excluded with
+    /// `false`, which substitutes an empty module.
     AliasField(String),
 }
 
@@ -188,6 +194,40 @@ impl ImportMap {
         self.direct.insert(AliasPattern::exact(pattern), mapping);
     }
 
+    /// Inserts an alias in the format of bundler configs, which redirects
+    /// `alias` to the first of `targets` which resolves, or ignores it when
+    /// there are no targets. An alias ending with `$` only matches the request
+    /// exactly, otherwise subpaths are redirected, too, e. g. `react` →
+    /// `preact/compat` also maps `react/jsx-runtime` to
+    /// `preact/compat/jsx-runtime`.
+    ///
+    /// Relative targets are resolved from `context`, or from the importing
+    /// module when it's `None`.
+    pub fn insert_user_alias(
+        &mut self,
+        alias: &str,
+        targets: &[String],
+        context: Option<FileSystemPathVc>,
+    ) {
+        let mapping = |suffix: &str| {
+            ImportMapping::primary_alternatives(
+                targets
+                    .iter()
+                    .map(|target| format!("{target}{suffix}"))
+                    .collect(),
+                context,
+            )
+            .cell()
+        };
+        match alias.strip_suffix('$') {
+            Some(alias) => self.insert_exact_alias(alias, mapping("")),
+            None => {
+                self.insert_exact_alias(alias, mapping(""));
+                self.insert_wildcard_alias(format!("{alias}/"), mapping("/*"));
+            }
+        }
+    }
+
     /// Inserts a wildcard alias into the import map.
     pub fn insert_wildcard_alias<'a>(
         &mut self,
@@ -340,7 +380,35 @@ pub struct ResolveOptions {
     pub import_map: Option<ImportMapVc>,
     /// An import map to use when a request is otherwise unresolveable.
     pub fallback_import_map: Option<ImportMapVc>,
+    /// Replacements for Node.js builtins which are otherwise unresolveable.
+    /// They are checked before the fallback import map.
+    pub polyfills: Option<PolyfillsVc>,
     pub resolved_map: Option<ResolvedMapVc>,
+    /// Modules replacing the results of resolving, which are checked first.
+    pub overrides: Option<ModuleOverridesVc>,
+    /// Modules which only exist in memory, resolved by their specifier. They
+    /// are checked after the overrides.
+    pub virtual_modules: Option<VirtualModulesVc>,
+    /// Requests which are loaded at runtime instead of being bundled. They
+    /// are checked after the overrides and before the import map.
+    pub externals: Option<ExternalsVc>,
+    /// Loads packages found in node_modules at runtime instead of bundling
+    /// them, e. g. for a Node.js server. Packages which can only be loaded
+    /// as ES modules are only external when imported with `import()`.
+    pub externalize_node_modules: bool,
+    /// The request is loaded asynchronously by `import()`.
+    pub async_import: bool,
+    /// Forbids requests which would access the network, e. g. remote modules.
+    pub hermetic: bool,
+    /// Keeps resolved files at the path they were requested by instead of
+    /// the real path of symlinks, like `--preserve-symlinks` in Node.js. A
+    /// package linked into multiple places becomes a separate module for
+    /// each of them then.
+    pub preserve_symlinks: bool,
+    /// Records the steps of resolving, see [trace_resolve].
+    ///
+    /// [trace_resolve]: super::trace::trace_resolve
+    pub trace: bool,
     pub placeholder_for_future_extensions: (),
 }
 
@@ -348,12 +416,37 @@ pub struct ResolveOptions {
 impl ResolveOptionsVc {
     #[turbo_tasks::function]
     pub async fn modules(self) -> Result<ResolveModulesOptionsVc> {
+        let options = self.await?;
         Ok(ResolveModulesOptions {
-            modules: self.await?.modules.clone(),
+            modules: options.modules.clone(),
+            preserve_symlinks: options.preserve_symlinks,
         }
         .into())
     }
 
+    /// Returns a new [ResolveOptionsVc] which records the steps of resolving.
+    #[turbo_tasks::function]
+    pub async fn with_tracing(self) -> Result<Self> {
+        let mut resolve_options = self.await?.clone_value();
+        resolve_options.trace = true;
+        Ok(resolve_options.into())
+    }
+
+    /// Returns a new [ResolveOptionsVc] which matches the "require" condition
+    /// of "exports" fields instead of the "import" condition, like
+    /// `require()` in Node.js.
+    #[turbo_tasks::function]
+    pub async fn with_require_conditions(self) -> Result<Self> {
+        let mut resolve_options = self.await?.clone_value();
+        for item in resolve_options.into_package.iter_mut() {
+            if let ResolveIntoPackage::ExportsField { conditions, .. } = item {
+                conditions.insert("import".to_string(), ConditionValue::Unset);
+                conditions.insert("require".to_string(), ConditionValue::Set);
+            }
+        }
+        Ok(resolve_options.into())
+    }
+
     /// Returns a new [ResolveOptionsVc] with its import map extended to include
     /// the given import map.
     #[turbo_tasks::function]
@@ -387,12 +480,15 @@ impl ResolveOptionsVc {
 #[derive(Hash, Clone, Debug)]
 pub struct ResolveModulesOptions {
     pub modules: Vec<ResolveModules>,
+    pub preserve_symlinks: bool,
 }
 
 #[turbo_tasks::function]
 pub async fn resolve_modules_options(options: ResolveOptionsVc) -> Result<ResolveModulesOptionsVc> {
+    let options = options.await?;
     Ok(ResolveModulesOptions {
-        modules: options.await?.modules.clone(),
+        modules: options.modules.clone(),
+        preserve_symlinks: options.preserve_symlinks,
     }
     .into())
 }
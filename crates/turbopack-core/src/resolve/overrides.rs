@@ -0,0 +1,64 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use turbo_tasks_fs::FileSystemPathVc;
+
+use super::parse::RequestVc;
+use crate::asset::{AssetOptionVc, AssetVc};
+
+/// Modules which replace the results of resolving, e. g. mocks of modules in
+/// a test bundle. They are checked before any other resolve option.
+///
+/// The overrides are part of the resolve options, so resolving is cached per
+/// set of overrides and builds with other overrides are not affected.
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Default)]
+pub struct ModuleOverrides {
+    /// The modules replacing requests, by the request, e. g. `axios`.
+    requests: IndexMap<String, AssetVc>,
+    /// The modules replacing the files requests resolve to, e. g. the file
+    /// `./api/client` resolves to. This overrides the file for every request
+    /// resolving to it.
+    paths: Vec<(FileSystemPathVc, AssetVc)>,
+}
+
+impl ModuleOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the module of requests which equal `request`.
+    pub fn override_request(&mut self, request: impl Into<String>, module: AssetVc) {
+        self.requests.insert(request.into(), module);
+    }
+
+    /// Replaces the file at `path` with `module`. `path` must be the path of
+    /// the resolved file, including its extension.
+    pub fn override_path(&mut self, path: FileSystemPathVc, module: AssetVc) {
+        self.paths.push((path, module));
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ModuleOverridesVc {
+    #[turbo_tasks::function]
+    pub async fn lookup_request(self, request: RequestVc) -> Result<AssetOptionVc> {
+        let this = self.await?;
+        let module = match request.await?.request() {
+            Some(request) => this.requests.get(&request).copied(),
+            None => None,
+        };
+        Ok(AssetOptionVc::cell(module))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn lookup_path(self, path: FileSystemPathVc) -> Result<AssetOptionVc> {
+        let this = self.await?;
+        let path = path.await?;
+        for (overridden, module) in this.paths.iter() {
+            if *overridden.await? == *path {
+                return Ok(AssetOptionVc::cell(Some(*module)));
+            }
+        }
+        Ok(AssetOptionVc::cell(None))
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{trace::TraceRawVcs, Value};
+use turbo_tasks_fs::FileSystemPathVc;
+
+use super::{
+    options::ResolveOptionsVc,
+    parse::{Request, RequestVc},
+    resolve, ResolveResult, ResolveResultVc, SpecialType,
+};
+use crate::issue::polyfill::PolyfillIssue;
+
+/// What a Node.js builtin is replaced with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub enum Polyfill {
+    /// A request which is resolved instead, e. g. `path-browserify`.
+    Request(String),
+    /// An empty module, for builtins which are only used on code paths which
+    /// don't run in the environment.
+    Empty,
+}
+
+/// Replacements for Node.js builtins like `path` or `buffer` in environments
+/// which don't provide them, like `resolve.fallback` in webpack. They are only
+/// used when a request can't be resolved otherwise, so a package of the same
+/// name in node_modules takes precedence. Every replacement is reported as an
+/// issue.
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Default)]
+pub struct Polyfills {
+    /// The polyfills by the name of the builtin, without the `node:` prefix.
+    modules: IndexMap<String, Polyfill>,
+    /// The directory polyfill requests are resolved from. Defaults to the
+    /// directory of the importing module.
+    context: Option<FileSystemPathVc>,
+}
+
+impl Polyfills {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the builtin `module` with `polyfill`. `module` is also
+    /// replaced when requested with the `node:` prefix.
+    pub fn add(&mut self, module: impl Into<String>, polyfill: Polyfill) {
+        self.modules.insert(module.into(), polyfill);
+    }
+
+    /// Resolves the requests of polyfills from `context`, e. g. the project
+    /// directory, so the packages don't need to be dependencies of the
+    /// packages importing the builtins.
+    pub fn set_context(&mut self, context: FileSystemPathVc) {
+        self.context = Some(context);
+    }
+}
+
+/// Resolves the polyfill of `request` when it's a builtin with a polyfill.
+pub(super) async fn resolve_polyfill(
+    context: FileSystemPathVc,
+    request: RequestVc,
+    options: ResolveOptionsVc,
+    polyfills: PolyfillsVc,
+) -> Result<Option<ResolveResultVc>> {
+    let request_string = match request.await?.request() {
+        Some(request) => request,
+        None => return Ok(None),
+    };
+    let module = request_string
+        .strip_prefix("node:")
+        .unwrap_or(&request_string);
+    let this = polyfills.await?;
+    let polyfill = match this.modules.get(module) {
+        Some(polyfill) => polyfill,
+        None => return Ok(None),
+    };
+    let result = match polyfill {
+        Polyfill::Request(polyfill_request) => {
+            let result = resolve(
+                this.context.unwrap_or(context),
+                RequestVc::parse(Value::new(polyfill_request.clone().into())),
+                options,
+            );
+            if *result.is_unresolveable().await? {
+                return Ok(None);
+            }
+            result
+        }
+        Polyfill::Empty => ResolveResult::Special(SpecialType::Empty, Vec::new()).cell(),
+    };
+    PolyfillIssue {
+        context,
+        module: module.to_string(),
+        polyfill: match polyfill {
+            Polyfill::Request(polyfill_request) => Some(polyfill_request.clone()),
+            Polyfill::Empty => None,
+        },
+    }
+    .cell()
+    .as_issue()
+    .emit();
+    Ok(Some(result))
+}
+
+/// Whether `request` is a builtin with the `node:` prefix, which can only be
+/// replaced by its polyfill.
+pub(super) fn is_node_prefixed(request: &Request) -> bool {
+    matches!(request, Request::Uri { protocol, .. } if protocol == "node:")
+}
@@ -0,0 +1,184 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use turbo_tasks::{emit, primitives::StringVc, CollectiblesSource, ValueToString};
+use turbo_tasks_fs::FileSystemPathVc;
+
+use super::{
+    options::{ResolveOptions, ResolveOptionsVc},
+    parse::RequestVc,
+    resolve, ResolveResult,
+};
+
+/// A step of resolving a request, recorded when tracing is enabled in the
+/// [ResolveOptions].
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub enum ResolveStep {
+    /// The request was redirected, e. g. by an import map or the `browser`
+    /// field.
+    Aliased { request: RequestVc, by: String },
+    /// The extensions were appended to the request to find a file.
+    TriedExtensions {
+        context: FileSystemPathVc,
+        request: String,
+        extensions: Vec<String>,
+    },
+    /// The "exports" field of a package matched a subpath.
+    ExportsField {
+        package_json: FileSystemPathVc,
+        subpath: String,
+        conditions: Vec<String>,
+        targets: Vec<String>,
+    },
+    /// No package with the name of the request was found.
+    PackageNotFound {
+        context: FileSystemPathVc,
+        module: String,
+    },
+    /// No file or directory matched the request.
+    NotFound {
+        context: FileSystemPathVc,
+        request: String,
+    },
+    /// The request resolved to a file.
+    Resolved { path: FileSystemPathVc },
+}
+
+impl ResolveStep {
+    /// The position of the step in a rendered trace. Steps are recorded by
+    /// different tasks, so they are not ordered by the time they happened.
+    fn rank(&self) -> u8 {
+        match self {
+            ResolveStep::Aliased { .. } => 0,
+            ResolveStep::PackageNotFound { .. } => 1,
+            ResolveStep::ExportsField { .. } => 2,
+            ResolveStep::TriedExtensions { .. } => 3,
+            ResolveStep::NotFound { .. } => 4,
+            ResolveStep::Resolved { .. } => 5,
+        }
+    }
+
+    async fn describe(&self) -> Result<String> {
+        Ok(match self {
+            ResolveStep::Aliased { request, by } => {
+                format!("{} was aliased by {by}", request.to_string().await?)
+            }
+            ResolveStep::TriedExtensions {
+                context,
+                request,
+                extensions,
+            } => format!(
+                "tried {request} with the extensions {} in {}",
+                extensions.join(", "),
+                context.to_string().await?
+            ),
+            ResolveStep::ExportsField {
+                package_json,
+                subpath,
+                conditions,
+                targets,
+            } => {
+                let targets = if targets.is_empty() {
+                    "nothing".to_string()
+                } else {
+                    targets.join(", ")
+                };
+                format!(
+                    "the \"exports\" field of {} mapped {subpath} to {targets} with the \
+                     conditions {}",
+                    package_json.to_string().await?,
+                    conditions.join(", ")
+                )
+            }
+            ResolveStep::PackageNotFound { context, module } => format!(
+                "the package {module} was not found from {}",
+                context.to_string().await?
+            ),
+            ResolveStep::NotFound { context, request } => {
+                format!("{request} was not found in {}", context.to_string().await?)
+            }
+            ResolveStep::Resolved { path } => {
+                format!("resolved to {}", path.to_string().await?)
+            }
+        })
+    }
+}
+
+/// Marks the steps as collectibles.
+#[turbo_tasks::value_trait]
+pub trait ResolveTraceEntry {
+    fn step(&self) -> ResolveStepVc;
+}
+
+#[turbo_tasks::value_impl]
+impl ResolveTraceEntry for ResolveStep {
+    #[turbo_tasks::function]
+    fn step(self_vc: ResolveStepVc) -> ResolveStepVc {
+        self_vc
+    }
+}
+
+/// Records `step` when tracing is enabled in `options`.
+pub(super) fn record(options: &ResolveOptions, step: impl FnOnce() -> ResolveStep) {
+    if options.trace {
+        emit(step().cell().as_resolve_trace_entry());
+    }
+}
+
+/// The steps of resolving a request, see [trace_resolve].
+#[turbo_tasks::value(shared)]
+pub struct ResolveTrace {
+    pub context: FileSystemPathVc,
+    pub request: RequestVc,
+    pub resolved: bool,
+    pub steps: Vec<ResolveStep>,
+}
+
+/// Resolves `request` like [resolve] with tracing enabled and returns the
+/// steps it took, e. g. to explain why a module can't be found. Tracing
+/// doesn't affect the results of resolving without it.
+#[turbo_tasks::function]
+pub async fn trace_resolve(
+    context: FileSystemPathVc,
+    request: RequestVc,
+    options: ResolveOptionsVc,
+) -> Result<ResolveTraceVc> {
+    let result = resolve(context, request, options.with_tracing());
+    let resolved = !matches!(&*result.await?, ResolveResult::Unresolveable(_));
+    let mut steps = Vec::new();
+    for entry in result.peek_collectibles::<ResolveTraceEntryVc>().await? {
+        let step = entry.step().await?.clone_value();
+        steps.push((step.rank(), step.describe().await?, step));
+    }
+    steps.sort_by(|(a, a_description, _), (b, b_description, _)| {
+        a.cmp(b).then_with(|| a_description.cmp(b_description))
+    });
+    Ok(ResolveTrace {
+        context,
+        request,
+        resolved,
+        steps: steps.into_iter().map(|(_, _, step)| step).collect(),
+    }
+    .cell())
+}
+
+#[turbo_tasks::value_impl]
+impl ResolveTraceVc {
+    /// Renders the trace as a list of steps, which can be used as the detail
+    /// of an issue.
+    #[turbo_tasks::function]
+    pub async fn render(self) -> Result<StringVc> {
+        let this = self.await?;
+        let mut rendered = format!(
+            "resolving {} in {} {}:\n",
+            this.request.to_string().await?,
+            this.context.to_string().await?,
+            if this.resolved { "succeeded" } else { "failed" }
+        );
+        for step in this.steps.iter() {
+            writeln!(rendered, "  - {}", step.describe().await?)?;
+        }
+        Ok(StringVc::cell(rendered))
+    }
+}
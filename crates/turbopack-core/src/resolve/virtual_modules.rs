@@ -0,0 +1,61 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use turbo_tasks_fs::FileSystemPathVc;
+
+use super::parse::RequestVc;
+use crate::{
+    asset::{AssetContentVc, AssetOptionVc},
+    virtual_asset::VirtualAssetVc,
+};
+
+/// Modules which only exist in memory, e. g. entries or route manifests
+/// generated by a framework, so they don't need to be written to temporary
+/// files. Requests equal to their specifier resolve to them, and they are
+/// processed like files at their path, so their type is derived from its
+/// extension and their relative imports are resolved from its directory.
+///
+/// The content is usually computed by a turbo-tasks function of the
+/// framework, which invalidates the module and everything depending on it
+/// when it's computed again, e. g. when a route was added.
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Default)]
+pub struct VirtualModules {
+    /// The path and content of the modules by their specifier, e. g.
+    /// `virtual:routes`.
+    modules: IndexMap<String, (FileSystemPathVc, AssetContentVc)>,
+}
+
+impl VirtualModules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a module for requests which equal `specifier`. `path`
+    /// doesn't need to exist, but should be in the project, e. g.
+    /// `[project]/.virtual/routes.js`, so the module gets the same module
+    /// rules as the files next to it.
+    pub fn add(
+        &mut self,
+        specifier: impl Into<String>,
+        path: FileSystemPathVc,
+        content: AssetContentVc,
+    ) {
+        self.modules.insert(specifier.into(), (path, content));
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl VirtualModulesVc {
+    #[turbo_tasks::function]
+    pub async fn lookup(self, request: RequestVc) -> Result<AssetOptionVc> {
+        let this = self.await?;
+        let module = match request.await?.request() {
+            Some(request) => this
+                .modules
+                .get(&request)
+                .map(|&(path, content)| VirtualAssetVc::new(path, content).into()),
+            None => None,
+        };
+        Ok(AssetOptionVc::cell(module))
+    }
+}
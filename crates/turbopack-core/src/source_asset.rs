@@ -3,6 +3,7 @@ use turbo_tasks_fs::{FileContent, FileSystemEntryType, FileSystemPathVc, LinkCon
 
 use crate::{
     asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    issue::hermetic::HermeticIssue,
     reference::AssetReferencesVc,
 };
 
@@ -30,21 +31,13 @@ impl Asset for SourceAsset {
 
     #[turbo_tasks::function]
     async fn content(&self) -> Result<AssetContentVc> {
-        let file_type = &*self.path.get_type().await?;
-        match file_type {
-            FileSystemEntryType::Symlink => match &*self.path.read_link().await? {
-                LinkContent::Link { target, link_type } => Ok(AssetContent::Redirect {
-                    target: target.clone(),
-                    link_type: *link_type,
-                }
-                .cell()),
-                _ => Err(anyhow::anyhow!("Invalid symlink")),
-            },
-            FileSystemEntryType::File => Ok(AssetContent::File(self.path.read()).cell()),
-            FileSystemEntryType::NotFound => {
+        match source_content(self.path).await {
+            // Reads outside of the root of a hermetic file system are reported as
+            // issues, the asset is treated as missing
+            Err(error) if HermeticIssue::emit_for_violation(self.path, &error) => {
                 Ok(AssetContent::File(FileContent::NotFound.cell()).cell())
             }
-            _ => Err(anyhow::anyhow!("Invalid file type {:?}", file_type)),
+            result => result,
         }
     }
 
@@ -55,3 +48,27 @@ impl Asset for SourceAsset {
         AssetReferencesVc::empty()
     }
 }
+
+async fn source_content(path: FileSystemPathVc) -> Result<AssetContentVc> {
+    let file_type = &*path.get_type().await?;
+    match file_type {
+        FileSystemEntryType::Symlink => match &*path.read_link().await? {
+            LinkContent::Link { target, link_type } => Ok(AssetContent::Redirect {
+                target: target.clone(),
+                link_type: *link_type,
+            }
+            .cell()),
+            _ => Err(anyhow::anyhow!("Invalid symlink")),
+        },
+        FileSystemEntryType::File => {
+            let content = path.read();
+            // Await the read, so failing reads are reported for this asset
+            content.resolve().await?;
+            Ok(AssetContent::File(content).cell())
+        }
+        FileSystemEntryType::NotFound => {
+            Ok(AssetContent::File(FileContent::NotFound.cell()).cell())
+        }
+        _ => Err(anyhow::anyhow!("Invalid file type {:?}", file_type)),
+    }
+}
@@ -1,10 +1,10 @@
 use std::{io::Write, ops::Deref, sync::Arc};
 
 use anyhow::Result;
-use async_recursion::async_recursion;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use sourcemap::SourceMap as CrateMap;
+use sourcemap::{SourceMap as CrateMap, SourceMapBuilder};
 use turbo_tasks::{primitives::BytesVc, TryJoinIterExt};
+use turbo_tasks_fs::FileSystemPathVc;
 
 use crate::source_pos::SourcePos;
 
@@ -26,6 +26,39 @@ pub enum SourceMap {
     Sectioned(#[turbo_tasks(trace_ignore)] SectionedSourceMap),
 }
 
+#[turbo_tasks::value(transparent)]
+pub struct OptionSourceMap(Option<SourceMapVc>);
+
+/// Controls how the source maps of chunks, which concatenate the code of many
+/// chunk items, are emitted.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Hash, PartialOrd, Ord)]
+pub enum SourceMapFormat {
+    /// An index map with a section for the map of every chunk item. Cheap to
+    /// generate, but not supported by all consumers of source maps.
+    Sectioned,
+    /// A regular map into which the maps of all chunk items are merged. The
+    /// maps are remapped in parallel before being merged.
+    Flattened,
+}
+
+/// Controls where the source maps of chunks are emitted and how chunks refer
+/// to them.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum SourceMapEmission {
+    /// A `.map` file next to the chunk, which the chunk refers to with a
+    /// `//# sourceMappingURL` comment.
+    External,
+    /// A base64 data URL in the `//# sourceMappingURL` comment of the chunk.
+    /// No `.map` file is emitted.
+    Inline,
+    /// A `.map` file next to the chunk, which the chunk doesn't refer to, so
+    /// browsers don't load it. Useful to upload the maps to an error
+    /// reporting service without publishing them.
+    Hidden,
+}
+
 /// A token represents a mapping in a source map. It may either be Synthetic,
 /// meaning it was generated by some build tool and doesn't represent a location
 /// in a user-authored source file, or it is Original, meaning it represents a
@@ -84,7 +117,6 @@ impl<'a> From<sourcemap::Token<'a>> for Token {
 
 impl SourceMap {
     /// Encoding a SourceMap stringifies it into JSON.
-    #[async_recursion]
     async fn encode<W: Write + Send>(&self, w: &mut W) -> Result<()> {
         match self {
             SourceMap::Regular(r) => r.0.to_writer(w)?,
@@ -98,10 +130,13 @@ impl SourceMap {
   "sections": ["#
                 )?;
 
+                // The sections are encoded in tasks of their own, so the encoded maps of
+                // unchanged sections are reused verbatim from the cache when other sections
+                // change.
                 let sections = s
                     .sections
                     .iter()
-                    .map(async move |s| Ok((s.offset, s.map.await?)))
+                    .map(async move |s| Ok((s.offset, s.map.to_bytes().await?)))
                     .try_join()
                     .await?;
 
@@ -119,7 +154,7 @@ impl SourceMap {
                         offset.line, offset.column,
                     )?;
 
-                    map.encode(w).await?;
+                    w.write_all(&map)?;
                     write!(w, r#"}}"#)?;
                 }
 
@@ -146,6 +181,34 @@ impl SourceMapVc {
     pub fn new_sectioned(sections: Vec<SourceMapSection>) -> Self {
         SourceMap::Sectioned(SectionedSourceMap::new(sections)).cell()
     }
+
+    /// Parses a regular source map from JSON, e. g. the map of a pre-compiled
+    /// input file. Relative `sources` are resolved from `base`, the directory
+    /// of the map, so they point to the original files.
+    pub async fn new_from_json(json: &[u8], base: FileSystemPathVc) -> Result<Self> {
+        // The parsed map can't be held across awaits, as it's not `Send`
+        let sources = {
+            let map = CrateMap::from_slice(json)?;
+            (0..map.get_source_count())
+                .filter_map(|index| match map.get_source(index) {
+                    // Sources with a scheme, e. g. `webpack://`, aren't files
+                    Some(source) if !source.contains("://") => Some((index, source.to_string())),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut paths = Vec::new();
+        for (index, source) in sources {
+            if let Some(path) = *base.try_join(&source).await? {
+                paths.push((index, format!("/{}", path.await?.path)));
+            }
+        }
+        let mut map = CrateMap::from_slice(json)?;
+        for (index, path) in paths {
+            map.set_source(index, &path);
+        }
+        Ok(Self::new_regular(map))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -158,6 +221,56 @@ impl SourceMapVc {
         Ok(BytesVc::cell(bytes))
     }
 
+    /// Merges a sectioned source map into a regular source map. Every section
+    /// is flattened in a task of its own, so large maps are remapped in
+    /// parallel, and the tokens of the flattened sections are then shifted by
+    /// the offsets of their sections and merged.
+    #[turbo_tasks::function]
+    pub async fn flatten(self) -> Result<SourceMapVc> {
+        let sections = match &*self.await? {
+            SourceMap::Regular(_) => return Ok(self),
+            SourceMap::Sectioned(s) => {
+                s.sections
+                    .iter()
+                    .map(async move |s| Ok((s.offset, s.map.flatten().await?)))
+                    .try_join()
+                    .await?
+            }
+        };
+
+        let mut builder = SourceMapBuilder::new(None);
+        for (offset, map) in sections {
+            let map = match &*map {
+                SourceMap::Regular(map) => map,
+                SourceMap::Sectioned(_) => unreachable!("flattened maps are regular"),
+            };
+            for token in map.tokens() {
+                let line = token.get_dst_line();
+                // Only the first line of a section is offset by the column.
+                let column = if line == 0 {
+                    token.get_dst_col() + offset.column as u32
+                } else {
+                    token.get_dst_col()
+                };
+                builder.add(
+                    line + offset.line as u32,
+                    column,
+                    token.get_src_line(),
+                    token.get_src_col(),
+                    token.get_source(),
+                    token.get_name(),
+                );
+            }
+            for (index, source) in map.sources().enumerate() {
+                if let Some(contents) = map.get_source_contents(index as u32) {
+                    let id = builder.add_source(source);
+                    builder.set_source_contents(id, Some(contents));
+                }
+            }
+        }
+        Ok(SourceMapVc::new_regular(builder.into_sourcemap()))
+    }
+
     /// Traces a generated line/column into an mapping token representing either
     /// synthetic code or user-authored original code.
     #[turbo_tasks::function]
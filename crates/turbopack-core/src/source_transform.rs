@@ -0,0 +1,33 @@
+use anyhow::Result;
+
+use crate::asset::AssetVc;
+
+/// Transforms the source of a module before it's processed as its module
+/// type, e. g. by a tool running in another process.
+#[turbo_tasks::value_trait]
+pub trait SourceTransform {
+    /// Returns the transformed source. It should keep the path of `source`,
+    /// so it's processed like the original source.
+    fn transform(&self, source: AssetVc) -> AssetVc;
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct SourceTransforms(Vec<SourceTransformVc>);
+
+#[turbo_tasks::value_impl]
+impl SourceTransformsVc {
+    #[turbo_tasks::function]
+    pub fn empty() -> Self {
+        SourceTransformsVc::cell(Vec::new())
+    }
+
+    /// Applies the transforms to `source` in order.
+    #[turbo_tasks::function]
+    pub async fn apply(self, source: AssetVc) -> Result<AssetVc> {
+        let mut source = source;
+        for transform in self.await?.iter() {
+            source = transform.transform(source);
+        }
+        Ok(source)
+    }
+}
@@ -10,7 +10,7 @@ use swc_core::{
 use turbo_tasks::{primitives::StringVc, TryJoinIterExt, Value, ValueToString, ValueToStringVc};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
-    asset::{Asset, AssetContentVc, AssetVc},
+    asset::{Asset, AssetContentVc, AssetOptionVc, AssetVc},
     chunk::{ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContextVc},
     context::AssetContextVc,
     reference::{AssetReference, AssetReferencesVc},
@@ -156,6 +156,11 @@ impl ChunkItem for ModuleChunkItem {
     fn references(&self) -> AssetReferencesVc {
         self.module.references()
     }
+
+    #[turbo_tasks::function]
+    fn asset(&self) -> AssetOptionVc {
+        AssetOptionVc::cell(Some(self.module.into()))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -2,7 +2,7 @@ pub(crate) mod optimize;
 mod writer;
 
 use anyhow::{anyhow, Result};
-use indexmap::IndexSet;
+use indexmap::{IndexMap, IndexSet};
 use turbo_tasks::{primitives::StringVc, TryJoinIterExt, ValueToString, ValueToStringVc};
 use turbo_tasks_fs::{File, FileSystemPathOptionVc, FileSystemPathVc};
 use turbo_tasks_hash::{encode_hex, Xxh3Hash64Hasher};
@@ -12,7 +12,8 @@ use turbopack_core::{
         chunk_content, chunk_content_split,
         optimize::{ChunkOptimizerVc, OptimizableChunk, OptimizableChunkVc},
         Chunk, ChunkContentResult, ChunkGroupReferenceVc, ChunkGroupVc, ChunkItem, ChunkItemVc,
-        ChunkReferenceVc, ChunkVc, ChunkableAssetVc, ChunkingContextVc, FromChunkableAsset,
+        ChunkItemsVc, ChunkReferenceVc, ChunkVc, ChunkableAssetVc, ChunkingContextVc,
+        FromChunkableAsset, ModuleSizesVc,
     },
     reference::{AssetReferenceVc, AssetReferencesVc},
 };
@@ -147,7 +148,32 @@ async fn css_chunk_content_single_entry(
 }
 
 #[turbo_tasks::value_impl]
-impl Chunk for CssChunk {}
+impl Chunk for CssChunk {
+    #[turbo_tasks::function]
+    async fn chunk_items(&self) -> Result<ChunkItemsVc> {
+        let content = css_chunk_content(self.context, self.main_entries).await?;
+        Ok(ChunkItemsVc::cell(
+            content
+                .chunk_items
+                .iter()
+                .map(|item| item.as_chunk_item())
+                .collect(),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    async fn module_sizes(&self) -> Result<ModuleSizesVc> {
+        let content = css_chunk_content(self.context, self.main_entries).await?;
+        let mut sizes = IndexMap::new();
+        for item in content.chunk_items.iter() {
+            if let Some(module) = *item.as_chunk_item().asset().await? {
+                let size = item.content().await?.inner_code.len() as u64;
+                *sizes.entry(module.resolve().await?).or_default() += size;
+            }
+        }
+        Ok(ModuleSizesVc::cell(sizes))
+    }
+}
 
 #[turbo_tasks::value_impl]
 impl OptimizableChunk for CssChunk {
@@ -288,6 +314,7 @@ impl FromChunkableAsset for CssChunkItemVc {
     async fn from_async_asset(
         _context: ChunkingContextVc,
         _asset: ChunkableAssetVc,
+        _parent_entry: AssetVc,
     ) -> Result<Option<(Self, ChunkableAssetVc)>> {
         Ok(None)
     }
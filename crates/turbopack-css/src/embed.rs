@@ -1,3 +1,4 @@
+use turbo_tasks::primitives::OptionStringVc;
 use turbopack_core::{
     asset::{Asset, AssetVc},
     chunk::{ChunkableAsset, ChunkableAssetVc, ChunkingContextVc},
@@ -17,4 +18,7 @@ pub trait CssEmbed {
     /// references.
     fn references(&self) -> AssetReferencesVc;
     fn embeddable_asset(&self) -> AssetVc;
+    /// A URL, e. g. a `data:` URL, which is used instead of the URL of the
+    /// [CssEmbed::embeddable_asset] when the asset is inlined.
+    fn inline_url(&self) -> OptionStringVc;
 }
@@ -12,11 +12,13 @@ mod module_asset;
 pub(crate) mod parse;
 mod path_visitor;
 pub(crate) mod references;
+mod sass;
 pub(crate) mod transform;
 
 use anyhow::Result;
 pub use asset::CssModuleAssetVc;
 pub use module_asset::ModuleCssModuleAssetVc;
+pub use sass::SassAssetVc;
 pub use transform::{CssInputTransform, CssInputTransformsVc};
 
 use crate::{chunk::CssChunkItemContentVc, references::import::ImportAssetReferenceVc};
@@ -1,20 +1,23 @@
-use std::fmt::Write;
+use std::{collections::BTreeMap, fmt::Write};
 
 use anyhow::Result;
+use indexmap::IndexMap;
 use swc_css_modules::CssClassName;
-use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
-    asset::{Asset, AssetContentVc, AssetVc},
+    asset::{Asset, AssetContentVc, AssetOptionVc, AssetVc},
     chunk::{
-        ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetReference,
-        ChunkableAssetReferenceVc, ChunkableAssetVc, ChunkingContextVc, ChunkingType,
-        ChunkingTypeOptionVc,
+        used_exports::ExportUsage, ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset,
+        ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkableAssetVc, ChunkingContextVc,
+        ChunkingType, ChunkingTypeOptionVc,
     },
     context::AssetContextVc,
+    issue::{codes, Issue, IssueSeverity, IssueSeverityVc, IssueVc},
     reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
     resolve::{
         origin::{ResolveOrigin, ResolveOriginVc},
+        parse::RequestVc,
         ResolveResult, ResolveResultVc,
     },
 };
@@ -25,9 +28,12 @@ use turbopack_ecmascript::{
         EcmascriptChunkVc, EcmascriptExports, EcmascriptExportsVc,
     },
     utils::stringify_str,
+    EsmExport, EsmExports,
 };
 
-use crate::{parse::ParseResult, transform::CssInputTransformsVc, CssModuleAssetVc};
+use crate::{
+    parse::ParseResult, references::css_resolve, transform::CssInputTransformsVc, CssModuleAssetVc,
+};
 
 #[turbo_tasks::value]
 #[derive(Clone)]
@@ -43,8 +49,52 @@ impl ModuleCssModuleAssetVc {
             inner: CssModuleAssetVc::new_module(source, context, transforms),
         })
     }
+
+    /// Returns the scoped class names of the CSS module. Classes composed from
+    /// other CSS modules (`composes: a from "./other.module.css"`) are
+    /// resolved to the class names of the other module.
+    #[turbo_tasks::function]
+    pub async fn classes(self) -> Result<ModuleCssClassesVc> {
+        let inner = self.await?.inner;
+        let mut classes = IndexMap::new();
+        if let ParseResult::Ok { exports, .. } = &*inner.parse().await? {
+            for (key, elements) in exports {
+                let mut names = Vec::new();
+                for element in elements {
+                    match element {
+                        CssClassName::Local { name } | CssClassName::Global { name } => {
+                            names.push(name.to_string())
+                        }
+                        CssClassName::Import { name, from } => {
+                            let request = RequestVc::parse(Value::new(from.to_string().into()));
+                            let assets = css_resolve(inner.as_resolve_origin(), request)
+                                .primary_assets()
+                                .await?;
+                            for asset in assets.iter() {
+                                if let Some(module) =
+                                    ModuleCssModuleAssetVc::resolve_from(asset).await?
+                                {
+                                    if let Some(imported) = module.classes().await?.get(&**name) {
+                                        names.push(imported.clone());
+                                    }
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+                classes.insert(key.to_string(), names.join(" "));
+            }
+        }
+        Ok(ModuleCssClassesVc::cell(classes))
+    }
 }
 
+/// The class names of a CSS module by their local name. Multiple class names
+/// are separated by spaces.
+#[turbo_tasks::value(transparent)]
+pub struct ModuleCssClasses(IndexMap<String, String>);
+
 #[turbo_tasks::value_impl]
 impl Asset for ModuleCssModuleAsset {
     #[turbo_tasks::function]
@@ -74,18 +124,45 @@ impl ChunkableAsset for ModuleCssModuleAsset {
 #[turbo_tasks::value_impl]
 impl EcmascriptChunkPlaceable for ModuleCssModuleAsset {
     #[turbo_tasks::function]
-    fn as_chunk_item(&self, context: ChunkingContextVc) -> EcmascriptChunkItemVc {
+    fn as_chunk_item(
+        self_vc: ModuleCssModuleAssetVc,
+        context: ChunkingContextVc,
+    ) -> EcmascriptChunkItemVc {
         ModuleChunkItem {
             context,
-            module: self.inner,
+            module: self_vc,
         }
         .cell()
         .into()
     }
 
+    /// Every class is a named export, the default export is an object of all
+    /// classes.
     #[turbo_tasks::function]
-    fn get_exports(&self) -> EcmascriptExportsVc {
-        EcmascriptExports::Value.cell()
+    async fn get_exports(self_vc: ModuleCssModuleAssetVc) -> Result<EcmascriptExportsVc> {
+        let classes = self_vc.classes().await?;
+        let mut exports = classes
+            .iter()
+            .map(|(key, value)| (key.clone(), EsmExport::Constant(value.clone())))
+            .collect::<BTreeMap<_, _>>();
+        exports.insert(
+            "default".to_string(),
+            EsmExport::ConstantObject(
+                classes
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+            ),
+        );
+        Ok(EcmascriptExports::EsmExports(
+            EsmExports {
+                path: self_vc.path(),
+                exports,
+                star_exports: Vec::new(),
+            }
+            .cell(),
+        )
+        .cell())
     }
 }
 
@@ -104,7 +181,7 @@ impl ResolveOrigin for ModuleCssModuleAsset {
 
 #[turbo_tasks::value]
 struct ModuleChunkItem {
-    module: CssModuleAssetVc,
+    module: ModuleCssModuleAssetVc,
     context: ChunkingContextVc,
 }
 
@@ -114,7 +191,7 @@ impl ValueToString for ModuleChunkItem {
     async fn to_string(&self) -> Result<StringVc> {
         Ok(StringVc::cell(format!(
             "{} (css module)",
-            self.module.await?.source.path().to_string().await?
+            self.module.path().to_string().await?
         )))
     }
 }
@@ -122,13 +199,33 @@ impl ValueToString for ModuleChunkItem {
 #[turbo_tasks::value_impl]
 impl ChunkItem for ModuleChunkItem {
     #[turbo_tasks::function]
-    fn references(&self) -> AssetReferencesVc {
-        AssetReferencesVc::cell(vec![CssProxyToCssAssetReference {
-            module: self.module,
+    async fn references(&self) -> Result<AssetReferencesVc> {
+        let inner = self.module.await?.inner;
+        let mut references = vec![CssProxyToCssAssetReference {
+            module: inner,
             context: self.context,
         }
         .cell()
-        .into()])
+        .into()];
+        // The styles of composed classes need to be loaded too
+        if let ParseResult::Ok { imports, .. } = &*inner.parse().await? {
+            for from in imports {
+                references.push(
+                    CssModuleComposeReference {
+                        origin: inner.as_resolve_origin(),
+                        request: RequestVc::parse(Value::new(from.to_string().into())),
+                    }
+                    .cell()
+                    .into(),
+                );
+            }
+        }
+        Ok(AssetReferencesVc::cell(references))
+    }
+
+    #[turbo_tasks::function]
+    fn asset(&self) -> AssetOptionVc {
+        AssetOptionVc::cell(Some(self.module.into()))
     }
 }
 
@@ -162,6 +259,39 @@ impl ChunkableAssetReference for CssProxyToCssAssetReference {
     }
 }
 
+#[turbo_tasks::value]
+struct CssModuleComposeReference {
+    origin: ResolveOriginVc,
+    request: RequestVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for CssModuleComposeReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "composes from {}",
+            self.request.to_string().await?,
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for CssModuleComposeReference {
+    #[turbo_tasks::function]
+    fn resolve_reference(&self) -> ResolveResultVc {
+        css_resolve(self.origin, self.request)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAssetReference for CssModuleComposeReference {
+    #[turbo_tasks::function]
+    fn chunking_type(&self, _context: ChunkingContextVc) -> ChunkingTypeOptionVc {
+        ChunkingTypeOptionVc::cell(Some(ChunkingType::Parallel))
+    }
+}
+
 #[turbo_tasks::value_impl]
 impl EcmascriptChunkItem for ModuleChunkItem {
     #[turbo_tasks::function]
@@ -171,43 +301,97 @@ impl EcmascriptChunkItem for ModuleChunkItem {
 
     #[turbo_tasks::function]
     async fn content(&self) -> Result<EcmascriptChunkItemContentVc> {
-        let parsed = self.module.parse().await?;
-        Ok(match &*parsed {
-            ParseResult::Ok { exports, .. } => {
-                let mut code = "__turbopack_export_value__({\n".to_string();
-                for (key, elements) in exports {
-                    let content = elements
-                        .iter()
-                        .map(|element| match element {
-                            CssClassName::Local { name } | CssClassName::Global { name } => &**name,
-                            CssClassName::Import { .. } => "TODO",
-                        })
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    writeln!(
-                        code,
-                        "  {}: {},",
-                        stringify_str(key),
-                        stringify_str(&content)
-                    )?;
-                }
-                code += "});\n";
-                EcmascriptChunkItemContent {
-                    inner_code: code,
-                    // TODO: We generate a minimal map for runtime code so that the filename is
-                    // displayed in dev tools.
-                    ..Default::default()
-                }
-            }
-            ParseResult::NotFound | ParseResult::Unparseable => {
-                EcmascriptChunkItemContent {
-                    inner_code: "__turbopack_export_value__({});\n".to_string(),
-                    // TODO: We generate a minimal map for runtime code so that the filename is
-                    // displayed in dev tools.
-                    ..Default::default()
+        let classes = self.module.classes().await?;
+        let usage = self.context.export_usage(self.module.path()).await?;
+
+        let mut code = "const classes = {\n".to_string();
+        for (key, value) in classes.iter() {
+            writeln!(code, "  {}: {},", stringify_str(key), stringify_str(value))?;
+        }
+        code += "};\n__turbopack_esm__({\n";
+        for key in classes.keys().filter(|key| usage.is_used(key)) {
+            writeln!(
+                code,
+                "  {}: () => classes[{}],",
+                stringify_str(key),
+                stringify_str(key)
+            )?;
+        }
+        if usage.is_used("default") {
+            code += "  default: () => classes,\n";
+        }
+        code += "});\n";
+
+        // When the classes are only imported by name or read from the default
+        // export by static member accesses, the unused classes are known
+        if let ExportUsage::Named(used) = &*usage {
+            if !used.contains("default") {
+                let unused = classes
+                    .keys()
+                    .filter(|key| !used.contains(*key))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if !unused.is_empty() {
+                    UnusedCssModuleClassesIssue {
+                        path: self.module.path(),
+                        classes: unused,
+                    }
+                    .cell()
+                    .as_issue()
+                    .emit();
                 }
             }
         }
+
+        Ok(EcmascriptChunkItemContent {
+            inner_code: code,
+            // TODO: We generate a minimal map for runtime code so that the filename is
+            // displayed in dev tools.
+            ..Default::default()
+        }
         .cell())
     }
 }
+
+#[turbo_tasks::value(shared)]
+struct UnusedCssModuleClassesIssue {
+    path: FileSystemPathVc,
+    classes: Vec<String>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for UnusedCssModuleClassesIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Hint.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Unused CSS module classes".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("css".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::css::UNUSED_CSS_MODULE_CLASSES.to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(format!(
+            "The classes {} are not used by any module importing this CSS module. Their styles \
+             can be removed.",
+            self.classes.join(", ")
+        ))
+    }
+}
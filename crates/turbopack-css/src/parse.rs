@@ -13,6 +13,7 @@ use swc_core::{
 use swc_css_modules::{CssClassName, TransformConfig};
 use turbo_tasks::{Value, ValueToString};
 use turbo_tasks_fs::{FileContent, FileSystemPath};
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
 use turbopack_core::asset::{AssetContent, AssetVc};
 use turbopack_swc_utils::emitter::IssueEmitter;
 
@@ -134,11 +135,10 @@ async fn parse_content(
                 &mut parsed_stylesheet,
                 // TODO swc_css_modules should take `impl TransformConfig + '_`
                 ModuleTransformConfig {
-                    // Note this uses an square emoji to join class name with module name
-                    // This emoji is usually not used in css class names so it's easy for the user
-                    // to see which class names are generated by css modules. Its also a pretty
-                    // small, so it's not too intense for the eyes.
-                    suffix: format!("◽{}", fs_path_str),
+                    suffix: format!(
+                        "__{}",
+                        &encode_hex(hash_xxh3_hash64(fs_path_str.as_bytes()))[..8]
+                    ),
                 },
             );
             let mut exports = result.renamed.into_iter().collect::<IndexMap<_, _>>();
@@ -159,6 +159,8 @@ async fn parse_content(
     .into())
 }
 
+/// Scopes the class names of a CSS module by appending a hash of the path of
+/// the module, e. g. `button` becomes `button__1a2b3c4d`.
 struct ModuleTransformConfig {
     suffix: String,
 }
@@ -5,7 +5,7 @@ use swc_core::{
         Globals, GLOBALS,
     },
     css::{
-        ast::{ImportPrelude, ImportPreludeHref, Url, UrlValue},
+        ast::{ComponentValue, Function, ImportPrelude, ImportPreludeHref, Str, Url, UrlValue},
         visit::{AstNodePath, AstParentKind, VisitAstPath, VisitWithPath},
     },
 };
@@ -21,7 +21,7 @@ use crate::{
     parse::{parse, ParseResult},
     references::{
         import::{ImportAssetReferenceVc, ImportAttributes},
-        url::UrlAssetReferenceVc,
+        url::{UrlAssetReferenceVc, UrlKind},
     },
     CssInputTransformsVc, CssModuleAssetType,
 };
@@ -71,6 +71,8 @@ struct AssetReferencesVisitor<'a> {
     origin: ResolveOriginVc,
     references: &'a mut Vec<AssetReferenceVc>,
     is_import: bool,
+    /// Whether the visited component values are arguments of `image-set()`.
+    is_image_set: bool,
 }
 
 impl<'a> AssetReferencesVisitor<'a> {
@@ -79,6 +81,7 @@ impl<'a> AssetReferencesVisitor<'a> {
             origin,
             references,
             is_import: false,
+            is_image_set: false,
         }
     }
 }
@@ -98,6 +101,20 @@ pub fn as_parent_path(ast_path: &AstNodePath<'_>) -> Vec<AstParentKind> {
     ast_path.iter().map(|n| n.kind()).collect()
 }
 
+impl<'a> AssetReferencesVisitor<'a> {
+    fn visit_image_set_str(&mut self, s: &Str, ast_path: &AstNodePath<'_>) {
+        self.references.push(
+            UrlAssetReferenceVc::new(
+                self.origin,
+                RequestVc::parse(Value::new(s.value.to_string().into())),
+                AstPathVc::cell(as_parent_path(ast_path)),
+                Value::new(UrlKind::ImageSetString),
+            )
+            .into(),
+        );
+    }
+}
+
 impl<'a> VisitAstPath for AssetReferencesVisitor<'a> {
     fn visit_import_prelude<'ast: 'r, 'r>(
         &mut self,
@@ -137,12 +154,38 @@ impl<'a> VisitAstPath for AssetReferencesVisitor<'a> {
                 self.origin,
                 RequestVc::parse(Value::new(src.to_string().into())),
                 AstPathVc::cell(as_parent_path(ast_path)),
+                Value::new(UrlKind::Url),
             )
             .into(),
         );
 
         u.visit_children_with_path(self, ast_path);
     }
+
+    fn visit_function<'ast: 'r, 'r>(&mut self, f: &'ast Function, ast_path: &mut AstNodePath<'r>) {
+        let is_image_set = self.is_image_set;
+        self.is_image_set = matches!(
+            &*f.name.value.to_ascii_lowercase(),
+            "image-set" | "-webkit-image-set"
+        );
+        f.visit_children_with_path(self, ast_path);
+        self.is_image_set = is_image_set;
+    }
+
+    fn visit_component_value<'ast: 'r, 'r>(
+        &mut self,
+        v: &'ast ComponentValue,
+        ast_path: &mut AstNodePath<'r>,
+    ) {
+        // Images in `image-set()` can be strings instead of `url()`s.
+        if let ComponentValue::Str(s) = v {
+            if self.is_image_set {
+                self.visit_image_set_str(s, ast_path);
+                return;
+            }
+        }
+        v.visit_children_with_path(self, ast_path);
+    }
 }
 
 #[turbo_tasks::function]
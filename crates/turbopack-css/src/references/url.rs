@@ -3,7 +3,7 @@ use swc_core::{
     common::DUMMY_SP,
     css::ast::{Str, UrlValue},
 };
-use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
 use turbopack_core::{
     asset::AssetVc,
     chunk::ChunkingContextVc,
@@ -21,25 +21,45 @@ use crate::{
 #[turbo_tasks::value(into = "new")]
 pub enum ReferencedAsset {
     Some(AssetVc),
+    /// The asset is inlined with this URL.
+    Inline(String),
     None,
 }
 
+/// The syntax an URL is referenced with in a stylesheet.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(PartialOrd, Ord, Hash, Debug, Copy, Clone)]
+pub enum UrlKind {
+    /// `url(...)`, e. g. in `background-image` or the `src` of `@font-face`.
+    Url,
+    /// A string in `image-set(...)`, e. g. `image-set("a.png" 1x, "b.png"
+    /// 2x)`.
+    ImageSetString,
+}
+
 #[turbo_tasks::value]
 #[derive(Hash, Debug)]
 pub struct UrlAssetReference {
     pub origin: ResolveOriginVc,
     pub request: RequestVc,
     pub path: AstPathVc,
+    pub kind: UrlKind,
 }
 
 #[turbo_tasks::value_impl]
 impl UrlAssetReferenceVc {
     #[turbo_tasks::function]
-    pub fn new(origin: ResolveOriginVc, request: RequestVc, path: AstPathVc) -> Self {
+    pub fn new(
+        origin: ResolveOriginVc,
+        request: RequestVc,
+        path: AstPathVc,
+        kind: Value<UrlKind>,
+    ) -> Self {
         Self::cell(UrlAssetReference {
             origin,
             request,
             path,
+            kind: kind.into_value(),
         })
     }
 
@@ -48,10 +68,11 @@ impl UrlAssetReferenceVc {
         let assets = self.resolve_reference().primary_assets();
         for asset in assets.await?.iter() {
             if let Some(embeddable) = CssEmbeddableVc::resolve_from(asset).await? {
-                return Ok(ReferencedAsset::Some(
-                    embeddable.as_css_embed(context).embeddable_asset(),
-                )
-                .into());
+                let embed = embeddable.as_css_embed(context);
+                if let Some(url) = &*embed.inline_url().await? {
+                    return Ok(ReferencedAsset::Inline(url.clone()).into());
+                }
+                return Ok(ReferencedAsset::Some(embed.embeddable_asset()).into());
             }
         }
         Ok(ReferencedAssetVc::cell(ReferencedAsset::None))
@@ -90,21 +111,37 @@ impl CodeGenerateable for UrlAssetReference {
 
         let mut visitors = Vec::new();
 
-        if let ReferencedAsset::Some(asset) = &*self_vc.get_referenced_asset(context).await? {
-            let path = asset.path().await?;
-            let relative_path = context_path
-                .get_relative_path_to(&path)
-                .unwrap_or_else(|| format!("/{}", path.path));
+        let url = match &*self_vc.get_referenced_asset(context).await? {
+            ReferencedAsset::Some(asset) => {
+                let path = asset.path().await?;
+                Some(
+                    context_path
+                        .get_relative_path_to(&path)
+                        .unwrap_or_else(|| format!("/{}", path.path)),
+                )
+            }
+            ReferencedAsset::Inline(url) => Some(url.clone()),
+            ReferencedAsset::None => None,
+        };
 
-            visitors.push(
-                create_visitor!((&this.path.await?), visit_mut_url(u: &mut Url) {
+        if let Some(url) = url {
+            let path = &this.path.await?;
+            visitors.push(match this.kind {
+                UrlKind::Url => create_visitor!(path, visit_mut_url(u: &mut Url) {
                     u.value = Some(box UrlValue::Str(Str {
                         span: DUMMY_SP,
-                        value: relative_path.as_str().into(),
+                        value: url.as_str().into(),
                         raw: None,
                     }))
                 }),
-            );
+                UrlKind::ImageSetString => create_visitor!(path, visit_mut_str(s: &mut Str) {
+                    *s = Str {
+                        span: s.span,
+                        value: url.as_str().into(),
+                        raw: None,
+                    }
+                }),
+            });
         }
 
         Ok(CodeGeneration { visitors }.into())
@@ -0,0 +1,299 @@
+//! Compilation of Sass stylesheets (`.scss` and `.sass`) to CSS with grass.
+//!
+//! grass reads files synchronously, so the stylesheet and all stylesheets it
+//! loads (transitively) with `@use`, `@forward` and `@import` are read with
+//! turbo-tasks before compiling. This tracks them as dependencies of the
+//! compilation, which is recomputed when one of them changes or when a file is
+//! added which would be loaded instead, e. g. a partial `_colors.scss`.
+//!
+//! grass 0.11 (newer versions require a newer indexmap than the workspace
+//! uses) only parses the SCSS syntax, so `.sass` files in the indented syntax
+//! fail to compile with a [SassCompileIssue].
+
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::Path,
+};
+
+use anyhow::Result;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::{util::normalize_path, File, FileContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    issue::{codes, Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    reference::AssetReferencesVc,
+};
+
+/// The extensions of the files loaded for a url without an extension, in the
+/// order Sass tries them.
+const SASS_EXTENSIONS: [&str; 3] = ["scss", "sass", "css"];
+
+/// A Sass stylesheet compiled to CSS. It has the path of the Sass source, so
+/// it can be processed like any other CSS source.
+#[turbo_tasks::value]
+pub struct SassAsset {
+    source: AssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl SassAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(source: AssetVc) -> Self {
+        Self::cell(SassAsset { source })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for SassAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.source.path()
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        compile(self.source)
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        // Loaded stylesheets are inlined into the compiled CSS.
+        AssetReferencesVc::empty()
+    }
+}
+
+#[turbo_tasks::function]
+async fn compile(source: AssetVc) -> Result<AssetContentVc> {
+    let path = source.path();
+    let entry = path.await?.path.clone();
+    let content = match &*source.content().await? {
+        AssetContent::File(file) => match &*file.await? {
+            FileContent::Content(file) => String::from_utf8_lossy(file.content()).into_owned(),
+            FileContent::NotFound => {
+                return Ok(AssetContent::File(FileContent::NotFound.cell()).cell());
+            }
+        },
+        AssetContent::Redirect { .. } => {
+            return Ok(AssetContent::File(FileContent::NotFound.cell()).cell());
+        }
+    };
+
+    let mut files = LoadedFiles::default();
+    let mut visited = HashSet::from([entry.clone()]);
+    let mut queue = vec![(path, content)];
+    while let Some((file_path, content)) = queue.pop() {
+        let key = file_path.await?.path.clone();
+        for url in loaded_urls(&content) {
+            for candidate in candidate_paths(&url) {
+                let candidate = match *file_path.parent().try_join(&candidate).await? {
+                    Some(candidate) => candidate,
+                    None => continue,
+                };
+                let candidate_key = candidate.await?.path.clone();
+                if !visited.insert(candidate_key.clone()) {
+                    continue;
+                }
+                if let FileContent::Content(file) = &*candidate.read().await? {
+                    queue.push((
+                        candidate,
+                        String::from_utf8_lossy(file.content()).into_owned(),
+                    ));
+                }
+            }
+        }
+        files.0.insert(key, content);
+    }
+
+    let options = grass::Options::default().fs(&files);
+    let css = match grass::from_path(&entry, &options) {
+        Ok(css) => css,
+        Err(err) => {
+            SassCompileIssue {
+                path,
+                message: StringVc::cell(err.to_string()),
+            }
+            .cell()
+            .as_issue()
+            .emit();
+            String::new()
+        }
+    };
+    Ok(AssetContent::File(FileContent::Content(File::from(css)).cell()).cell())
+}
+
+/// Returns the urls loaded by the `@use`, `@forward` and `@import` rules in
+/// `content`, except for built-in modules and plain CSS imports which are not
+/// loaded by Sass.
+fn loaded_urls(content: &str) -> Vec<String> {
+    let mut urls = Vec::new();
+    let mut rest = content;
+    while let Some(index) = rest.find('@') {
+        rest = &rest[index + 1..];
+        let keyword_len = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        if !matches!(&rest[..keyword_len], "use" | "forward" | "import") {
+            continue;
+        }
+        rest = &rest[keyword_len..];
+        // `@import` accepts a comma separated list of urls.
+        loop {
+            let trimmed = rest.trim_start();
+            let quote = match trimmed.chars().next() {
+                Some(quote @ ('"' | '\'')) => quote,
+                _ => break,
+            };
+            let end = match trimmed[1..].find(quote) {
+                Some(end) => end + 1,
+                None => break,
+            };
+            let url = &trimmed[1..end];
+            if !url.starts_with("sass:")
+                && !url.contains("://")
+                && !url.starts_with("//")
+                && !url.ends_with(".css")
+            {
+                urls.push(url.to_string());
+            }
+            rest = trimmed[end + 1..].trim_start();
+            match rest.strip_prefix(',') {
+                Some(next) => rest = next,
+                None => break,
+            }
+        }
+    }
+    urls
+}
+
+/// Returns the paths Sass tries for `url`, relative to the loading
+/// stylesheet: the file itself, its partial and the index files of a
+/// directory.
+fn candidate_paths(url: &str) -> Vec<String> {
+    let (dir, name) = match url.rsplit_once('/') {
+        Some((dir, name)) => (format!("{dir}/"), name),
+        None => (String::new(), url),
+    };
+    let has_extension = SASS_EXTENSIONS
+        .iter()
+        .any(|extension| name.ends_with(&format!(".{extension}")));
+    if has_extension {
+        return vec![format!("{dir}{name}"), format!("{dir}_{name}")];
+    }
+    let mut candidates = Vec::new();
+    for extension in SASS_EXTENSIONS {
+        candidates.push(format!("{dir}{name}.{extension}"));
+        candidates.push(format!("{dir}_{name}.{extension}"));
+    }
+    for extension in SASS_EXTENSIONS {
+        candidates.push(format!("{url}/index.{extension}"));
+        candidates.push(format!("{url}/_index.{extension}"));
+    }
+    candidates
+}
+
+/// The contents of the files read for a compilation by their path, which are
+/// the only files grass can see.
+#[derive(Debug, Default)]
+struct LoadedFiles(HashMap<String, String>);
+
+impl LoadedFiles {
+    fn key(path: &Path) -> Option<String> {
+        normalize_path(&path.to_string_lossy().replace('\\', "/"))
+    }
+}
+
+impl grass::Fs for LoadedFiles {
+    fn is_dir(&self, path: &Path) -> bool {
+        match Self::key(path) {
+            Some(dir) => self
+                .0
+                .keys()
+                .any(|file| file.starts_with(&format!("{dir}/"))),
+            None => false,
+        }
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        Self::key(path).map_or(false, |file| self.0.contains_key(&file))
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        Self::key(path)
+            .and_then(|file| self.0.get(&file))
+            .map(|content| content.as_bytes().to_vec())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+}
+
+#[turbo_tasks::value(shared)]
+struct SassCompileIssue {
+    path: FileSystemPathVc,
+    message: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for SassCompileIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Sass compilation failed".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("css".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::css::SASS_COMPILE.to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{candidate_paths, loaded_urls};
+
+    #[test]
+    fn finds_loaded_urls() {
+        let content = r#"
+            @use "sass:math";
+            @use 'theme/colors' as c;
+            @forward "mixins";
+            @import "reset", 'layout/grid';
+            @import "https://example.com/font.css";
+            @import "print.css";
+            .a { width: math.div(1px, 2); }
+        "#;
+        assert_eq!(
+            loaded_urls(content),
+            vec!["theme/colors", "mixins", "reset", "layout/grid"]
+        );
+    }
+
+    #[test]
+    fn candidate_paths_of_urls() {
+        assert_eq!(
+            candidate_paths("theme/colors.scss"),
+            vec!["theme/colors.scss", "theme/_colors.scss"]
+        );
+        let candidates = candidate_paths("theme");
+        assert_eq!(&candidates[..2], &["theme.scss", "_theme.scss"]);
+        assert!(candidates.contains(&"theme/_index.scss".to_string()));
+    }
+}
@@ -9,10 +9,13 @@ use turbopack_core::{
     reference::AssetReferencesVc,
     version::{Update, UpdateVc, Version, VersionVc, VersionedContent, VersionedContentVc},
 };
+use turbopack_ecmascript::chunk::library::module_preloads;
 
 /// The HTML entry point of the dev server.
 ///
-/// Generates an HTML page that includes the ES and CSS chunks.
+/// Generates an HTML page that includes the ES and CSS chunks. The ES module
+/// files the chunks import, e. g. pre-bundled packages, are preloaded, see
+/// [module_preloads].
 #[turbo_tasks::value(shared)]
 #[derive(Clone)]
 pub struct DevHtmlAsset {
@@ -112,19 +115,40 @@ impl DevHtmlAssetVc {
             }
         }
 
-        Ok(DevHtmlAssetContentVc::new(chunk_paths, this.body.clone()))
+        let mut preload_paths = vec![];
+        for library in module_preloads(this.chunk_groups.clone(), Vec::new())
+            .await?
+            .iter()
+        {
+            let library_path = &*library.path().await?;
+            if let Some(relative_path) = context_path.get_path_to(library_path) {
+                preload_paths.push(format!("/{relative_path}"));
+            }
+        }
+
+        Ok(DevHtmlAssetContentVc::new(
+            chunk_paths,
+            preload_paths,
+            this.body.clone(),
+        ))
     }
 }
 
 #[turbo_tasks::value]
 struct DevHtmlAssetContent {
     chunk_paths: Vec<String>,
+    preload_paths: Vec<String>,
     body: Option<String>,
 }
 
 impl DevHtmlAssetContentVc {
-    pub fn new(chunk_paths: Vec<String>, body: Option<String>) -> Self {
-        DevHtmlAssetContent { chunk_paths, body }.cell()
+    pub fn new(chunk_paths: Vec<String>, preload_paths: Vec<String>, body: Option<String>) -> Self {
+        DevHtmlAssetContent {
+            chunk_paths,
+            preload_paths,
+            body,
+        }
+        .cell()
     }
 }
 
@@ -135,13 +159,22 @@ impl DevHtmlAssetContentVc {
         let this = self.await?;
 
         let mut scripts = Vec::new();
-        let mut stylesheets = Vec::new();
+        // The preloads come first, so the files are fetched while the
+        // stylesheets load
+        let mut head = Vec::new();
+
+        for relative_path in &*this.preload_paths {
+            head.push(format!(
+                "<link rel=\"modulepreload\" href=\"{}\">",
+                relative_path
+            ));
+        }
 
         for relative_path in &*this.chunk_paths {
             if relative_path.ends_with(".js") {
                 scripts.push(format!("<script src=\"{}\"></script>", relative_path));
             } else if relative_path.ends_with(".css") {
-                stylesheets.push(format!(
+                head.push(format!(
                     "<link data-turbopack rel=\"stylesheet\" href=\"{}\">",
                     relative_path
                 ));
@@ -157,7 +190,7 @@ impl DevHtmlAssetContentVc {
 
         let html = format!(
             "<!DOCTYPE html>\n<html>\n<head>\n{}\n</head>\n<body>\n{}\n{}\n</body>\n</html>",
-            stylesheets.join("\n"),
+            head.join("\n"),
             body,
             scripts.join("\n"),
         );
@@ -194,7 +227,9 @@ impl VersionedContent for DevHtmlAssetContent {
         let to = to_version.await?;
         let from = from_version.await?;
 
-        if to.content.chunk_paths == from.content.chunk_paths {
+        if to.content.chunk_paths == from.content.chunk_paths
+            && to.content.preload_paths == from.content.preload_paths
+        {
             return Ok(Update::None.into());
         }
 
@@ -220,6 +255,9 @@ impl Version for DevHtmlAssetVersion {
         for relative_path in &*self.content.chunk_paths {
             hasher.write_ref(relative_path);
         }
+        for relative_path in &*self.content.preload_paths {
+            hasher.write_ref(relative_path);
+        }
         if let Some(body) = &self.content.body {
             hasher.write_ref(body);
         }
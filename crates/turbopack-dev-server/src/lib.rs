@@ -5,6 +5,7 @@
 pub mod fs;
 pub mod html;
 pub mod introspect;
+pub mod module_graph;
 pub mod source;
 pub mod update;
 
@@ -34,6 +35,7 @@ use turbo_tasks::{
     run_once, trace::TraceRawVcs, util::FormatDuration, RawVc, TransientValue, TurboTasksApi, Value,
 };
 use turbo_tasks_fs::FileContent;
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
 use turbopack_cli_utils::issue::{ConsoleUi, ConsoleUiVc};
 use turbopack_core::asset::AssetContent;
 
@@ -86,6 +88,36 @@ async fn handle_issues<T: Into<RawVc>>(
     Ok(())
 }
 
+/// Responds with the content of an asset. Assets are recomputed
+/// incrementally, so most of them are unchanged between two requests. Clients
+/// revalidate them with their ETag and get a 304 instead of the content when
+/// it's still the same.
+fn asset_response(
+    request: &Request<hyper::Body>,
+    content_type: String,
+    bytes: Vec<u8>,
+) -> Result<Response<hyper::Body>> {
+    let etag = format!("\"{}\"", encode_hex(hash_xxh3_hash64(&bytes)));
+    if request
+        .headers()
+        .get(hyper::header::IF_NONE_MATCH)
+        .map_or(false, |value| value.as_bytes() == etag.as_bytes())
+    {
+        return Ok(Response::builder()
+            .status(304)
+            .header("ETag", etag)
+            .header("Cache-Control", "no-cache")
+            .body(hyper::Body::empty())?);
+    }
+    Ok(Response::builder()
+        .status(200)
+        .header("Content-Type", content_type)
+        .header("Content-Length", bytes.len().to_string())
+        .header("ETag", etag)
+        .header("Cache-Control", "no-cache")
+        .body(hyper::Body::from(bytes))?)
+}
+
 async fn process_request_with_content_source(
     path: &str,
     mut resolved_source: ContentSourceVc,
@@ -131,11 +163,7 @@ async fn process_request_with_content_source(
                         );
 
                         let bytes = content.content().to_vec();
-                        return Ok(Response::builder()
-                            .status(200)
-                            .header("Content-Type", content_type)
-                            .header("Content-Length", bytes.len().to_string())
-                            .body(hyper::Body::from(bytes))?);
+                        return asset_response(&request, content_type, bytes);
                     }
                 }
             }
@@ -232,7 +260,8 @@ impl DevServer {
                             )
                             .await?;
                             let status = response.status().as_u16();
-                            let success = response.status().is_success();
+                            let success = response.status().is_success()
+                                || response.status() == hyper::StatusCode::NOT_MODIFIED;
                             let elapsed = start.elapsed();
                             if !success
                                 || (cfg!(feature = "log_request_stats")
@@ -392,3 +421,45 @@ pub fn register() {
     turbopack_ecmascript::register();
     include!(concat!(env!("OUT_DIR"), "/register.rs"));
 }
+
+#[cfg(test)]
+mod tests {
+    use hyper::{header, Request, StatusCode};
+
+    use super::asset_response;
+
+    fn request(if_none_match: Option<&str>) -> Request<hyper::Body> {
+        let mut request = Request::builder().uri("/index.js");
+        if let Some(etag) = if_none_match {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        request.body(hyper::Body::empty()).unwrap()
+    }
+
+    fn content_type() -> String {
+        "application/javascript; charset=utf-8".to_string()
+    }
+
+    #[test]
+    fn revalidates_assets_with_etags() {
+        let response = asset_response(&request(None), content_type(), b"a".to_vec()).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        // Unchanged asset
+        let response =
+            asset_response(&request(Some(&etag)), content_type(), b"a".to_vec()).unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag.as_str());
+
+        // Changed asset
+        let response =
+            asset_response(&request(Some(&etag)), content_type(), b"b".to_vec()).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(response.headers()[header::ETAG], etag.as_str());
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "1");
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::{HashSet, VecDeque};
+
+use anyhow::Result;
+use turbo_tasks::Value;
+use turbo_tasks_fs::{File, FileContent};
+use turbopack_core::{
+    asset::{AssetContent, AssetVc, AssetsVc},
+    introspect::{asset::IntrospectableAssetVc, IntrospectableVc},
+    reference::graph::{ModuleGraphFormat, ModuleGraphVc},
+};
+
+use crate::source::{
+    ContentSource, ContentSourceData, ContentSourceResult, ContentSourceResultVc, ContentSourceVc,
+};
+
+/// Serves the module graph of the assets found by introspecting `roots`, as
+/// `graph.json` or as `graph.dot` for Graphviz. `why/<path>` lists the import
+/// chains which include the modules at `<path>`.
+///
+/// Only assets which are already known to the sources are included, e. g. a
+/// lazily compiled page needs to be requested before.
+#[turbo_tasks::value(shared)]
+pub struct ModuleGraphSource {
+    pub roots: HashSet<IntrospectableVc>,
+}
+
+#[turbo_tasks::value_impl]
+impl ModuleGraphSourceVc {
+    /// The [ModuleGraphVc] of the assets found by introspecting the roots.
+    #[turbo_tasks::function]
+    pub async fn graph(self) -> Result<ModuleGraphVc> {
+        let mut queue = self.await?.roots.iter().copied().collect::<VecDeque<_>>();
+        let mut visited = HashSet::new();
+        let mut assets = Vec::new();
+        while let Some(introspectable) = queue.pop_front() {
+            let introspectable = introspectable.resolve().await?;
+            if !visited.insert(introspectable) {
+                continue;
+            }
+            if let Some(asset) = IntrospectableAssetVc::resolve_from(introspectable).await? {
+                assets.push(asset.asset());
+            } else if let Some(asset) = AssetVc::resolve_from(introspectable).await? {
+                assets.push(asset);
+            } else {
+                for &(_, child) in introspectable.children().await?.iter() {
+                    queue.push_back(child);
+                }
+            }
+        }
+        Ok(ModuleGraphVc::from_roots(AssetsVc::cell(assets)))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for ModuleGraphSource {
+    #[turbo_tasks::function]
+    async fn get(
+        self_vc: ModuleGraphSourceVc,
+        path: &str,
+        _data: Value<ContentSourceData>,
+    ) -> Result<ContentSourceResultVc> {
+        let graph = self_vc.graph();
+        let (content, content_type) = if let Some(module_path) = path.strip_prefix("why/") {
+            let modules = graph.modules_at_path(module_path).await?;
+            if modules.is_empty() {
+                return Ok(ContentSourceResult::NotFound.cell());
+            }
+            let mut content = String::new();
+            for &module in modules.iter() {
+                if !content.is_empty() {
+                    content.push('\n');
+                }
+                content.push_str(&graph.import_chains(module).to_text().await?);
+            }
+            (content, mime::TEXT_PLAIN_UTF_8)
+        } else {
+            let (format, content_type) = match path {
+                "graph.json" => (ModuleGraphFormat::Json, mime::APPLICATION_JSON),
+                "graph.dot" => (ModuleGraphFormat::Dot, mime::TEXT_PLAIN_UTF_8),
+                _ => return Ok(ContentSourceResult::NotFound.cell()),
+            };
+            let content = graph.render(Value::new(format)).await?;
+            (content.clone_value(), content_type)
+        };
+        Ok(ContentSourceResult::Static(
+            AssetContent::File(
+                FileContent::Content(File::from(content).with_content_type(content_type)).cell(),
+            )
+            .cell()
+            .into(),
+        )
+        .cell())
+    }
+}
@@ -2,7 +2,10 @@ use std::{collections::BTreeMap, ops::Deref, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use turbopack_cli_utils::issue::{format_issue, LogOptions};
+use turbopack_cli_utils::{
+    issue::{format_code_frame, format_issue, LogOptions},
+    reporter::OutputFormat,
+};
 use turbopack_core::{
     issue::{IssueSeverity, PlainIssue},
     source_pos::SourcePos,
@@ -102,6 +105,8 @@ pub struct IssueSource<'a> {
     pub asset: Asset<'a>,
     pub start: SourcePos,
     pub end: SourcePos,
+    /// The highlighted lines around the source, styled with ANSI escapes.
+    pub code_frame: String,
 }
 
 #[derive(Serialize)]
@@ -109,6 +114,7 @@ pub struct Issue<'a> {
     pub severity: IssueSeverity,
     pub context: &'a str,
     pub category: &'a str,
+    pub code: &'a str,
 
     pub title: &'a str,
     pub description: &'a str,
@@ -129,12 +135,14 @@ impl<'a> From<&'a PlainIssue> for Issue<'a> {
             },
             start: source.start,
             end: source.end,
+            code_frame: format_code_frame(source),
         });
 
         Issue {
             severity: plain.severity,
             context: &plain.context,
             category: &plain.category,
+            code: &plain.code,
             title: &plain.title,
             description: &plain.description,
             documentation_link: &plain.documentation_link,
@@ -149,6 +157,13 @@ impl<'a> From<&'a PlainIssue> for Issue<'a> {
                     show_all: true,
                     log_detail: true,
                     log_level: IssueSeverity::Info,
+                    fail_level: IssueSeverity::Fatal,
+                    code_overrides: Default::default(),
+                    path_overrides: Default::default(),
+                    format: OutputFormat::Pretty,
+                    terminal_width: None,
+                    messages: Default::default(),
+                    diagnostics_file: None,
                 },
             ),
         }
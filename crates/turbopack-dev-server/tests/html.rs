@@ -0,0 +1,112 @@
+use std::{collections::HashMap, path::PathBuf, sync::Mutex};
+
+use anyhow::{bail, Result};
+use turbo_tasks::{NothingVc, TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, FileContent, FileSystem};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    ecmascript::EcmascriptModuleAssetVc, module_options::ModuleOptionsContext,
+    prebundle::PrebundledDependenciesVc, resolve_options_context::ResolveOptionsContext,
+    transition::TransitionsByNameVc, ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::{AssetContent, AssetContentVc, AssetsVc},
+    chunk::{dev::DevChunkingContextVc, ChunkGroupVc},
+    context::AssetContext,
+    environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    source_asset::SourceAssetVc,
+};
+use turbopack_dev_server::{fs::DevServerFileSystemVc, html::DevHtmlAssetVc};
+
+static HTML: Mutex<String> = Mutex::new(String::new());
+
+#[tokio::test]
+async fn preloads_the_module_files_of_chunk_groups() {
+    turbopack::register();
+    turbopack_dev_server::register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let project_root = DiskFileSystemVc::new(
+            "project".to_string(),
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/html")
+                .to_string_lossy()
+                .to_string(),
+        )
+        .root();
+        let server_root = DevServerFileSystemVc::new().as_file_system().root();
+        let env = EnvironmentVc::new(
+            Value::new(ExecutionEnvironment::Browser(
+                BrowserEnvironment {
+                    dom: true,
+                    web_worker: false,
+                    service_worker: false,
+                    browserslist_query: "Chrome 102".to_string(),
+                }
+                .into(),
+            )),
+            Value::new(EnvironmentIntention::Client),
+        );
+        let context = ModuleAssetContextVc::new(
+            TransitionsByNameVc::cell(HashMap::new()),
+            env,
+            ModuleOptionsContext::default().cell(),
+            ResolveOptionsContext {
+                enable_node_modules: true,
+                ..Default::default()
+            }
+            .cell(),
+        );
+        let entry = SourceAssetVc::new(project_root.join("index.js")).into();
+
+        // The package the app imports is an ES module file served by the dev
+        // server
+        let prebundled = PrebundledDependenciesVc::new(
+            context,
+            AssetsVc::cell(vec![context.process(entry)]),
+            project_root,
+            server_root.join("prebundle"),
+        );
+        let app = context
+            .with_extended_import_map(prebundled.import_map())
+            .process(entry);
+        let app = match EcmascriptModuleAssetVc::resolve_from(app).await? {
+            Some(app) => app,
+            None => bail!("the entry is not an ECMAScript module"),
+        };
+        let chunking_context = DevChunkingContextVc::builder(
+            project_root,
+            server_root,
+            server_root.join("_chunks"),
+            server_root.join("_assets"),
+        )
+        .build();
+        let html = DevHtmlAssetVc::new(
+            server_root.join("index.html"),
+            vec![ChunkGroupVc::from_chunk(
+                app.as_evaluated_chunk(chunking_context, None),
+            )],
+        );
+        *HTML.lock().unwrap() = read(html.as_asset().content()).await?;
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+
+    let html = HTML.lock().unwrap();
+    let head = &html[..html.find("</head>").unwrap()];
+    assert!(
+        head.contains(r#"<link rel="modulepreload" href="/prebundle/dep.mjs">"#),
+        "{html}"
+    );
+    assert!(html.contains(r#"<script src="/_chunks/"#), "{html}");
+}
+
+async fn read(content: AssetContentVc) -> Result<String> {
+    if let AssetContent::File(file) = &*content.await? {
+        if let FileContent::Content(file) = &*file.await? {
+            return Ok(String::from_utf8(file.content().to_vec())?);
+        }
+    }
+    bail!("the content is not a file")
+}
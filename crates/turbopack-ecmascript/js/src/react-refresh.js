@@ -0,0 +1,164 @@
+// Sets up React Fast Refresh with the `react-refresh` package of the project.
+// This module is evaluated as a runtime entry, before the modules containing
+// components, which call `$RefreshReg$` and `$RefreshSig$` as inserted by the
+// refresh transform. The runtime calls `$RefreshInterceptModuleExecution$`
+// around the execution of every module and uses `$RefreshHelpers$` to accept
+// HMR updates of modules which only export components.
+
+import * as RefreshRuntime from "react-refresh/runtime";
+
+/** @typedef {import('../types').RefreshHelpers} RefreshHelpers */
+/** @typedef {import('../types').ModuleId} ModuleId */
+/** @typedef {import('../types').Exports} Exports */
+
+RefreshRuntime.injectIntoGlobalHook(self);
+
+// Modules executed outside of the runtime, e. g. other runtime entries, don't
+// register their components.
+self.$RefreshReg$ = () => {};
+self.$RefreshSig$ = () => (type) => type;
+
+/**
+ * Scopes the registrations of the module being executed to its id, so
+ * components of different modules with the same name don't collide.
+ *
+ * @param {ModuleId} moduleId
+ * @returns {() => void}
+ */
+self.$RefreshInterceptModuleExecution$ = (moduleId) => {
+  const prevRefreshReg = self.$RefreshReg$;
+  const prevRefreshSig = self.$RefreshSig$;
+
+  self.$RefreshReg$ = (type, id) => {
+    RefreshRuntime.register(type, moduleId + " " + id);
+  };
+  self.$RefreshSig$ = RefreshRuntime.createSignatureFunctionForTransform;
+
+  return () => {
+    self.$RefreshReg$ = prevRefreshReg;
+    self.$RefreshSig$ = prevRefreshSig;
+  };
+};
+
+/**
+ * Registers the exports of a module, so components which are not declared in
+ * it, e. g. the results of `React.memo`, are refreshed too.
+ *
+ * @param {Exports} moduleExports
+ * @param {ModuleId} moduleId
+ */
+function registerExportsForReactRefresh(moduleExports, moduleId) {
+  RefreshRuntime.register(moduleExports, moduleId + " %exports%");
+  if (moduleExports == null || typeof moduleExports !== "object") {
+    return;
+  }
+  for (const key in moduleExports) {
+    RefreshRuntime.register(
+      moduleExports[key],
+      moduleId + " %exports% " + key
+    );
+  }
+}
+
+/**
+ * A module is a Refresh Boundary when all of its exports are components. Its
+ * updates are applied by re-rendering the components, instead of re-executing
+ * the modules importing it.
+ *
+ * The exports of ES modules are getters of the runtime, which don't have side
+ * effects, so they are read.
+ *
+ * @param {Exports} moduleExports
+ * @returns {boolean}
+ */
+function isReactRefreshBoundary(moduleExports) {
+  if (RefreshRuntime.isLikelyComponentType(moduleExports)) {
+    return true;
+  }
+  if (moduleExports == null || typeof moduleExports !== "object") {
+    return false;
+  }
+  let hasExports = false;
+  let areAllExportsComponents = true;
+  for (const key in moduleExports) {
+    hasExports = true;
+    if (key === "__esModule") {
+      continue;
+    }
+    if (!RefreshRuntime.isLikelyComponentType(moduleExports[key])) {
+      areAllExportsComponents = false;
+    }
+  }
+  return hasExports && areAllExportsComponents;
+}
+
+/**
+ * The signature of a boundary lists the component families of its exports.
+ * Families are kept by the refresh runtime across updates, so it only changes
+ * when exports are added or removed, or when a component is replaced by an
+ * incompatible one, e. g. a class by a function.
+ *
+ * @param {Exports} moduleExports
+ * @returns {any[]}
+ */
+function getRefreshBoundarySignature(moduleExports) {
+  const signature = [RefreshRuntime.getFamilyByType(moduleExports)];
+  if (moduleExports == null || typeof moduleExports !== "object") {
+    return signature;
+  }
+  for (const key in moduleExports) {
+    if (key === "__esModule") {
+      continue;
+    }
+    signature.push(key, RefreshRuntime.getFamilyByType(moduleExports[key]));
+  }
+  return signature;
+}
+
+/**
+ * Whether an update changed the signature of a boundary, so the modules
+ * importing it need to be re-executed.
+ *
+ * @param {Exports} prevExports
+ * @param {Exports} nextExports
+ * @returns {boolean}
+ */
+function shouldInvalidateReactRefreshBoundary(prevExports, nextExports) {
+  const prevSignature = getRefreshBoundarySignature(prevExports);
+  const nextSignature = getRefreshBoundarySignature(nextExports);
+  if (prevSignature.length !== nextSignature.length) {
+    return true;
+  }
+  for (let i = 0; i < nextSignature.length; i++) {
+    if (prevSignature[i] !== nextSignature[i]) {
+      return true;
+    }
+  }
+  return false;
+}
+
+let isUpdateScheduled = false;
+
+/**
+ * Re-renders the updated components. All modules of an HMR update are applied
+ * before, so they're re-rendered once.
+ */
+function scheduleUpdate() {
+  if (isUpdateScheduled) {
+    return;
+  }
+  isUpdateScheduled = true;
+  setTimeout(() => {
+    isUpdateScheduled = false;
+    RefreshRuntime.performReactRefresh();
+  }, 30);
+}
+
+/** @type {RefreshHelpers} */
+self.$RefreshHelpers$ = {
+  registerExportsForReactRefresh,
+  isReactRefreshBoundary,
+  shouldInvalidateReactRefreshBoundary,
+  getRefreshBoundarySignature,
+  scheduleUpdate,
+};
@@ -13,13 +13,16 @@ export interface HotState {
   selfDeclined: boolean;
   selfInvalidated: boolean;
   disposeHandlers: ((data: object) => void)[];
+  acceptedDependencies: Record<ModuleId, AcceptCallback>;
+  acceptedErrorHandlers: Record<ModuleId, AcceptErrorHandler | undefined>;
+  declinedDependencies: Record<ModuleId, true>;
 }
 
 export type AcceptErrorHandler = (
   err: Error,
-  context: { moduleId: ModuleId; dependencyId: string | number }
+  context: { moduleId: ModuleId; dependencyId: ModuleId }
 ) => void;
-export type AcceptCallback = (outdatedDependencies: string[]) => void;
+export type AcceptCallback = (outdatedDependencies: ModuleId[]) => void;
 
 export interface AcceptFunction {
   // accept updates for self
@@ -27,7 +30,7 @@ export interface AcceptFunction {
 
   // accept updates for the given modules
   (
-    modules?: string | string[],
+    modules?: ModuleId | ModuleId[],
     callback?: AcceptCallback,
     errorHandler?: AcceptErrorHandler
   ): void;
@@ -41,7 +44,7 @@ export interface Hot {
 
   accept: AcceptFunction;
 
-  decline: (module?: string | string[]) => void;
+  decline: (module?: ModuleId | ModuleId[]) => void;
 
   dispose: (callback: (data: HotData) => void) => void;
 
@@ -58,9 +58,11 @@ interface TurbopackContext {
   s: EsmExport;
   v: ExportValue;
   m: Module;
+  h: Hot;
   c: ModuleCache;
   l: LoadChunk;
   p: Partial<NodeJS.Process> & Pick<NodeJS.Process, "env">;
+  u: string;
 }
 
 type ModuleFactory = (
@@ -89,6 +91,9 @@ export interface TurbopackGlobals {
   TURBOPACK_CHUNK_UPDATE_LISTENERS?:
     | ChunkUpdateProvider
     | [ChunkPath, UpdateCallback][];
+  TURBOPACK_CHUNK_FILES?: Record<ChunkPath, string>;
+  TURBOPACK_PUBLIC_PATH?: string;
+  TURBOPACK_CHUNK_FORMAT?: "systemjs" | "amd";
 }
 
 declare global {
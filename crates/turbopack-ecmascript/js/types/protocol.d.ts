@@ -65,14 +65,17 @@ export type IssueSource = {
   asset: IssueAsset;
   start: SourcePos;
   end: SourcePos;
+  code_frame: string;
 };
 
 export type Issue = {
   severity: IssueSeverity;
   context: string;
   category: string;
+  code: string;
   title: string;
   description: string;
+  detail: string;
   documentation_link: string;
   source: IssueSource | null;
   sub_issues: Issue[];
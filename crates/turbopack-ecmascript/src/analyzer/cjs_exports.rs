@@ -0,0 +1,329 @@
+use std::collections::BTreeSet;
+
+use swc_core::{
+    common::Mark,
+    ecma::{
+        ast::*,
+        visit::{Visit, VisitWith},
+    },
+};
+
+use crate::utils::unparen;
+
+/// Exports of a CommonJS module which could be detected statically, in the
+/// spirit of `cjs-module-lexer`.
+#[derive(Default, Debug, Clone)]
+pub struct CjsExportsAnalysis {
+    /// Names assigned on `exports` or `module.exports`.
+    pub names: BTreeSet<String>,
+    /// Requests of modules whose exports are re-exported as a whole, e.g.
+    /// `module.exports = require("./impl")`.
+    pub reexports: Vec<String>,
+    /// `true` when some exports could not be analysed statically (e.g.
+    /// `exports[key] = ...` or `module.exports = factory()`), so `names` might
+    /// be incomplete.
+    pub has_dynamic_exports: bool,
+}
+
+/// Detects `exports.foo = ...`, `module.exports.foo = ...`,
+/// `module.exports = { ... }`, `module.exports = require("...")` and
+/// `Object.defineProperty(exports, "foo", ...)` shapes in a program.
+/// Conditional assignments like `module.exports = prod ? a : b` contribute
+/// the exports of both branches.
+pub fn detect_cjs_exports(program: &Program, unresolved_mark: Mark) -> CjsExportsAnalysis {
+    let mut visitor = CjsExportsVisitor {
+        unresolved_mark,
+        exports: CjsExportsAnalysis::default(),
+    };
+    program.visit_with(&mut visitor);
+    visitor.exports
+}
+
+struct CjsExportsVisitor {
+    unresolved_mark: Mark,
+    exports: CjsExportsAnalysis,
+}
+
+impl CjsExportsVisitor {
+    fn is_free_ident(&self, expr: &Expr, name: &str) -> bool {
+        match unparen(expr) {
+            Expr::Ident(ident) => {
+                &*ident.sym == name && ident.span.ctxt.outer() == self.unresolved_mark
+            }
+            _ => false,
+        }
+    }
+
+    /// `module.exports`
+    fn is_module_exports(&self, expr: &Expr) -> bool {
+        match unparen(expr) {
+            Expr::Member(member) => {
+                self.is_free_ident(&member.obj, "module")
+                    && static_prop_name(&member.prop).as_deref() == Some("exports")
+            }
+            _ => false,
+        }
+    }
+
+    /// `exports` or `module.exports`
+    fn is_exports_object(&self, expr: &Expr) -> bool {
+        self.is_free_ident(expr, "exports") || self.is_module_exports(expr)
+    }
+
+    fn add_object_literal(&mut self, obj: &ObjectLit) {
+        for prop in obj.props.iter() {
+            let name = match prop {
+                PropOrSpread::Spread(_) => None,
+                PropOrSpread::Prop(prop) => match &**prop {
+                    Prop::Shorthand(ident) => Some(ident.sym.to_string()),
+                    Prop::KeyValue(KeyValueProp { key, .. })
+                    | Prop::Getter(GetterProp { key, .. })
+                    | Prop::Setter(SetterProp { key, .. })
+                    | Prop::Method(MethodProp { key, .. }) => static_prop_key(key),
+                    Prop::Assign(_) => None,
+                },
+            };
+            self.add_name(name);
+        }
+    }
+
+    fn add_name(&mut self, name: Option<String>) {
+        match name {
+            Some(name) => {
+                self.exports.names.insert(name);
+            }
+            None => self.exports.has_dynamic_exports = true,
+        }
+    }
+
+    /// `require("...")`
+    fn require_request<'a>(&self, expr: &'a Expr) -> Option<&'a str> {
+        match unparen(expr) {
+            Expr::Call(CallExpr {
+                callee: Callee::Expr(callee),
+                args,
+                ..
+            }) if args.len() == 1 && self.is_free_ident(callee, "require") => match &args[0] {
+                ExprOrSpread { spread: None, expr } => match unparen(expr) {
+                    Expr::Lit(Lit::Str(str)) => Some(&*str.value),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The value assigned to `module.exports`.
+    fn add_module_exports_value(&mut self, value: &Expr) {
+        if let Some(request) = self.require_request(value) {
+            self.exports.reexports.push(request.to_string());
+            return;
+        }
+        match unparen(value) {
+            Expr::Object(obj) => self.add_object_literal(obj),
+            Expr::Cond(CondExpr { cons, alt, .. }) => {
+                self.add_module_exports_value(cons);
+                self.add_module_exports_value(alt);
+            }
+            Expr::Assign(AssignExpr {
+                op: AssignOp::Assign,
+                right,
+                ..
+            }) => {
+                // `module.exports = exports = { ... }`
+                self.add_module_exports_value(right);
+            }
+            _ => self.exports.has_dynamic_exports = true,
+        }
+    }
+
+    fn visit_exports_assignment(&mut self, target: &Expr, value: &Expr) {
+        let member = match unparen(target) {
+            Expr::Member(member) => member,
+            _ => return,
+        };
+        if self.is_module_exports(target) {
+            self.add_module_exports_value(value);
+        } else if self.is_exports_object(&member.obj) {
+            self.add_name(static_prop_name(&member.prop));
+        }
+    }
+
+    /// `Object.defineProperty(exports, "foo", ...)` and
+    /// `Object.defineProperties(exports, { ... })`
+    fn visit_object_define(&mut self, call: &CallExpr) {
+        let method = match &call.callee {
+            Callee::Expr(callee) => match unparen(callee) {
+                Expr::Member(member) if self.is_free_ident(&member.obj, "Object") => {
+                    static_prop_name(&member.prop)
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        let (target, arg) = match (call.args.get(0), call.args.get(1)) {
+            (Some(target), Some(arg)) if target.spread.is_none() && arg.spread.is_none() => {
+                (&target.expr, &arg.expr)
+            }
+            _ => return,
+        };
+        if !self.is_exports_object(target) {
+            return;
+        }
+        match method.as_deref() {
+            Some("defineProperty") => {
+                let name = match unparen(arg) {
+                    Expr::Lit(Lit::Str(str)) => Some(str.value.to_string()),
+                    _ => None,
+                };
+                self.add_name(name);
+            }
+            Some("defineProperties") => match unparen(arg) {
+                Expr::Object(obj) => self.add_object_literal(obj),
+                _ => self.exports.has_dynamic_exports = true,
+            },
+            _ => {}
+        }
+    }
+}
+
+impl Visit for CjsExportsVisitor {
+    fn visit_assign_expr(&mut self, assign: &AssignExpr) {
+        if assign.op == AssignOp::Assign {
+            match &assign.left {
+                PatOrExpr::Expr(target) => self.visit_exports_assignment(target, &assign.right),
+                PatOrExpr::Pat(box Pat::Expr(target)) => {
+                    self.visit_exports_assignment(target, &assign.right)
+                }
+                _ => {}
+            }
+        }
+        assign.visit_children_with(self);
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        self.visit_object_define(call);
+        call.visit_children_with(self);
+    }
+}
+
+fn static_prop_name(prop: &MemberProp) -> Option<String> {
+    match prop {
+        MemberProp::Ident(ident) => Some(ident.sym.to_string()),
+        MemberProp::Computed(ComputedPropName { expr, .. }) => match unparen(expr) {
+            Expr::Lit(Lit::Str(str)) => Some(str.value.to_string()),
+            _ => None,
+        },
+        MemberProp::PrivateName(_) => None,
+    }
+}
+
+fn static_prop_key(key: &PropName) -> Option<String> {
+    match key {
+        PropName::Ident(ident) => Some(ident.sym.to_string()),
+        PropName::Str(str) => Some(str.value.to_string()),
+        PropName::Num(num) => Some(num.value.to_string()),
+        PropName::Computed(ComputedPropName { expr, .. }) => match unparen(expr) {
+            Expr::Lit(Lit::Str(str)) => Some(str.value.to_string()),
+            _ => None,
+        },
+        PropName::BigInt(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{errors::HANDLER, FileName, Mark, SourceMap},
+        ecma::{
+            ast::{EsVersion, Program},
+            parser::parse_file_as_script,
+            transforms::base::resolver,
+            visit::VisitMutWith,
+        },
+        testing::run_test,
+    };
+
+    use super::{detect_cjs_exports, CjsExportsAnalysis};
+
+    fn detect(src: &str) -> CjsExportsAnalysis {
+        run_test(false, |cm: std::sync::Arc<SourceMap>, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.to_string());
+            let script = parse_file_as_script(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))
+            .unwrap();
+            let mut program = Program::Script(script);
+            let unresolved_mark = Mark::new();
+            let top_level_mark = Mark::new();
+            program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+            Ok(detect_cjs_exports(&program, unresolved_mark))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn exports_assignments() {
+        let exports = detect(
+            r#"
+            exports.a = 1;
+            module.exports.b = 2;
+            exports["c"] = 3;
+            function f(exports) { exports.notExported = 4; }
+            "#,
+        );
+        assert_eq!(
+            exports.names.iter().map(|s| s.as_str()).collect::<Vec<_>>(),
+            vec!["a", "b", "c"]
+        );
+        assert!(!exports.has_dynamic_exports);
+    }
+
+    #[test]
+    fn module_exports_object() {
+        let exports = detect(
+            r#"
+            const a = 1;
+            module.exports = { a, b: 2, "c": 3, d() {}, __esModule: true };
+            "#,
+        );
+        assert_eq!(
+            exports.names.iter().map(|s| s.as_str()).collect::<Vec<_>>(),
+            vec!["__esModule", "a", "b", "c", "d"]
+        );
+        assert!(!exports.has_dynamic_exports);
+    }
+
+    #[test]
+    fn dynamic_exports() {
+        assert!(detect("exports[key] = 1;").has_dynamic_exports);
+        assert!(detect("module.exports = { ...other };").has_dynamic_exports);
+        assert!(detect("module.exports = factory();").has_dynamic_exports);
+    }
+
+    #[test]
+    fn reassignment_patterns() {
+        let exports = detect(
+            r#"
+            Object.defineProperty(exports, "__esModule", { value: true });
+            Object.defineProperty(module.exports, "a", { get() { return 1; } });
+            Object.defineProperties(exports, { b: { value: 2 } });
+            module.exports = process.env.NODE_ENV === "production"
+                ? require("./prod")
+                : { c: 3 };
+            "#,
+        );
+        assert_eq!(
+            exports.names.iter().map(|s| s.as_str()).collect::<Vec<_>>(),
+            vec!["__esModule", "a", "b", "c"]
+        );
+        assert_eq!(exports.reexports, vec!["./prod".to_string()]);
+        assert!(!exports.has_dynamic_exports);
+    }
+}
@@ -39,6 +39,12 @@ pub enum Effect {
     ImportedBinding {
         esm_reference_index: usize,
         export: Option<String>,
+        /// The property when the binding is only read by a static member
+        /// access, like `styles.button`.
+        member: Option<String>,
+        /// The properties when the binding is only destructured into static
+        /// keys, like `const { a, b } = data`.
+        destructured: Option<Vec<String>>,
         ast_path: Vec<AstParentKind>,
         span: Span,
     },
@@ -46,6 +52,85 @@ pub enum Effect {
         span: Span,
         ast_path: Vec<AstParentKind>,
     },
+    /// A reference to a free variable, e. g. the global `Buffer`.
+    FreeVar {
+        var: JsValue,
+        ast_path: Vec<AstParentKind>,
+        span: Span,
+    },
+    /// An `if` statement or a ternary, whose branches can be removed when
+    /// the condition is known at compile time.
+    Conditional {
+        condition: BranchCondition,
+        kind: ConditionalKind,
+        ast_path: Vec<AstParentKind>,
+        span: Span,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ConditionalKind {
+    If,
+    Ternary,
+}
+
+/// The condition of an `if` statement or a ternary. Comparisons are kept
+/// apart from the compared values, so the condition can be evaluated once
+/// the values are linked.
+#[derive(Debug, Clone)]
+pub enum BranchCondition {
+    /// The branch is taken when the value is truthy.
+    Value(JsValue),
+    Not(Box<BranchCondition>),
+    And(Box<BranchCondition>, Box<BranchCondition>),
+    Or(Box<BranchCondition>, Box<BranchCondition>),
+    /// `left === right`, or `left == right` when `loose`.
+    Equal {
+        left: JsValue,
+        right: JsValue,
+        loose: bool,
+    },
+}
+
+impl BranchCondition {
+    pub fn normalize(&mut self) {
+        for value in self.values_mut() {
+            value.normalize();
+        }
+    }
+
+    /// The values the condition depends on.
+    pub fn values_mut(&mut self) -> Vec<&mut JsValue> {
+        match self {
+            BranchCondition::Value(value) => vec![value],
+            BranchCondition::Not(condition) => condition.values_mut(),
+            BranchCondition::And(left, right) | BranchCondition::Or(left, right) => {
+                let mut values = left.values_mut();
+                values.extend(right.values_mut());
+                values
+            }
+            BranchCondition::Equal { left, right, .. } => vec![left, right],
+        }
+    }
+
+    /// Whether the branch is taken, if it's known at compile time.
+    pub fn evaluate(&self) -> Option<bool> {
+        match self {
+            BranchCondition::Value(value) => value.is_truthy(),
+            BranchCondition::Not(condition) => condition.evaluate().map(|value| !value),
+            BranchCondition::And(left, right) => match left.evaluate()? {
+                true => right.evaluate(),
+                false => Some(false),
+            },
+            BranchCondition::Or(left, right) => match left.evaluate()? {
+                true => Some(true),
+                false => right.evaluate(),
+            },
+            BranchCondition::Equal { left, right, loose } => {
+                left.as_constant()?.equals(&right.as_constant()?, *loose)
+            }
+        }
+    }
 }
 
 impl Effect {
@@ -87,6 +172,8 @@ impl Effect {
             Effect::ImportedBinding {
                 esm_reference_index: _,
                 export: _,
+                member: _,
+                destructured: _,
                 ast_path: _,
                 span: _,
             } => {}
@@ -94,6 +181,33 @@ impl Effect {
                 span: _,
                 ast_path: _,
             } => {}
+            Effect::FreeVar {
+                var,
+                ast_path: _,
+                span: _,
+            } => {
+                var.normalize();
+            }
+            Effect::Conditional {
+                condition,
+                kind: _,
+                ast_path: _,
+                span: _,
+            } => {
+                condition.normalize();
+            }
+        }
+    }
+
+    pub fn ast_path(&self) -> &[AstParentKind] {
+        match self {
+            Effect::Call { ast_path, .. }
+            | Effect::MemberCall { ast_path, .. }
+            | Effect::Member { ast_path, .. }
+            | Effect::ImportedBinding { ast_path, .. }
+            | Effect::ImportMeta { ast_path, .. }
+            | Effect::FreeVar { ast_path, .. }
+            | Effect::Conditional { ast_path, .. } => ast_path,
         }
     }
 }
@@ -414,6 +528,105 @@ impl EvalContext {
             _ => JsValue::Unknown(None, "unsupported expression"),
         }
     }
+
+    /// Evaluates the condition of a branch. Returns `None` when evaluating
+    /// the condition could have side effects, so it can't be removed.
+    pub fn eval_condition(&self, e: &Expr) -> Option<BranchCondition> {
+        Some(match unparen(e) {
+            Expr::Unary(UnaryExpr {
+                op: op!("!"), arg, ..
+            }) => BranchCondition::Not(box self.eval_condition(arg)?),
+            Expr::Bin(BinExpr {
+                op: op!("&&"),
+                left,
+                right,
+                ..
+            }) => BranchCondition::And(
+                box self.eval_condition(left)?,
+                box self.eval_condition(right)?,
+            ),
+            Expr::Bin(BinExpr {
+                op: op!("||"),
+                left,
+                right,
+                ..
+            }) => BranchCondition::Or(
+                box self.eval_condition(left)?,
+                box self.eval_condition(right)?,
+            ),
+            Expr::Bin(BinExpr {
+                op: op @ (op!("===") | op!("==") | op!("!==") | op!("!=")),
+                left,
+                right,
+                ..
+            }) => {
+                if !is_pure(left) || !is_pure(right) {
+                    return None;
+                }
+                let equal = BranchCondition::Equal {
+                    left: self.eval(left),
+                    right: self.eval(right),
+                    loose: matches!(op, op!("==") | op!("!=")),
+                };
+                match op {
+                    op!("!==") | op!("!=") => BranchCondition::Not(box equal),
+                    _ => equal,
+                }
+            }
+            e if is_pure(e) => BranchCondition::Value(self.eval(e)),
+            _ => return None,
+        })
+    }
+}
+
+/// Whether evaluating the expression has no side effects besides reading
+/// properties.
+fn is_pure(e: &Expr) -> bool {
+    match e {
+        Expr::Lit(_) | Expr::Ident(_) => true,
+        Expr::Paren(ParenExpr { expr, .. }) => is_pure(expr),
+        Expr::Member(MemberExpr { obj, prop, .. }) => {
+            is_pure(obj)
+                && match prop {
+                    MemberProp::Ident(_) => true,
+                    MemberProp::Computed(ComputedPropName { expr, .. }) => is_pure(expr),
+                    MemberProp::PrivateName(_) => false,
+                }
+        }
+        Expr::Unary(UnaryExpr { op, arg, .. }) => *op != op!("delete") && is_pure(arg),
+        Expr::Bin(BinExpr { left, right, .. }) => is_pure(left) && is_pure(right),
+        Expr::Tpl(Tpl { exprs, .. }) => exprs.iter().all(|e| is_pure(e)),
+        _ => false,
+    }
+}
+
+/// Whether the statement contains declarations which are hoisted out of it,
+/// so it can't be removed without changing the scope.
+fn has_hoisted_declarations(stmt: &Stmt) -> bool {
+    struct HoistedDeclarations {
+        found: bool,
+    }
+
+    impl Visit for HoistedDeclarations {
+        fn visit_var_decl(&mut self, decl: &VarDecl) {
+            if decl.kind == VarDeclKind::Var {
+                self.found = true;
+            }
+        }
+
+        fn visit_fn_decl(&mut self, _: &FnDecl) {
+            self.found = true;
+        }
+
+        // Declarations in functions are not hoisted out of them
+        fn visit_function(&mut self, _: &Function) {}
+
+        fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+    }
+
+    let mut visitor = HoistedDeclarations { found: false };
+    stmt.visit_with(&mut visitor);
+    visitor.found
 }
 
 struct Analyzer<'a> {
@@ -733,6 +946,18 @@ impl Analyzer<'_> {
             ast_path: as_parent_path(ast_path),
             span: member_expr.span(),
         });
+        // `const { a, b } = obj.prop` reads `obj.prop.a` and `obj.prop.b`
+        if let Some(properties) = destructured_properties(ast_path) {
+            let value = self.eval_context.eval(&Expr::Member(member_expr.clone()));
+            for property in properties {
+                self.data.effects.push(Effect::Member {
+                    obj: value.clone(),
+                    prop: property.into(),
+                    ast_path: as_parent_path(ast_path),
+                    span: member_expr.span(),
+                });
+            }
+        }
     }
 
     fn take_return_values(&mut self) -> Box<JsValue> {
@@ -1096,12 +1321,56 @@ impl VisitAstPath for Analyzer<'_> {
             self.data.effects.push(Effect::ImportedBinding {
                 esm_reference_index,
                 export,
+                member: static_member_read(ast_path),
+                destructured: destructured_properties(ast_path),
+                ast_path: as_parent_path(ast_path),
+                span: ident.span(),
+            })
+        } else if is_unresolved(ident, self.eval_context.unresolved_mark) {
+            self.data.effects.push(Effect::FreeVar {
+                var: self.eval_context.eval(&Expr::Ident(ident.clone())),
                 ast_path: as_parent_path(ast_path),
                 span: ident.span(),
             })
         }
     }
 
+    fn visit_if_stmt<'ast: 'r, 'r>(
+        &mut self,
+        stmt: &'ast IfStmt,
+        ast_path: &mut AstNodePath<AstParentNodeRef<'r>>,
+    ) {
+        let removable = !has_hoisted_declarations(&stmt.cons)
+            && !stmt.alt.as_deref().map_or(false, has_hoisted_declarations);
+        if removable {
+            if let Some(condition) = self.eval_context.eval_condition(&stmt.test) {
+                self.data.effects.push(Effect::Conditional {
+                    condition,
+                    kind: ConditionalKind::If,
+                    ast_path: as_parent_path(ast_path),
+                    span: stmt.span,
+                });
+            }
+        }
+        stmt.visit_children_with_path(self, ast_path);
+    }
+
+    fn visit_cond_expr<'ast: 'r, 'r>(
+        &mut self,
+        expr: &'ast CondExpr,
+        ast_path: &mut AstNodePath<AstParentNodeRef<'r>>,
+    ) {
+        if let Some(condition) = self.eval_context.eval_condition(&expr.test) {
+            self.data.effects.push(Effect::Conditional {
+                condition,
+                kind: ConditionalKind::Ternary,
+                ast_path: as_parent_path(ast_path),
+                span: expr.span,
+            });
+        }
+        expr.visit_children_with_path(self, ast_path);
+    }
+
     fn visit_meta_prop_expr<'ast: 'r, 'r>(
         &mut self,
         expr: &'ast MetaPropExpr,
@@ -1118,6 +1387,71 @@ impl VisitAstPath for Analyzer<'_> {
     }
 }
 
+/// Returns the property of the member expression the identifier at `ast_path`
+/// is the object of, when the property is static and the member is only read.
+fn static_member_read(ast_path: &AstNodePath<AstParentNodeRef<'_>>) -> Option<String> {
+    let mut parents = ast_path.iter().rev().skip(1);
+    let prop = match parents.next()? {
+        AstParentNodeRef::MemberExpr(member_expr, MemberExprField::Obj) => {
+            match &member_expr.prop {
+                MemberProp::Ident(ident) => ident.sym.to_string(),
+                MemberProp::Computed(ComputedPropName {
+                    expr: box Expr::Lit(Lit::Str(str)),
+                    ..
+                }) => str.value.to_string(),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    if !matches!(
+        parents.next()?,
+        AstParentNodeRef::Expr(_, ExprField::Member)
+    ) {
+        return None;
+    }
+    // Assignments, updates and deletes write to the member
+    match parents.next() {
+        Some(
+            AstParentNodeRef::PatOrExpr(..)
+            | AstParentNodeRef::Pat(..)
+            | AstParentNodeRef::UpdateExpr(..),
+        ) => None,
+        Some(AstParentNodeRef::UnaryExpr(unary_expr, _)) if unary_expr.op == op!("delete") => None,
+        _ => Some(prop),
+    }
+}
+
+/// Returns the keys of the object pattern the identifier at `ast_path` is
+/// destructured into, when it's the initializer of a variable declaration and
+/// all keys are static. Rest patterns read all properties.
+fn destructured_properties(ast_path: &AstNodePath<AstParentNodeRef<'_>>) -> Option<Vec<String>> {
+    let mut parents = ast_path.iter().rev().skip(1);
+    let object_pat = match parents.next()? {
+        AstParentNodeRef::VarDeclarator(
+            VarDeclarator {
+                name: Pat::Object(object_pat),
+                ..
+            },
+            VarDeclaratorField::Init,
+        ) => object_pat,
+        _ => return None,
+    };
+    object_pat
+        .props
+        .iter()
+        .map(|prop| match prop {
+            ObjectPatProp::KeyValue(KeyValuePatProp { key, .. }) => match key {
+                PropName::Ident(ident) => Some(ident.sym.to_string()),
+                PropName::Str(str) => Some(str.value.to_string()),
+                _ => None,
+            },
+            ObjectPatProp::Assign(AssignPatProp { key, .. }) => Some(key.sym.to_string()),
+            ObjectPatProp::Rest(_) => None,
+        })
+        .collect()
+}
+
 impl<'a> Analyzer<'a> {
     fn visit_pat_with_value<'ast: 'r, 'r>(
         &mut self,
@@ -146,6 +146,17 @@ impl ImportMap {
         self.reexports.iter().map(|(i, r)| (*i, r))
     }
 
+    /// Returns true when the reference at index `i` is only used by named
+    /// imports, i. e. it's neither imported as a namespace nor re-exported.
+    pub fn is_named_imports_only(&self, i: usize) -> bool {
+        !self.namespace_imports.values().any(|j| *j == i)
+            && !self.reexports.iter().any(|(j, _)| *j == i)
+            && !self
+                .imports
+                .values()
+                .any(|(j, sym)| *j == i && &**sym == "__turbopack_module_id__")
+    }
+
     /// Analyze ES import
     pub(super) fn analyze(m: &Program) -> Self {
         let mut data = ImportMap::default();
@@ -23,6 +23,7 @@ use self::imports::ImportAnnotations;
 pub(crate) use self::imports::ImportMap;
 
 pub mod builtin;
+pub mod cjs_exports;
 pub mod graph;
 pub mod imports;
 pub mod linker;
@@ -92,6 +93,39 @@ impl ConstantValue {
             _ => None,
         }
     }
+
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Self::Undefined | Self::False | Self::Null => false,
+            Self::True | Self::Regex(..) => true,
+            Self::StrWord(s) => !s.is_empty(),
+            Self::StrAtom(s) => !s.is_empty(),
+            Self::Num(ConstantNumber(n)) => *n != 0.0 && !n.is_nan(),
+            Self::BigInt(n) => *n != BigInt::from(0),
+        }
+    }
+
+    /// Compares the values like `===`, or like `==` when `loose`. Returns
+    /// `None` when the result depends on how the values are coerced.
+    pub fn equals(&self, other: &Self, loose: bool) -> Option<bool> {
+        if let (Some(left), Some(right)) = (self.as_str(), other.as_str()) {
+            return Some(left == right);
+        }
+        Some(match (self, other) {
+            (Self::Num(ConstantNumber(left)), Self::Num(ConstantNumber(right))) => left == right,
+            (Self::BigInt(left), Self::BigInt(right)) => left == right,
+            (Self::True | Self::False, Self::True | Self::False) => {
+                self.is_truthy() == other.is_truthy()
+            }
+            (Self::Undefined, Self::Undefined) | (Self::Null, Self::Null) => true,
+            (Self::Undefined, Self::Null) | (Self::Null, Self::Undefined) => loose,
+            // Regular expression literals create a new object each time
+            (Self::Regex(..), _) | (_, Self::Regex(..)) if !loose => false,
+            (Self::Undefined | Self::Null, _) | (_, Self::Undefined | Self::Null) => false,
+            _ if loose => return None,
+            _ => false,
+        })
+    }
 }
 
 impl Default for ConstantValue {
@@ -858,6 +892,10 @@ impl JsValue {
                         "process",
                         "The Node.js process module: https://nodejs.org/api/process.html",
                     ),
+                    WellKnownObjectKind::NodeProcessEnv => (
+                        "process.env",
+                        "The Node.js process.env property: https://nodejs.org/api/process.html#processenv",
+                    ),
                     WellKnownObjectKind::NodePreGyp => (
                         "@mapbox/node-pre-gyp",
                         "The Node.js @mapbox/node-pre-gyp module: https://github.com/mapbox/node-pre-gyp",
@@ -907,6 +945,7 @@ impl JsValue {
                     ),
                     WellKnownFunctionKind::Require => ("require".to_string(), "The require method from CommonJS"),
                     WellKnownFunctionKind::RequireResolve => ("require.resolve".to_string(), "The require.resolve method from CommonJS"),
+                    WellKnownFunctionKind::RequireEnsure => ("require.ensure".to_string(), "The require.ensure method from webpack"),
                     WellKnownFunctionKind::Define => ("define".to_string(), "The define method from AMD"),
                     WellKnownFunctionKind::FsReadMethod(name) => (
                         format!("fs.{name}"),
@@ -1430,6 +1469,38 @@ impl JsValue {
         }
     }
 
+    /// Whether the value is truthy, if it's known at compile time.
+    pub fn is_truthy(&self) -> Option<bool> {
+        match self {
+            JsValue::Constant(value) => Some(value.is_truthy()),
+            JsValue::FreeVar(FreeVarKind::Other(name)) if &**name == "undefined" => Some(false),
+            JsValue::Array(..)
+            | JsValue::Object(..)
+            | JsValue::Url(..)
+            | JsValue::Function(..)
+            | JsValue::WellKnownObject(_)
+            | JsValue::WellKnownFunction(_) => Some(true),
+            JsValue::Alternatives(_, values) => {
+                let mut values = values.iter().map(|value| value.is_truthy());
+                let first = values.next()??;
+                values.all(|value| value == Some(first)).then_some(first)
+            }
+            _ => None,
+        }
+    }
+
+    /// The constant value, where `undefined` is constant unless it's
+    /// shadowed.
+    pub fn as_constant(&self) -> Option<ConstantValue> {
+        match self {
+            JsValue::Constant(value) => Some(value.clone()),
+            JsValue::FreeVar(FreeVarKind::Other(name)) if &**name == "undefined" => {
+                Some(ConstantValue::Undefined)
+            }
+            _ => None,
+        }
+    }
+
     pub fn is_string(&self) -> bool {
         match self {
             JsValue::Constant(ConstantValue::StrWord(..))
@@ -1859,6 +1930,22 @@ pub enum FreeVarKind {
     Other(JsWord),
 }
 
+impl FreeVarKind {
+    /// The name of the free variable in the source.
+    pub fn name(&self) -> &str {
+        match self {
+            FreeVarKind::Object => "Object",
+            FreeVarKind::Dirname => "__dirname",
+            FreeVarKind::Filename => "__filename",
+            FreeVarKind::Require => "require",
+            FreeVarKind::Define => "define",
+            FreeVarKind::Import => "import",
+            FreeVarKind::NodeProcess => "process",
+            FreeVarKind::Other(name) => name,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum WellKnownObjectKind {
     GlobalObject,
@@ -1874,6 +1961,7 @@ pub enum WellKnownObjectKind {
     OsModule,
     OsModuleDefault,
     NodeProcess,
+    NodeProcessEnv,
     NodePreGyp,
     NodeExpressApp,
     NodeProtobufLoader,
@@ -1890,6 +1978,9 @@ pub enum WellKnownFunctionKind {
     Import,
     Require,
     RequireResolve,
+    /// `require.ensure` from webpack, which loads its dependencies in a
+    /// separate chunk.
+    RequireEnsure,
     Define,
     FsReadMethod(JsWord),
     PathToFileUrl,
@@ -1995,7 +2086,7 @@ mod tests {
     use super::{
         graph::{create_graph, EvalContext},
         linker::{link, LinkCache},
-        JsValue,
+        ConstantNumber, ConstantValue, JsValue,
     };
 
     #[fixture("tests/analyzer/graph/**/input.js")]
@@ -2142,4 +2233,38 @@ mod tests {
         })
         .unwrap();
     }
+
+    #[test]
+    fn constant_equality() {
+        let num = |n: f64| ConstantValue::Num(ConstantNumber(n));
+        let str = |s: &str| ConstantValue::StrWord(s.into());
+
+        // `null == undefined`, but `null !== undefined`
+        assert_eq!(
+            ConstantValue::Null.equals(&ConstantValue::Undefined, true),
+            Some(true)
+        );
+        assert_eq!(
+            ConstantValue::Null.equals(&ConstantValue::Undefined, false),
+            Some(false)
+        );
+        assert_eq!(ConstantValue::Null.equals(&num(0.0), true), Some(false));
+
+        // NaN is not equal to itself, 0 and -0 are equal
+        assert_eq!(num(f64::NAN).equals(&num(f64::NAN), true), Some(false));
+        assert_eq!(num(f64::NAN).equals(&num(f64::NAN), false), Some(false));
+        assert_eq!(num(0.0).equals(&num(-0.0), false), Some(true));
+
+        // `"1" == 1` depends on the coercion, `"1" !== 1`
+        assert_eq!(str("1").equals(&num(1.0), true), None);
+        assert_eq!(str("1").equals(&num(1.0), false), Some(false));
+        assert_eq!(
+            str("production").equals(&str("production"), false),
+            Some(true)
+        );
+        assert_eq!(
+            str("production").equals(&str("development"), true),
+            Some(false)
+        );
+    }
 }
@@ -360,6 +360,9 @@ pub fn well_known_function_member(kind: WellKnownFunctionKind, prop: JsValue) ->
         (WellKnownFunctionKind::Require, Some("resolve")) => {
             JsValue::WellKnownFunction(WellKnownFunctionKind::RequireResolve)
         }
+        (WellKnownFunctionKind::Require, Some("ensure")) => {
+            JsValue::WellKnownFunction(WellKnownFunctionKind::RequireEnsure)
+        }
         (WellKnownFunctionKind::Require, Some("cache")) => {
             JsValue::WellKnownObject(WellKnownObjectKind::RequireCache)
         }
@@ -402,6 +405,7 @@ pub async fn well_known_object_member(
             os_module_member(kind, prop)
         }
         WellKnownObjectKind::NodeProcess => node_process_member(prop, environment).await?,
+        WellKnownObjectKind::NodeProcessEnv => node_process_env_member(prop, environment).await?,
         WellKnownObjectKind::NodePreGyp => node_pre_gyp(prop),
         WellKnownObjectKind::NodeExpressApp => express(prop),
         WellKnownObjectKind::NodeProtobufLoader => protobuf_loader(prop),
@@ -539,10 +543,20 @@ fn os_module_member(kind: WellKnownObjectKind, prop: JsValue) -> JsValue {
 }
 
 async fn node_process_member(prop: JsValue, environment: EnvironmentVc) -> Result<JsValue> {
+    if prop.as_str() != Some("env") && !*environment.node_globals().await? {
+        return Ok(JsValue::Unknown(
+            Some(Arc::new(JsValue::member(
+                box JsValue::WellKnownObject(WellKnownObjectKind::NodeProcess),
+                box prop,
+            ))),
+            "only process.env is available in this environment",
+        ));
+    }
     Ok(match prop.as_str() {
         Some("arch") => environment.compile_target().await?.arch.as_str().into(),
         Some("platform") => environment.compile_target().await?.platform.as_str().into(),
         Some("cwd") => JsValue::WellKnownFunction(WellKnownFunctionKind::ProcessCwd),
+        Some("env") => JsValue::WellKnownObject(WellKnownObjectKind::NodeProcessEnv),
         _ => JsValue::Unknown(
             Some(Arc::new(JsValue::member(
                 box JsValue::WellKnownObject(WellKnownObjectKind::NodeProcess),
@@ -553,6 +567,28 @@ async fn node_process_member(prop: JsValue, environment: EnvironmentVc) -> Resul
     })
 }
 
+async fn node_process_env_member(prop: JsValue, environment: EnvironmentVc) -> Result<JsValue> {
+    let unknown = |reason| {
+        JsValue::Unknown(
+            Some(Arc::new(JsValue::member(
+                box JsValue::WellKnownObject(WellKnownObjectKind::NodeProcessEnv),
+                box prop.clone(),
+            ))),
+            reason,
+        )
+    };
+    Ok(match prop.as_str() {
+        Some(name) => match &*environment.read_defined_env(name).await? {
+            Some(value) => value.as_str().into(),
+            None if *environment.runtime_env().await? => {
+                unknown("process.env variable is only known at runtime")
+            }
+            None => unknown("process.env variable is not defined for this environment"),
+        },
+        None => unknown("dynamic property on process.env"),
+    })
+}
+
 fn node_pre_gyp(prop: JsValue) -> JsValue {
     match prop.as_str() {
         Some("find") => JsValue::WellKnownFunction(WellKnownFunctionKind::NodePreGypFind),
@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+
+use anyhow::Result;
+use indexmap::{IndexMap, IndexSet};
+use turbo_tasks::TryJoinIterExt;
+use turbopack_core::chunk::{
+    ChunkItem, ChunkableAssetReferenceVc, ChunkingContextVc, ChunkingType,
+};
+
+use super::{EcmascriptChunkPlaceableVc, EcmascriptChunkPlaceablesVc, EcmascriptChunkVc};
+
+/// The common chunks of the async chunks imported by a chunk, keyed by the
+/// module which is imported asynchronously.
+#[turbo_tasks::value(transparent)]
+pub(super) struct AsyncCommonChunks(IndexMap<EcmascriptChunkPlaceableVc, Vec<EcmascriptChunkVc>>);
+
+pub(super) struct ReachableModules {
+    /// Modules which are loaded together with the entry.
+    pub(super) loaded: IndexSet<EcmascriptChunkPlaceableVc>,
+    /// Modules which are imported asynchronously by the loaded modules.
+    pub(super) async_imports: IndexSet<EcmascriptChunkPlaceableVc>,
+}
+
+pub(super) async fn reachable_modules(
+    context: ChunkingContextVc,
+    entry: EcmascriptChunkPlaceableVc,
+) -> Result<ReachableModules> {
+    let entry = entry.resolve().await?;
+    let mut loaded = IndexSet::new();
+    loaded.insert(entry);
+    let mut async_imports = IndexSet::new();
+    let mut queue = VecDeque::from([entry]);
+    while let Some(placeable) = queue.pop_front() {
+        for (module, is_async) in referenced_modules(context, placeable).await? {
+            if is_async {
+                async_imports.insert(module);
+            } else if loaded.insert(module) {
+                queue.push_back(module);
+            }
+        }
+    }
+    Ok(ReachableModules {
+        loaded,
+        async_imports,
+    })
+}
+
+/// The modules `placeable` references, and whether they are imported
+/// asynchronously.
+pub(super) async fn referenced_modules(
+    context: ChunkingContextVc,
+    placeable: EcmascriptChunkPlaceableVc,
+) -> Result<Vec<(EcmascriptChunkPlaceableVc, bool)>> {
+    let mut modules = Vec::new();
+    for reference in placeable.as_chunk_item(context).references().await?.iter() {
+        let chunking_type = match ChunkableAssetReferenceVc::resolve_from(reference).await? {
+            Some(reference) => *reference.chunking_type(context).await?,
+            None => None,
+        };
+        let is_async = match chunking_type {
+            Some(
+                ChunkingType::Placed | ChunkingType::PlacedOrParallel | ChunkingType::Parallel,
+            ) => false,
+            Some(ChunkingType::SeparateAsync) => true,
+            Some(ChunkingType::Separate) | None => continue,
+        };
+        for asset in reference.resolve_reference().primary_assets().await?.iter() {
+            if let Some(module) = EcmascriptChunkPlaceableVc::resolve_from(asset).await? {
+                modules.push((module, is_async));
+            }
+        }
+    }
+    Ok(modules)
+}
+
+/// Extracts the modules which are loaded by multiple async chunks imported by
+/// the chunk with `parent_entry` into common chunks, according to the
+/// [CommonChunkOptions] of the context.
+///
+/// Modules are grouped by the async chunks loading them, so every common
+/// chunk is only loaded by async chunks which need all of its modules.
+///
+/// [CommonChunkOptions]: turbopack_core::chunk::CommonChunkOptions
+#[turbo_tasks::function]
+pub(super) async fn async_common_chunks(
+    context: ChunkingContextVc,
+    parent_entry: EcmascriptChunkPlaceableVc,
+) -> Result<AsyncCommonChunksVc> {
+    let options = match *context.common_chunk_options().await? {
+        Some(options) => options,
+        None => return Ok(AsyncCommonChunksVc::cell(IndexMap::new())),
+    };
+    let parent = reachable_modules(context, parent_entry).await?;
+    let async_imports = parent.async_imports.into_iter().collect::<Vec<_>>();
+
+    // The async chunks loading each module, except for modules loaded by the
+    // parent already
+    let mut importers_by_module = IndexMap::<_, Vec<usize>>::new();
+    for (i, &import) in async_imports.iter().enumerate() {
+        for module in reachable_modules(context, import).await?.loaded {
+            if !parent.loaded.contains(&module) {
+                importers_by_module.entry(module).or_default().push(i);
+            }
+        }
+    }
+    let mut modules_by_importers = IndexMap::<_, Vec<_>>::new();
+    for (module, importers) in importers_by_module {
+        if importers.len() >= options.min_async_chunks {
+            modules_by_importers
+                .entry(importers)
+                .or_default()
+                .push(module);
+        }
+    }
+
+    let mut groups = modules_by_importers
+        .into_iter()
+        .map(|(importers, modules)| async move {
+            let sizes = modules
+                .iter()
+                .map(|module| async move {
+                    Ok(module
+                        .as_chunk_item(context)
+                        .content()
+                        .await?
+                        .inner_code
+                        .len())
+                })
+                .try_join()
+                .await?;
+            Ok((importers, modules, sizes.into_iter().sum::<usize>()))
+        })
+        .try_join()
+        .await?;
+    groups.retain(|&(_, _, size)| size >= options.min_size);
+    // Larger common chunks save more, so they are preferred when the number of
+    // requests is limited. The sort is stable, which keeps it deterministic.
+    groups.sort_by(|(_, _, a), (_, _, b)| b.cmp(a));
+
+    let mut requests = vec![1; async_imports.len()];
+    let mut common_chunks = IndexMap::<_, Vec<_>>::new();
+    for (importers, modules, _) in groups {
+        if importers
+            .iter()
+            .any(|&i| requests[i] >= options.max_parallel_requests)
+        {
+            // The modules stay in the async chunks
+            continue;
+        }
+        let chunk = EcmascriptChunkVc::new_normalized(
+            context,
+            EcmascriptChunkPlaceablesVc::cell(modules),
+            Some(EcmascriptChunkPlaceablesVc::cell(vec![parent_entry])),
+            None,
+        );
+        for i in importers {
+            requests[i] += 1;
+            common_chunks
+                .entry(async_imports[i])
+                .or_default()
+                .push(chunk);
+        }
+    }
+    Ok(AsyncCommonChunksVc::cell(common_chunks))
+}
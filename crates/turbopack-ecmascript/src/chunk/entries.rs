@@ -0,0 +1,185 @@
+use anyhow::{bail, Result};
+use indexmap::{IndexMap, IndexSet};
+use turbo_tasks::TryJoinIterExt;
+use turbopack_core::chunk::{resource_hints::EntryChunkGroupsVc, ChunkGroupVc, ChunkingContextVc};
+
+use super::{
+    code_size, common::reachable_modules, EcmascriptChunkPlaceableVc, EcmascriptChunkPlaceablesVc,
+    EcmascriptChunkVc,
+};
+
+/// A named entry of a compilation with several entries.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct EcmascriptEntry {
+    /// The name of the entry, which the chunk group is keyed by.
+    pub name: String,
+    pub module: EcmascriptChunkPlaceableVc,
+    /// A template for the path of the entry chunk relative to the output
+    /// root, e. g. `js/[name].js`. `[name]` is replaced with the name of the
+    /// entry. The path is derived from the path of the module when this is
+    /// `None`.
+    pub filename: Option<String>,
+}
+
+/// Controls the extraction of modules which are loaded by multiple entries
+/// into shared chunks. The shared chunks are loaded in parallel to the chunks
+/// of the entries using them.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord)]
+pub struct SharedChunkOptions {
+    /// Modules are only extracted when at least this many entries load them.
+    pub min_entries: usize,
+    /// Shared chunks with less code than this (in bytes) are not extracted,
+    /// their modules stay in the chunks of the entries instead.
+    pub min_size: usize,
+}
+
+impl Default for SharedChunkOptions {
+    fn default() -> Self {
+        Self {
+            min_entries: 2,
+            min_size: 0,
+        }
+    }
+}
+
+/// The entries of a compilation.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct EcmascriptEntries {
+    pub entries: Vec<EcmascriptEntry>,
+    /// Whether modules shared by the entries are extracted into shared
+    /// chunks, and how. Otherwise, every entry chunk contains all modules it
+    /// loads.
+    pub shared_chunks: Option<SharedChunkOptions>,
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptEntriesVc {
+    /// Creates the chunk groups of the entries, keyed by the name of the
+    /// entry. The chunk of each entry evaluates `runtime_entries` followed by
+    /// the module of the entry.
+    ///
+    /// Modules are grouped by the entries loading them, so every shared chunk
+    /// is only loaded by entries which need all of its modules.
+    #[turbo_tasks::function]
+    pub async fn chunk_groups(
+        self,
+        context: ChunkingContextVc,
+        runtime_entries: Option<EcmascriptChunkPlaceablesVc>,
+    ) -> Result<EntryChunkGroupsVc> {
+        let this = self.await?;
+        let mut names = IndexSet::new();
+        for entry in this.entries.iter() {
+            if !names.insert(&entry.name) {
+                bail!("there are multiple entries named {}", entry.name);
+            }
+        }
+
+        let shared_chunks = match this.shared_chunks {
+            Some(options) => {
+                shared_chunks(context, &this.entries, runtime_entries, options).await?
+            }
+            None => vec![Vec::new(); this.entries.len()],
+        };
+
+        let mut chunk_groups = IndexMap::new();
+        for (entry, shared_chunks) in this.entries.iter().zip(shared_chunks) {
+            let chunk = EcmascriptChunkVc::new_entry(
+                context,
+                entry.module,
+                runtime_entries,
+                shared_chunks,
+                entry
+                    .filename
+                    .as_ref()
+                    .map(|template| render_filename(template, &entry.name)),
+            );
+            chunk_groups.insert(entry.name.clone(), ChunkGroupVc::from_chunk(chunk.into()));
+        }
+        Ok(EntryChunkGroupsVc::cell(chunk_groups))
+    }
+}
+
+/// Returns the shared chunks loaded by each of `entries`.
+async fn shared_chunks(
+    context: ChunkingContextVc,
+    entries: &[EcmascriptEntry],
+    runtime_entries: Option<EcmascriptChunkPlaceablesVc>,
+    options: SharedChunkOptions,
+) -> Result<Vec<Vec<EcmascriptChunkVc>>> {
+    let runtime_entries: Vec<EcmascriptChunkPlaceableVc> = match runtime_entries {
+        Some(runtime_entries) => runtime_entries.await?.iter().copied().collect(),
+        None => Vec::new(),
+    };
+
+    // The entries loading each module
+    let mut importers_by_module = IndexMap::<_, Vec<usize>>::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let mut loaded = IndexSet::new();
+        for &module in runtime_entries.iter().chain([&entry.module]) {
+            loaded.extend(reachable_modules(context, module).await?.loaded);
+        }
+        for module in loaded {
+            importers_by_module.entry(module).or_default().push(i);
+        }
+    }
+    let mut modules_by_importers = IndexMap::<_, Vec<_>>::new();
+    for (module, importers) in importers_by_module {
+        if importers.len() >= options.min_entries {
+            modules_by_importers
+                .entry(importers)
+                .or_default()
+                .push(module);
+        }
+    }
+
+    let groups = modules_by_importers
+        .into_iter()
+        .map(|(importers, modules)| async move {
+            let size =
+                code_size(modules.iter().map(|module| module.as_chunk_item(context))).await?;
+            Ok((importers, modules, size))
+        })
+        .try_join()
+        .await?;
+
+    let mut shared_chunks = vec![Vec::new(); entries.len()];
+    for (importers, modules, size) in groups {
+        if size < options.min_size {
+            // The modules stay in the chunks of the entries
+            continue;
+        }
+        let chunk = EcmascriptChunkVc::new_normalized(
+            context,
+            EcmascriptChunkPlaceablesVc::cell(modules),
+            None,
+            None,
+        );
+        for i in importers {
+            shared_chunks[i].push(chunk);
+        }
+    }
+    Ok(shared_chunks)
+}
+
+/// Replaces the `[name]` placeholders of a filename template.
+fn render_filename(template: &str, name: &str) -> String {
+    template.replace("[name]", name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_filename;
+
+    #[test]
+    fn filenames() {
+        assert_eq!(render_filename("[name].js", "main"), "main.js");
+        assert_eq!(
+            render_filename("js/[name]/[name].entry.js", "admin"),
+            "js/admin/admin.entry.js"
+        );
+        assert_eq!(render_filename("bundle.js", "main"), "bundle.js");
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use turbopack_core::chunk::{
+    ChunkItem, ChunkableAssetReferenceVc, ChunkingContextVc, ChunkingType, ModuleIdsVc,
+};
+
+use super::{EcmascriptChunkPlaceableVc, EcmascriptChunkPlaceablesVc};
+
+/// Returns the ids of all modules which are loaded together with `entries`,
+/// in the order they need to be evaluated in: every module comes after the
+/// modules it imports, and the entries come in the order they are listed, each
+/// after its own dependencies. Modules in an import cycle are ordered by the
+/// first import reaching them. Modules in separately loaded chunk groups (e. g.
+/// dynamic imports) are not included.
+#[turbo_tasks::function]
+pub(super) async fn module_evaluation_order(
+    context: ChunkingContextVc,
+    entries: EcmascriptChunkPlaceablesVc,
+) -> Result<ModuleIdsVc> {
+    let mut visited = HashSet::new();
+    let mut order = Vec::new();
+    for entry in entries.await?.iter() {
+        let entry = entry.resolve().await?;
+        if !visited.insert(entry) {
+            continue;
+        }
+        let mut stack = vec![(
+            entry,
+            loaded_dependencies(context, entry).await?.into_iter(),
+        )];
+        // Depth-first post-order, so dependencies are evaluated before their
+        // dependents
+        while let Some((placeable, dependencies)) = stack.last_mut() {
+            if let Some(dependency) = dependencies.next() {
+                if visited.insert(dependency) {
+                    let dependencies = loaded_dependencies(context, dependency).await?;
+                    stack.push((dependency, dependencies.into_iter()));
+                }
+            } else {
+                order.push(placeable.as_chunk_item(context).id());
+                stack.pop();
+            }
+        }
+    }
+    Ok(ModuleIdsVc::cell(order))
+}
+
+/// The modules referenced by `placeable` which are loaded together with it, in
+/// reference order.
+async fn loaded_dependencies(
+    context: ChunkingContextVc,
+    placeable: EcmascriptChunkPlaceableVc,
+) -> Result<Vec<EcmascriptChunkPlaceableVc>> {
+    let mut dependencies = Vec::new();
+    for reference in placeable.as_chunk_item(context).references().await?.iter() {
+        let chunking_type = match ChunkableAssetReferenceVc::resolve_from(reference).await? {
+            Some(reference) => *reference.chunking_type(context).await?,
+            None => None,
+        };
+        if !matches!(
+            chunking_type,
+            Some(ChunkingType::Placed | ChunkingType::PlacedOrParallel | ChunkingType::Parallel)
+        ) {
+            continue;
+        }
+        for asset in reference.resolve_reference().primary_assets().await?.iter() {
+            if let Some(dependency) = EcmascriptChunkPlaceableVc::resolve_from(asset).await? {
+                dependencies.push(dependency);
+            }
+        }
+    }
+    Ok(dependencies)
+}
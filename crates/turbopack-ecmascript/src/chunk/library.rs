@@ -0,0 +1,884 @@
+//! Libraries, which are built from an entry module to be imported by other
+//! projects instead of being loaded by a page.
+//!
+//! The code of a library is the code of a chunk containing the entry and the
+//! runtime, wrapped into a module of the [LibraryFormat] which exports the
+//! exports of the entry. The runtime registers the chunk on a `self` of its
+//! own, so libraries don't interfere with each other or with an app built
+//! with turbopack. External modules are imported by the wrapper, so
+//! libraries don't need to bundle their peer dependencies.
+//!
+//! Libraries can be emitted as a single file per entry, or with a file per
+//! module of the project, see [preserved_modules].
+
+use std::{
+    collections::VecDeque,
+    fmt::{Display, Write as _},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use indexmap::{IndexMap, IndexSet};
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::{File, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    chunk::{
+        ChunkFormat, ChunkGroupReferenceVc, ChunkGroupVc, ChunkItem, ChunkReferenceVc,
+        ChunkableAssetVc, ChunkingContextVc, FromChunkableAsset,
+    },
+    code_builder::Code,
+    reference::{AssetReference, AssetReferencesVc, SingleAssetReferenceVc},
+    resolve::{ResolveResult, SpecialType},
+};
+
+use super::{
+    common::{reachable_modules, referenced_modules},
+    ecmascript_chunk_content,
+    minify::minify,
+    runtime::runtime_code,
+    EcmascriptChunkContentEntryVc, EcmascriptChunkEvaluate, EcmascriptChunkItemVc,
+    EcmascriptChunkPlaceableVc, EcmascriptChunkPlaceablesVc, EcmascriptChunkVc, EcmascriptExports,
+};
+use crate::{
+    references::esm::export::expand_star_exports,
+    utils::{stringify_module_id, stringify_str},
+};
+
+/// The module format a library is emitted in.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum LibraryFormat {
+    /// A CommonJS module, which assigns the exports of the entry to
+    /// `module.exports`.
+    Cjs,
+    /// An ES module, which has the exports of the entry as named exports.
+    Esm,
+    /// A UMD module, which is loaded as an AMD module or as a CommonJS module,
+    /// or assigns the exports of the entry to a global variable otherwise.
+    Umd,
+}
+
+impl LibraryFormat {
+    pub const ALL: [LibraryFormat; 3] =
+        [LibraryFormat::Cjs, LibraryFormat::Esm, LibraryFormat::Umd];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LibraryFormat::Cjs => "cjs",
+            LibraryFormat::Esm => "esm",
+            LibraryFormat::Umd => "umd",
+        }
+    }
+
+    /// The extension of the files of the format, including the dot. ES
+    /// modules use `.mjs`, so Node.js loads them as ES modules regardless of
+    /// the package.json.
+    pub fn extension(self) -> &'static str {
+        match self {
+            LibraryFormat::Cjs | LibraryFormat::Umd => ".js",
+            LibraryFormat::Esm => ".mjs",
+        }
+    }
+}
+
+impl Default for LibraryFormat {
+    fn default() -> Self {
+        LibraryFormat::Esm
+    }
+}
+
+impl Display for LibraryFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for LibraryFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match LibraryFormat::ALL
+            .into_iter()
+            .find(|format| format.name() == s)
+        {
+            Some(format) => Ok(format),
+            None => bail!(
+                "unknown library format \"{s}\", expected one of {}",
+                LibraryFormat::ALL.map(|format| format.name()).join(", ")
+            ),
+        }
+    }
+}
+
+/// The options shared by the libraries of a build.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct EcmascriptLibraryOptions {
+    pub format: LibraryFormat,
+    /// The global variable UMD libraries assign the exports to. Required for
+    /// [LibraryFormat::Umd].
+    pub name: Option<String>,
+    /// The global variables UMD libraries read external modules from, keyed
+    /// by the request. Externals without a global are read from the global
+    /// variable named like the request.
+    pub globals: IndexMap<String, String>,
+}
+
+/// The files preserved modules are emitted to, see [preserved_modules].
+#[turbo_tasks::value(transparent)]
+pub struct PreservedModules(IndexMap<EcmascriptChunkPlaceableVc, FileSystemPathVc>);
+
+/// Maps the modules of the project which are reachable from `entries` to the
+/// files they are emitted to when modules are preserved, like the `src`
+/// directory of a package is compiled to its `dist` directory.
+///
+/// The files mirror the path of the modules relative to `root`, or relative
+/// to `project_root` for modules outside of `root`, within `output_root`.
+/// Modules outside of `project_root` are not preserved, they are placed into
+/// the files of the modules importing them.
+#[turbo_tasks::function]
+pub async fn preserved_modules(
+    context: ChunkingContextVc,
+    entries: EcmascriptChunkPlaceablesVc,
+    project_root: FileSystemPathVc,
+    root: FileSystemPathVc,
+    output_root: FileSystemPathVc,
+    options: EcmascriptLibraryOptionsVc,
+) -> Result<PreservedModulesVc> {
+    let mut modules = IndexSet::new();
+    let mut roots = entries.await?.iter().copied().collect::<VecDeque<_>>();
+    let mut seen_roots = IndexSet::new();
+    while let Some(entry) = roots.pop_front() {
+        if !seen_roots.insert(entry.resolve().await?) {
+            continue;
+        }
+        let reachable = reachable_modules(context, entry).await?;
+        modules.extend(reachable.loaded);
+        roots.extend(reachable.async_imports);
+    }
+
+    let project_root = project_root.await?;
+    let root = root.await?;
+    let extension = options.await?.format.extension();
+    let mut preserved = IndexMap::new();
+    for module in modules {
+        let path = module.path().await?;
+        let relative = match root.get_path_to(&path) {
+            Some(relative) => relative,
+            None => match project_root.get_path_to(&path) {
+                Some(relative) => relative,
+                None => continue,
+            },
+        };
+        preserved.insert(
+            module,
+            output_root.join(&with_extension(relative, extension)),
+        );
+    }
+    Ok(PreservedModulesVc::cell(preserved))
+}
+
+/// A library exporting the exports of `entry`.
+///
+/// Without `preserved_modules`, the library contains all modules `entry`
+/// loads, including the ones imported with `import()`. Otherwise it only
+/// contains `entry` and the modules which are not preserved, and imports the
+/// preserved modules from their files.
+///
+/// Stylesheets and assets of the modules are emitted next to the library,
+/// but they are not loaded by it.
+#[turbo_tasks::value(shared)]
+pub struct EcmascriptLibraryAsset {
+    /// The path of the library, which has to be in the output root of
+    /// `context`.
+    pub path: FileSystemPathVc,
+    /// The context of the chunk of the library. It needs to place all modules
+    /// into the chunk, see
+    /// [DevChunkingContextBuilder::single_file_output][single_file_output].
+    ///
+    /// [single_file_output]: turbopack_core::chunk::dev::DevChunkingContextBuilder::single_file_output
+    pub context: ChunkingContextVc,
+    pub entry: EcmascriptChunkPlaceableVc,
+    pub options: EcmascriptLibraryOptionsVc,
+    pub preserved_modules: Option<PreservedModulesVc>,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct EcmascriptLibraryAssets(Vec<EcmascriptLibraryAssetVc>);
+
+#[turbo_tasks::value_impl]
+impl EcmascriptLibraryAssetVc {
+    /// The chunk the code of a library without preserved modules is taken
+    /// from. It's not emitted, but it's named like the library, so errors
+    /// and the automatic public path refer to the library.
+    #[turbo_tasks::function]
+    async fn chunk(self) -> Result<EcmascriptChunkVc> {
+        let this = self.await?;
+        let entries = EcmascriptChunkPlaceablesVc::cell(vec![this.entry]);
+        Ok(EcmascriptChunkVc::new_normalized(
+            this.context,
+            entries,
+            None,
+            Some(
+                EcmascriptChunkEvaluate {
+                    evaluate_entries: entries,
+                    chunk_group: None,
+                    shared_chunks: Vec::new(),
+                    filename: Some(self.chunk_path().await?.clone_value()),
+                }
+                .cell(),
+            ),
+        ))
+    }
+
+    /// The path of the library relative to the output root, which is the path
+    /// its chunk is registered with.
+    #[turbo_tasks::function]
+    async fn chunk_path(self) -> Result<StringVc> {
+        let this = self.await?;
+        let path = this.path.await?;
+        let output_root = this.context.output_root().await?;
+        match output_root.get_path_to(&path) {
+            Some(path) => Ok(StringVc::cell(path.to_string())),
+            None => bail!(
+                "library {} is not in the output root {}",
+                path.path,
+                output_root.path
+            ),
+        }
+    }
+
+    /// The library of a preserved module which is imported by this library.
+    #[turbo_tasks::function]
+    async fn preserved_module(self, module: EcmascriptChunkPlaceableVc) -> Result<Self> {
+        let this = self.await?;
+        let preserved_modules = this
+            .preserved_modules
+            .context("modules are not preserved")?;
+        let path = *preserved_modules
+            .await?
+            .get(&module)
+            .ok_or_else(|| anyhow!("module is not preserved"))?;
+        Ok(EcmascriptLibraryAsset {
+            path,
+            context: this.context,
+            entry: module,
+            options: this.options,
+            preserved_modules: this.preserved_modules,
+        }
+        .cell())
+    }
+
+    /// The libraries of the preserved modules this library imports with
+    /// import declarations. They are loaded before the library is evaluated.
+    #[turbo_tasks::function]
+    pub async fn static_imports(self) -> Result<EcmascriptLibraryAssetsVc> {
+        let this = self.await?;
+        let preserved_modules = match this.preserved_modules {
+            Some(preserved_modules) => preserved_modules,
+            None => return Ok(EcmascriptLibraryAssetsVc::cell(Vec::new())),
+        };
+        let (_, imports) =
+            preserved_module_content(this.context, this.entry, &*preserved_modules.await?).await?;
+        Ok(EcmascriptLibraryAssetsVc::cell(
+            imports
+                .into_iter()
+                .map(|module| self.preserved_module(module))
+                .collect(),
+        ))
+    }
+}
+
+/// The files of ES module libraries which a page loading `chunk_groups` and
+/// `module_scripts` imports statically: the libraries the chunks reference,
+/// e. g. pre-bundled packages, and the files they and the module scripts
+/// import, directly or through other files. Preloading them with
+/// `<link rel="modulepreload">` avoids discovering the import graph one level
+/// at a time. They are ordered breadth first, so the files closest to the page
+/// are fetched first.
+#[turbo_tasks::function]
+pub async fn module_preloads(
+    chunk_groups: Vec<ChunkGroupVc>,
+    module_scripts: Vec<EcmascriptLibraryAssetVc>,
+) -> Result<EcmascriptLibraryAssetsVc> {
+    let mut queue = VecDeque::new();
+    for chunk_group in chunk_groups.iter() {
+        for chunk in chunk_group.chunks().await?.iter() {
+            for reference in chunk.references().await?.iter() {
+                for asset in reference.resolve_reference().primary_assets().await?.iter() {
+                    if let Some(library) = EcmascriptLibraryAssetVc::resolve_from(asset).await? {
+                        if library.await?.options.await?.format == LibraryFormat::Esm {
+                            queue.push_back(library);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    // The module scripts are loaded by the page itself
+    let mut visited = IndexSet::new();
+    for &script in module_scripts.iter() {
+        visited.insert(script.resolve().await?);
+        queue.extend(script.static_imports().await?.iter().copied());
+    }
+    let mut preloads = Vec::new();
+    while let Some(library) = queue.pop_front() {
+        let library = library.resolve().await?;
+        if !visited.insert(library) {
+            continue;
+        }
+        preloads.push(library);
+        queue.extend(library.static_imports().await?.iter().copied());
+    }
+    Ok(EcmascriptLibraryAssetsVc::cell(preloads))
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for EcmascriptLibraryAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn content(self_vc: EcmascriptLibraryAssetVc) -> Result<AssetContentVc> {
+        let this = self_vc.await?;
+        let options = this.options.await?;
+        let chunk_path = self_vc.chunk_path().await?;
+
+        let (chunk_code, chunk_items, imports) = match this.preserved_modules {
+            None => {
+                let chunk = self_vc.chunk().await?;
+                let chunk_items =
+                    ecmascript_chunk_content(chunk.context, chunk.main_entries, chunk.omit_entries)
+                        .await?
+                        .chunk_items
+                        .to_set()
+                        .await?;
+                let code = self_vc.chunk().chunk_content().code().source_code().await?;
+                (
+                    code.clone_value(),
+                    chunk_items.iter().copied().collect::<Vec<_>>(),
+                    Vec::new(),
+                )
+            }
+            Some(preserved_modules) => {
+                let preserved_modules = preserved_modules.await?;
+                let (chunk_items, preserved_imports) =
+                    preserved_module_content(this.context, this.entry, &preserved_modules).await?;
+                let directory = this.path.parent().await?;
+                let mut imports = Vec::new();
+                for module in preserved_imports {
+                    let path = preserved_modules[&module];
+                    let path = path.await?;
+                    imports.push(LibraryImport {
+                        id: stringify_module_id(&*module.as_chunk_item(this.context).id().await?),
+                        request: directory
+                            .get_relative_path_to(&path)
+                            .context("preserved modules must be in the output root")?,
+                        esm: matches!(
+                            &*module.get_exports().await?,
+                            EcmascriptExports::EsmExports(_)
+                        ),
+                    });
+                }
+                let code =
+                    preserved_module_code(this.context, &chunk_path, chunk_items.iter().copied())
+                        .await?;
+                (code, chunk_items.into_iter().collect(), imports)
+            }
+        };
+
+        let mut externals = IndexSet::new();
+        for chunk_item in chunk_items {
+            for reference in chunk_item.references().await?.iter() {
+                if let ResolveResult::Special(
+                    SpecialType::OriginalReferenceTypeExternal(request),
+                    _,
+                ) = &*reference.resolve_reference().await?
+                {
+                    externals.insert(request.clone());
+                }
+            }
+        }
+
+        let exports = match &*this.entry.get_exports().await? {
+            EcmascriptExports::EsmExports(exports) => {
+                let mut names = expand_star_exports(this.entry)
+                    .await?
+                    .iter()
+                    .filter(|name| is_identifier(name))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                names.sort();
+                LibraryExports::Esm {
+                    default: exports.await?.exports.contains_key("default"),
+                    names,
+                }
+            }
+            EcmascriptExports::CommonJs(_) => LibraryExports::CommonJs {
+                names: cjs_export_names(this.entry).await?,
+            },
+            EcmascriptExports::Value => LibraryExports::Value,
+            EcmascriptExports::None => LibraryExports::None,
+        };
+
+        let code = library_code(&LibraryCode {
+            format: options.format,
+            name: options.name.as_deref(),
+            globals: &options.globals,
+            chunk_path: &chunk_path,
+            chunk_code: &chunk_code,
+            entry_id: &stringify_module_id(&*this.entry.as_chunk_item(this.context).id().await?),
+            externals: &externals.into_iter().collect::<Vec<_>>(),
+            imports: &imports,
+            exports: &exports,
+        })?;
+        Ok(File::from(code).into())
+    }
+
+    #[turbo_tasks::function]
+    async fn references(self_vc: EcmascriptLibraryAssetVc) -> Result<AssetReferencesVc> {
+        let this = self_vc.await?;
+        let content = ecmascript_chunk_content(
+            this.context,
+            EcmascriptChunkPlaceablesVc::cell(vec![this.entry]),
+            None,
+        )
+        .await?;
+        let mut references = content.external_asset_references.clone();
+        for chunk in content.chunks.iter() {
+            references.push(ChunkReferenceVc::new_parallel(*chunk).into());
+        }
+        for chunk_group in content.async_chunk_groups.iter() {
+            references.push(ChunkGroupReferenceVc::new(*chunk_group).into());
+        }
+        for library in self_vc.static_imports().await?.iter() {
+            references.push(
+                SingleAssetReferenceVc::new(
+                    (*library).into(),
+                    StringVc::cell("preserved module".to_string()),
+                )
+                .into(),
+            );
+        }
+        Ok(AssetReferencesVc::cell(references))
+    }
+}
+
+/// Returns the chunk items placed into the file of the preserved module
+/// `entry`, and the preserved modules the file imports from their files.
+async fn preserved_module_content(
+    context: ChunkingContextVc,
+    entry: EcmascriptChunkPlaceableVc,
+    preserved_modules: &IndexMap<EcmascriptChunkPlaceableVc, FileSystemPathVc>,
+) -> Result<(
+    IndexSet<EcmascriptChunkItemVc>,
+    IndexSet<EcmascriptChunkPlaceableVc>,
+)> {
+    let entry = entry.resolve().await?;
+    let mut chunk_items = IndexSet::new();
+    let mut imports = IndexSet::new();
+    let mut placed = IndexSet::new();
+    placed.insert(entry);
+    let mut queue = VecDeque::from([entry]);
+    while let Some(placeable) = queue.pop_front() {
+        chunk_items.insert(placeable.as_chunk_item(context));
+        for (module, is_async) in referenced_modules(context, placeable).await? {
+            if is_async {
+                // The loader imports the module from the same file, like with
+                // single file output
+                let chunkable = ChunkableAssetVc::resolve_from(module)
+                    .await?
+                    .context("modules placed in chunks must be chunkable")?;
+                if let Some((loader, _)) = EcmascriptChunkItemVc::from_async_asset(
+                    context,
+                    chunkable,
+                    placeable.as_asset(),
+                )
+                .await?
+                {
+                    chunk_items.insert(loader);
+                }
+            }
+            if module == entry {
+                continue;
+            }
+            if preserved_modules.contains_key(&module) {
+                imports.insert(module);
+            } else if placed.insert(module) {
+                queue.push_back(module);
+            }
+        }
+    }
+    Ok((chunk_items, imports))
+}
+
+/// Returns the code registering `chunk_items` as the chunk `chunk_path`,
+/// followed by the runtime, like the code of a chunk evaluating entries.
+async fn preserved_module_code(
+    context: ChunkingContextVc,
+    chunk_path: &str,
+    chunk_items: impl Iterator<Item = EcmascriptChunkItemVc>,
+) -> Result<String> {
+    let mut code = Code::new();
+    writeln!(
+        code,
+        "(self.TURBOPACK = self.TURBOPACK || []).push([{}, {{",
+        stringify_str(chunk_path)
+    )?;
+    for chunk_item in chunk_items {
+        let entry = EcmascriptChunkContentEntryVc::new(chunk_item).await?;
+        write!(code, "\n{}: ", &stringify_module_id(entry.id()))?;
+        code.push_code(entry.code());
+        code += ",";
+    }
+    code += "\n}]);\n";
+    let public_path = context.public_path().await?.clone_value();
+    // Libraries are wrapped in their own format, their chunks push themselves
+    // onto the `TURBOPACK` list of the library
+    code += runtime_code(public_path.as_ref(), ChunkFormat::Global)
+        .await?
+        .as_str();
+    if let Some(options) = *context.minify_options().await? {
+        let minified = minify(code.cell(), options.cell()).await?;
+        code = Code::new();
+        code.push_code(&minified);
+    }
+    Ok(code.source_code().to_string())
+}
+
+/// A preserved module imported by a library.
+struct LibraryImport {
+    /// The stringified id of the module.
+    id: String,
+    /// The request importing the file of the module, relative to the library.
+    request: String,
+    /// Whether the module has ES module exports. Otherwise the library of
+    /// the module exports its exports as the default export.
+    esm: bool,
+}
+
+/// The exports of the entry of a library.
+enum LibraryExports {
+    /// The entry has no exports.
+    None,
+    /// The exports of the entry are a single value, e. g. of a module which
+    /// isn't analyzed. ES module libraries export it as the default export.
+    Value,
+    /// The entry is an ES module. Named exports which aren't identifiers are
+    /// omitted by ES module libraries.
+    Esm { default: bool, names: Vec<String> },
+    /// The entry is a CommonJS module. ES module libraries export
+    /// `module.exports` as the default export, and the statically detected
+    /// names of its properties as named exports, like Node.js does.
+    CommonJs { names: Vec<String> },
+}
+
+struct LibraryCode<'a> {
+    format: LibraryFormat,
+    name: Option<&'a str>,
+    globals: &'a IndexMap<String, String>,
+    chunk_path: &'a str,
+    /// The code registering the chunk containing the entry, and the runtime.
+    chunk_code: &'a str,
+    /// The stringified id of the entry.
+    entry_id: &'a str,
+    /// The requests of the external modules.
+    externals: &'a [String],
+    imports: &'a [LibraryImport],
+    exports: &'a LibraryExports,
+}
+
+/// Wraps the code of the chunk of a library into a module of its format.
+fn library_code(library: &LibraryCode) -> Result<String> {
+    let mut code = String::new();
+    match library.format {
+        LibraryFormat::Cjs => {
+            writeln!(
+                code,
+                "module.exports = (function () {{\n{}}})();",
+                library_body(library)?
+            )?;
+        }
+        LibraryFormat::Esm => {
+            for (i, request) in library.externals.iter().enumerate() {
+                writeln!(
+                    code,
+                    "import * as __turbopack_external_{i}__ from {};",
+                    stringify_str(request)
+                )?;
+            }
+            for (i, import) in library.imports.iter().enumerate() {
+                writeln!(
+                    code,
+                    "import * as __turbopack_import_{i}__ from {};",
+                    stringify_str(&import.request)
+                )?;
+            }
+            writeln!(
+                code,
+                "var __turbopack_exports__ = (function () {{\n{}{}}})();",
+                require_shim(library.externals)?,
+                library_body(library)?
+            )?;
+            match library.exports {
+                LibraryExports::None => {}
+                LibraryExports::Value => {
+                    writeln!(code, "export default __turbopack_exports__;")?;
+                }
+                LibraryExports::Esm { default, names } => {
+                    if *default {
+                        writeln!(code, "export default __turbopack_exports__.default;")?;
+                    }
+                    named_exports(&mut code, names)?;
+                }
+                LibraryExports::CommonJs { names } => {
+                    writeln!(code, "export default __turbopack_exports__;")?;
+                    named_exports(&mut code, names)?;
+                }
+            }
+        }
+        LibraryFormat::Umd => {
+            let name = library
+                .name
+                .context("UMD libraries need a name, which their exports are assigned to")?;
+            if !library.imports.is_empty() {
+                bail!("UMD libraries can't preserve modules");
+            }
+            let externals = library.externals;
+            let amd = externals
+                .iter()
+                .map(|request| stringify_str(request))
+                .collect::<Vec<_>>();
+            let cjs = externals
+                .iter()
+                .map(|request| format!("require({})", stringify_str(request)))
+                .collect::<Vec<_>>();
+            let globals = externals
+                .iter()
+                .map(|request| {
+                    let global = library.globals.get(request).unwrap_or(request);
+                    format!("root[{}]", stringify_str(global))
+                })
+                .collect::<Vec<_>>();
+            let params = (0..externals.len())
+                .map(|i| format!("__turbopack_external_{i}__"))
+                .collect::<Vec<_>>();
+            write!(
+                code,
+                r#"(function (root, factory) {{
+  if (typeof define === "function" && define.amd) {{
+    define([{amd}], factory);
+  }} else if (typeof module === "object" && module.exports) {{
+    module.exports = factory({cjs});
+  }} else {{
+    root[{name}] = factory({globals});
+  }}
+}})(typeof globalThis !== "undefined" ? globalThis : this, function ({params}) {{
+{require_shim}{body}}});
+"#,
+                amd = amd.join(", "),
+                cjs = cjs.join(", "),
+                name = stringify_str(name),
+                globals = globals.join(", "),
+                params = params.join(", "),
+                require_shim = require_shim(externals)?,
+                body = library_body(library)?,
+            )?;
+        }
+    }
+    Ok(code)
+}
+
+/// Writes the named exports of an ES module library, which are read from the
+/// exports of the entry.
+fn named_exports(code: &mut String, names: &[String]) -> Result<()> {
+    if names.is_empty() {
+        return Ok(());
+    }
+    for (i, name) in names.iter().enumerate() {
+        writeln!(
+            code,
+            "var __turbopack_export_{i}__ = __turbopack_exports__.{name};"
+        )?;
+    }
+    let specifiers = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| format!("__turbopack_export_{i}__ as {name}"))
+        .collect::<Vec<_>>();
+    writeln!(code, "export {{ {} }};", specifiers.join(", "))?;
+    Ok(())
+}
+
+/// Returns the statically detected export names of the CommonJS module
+/// `module` which are identifiers, including the ones of the modules it
+/// re-exports with `module.exports = require("...")`.
+async fn cjs_export_names(module: EcmascriptChunkPlaceableVc) -> Result<Vec<String>> {
+    let mut names = IndexSet::new();
+    let mut seen = IndexSet::new();
+    let mut queue = VecDeque::from([module.resolve().await?]);
+    while let Some(module) = queue.pop_front() {
+        if !seen.insert(module) {
+            continue;
+        }
+        match &*module.get_exports().await? {
+            EcmascriptExports::CommonJs(exports) => {
+                let exports = exports.await?;
+                names.extend(exports.named_exports().cloned());
+                for reexport in exports.reexports.iter() {
+                    let assets = reexport.resolve_reference().primary_assets();
+                    for asset in assets.await?.iter() {
+                        if let Some(placeable) =
+                            EcmascriptChunkPlaceableVc::resolve_from(asset).await?
+                        {
+                            queue.push_back(placeable);
+                        }
+                    }
+                }
+            }
+            EcmascriptExports::EsmExports(_) => {
+                names.extend(expand_star_exports(module).await?.iter().cloned());
+            }
+            EcmascriptExports::Value | EcmascriptExports::None => {}
+        }
+    }
+    let mut names = names
+        .into_iter()
+        .filter(|name| is_identifier(name))
+        .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+}
+
+/// Returns a `require` for libraries which import their external modules,
+/// which the runtime loads external modules with.
+fn require_shim(externals: &[String]) -> Result<String> {
+    if externals.is_empty() {
+        return Ok(String::new());
+    }
+    let mut code = "var __turbopack_externals__ = {\n  __proto__: null,\n".to_string();
+    for (i, request) in externals.iter().enumerate() {
+        writeln!(
+            code,
+            "  {}: __turbopack_external_{i}__,",
+            stringify_str(request)
+        )?;
+    }
+    code += r#"};
+function require(id) {
+  if (!(id in __turbopack_externals__)) {
+    throw new Error("Cannot find module '" + id + "'");
+  }
+  return __turbopack_externals__[id];
+}
+"#;
+    Ok(code)
+}
+
+/// Returns the body of the function evaluating the chunk of a library and
+/// returning the exports of the entry.
+fn library_body(library: &LibraryCode) -> Result<String> {
+    let mut code = String::new();
+    // Preserved modules are registered before the chunk, so the modules of
+    // the chunk import them from their files
+    let mut factories = String::new();
+    for (i, import) in library.imports.iter().enumerate() {
+        let factory = match (library.format, import.esm) {
+            (LibraryFormat::Esm, true) => format!(
+                "function ({{ s }}) {{\n    var ns = __turbopack_import_{i}__;\n    \
+                 s(Object.fromEntries(Object.keys(ns).map(function (key) {{\n      return [key, \
+                 function () {{ return ns[key]; }}];\n    }})));\n  }}"
+            ),
+            (LibraryFormat::Esm, false) => {
+                format!("function ({{ v }}) {{\n    v(__turbopack_import_{i}__.default);\n  }}")
+            }
+            _ => format!(
+                "function ({{ m }}) {{\n    m.exports = require({});\n  }}",
+                stringify_str(&import.request)
+            ),
+        };
+        write!(factories, "\n  {}: {factory},", import.id)?;
+    }
+    let turbopack = if factories.is_empty() {
+        "undefined".to_string()
+    } else {
+        format!(
+            "[[{}, {{{factories}\n  }}]]",
+            stringify_str(library.chunk_path)
+        )
+    };
+    // The runtime registers chunks on `self`, which is a scope of the library
+    // inheriting the globals
+    writeln!(
+        code,
+        "var self = Object.assign(Object.create(globalThis), {{
+  TURBOPACK: {turbopack},
+  TURBOPACK_CHUNK_UPDATE_LISTENERS: undefined,
+  TURBOPACK_PUBLIC_PATH: undefined,
+  TURBOPACK_CHUNK_FORMAT: undefined,
+}});
+var __turbopack_exports__;"
+    )?;
+    code += library.chunk_code;
+    if !library.chunk_code.ends_with('\n') {
+        code += "\n";
+    }
+    writeln!(
+        code,
+        "self.TURBOPACK.push([{}, {{}}, function (runtime) {{
+  var module = runtime.cache[{id}] || runtime.instantiateRuntimeModule({id});
+  __turbopack_exports__ = module.exports;
+}}]);
+return __turbopack_exports__;",
+        stringify_str(library.chunk_path),
+        id = library.entry_id,
+    )?;
+    Ok(code)
+}
+
+/// Replaces the extension of JavaScript and TypeScript modules with
+/// `extension`, or appends it to the path of other modules, e. g.
+/// `logo.png.js`.
+fn with_extension(path: &str, extension: &str) -> String {
+    const EXTENSIONS: [&str; 8] = [".js", ".jsx", ".mjs", ".cjs", ".ts", ".tsx", ".mts", ".cts"];
+    let stem = EXTENSIONS
+        .iter()
+        .find_map(|source_extension| path.strip_suffix(source_extension))
+        .unwrap_or(path);
+    format!("{stem}{extension}")
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_identifier, with_extension};
+
+    #[test]
+    fn preserved_module_extensions() {
+        assert_eq!(with_extension("src/index.ts", ".mjs"), "src/index.mjs");
+        assert_eq!(with_extension("src/Button.tsx", ".js"), "src/Button.js");
+        assert_eq!(with_extension("lib/util.js", ".js"), "lib/util.js");
+        assert_eq!(
+            with_extension("assets/logo.png", ".js"),
+            "assets/logo.png.js"
+        );
+    }
+
+    #[test]
+    fn export_names() {
+        assert!(is_identifier("useState"));
+        assert!(is_identifier("$_value1"));
+        assert!(!is_identifier("1st"));
+        assert!(!is_identifier("kebab-case"));
+        assert!(!is_identifier(""));
+    }
+}
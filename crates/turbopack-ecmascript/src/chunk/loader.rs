@@ -2,22 +2,28 @@ use std::fmt::Write as FmtWrite;
 
 use anyhow::{anyhow, bail, Result};
 use indexmap::IndexSet;
-use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks::{
+    primitives::{BoolVc, StringVc},
+    ValueToString, ValueToStringVc,
+};
 use turbo_tasks_fs::FileSystemPathVc;
+use turbo_tasks_hash::{encode_hex, Xxh3Hash64Hasher};
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc},
     chunk::{
-        ChunkGroupVc, ChunkItem, ChunkItemVc, ChunkReferenceVc, ChunkVc, ChunkableAsset,
-        ChunkableAssetVc, ChunkingContextVc, ChunksVc,
+        ChunkGroupVc, ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetVc,
+        ChunkingContextVc, ChunksVc, ParallelChunkReference, ParallelChunkReferenceVc,
     },
-    reference::AssetReferencesVc,
+    reference::{AssetReference, AssetReferencesVc},
+    resolve::{ResolveResult, ResolveResultVc},
 };
 
+use super::common::async_common_chunks;
 use crate::{
     chunk::{
         EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkItemContentVc,
         EcmascriptChunkItemVc, EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc,
-        EcmascriptChunkVc, EcmascriptExports, EcmascriptExportsVc,
+        EcmascriptChunkPlaceablesVc, EcmascriptChunkVc, EcmascriptExports, EcmascriptExportsVc,
     },
     utils::{stringify_module_id, stringify_str},
 };
@@ -51,12 +57,16 @@ impl ManifestLoaderItemVc {
 #[turbo_tasks::value_impl]
 impl ValueToString for ManifestLoaderItem {
     #[turbo_tasks::function]
-    fn to_string(&self) -> StringVc {
-        self.manifest
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(self
+            .manifest
             .path()
             .parent()
-            .join("manifest-loader.js")
-            .to_string()
+            .join(&format!(
+                "manifest-loader{}.js",
+                self.manifest.suffix().await?
+            ))
+            .to_string())
     }
 }
 
@@ -81,6 +91,29 @@ impl EcmascriptChunkItem for ManifestLoaderItem {
 
         let manifest = self.manifest.await?;
         let asset = manifest.asset.as_asset();
+
+        if *self.context.is_single_file_output_enabled().await? {
+            // The module is placed into the chunk of the loader, so it only needs to be
+            // imported. It's still imported asynchronously, like with a chunk load.
+            let placeable = EcmascriptChunkPlaceableVc::resolve_from(asset)
+                .await?
+                .ok_or_else(|| anyhow!("asset is not placeable in ecmascript chunk"))?;
+            let dynamic_id = &*placeable.as_chunk_item(self.context).id().await?;
+            write!(
+                code,
+                "
+__turbopack_export_value__((__turbopack_import__) => {{
+    return Promise.resolve().then(() => __turbopack_import__({dynamic_id}));
+}});",
+                dynamic_id = stringify_module_id(dynamic_id),
+            )?;
+            return Ok(EcmascriptChunkItemContent {
+                inner_code: code,
+                ..Default::default()
+            }
+            .into());
+        }
+
         let chunk = self.manifest.as_chunk(self.context);
         let chunk_path = &*chunk.path().await?;
 
@@ -147,31 +180,111 @@ __turbopack_export_value__((__turbopack_import__) => {{
 pub struct ManifestChunkAsset {
     pub asset: ChunkableAssetVc,
     pub chunking_context: ChunkingContextVc,
+    /// Entries of the chunk importing the asset. Modules loaded by them are
+    /// omitted from the chunks of the asset.
+    pub available_entries: Option<EcmascriptChunkPlaceablesVc>,
+    /// Entry of the chunk importing the asset, when modules shared with other
+    /// async chunks of that chunk are extracted into common chunks.
+    pub parent_entry: Option<EcmascriptChunkPlaceableVc>,
 }
 
 #[turbo_tasks::value_impl]
 impl ManifestChunkAssetVc {
     #[turbo_tasks::function]
-    pub fn new(asset: ChunkableAssetVc, chunking_context: ChunkingContextVc) -> Self {
+    pub fn new(
+        asset: ChunkableAssetVc,
+        chunking_context: ChunkingContextVc,
+        available_entries: Option<EcmascriptChunkPlaceablesVc>,
+        parent_entry: Option<EcmascriptChunkPlaceableVc>,
+    ) -> Self {
         Self::cell(ManifestChunkAsset {
             asset,
             chunking_context,
+            available_entries,
+            parent_entry,
         })
     }
 
     #[turbo_tasks::function]
     async fn chunks(self) -> Result<ChunksVc> {
         let this = self.await?;
-        let chunk_group = ChunkGroupVc::from_asset(this.asset, this.chunking_context);
-        Ok(chunk_group.chunks())
+        let placeable = EcmascriptChunkPlaceableVc::resolve_from(this.asset).await?;
+        let common_chunks = match (this.parent_entry, placeable) {
+            (Some(parent_entry), Some(placeable)) => {
+                async_common_chunks(this.chunking_context, parent_entry)
+                    .await?
+                    .get(&placeable)
+                    .cloned()
+                    .unwrap_or_default()
+            }
+            _ => Vec::new(),
+        };
+        // Modules of the common chunks are omitted, as they are loaded in parallel
+        let mut omit_entries = Vec::new();
+        if let Some(available_entries) = this.available_entries {
+            omit_entries.extend(available_entries.await?.iter().copied());
+        }
+        for chunk in common_chunks.iter() {
+            omit_entries.extend(chunk.await?.main_entries.await?.iter().copied());
+        }
+        let chunk_group = if omit_entries.is_empty() {
+            ChunkGroupVc::from_asset(this.asset, this.chunking_context)
+        } else {
+            let placeable =
+                placeable.ok_or_else(|| anyhow!("asset is not placeable in ecmascript chunk"))?;
+            let chunk = EcmascriptChunkVc::new_normalized(
+                this.chunking_context,
+                EcmascriptChunkPlaceablesVc::cell(vec![placeable]),
+                Some(EcmascriptChunkPlaceablesVc::cell(omit_entries)),
+                None,
+            );
+            ChunkGroupVc::from_chunk(chunk.into())
+        };
+        let mut chunks = IndexSet::new();
+        for chunk in common_chunks {
+            chunks.extend(
+                ChunkGroupVc::from_chunk(chunk.into())
+                    .chunks()
+                    .await?
+                    .iter()
+                    .copied(),
+            );
+        }
+        chunks.extend(chunk_group.chunks().await?.iter().copied());
+        Ok(ChunksVc::cell(chunks.into_iter().collect()))
+    }
+
+    /// Distinguishes manifests of the same asset which are imported from
+    /// different chunks, as their chunks omit different modules.
+    #[turbo_tasks::function]
+    async fn suffix(self) -> Result<StringVc> {
+        let this = self.await?;
+        if this.available_entries.is_none() && this.parent_entry.is_none() {
+            return Ok(StringVc::cell(String::new()));
+        }
+        let mut hasher = Xxh3Hash64Hasher::new();
+        if let Some(available_entries) = this.available_entries {
+            for entry in available_entries.await?.iter() {
+                hasher.write_value(entry.path().to_string().await?);
+            }
+        }
+        if let Some(parent_entry) = this.parent_entry {
+            hasher.write_value(parent_entry.path().to_string().await?);
+        }
+        let hash = encode_hex(hasher.finish());
+        Ok(StringVc::cell(format!("_{}", &hash[..6])))
     }
 }
 
 #[turbo_tasks::value_impl]
 impl Asset for ManifestChunkAsset {
     #[turbo_tasks::function]
-    fn path(&self) -> FileSystemPathVc {
-        self.asset.path().join("manifest-chunk.js")
+    async fn path(self_vc: ManifestChunkAssetVc) -> Result<FileSystemPathVc> {
+        Ok(self_vc
+            .await?
+            .asset
+            .path()
+            .join(&format!("manifest-chunk{}.js", self_vc.suffix().await?)))
     }
 
     #[turbo_tasks::function]
@@ -287,17 +400,62 @@ __turbopack_export_value__(Promise.all(chunks.map(__turbopack_load__)));"
 #[turbo_tasks::value_impl]
 impl ChunkItem for ManifestChunkItem {
     #[turbo_tasks::function]
-    async fn references(&self) -> Result<AssetReferencesVc> {
-        let chunks = self.manifest.chunks();
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::cell(vec![ManifestChunksReferenceVc::new(self.manifest).into()])
+    }
+}
+
+/// References the chunks loaded by a manifest chunk. They are only computed
+/// when the reference is resolved, e. g. when the dev server serves the
+/// manifest chunk, and not already when the chunk group of the manifest chunk
+/// is built, which happens when the chunk importing the asset is served.
+#[turbo_tasks::value]
+struct ManifestChunksReference {
+    manifest: ManifestChunkAssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ManifestChunksReferenceVc {
+    #[turbo_tasks::function]
+    fn new(manifest: ManifestChunkAssetVc) -> Self {
+        Self::cell(ManifestChunksReference { manifest })
+    }
+}
 
-        Ok(AssetReferencesVc::cell(
-            chunks
-                .await?
-                .iter()
-                .copied()
-                .map(ChunkReferenceVc::new)
-                .map(Into::into)
-                .collect(),
-        ))
+#[turbo_tasks::value_impl]
+impl AssetReference for ManifestChunksReference {
+    #[turbo_tasks::function]
+    async fn resolve_reference(&self) -> Result<ResolveResultVc> {
+        let chunks = self
+            .manifest
+            .chunks()
+            .await?
+            .iter()
+            .map(|chunk| chunk.as_asset())
+            .collect();
+        Ok(ResolveResult::Alternatives(chunks, Vec::new()).into())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for ManifestChunksReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "chunks of {}",
+            self.manifest.path().to_string().await?
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ParallelChunkReference for ManifestChunksReference {
+    /// Like a [ChunkReference], the chunks are loaded by the manifest chunk
+    /// and not in parallel with it.
+    ///
+    /// [ChunkReference]: turbopack_core::chunk::ChunkReference
+    #[turbo_tasks::function]
+    fn is_loaded_in_parallel(&self) -> BoolVc {
+        BoolVc::cell(false)
     }
 }
@@ -0,0 +1,161 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use swc_core::{
+    common::{
+        input::StringInput, source_map::SourceMapGenConfig, FileName, Globals, Mark, SourceMap,
+        GLOBALS,
+    },
+    ecma::{
+        ast::EsVersion,
+        codegen::{text_writer::JsWriter, Emitter},
+        minifier::{
+            optimize,
+            option::{
+                terser::TerserCompressorOptions, ExtraOptions, MangleOptions,
+                MinifyOptions as SwcMinifyOptions,
+            },
+        },
+        parser::{lexer::Lexer, EsConfig, Parser, Syntax},
+        transforms::base::{fixer::fixer, hygiene::hygiene, resolver},
+        visit::VisitMutWith,
+    },
+};
+use turbopack_core::{
+    chunk::MinifyOptionsVc,
+    code_builder::{Code, CodeVc},
+    source_map::{
+        GenerateSourceMap, GenerateSourceMapVc, SourceMap as TurbopackSourceMap, SourceMapVc,
+    },
+};
+
+/// Minifies the code of a chunk with the SWC minifier. The source map of the
+/// minified code is chained with the source map of `code`, so it still maps to
+/// the original sources.
+#[turbo_tasks::function]
+pub(super) async fn minify(code: CodeVc, options: MinifyOptionsVc) -> Result<CodeVc> {
+    let options = *options.await?;
+    let original_code = code.await?;
+    let original_map = if original_code.has_source_map() {
+        Some(code.generate_source_map().flatten().await?)
+    } else {
+        None
+    };
+
+    let cm = Arc::new(SourceMap::default());
+    let fm = cm.new_source_file(FileName::Anon, original_code.source_code().to_string());
+    let (source_code, mut mappings) = GLOBALS.set(&Globals::new(), || -> Result<_> {
+        let lexer = Lexer::new(
+            Syntax::Es(EsConfig::default()),
+            EsVersion::latest(),
+            StringInput::from(&*fm),
+            None,
+        );
+        let mut program = Parser::new_from(lexer).parse_program().map_err(|err| {
+            anyhow!(
+                "failed to parse chunk for minification: {}",
+                err.kind().msg()
+            )
+        })?;
+
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+        program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+
+        let compress = if options.compress_passes > 0 {
+            let compress: TerserCompressorOptions = serde_json::from_value(serde_json::json!({
+                "passes": options.compress_passes,
+                "keep_classnames": options.keep_classnames,
+            }))?;
+            Some(compress.into_config(cm.clone()))
+        } else {
+            None
+        };
+        let mangle = options.mangle.then(|| MangleOptions {
+            keep_class_names: options.keep_classnames,
+            ..Default::default()
+        });
+        let mut program = optimize(
+            program,
+            cm.clone(),
+            None,
+            None,
+            &SwcMinifyOptions {
+                compress,
+                mangle,
+                ..Default::default()
+            },
+            &ExtraOptions {
+                unresolved_mark,
+                top_level_mark,
+            },
+        );
+        program.visit_mut_with(&mut hygiene());
+        program.visit_mut_with(&mut fixer(None));
+
+        let mut bytes = Vec::new();
+        let mut mappings = Vec::new();
+        let mut emitter = Emitter {
+            cfg: swc_core::ecma::codegen::Config {
+                minify: true,
+                ..Default::default()
+            },
+            cm: cm.clone(),
+            comments: None,
+            wr: JsWriter::new(
+                cm.clone(),
+                "\n",
+                &mut bytes,
+                original_map.is_some().then_some(&mut mappings),
+            ),
+        };
+        emitter.emit_program(&program)?;
+        Ok((String::from_utf8(bytes)?, mappings))
+    })?;
+
+    let source_map = match original_map.as_deref() {
+        Some(TurbopackSourceMap::Regular(original_map)) => {
+            let map = cm.build_source_map_with_config(
+                &mut mappings,
+                // Maps the minified code to the original sources, through the
+                // unminified code of the chunk.
+                Some(&****original_map),
+                MinifiedSourceMapConfig,
+            );
+            Some(
+                MinifiedSourceMap {
+                    map: SourceMapVc::new_regular(map),
+                }
+                .cell()
+                .as_generate_source_map(),
+            )
+        }
+        _ => None,
+    };
+
+    let mut code = Code::new();
+    code.push_source(&source_code, source_map);
+    Ok(code.cell())
+}
+
+struct MinifiedSourceMapConfig;
+
+impl SourceMapGenConfig for MinifiedSourceMapConfig {
+    fn file_name_to_source(&self, f: &FileName) -> String {
+        f.to_string()
+    }
+}
+
+/// The source map of a minified chunk.
+#[turbo_tasks::value(shared)]
+struct MinifiedSourceMap {
+    map: SourceMapVc,
+}
+
+#[turbo_tasks::value_impl]
+impl GenerateSourceMap for MinifiedSourceMap {
+    #[turbo_tasks::function]
+    fn generate_source_map(&self) -> SourceMapVc {
+        self.map
+    }
+}
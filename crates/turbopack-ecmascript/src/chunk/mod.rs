@@ -1,35 +1,47 @@
+pub(crate) mod common;
+pub mod entries;
+pub(crate) mod evaluation;
+pub mod library;
 pub mod loader;
+pub(crate) mod minify;
+pub mod module_ids;
 pub(crate) mod optimize;
+pub mod runtime;
 pub mod source_map;
 
-use std::{fmt::Write as _, slice::Iter};
+use std::{collections::HashSet, fmt::Write as _, slice::Iter};
 
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{anyhow, bail, Result};
 use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 use turbo_tasks::{
-    primitives::{JsonValueVc, StringReadRef, StringVc, StringsVc},
+    primitives::{JsonValueVc, StringReadRef, StringVc, StringsVc, U64Vc},
     trace::TraceRawVcs,
-    TryJoinIterExt, ValueToString, ValueToStringVc,
+    HeapSize, TryJoinIterExt, ValueToString, ValueToStringVc,
 };
-use turbo_tasks_fs::{embed_file, File, FileContent, FileSystemPathOptionVc, FileSystemPathVc};
+use turbo_tasks_fs::{File, FileSystemPathOptionVc, FileSystemPathVc};
 use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64, Xxh3Hash64Hasher};
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc},
     chunk::{
         chunk_content, chunk_content_split,
         optimize::{ChunkOptimizerVc, OptimizableChunk, OptimizableChunkVc},
-        Chunk, ChunkContentResult, ChunkGroupReferenceVc, ChunkGroupVc, ChunkItem, ChunkItemVc,
-        ChunkReferenceVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContextVc,
-        FromChunkableAsset, ModuleId, ModuleIdReadRef, ModuleIdVc, ModuleIdsVc,
+        Chunk, ChunkContentResult, ChunkFormat, ChunkGroupReferenceVc, ChunkGroupVc, ChunkItem,
+        ChunkItemVc, ChunkItemsVc, ChunkReferenceVc, ChunkVc, ChunkableAsset, ChunkableAssetVc,
+        ChunkingContextVc, FromChunkableAsset, MinifyOptions, ModuleEvaluation, ModuleId,
+        ModuleIdReadRef, ModuleIdStrategy, ModuleIdStrategyVc, ModuleIdVc, ModuleIdsVc,
+        ModuleSizesVc, PublicPath,
     },
     code_builder::{Code, CodeReadRef, CodeVc},
     introspect::{
         asset::{children_from_asset_references, content_to_details, IntrospectableAssetVc},
         Introspectable, IntrospectableChildrenVc, IntrospectableVc,
     },
+    issue::{code_gen::CodeGenerationIssue, IssueSeverity},
     reference::{AssetReferenceVc, AssetReferencesVc},
-    source_map::{GenerateSourceMap, GenerateSourceMapVc, SourceMapVc},
+    source_map::{
+        GenerateSourceMap, GenerateSourceMapVc, SourceMapEmission, SourceMapFormat, SourceMapVc,
+    },
     version::{
         PartialUpdate, TotalUpdate, Update, UpdateVc, Version, VersionVc, VersionedContent,
         VersionedContentVc,
@@ -37,13 +49,17 @@ use turbopack_core::{
 };
 
 use self::{
+    evaluation::module_evaluation_order,
     loader::{ManifestChunkAssetVc, ManifestLoaderItemVc},
+    minify::minify,
+    module_ids::{hash_module_name, HASHED_MODULE_ID_LENGTH},
     optimize::EcmascriptChunkOptimizerVc,
+    runtime::{chunk_epilogue, chunk_prologue, runtime_code, EcmascriptRuntimeChunkVc},
     source_map::EcmascriptChunkSourceMapAssetReferenceVc,
 };
 use crate::{
     parse::ParseResultSourceMapVc,
-    references::esm::EsmExportsVc,
+    references::{cjs::CjsExportsVc, esm::EsmExportsVc},
     utils::{stringify_module_id, stringify_str, FormatIter},
 };
 
@@ -103,6 +119,46 @@ impl EcmascriptChunkVc {
                 EcmascriptChunkEvaluate {
                     evaluate_entries: entries,
                     chunk_group: None,
+                    shared_chunks: Vec::new(),
+                    filename: None,
+                }
+                .cell(),
+            ),
+        ))
+    }
+
+    /// Creates a chunk which evaluates `main_entry` after `runtime_entries`,
+    /// like [EcmascriptChunkVc::new_evaluate]. The modules of `shared_chunks`
+    /// are omitted, these chunks are loaded in parallel instead. The chunk is
+    /// emitted to `filename` within the output root when it's set.
+    #[turbo_tasks::function]
+    pub async fn new_entry(
+        context: ChunkingContextVc,
+        main_entry: EcmascriptChunkPlaceableVc,
+        runtime_entries: Option<EcmascriptChunkPlaceablesVc>,
+        shared_chunks: Vec<EcmascriptChunkVc>,
+        filename: Option<String>,
+    ) -> Result<Self> {
+        let mut entries = Vec::new();
+        if let Some(runtime_entries) = runtime_entries {
+            entries.extend(runtime_entries.await?.iter().copied());
+        }
+        entries.push(main_entry);
+        let entries = EcmascriptChunkPlaceablesVc::cell(entries);
+        let mut omit_entries = Vec::new();
+        for chunk in shared_chunks.iter() {
+            omit_entries.extend(chunk.await?.main_entries.await?.iter().copied());
+        }
+        Ok(Self::new_normalized(
+            context,
+            entries,
+            (!omit_entries.is_empty()).then(|| EcmascriptChunkPlaceablesVc::cell(omit_entries)),
+            Some(
+                EcmascriptChunkEvaluate {
+                    evaluate_entries: entries,
+                    chunk_group: None,
+                    shared_chunks,
+                    filename,
                 }
                 .cell(),
             ),
@@ -133,6 +189,21 @@ impl EcmascriptChunkVc {
         Ok(FileSystemPathOptionVc::cell(Some(current)))
     }
 
+    /// Returns the size of the code of the chunk items in the chunk, in bytes.
+    #[turbo_tasks::function]
+    pub async fn code_size(self) -> Result<U64Vc> {
+        let this = self.await?;
+        let chunk_items =
+            ecmascript_chunk_content(this.context, this.main_entries, this.omit_entries)
+                .await?
+                .chunk_items
+                .to_set()
+                .await?;
+        Ok(U64Vc::cell(
+            code_size(chunk_items.iter().copied()).await? as u64,
+        ))
+    }
+
     #[turbo_tasks::function]
     pub async fn compare(
         left: EcmascriptChunkVc,
@@ -187,6 +258,11 @@ pub struct EcmascriptChunkEvaluate {
     /// All chunks of this chunk group need to be ready for execution to start.
     /// When None, it will use a chunk group created from the current chunk.
     chunk_group: Option<ChunkGroupVc>,
+    /// Chunks shared with other entries, which are loaded in parallel.
+    shared_chunks: Vec<EcmascriptChunkVc>,
+    /// The path of the chunk relative to the output root, which replaces the
+    /// path derived from the entries.
+    filename: Option<String>,
 }
 
 #[turbo_tasks::value_impl]
@@ -200,6 +276,7 @@ impl EcmascriptChunkEvaluateVc {
         let &EcmascriptChunkEvaluate {
             evaluate_entries,
             chunk_group,
+            ..
         } = &*self.await?;
         let chunk_group =
             chunk_group.unwrap_or_else(|| ChunkGroupVc::from_chunk(origin_chunk.into()));
@@ -221,9 +298,14 @@ impl EcmascriptChunkEvaluateVc {
             .iter()
             .map(|entry| entry.as_chunk_item(context).id())
             .collect();
+        let eager_modules_ids = match *context.module_evaluation().await? {
+            ModuleEvaluation::Deferred => None,
+            ModuleEvaluation::Eager => Some(module_evaluation_order(context, evaluate_entries)),
+        };
         Ok(EcmascriptChunkContentEvaluate {
             chunks_server_paths: StringsVc::cell(chunks_server_paths),
             entry_modules_ids: ModuleIdsVc::cell(entry_modules_ids),
+            eager_modules_ids,
         }
         .cell())
     }
@@ -239,9 +321,38 @@ pub struct EcmascriptChunkContentResult {
 
 #[turbo_tasks::value_impl]
 impl EcmascriptChunkContentResultVc {
+    /// Removes the chunk items and parallel chunks which are also part of
+    /// `other`, as they are available already when `other` is loaded.
     #[turbo_tasks::function]
-    fn filter(self, _other: EcmascriptChunkContentResultVc) -> EcmascriptChunkContentResultVc {
-        todo!()
+    async fn filter(
+        self,
+        other: EcmascriptChunkContentResultVc,
+    ) -> Result<EcmascriptChunkContentResultVc> {
+        let this = self.await?;
+        let other = other.await?;
+        let other_chunk_items = other.chunk_items.to_set().await?;
+        let chunk_items = this
+            .chunk_items
+            .to_set()
+            .await?
+            .iter()
+            .filter(|item| !other_chunk_items.contains(*item))
+            .copied()
+            .collect::<Vec<_>>();
+        Ok(EcmascriptChunkContentResult {
+            chunk_items: EcmascriptChunkItemsVc::cell(EcmascriptChunkItems::make_chunks(
+                &chunk_items,
+            )),
+            chunks: this
+                .chunks
+                .iter()
+                .filter(|chunk| !other.chunks.contains(*chunk))
+                .copied()
+                .collect(),
+            async_chunk_groups: this.async_chunk_groups.clone(),
+            external_asset_references: this.external_asset_references.clone(),
+        }
+        .cell())
     }
 }
 
@@ -277,16 +388,21 @@ async fn ecmascript_chunk_content_internal(
     entries: EcmascriptChunkPlaceablesVc,
 ) -> Result<EcmascriptChunkContentResultVc> {
     let entries = entries.await?;
-    let entries = entries.iter().copied();
 
     let contents = entries
-        .map(|entry| ecmascript_chunk_content_single_entry(context, entry))
+        .iter()
+        .map(|&entry| ecmascript_chunk_content_single_entry(context, entry))
         .collect::<Vec<_>>();
 
     if contents.len() == 1 {
         return Ok(contents.into_iter().next().unwrap());
     }
 
+    let mut resolved_entries = HashSet::new();
+    for entry in entries.iter() {
+        resolved_entries.insert(entry.resolve().await?);
+    }
+
     let mut all_chunk_items = IndexSet::<EcmascriptChunkItemVc>::new();
     let mut all_chunks = IndexSet::<ChunkVc>::new();
     let mut all_async_chunk_groups = IndexSet::<ChunkGroupVc>::new();
@@ -302,7 +418,20 @@ async fn ecmascript_chunk_content_internal(
         for chunk in chunk_items.await?.iter() {
             all_chunk_items.extend(chunk.await?.iter().copied());
         }
-        all_chunks.extend(chunks.iter().copied());
+        for &chunk in chunks.iter() {
+            // Chunks split off for the other entries of this chunk, e. g. when
+            // merging small chunks, are part of this chunk already
+            if let Some(ecmascript_chunk) = EcmascriptChunkVc::resolve_from(chunk).await? {
+                let mut contained = true;
+                for entry in ecmascript_chunk.await?.main_entries.await?.iter() {
+                    contained &= resolved_entries.contains(&entry.resolve().await?);
+                }
+                if contained {
+                    continue;
+                }
+            }
+            all_chunks.insert(chunk);
+        }
         all_async_chunk_groups.extend(async_chunk_groups.iter().copied());
         all_external_asset_references.extend(external_asset_references.iter().copied());
     }
@@ -324,15 +453,38 @@ async fn ecmascript_chunk_content_single_entry(
     entry: EcmascriptChunkPlaceableVc,
 ) -> Result<EcmascriptChunkContentResultVc> {
     let asset = entry.as_asset();
+    let max_chunk_size = context.chunk_size_limits().await?.max_chunk_size;
+
+    let mut content = chunk_content::<EcmascriptChunkItemVc>(context, asset, None).await?;
+    // Chunks exceeding the max size are split along the module graph, i. e.
+    // modules which can be placed in parallel chunks are moved into chunks of
+    // their own. These are split again when they still exceed the max size.
+    if let (Some(res), Some(max_chunk_size)) = (&content, max_chunk_size) {
+        if code_size(res.chunk_items.iter().copied()).await? > max_chunk_size {
+            content = None;
+        }
+    }
+    let content = match content {
+        Some(res) => res,
+        None => chunk_content_split::<EcmascriptChunkItemVc>(context, asset, None).await?,
+    };
+    Ok(EcmascriptChunkContentResultVc::cell(content.into()))
+}
 
-    Ok(EcmascriptChunkContentResultVc::cell(
-        if let Some(res) = chunk_content::<EcmascriptChunkItemVc>(context, asset, None).await? {
-            res
-        } else {
-            chunk_content_split::<EcmascriptChunkItemVc>(context, asset, None).await?
+/// Returns the size of the code of `chunk_items`, in bytes.
+async fn code_size(chunk_items: impl Iterator<Item = EcmascriptChunkItemVc>) -> Result<usize> {
+    let contents = chunk_items
+        .map(|chunk_item| chunk_item.content())
+        .try_join()
+        .await?;
+    let mut size = 0;
+    for content in contents {
+        size += content.inner_code.len();
+        for concatenated in content.concatenated_code.iter().copied().try_join().await? {
+            size += concatenated.inner_code.len();
         }
-        .into(),
-    ))
+    }
+    Ok(size)
 }
 
 #[turbo_tasks::value(serialization = "none")]
@@ -341,6 +493,12 @@ pub struct EcmascriptChunkContent {
     chunk_path: FileSystemPathVc,
     output_root: FileSystemPathVc,
     evaluate: Option<EcmascriptChunkContentEvaluateVc>,
+    include_runtime: bool,
+    public_path: Option<PublicPath>,
+    chunk_format: ChunkFormat,
+    minify: Option<MinifyOptions>,
+    source_map_format: SourceMapFormat,
+    source_map_emission: SourceMapEmission,
 }
 
 #[turbo_tasks::value(transparent)]
@@ -445,11 +603,24 @@ impl EcmascriptChunkContentVc {
         let chunk_content = chunk_content.await?;
         let module_factories = chunk_content.chunk_items.to_entry_snapshot().await?;
         let output_root = context.output_root();
+        // The runtime is loaded in parallel when it's a chunk of its own
+        let include_runtime = evaluate.is_some() && context.runtime_chunk_path().await?.is_none();
+        let public_path = context.public_path().await?.clone_value();
+        let chunk_format = *context.chunk_format().await?;
+        let minify = *context.minify_options().await?;
+        let source_map_format = context.source_map_format().await?.clone_value();
+        let source_map_emission = *context.source_map_emission().await?;
         Ok(EcmascriptChunkContent {
             module_factories,
             chunk_path,
             output_root,
             evaluate,
+            include_runtime,
+            public_path,
+            chunk_format,
+            minify,
+            source_map_format,
+            source_map_emission,
         }
         .cell())
     }
@@ -483,9 +654,34 @@ impl EcmascriptChunkContentEntryVc {
     #[turbo_tasks::function]
     async fn new(chunk_item: EcmascriptChunkItemVc) -> Result<Self> {
         let content = chunk_item.content();
-        let factory = module_factory(content);
+        let mut factory = module_factory(content);
         let id = chunk_item.id().await?;
-        let code = factory.await?;
+        let code = match factory.await {
+            Ok(code) => code,
+            Err(err) => {
+                if !*chunk_item
+                    .chunking_context()
+                    .is_error_tolerant_emit_enabled()
+                    .await?
+                {
+                    return Err(err);
+                }
+                let path = chunk_item.to_string().await?;
+                CodeGenerationIssue {
+                    severity: IssueSeverity::Error.into(),
+                    path: chunk_item.chunking_context().output_root(),
+                    title: StringVc::cell(format!("Failed to compile {path}")),
+                    message: StringVc::cell(format!("{err:?}")),
+                    // The failing code isn't known, so there is no code frame
+                    source: None,
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                factory = module_factory(error_module_content(&path, &format!("{err}")));
+                factory.await?
+            }
+        };
         let hash = hash_xxh3_hash64(code.source_code().as_bytes());
         Ok(EcmascriptChunkContentEntry {
             chunk_item,
@@ -498,6 +694,20 @@ impl EcmascriptChunkContentEntryVc {
     }
 }
 
+/// The content of a chunk item whose code failed to generate, which throws the
+/// error when it's evaluated.
+#[turbo_tasks::function]
+pub(crate) fn error_module_content(name: &str, error: &str) -> EcmascriptChunkItemContentVc {
+    EcmascriptChunkItemContent {
+        inner_code: format!(
+            "const e = new Error({});\ne.code = 'MODULE_FAILED';\nthrow e;",
+            stringify_str(&format!("Could not compile module '{name}': {error}"))
+        ),
+        ..Default::default()
+    }
+    .cell()
+}
+
 #[turbo_tasks::function]
 async fn module_factory(content: EcmascriptChunkItemContentVc) -> Result<CodeVc> {
     let content = content.await?;
@@ -519,6 +729,15 @@ async fn module_factory(content: EcmascriptChunkItemContentVc) -> Result<CodeVc>
     if content.options.exports {
         args.push("e: exports");
     }
+    if content.options.webpack_require {
+        args.push("w: __turbopack_webpack_require__");
+    }
+    if content.options.hot {
+        args.push("h: __turbopack_hot__");
+    }
+    if content.options.public_path {
+        args.push("u: __turbopack_public_path__");
+    }
     let mut code = Code::new();
     let args = FormatIter(|| args.iter().copied().intersperse(", "));
     if content.options.this {
@@ -526,6 +745,14 @@ async fn module_factory(content: EcmascriptChunkItemContentVc) -> Result<CodeVc>
     } else {
         write!(code, "(({{ {} }}) => (() => {{\n\n", args,)?;
     }
+    for concatenated in content.concatenated_code.iter() {
+        let concatenated = concatenated.await?;
+        let source_map = concatenated
+            .source_map
+            .map(|sm| sm.as_generate_source_map());
+        code.push_source(&concatenated.inner_code, source_map);
+        code += "\n";
+    }
     let source_map = content.source_map.map(|sm| sm.as_generate_source_map());
     code.push_source(&content.inner_code, source_map);
     if content.options.this {
@@ -575,6 +802,10 @@ impl EcmascriptChunkContentVc {
                 this.output_root.to_string().await?
             );
         };
+        // Evaluated chunks of module loader formats export the exports of the
+        // last entry
+        let exports = this.evaluate.is_some() && this.chunk_format != ChunkFormat::Global;
+        code += chunk_prologue(this.chunk_format, exports);
         writeln!(
             code,
             "(self.TURBOPACK = self.TURBOPACK || []).push([{}, {{",
@@ -595,17 +826,50 @@ impl EcmascriptChunkContentVc {
                 .map(|path| format!(" && loadedChunks.has({})", stringify_str(path)))
                 .collect::<Vec<_>>()
                 .join("");
-            let entries_ids = &*evaluate.entry_modules_ids.await?;
-            let entries_instantiations = entries_ids
-                .iter()
-                .map(|id| async move {
-                    let id = id.await?;
-                    let id = stringify_module_id(&id);
-                    Ok(format!(r#"instantiateRuntimeModule({id});"#)) as Result<_>
-                })
-                .try_join()
-                .await?
-                .join("\n");
+            let (mut runtime_params, mut instantiations) =
+                if let Some(eager_modules_ids) = evaluate.eager_modules_ids {
+                    // Modules might have been imported by a module earlier in the list
+                    // already, so they need to be looked up in the cache first.
+                    let instantiations = eager_modules_ids
+                        .await?
+                        .iter()
+                        .map(|id| async move {
+                            let id = id.await?;
+                            let id = stringify_module_id(&id);
+                            Ok(format!(r#"cache[{id}] || instantiateRuntimeModule({id});"#))
+                                as Result<_>
+                        })
+                        .try_join()
+                        .await?
+                        .join("\n    ");
+                    (
+                        "loadedChunks, cache, instantiateRuntimeModule",
+                        instantiations,
+                    )
+                } else {
+                    let entries_ids = &*evaluate.entry_modules_ids.await?;
+                    let instantiations = entries_ids
+                        .iter()
+                        .map(|id| async move {
+                            let id = id.await?;
+                            let id = stringify_module_id(&id);
+                            Ok(format!(r#"instantiateRuntimeModule({id});"#)) as Result<_>
+                        })
+                        .try_join()
+                        .await?
+                        .join("\n");
+                    ("loadedChunks, instantiateRuntimeModule", instantiations)
+                };
+            if exports {
+                if let Some(id) = evaluate.entry_modules_ids.await?.last() {
+                    let id = stringify_module_id(&*id.await?);
+                    write!(
+                        instantiations,
+                        "\n    __turbopack_chunk_exports__(cache[{id}].exports);"
+                    )?;
+                    runtime_params = "loadedChunks, cache, instantiateRuntimeModule";
+                }
+            }
             // Add a runnable to the chunk that requests the entry module to ensure it gets
             // executed when the chunk is evaluated.
             // The condition stops the entry module from being executed while chunks it
@@ -614,27 +878,47 @@ impl EcmascriptChunkContentVc {
             // all dependent chunks have been evaluated.
             write!(
                 code,
-                ", ({{ loadedChunks, instantiateRuntimeModule }}) => {{
+                ", ({{ {runtime_params} }}) => {{
     if(!(true{condition})) return true;
-    {entries_instantiations}
+    {instantiations}
 }}"
             )?;
         }
         code += "]);\n";
-        if this.evaluate.is_some() {
-            let runtime_code = embed_file!("js/src/runtime.js").await?;
-            let runtime_code = match &*runtime_code {
-                FileContent::NotFound => return Err(anyhow!("runtime code is not found")),
-                FileContent::Content(file) => String::from_utf8(file.content().to_vec())
-                    .context("runtime code is invalid UTF-8")?,
-            };
+        if this.include_runtime {
             // Add the turbopack runtime to the chunk.
-            code += runtime_code.as_str();
+            code += runtime_code(this.public_path.as_ref(), this.chunk_format)
+                .await?
+                .as_str();
+        }
+        code += chunk_epilogue(this.chunk_format, exports);
+
+        if let Some(options) = this.minify {
+            let minified = minify(code.cell(), options.cell()).await?;
+            code = Code::new();
+            code.push_code(&minified);
         }
 
         if code.has_source_map() {
-            let filename = chunk_path.file_name();
-            write!(code, "\n\n//# sourceMappingURL={}.map", filename)?;
+            match this.source_map_emission {
+                SourceMapEmission::External => {
+                    let filename = chunk_path.file_name();
+                    write!(code, "\n\n//# sourceMappingURL={}.map", filename)?;
+                }
+                SourceMapEmission::Inline => {
+                    let mut source_map = code.clone().cell().generate_source_map();
+                    if this.source_map_format == SourceMapFormat::Flattened {
+                        source_map = source_map.flatten();
+                    }
+                    let source_map = source_map.to_bytes().await?;
+                    write!(
+                        code,
+                        "\n\n//# sourceMappingURL=data:application/json;charset=utf-8;base64,{}",
+                        base64::encode(&*source_map)
+                    )?;
+                }
+                SourceMapEmission::Hidden => {}
+            }
         }
 
         Ok(code.cell())
@@ -784,7 +1068,40 @@ impl Version for EcmascriptChunkVersion {
 }
 
 #[turbo_tasks::value_impl]
-impl Chunk for EcmascriptChunk {}
+impl Chunk for EcmascriptChunk {
+    #[turbo_tasks::function]
+    async fn chunk_items(&self) -> Result<ChunkItemsVc> {
+        let content =
+            ecmascript_chunk_content(self.context, self.main_entries, self.omit_entries).await?;
+        let mut chunk_items = Vec::new();
+        for chunk in content.chunk_items.await?.iter() {
+            chunk_items.extend(chunk.await?.iter().map(|item| item.as_chunk_item()));
+        }
+        Ok(ChunkItemsVc::cell(chunk_items))
+    }
+
+    #[turbo_tasks::function]
+    async fn module_sizes(&self) -> Result<ModuleSizesVc> {
+        let content =
+            ecmascript_chunk_content(self.context, self.main_entries, self.omit_entries).await?;
+        let mut sizes = IndexMap::new();
+        for chunk in content.chunk_items.await?.iter() {
+            for item in chunk.await?.iter() {
+                if let Some(module) = *item.as_chunk_item().asset().await? {
+                    let content = item.content().await?;
+                    // Concatenated modules are part of the code of the module
+                    // they are concatenated into.
+                    let mut size = content.inner_code.len() as u64;
+                    for concatenated in content.concatenated_code.iter() {
+                        size += concatenated.await?.inner_code.len() as u64;
+                    }
+                    *sizes.entry(module.resolve().await?).or_default() += size;
+                }
+            }
+        }
+        Ok(ModuleSizesVc::cell(sizes))
+    }
+}
 
 #[turbo_tasks::value_impl]
 impl OptimizableChunk for EcmascriptChunk {
@@ -873,6 +1190,12 @@ impl Asset for EcmascriptChunk {
     async fn path(self_vc: EcmascriptChunkVc) -> Result<FileSystemPathVc> {
         let this = self_vc.await?;
 
+        if let Some(evaluate) = this.evaluate {
+            if let Some(filename) = &evaluate.await?.filename {
+                return Ok(this.context.output_root().join(filename));
+            }
+        }
+
         // All information that makes the chunk unique need to be encoded in the path.
         // As we can't make the path that long, we split info into "hashed info" and
         // "named info". All hashed info is hashed and that hash is appended to
@@ -892,6 +1215,14 @@ impl Asset for EcmascriptChunk {
                 need_hash = true;
             }
         }
+        // omitted entries only contribute to the hashed info
+        if let Some(omit_entries) = this.omit_entries {
+            for entry in omit_entries.await?.iter() {
+                let path = entry.path().to_string().await?;
+                hasher.write_value(path);
+                need_hash = true;
+            }
+        }
         let main_entries = this.main_entries.await?;
         // If there is only a single entry we can used that for the named info.
         // If there are multiple entries we hash them and use the common parent as named
@@ -946,14 +1277,36 @@ impl Asset for EcmascriptChunk {
         for chunk_group in content.async_chunk_groups.iter() {
             references.push(ChunkGroupReferenceVc::new(*chunk_group).into());
         }
+        if let Some(evaluate) = this.evaluate {
+            for chunk in evaluate.await?.shared_chunks.iter() {
+                references.push(ChunkReferenceVc::new_parallel((*chunk).into()).into());
+            }
+            if let Some(runtime_chunk_path) = *this.context.runtime_chunk_path().await? {
+                references.push(
+                    ChunkReferenceVc::new_parallel(
+                        EcmascriptRuntimeChunkVc::new(
+                            runtime_chunk_path,
+                            this.context.output_root(),
+                            this.context.public_path(),
+                            this.context.chunk_format(),
+                        )
+                        .into(),
+                    )
+                    .into(),
+                );
+            }
+        }
 
-        references.push(
-            EcmascriptChunkSourceMapAssetReferenceVc::new(
-                self_vc,
-                *this.context.is_hot_module_replacement_enabled().await?,
-            )
-            .into(),
-        );
+        // Inline source maps are part of the chunk, there's no file to emit
+        if *this.context.source_map_emission().await? != SourceMapEmission::Inline {
+            references.push(
+                EcmascriptChunkSourceMapAssetReferenceVc::new(
+                    self_vc,
+                    *this.context.is_hot_module_replacement_enabled().await?,
+                )
+                .into(),
+            );
+        }
 
         Ok(AssetReferencesVc::cell(references))
     }
@@ -1029,22 +1382,31 @@ impl GenerateSourceMap for EcmascriptChunk {
 struct EcmascriptChunkContentEvaluate {
     chunks_server_paths: StringsVc,
     entry_modules_ids: ModuleIdsVc,
+    /// All modules of the chunk group in evaluation order, including the
+    /// entries. Only set for [ModuleEvaluation::Eager].
+    eager_modules_ids: Option<ModuleIdsVc>,
 }
 
 #[turbo_tasks::value]
 pub struct EcmascriptChunkContext {
     context: ChunkingContextVc,
+    module_id_strategy: ModuleIdStrategyVc,
 }
 
 #[turbo_tasks::value_impl]
 impl EcmascriptChunkContextVc {
     #[turbo_tasks::function]
     pub fn of(context: ChunkingContextVc) -> EcmascriptChunkContextVc {
-        EcmascriptChunkContextVc::cell(EcmascriptChunkContext { context })
+        EcmascriptChunkContextVc::cell(EcmascriptChunkContext {
+            context,
+            module_id_strategy: context.module_id_strategy(),
+        })
     }
 
+    /// The readable name of a chunk item, which is used as its module id by
+    /// [ModuleIdStrategy::Named].
     #[turbo_tasks::function]
-    pub async fn chunk_item_id(self, chunk_item: EcmascriptChunkItemVc) -> Result<ModuleIdVc> {
+    pub async fn chunk_item_name(self, chunk_item: EcmascriptChunkItemVc) -> Result<StringVc> {
         let layer = &*self.await?.context.layer().await?;
         let mut s = chunk_item.to_string().await?.clone_value();
         if !layer.is_empty() {
@@ -1055,14 +1417,36 @@ impl EcmascriptChunkContextVc {
                 write!(s, " ({layer})")?;
             }
         }
-        Ok(ModuleId::String(s).cell())
+        Ok(StringVc::cell(s))
+    }
+
+    #[turbo_tasks::function]
+    pub async fn chunk_item_id(self, chunk_item: EcmascriptChunkItemVc) -> Result<ModuleIdVc> {
+        let name = self.chunk_item_name(chunk_item).await?;
+        let id = match &*self.await?.module_id_strategy.await? {
+            ModuleIdStrategy::Named => ModuleId::String(name.clone_value()),
+            ModuleIdStrategy::Hashed(ids) => {
+                let id = match ids {
+                    Some(ids) => ids.await?.get(&*name).cloned(),
+                    None => None,
+                };
+                ModuleId::String(id.unwrap_or_else(|| {
+                    hash_module_name(&name)[..HASHED_MODULE_ID_LENGTH].to_string()
+                }))
+            }
+            ModuleIdStrategy::Numeric(ids) => match ids.await?.get(&*name) {
+                Some(id) => ModuleId::Number(*id),
+                None => ModuleId::String(name.clone_value()),
+            },
+        };
+        Ok(id.cell())
     }
 }
 
 #[turbo_tasks::value(shared)]
 pub enum EcmascriptExports {
     EsmExports(EsmExportsVc),
-    CommonJs,
+    CommonJs(CjsExportsVc),
     Value,
     None,
 }
@@ -1084,20 +1468,40 @@ impl EcmascriptChunkPlaceablesVc {
     }
 }
 
-#[turbo_tasks::value(shared)]
+#[turbo_tasks::value(shared, heap_size)]
 #[derive(Default)]
 pub struct EcmascriptChunkItemContent {
     pub inner_code: String,
     pub source_map: Option<ParseResultSourceMapVc>,
+    /// Code of modules which are concatenated into the scope of this module,
+    /// placed before `inner_code`. Only their code and source map is used.
+    pub concatenated_code: Vec<EcmascriptChunkItemContentVc>,
     pub options: EcmascriptChunkItemOptions,
     pub placeholder_for_future_extensions: (),
 }
 
+impl HeapSize for EcmascriptChunkItemContent {
+    fn heap_size(&self) -> usize {
+        self.inner_code.heap_size()
+            + self.concatenated_code.capacity()
+                * std::mem::size_of::<EcmascriptChunkItemContentVc>()
+    }
+}
+
 #[derive(PartialEq, Eq, Default, Debug, Clone, Serialize, Deserialize, TraceRawVcs)]
 pub struct EcmascriptChunkItemOptions {
     pub module: bool,
     pub exports: bool,
     pub this: bool,
+    /// Whether the module uses `__turbopack_webpack_require__`, the runtime
+    /// shim for `__webpack_require__`.
+    pub webpack_require: bool,
+    /// Whether the module uses `__turbopack_hot__`, the `import.meta.hot` API
+    /// of ES modules.
+    pub hot: bool,
+    /// Whether the module uses `__turbopack_public_path__`, the URL prefix
+    /// chunks and assets are loaded from at runtime.
+    pub public_path: bool,
     pub placeholder_for_future_extensions: (),
 }
 
@@ -1122,8 +1526,21 @@ impl FromChunkableAsset for EcmascriptChunkItemVc {
     async fn from_async_asset(
         context: ChunkingContextVc,
         asset: ChunkableAssetVc,
+        parent_entry: AssetVc,
     ) -> Result<Option<(Self, ChunkableAssetVc)>> {
-        let chunk = ManifestChunkAssetVc::new(asset, context);
+        let available_entries = if *context.is_async_chunk_deduplication_enabled().await? {
+            EcmascriptChunkPlaceableVc::resolve_from(parent_entry)
+                .await?
+                .map(|parent_entry| EcmascriptChunkPlaceablesVc::cell(vec![parent_entry]))
+        } else {
+            None
+        };
+        let parent_entry = if context.common_chunk_options().await?.is_some() {
+            EcmascriptChunkPlaceableVc::resolve_from(parent_entry).await?
+        } else {
+            None
+        };
+        let chunk = ManifestChunkAssetVc::new(asset, context, available_entries, parent_entry);
         Ok(Some((
             ManifestLoaderItemVc::new(context, chunk).into(),
             chunk.into(),
@@ -1139,7 +1556,7 @@ pub struct EcmascriptChunkItems(Vec<EcmascriptChunkItemsChunkVc>);
 
 impl EcmascriptChunkItems {
     pub fn make_chunks(list: &[EcmascriptChunkItemVc]) -> Vec<EcmascriptChunkItemsChunkVc> {
-        let size = list.len().div_ceil(100);
+        let size = list.len().div_ceil(100).max(1);
         let chunk_items = list
             .chunks(size)
             .map(|chunk| EcmascriptChunkItemsChunkVc::cell(chunk.to_vec()))
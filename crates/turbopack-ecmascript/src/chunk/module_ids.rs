@@ -0,0 +1,198 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use turbo_tasks::primitives::StringsVc;
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
+use turbopack_core::chunk::{
+    ChunkItem, ChunkableAssetReferenceVc, ChunkableAssetVc, ChunkingContextVc, ChunkingType,
+    FromChunkableAsset, HashedModuleIdsVc, NumericModuleIdsVc,
+};
+
+use super::{
+    EcmascriptChunkContextVc, EcmascriptChunkItemVc, EcmascriptChunkPlaceableVc,
+    EcmascriptChunkPlaceablesVc,
+};
+
+/// Number of hex digits of hashed module ids. This keeps collisions unlikely
+/// even for module graphs with hundreds of thousands of modules.
+pub(crate) const HASHED_MODULE_ID_LENGTH: usize = 12;
+
+/// Number of hex digits the ids of colliding names are lengthened by, up to
+/// the full length of the hash.
+const HASHED_MODULE_ID_LENGTH_STEP: usize = 2;
+
+/// The full hash of the readable name of a chunk item, as hex digits. Hashed
+/// module ids are prefixes of it.
+pub(crate) fn hash_module_name(name: &str) -> String {
+    encode_hex(hash_xxh3_hash64(name.as_bytes()))
+}
+
+/// The readable names of the chunk items of all modules reachable from
+/// `entries`, in sorted order. This includes the loaders and manifests of
+/// async imports, which are chunk items of their own.
+#[turbo_tasks::function]
+async fn module_names(
+    context: ChunkingContextVc,
+    entries: EcmascriptChunkPlaceablesVc,
+) -> Result<StringsVc> {
+    let chunk_context = EcmascriptChunkContextVc::of(context);
+    let mut names = BTreeSet::new();
+    let mut visited = HashSet::new();
+    let mut queue = entries.await?.iter().copied().collect::<VecDeque<_>>();
+    while let Some(placeable) = queue.pop_front() {
+        let placeable = placeable.resolve().await?;
+        if !visited.insert(placeable) {
+            continue;
+        }
+        let chunk_item = placeable.as_chunk_item(context);
+        names.insert(
+            chunk_context
+                .chunk_item_name(chunk_item)
+                .await?
+                .clone_value(),
+        );
+        for reference in chunk_item.references().await?.iter() {
+            let chunking_type = match ChunkableAssetReferenceVc::resolve_from(reference).await? {
+                Some(reference) => *reference.chunking_type(context).await?,
+                None => None,
+            };
+            for asset in reference.resolve_reference().primary_assets().await?.iter() {
+                if matches!(chunking_type, Some(ChunkingType::SeparateAsync)) {
+                    if let Some(chunkable) = ChunkableAssetVc::resolve_from(asset).await? {
+                        if let Some((loader, manifest)) = EcmascriptChunkItemVc::from_async_asset(
+                            context,
+                            chunkable,
+                            placeable.as_asset(),
+                        )
+                        .await?
+                        {
+                            names
+                                .insert(chunk_context.chunk_item_name(loader).await?.clone_value());
+                            if let Some(manifest) =
+                                EcmascriptChunkPlaceableVc::resolve_from(manifest).await?
+                            {
+                                queue.push_back(manifest);
+                            }
+                        }
+                    }
+                }
+                if let Some(placeable) = EcmascriptChunkPlaceableVc::resolve_from(asset).await? {
+                    queue.push_back(placeable);
+                }
+            }
+        }
+    }
+    Ok(StringsVc::cell(names.into_iter().collect()))
+}
+
+/// Assigns numeric module ids to the chunk items of all modules reachable from
+/// `entries`, in the order of their readable names. The result can be used with
+/// [turbopack_core::chunk::ModuleIdStrategy::Numeric]. Ids change when modules
+/// are added or removed, so they are best suited for single production builds.
+#[turbo_tasks::function]
+pub async fn compute_numeric_module_ids(
+    context: ChunkingContextVc,
+    entries: EcmascriptChunkPlaceablesVc,
+) -> Result<NumericModuleIdsVc> {
+    Ok(NumericModuleIdsVc::cell(
+        module_names(context, entries)
+            .await?
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (name.clone(), id as u32))
+            .collect(),
+    ))
+}
+
+/// Assigns hashed module ids to the chunk items of all modules reachable from
+/// `entries`, which don't collide with each other. The result can be used with
+/// [turbopack_core::chunk::ModuleIdStrategy::Hashed]. Ids only change when the
+/// names of their modules change or when another module's id collides with
+/// them.
+#[turbo_tasks::function]
+pub async fn compute_hashed_module_ids(
+    context: ChunkingContextVc,
+    entries: EcmascriptChunkPlaceablesVc,
+) -> Result<HashedModuleIdsVc> {
+    let names = module_names(context, entries).await?;
+    Ok(HashedModuleIdsVc::cell(assign_hashed_module_ids(
+        names
+            .iter()
+            .map(|name| (name.as_str(), hash_module_name(name))),
+    )))
+}
+
+/// Assigns every name the shortest prefix of its hash which is at least
+/// [HASHED_MODULE_ID_LENGTH] digits long and not shared by another name.
+/// Names whose full hashes collide get the index of the name among them
+/// appended, in the order they are passed in.
+fn assign_hashed_module_ids<'a>(
+    hashes: impl IntoIterator<Item = (&'a str, String)>,
+) -> IndexMap<String, String> {
+    let mut ids = IndexMap::new();
+    let mut pending = hashes.into_iter().collect::<Vec<_>>();
+    let mut length = HASHED_MODULE_ID_LENGTH;
+    while !pending.is_empty() {
+        let mut by_id = BTreeMap::<String, Vec<_>>::new();
+        for (name, hash) in pending {
+            let id = hash[..length.min(hash.len())].to_string();
+            by_id.entry(id).or_default().push((name, hash));
+        }
+        pending = Vec::new();
+        for (id, names) in by_id {
+            match &names[..] {
+                [(name, _)] => {
+                    ids.insert(name.to_string(), id);
+                }
+                [(_, hash), ..] if length >= hash.len() => {
+                    for (index, (name, _)) in names.iter().enumerate() {
+                        ids.insert(name.to_string(), format!("{id}-{index}"));
+                    }
+                }
+                _ => pending.extend(names),
+            }
+        }
+        length += HASHED_MODULE_ID_LENGTH_STEP;
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assign_hashed_module_ids, hash_module_name};
+
+    #[test]
+    fn lengthens_colliding_hashes() {
+        let ids = assign_hashed_module_ids([
+            ("a", "0123456789ab0000".to_string()),
+            ("b", "0123456789ab1100".to_string()),
+            ("c", "0123456789ab1111".to_string()),
+            ("d", "ffffffffffffffff".to_string()),
+            ("e", "ffffffffffffffff".to_string()),
+            ("f", "fedcba9876543210".to_string()),
+        ]);
+        assert_eq!(ids["a"], "0123456789ab00");
+        assert_eq!(ids["b"], "0123456789ab1100");
+        assert_eq!(ids["c"], "0123456789ab1111");
+        assert_eq!(ids["d"], "ffffffffffffffff-0");
+        assert_eq!(ids["e"], "ffffffffffffffff-1");
+        assert_eq!(ids["f"], "fedcba987654");
+    }
+
+    #[test]
+    fn uses_prefixes_of_the_hash() {
+        let ids = assign_hashed_module_ids([
+            ("[project]/a.js", hash_module_name("[project]/a.js")),
+            ("[project]/b.js", hash_module_name("[project]/b.js")),
+        ]);
+        assert_eq!(
+            ids["[project]/a.js"],
+            hash_module_name("[project]/a.js")[..12]
+        );
+        assert_eq!(
+            ids["[project]/b.js"],
+            hash_module_name("[project]/b.js")[..12]
+        );
+    }
+}
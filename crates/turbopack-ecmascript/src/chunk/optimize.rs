@@ -1,12 +1,15 @@
 use std::{cmp::Ordering, collections::HashSet, mem::take};
 
 use anyhow::{bail, Result};
-use indexmap::IndexSet;
-use turbo_tasks::TryJoinIterExt;
+use indexmap::{IndexMap, IndexSet};
+use turbo_tasks::{TryJoinIterExt, ValueToString};
 use turbo_tasks_fs::FileSystemPathOptionVc;
-use turbopack_core::chunk::{
-    optimize::{optimize_by_common_parent, ChunkOptimizer, ChunkOptimizerVc},
-    ChunkGroupVc, ChunkVc, ChunkingContextVc, ChunksVc,
+use turbopack_core::{
+    asset::Asset,
+    chunk::{
+        optimize::{optimize_by_common_parent, ChunkOptimizer, ChunkOptimizerVc},
+        ChunkGroupVc, ChunkSizeLimitsVc, ChunkVc, ChunkingContextVc, ChunksVc,
+    },
 };
 
 use super::{EcmascriptChunkPlaceablesVc, EcmascriptChunkVc};
@@ -27,13 +30,91 @@ impl EcmascriptChunkOptimizerVc {
 impl ChunkOptimizer for EcmascriptChunkOptimizer {
     #[turbo_tasks::function]
     async fn optimize(&self, chunks: ChunksVc, chunk_group: ChunkGroupVc) -> Result<ChunksVc> {
-        optimize_by_common_parent(chunks, get_common_parent, |local, children| {
-            optimize_ecmascript(local, children, chunk_group)
-        })
-        .await
+        let VendorSplit {
+            vendor_chunks,
+            app_chunks,
+        } = split_vendor_chunks(self.0, chunks).await?;
+        let size_limits = self.0.chunk_size_limits();
+        let app_chunks =
+            optimize_by_common_parent(app_chunks, get_common_parent, |local, children| {
+                optimize_ecmascript(local, children, chunk_group, size_limits)
+            })
+            .await?;
+        if vendor_chunks.is_empty() {
+            return Ok(app_chunks);
+        }
+        let mut chunks = vendor_chunks;
+        chunks.extend(app_chunks.await?.iter().copied());
+        Ok(ChunksVc::cell(chunks))
     }
 }
 
+struct VendorSplit {
+    vendor_chunks: Vec<ChunkVc>,
+    app_chunks: ChunksVc,
+}
+
+/// Merges the chunks which only contain entries of the same vendor chunk into
+/// that vendor chunk. The vendor chunks are not optimized together with the
+/// chunks of the application, so they only depend on the vendored modules.
+async fn split_vendor_chunks(context: ChunkingContextVc, chunks: ChunksVc) -> Result<VendorSplit> {
+    let vendor_chunk_groups = context.vendor_chunk_groups();
+    if vendor_chunk_groups.await?.is_empty() {
+        return Ok(VendorSplit {
+            vendor_chunks: Vec::new(),
+            app_chunks: chunks,
+        });
+    }
+    let mut vendors = IndexMap::<String, Vec<(String, EcmascriptChunkVc)>>::new();
+    let mut app_chunks = Vec::new();
+    for &chunk in chunks.await?.iter() {
+        let ecma_chunk = ecma(chunk).await?;
+        let content = ecma_chunk.await?;
+        // Chunks evaluating entries belong to the application
+        let mut vendor = None;
+        if content.evaluate.is_none() {
+            for entry in content.main_entries.await?.iter() {
+                let name = vendor_chunk_groups.vendor_chunk_name(entry.path()).await?;
+                match (&*name, &vendor) {
+                    (Some(name), None) => vendor = Some(name.clone()),
+                    (Some(name), Some(vendor)) if name == vendor => {}
+                    _ => {
+                        vendor = None;
+                        break;
+                    }
+                }
+            }
+        }
+        match vendor {
+            Some(vendor) => {
+                let path = chunk.path().to_string().await?.clone_value();
+                vendors.entry(vendor).or_default().push((path, ecma_chunk));
+            }
+            None => app_chunks.push(chunk),
+        }
+    }
+    let mut vendor_chunks = Vec::new();
+    for (_, mut chunks) in vendors {
+        // The order of the chunks depends on the importing modules. Sorting them
+        // makes the merged chunk the same for every chunk group.
+        chunks.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let chunks = chunks
+            .into_iter()
+            .map(|(_, chunk)| chunk)
+            .collect::<Vec<_>>();
+        let chunk = if chunks.len() == 1 {
+            chunks[0]
+        } else {
+            merge_chunks(chunks[0], &chunks).await?
+        };
+        vendor_chunks.push(chunk.as_chunk());
+    }
+    Ok(VendorSplit {
+        vendor_chunks,
+        app_chunks: ChunksVc::cell(app_chunks),
+    })
+}
+
 async fn ecma(chunk: ChunkVc) -> Result<EcmascriptChunkVc> {
     if let Some(chunk) = EcmascriptChunkVc::resolve_from(chunk).await? {
         Ok(chunk)
@@ -61,10 +142,15 @@ async fn merge_chunks(
         .flat_map(|e| e.iter().copied())
         .collect::<IndexSet<_>>();
     let evaluate = chunks.iter().find_map(|e| e.evaluate);
+    let first = first.await?;
+    // Omitted entries can only be kept when all chunks omit the same entries
+    let omit_entries = first
+        .omit_entries
+        .filter(|omit_entries| chunks.iter().all(|c| c.omit_entries == Some(*omit_entries)));
     Ok(EcmascriptChunkVc::new_normalized(
-        first.await?.context,
+        first.context,
         EcmascriptChunkPlaceablesVc::cell(main_entries.into_iter().collect()),
-        None,
+        omit_entries,
         evaluate,
     ))
 }
@@ -267,11 +353,50 @@ async fn merge_duplicated_and_contained(
     Ok(())
 }
 
+/// Merges chunks with less code than `min_chunk_size` with each other, until
+/// the merged chunks reach the min size. Chunks evaluating entries are kept as
+/// they are.
+async fn merge_small_chunks(
+    chunks: &mut Vec<EcmascriptChunkVc>,
+    min_chunk_size: usize,
+) -> Result<()> {
+    let sizes = chunks
+        .iter()
+        .map(|chunk| chunk.code_size())
+        .try_join()
+        .await?;
+    let mut small_chunks = Vec::new();
+    let mut small_chunks_size = 0;
+    for (chunk, size) in take(chunks).into_iter().zip(sizes) {
+        let size = *size as usize;
+        if size >= min_chunk_size || chunk.await?.evaluate.is_some() {
+            chunks.push(chunk);
+            continue;
+        }
+        small_chunks.push(chunk);
+        // Shared chunk items are counted multiple times, so the merged chunk can
+        // be smaller than this
+        small_chunks_size += size;
+        if small_chunks_size >= min_chunk_size {
+            let merged = take(&mut small_chunks);
+            chunks.push(merge_chunks(merged[0], &merged).await?);
+            small_chunks_size = 0;
+        }
+    }
+    match small_chunks.len() {
+        0 => {}
+        1 => chunks.push(small_chunks[0]),
+        _ => chunks.push(merge_chunks(small_chunks[0], &small_chunks).await?),
+    }
+    Ok(())
+}
+
 #[turbo_tasks::function]
 async fn optimize_ecmascript(
     local: Option<ChunksVc>,
     children: Option<ChunksVc>,
     chunk_group: ChunkGroupVc,
+    size_limits: ChunkSizeLimitsVc,
 ) -> Result<ChunksVc> {
     let mut chunks = Vec::new();
     // TODO optimize
@@ -299,6 +424,8 @@ async fn optimize_ecmascript(
                         EcmascriptChunkEvaluate {
                             evaluate_entries: evaluate.evaluate_entries,
                             chunk_group: Some(chunk_group),
+                            shared_chunks: evaluate.shared_chunks.clone(),
+                            filename: evaluate.filename.clone(),
                         }
                         .cell(),
                     ),
@@ -324,9 +451,10 @@ async fn optimize_ecmascript(
     // bigger chunk should be preferred, to make it smaller.
     // TODO implement that
 
-    // Multiple very small chunks could be merged to avoid requests. (We use a small
-    // threshold for that.)
-    // TODO implement that
+    // Multiple very small chunks are merged to avoid requests.
+    if let Some(min_chunk_size) = size_limits.await?.min_chunk_size {
+        merge_small_chunks(&mut chunks, min_chunk_size).await?;
+    }
 
     // When there are too many chunks, try hard to reduce the number of chunks to
     // limit the request count.
@@ -0,0 +1,187 @@
+use anyhow::{anyhow, bail, Context, Result};
+use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::{embed_file, File, FileContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    chunk::{Chunk, ChunkFormat, ChunkFormatVc, ChunkVc, OptionPublicPathVc, PublicPath},
+    reference::AssetReferencesVc,
+};
+
+use crate::utils::stringify_str;
+
+/// Returns the code of the runtime, which registers chunks and instantiates
+/// their modules. The runtime loads chunks from `public_path`, unless the page
+/// sets `TURBOPACK_PUBLIC_PATH` itself, with the module loader of the host
+/// for chunks in the SystemJS and AMD formats.
+pub(super) async fn runtime_code(
+    public_path: Option<&PublicPath>,
+    chunk_format: ChunkFormat,
+) -> Result<String> {
+    let runtime_code = embed_file!("js/src/runtime.js").await?;
+    let code = match &*runtime_code {
+        FileContent::NotFound => return Err(anyhow!("runtime code is not found")),
+        FileContent::Content(file) => {
+            String::from_utf8(file.content().to_vec()).context("runtime code is invalid UTF-8")?
+        }
+    };
+    let code = match chunk_format {
+        ChunkFormat::Global => code,
+        chunk_format => format!(
+            "self.TURBOPACK_CHUNK_FORMAT = {};\n{}",
+            stringify_str(chunk_format.name()),
+            code
+        ),
+    };
+    Ok(match public_path {
+        Some(public_path) => format!(
+            "if (self.TURBOPACK_PUBLIC_PATH == null) {{\n  self.TURBOPACK_PUBLIC_PATH = \
+             {};\n}}\n{}",
+            stringify_str(&public_path.to_string()),
+            code
+        ),
+        None => code,
+    })
+}
+
+/// Returns the code preceding the code of a chunk in `chunk_format`. With
+/// `exports`, the chunk module exports the value the chunk passes to
+/// `__turbopack_chunk_exports__`.
+pub(super) fn chunk_prologue(chunk_format: ChunkFormat, exports: bool) -> &'static str {
+    match (chunk_format, exports) {
+        (ChunkFormat::Global, _) => "",
+        (ChunkFormat::SystemJs, false) => {
+            "System.register([], function () {
+  return {
+    execute: function () {
+"
+        }
+        (ChunkFormat::SystemJs, true) => {
+            "System.register([], function (__turbopack_export__) {
+  return {
+    execute: function () {
+      return new Promise(function (__turbopack_chunk_exports__) {
+"
+        }
+        (ChunkFormat::Amd, false) => "define(function () {\n",
+        // The factory can't wait for the chunk group, so the module is a
+        // promise of the exports
+        (ChunkFormat::Amd, true) => {
+            "define(function () {
+  return new Promise(function (__turbopack_chunk_exports__) {
+"
+        }
+    }
+}
+
+/// Returns the code following the code of a chunk in `chunk_format`, see
+/// [chunk_prologue].
+pub(super) fn chunk_epilogue(chunk_format: ChunkFormat, exports: bool) -> &'static str {
+    match (chunk_format, exports) {
+        (ChunkFormat::Global, _) => "",
+        (ChunkFormat::SystemJs, false) => {
+            "    },
+  };
+});
+"
+        }
+        // Values which aren't objects, like functions assigned to
+        // `module.exports`, are exported as the default export
+        (ChunkFormat::SystemJs, true) => {
+            "      }).then(function (exports) {
+        if (exports !== null && typeof exports === \"object\") {
+          __turbopack_export__(exports);
+        } else {
+          __turbopack_export__(\"default\", exports);
+        }
+      });
+    },
+  };
+});
+"
+        }
+        (ChunkFormat::Amd, false) => "});\n",
+        (ChunkFormat::Amd, true) => "  });\n});\n",
+    }
+}
+
+/// A chunk which only contains the runtime. It's loaded in parallel to the
+/// chunks evaluating entries, so these don't change when the runtime changes
+/// and all chunk groups share the same runtime chunk.
+#[turbo_tasks::value]
+pub struct EcmascriptRuntimeChunk {
+    path: FileSystemPathVc,
+    output_root: FileSystemPathVc,
+    public_path: OptionPublicPathVc,
+    chunk_format: ChunkFormatVc,
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptRuntimeChunkVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        path: FileSystemPathVc,
+        output_root: FileSystemPathVc,
+        public_path: OptionPublicPathVc,
+        chunk_format: ChunkFormatVc,
+    ) -> Self {
+        EcmascriptRuntimeChunk {
+            path,
+            output_root,
+            public_path,
+            chunk_format,
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Chunk for EcmascriptRuntimeChunk {}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for EcmascriptRuntimeChunk {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "runtime chunk {}",
+            self.path.to_string().await?
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for EcmascriptRuntimeChunk {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let path = &*self.path.await?;
+        let server_path = if let Some(path) = self.output_root.await?.get_path_to(path) {
+            path
+        } else {
+            bail!(
+                "runtime chunk path {} is not in output root {}",
+                self.path.to_string().await?,
+                self.output_root.to_string().await?
+            );
+        };
+        // The chunk registers itself like any other chunk, so chunks waiting for
+        // all chunks of their chunk group to be loaded also wait for the runtime.
+        let chunk_format = *self.chunk_format.await?;
+        let code = format!(
+            "{}(self.TURBOPACK = self.TURBOPACK || []).push([{}, {{}}]);\n{}{}",
+            chunk_prologue(chunk_format, false),
+            stringify_str(server_path),
+            runtime_code(self.public_path.await?.as_ref(), chunk_format).await?,
+            chunk_epilogue(chunk_format, false)
+        );
+        Ok(File::from(code).into())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
@@ -8,7 +8,7 @@ use turbopack_core::{
     code_builder::CodeVc,
     reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
     resolve::{ResolveResult, ResolveResultVc},
-    source_map::GenerateSourceMap,
+    source_map::{GenerateSourceMap, SourceMapFormat},
 };
 
 use super::{EcmascriptChunkItemVc, EcmascriptChunkVc};
@@ -38,12 +38,11 @@ impl Asset for EcmascriptChunkSourceMapAsset {
 
     #[turbo_tasks::function]
     async fn content(&self) -> Result<AssetContentVc> {
-        let sm = self
-            .chunk
-            .chunk_content()
-            .generate_source_map()
-            .to_bytes()
-            .await?;
+        let mut sm = self.chunk.chunk_content().generate_source_map();
+        if *self.chunk.await?.context.source_map_format().await? == SourceMapFormat::Flattened {
+            sm = sm.flatten();
+        }
+        let sm = sm.to_bytes().await?;
         Ok(File::from(sm.as_slice()).into())
     }
 
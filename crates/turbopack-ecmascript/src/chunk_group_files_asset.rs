@@ -3,7 +3,7 @@ use serde_json::Value;
 use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
-    asset::{Asset, AssetContentVc, AssetVc},
+    asset::{Asset, AssetContentVc, AssetOptionVc, AssetVc},
     chunk::{
         ChunkGroupVc, ChunkItem, ChunkItemVc, ChunkReferenceVc, ChunkVc, ChunkableAsset,
         ChunkableAssetVc, ChunkingContextVc, ChunksVc,
@@ -153,4 +153,9 @@ impl ChunkItem for ChunkGroupFilesChunkItem {
                 .collect(),
         ))
     }
+
+    #[turbo_tasks::function]
+    fn asset(&self) -> AssetOptionVc {
+        AssetOptionVc::cell(Some(self.inner.into()))
+    }
 }
@@ -10,10 +10,17 @@ use turbopack_core::chunk::ChunkingContextVc;
     into = "new",
     cell = "new"
 )]
+#[derive(Default)]
 pub struct CodeGeneration {
     /// ast nodes matching the span will be visitor by the visitor
     #[turbo_tasks(debug_ignore, trace_ignore)]
     pub visitors: Vec<(Vec<AstParentKind>, Box<dyn VisitorFactory>)>,
+    /// References which can't be generated and are replaced with code throwing
+    /// at runtime, with the path of the replaced ast node (empty when there is
+    /// no such node) and the reason. They are reported as errors when strict
+    /// reference validation is enabled.
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    pub invalid_references: Vec<(Vec<AstParentKind>, String)>,
 }
 
 pub trait VisitorFactory: Send + Sync {
@@ -0,0 +1,521 @@
+//! Scope hoisting.
+//!
+//! [compute_concatenated_modules] walks a module graph and finds ESM modules
+//! which are only imported by a single other ESM module. The result can be
+//! passed to a chunking context, which makes code generation concatenate these
+//! modules into the scope of their importer. They don't need a module factory
+//! and exports object of their own and imports of their exports become plain
+//! variable references.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::{anyhow, Result};
+use indexmap::{IndexMap, IndexSet};
+use swc_core::ecma::{
+    ast::{
+        ArrowExpr, AssignPat, AssignPatProp, BindingIdent, Class, ClassDecl, Decl, Expr, FnDecl,
+        Function, Id, Ident, KeyValuePatProp, KeyValueProp, MemberProp, ModuleItem, ObjectPatProp,
+        Pat, Program, Prop, PropName, Stmt, SuperProp, VarDecl, VarDeclKind, VarDeclarator,
+    },
+    atoms::JsWord,
+    utils::find_pat_ids,
+    visit::{noop_visit_mut_type, noop_visit_type, Visit, VisitMut, VisitMutWith, VisitWith},
+};
+use turbo_tasks::ValueToString;
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::{Asset, AssetVc},
+    chunk::{
+        concatenation::{ConcatenatedModulesVc, ModuleConcatenation},
+        ChunkableAssetReference, ChunkingContextVc, ChunkingType,
+    },
+};
+
+use crate::{
+    chunk::{
+        EcmascriptChunkItemContent, EcmascriptChunkItemContentVc, EcmascriptChunkPlaceableVc,
+        EcmascriptChunkPlaceablesVc, EcmascriptExports,
+    },
+    magic_identifier,
+    references::esm::{base::ReferencedAsset, EsmAssetReferenceVc, EsmExport},
+    utils::{stringify_module_id, stringify_str},
+    EcmascriptModuleAssetVc,
+};
+
+/// Finds the modules reachable from `entries` which can be concatenated into
+/// the scope of the module importing them. A module is concatenated when
+///  * it is an ESM module which only exports its own bindings,
+///  * all references to it are static ESM imports of named exports from the
+///    same ESM module,
+///  * it is not an entry and
+///  * the module it is concatenated into is not reachable from it.
+#[turbo_tasks::function]
+pub async fn compute_concatenated_modules(
+    entries: EcmascriptChunkPlaceablesVc,
+) -> Result<ConcatenatedModulesVc> {
+    // The referenced assets of each asset, in reference order
+    let mut graph: IndexMap<AssetVc, Vec<AssetVc>> = IndexMap::new();
+    let mut importers: HashMap<AssetVc, IndexSet<AssetVc>> = HashMap::new();
+    let mut not_concatenable = HashSet::new();
+    let mut esm_modules = HashSet::new();
+    let mut local_exports_only = HashSet::new();
+    let mut paths = HashMap::new();
+    let mut assets_per_path: HashMap<FileSystemPathVc, usize> = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    for entry in entries.await?.iter() {
+        let entry = entry.as_asset().resolve().await?;
+        not_concatenable.insert(entry);
+        queue.push_back(entry);
+    }
+
+    while let Some(asset) = queue.pop_front() {
+        if graph.contains_key(&asset) {
+            continue;
+        }
+        let path = asset.path().resolve().await?;
+        paths.insert(asset, path);
+        *assets_per_path.entry(path).or_default() += 1;
+
+        if let Some(module) = EcmascriptModuleAssetVc::resolve_from(asset).await? {
+            match &*module.analyze().await?.exports.await? {
+                EcmascriptExports::EsmExports(exports) => {
+                    let exports = exports.await?;
+                    esm_modules.insert(asset);
+                    if exports.star_exports.is_empty()
+                        && exports
+                            .exports
+                            .values()
+                            .all(|export| matches!(export, EsmExport::LocalBinding(_)))
+                    {
+                        local_exports_only.insert(asset);
+                    }
+                }
+                // ESM modules without exports, scripts have CommonJS exports
+                EcmascriptExports::None => {
+                    esm_modules.insert(asset);
+                    local_exports_only.insert(asset);
+                }
+                _ => {}
+            }
+        }
+
+        let mut targets = Vec::new();
+        for reference in asset.references().await?.iter() {
+            let concatenable = match EsmAssetReferenceVc::resolve_from(reference).await? {
+                Some(reference) => {
+                    let reference = reference.await?;
+                    reference.annotations.chunking_type().is_none()
+                        && (reference.export_name.is_some() || reference.named_imports_only)
+                }
+                None => false,
+            };
+            for target in reference.resolve_reference().primary_assets().await?.iter() {
+                let target = target.resolve().await?;
+                if concatenable {
+                    importers.entry(target).or_default().insert(asset);
+                } else {
+                    not_concatenable.insert(target);
+                }
+                targets.push(target);
+                queue.push_back(target);
+            }
+        }
+        graph.insert(asset, targets);
+    }
+
+    // Modules with the same path can't be told apart by the chunking context
+    let has_unique_path = |asset: &AssetVc| assets_per_path[&paths[asset]] == 1;
+
+    // The module each concatenated module is concatenated into
+    let mut parents = HashMap::new();
+    for (asset, importers) in importers.iter() {
+        if not_concatenable.contains(asset)
+            || !local_exports_only.contains(asset)
+            || importers.len() != 1
+            || !has_unique_path(asset)
+        {
+            continue;
+        }
+        let importer = importers[0];
+        if importer != *asset && esm_modules.contains(&importer) && has_unique_path(&importer) {
+            parents.insert(*asset, importer);
+        }
+    }
+
+    let mut roots;
+    loop {
+        roots = HashMap::new();
+        let mut changed = false;
+        for &asset in graph.keys() {
+            if !parents.contains_key(&asset) {
+                continue;
+            }
+            let mut current = asset;
+            let mut seen = HashSet::new();
+            while let Some(&parent) = parents.get(&current) {
+                if !seen.insert(current) {
+                    break;
+                }
+                current = parent;
+            }
+            if parents.contains_key(&current) {
+                // The chain of importers is a cycle
+                parents.remove(&asset);
+                changed = true;
+            } else {
+                roots.insert(asset, current);
+            }
+        }
+        if changed {
+            continue;
+        }
+
+        // Concatenated modules are evaluated before the module they are
+        // concatenated into, which is incorrect when they import it.
+        let mut groups: IndexMap<AssetVc, Vec<AssetVc>> = IndexMap::new();
+        for &asset in graph.keys() {
+            if let Some(root) = roots.get(&asset) {
+                groups.entry(*root).or_default().push(asset);
+            }
+        }
+        for (root, members) in groups.iter() {
+            let members = members.iter().copied().collect::<HashSet<_>>();
+            let mut visited = HashSet::new();
+            let mut queue = members.iter().copied().collect::<VecDeque<_>>();
+            let mut reaches_root = false;
+            while let Some(asset) = queue.pop_front() {
+                if asset == *root {
+                    reaches_root = true;
+                    break;
+                }
+                if visited.insert(asset) {
+                    queue.extend(graph[&asset].iter().copied());
+                }
+            }
+            if reaches_root {
+                parents.retain(|_, parent| *parent != *root);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    fn collect_members(
+        asset: AssetVc,
+        graph: &IndexMap<AssetVc, Vec<AssetVc>>,
+        parents: &HashMap<AssetVc, AssetVc>,
+        members: &mut Vec<AssetVc>,
+    ) {
+        for target in graph[&asset].iter() {
+            if parents.get(target) == Some(&asset) && !members.contains(target) {
+                collect_members(*target, graph, parents, members);
+                members.push(*target);
+            }
+        }
+    }
+
+    let root_assets = roots.values().copied().collect::<HashSet<_>>();
+    let mut concatenated_modules = IndexMap::new();
+    for &asset in graph.keys() {
+        if roots.contains_key(&asset) {
+            concatenated_modules.insert(paths[&asset], ModuleConcatenation::Inner);
+        } else if root_assets.contains(&asset) {
+            let mut members = Vec::new();
+            collect_members(asset, &graph, &parents, &mut members);
+            concatenated_modules.insert(paths[&asset], ModuleConcatenation::Root(members));
+        }
+    }
+
+    Ok(ConcatenatedModulesVc::cell(concatenated_modules))
+}
+
+/// Whether `module` is concatenated into the scope of the module importing
+/// it.
+pub(crate) async fn is_concatenated(
+    module: EcmascriptChunkPlaceableVc,
+    context: ChunkingContextVc,
+) -> Result<bool> {
+    Ok(matches!(
+        *context.module_concatenation(module.path()).await?,
+        ModuleConcatenation::Inner
+    ))
+}
+
+/// The name of the top level binding `name` of the module at `path` when the
+/// module is concatenated into another module.
+pub(crate) fn concatenated_ident(name: &str, path: &str) -> String {
+    magic_identifier::encode(&format!("{} in {}", name, path))
+}
+
+/// The name of the variable holding the export `export` of the concatenated
+/// module `module`.
+pub(crate) async fn concatenated_export_ident(
+    module: EcmascriptChunkPlaceableVc,
+    export: &str,
+) -> Result<Option<String>> {
+    if let EcmascriptExports::EsmExports(exports) = &*module.get_exports().await? {
+        if let Some(EsmExport::LocalBinding(name)) = exports.await?.exports.get(export) {
+            let path = module.path().to_string().await?;
+            return Ok(Some(concatenated_ident(name, &path)));
+        }
+    }
+    Ok(None)
+}
+
+/// The code of the modules concatenated into `root`, in evaluation order.
+/// Modules imported by the concatenated modules are imported at the position
+/// they would be evaluated at without concatenation.
+pub(crate) async fn concatenated_code(
+    root: EcmascriptModuleAssetVc,
+    members: &[AssetVc],
+    context: ChunkingContextVc,
+) -> Result<Vec<EcmascriptChunkItemContentVc>> {
+    let mut resolved_members = HashSet::new();
+    for member in members {
+        resolved_members.insert(member.resolve().await?);
+    }
+
+    let mut code = Vec::new();
+    let mut imported = HashSet::new();
+    let mut visited = HashSet::new();
+    let mut stack = vec![(
+        root.as_ecmascript_chunk_placeable(),
+        esm_references(root, context).await?.into_iter(),
+    )];
+    while let Some((module, references)) = stack.last_mut() {
+        let reference = match references.next() {
+            Some(reference) => reference,
+            None => {
+                let module = *module;
+                // The code of the root module itself is not part of the
+                // concatenated code
+                if stack.len() > 1 {
+                    code.push(module.as_chunk_item(context).content());
+                }
+                stack.pop();
+                continue;
+            }
+        };
+        match &*reference.get_referenced_asset().await? {
+            ReferencedAsset::Some(asset) => {
+                let asset = asset.resolve().await?;
+                if resolved_members.contains(&asset.as_asset()) {
+                    if visited.insert(asset) {
+                        let module = EcmascriptModuleAssetVc::resolve_from(asset)
+                            .await?
+                            .ok_or_else(|| {
+                                anyhow!("concatenated module is not an ecmascript module")
+                            })?;
+                        let references = esm_references(module, context).await?;
+                        stack.push((asset, references.into_iter()));
+                    }
+                } else if imported.insert(ImportedModule::Module(asset)) {
+                    let id = asset.as_chunk_item(context).id().await?;
+                    code.push(
+                        EcmascriptChunkItemContent {
+                            inner_code: format!(
+                                "__turbopack_import__({});",
+                                stringify_module_id(&id)
+                            ),
+                            ..Default::default()
+                        }
+                        .cell(),
+                    );
+                }
+            }
+            ReferencedAsset::OriginalReferenceTypeExternal(request) => {
+                if imported.insert(ImportedModule::External(request.clone())) {
+                    code.push(
+                        EcmascriptChunkItemContent {
+                            inner_code: format!(
+                                "__turbopack_external_require__({});",
+                                stringify_str(request)
+                            ),
+                            ..Default::default()
+                        }
+                        .cell(),
+                    );
+                }
+            }
+            // Global variables are read where they are used
+            ReferencedAsset::GlobalExternal(_) | ReferencedAsset::None => {}
+        }
+    }
+    Ok(code)
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum ImportedModule {
+    Module(EcmascriptChunkPlaceableVc),
+    External(String),
+}
+
+/// The ESM references of `module` which import a module while it's evaluated,
+/// in reference order.
+async fn esm_references(
+    module: EcmascriptModuleAssetVc,
+    context: ChunkingContextVc,
+) -> Result<Vec<EsmAssetReferenceVc>> {
+    let mut references = Vec::new();
+    for reference in module.analyze().await?.references.await?.iter() {
+        if let Some(reference) = EsmAssetReferenceVc::resolve_from(reference).await? {
+            if matches!(
+                *reference.chunking_type(context).await?,
+                Some(ChunkingType::Separate) | None
+            ) {
+                continue;
+            }
+            references.push(reference);
+        }
+    }
+    Ok(references)
+}
+
+/// Renames the top level bindings of a module which is concatenated into
+/// another module, so they don't collide with the bindings of the other
+/// modules in the same scope.
+pub(crate) fn rename_top_level_bindings(program: &mut Program, path: &str) {
+    let mut collector = TopLevelBindingsCollector::default();
+    match program {
+        Program::Module(module) => {
+            for item in module.body.iter() {
+                match item {
+                    ModuleItem::Stmt(stmt) => collector.collect_stmt(stmt),
+                    ModuleItem::ModuleDecl(decl) => decl.visit_with(&mut collector),
+                }
+            }
+        }
+        Program::Script(script) => {
+            for stmt in script.body.iter() {
+                collector.collect_stmt(stmt);
+            }
+        }
+    }
+    let renames = collector
+        .bindings
+        .into_iter()
+        .map(|id| {
+            let renamed = concatenated_ident(&id.0, path);
+            (id, JsWord::from(renamed))
+        })
+        .collect();
+    program.visit_mut_with(&mut TopLevelBindingsRenamer { renames });
+}
+
+#[derive(Default)]
+struct TopLevelBindingsCollector {
+    bindings: HashSet<Id>,
+}
+
+impl TopLevelBindingsCollector {
+    fn collect_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Decl(Decl::Class(ClassDecl { ident, .. }))
+            | Stmt::Decl(Decl::Fn(FnDecl { ident, .. })) => {
+                self.bindings.insert(ident.to_id());
+            }
+            Stmt::Decl(Decl::Var(var)) => {
+                self.bindings.extend(find_pat_ids::<_, Id>(&var.decls));
+            }
+            _ => stmt.visit_with(self),
+        }
+    }
+}
+
+/// Collects the `var` declarations nested in top level statements, which are
+/// top level bindings too.
+impl Visit for TopLevelBindingsCollector {
+    noop_visit_type!();
+
+    fn visit_var_declarator(&mut self, _: &VarDeclarator) {}
+
+    fn visit_var_decl(&mut self, var: &VarDecl) {
+        if var.kind == VarDeclKind::Var {
+            self.bindings.extend(find_pat_ids::<_, Id>(&var.decls));
+        }
+        var.visit_children_with(self);
+    }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+
+    fn visit_class(&mut self, _: &Class) {}
+}
+
+struct TopLevelBindingsRenamer {
+    renames: HashMap<Id, JsWord>,
+}
+
+impl VisitMut for TopLevelBindingsRenamer {
+    noop_visit_mut_type!();
+
+    fn visit_mut_ident(&mut self, ident: &mut Ident) {
+        if let Some(renamed) = self.renames.get(&ident.to_id()) {
+            ident.sym = renamed.clone();
+        }
+    }
+
+    fn visit_mut_member_prop(&mut self, prop: &mut MemberProp) {
+        if let MemberProp::Computed(computed) = prop {
+            computed.visit_mut_with(self);
+        }
+    }
+
+    fn visit_mut_super_prop(&mut self, prop: &mut SuperProp) {
+        if let SuperProp::Computed(computed) = prop {
+            computed.visit_mut_with(self);
+        }
+    }
+
+    fn visit_mut_prop_name(&mut self, name: &mut PropName) {
+        if let PropName::Computed(computed) = name {
+            computed.visit_mut_with(self);
+        }
+    }
+
+    fn visit_mut_prop(&mut self, prop: &mut Prop) {
+        if let Prop::Shorthand(ident) = prop {
+            if let Some(renamed) = self.renames.get(&ident.to_id()) {
+                *prop = Prop::KeyValue(KeyValueProp {
+                    key: PropName::Ident(ident.clone()),
+                    value: box Expr::Ident(Ident::new(renamed.clone(), ident.span)),
+                });
+                return;
+            }
+        }
+        prop.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_object_pat_prop(&mut self, prop: &mut ObjectPatProp) {
+        if let ObjectPatProp::Assign(AssignPatProp { span, key, value }) = prop {
+            if let Some(renamed) = self.renames.get(&key.to_id()) {
+                let binding = Pat::Ident(BindingIdent {
+                    id: Ident::new(renamed.clone(), key.span),
+                    type_ann: None,
+                });
+                let value = match value.take() {
+                    Some(mut default) => {
+                        default.visit_mut_with(self);
+                        Pat::Assign(AssignPat {
+                            span: *span,
+                            left: box binding,
+                            right: default,
+                            type_ann: None,
+                        })
+                    }
+                    None => binding,
+                };
+                *prop = ObjectPatProp::KeyValue(KeyValuePatProp {
+                    key: PropName::Ident(key.clone()),
+                    value: box value,
+                });
+                return;
+            }
+        }
+        prop.visit_mut_children_with(self);
+    }
+}
@@ -0,0 +1,123 @@
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    fmt::Write as _,
+};
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, CompletionVc, ValueToString};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::Asset,
+    issue::{codes, Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+};
+
+use crate::{chunk::EcmascriptChunkPlaceablesVc, EcmascriptModuleAssetVc};
+
+/// Lists `process.env` variables which are read by modules, but have no value
+/// in the environment those modules are compiled for.
+#[turbo_tasks::value(shared)]
+pub struct UndefinedEnvIssue {
+    pub path: FileSystemPathVc,
+    /// Variable names, mapped to the modules reading them.
+    pub variables: BTreeMap<String, Vec<FileSystemPathVc>>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for UndefinedEnvIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Environment variables are read but not defined".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("analyze".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::config::UNDEFINED_ENV.to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn description(&self) -> Result<StringVc> {
+        let mut description = "The following variables are read from `process.env`, but have no \
+                               value at build time. They will be `undefined` at runtime:\n"
+            .to_string();
+        for (name, modules) in self.variables.iter() {
+            write!(description, "\n- {name} (read by ")?;
+            for (i, module) in modules.iter().enumerate() {
+                if i > 0 {
+                    description.push_str(", ");
+                }
+                description.push_str(&module.to_string().await?);
+            }
+            description.push(')');
+        }
+        Ok(StringVc::cell(description))
+    }
+}
+
+/// Collects the statically detected `process.env.X` reads of all modules
+/// reachable from `entries` and emits a single [UndefinedEnvIssue] for the
+/// variables which are not defined in the environment of the reading module.
+/// Environments which read `process.env` at runtime or don't know their
+/// variables are not checked.
+#[turbo_tasks::function]
+pub async fn check_undefined_env_reads(
+    entries: EcmascriptChunkPlaceablesVc,
+) -> Result<CompletionVc> {
+    let mut variables: BTreeMap<String, Vec<FileSystemPathVc>> = BTreeMap::new();
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    let entries = entries.await?;
+    for entry in entries.iter() {
+        queue.push_back(entry.as_asset().resolve().await?);
+    }
+
+    while let Some(asset) = queue.pop_front() {
+        if !visited.insert(asset) {
+            continue;
+        }
+        if let Some(module) = EcmascriptModuleAssetVc::resolve_from(asset).await? {
+            let environment = module.await?.environment;
+            if *environment.defines_env().await? && !*environment.runtime_env().await? {
+                for name in module.analyze().await?.process_env_reads.await?.iter() {
+                    if environment.read_defined_env(name).await?.is_none() {
+                        variables
+                            .entry(name.clone())
+                            .or_default()
+                            .push(asset.path());
+                    }
+                }
+            }
+        }
+        for reference in asset.references().await?.iter() {
+            for target in reference.resolve_reference().primary_assets().await?.iter() {
+                queue.push_back(target.resolve().await?);
+            }
+        }
+    }
+
+    if let (false, Some(entry)) = (variables.is_empty(), entries.first()) {
+        UndefinedEnvIssue {
+            path: entry.path(),
+            variables,
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    }
+
+    Ok(CompletionVc::new())
+}
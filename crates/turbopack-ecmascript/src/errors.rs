@@ -6,6 +6,7 @@ pub mod failed_to_analyse {
         pub const FS_METHOD: &str = "TP1004";
         pub const CHILD_PROCESS_SPAWN: &str = "TP1005";
         pub const PATH_METHOD: &str = "TP1006";
+        pub const REQUIRE_ENSURE: &str = "TP1007";
         pub const NODE_PRE_GYP_FIND: &str = "TP1100";
         pub const NODE_GYP_BUILD: &str = "TP1101";
         pub const NODE_BINDINGS: &str = "TP1102";
@@ -15,4 +16,11 @@ pub mod failed_to_analyse {
         pub const AMD_DEFINE: &str = "TP1200";
         pub const IMPORT_META: &str = "TP1106";
     }
+
+    pub mod esm {
+        pub const EXPORT_STAR_WITHOUT_EXPORTS: &str = "TP1300";
+        pub const EXPORT_STAR_WITH_DEFAULT_ONLY: &str = "TP1301";
+        pub const EXPORT_STAR_WITH_DYNAMIC_EXPORTS: &str = "TP1302";
+        pub const IMPORT_NOT_FOUND_IN_COMMONJS: &str = "TP1303";
+    }
 }
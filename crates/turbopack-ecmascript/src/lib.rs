@@ -10,15 +10,20 @@ pub mod analyzer;
 pub mod chunk;
 pub mod chunk_group_files_asset;
 pub mod code_gen;
-mod errors;
+pub mod concatenation;
+pub mod env_reads;
+pub mod errors;
 pub mod magic_identifier;
 pub(crate) mod parse;
 mod path_visitor;
+pub mod react_refresh;
 pub(crate) mod references;
 pub mod resolve;
+pub mod side_effects;
 pub(crate) mod special_cases;
 pub(crate) mod transform;
 pub mod typescript;
+pub mod used_exports;
 pub mod utils;
 pub mod webpack;
 
@@ -27,37 +32,48 @@ use chunk::{
     EcmascriptChunkItem, EcmascriptChunkItemVc, EcmascriptChunkPlaceablesVc, EcmascriptChunkVc,
 };
 use code_gen::CodeGenerateableVc;
-use parse::{parse, ParseResult, ParseResultSourceMap};
-use path_visitor::ApplyVisitors;
+use parse::{input_source_map, parse, ParseResult, ParseResultSourceMap};
+use path_visitor::{find_node_spans, ApplyVisitors};
+pub use references::esm::{
+    EsmAssetReference, EsmAssetReferenceVc, EsmExport, EsmExports, EsmExportsVc,
+};
 use references::AnalyzeEcmascriptModuleResult;
 use swc_core::{
-    common::GLOBALS,
+    common::{source_map::Pos, GLOBALS},
     ecma::{
         codegen::{text_writer::JsWriter, Emitter},
         visit::{VisitMutWith, VisitMutWithPath},
     },
 };
-pub use transform::{EcmascriptInputTransform, EcmascriptInputTransformsVc};
+pub use transform::{
+    CompileTimeDefineValue, CompileTimeDefines, CompileTimeDefinesVc, EcmascriptInputTransform,
+    EcmascriptInputTransformsVc, JsxRuntime,
+};
 use turbo_tasks::{primitives::StringVc, TryJoinIterExt, Value, ValueToString, ValueToStringVc};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
-    asset::{Asset, AssetContentVc, AssetVc},
-    chunk::{ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContextVc},
+    asset::{Asset, AssetContentVc, AssetOptionVc, AssetVc},
+    chunk::{
+        concatenation::ModuleConcatenation, ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset,
+        ChunkableAssetVc, ChunkingContextVc,
+    },
     context::AssetContextVc,
     environment::EnvironmentVc,
+    issue::{code_gen::InvalidReferenceIssue, panic::catch_panic, IssueSourceVc},
     reference::AssetReferencesVc,
     resolve::origin::{ResolveOrigin, ResolveOriginVc},
 };
 
 use self::{
     chunk::{
-        EcmascriptChunkItemContent, EcmascriptChunkItemContentVc, EcmascriptChunkItemOptions,
-        EcmascriptExportsVc,
+        error_module_content, EcmascriptChunkItemContent, EcmascriptChunkItemContentVc,
+        EcmascriptChunkItemOptions, EcmascriptExportsVc,
     },
     references::AnalyzeEcmascriptModuleResultVc,
 };
 use crate::{
     chunk::{EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc},
+    concatenation::{concatenated_code, rename_top_level_bindings},
     references::analyze_ecmascript_module,
 };
 
@@ -199,8 +215,42 @@ impl ValueToString for ModuleChunkItem {
 #[turbo_tasks::value_impl]
 impl ChunkItem for ModuleChunkItem {
     #[turbo_tasks::function]
-    fn references(&self) -> AssetReferencesVc {
-        self.module.references()
+    async fn references(&self) -> Result<AssetReferencesVc> {
+        let members = match &*self
+            .context
+            .module_concatenation(self.module.path())
+            .await?
+        {
+            ModuleConcatenation::Root(members) => {
+                members
+                    .iter()
+                    .map(|member| member.resolve())
+                    .try_join()
+                    .await?
+            }
+            _ => return Ok(self.module.references()),
+        };
+        // The references of the concatenated modules are part of this module, the
+        // concatenated modules themselves are not
+        let mut references = Vec::new();
+        for module in std::iter::once(self.module.as_asset()).chain(members.iter().copied()) {
+            for reference in module.references().await?.iter() {
+                let assets = reference.resolve_reference().primary_assets().await?;
+                let mut is_concatenated = !assets.is_empty();
+                for asset in assets.iter() {
+                    is_concatenated &= members.contains(&asset.resolve().await?);
+                }
+                if !is_concatenated {
+                    references.push(*reference);
+                }
+            }
+        }
+        Ok(AssetReferencesVc::cell(references))
+    }
+
+    #[turbo_tasks::function]
+    fn asset(&self) -> AssetOptionVc {
+        AssetOptionVc::cell(Some(self.module.into()))
     }
 }
 
@@ -213,11 +263,33 @@ impl EcmascriptChunkItem for ModuleChunkItem {
 
     #[turbo_tasks::function]
     async fn content(&self) -> Result<EcmascriptChunkItemContentVc> {
+        let path = self.module.path();
+        Ok(
+            match catch_panic(path, "generating code for", self.module_content()).await? {
+                Some(content) => content,
+                None => error_module_content(
+                    &path.to_string().await?,
+                    "turbopack panicked while generating its code",
+                ),
+            },
+        )
+    }
+}
+
+impl ModuleChunkItem {
+    async fn module_content(&self) -> Result<EcmascriptChunkItemContentVc> {
         let AnalyzeEcmascriptModuleResult {
             references,
             code_generation,
+            panicked,
             ..
         } = &*self.module.analyze().await?;
+        if let Some(stage) = panicked {
+            return Ok(error_module_content(
+                &self.module.path().to_string().await?,
+                &format!("turbopack panicked while {stage} it"),
+            ));
+        }
         let context = self.context;
         let mut code_gens = Vec::new();
         for r in references.await?.iter() {
@@ -234,6 +306,7 @@ impl EcmascriptChunkItem for ModuleChunkItem {
         // TOOD use interval tree with references into "code_gens"
         let mut visitors = Vec::new();
         let mut root_visitors = Vec::new();
+        let mut invalid_references = Vec::new();
         for code_gen in code_gens {
             for (path, visitor) in code_gen.visitors.iter() {
                 if path.is_empty() {
@@ -242,10 +315,27 @@ impl EcmascriptChunkItem for ModuleChunkItem {
                     visitors.push((path, &**visitor));
                 }
             }
+            invalid_references.extend(code_gen.invalid_references.iter());
         }
 
+        let (concatenated, concatenated_path) = match &*context
+            .module_concatenation(self.module.path())
+            .await?
+        {
+            ModuleConcatenation::None => (Vec::new(), None),
+            ModuleConcatenation::Root(members) => (
+                concatenated_code(self.module, members, context).await?,
+                None,
+            ),
+            ModuleConcatenation::Inner => (Vec::new(), Some(self.module.path().to_string().await?)),
+        };
+
         let module = self.module.await?;
         let parsed = parse(module.source, Value::new(module.ty), module.transforms).await?;
+        let webpack_require = module
+            .transforms
+            .await?
+            .contains(&EcmascriptInputTransform::WebpackRuntimeShims);
 
         if let ParseResult::Ok {
             program,
@@ -255,6 +345,29 @@ impl EcmascriptChunkItem for ModuleChunkItem {
             ..
         } = &*parsed
         {
+            if !invalid_references.is_empty()
+                && *context.is_strict_reference_validation_enabled().await?
+            {
+                let spans =
+                    find_node_spans(program, invalid_references.iter().map(|(path, _)| path));
+                for (path, message) in invalid_references {
+                    InvalidReferenceIssue {
+                        path: self.module.path(),
+                        message: StringVc::cell(message.clone()),
+                        source: spans.get(path).map(|span| {
+                            IssueSourceVc::from_byte_offset(
+                                module.source,
+                                source_map.lookup_byte_offset(span.lo()).pos.to_usize(),
+                                source_map.lookup_byte_offset(span.hi()).pos.to_usize(),
+                            )
+                        }),
+                    }
+                    .cell()
+                    .as_issue()
+                    .emit();
+                }
+            }
+
             let mut program = program.clone();
 
             GLOBALS.set(globals, || {
@@ -267,6 +380,9 @@ impl EcmascriptChunkItem for ModuleChunkItem {
                 for visitor in root_visitors {
                     program.visit_mut_with(&mut visitor.create());
                 }
+                if let Some(path) = &concatenated_path {
+                    rename_top_level_bindings(&mut program, path);
+                }
                 program.visit_mut_with(&mut swc_core::ecma::transforms::base::fixer::fixer(None));
             });
 
@@ -276,24 +392,34 @@ impl EcmascriptChunkItem for ModuleChunkItem {
 
             let mut srcmap = vec![];
 
-            let mut emitter = Emitter {
-                cfg: swc_core::ecma::codegen::Config {
-                    ..Default::default()
-                },
-                cm: source_map.clone(),
-                comments: None,
-                wr: JsWriter::new(source_map.clone(), "\n", &mut bytes, Some(&mut srcmap)),
-            };
+            {
+                let mut emitter = Emitter {
+                    cfg: swc_core::ecma::codegen::Config {
+                        ..Default::default()
+                    },
+                    cm: source_map.clone(),
+                    comments: None,
+                    wr: JsWriter::new(source_map.clone(), "\n", &mut bytes, Some(&mut srcmap)),
+                };
 
-            emitter.emit_program(&program)?;
+                emitter.emit_program(&program)?;
+            }
 
-            let srcmap = ParseResultSourceMap::new(source_map.clone(), srcmap).cell();
+            let srcmap = ParseResultSourceMap::new(
+                source_map.clone(),
+                srcmap,
+                input_source_map(module.source),
+            )
+            .cell();
 
             Ok(EcmascriptChunkItemContent {
                 inner_code: String::from_utf8(bytes)?,
                 source_map: Some(srcmap),
+                concatenated_code: concatenated,
                 options: if eval_context.is_esm() {
                     EcmascriptChunkItemOptions {
+                        webpack_require,
+                        hot: *context.is_hot_module_replacement_enabled().await?,
                         ..Default::default()
                     }
                 } else {
@@ -302,6 +428,7 @@ impl EcmascriptChunkItem for ModuleChunkItem {
                         module: true,
                         exports: true,
                         this: true,
+                        webpack_require,
                         ..Default::default()
                     }
                 },
@@ -19,24 +19,28 @@ use swc_core::{
         visit::VisitMutWith,
     },
 };
-use turbo_tasks::{primitives::U64Vc, Value};
+use turbo_tasks::{primitives::U64Vc, HeapSize, Value};
 use turbo_tasks_fs::{FileContent, FileSystemPath, FileSystemPathVc};
 use turbo_tasks_hash::{DeterministicHasher, Xxh3Hash64Hasher};
 use turbopack_core::{
     asset::{AssetContent, AssetVc},
-    source_map::{GenerateSourceMap, GenerateSourceMapVc, SourceMapVc},
+    issue::panic::catch_panic,
+    source_map::{
+        GenerateSourceMap, GenerateSourceMapVc, OptionSourceMapVc, SourceMap as TurbopackSourceMap,
+        SourceMapVc,
+    },
 };
 use turbopack_swc_utils::emitter::IssueEmitter;
 
 use super::EcmascriptModuleAssetType;
 use crate::{
     analyzer::graph::EvalContext,
-    transform::{EcmascriptInputTransformsVc, TransformContext},
+    transform::{require_ensure_callbacks, EcmascriptInputTransformsVc, TransformContext},
     utils::WrapFuture,
     EcmascriptInputTransform,
 };
 
-#[turbo_tasks::value(shared, serialization = "none", eq = "manual")]
+#[turbo_tasks::value(shared, serialization = "none", eq = "manual", heap_size)]
 #[allow(clippy::large_enum_variant)]
 pub enum ParseResult {
     Ok {
@@ -52,9 +56,35 @@ pub enum ParseResult {
         source_map: Arc<SourceMap>,
     },
     Unparseable,
+    /// Parsing the module panicked, which is reported as a [PanicIssue].
+    ///
+    /// [PanicIssue]: turbopack_core::issue::panic::PanicIssue
+    Panicked,
     NotFound,
 }
 
+/// The estimated memory an AST and the related data takes per byte of its
+/// source code.
+const PARSED_BYTES_PER_SOURCE_BYTE: usize = 10;
+
+/// The AST isn't walked, its size is estimated from the size of the source
+/// code, which the source map holds, too.
+impl HeapSize for ParseResult {
+    fn heap_size(&self) -> usize {
+        match self {
+            ParseResult::Ok { source_map, .. } => {
+                source_map
+                    .files()
+                    .iter()
+                    .map(|file| file.src.len())
+                    .sum::<usize>()
+                    * (1 + PARSED_BYTES_PER_SOURCE_BYTE)
+            }
+            ParseResult::Unparseable | ParseResult::Panicked | ParseResult::NotFound => 0,
+        }
+    }
+}
+
 impl PartialEq for ParseResult {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
@@ -76,19 +106,30 @@ pub struct ParseResultSourceMap {
     /// SourceMap.
     #[turbo_tasks(debug_ignore, trace_ignore)]
     mappings: Vec<(BytePos, LineCol)>,
+
+    /// The source map of the parsed file itself, when it was compiled from
+    /// other sources. The generated map is chained with it.
+    original_source_map: OptionSourceMapVc,
 }
 
 impl PartialEq for ParseResultSourceMap {
     fn eq(&self, other: &Self) -> bool {
-        Arc::ptr_eq(&self.source_map, &other.source_map) && self.mappings == other.mappings
+        Arc::ptr_eq(&self.source_map, &other.source_map)
+            && self.mappings == other.mappings
+            && self.original_source_map == other.original_source_map
     }
 }
 
 impl ParseResultSourceMap {
-    pub fn new(source_map: Arc<SourceMap>, mappings: Vec<(BytePos, LineCol)>) -> Self {
+    pub fn new(
+        source_map: Arc<SourceMap>,
+        mappings: Vec<(BytePos, LineCol)>,
+        original_source_map: OptionSourceMapVc,
+    ) -> Self {
         ParseResultSourceMap {
             source_map,
             mappings,
+            original_source_map,
         }
     }
 }
@@ -96,17 +137,78 @@ impl ParseResultSourceMap {
 #[turbo_tasks::value_impl]
 impl GenerateSourceMap for ParseResultSourceMap {
     #[turbo_tasks::function]
-    fn generate_source_map(&self) -> SourceMapVc {
+    async fn generate_source_map(&self) -> Result<SourceMapVc> {
+        let original_source_map = match *self.original_source_map.await? {
+            Some(map) => Some(map.await?),
+            None => None,
+        };
+        let original_source_map = match original_source_map.as_deref() {
+            Some(TurbopackSourceMap::Regular(map)) => Some(&****map),
+            _ => None,
+        };
         let map = self.source_map.build_source_map_with_config(
             // SWC expects a mutable vec, but it never modifies. Seems like an oversight.
             &mut self.mappings.clone(),
-            None,
+            original_source_map,
             InlineSourcesContentConfig {},
         );
-        SourceMapVc::new_regular(map)
+        Ok(SourceMapVc::new_regular(map))
     }
 }
 
+/// Reads the source map which `source` refers to with a `//# sourceMappingURL`
+/// comment, either a `.map` file or an inline base64 data URL. Packages are
+/// often published pre-compiled with such maps, which point to their original
+/// sources, e. g. TypeScript files.
+#[turbo_tasks::function]
+pub async fn input_source_map(source: AssetVc) -> Result<OptionSourceMapVc> {
+    const PREFIX: &str = "sourceMappingURL=";
+    let none = OptionSourceMapVc::cell(None);
+    let file = match &*source.content().await? {
+        AssetContent::File(file) => file.await?,
+        AssetContent::Redirect { .. } => return Ok(none),
+    };
+    let code = match &*file {
+        FileContent::Content(file) => String::from_utf8_lossy(file.content()).into_owned(),
+        FileContent::NotFound => return Ok(none),
+    };
+    // The comment is only respected at the end of the file
+    let url = match code.rfind(PREFIX) {
+        Some(index) if code[..index].ends_with("//# ") || code[..index].ends_with("//@ ") => {
+            code[index + PREFIX.len()..].split_whitespace().next()
+        }
+        _ => None,
+    };
+    let url = match url {
+        Some(url) => url,
+        None => return Ok(none),
+    };
+
+    let base = source.path().parent();
+    let (json, base) = if let Some(data) = url.strip_prefix("data:application/json;") {
+        match data.split_once("base64,") {
+            Some((_, data)) => match base64::decode(data) {
+                Ok(json) => (json, base),
+                Err(_) => return Ok(none),
+            },
+            None => return Ok(none),
+        }
+    } else {
+        let path = match *base.try_join(url).await? {
+            Some(path) => path,
+            None => return Ok(none),
+        };
+        match &*path.read().await? {
+            FileContent::Content(file) => (file.content().to_vec(), path.parent()),
+            FileContent::NotFound => return Ok(none),
+        }
+    };
+    // Invalid maps are ignored, the generated code is mapped then
+    Ok(OptionSourceMapVc::cell(
+        SourceMapVc::new_from_json(&json, base).await.ok(),
+    ))
+}
+
 /// A config to generate a source map which includes the source content of every
 /// source file. SWC doesn't inline sources content by default when generating a
 /// sourcemap, so we need to provide a custom config to do it.
@@ -142,7 +244,13 @@ pub async fn parse(
             FileContent::Content(file) => match String::from_utf8(file.content().to_vec()) {
                 Ok(string) => {
                     let transforms = &*transforms.await?;
-                    parse_content(string, fs_path, file_path_hash, source, ty, transforms).await?
+                    catch_panic(
+                        source.path(),
+                        "parsing",
+                        parse_content(string, fs_path, file_path_hash, source, ty, transforms),
+                    )
+                    .await?
+                    .unwrap_or_else(|| ParseResult::Panicked.cell())
                 }
                 // FIXME: report error
                 Err(_err) => ParseResult::Unparseable.cell(),
@@ -253,6 +361,9 @@ async fn parse_content(
                 top_level_mark,
                 is_typescript,
             ));
+            // `require.ensure` callbacks receive `require` as parameter, which
+            // needs to be the free `require` to analyse the calls to it.
+            parsed_program.visit_mut_with(&mut require_ensure_callbacks(unresolved_mark));
 
             let context = TransformContext {
                 comments: &comments,
@@ -1,10 +1,19 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
 
 use swc_core::{
-    common::pass::AstKindPath,
+    common::{
+        pass::{AstKindPath, AstNodePath},
+        Span, Spanned,
+    },
     ecma::{
         ast::*,
-        visit::{AstParentKind, VisitMut, VisitMutAstPath, VisitMutWith, VisitMutWithPath},
+        visit::{
+            AstParentKind, AstParentNodeRef, VisitAstPath, VisitMut, VisitMutAstPath, VisitMutWith,
+            VisitMutWithPath, VisitWithPath,
+        },
     },
 };
 
@@ -115,6 +124,59 @@ impl VisitMutAstPath for ApplyVisitors<'_> {
     method!(visit_mut_str, Str);
 }
 
+/// Finds the spans of the nodes at `paths`, i. e. the nodes visitors with
+/// these paths are applied to by [ApplyVisitors].
+pub fn find_node_spans<'a>(
+    program: &Program,
+    paths: impl IntoIterator<Item = &'a AstPath>,
+) -> HashMap<AstPath, Span> {
+    let mut visitor = FindNodeSpans {
+        paths: paths.into_iter().collect(),
+        spans: HashMap::new(),
+    };
+    program.visit_with_path(&mut visitor, &mut Default::default());
+    visitor.spans
+}
+
+struct FindNodeSpans<'a> {
+    paths: HashSet<&'a AstPath>,
+    spans: HashMap<AstPath, Span>,
+}
+
+impl FindNodeSpans<'_> {
+    fn record(&mut self, span: Span, ast_path: &AstNodePath<AstParentNodeRef<'_>>) {
+        let path: AstPath = ast_path.iter().map(|n| n.kind()).collect();
+        if self.paths.contains(&path) {
+            self.spans.entry(path).or_insert(span);
+        }
+    }
+}
+
+macro_rules! find_span {
+    ($name:ident, $T:ty) => {
+        fn $name<'ast: 'r, 'r>(
+            &mut self,
+            n: &'ast $T,
+            ast_path: &mut AstNodePath<AstParentNodeRef<'r>>,
+        ) {
+            self.record(n.span(), ast_path);
+            n.visit_children_with_path(self, ast_path);
+        }
+    };
+}
+
+impl VisitAstPath for FindNodeSpans<'_> {
+    find_span!(visit_prop, Prop);
+    find_span!(visit_expr, Expr);
+    find_span!(visit_pat, Pat);
+    find_span!(visit_stmt, Stmt);
+    find_span!(visit_module_decl, ModuleDecl);
+    find_span!(visit_module_item, ModuleItem);
+    find_span!(visit_call_expr, CallExpr);
+    find_span!(visit_lit, Lit);
+    find_span!(visit_str, Str);
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -0,0 +1,40 @@
+use anyhow::{anyhow, Result};
+use turbo_tasks_fs::{embed_file, File, FileContent, FileSystemPathVc};
+use turbopack_core::{
+    context::AssetContextVc,
+    resolve::{parse::RequestVc, resolve, ResolveResult},
+    virtual_asset::VirtualAssetVc,
+};
+
+use crate::{chunk::EcmascriptChunkPlaceableVc, resolve::apply_esm_specific_options};
+
+/// Returns the module which sets up React Fast Refresh with the
+/// `react-refresh` package resolved from `project_path`. It needs to be
+/// evaluated as runtime entry, before the modules compiled with the React
+/// Refresh transform.
+///
+/// Projects which don't depend on `react-refresh` get a module which only
+/// defines the functions called by the transformed modules, so they still run
+/// without Fast Refresh.
+#[turbo_tasks::function]
+pub async fn react_refresh_runtime_entry(
+    context: AssetContextVc,
+    project_path: FileSystemPathVc,
+) -> Result<EcmascriptChunkPlaceableVc> {
+    let options = apply_esm_specific_options(context.resolve_options(project_path));
+    let request = RequestVc::parse_string("react-refresh/runtime".to_string());
+    let content = match &*resolve(project_path, request, options).await? {
+        ResolveResult::Unresolveable(_) => FileContent::Content(File::from(
+            "self.$RefreshReg$ = () => {};\nself.$RefreshSig$ = () => (type) => type;\n",
+        ))
+        .cell(),
+        _ => embed_file!("js/src/react-refresh.js"),
+    };
+    let asset = VirtualAssetVc::new(
+        project_path.join("__turbopack_react_refresh__.js"),
+        content.into(),
+    );
+    EcmascriptChunkPlaceableVc::resolve_from(context.process(asset.into()))
+        .await?
+        .ok_or_else(|| anyhow!("React Refresh runtime entry is not placeable"))
+}
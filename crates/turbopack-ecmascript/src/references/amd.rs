@@ -26,6 +26,7 @@ use crate::{
     create_visitor,
     references::{
         pattern_mapping::{PatternMapping, PatternMappingReadRef},
+        util::{ast_path_issue_source, global_external_expr},
         AstPathVc,
     },
     resolve::cjs_resolve,
@@ -136,6 +137,7 @@ impl CodeGenerateable for AmdDefineWithDependenciesCodeGen {
                                 context,
                                 cjs_resolve(self.origin, *request),
                                 Value::new(Cjs),
+                                ast_path_issue_source(self.origin, self.path),
                             )
                             .await?,
                         )
@@ -157,6 +159,14 @@ impl CodeGenerateable for AmdDefineWithDependenciesCodeGen {
         let factory_type = self.factory_type;
 
         let path = self.path.await?;
+        let invalid_references = resolved_elements
+            .iter()
+            .filter_map(|element| match element {
+                ResolvedElement::PatternMapping(pm) => pm.invalid_reason(),
+                ResolvedElement::Expr(_) => None,
+            })
+            .map(|reason| (path.to_vec(), reason))
+            .collect();
         visitors.push(
             // Transforms `define([dep1, dep2], factory)` into:
             // ```js
@@ -175,6 +185,7 @@ impl CodeGenerateable for AmdDefineWithDependenciesCodeGen {
                             ResolvedElement::PatternMapping(pm) => {
                                 match &**pm {
                                     PatternMapping::Invalid => Expr::Ident(Ident::new("undefined".into(), DUMMY_SP)),
+                                    PatternMapping::GlobalExternal(name) => global_external_expr(name),
                                     pm => Expr::Call(CallExpr {
                                         span: DUMMY_SP,
                                         callee: Callee::Expr(
@@ -248,6 +259,10 @@ impl CodeGenerateable for AmdDefineWithDependenciesCodeGen {
             }),
         );
 
-        Ok(CodeGeneration { visitors }.into())
+        Ok(CodeGeneration {
+            visitors,
+            invalid_references,
+        }
+        .into())
     }
 }
@@ -1,3 +1,5 @@
+use std::collections::{BTreeSet, HashSet};
+
 use anyhow::Result;
 use swc_core::{
     common::DUMMY_SP,
@@ -6,15 +8,20 @@ use swc_core::{
 use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
 use turbopack_core::{
     chunk::{ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc},
-    reference::{AssetReference, AssetReferenceVc},
+    issue::OptionIssueSourceVc,
+    reference::{AssetReference, AssetReferenceKind, AssetReferenceKindVc, AssetReferenceVc},
     resolve::{origin::ResolveOriginVc, parse::RequestVc, ResolveResultVc},
 };
 
 use super::pattern_mapping::{PatternMapping, PatternMappingVc, ResolveType::Cjs};
 use crate::{
+    chunk::{EcmascriptChunkPlaceableVc, EcmascriptExports},
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
     create_visitor,
-    references::{util::throw_module_not_found_expr, AstPathVc},
+    references::{
+        util::{ast_path_issue_source, global_external_expr, throw_module_not_found_expr},
+        AstPathVc,
+    },
     resolve::cjs_resolve,
 };
 
@@ -39,6 +46,11 @@ impl AssetReference for CjsAssetReference {
     fn resolve_reference(&self) -> ResolveResultVc {
         cjs_resolve(self.origin, self.request)
     }
+
+    #[turbo_tasks::function]
+    fn kind(&self) -> AssetReferenceKindVc {
+        AssetReferenceKind::Cjs.cell()
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -81,6 +93,16 @@ impl AssetReference for CjsRequireAssetReference {
     fn resolve_reference(&self) -> ResolveResultVc {
         cjs_resolve(self.origin, self.request)
     }
+
+    #[turbo_tasks::function]
+    fn kind(&self) -> AssetReferenceKindVc {
+        AssetReferenceKind::Cjs.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn issue_source(&self) -> OptionIssueSourceVc {
+        ast_path_issue_source(self.origin, self.path)
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -107,17 +129,28 @@ impl CodeGenerateable for CjsRequireAssetReference {
             context,
             cjs_resolve(self.origin, self.request),
             Value::new(Cjs),
+            ast_path_issue_source(self.origin, self.path),
         )
         .await?;
         let mut visitors = Vec::new();
 
         let path = &self.path.await?;
+        let invalid_references = pm
+            .invalid_reason()
+            .map(|reason| (path.to_vec(), reason))
+            .into_iter()
+            .collect();
         if let PatternMapping::Invalid = &*pm {
             let request_string = self.request.to_string().await?.clone();
             visitors.push(create_visitor!(path, visit_mut_expr(expr: &mut Expr) {
                 // In Node.js, a require call that cannot be resolved will throw an error.
                 *expr = throw_module_not_found_expr(&request_string);
             }));
+        } else if let PatternMapping::GlobalExternal(name) = &*pm {
+            let name = name.clone();
+            visitors.push(create_visitor!(path, visit_mut_expr(expr: &mut Expr) {
+                *expr = global_external_expr(&name);
+            }));
         } else {
             visitors.push(
                 create_visitor!(exact path, visit_mut_call_expr(call_expr: &mut CallExpr) {
@@ -140,7 +173,11 @@ impl CodeGenerateable for CjsRequireAssetReference {
             );
         }
 
-        Ok(CodeGeneration { visitors }.into())
+        Ok(CodeGeneration {
+            visitors,
+            invalid_references,
+        }
+        .into())
     }
 }
 
@@ -170,6 +207,16 @@ impl AssetReference for CjsRequireResolveAssetReference {
     fn resolve_reference(&self) -> ResolveResultVc {
         cjs_resolve(self.origin, self.request)
     }
+
+    #[turbo_tasks::function]
+    fn kind(&self) -> AssetReferenceKindVc {
+        AssetReferenceKind::Cjs.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn issue_source(&self) -> OptionIssueSourceVc {
+        ast_path_issue_source(self.origin, self.path)
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -196,11 +243,17 @@ impl CodeGenerateable for CjsRequireResolveAssetReference {
             context,
             cjs_resolve(self.origin, self.request),
             Value::new(Cjs),
+            ast_path_issue_source(self.origin, self.path),
         )
         .await?;
         let mut visitors = Vec::new();
 
         let path = &self.path.await?;
+        let invalid_references = pm
+            .invalid_reason()
+            .map(|reason| (path.to_vec(), reason))
+            .into_iter()
+            .collect();
         if let PatternMapping::Invalid = &*pm {
             let request_string = self.request.to_string().await?.clone();
             visitors.push(create_visitor!(path, visit_mut_expr(expr: &mut Expr) {
@@ -223,7 +276,11 @@ impl CodeGenerateable for CjsRequireResolveAssetReference {
             }));
         }
 
-        Ok(CodeGeneration { visitors }.into())
+        Ok(CodeGeneration {
+            visitors,
+            invalid_references,
+        }
+        .into())
     }
 }
 
@@ -248,6 +305,76 @@ impl CodeGenerateable for CjsRequireCacheAccess {
             }
         }));
 
-        Ok(CodeGeneration { visitors }.into())
+        Ok(CodeGeneration {
+            visitors,
+            ..Default::default()
+        }
+        .into())
+    }
+}
+
+/// Statically detected exports of a CommonJS module. ESM importers can use
+/// these names for named imports and `export *`, while the values are still
+/// read from the `exports` object at runtime, so bindings stay live.
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub struct CjsExports {
+    /// Names assigned on `exports` or `module.exports`.
+    pub names: BTreeSet<String>,
+    /// Modules whose exports are re-exported as a whole, e.g.
+    /// `module.exports = require("./impl")`.
+    pub reexports: Vec<CjsAssetReferenceVc>,
+    /// `true` when some exports could not be analysed statically, so `names`
+    /// might be incomplete.
+    pub has_dynamic_exports: bool,
+}
+
+impl CjsExports {
+    /// Returns the detected names which are exposed as named exports, i. e.
+    /// without `default` and the `__esModule` marker.
+    pub fn named_exports(&self) -> impl Iterator<Item = &String> {
+        self.names
+            .iter()
+            .filter(|name| *name != "default" && *name != "__esModule")
+    }
+}
+
+/// Whether `name` might be exported by the CommonJS module with the detected
+/// `exports`, directly or by a module it re-exports. This is only `false`
+/// when all exports of these modules could be detected and `name` isn't one of
+/// them. `default` is always available, as it's the `exports` object itself.
+pub(crate) async fn cjs_may_export(exports: CjsExportsVc, name: &str) -> Result<bool> {
+    if name == "default" {
+        return Ok(true);
+    }
+    let mut visited = HashSet::new();
+    let mut queue = vec![exports];
+    while let Some(exports) = queue.pop() {
+        if !visited.insert(exports.resolve().await?) {
+            continue;
+        }
+        let exports = exports.await?;
+        if exports.has_dynamic_exports || exports.names.contains(name) {
+            return Ok(true);
+        }
+        for reexport in exports.reexports.iter() {
+            for asset in reexport.resolve_reference().primary_assets().await?.iter() {
+                let placeable = match EcmascriptChunkPlaceableVc::resolve_from(asset).await? {
+                    Some(placeable) => placeable,
+                    None => return Ok(true),
+                };
+                match &*placeable.get_exports().await? {
+                    EcmascriptExports::CommonJs(exports) => queue.push(*exports),
+                    EcmascriptExports::EsmExports(exports) => {
+                        let exports = exports.await?;
+                        if !exports.star_exports.is_empty() || exports.exports.contains_key(name) {
+                            return Ok(true);
+                        }
+                    }
+                    EcmascriptExports::Value | EcmascriptExports::None => return Ok(true),
+                }
+            }
+        }
     }
+    Ok(false)
 }
@@ -0,0 +1,85 @@
+use std::mem::replace;
+
+use anyhow::Result;
+use swc_core::{
+    common::DUMMY_SP,
+    ecma::{
+        ast::{CondExpr, EmptyStmt, Expr, IfStmt, Invalid, ParenExpr, Stmt},
+        visit::{
+            fields::{ExprField, StmtField},
+            AstParentKind,
+        },
+    },
+};
+use turbopack_core::chunk::ChunkingContextVc;
+
+use super::AstPathVc;
+use crate::{
+    code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
+    create_visitor,
+};
+
+/// An `if` statement or a ternary whose condition is known at compile time,
+/// e. g. `process.env.NODE_ENV !== "production"` in production builds. It's
+/// replaced with the branch which is taken.
+#[turbo_tasks::value(shared)]
+#[derive(Hash, Debug)]
+pub struct ConstantCondition {
+    /// Whether the condition is truthy.
+    pub value: bool,
+    pub path: AstPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ConstantConditionVc {
+    #[turbo_tasks::function]
+    pub fn new(value: bool, path: AstPathVc) -> Self {
+        Self::cell(ConstantCondition { value, path })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for ConstantCondition {
+    #[turbo_tasks::function]
+    async fn code_generation(&self, _context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        let value = self.value;
+        let path = &self.path.await?;
+
+        let mut visitors = Vec::new();
+        match path.last() {
+            Some(AstParentKind::Stmt(StmtField::If)) => {
+                visitors.push(create_visitor!(path, visit_mut_stmt(stmt: &mut Stmt) {
+                    if let Stmt::If(IfStmt { cons, alt, .. }) = stmt {
+                        let empty = Stmt::Empty(EmptyStmt { span: DUMMY_SP });
+                        let branch = if value { Some(cons) } else { alt.as_mut() };
+                        let replacement = match branch {
+                            Some(branch) => replace(&mut **branch, empty),
+                            None => empty,
+                        };
+                        *stmt = replacement;
+                    }
+                }));
+            }
+            Some(AstParentKind::Expr(ExprField::Cond)) => {
+                visitors.push(create_visitor!(path, visit_mut_expr(expr: &mut Expr) {
+                    if let Expr::Cond(CondExpr { cons, alt, .. }) = expr {
+                        let branch = if value { cons } else { alt };
+                        let invalid = Expr::Invalid(Invalid { span: DUMMY_SP });
+                        let replacement = Expr::Paren(ParenExpr {
+                            span: DUMMY_SP,
+                            expr: box replace(&mut **branch, invalid),
+                        });
+                        *expr = replacement;
+                    }
+                }));
+            }
+            _ => {}
+        }
+
+        Ok(CodeGeneration {
+            visitors,
+            ..Default::default()
+        }
+        .into())
+    }
+}
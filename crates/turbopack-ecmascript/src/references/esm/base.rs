@@ -5,14 +5,18 @@ use swc_core::{
     ecma::ast::{Expr, ExprStmt, Ident, Lit, Module, ModuleItem, Program, Script, Stmt},
     quote,
 };
-use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
+use turbo_tasks::{
+    primitives::{BoolVc, OptionStringVc, StringVc},
+    Value, ValueToString, ValueToStringVc,
+};
 use turbopack_core::{
     asset::Asset,
     chunk::{
         ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc, ChunkingType,
         ChunkingTypeOptionVc, ModuleId,
     },
-    reference::{AssetReference, AssetReferenceVc},
+    issue::{analyze::AnalyzeIssue, IssueSeverity},
+    reference::{AssetReference, AssetReferenceKind, AssetReferenceKindVc, AssetReferenceVc},
     resolve::{
         origin::ResolveOriginVc, parse::RequestVc, ResolveResult, ResolveResultVc, SpecialType,
     },
@@ -20,17 +24,24 @@ use turbopack_core::{
 
 use crate::{
     analyzer::imports::ImportAnnotations,
-    chunk::EcmascriptChunkPlaceableVc,
+    chunk::{EcmascriptChunkPlaceableVc, EcmascriptExports},
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
-    create_visitor, magic_identifier,
-    references::util::{request_to_string, throw_module_not_found_expr},
+    concatenation::is_concatenated,
+    create_visitor, errors, magic_identifier,
+    references::{
+        cjs::cjs_may_export,
+        util::{global_external_namespace_expr, request_to_string, throw_module_not_found_expr},
+    },
     resolve::esm_resolve,
+    side_effects::{follow_reexports, is_skippable_reexport_module},
 };
 
 #[turbo_tasks::value]
 pub enum ReferencedAsset {
     Some(EcmascriptChunkPlaceableVc),
     OriginalReferenceTypeExternal(String),
+    /// An external read from the global variable with the name.
+    GlobalExternal(String),
     None,
 }
 
@@ -47,6 +58,10 @@ impl ReferencedAsset {
             ReferencedAsset::OriginalReferenceTypeExternal(request) => {
                 Some(magic_identifier::encode(&format!("external {}", request)))
             }
+            ReferencedAsset::GlobalExternal(name) => Some(magic_identifier::encode(&format!(
+                "global external {}",
+                name
+            ))),
             ReferencedAsset::None => None,
         })
     }
@@ -58,6 +73,19 @@ pub struct EsmAssetReference {
     pub origin: ResolveOriginVc,
     pub request: RequestVc,
     pub annotations: ImportAnnotations,
+    /// Set for references which only import this single export. They are
+    /// re-routed through side effect free re-export modules to the module
+    /// defining the export.
+    pub export_name: Option<String>,
+    /// Set for references which only read a static member of the export, like
+    /// `styles.button`. Only that member is used when the export is a
+    /// [EsmExport::ConstantObject](super::EsmExport::ConstantObject) or a
+    /// [EsmExport::PropertiesObject](super::EsmExport::PropertiesObject).
+    pub member: Option<String>,
+    /// The imported module is only used by named imports, which use separate
+    /// references with an `export_name`. Skippable re-export modules are not
+    /// imported by this reference then.
+    pub named_imports_only: bool,
 }
 
 impl EsmAssetReference {
@@ -72,8 +100,10 @@ impl EsmAssetReference {
 
 #[turbo_tasks::value_impl]
 impl EsmAssetReferenceVc {
+    /// The module which is imported by the request, without following
+    /// re-exports.
     #[turbo_tasks::function]
-    pub(super) async fn get_referenced_asset(self) -> Result<ReferencedAssetVc> {
+    async fn get_imported_asset(self) -> Result<ReferencedAssetVc> {
         let this = self.await?;
         let resolve_result = esm_resolve(this.get_origin(), this.request);
         match &*resolve_result.await? {
@@ -87,6 +117,9 @@ impl EsmAssetReferenceVc {
             ResolveResult::Special(SpecialType::OriginalReferenceTypeExternal(request), _) => {
                 return Ok(ReferencedAsset::OriginalReferenceTypeExternal(request.clone()).cell());
             }
+            ResolveResult::Special(SpecialType::GlobalExternal(name), _) => {
+                return Ok(ReferencedAsset::GlobalExternal(name.clone()).cell());
+            }
             _ => {}
         }
         let assets = resolve_result.primary_assets();
@@ -98,25 +131,113 @@ impl EsmAssetReferenceVc {
         Ok(ReferencedAssetVc::cell(ReferencedAsset::None))
     }
 
+    #[turbo_tasks::function]
+    pub(crate) async fn get_referenced_asset(self) -> Result<ReferencedAssetVc> {
+        let this = self.await?;
+        let imported_asset = self.get_imported_asset();
+        if let ReferencedAsset::Some(asset) = &*imported_asset.await? {
+            if let Some(export_name) = &this.export_name {
+                let followed = follow_reexports(*asset, export_name.clone()).await?;
+                return Ok(ReferencedAsset::Some(followed.module).cell());
+            }
+            if this.named_imports_only && *is_skippable_reexport_module(*asset).await? {
+                return Ok(ReferencedAsset::None.cell());
+            }
+        }
+        Ok(imported_asset)
+    }
+
+    /// The name of the imported export in the referenced asset, which differs
+    /// from `export_name` when re-exports have been followed. `None` refers to
+    /// the namespace object.
+    #[turbo_tasks::function]
+    pub(crate) async fn get_referenced_export(self) -> Result<OptionStringVc> {
+        let this = self.await?;
+        if let Some(export_name) = &this.export_name {
+            if let ReferencedAsset::Some(asset) = &*self.get_imported_asset().await? {
+                let followed = follow_reexports(*asset, export_name.clone()).await?;
+                return Ok(OptionStringVc::cell(followed.export_name.clone()));
+            }
+        }
+        Ok(OptionStringVc::cell(this.export_name.clone()))
+    }
+
+    /// Whether the referenced asset differs from the imported asset, because
+    /// re-exports have been followed or the imported module is skipped.
+    #[turbo_tasks::function]
+    async fn is_rerouted(self) -> Result<BoolVc> {
+        Ok(BoolVc::cell(
+            *self.get_referenced_asset().await? != *self.get_imported_asset().await?,
+        ))
+    }
+
     #[turbo_tasks::function]
     pub fn new(
         origin: ResolveOriginVc,
         request: RequestVc,
         annotations: Value<ImportAnnotations>,
+        named_imports_only: bool,
     ) -> Self {
         Self::cell(EsmAssetReference {
             origin,
             request,
             annotations: annotations.into_value(),
+            export_name: None,
+            member: None,
+            named_imports_only,
         })
     }
+
+    /// Creates a reference to the same module, which only imports
+    /// `export_name`.
+    #[turbo_tasks::function]
+    pub async fn with_export(self, export_name: &str) -> Result<Self> {
+        let this = self.await?;
+        Ok(Self::cell(EsmAssetReference {
+            origin: this.origin,
+            request: this.request,
+            annotations: this.annotations.clone(),
+            export_name: Some(export_name.to_string()),
+            member: None,
+            named_imports_only: false,
+        }))
+    }
+
+    /// Creates a reference to the same module, which only reads the static
+    /// member `member` of `export_name`.
+    #[turbo_tasks::function]
+    pub async fn with_export_member(self, export_name: &str, member: &str) -> Result<Self> {
+        let this = self.await?;
+        Ok(Self::cell(EsmAssetReference {
+            origin: this.origin,
+            request: this.request,
+            annotations: this.annotations.clone(),
+            export_name: Some(export_name.to_string()),
+            member: Some(member.to_string()),
+            named_imports_only: false,
+        }))
+    }
 }
 
 #[turbo_tasks::value_impl]
 impl AssetReference for EsmAssetReference {
     #[turbo_tasks::function]
-    fn resolve_reference(&self) -> ResolveResultVc {
-        esm_resolve(self.get_origin(), self.request)
+    async fn resolve_reference(self_vc: EsmAssetReferenceVc) -> Result<ResolveResultVc> {
+        let this = self_vc.await?;
+        if (this.export_name.is_some() || this.named_imports_only) && *self_vc.is_rerouted().await?
+        {
+            return Ok(match &*self_vc.get_referenced_asset().await? {
+                ReferencedAsset::Some(asset) => ResolveResult::Single(asset.as_asset(), Vec::new()),
+                _ => ResolveResult::Alternatives(Vec::new(), Vec::new()),
+            }
+            .cell());
+        }
+        Ok(esm_resolve(this.get_origin(), this.request))
+    }
+
+    #[turbo_tasks::function]
+    fn kind(&self) -> AssetReferenceKindVc {
+        AssetReferenceKind::Esm.cell()
     }
 }
 
@@ -124,10 +245,16 @@ impl AssetReference for EsmAssetReference {
 impl ValueToString for EsmAssetReference {
     #[turbo_tasks::function]
     async fn to_string(&self) -> Result<StringVc> {
+        let export = match (&self.export_name, &self.member) {
+            (Some(export_name), Some(member)) => format!(" (only {export_name}.{member})"),
+            (Some(export_name), None) => format!(" (only {export_name})"),
+            (None, _) => String::new(),
+        };
         Ok(StringVc::cell(format!(
-            "import {} {}",
+            "import {} {}{}",
             self.request.to_string().await?,
-            self.annotations
+            self.annotations,
+            export
         )))
     }
 }
@@ -162,11 +289,30 @@ impl CodeGenerateable for EsmAssetReference {
         let chunking_type = self_vc.chunking_type(context).await?;
         let resolved = self_vc.resolve_reference().await?;
 
+        if let Some(export_name) = &*self_vc.get_referenced_export().await? {
+            check_commonjs_export(self_vc, export_name).await?;
+        }
+
+        // References to a single export import the same module as the reference
+        // of the import statement, unless they have been re-routed. The
+        // import statement throws when the request is unresolvable
+        if self_vc.await?.export_name.is_some() && !*self_vc.is_rerouted().await? {
+            return Ok(CodeGeneration {
+                visitors,
+                ..Default::default()
+            }
+            .into());
+        }
+
         // Insert code that throws immediately at time of import if a request is
         // unresolvable
         if resolved.is_unresolveable() {
             let this = &*self_vc.await?;
             let request = request_to_string(this.request).await?.to_string();
+            let invalid_references = vec![(
+                Vec::new(),
+                format!("the request '{request}' can't be resolved"),
+            )];
             visitors.push(create_visitor!(visit_mut_program(program: &mut Program) {
                 insert_hoisted_stmt(program, Stmt::Expr(ExprStmt {
                         expr: Box::new(throw_module_not_found_expr(
@@ -176,14 +322,26 @@ impl CodeGenerateable for EsmAssetReference {
                     }));
             }));
 
-            return Ok(CodeGeneration { visitors }.into());
+            return Ok(CodeGeneration {
+                visitors,
+                invalid_references,
+            }
+            .into());
         }
 
         // separate chunks can't be imported as the modules are not available
         if !matches!(*chunking_type, None | Some(ChunkingType::Separate)) {
             let referenced_asset = self_vc.get_referenced_asset().await?;
+            let concatenated = match &*referenced_asset {
+                ReferencedAsset::Some(asset) => is_concatenated(*asset, context).await?,
+                _ => false,
+            };
             if let Some(ident) = referenced_asset.get_ident().await? {
                 match &*referenced_asset {
+                    ReferencedAsset::Some(_) if concatenated => {
+                        // The bindings of concatenated modules are in scope
+                        // already
+                    }
                     ReferencedAsset::Some(asset) => {
                         let id = asset.as_chunk_item(context).id().await?;
                         visitors.push(create_visitor!(visit_mut_program(program: &mut Program) {
@@ -210,13 +368,61 @@ impl CodeGenerateable for EsmAssetReference {
                             insert_hoisted_stmt(program, stmt);
                         }));
                     }
+                    ReferencedAsset::GlobalExternal(name) => {
+                        let name = name.clone();
+                        visitors.push(create_visitor!(visit_mut_program(program: &mut Program) {
+                            let stmt = quote!(
+                                "var $name = $namespace;" as Stmt,
+                                name = Ident::new(ident.clone().into(), DUMMY_SP),
+                                namespace: Expr = global_external_namespace_expr(&name)
+                            );
+                            insert_hoisted_stmt(program, stmt);
+                        }));
+                    }
                     ReferencedAsset::None => {}
                 }
             }
         }
 
-        Ok(CodeGeneration { visitors }.into())
+        Ok(CodeGeneration {
+            visitors,
+            ..Default::default()
+        }
+        .into())
+    }
+}
+
+/// Warns when the export `export_name` is imported from a CommonJS module
+/// whose exports could be detected, but don't include it.
+async fn check_commonjs_export(reference: EsmAssetReferenceVc, export_name: &str) -> Result<()> {
+    let asset = match &*reference.get_referenced_asset().await? {
+        ReferencedAsset::Some(asset) => *asset,
+        _ => return Ok(()),
+    };
+    if let EcmascriptExports::CommonJs(exports) = &*asset.get_exports().await? {
+        if !cjs_may_export(*exports, export_name).await? {
+            let this = reference.await?;
+            AnalyzeIssue {
+                code: Some(
+                    errors::failed_to_analyse::esm::IMPORT_NOT_FOUND_IN_COMMONJS.to_string(),
+                ),
+                category: StringVc::cell("analyze".to_string()),
+                message: StringVc::cell(format!(
+                    "`{export_name}` is imported from {}, which is a CommonJS module that doesn't \
+                     export it\nThe import will be undefined at runtime.",
+                    asset.path().to_string().await?
+                )),
+                path: this.origin.origin_path(),
+                severity: IssueSeverity::Warning.into(),
+                source: None,
+                title: StringVc::cell("export not found".to_string()),
+            }
+            .cell()
+            .as_issue()
+            .emit();
+        }
     }
+    Ok(())
 }
 
 lazy_static! {
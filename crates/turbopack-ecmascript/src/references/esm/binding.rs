@@ -6,14 +6,19 @@ use swc_core::{
             ComputedPropName, Expr, Ident, KeyValueProp, Lit, MemberExpr, MemberProp, Prop,
             PropName, Str,
         },
-        visit::fields::{ExprField, PropField},
+        visit::{
+            fields::{ExprField, MemberExprField, PropField},
+            AstParentKind,
+        },
     },
 };
 use turbopack_core::chunk::ChunkingContextVc;
 
-use super::EsmAssetReferenceVc;
+use super::{base::ReferencedAsset, export::EsmExport, EsmAssetReferenceVc};
 use crate::{
+    chunk::{EcmascriptChunkPlaceableVc, EcmascriptExports},
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
+    concatenation::{concatenated_export_ident, is_concatenated},
     create_visitor,
     references::AstPathVc,
 };
@@ -23,6 +28,9 @@ use crate::{
 pub struct EsmBinding {
     pub reference: EsmAssetReferenceVc,
     pub export: Option<String>,
+    /// The static member read from the binding, see
+    /// [EsmAssetReference::member](super::base::EsmAssetReference::member).
+    pub member: Option<String>,
     pub ast_path: AstPathVc,
 }
 
@@ -32,11 +40,13 @@ impl EsmBindingVc {
     pub fn new(
         reference: EsmAssetReferenceVc,
         export: Option<String>,
+        member: Option<String>,
         ast_path: AstPathVc,
     ) -> Self {
         EsmBinding {
             reference,
             export,
+            member,
             ast_path,
         }
         .cell()
@@ -48,11 +58,11 @@ impl CodeGenerateable for EsmBinding {
     #[turbo_tasks::function]
     async fn code_generation(
         self_vc: EsmBindingVc,
-        _context: ChunkingContextVc,
+        context: ChunkingContextVc,
     ) -> Result<CodeGenerationVc> {
         let this = self_vc.await?;
         let mut visitors = Vec::new();
-        let imported_module = this.reference.get_referenced_asset();
+        let referenced_asset = this.reference.get_referenced_asset().await?;
 
         fn make_expr(imported_module: &str, export: Option<&str>) -> Expr {
             if let Some(export) = export {
@@ -74,16 +84,70 @@ impl CodeGenerateable for EsmBinding {
         }
 
         let mut ast_path = this.ast_path.await?.clone_value();
-        let imported_module = imported_module.await?.get_ident().await?;
+        // The export might have a different name in the referenced module when
+        // re-exports have been followed
+        let export = if this.reference.await?.export_name.is_some() {
+            this.reference.get_referenced_export().await?.clone_value()
+        } else {
+            this.export.clone()
+        };
+        // Members of constant objects are replaced by the constant, the whole
+        // member expression is replaced then
+        if let (ReferencedAsset::Some(asset), Some(export), Some(member)) =
+            (&*referenced_asset, &export, &this.member)
+        {
+            if let Some(value) = constant_object_member(*asset, export, member).await? {
+                let member_object = [
+                    AstParentKind::Expr(ExprField::Member),
+                    AstParentKind::MemberExpr(MemberExprField::Obj),
+                    AstParentKind::Expr(ExprField::Ident),
+                ];
+                if ast_path.ends_with(&member_object) {
+                    ast_path.truncate(ast_path.len() - member_object.len());
+                    let replacement = Expr::Lit(Lit::Str(Str {
+                        span: DUMMY_SP,
+                        value: value.into(),
+                        raw: None,
+                    }));
+                    visitors.push(
+                        create_visitor!(exact ast_path, visit_mut_expr(expr: &mut Expr) {
+                            *expr = replacement.clone();
+                        }),
+                    );
+                    return Ok(CodeGeneration {
+                        visitors,
+                        ..Default::default()
+                    }
+                    .into());
+                }
+            }
+        }
+
+        // Exports of concatenated modules are referenced by their binding, other
+        // modules by a property of the imported module
+        let mut replacement = None;
+        if let (ReferencedAsset::Some(asset), Some(export)) = (&*referenced_asset, &export) {
+            if is_concatenated(*asset, context).await? {
+                replacement = concatenated_export_ident(*asset, export)
+                    .await?
+                    .map(|ident| Expr::Ident(Ident::new(ident.into(), DUMMY_SP)));
+            }
+        }
+        if replacement.is_none() {
+            replacement = referenced_asset
+                .get_ident()
+                .await?
+                .map(|ident| make_expr(&ident, export.as_deref()));
+        }
 
         loop {
             match ast_path.last() {
-                Some(swc_core::ecma::visit::AstParentKind::Expr(ExprField::Ident)) => {
+                Some(AstParentKind::Expr(ExprField::Ident)) => {
                     ast_path.pop();
                     visitors.push(
                         create_visitor!(exact ast_path, visit_mut_expr(expr: &mut Expr) {
-                            if let Some(ident) = imported_module.as_deref() {
-                              *expr = make_expr(ident, this.export.as_deref());
+                            if let Some(replacement) = &replacement {
+                              *expr = replacement.clone();
                             }
                             // If there's no identifier for the imported module,
                             // resolution failed and will insert code that throws
@@ -92,14 +156,14 @@ impl CodeGenerateable for EsmBinding {
                     );
                     break;
                 }
-                Some(swc_core::ecma::visit::AstParentKind::Prop(PropField::Shorthand)) => {
+                Some(AstParentKind::Prop(PropField::Shorthand)) => {
                     ast_path.pop();
                     visitors.push(
                         create_visitor!(ast_path, visit_mut_prop(prop: &mut Prop) {
                             if let Prop::Shorthand(ident) = prop {
                               // TODO: Merge with the above condition when https://rust-lang.github.io/rfcs/2497-if-let-chains.html lands.
-                              if let Some(imported_ident) = imported_module.as_deref() {
-                                *prop = Prop::KeyValue(KeyValueProp { key: PropName::Ident(ident.clone()), value: box make_expr(imported_ident, this.export.as_deref())});
+                              if let Some(replacement) = &replacement {
+                                *prop = Prop::KeyValue(KeyValueProp { key: PropName::Ident(ident.clone()), value: box replacement.clone()});
                               }
                             }
                         }),
@@ -113,6 +177,25 @@ impl CodeGenerateable for EsmBinding {
             }
         }
 
-        Ok(CodeGeneration { visitors }.into())
+        Ok(CodeGeneration {
+            visitors,
+            ..Default::default()
+        }
+        .into())
+    }
+}
+
+/// Returns the constant `member` of the export `export` of `asset`, when the
+/// export is a [EsmExport::ConstantObject].
+async fn constant_object_member(
+    asset: EcmascriptChunkPlaceableVc,
+    export: &str,
+    member: &str,
+) -> Result<Option<String>> {
+    if let EcmascriptExports::EsmExports(exports) = &*asset.get_exports().await? {
+        if let Some(EsmExport::ConstantObject(values)) = exports.await?.exports.get(export) {
+            return Ok(values.get(member).cloned());
+        }
     }
+    Ok(None)
 }
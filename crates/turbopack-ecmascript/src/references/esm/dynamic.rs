@@ -9,7 +9,8 @@ use turbopack_core::{
         ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc, ChunkingType,
         ChunkingTypeOptionVc,
     },
-    reference::{AssetReference, AssetReferenceVc},
+    issue::OptionIssueSourceVc,
+    reference::{AssetReference, AssetReferenceKind, AssetReferenceKindVc, AssetReferenceVc},
     resolve::{origin::ResolveOriginVc, parse::RequestVc, ResolveResultVc},
 };
 
@@ -17,8 +18,11 @@ use super::super::pattern_mapping::{PatternMapping, PatternMappingVc, ResolveTyp
 use crate::{
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
     create_visitor,
-    references::AstPathVc,
-    resolve::esm_resolve,
+    references::{
+        util::{ast_path_issue_source, global_external_namespace_expr},
+        AstPathVc,
+    },
+    resolve::esm_async_resolve,
 };
 
 #[turbo_tasks::value]
@@ -45,7 +49,17 @@ impl EsmAsyncAssetReferenceVc {
 impl AssetReference for EsmAsyncAssetReference {
     #[turbo_tasks::function]
     fn resolve_reference(&self) -> ResolveResultVc {
-        esm_resolve(self.origin, self.request)
+        esm_async_resolve(self.origin, self.request)
+    }
+
+    #[turbo_tasks::function]
+    fn kind(&self) -> AssetReferenceKindVc {
+        AssetReferenceKind::Async.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn issue_source(&self) -> OptionIssueSourceVc {
+        ast_path_issue_source(self.origin, self.path)
     }
 }
 
@@ -76,12 +90,18 @@ impl CodeGenerateable for EsmAsyncAssetReference {
             self.request,
             self.origin,
             context,
-            esm_resolve(self.origin, self.request),
+            esm_async_resolve(self.origin, self.request),
             Value::new(EsmAsync),
+            ast_path_issue_source(self.origin, self.path),
         )
         .await?;
 
         let path = &self.path.await?;
+        let invalid_references = pm
+            .invalid_reason()
+            .map(|reason| (path.to_vec(), reason))
+            .into_iter()
+            .collect();
 
         let visitor = if let PatternMapping::Invalid = &*pm {
             create_visitor!(exact path, visit_mut_call_expr(call_expr: &mut CallExpr) {
@@ -110,6 +130,14 @@ impl CodeGenerateable for EsmAsyncAssetReference {
                     ExprOrSpread { spread: None, expr: error, },
                 ];
             })
+        } else if let PatternMapping::GlobalExternal(name) = &*pm {
+            let name = name.clone();
+            create_visitor!(exact path, visit_mut_call_expr(call_expr: &mut CallExpr) {
+                call_expr.callee = Callee::Expr(quote_expr!("Promise.resolve"));
+                call_expr.args = vec![
+                    ExprOrSpread { spread: None, expr: box global_external_namespace_expr(&name) },
+                ];
+            })
         } else {
             create_visitor!(exact path, visit_mut_call_expr(call_expr: &mut CallExpr) {
                 let old_args = std::mem::take(&mut call_expr.args);
@@ -136,6 +164,7 @@ impl CodeGenerateable for EsmAsyncAssetReference {
 
         Ok(CodeGeneration {
             visitors: vec![visitor],
+            invalid_references,
         }
         .into())
     }
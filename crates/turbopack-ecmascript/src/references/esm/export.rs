@@ -18,17 +18,19 @@ use turbo_tasks::{
     trace::TraceRawVcs,
     ValueToString,
 };
+use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
     asset::Asset,
-    chunk::ChunkingContextVc,
+    chunk::{concatenation::ModuleConcatenation, ChunkingContextVc},
     issue::{analyze::AnalyzeIssue, IssueSeverity},
+    reference::AssetReference,
 };
 
 use super::{base::ReferencedAsset, EsmAssetReferenceVc};
 use crate::{
     chunk::{EcmascriptChunkPlaceableVc, EcmascriptExports},
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
-    create_visitor,
+    create_visitor, errors,
 };
 
 #[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
@@ -36,11 +38,26 @@ pub enum EsmExport {
     LocalBinding(String),
     ImportedBinding(EsmAssetReferenceVc, String),
     ImportedNamespace(EsmAssetReferenceVc),
+    /// A string constant, e. g. a class name of a CSS module.
+    Constant(String),
+    /// An object of string constants, e. g. the class names of a CSS module.
+    /// Static member accesses on an import of it are replaced with the
+    /// constants.
+    ConstantObject(BTreeMap<String, String>),
+    /// A property of the [EsmExport::PropertiesObject] export, e. g. a
+    /// top-level key of a JSON module.
+    ObjectProperty,
+    /// An object with a property for each [EsmExport::ObjectProperty] export,
+    /// e. g. the value of a JSON module. Static member reads and destructuring
+    /// of an import of it only use the exports of the same names.
+    PropertiesObject,
     Error,
 }
 
 #[turbo_tasks::function]
-async fn expand_star_exports(root_asset: EcmascriptChunkPlaceableVc) -> Result<StringsVc> {
+pub(crate) async fn expand_star_exports(
+    root_asset: EcmascriptChunkPlaceableVc,
+) -> Result<StringsVc> {
     let mut set = HashSet::new();
     let mut checked_assets = HashSet::new();
     checked_assets.insert(root_asset);
@@ -59,7 +76,7 @@ async fn expand_star_exports(root_asset: EcmascriptChunkPlaceableVc) -> Result<S
                 }
             }
             EcmascriptExports::None => AnalyzeIssue {
-                code: None,
+                code: Some(errors::failed_to_analyse::esm::EXPORT_STAR_WITHOUT_EXPORTS.to_string()),
                 category: StringVc::cell("analyze".to_string()),
                 message: StringVc::cell(format!(
                     "export * used with module {} which has no exports\nTypescript only: Did you \
@@ -75,7 +92,9 @@ async fn expand_star_exports(root_asset: EcmascriptChunkPlaceableVc) -> Result<S
             .as_issue()
             .emit(),
             EcmascriptExports::Value => AnalyzeIssue {
-                code: None,
+                code: Some(
+                    errors::failed_to_analyse::esm::EXPORT_STAR_WITH_DEFAULT_ONLY.to_string(),
+                ),
                 category: StringVc::cell("analyze".to_string()),
                 message: StringVc::cell(format!(
                     "export * used with module {} which only has a default export (default export \
@@ -91,23 +110,46 @@ async fn expand_star_exports(root_asset: EcmascriptChunkPlaceableVc) -> Result<S
             .cell()
             .as_issue()
             .emit(),
-            EcmascriptExports::CommonJs => AnalyzeIssue {
-                code: None,
-                category: StringVc::cell("analyze".to_string()),
-                message: StringVc::cell(format!(
-                    "export * used with module {} which is a CommonJS module with exports only \
-                     available at runtime\nList all export names manually (`export {{ a, b, c }} \
-                     from \"...\") or rewrite the module to ESM.`",
-                    asset.path().to_string().await?
-                )),
-                path: asset.path(),
-                severity: IssueSeverity::Warning.into(),
-                source: None,
-                title: StringVc::cell("unexpected export *".to_string()),
+            EcmascriptExports::CommonJs(cjs_exports) => {
+                let cjs_exports = cjs_exports.await?;
+                set.extend(cjs_exports.named_exports().cloned());
+                for reexport in cjs_exports.reexports.iter() {
+                    let assets = reexport.resolve_reference().primary_assets();
+                    for asset in assets.await?.iter() {
+                        if let Some(placeable) =
+                            EcmascriptChunkPlaceableVc::resolve_from(asset).await?
+                        {
+                            if checked_assets.insert(placeable) {
+                                queue.push((placeable, placeable.get_exports()));
+                            }
+                        }
+                    }
+                }
+                if cjs_exports.has_dynamic_exports
+                    || (cjs_exports.names.is_empty() && cjs_exports.reexports.is_empty())
+                {
+                    AnalyzeIssue {
+                        code: Some(
+                            errors::failed_to_analyse::esm::EXPORT_STAR_WITH_DYNAMIC_EXPORTS
+                                .to_string(),
+                        ),
+                        category: StringVc::cell("analyze".to_string()),
+                        message: StringVc::cell(format!(
+                            "export * used with module {} which is a CommonJS module with exports \
+                             only available at runtime\nList all export names manually (`export \
+                             {{ a, b, c }} from \"...\") or rewrite the module to ESM.`",
+                            asset.path().to_string().await?
+                        )),
+                        path: asset.path(),
+                        severity: IssueSeverity::Warning.into(),
+                        source: None,
+                        title: StringVc::cell("unexpected export *".to_string()),
+                    }
+                    .cell()
+                    .as_issue()
+                    .emit()
+                }
             }
-            .cell()
-            .as_issue()
-            .emit(),
         }
     }
     Ok(StringsVc::cell(set.into_iter().collect()))
@@ -116,6 +158,8 @@ async fn expand_star_exports(root_asset: EcmascriptChunkPlaceableVc) -> Result<S
 #[turbo_tasks::value(shared)]
 #[derive(Hash, Debug)]
 pub struct EsmExports {
+    /// The path of the module, used to look up the usage of its exports.
+    pub path: FileSystemPathVc,
     pub exports: BTreeMap<String, EsmExport>,
     pub star_exports: Vec<EsmAssetReferenceVc>,
 }
@@ -125,10 +169,23 @@ impl CodeGenerateable for EsmExports {
     #[turbo_tasks::function]
     async fn code_generation(
         self_vc: EsmExportsVc,
-        _context: ChunkingContextVc,
+        context: ChunkingContextVc,
     ) -> Result<CodeGenerationVc> {
         let this = self_vc.await?;
         let mut visitors = Vec::new();
+        // Concatenated modules have no exports object, their bindings are
+        // referenced directly
+        if matches!(
+            *context.module_concatenation(this.path).await?,
+            ModuleConcatenation::Inner
+        ) {
+            return Ok(CodeGeneration {
+                visitors,
+                ..Default::default()
+            }
+            .into());
+        }
+        let usage = context.export_usage(this.path).await?;
 
         let mut all_exports: BTreeMap<Cow<str>, Cow<EsmExport>> = this
             .exports
@@ -136,6 +193,7 @@ impl CodeGenerateable for EsmExports {
             .map(|(k, v)| (Cow::<str>::Borrowed(k), Cow::Borrowed(v)))
             .collect();
         let mut props = Vec::new();
+        let mut invalid_references = Vec::new();
         for esm_ref in this.star_exports.iter() {
             if let ReferencedAsset::Some(asset) = &*esm_ref.get_referenced_asset().await? {
                 let export_names = expand_star_exports(*asset).await?;
@@ -149,11 +207,53 @@ impl CodeGenerateable for EsmExports {
                 }
             }
         }
+        all_exports.retain(|exported, _| usage.is_used(exported));
         for (exported, local) in all_exports.into_iter() {
             let expr = match local.as_ref() {
-                EsmExport::Error => Some(quote!(
-                    "(() => { throw new Error(\"Failed binding. See build errors!\"); })" as Expr,
+                EsmExport::Error => {
+                    invalid_references.push((
+                        Vec::new(),
+                        format!("the export '{exported}' can't be bound"),
+                    ));
+                    Some(quote!(
+                        "(() => { throw new Error(\"Failed binding. See build errors!\"); })"
+                            as Expr,
+                    ))
+                }
+                EsmExport::Constant(value) => Some(quote!(
+                    "(() => $value)" as Expr,
+                    value: Expr = Expr::Lit(Lit::Str(Str {
+                        span: DUMMY_SP,
+                        value: value.as_str().into(),
+                        raw: None,
+                    }))
                 )),
+                EsmExport::ConstantObject(values) => Some(quote!(
+                    "(() => ($object))" as Expr,
+                    object: Expr = Expr::Object(ObjectLit {
+                        span: DUMMY_SP,
+                        props: values
+                            .iter()
+                            .map(|(key, value)| {
+                                PropOrSpread::Prop(box Prop::KeyValue(KeyValueProp {
+                                    key: PropName::Str(Str {
+                                        span: DUMMY_SP,
+                                        value: key.as_str().into(),
+                                        raw: None,
+                                    }),
+                                    value: box Expr::Lit(Lit::Str(Str {
+                                        span: DUMMY_SP,
+                                        value: value.as_str().into(),
+                                        raw: None,
+                                    })),
+                                }))
+                            })
+                            .collect(),
+                    })
+                )),
+                // Modules with these exports generate their exports object
+                // themselves, e. g. JSON modules
+                EsmExport::ObjectProperty | EsmExport::PropertiesObject => None,
                 EsmExport::LocalBinding(name) => Some(quote!(
                     "(() => $local)" as Expr,
                     local = Ident::new((name as &str).into(), DUMMY_SP)
@@ -218,6 +318,10 @@ impl CodeGenerateable for EsmExports {
             }
         }));
 
-        Ok(CodeGeneration { visitors }.into())
+        Ok(CodeGeneration {
+            visitors,
+            invalid_references,
+        }
+        .into())
     }
 }
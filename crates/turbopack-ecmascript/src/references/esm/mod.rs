@@ -9,6 +9,6 @@ pub use self::{
     base::{EsmAssetReference, EsmAssetReferenceVc},
     binding::{EsmBinding, EsmBindingVc},
     dynamic::{EsmAsyncAssetReference, EsmAsyncAssetReferenceVc},
-    export::{EsmExports, EsmExportsVc},
+    export::{EsmExport, EsmExports, EsmExportsVc},
     module_item::{EsmModuleItem, EsmModuleItemVc},
 };
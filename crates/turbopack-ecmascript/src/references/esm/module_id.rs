@@ -6,7 +6,7 @@ use turbopack_core::{
         ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc,
         ChunkingTypeOptionVc, ModuleId,
     },
-    reference::{AssetReference, AssetReferenceVc},
+    reference::{AssetReference, AssetReferenceKindVc, AssetReferenceVc},
     resolve::ResolveResultVc,
 };
 
@@ -38,6 +38,11 @@ impl AssetReference for EsmModuleIdAssetReference {
     fn resolve_reference(&self) -> ResolveResultVc {
         self.inner.resolve_reference()
     }
+
+    #[turbo_tasks::function]
+    fn kind(&self) -> AssetReferenceKindVc {
+        self.inner.kind()
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -77,6 +82,10 @@ impl CodeGenerateable for EsmModuleIdAssetReference {
             );
         }
 
-        Ok(CodeGeneration { visitors }.into())
+        Ok(CodeGeneration {
+            visitors,
+            ..Default::default()
+        }
+        .into())
     }
 }
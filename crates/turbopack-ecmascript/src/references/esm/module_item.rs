@@ -96,6 +96,10 @@ impl CodeGenerateable for EsmModuleItem {
             }),
         );
 
-        Ok(CodeGeneration { visitors }.into())
+        Ok(CodeGeneration {
+            visitors,
+            ..Default::default()
+        }
+        .into())
     }
 }
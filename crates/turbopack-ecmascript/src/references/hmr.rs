@@ -0,0 +1,175 @@
+use anyhow::Result;
+use swc_core::{
+    common::DUMMY_SP,
+    ecma::ast::{Expr, Ident},
+    quote,
+};
+use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
+use turbopack_core::{
+    chunk::{ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc},
+    issue::OptionIssueSourceVc,
+    reference::{AssetReference, AssetReferenceKind, AssetReferenceKindVc, AssetReferenceVc},
+    resolve::{origin::ResolveOriginVc, parse::RequestVc, ResolveResultVc},
+};
+
+use super::pattern_mapping::{PatternMapping, PatternMappingVc, ResolveType::Cjs};
+use crate::{
+    code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
+    create_visitor,
+    references::{util::ast_path_issue_source, AstPathVc},
+    resolve::{cjs_resolve, esm_resolve},
+};
+
+/// A dependency passed to `module.hot.accept` or `module.hot.decline`, or
+/// their `import.meta.hot` counterparts. The request is replaced with the id
+/// of the module, which the runtime compares to the ids of updated modules.
+#[turbo_tasks::value]
+#[derive(Hash, Debug)]
+pub struct ModuleHotReferenceAssetReference {
+    pub origin: ResolveOriginVc,
+    pub request: RequestVc,
+    pub path: AstPathVc,
+    /// The dependency is passed to `import.meta.hot`, so it's resolved like
+    /// an ESM import instead of a `require` call.
+    pub esm: bool,
+}
+
+#[turbo_tasks::value_impl]
+impl ModuleHotReferenceAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(origin: ResolveOriginVc, request: RequestVc, path: AstPathVc, esm: bool) -> Self {
+        Self::cell(ModuleHotReferenceAssetReference {
+            origin,
+            request,
+            path,
+            esm,
+        })
+    }
+}
+
+impl ModuleHotReferenceAssetReference {
+    fn resolve(&self) -> ResolveResultVc {
+        if self.esm {
+            esm_resolve(self.origin, self.request)
+        } else {
+            cjs_resolve(self.origin, self.request)
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for ModuleHotReferenceAssetReference {
+    #[turbo_tasks::function]
+    fn resolve_reference(&self) -> ResolveResultVc {
+        self.resolve()
+    }
+
+    #[turbo_tasks::function]
+    fn kind(&self) -> AssetReferenceKindVc {
+        if self.esm {
+            AssetReferenceKind::Esm.cell()
+        } else {
+            AssetReferenceKind::Cjs.cell()
+        }
+    }
+
+    #[turbo_tasks::function]
+    fn issue_source(&self) -> OptionIssueSourceVc {
+        ast_path_issue_source(self.origin, self.path)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for ModuleHotReferenceAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "module.hot dependency {}",
+            self.request.to_string().await?,
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAssetReference for ModuleHotReferenceAssetReference {}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for ModuleHotReferenceAssetReference {
+    #[turbo_tasks::function]
+    async fn code_generation(&self, context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        let pm = PatternMappingVc::resolve_request(
+            self.request,
+            self.origin,
+            context,
+            self.resolve(),
+            Value::new(Cjs),
+            ast_path_issue_source(self.origin, self.path),
+        )
+        .await?;
+        let mut visitors = Vec::new();
+
+        let path = &self.path.await?;
+        let invalid_references = pm
+            .invalid_reason()
+            .map(|reason| (path.to_vec(), reason))
+            .into_iter()
+            .collect();
+        // A dependency which can't be resolved is kept as is, it will never be
+        // updated.
+        if let PatternMapping::Single(_) = &*pm {
+            visitors.push(
+                create_visitor!(exact path, visit_mut_expr(expr: &mut Expr) {
+                    *expr = pm.create();
+                }),
+            );
+        }
+
+        Ok(CodeGeneration {
+            visitors,
+            invalid_references,
+        }
+        .into())
+    }
+}
+
+/// An `import.meta.hot` expression, which is the `module.hot` API for ES
+/// modules.
+#[turbo_tasks::value(shared)]
+#[derive(Hash, Debug)]
+pub struct ImportMetaHot {
+    pub path: AstPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMetaHotVc {
+    #[turbo_tasks::function]
+    pub fn new(path: AstPathVc) -> Self {
+        Self::cell(ImportMetaHot { path })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for ImportMetaHot {
+    #[turbo_tasks::function]
+    async fn code_generation(&self, context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        let mut visitors = Vec::new();
+
+        // Without HMR, `import.meta.hot` is undefined, so code guarded by it
+        // can be removed by the minifier.
+        let hmr_enabled = *context.is_hot_module_replacement_enabled().await?;
+        let path = &self.path.await?;
+        visitors.push(create_visitor!(path, visit_mut_expr(expr: &mut Expr) {
+            *expr = if hmr_enabled {
+                Expr::Ident(Ident::new("__turbopack_hot__".into(), DUMMY_SP))
+            } else {
+                quote!("undefined" as Expr)
+            };
+        }));
+
+        Ok(CodeGeneration {
+            visitors,
+            ..Default::default()
+        }
+        .into())
+    }
+}
@@ -1,14 +1,18 @@
 pub mod amd;
 pub mod cjs;
+pub mod constant_condition;
 pub mod esm;
+pub mod hmr;
 pub mod node;
 pub mod pattern_mapping;
+pub mod provide;
 pub mod raw;
+pub mod require_ensure;
 pub mod typescript;
 pub mod util;
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     future::Future,
     mem::take,
     pin::Pin,
@@ -27,14 +31,25 @@ use swc_core::{
     },
     ecma::{
         ast::*,
-        visit::{AstParentKind, AstParentNodeRef, VisitAstPath, VisitWithPath},
+        visit::{
+            fields::{
+                ArrayLitField, CallExprField, CondExprField, ExprField, ExprOrSpreadField,
+                IfStmtField,
+            },
+            AstParentKind, AstParentNodeRef, VisitAstPath, VisitWithPath,
+        },
     },
 };
-use turbo_tasks::{TryJoinIterExt, Value};
+use turbo_tasks::{
+    primitives::{BoolVc, StringsVc},
+    TryJoinIterExt, Value,
+};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
     asset::AssetVc,
+    chunk::used_exports::ExportUsage,
     environment::EnvironmentVc,
+    issue::panic::catch_panic,
     reference::{AssetReferenceVc, AssetReferencesVc, SourceMapVc},
     resolve::{
         find_context_file, origin::ResolveOriginVc, parse::RequestVc, pattern::Pattern, resolve,
@@ -53,6 +68,7 @@ use self::{
         export::EsmExport, EsmAssetReferenceVc, EsmAsyncAssetReferenceVc, EsmExports,
         EsmModuleItemVc,
     },
+    hmr::{ImportMetaHotVc, ModuleHotReferenceAssetReferenceVc},
     node::{DirAssetReferenceVc, PackageJsonReferenceVc},
     raw::SourceAssetReferenceVc,
     typescript::{
@@ -62,7 +78,8 @@ use self::{
 use super::{
     analyzer::{
         builtin::replace_builtin,
-        graph::{create_graph, Effect},
+        cjs_exports::detect_cjs_exports,
+        graph::{create_graph, ConditionalKind, Effect},
         linker::{link, LinkCache},
         well_known::replace_well_known,
         ConstantValue, FreeVarKind, JsValue, ObjectPart, WellKnownFunctionKind,
@@ -86,10 +103,16 @@ use crate::{
     magic_identifier,
     references::{
         cjs::{
-            CjsRequireAssetReferenceVc, CjsRequireCacheAccess, CjsRequireResolveAssetReferenceVc,
+            CjsExports, CjsRequireAssetReferenceVc, CjsRequireCacheAccess,
+            CjsRequireResolveAssetReferenceVc,
         },
+        constant_condition::ConstantConditionVc,
         esm::{module_id::EsmModuleIdAssetReferenceVc, EsmBindingVc, EsmExportsVc},
+        provide::ProvideAssetReferenceVc,
+        require_ensure::{RequireEnsureAssetReferenceVc, RequireEnsureCodeGenVc},
     },
+    side_effects::is_reexport_only,
+    used_exports::{EsmImportUsage, EsmImportUsagesVc},
     EcmascriptInputTransformsVc,
 };
 
@@ -98,6 +121,16 @@ pub struct AnalyzeEcmascriptModuleResult {
     pub references: AssetReferencesVc,
     pub code_generation: CodeGenerateablesVc,
     pub exports: EcmascriptExportsVc,
+    /// How the exports of ESM imported modules are used.
+    pub import_usage: EsmImportUsagesVc,
+    /// Names of statically detected `process.env.X` reads.
+    pub process_env_reads: StringsVc,
+    /// The module only consists of re-exports.
+    pub reexport_only: BoolVc,
+    /// The stage of processing the module which panicked, e. g. "parsing".
+    /// The rest of the result is empty then, and the code of the module
+    /// throws an error instead.
+    pub panicked: Option<String>,
 }
 
 /// A temporary analysis result builder to pass around, to be turned into an
@@ -106,6 +139,10 @@ pub(crate) struct AnalyzeEcmascriptModuleResultBuilder {
     references: Vec<AssetReferenceVc>,
     code_gens: Vec<CodeGenerateableVc>,
     exports: EcmascriptExports,
+    import_usage: Vec<EsmImportUsage>,
+    process_env_reads: BTreeSet<String>,
+    reexport_only: bool,
+    panicked: Option<String>,
 }
 
 impl AnalyzeEcmascriptModuleResultBuilder {
@@ -114,6 +151,10 @@ impl AnalyzeEcmascriptModuleResultBuilder {
             references: Vec::new(),
             code_gens: Vec::new(),
             exports: EcmascriptExports::None,
+            import_usage: Vec::new(),
+            process_env_reads: BTreeSet::new(),
+            reexport_only: false,
+            panicked: None,
         }
     }
 
@@ -138,12 +179,36 @@ impl AnalyzeEcmascriptModuleResultBuilder {
         self.exports = exports;
     }
 
+    /// Sets how the exports of ESM imported modules are used.
+    pub fn set_import_usage(&mut self, import_usage: Vec<EsmImportUsage>) {
+        self.import_usage = import_usage;
+    }
+
+    /// Records a statically detected `process.env.X` read.
+    pub fn add_process_env_read(&mut self, name: &str) {
+        self.process_env_reads.insert(name.to_string());
+    }
+
+    /// Marks the module as only consisting of re-exports.
+    pub fn set_reexport_only(&mut self, reexport_only: bool) {
+        self.reexport_only = reexport_only;
+    }
+
+    /// Marks the module as failed, as `stage` of processing it panicked.
+    pub fn set_panicked(&mut self, stage: &str) {
+        self.panicked = Some(stage.to_string());
+    }
+
     /// Builds the final analysis result.
     pub fn build(self) -> AnalyzeEcmascriptModuleResultVc {
         AnalyzeEcmascriptModuleResultVc::cell(AnalyzeEcmascriptModuleResult {
             references: AssetReferencesVc::cell(self.references),
             code_generation: CodeGenerateablesVc::cell(self.code_gens),
             exports: self.exports.into(),
+            import_usage: EsmImportUsagesVc::cell(self.import_usage),
+            process_env_reads: StringsVc::cell(self.process_env_reads.into_iter().collect()),
+            reexport_only: BoolVc::cell(self.reexport_only),
+            panicked: self.panicked,
         })
     }
 }
@@ -167,6 +232,26 @@ pub(crate) async fn analyze_ecmascript_module(
     ty: Value<EcmascriptModuleAssetType>,
     transforms: EcmascriptInputTransformsVc,
     environment: EnvironmentVc,
+) -> Result<AnalyzeEcmascriptModuleResultVc> {
+    Ok(catch_panic(
+        source.path(),
+        "analyzing",
+        analyze_ecmascript_module_internal(source, origin, ty, transforms, environment),
+    )
+    .await?
+    .unwrap_or_else(|| {
+        let mut analysis = AnalyzeEcmascriptModuleResultBuilder::new();
+        analysis.set_panicked("analyzing");
+        analysis.build()
+    }))
+}
+
+async fn analyze_ecmascript_module_internal(
+    source: AssetVc,
+    origin: ResolveOriginVc,
+    ty: Value<EcmascriptModuleAssetType>,
+    transforms: EcmascriptInputTransformsVc,
+    environment: EnvironmentVc,
 ) -> Result<AnalyzeEcmascriptModuleResultVc> {
     let mut analysis = AnalyzeEcmascriptModuleResultBuilder::new();
     let path = source.path();
@@ -251,9 +336,8 @@ pub(crate) async fn analyze_ecmascript_module(
                         }
                         if let Some(m) = SOURCE_MAP_FILE_REFERENCE.captures(&comment.text) {
                             let path = &m[1];
-                            // TODO this probably needs to be a field in EcmascriptModuleAsset so it
-                            // knows to use that SourceMap when running code generation.
-                            // The reference is needed too for turbotrace
+                            // Code generation reads the map with `input_source_map`, the
+                            // reference is needed for turbotrace
                             analysis.add_reference(SourceMapVc::new(
                                 source.path(),
                                 source.path().parent().join(path),
@@ -280,15 +364,18 @@ pub(crate) async fn analyze_ecmascript_module(
                 webpack_chunks,
                 esm_exports,
                 esm_star_exports,
+                hot_dependencies,
+                import_meta_hot_spans,
             ) = HANDLER.set(&handler, || {
                 GLOBALS.set(globals, || {
                     let var_graph = create_graph(program, eval_context);
 
-                    for (src, annotations) in eval_context.imports.references() {
+                    for (i, (src, annotations)) in eval_context.imports.references().enumerate() {
                         let r = EsmAssetReferenceVc::new(
                             origin,
                             RequestVc::parse(Value::new(src.to_string().into())),
                             Value::new(annotations.clone()),
+                            eval_context.imports.is_named_imports_only(i),
                         );
                         import_references.push(r);
                         analysis.add_reference(r);
@@ -334,10 +421,36 @@ pub(crate) async fn analyze_ecmascript_module(
                         visitor.webpack_chunks,
                         visitor.esm_exports,
                         visitor.esm_star_exports,
+                        visitor.hot_dependencies,
+                        visitor.import_meta_hot_spans,
                     )
                 })
             });
 
+            analysis.set_reexport_only(is_reexport_only(program));
+
+            for (request, path, esm) in hot_dependencies {
+                analysis.add_reference(ModuleHotReferenceAssetReferenceVc::new(
+                    origin,
+                    RequestVc::parse(Value::new(request.into())),
+                    AstPathVc::cell(path),
+                    esm,
+                ));
+            }
+
+            let mut import_usage = import_references
+                .iter()
+                .map(|r| EsmImportUsage::new(*r))
+                .collect::<Vec<_>>();
+            for (i, reexport) in eval_context.imports.reexports() {
+                let usage = &mut import_usage[i];
+                match reexport {
+                    Reexport::Star => usage.star_reexport = true,
+                    Reexport::Namespace { .. } => usage.usage = ExportUsage::All,
+                    Reexport::Named { imported, .. } => usage.add_export(imported),
+                }
+            }
+
             let mut ignore_effect_span = None;
             // Check if it was a webpack entry
             if let Some((request, span)) = webpack_runtime {
@@ -382,6 +495,7 @@ pub(crate) async fn analyze_ecmascript_module(
 
             let exports = if !esm_exports.is_empty() || !esm_star_exports.is_empty() {
                 let esm_exports: EsmExportsVc = EsmExports {
+                    path,
                     exports: esm_exports,
                     star_exports: esm_star_exports,
                 }
@@ -391,7 +505,24 @@ pub(crate) async fn analyze_ecmascript_module(
             } else if let Program::Module(_) = program {
                 EcmascriptExports::None
             } else {
-                EcmascriptExports::CommonJs
+                let cjs_exports = detect_cjs_exports(program, eval_context.unresolved_mark);
+                EcmascriptExports::CommonJs(
+                    CjsExports {
+                        names: cjs_exports.names,
+                        reexports: cjs_exports
+                            .reexports
+                            .into_iter()
+                            .map(|request| {
+                                CjsAssetReferenceVc::new(
+                                    origin,
+                                    RequestVc::parse(Value::new(request.into())),
+                                )
+                            })
+                            .collect(),
+                        has_dynamic_exports: cjs_exports.has_dynamic_exports,
+                    }
+                    .cell(),
+                )
             };
 
             analysis.set_exports(exports);
@@ -606,6 +737,40 @@ pub(crate) async fn analyze_ecmascript_module(
                         )
                     }
 
+                    JsValue::WellKnownFunction(WellKnownFunctionKind::RequireEnsure) => {
+                        let args = linked_args().await?;
+                        if let [JsValue::Array(_, deps), _, ..] = &args[..] {
+                            if args.len() <= 4 && deps.iter().all(|dep| dep.as_str().is_some()) {
+                                let requests = deps
+                                    .iter()
+                                    .filter_map(|dep| dep.as_str())
+                                    .map(|dep| RequestVc::parse_string(dep.to_string()))
+                                    .collect::<Vec<_>>();
+                                for request in requests.iter() {
+                                    analysis.add_reference(RequireEnsureAssetReferenceVc::new(
+                                        origin, *request,
+                                    ));
+                                }
+                                analysis.add_code_gen(RequireEnsureCodeGenVc::new(
+                                    requests,
+                                    origin,
+                                    AstPathVc::cell(ast_path.to_vec()),
+                                ));
+                                return Ok(());
+                            }
+                        }
+                        let (args, hints) = explain_args(&args);
+                        handler.span_warn_with_code(
+                            span,
+                            &format!(
+                                "require.ensure({args}) is not statically analyse-able{hints}",
+                            ),
+                            DiagnosticId::Error(
+                                errors::failed_to_analyse::ecmascript::REQUIRE_ENSURE.to_string(),
+                            ),
+                        )
+                    }
+
                     JsValue::WellKnownFunction(WellKnownFunctionKind::FsReadMethod(name)) => {
                         let args = linked_args().await?;
                         if !args.is_empty() {
@@ -1052,6 +1217,14 @@ pub(crate) async fn analyze_ecmascript_module(
                             .cell(),
                         );
                     }
+                    (
+                        JsValue::WellKnownObject(WellKnownObjectKind::NodeProcessEnv),
+                        JsValue::Constant(s),
+                    ) => {
+                        if let Some(name) = s.as_str() {
+                            analysis.add_process_env_read(name);
+                        }
+                    }
                     _ => {}
                 }
 
@@ -1062,8 +1235,19 @@ pub(crate) async fn analyze_ecmascript_module(
             let linker = |value| value_visitor(source, origin, value, environment);
             let effects = take(&mut var_graph.effects);
             let link_value = |value| link(&var_graph, value, &linker, &cache);
+            let mut export_references = HashMap::new();
+            // Paths of branches which are never taken
+            let mut dead_branches: Vec<Vec<AstParentKind>> = Vec::new();
 
             for effect in effects.into_iter() {
+                // References in branches which are never taken are removed with the
+                // branches
+                if dead_branches
+                    .iter()
+                    .any(|branch| effect.ast_path().starts_with(branch))
+                {
+                    continue;
+                }
                 match effect {
                     Effect::Call {
                         func,
@@ -1139,9 +1323,28 @@ pub(crate) async fn analyze_ecmascript_module(
                     Effect::ImportedBinding {
                         esm_reference_index,
                         export,
+                        member,
+                        destructured,
                         ast_path,
                         span: _,
                     } => {
+                        let named_imports_only = eval_context
+                            .imports
+                            .is_named_imports_only(esm_reference_index);
+                        // Static member reads and destructuring of named imports are
+                        // tracked by the references to the members
+                        let member = member.filter(|_| named_imports_only && export.is_some());
+                        let destructured = destructured.filter(|properties| {
+                            named_imports_only && export.is_some() && !properties.is_empty()
+                        });
+                        if let Some(usage) = import_usage.get_mut(esm_reference_index) {
+                            match export.as_deref() {
+                                Some("__turbopack_module_id__") => {}
+                                Some(_) if member.is_some() || destructured.is_some() => {}
+                                Some(name) => usage.add_export(name),
+                                None => usage.usage = ExportUsage::All,
+                            }
+                        }
                         if let Some(r) = import_references.get(esm_reference_index) {
                             if let Some("__turbopack_module_id__") = export.as_deref() {
                                 analysis.add_reference(EsmModuleIdAssetReferenceVc::new(
@@ -1149,15 +1352,64 @@ pub(crate) async fn analyze_ecmascript_module(
                                     AstPathVc::cell(ast_path),
                                 ))
                             } else {
+                                let r = match (export.as_deref(), &destructured) {
+                                    (Some(name), Some(properties)) => {
+                                        // The binding uses the reference of any of the
+                                        // properties, they all import the same module
+                                        let mut binding_reference = None;
+                                        for property in properties {
+                                            let property_reference = *export_references
+                                                .entry((
+                                                    esm_reference_index,
+                                                    name.to_string(),
+                                                    Some(property.clone()),
+                                                ))
+                                                .or_insert_with(|| {
+                                                    let export_reference =
+                                                        r.with_export_member(name, property);
+                                                    analysis.add_reference(export_reference);
+                                                    export_reference
+                                                });
+                                            binding_reference.get_or_insert(property_reference);
+                                        }
+                                        binding_reference.unwrap_or(*r)
+                                    }
+                                    (Some(name), None) if named_imports_only => {
+                                        // A reference per imported export allows to skip
+                                        // modules which only re-export
+                                        *export_references
+                                            .entry((
+                                                esm_reference_index,
+                                                name.to_string(),
+                                                member.clone(),
+                                            ))
+                                            .or_insert_with(|| {
+                                                let export_reference = match &member {
+                                                    Some(member) => {
+                                                        r.with_export_member(name, member)
+                                                    }
+                                                    None => r.with_export(name),
+                                                };
+                                                analysis.add_reference(export_reference);
+                                                export_reference
+                                            })
+                                    }
+                                    _ => *r,
+                                };
                                 analysis.add_code_gen(EsmBindingVc::new(
-                                    *r,
+                                    r,
                                     export,
+                                    member,
                                     AstPathVc::cell(ast_path),
                                 ));
                             }
                         }
                     }
                     Effect::ImportMeta { span, ast_path: _ } => {
+                        // `import.meta.hot` is replaced by the HMR API
+                        if import_meta_hot_spans.contains(&span) {
+                            continue;
+                        }
                         handler.span_warn_with_code(
                             span,
                             "import.meta is not yet supported",
@@ -1166,9 +1418,64 @@ pub(crate) async fn analyze_ecmascript_module(
                             ),
                         );
                     }
+                    Effect::FreeVar {
+                        var,
+                        ast_path,
+                        span: _,
+                    } => {
+                        // Globals the environment doesn't have are imported from the
+                        // modules providing them
+                        if let JsValue::FreeVar(kind) = &var {
+                            if let Some(global) = &*environment.provided_global(kind.name()).await?
+                            {
+                                analysis.add_reference(ProvideAssetReferenceVc::new(
+                                    origin,
+                                    RequestVc::parse(Value::new(global.request.clone().into())),
+                                    global.export.clone(),
+                                    AstPathVc::cell(ast_path),
+                                ));
+                            }
+                        }
+                    }
+                    Effect::Conditional {
+                        mut condition,
+                        kind,
+                        ast_path,
+                        span: _,
+                    } => {
+                        for value in condition.values_mut() {
+                            *value = link_value(take(value)).await?;
+                        }
+                        if let Some(taken) = condition.evaluate() {
+                            let skipped = match (kind, taken) {
+                                (ConditionalKind::If, true) => {
+                                    AstParentKind::IfStmt(IfStmtField::Alt)
+                                }
+                                (ConditionalKind::If, false) => {
+                                    AstParentKind::IfStmt(IfStmtField::Cons)
+                                }
+                                (ConditionalKind::Ternary, true) => {
+                                    AstParentKind::CondExpr(CondExprField::Alt)
+                                }
+                                (ConditionalKind::Ternary, false) => {
+                                    AstParentKind::CondExpr(CondExprField::Cons)
+                                }
+                            };
+                            let mut branch = ast_path.clone();
+                            branch.push(skipped);
+                            dead_branches.push(branch);
+                            analysis.add_code_gen(ConstantConditionVc::new(
+                                taken,
+                                AstPathVc::cell(ast_path),
+                            ));
+                        }
+                    }
                 }
             }
+
+            analysis.set_import_usage(import_usage);
         }
+        ParseResult::Panicked => analysis.set_panicked("parsing"),
         ParseResult::Unparseable | ParseResult::NotFound => {}
     };
 
@@ -1406,6 +1713,9 @@ async fn value_visitor_inner(
             JsValue::FreeVar(FreeVarKind::Object) => {
                 JsValue::WellKnownObject(WellKnownObjectKind::GlobalObject)
             }
+            JsValue::FreeVar(FreeVarKind::Other(ref name)) if &**name == "undefined" => {
+                JsValue::Constant(ConstantValue::Undefined)
+            }
             JsValue::FreeVar(_) => JsValue::Unknown(Some(Arc::new(v)), "unknown global"),
             JsValue::Module(ModuleValue {
                 module: ref name, ..
@@ -1524,6 +1834,10 @@ impl StaticAnalyser {
                 },
                 _ => StaticExpr::Unknown,
             },
+            Expr::MetaProp(MetaPropExpr {
+                kind: MetaPropKind::ImportMeta,
+                ..
+            }) => StaticExpr::FreeVar(vec!["import.meta".to_string()]),
             _ => StaticExpr::Unknown,
         }
     }
@@ -1539,6 +1853,11 @@ struct AssetReferencesVisitor<'a> {
     webpack_runtime: Option<(String, Span)>,
     webpack_entry: bool,
     webpack_chunks: Vec<Lit>,
+    /// Requests passed to `module.hot.accept`/`decline`, with the paths to
+    /// them and whether they were passed to `import.meta.hot`.
+    hot_dependencies: Vec<(String, Vec<AstParentKind>, bool)>,
+    /// Spans of the `import.meta` of `import.meta.hot` expressions.
+    import_meta_hot_spans: HashSet<Span>,
 }
 
 impl<'a> AssetReferencesVisitor<'a> {
@@ -1557,6 +1876,8 @@ impl<'a> AssetReferencesVisitor<'a> {
             webpack_runtime: None,
             webpack_entry: false,
             webpack_chunks: Vec::new(),
+            hot_dependencies: Vec::new(),
+            import_meta_hot_spans: HashSet::new(),
         }
     }
 }
@@ -1849,12 +2170,89 @@ impl<'a> VisitAstPath for AssetReferencesVisitor<'a> {
                             }
                         }
                     }
+                    [module, hot, method]
+                        if (module == "module" || module == "import.meta")
+                            && hot == "hot"
+                            && (method == "accept" || method == "decline") =>
+                    {
+                        self.add_hot_dependencies(call, ast_path, module == "import.meta");
+                    }
                     _ => {}
                 }
             }
         }
         call.visit_children_with_path(self, ast_path);
     }
+
+    fn visit_member_expr<'ast: 'r, 'r>(
+        &mut self,
+        member: &'ast MemberExpr,
+        ast_path: &mut AstNodePath<AstParentNodeRef<'r>>,
+    ) {
+        if let (
+            Expr::MetaProp(
+                meta_prop @ MetaPropExpr {
+                    kind: MetaPropKind::ImportMeta,
+                    ..
+                },
+            ),
+            MemberProp::Ident(prop),
+        ) = (&*member.obj, &member.prop)
+        {
+            if &*prop.sym == "hot" {
+                self.import_meta_hot_spans.insert(meta_prop.span);
+                self.analysis
+                    .add_code_gen(ImportMetaHotVc::new(AstPathVc::cell(as_parent_path(
+                        ast_path,
+                    ))));
+            }
+        }
+        member.visit_children_with_path(self, ast_path);
+    }
+}
+
+impl<'a> AssetReferencesVisitor<'a> {
+    /// Records the dependencies passed as first argument to
+    /// `module.hot.accept` or `module.hot.decline`, either a string or an
+    /// array of strings.
+    fn add_hot_dependencies(
+        &mut self,
+        call: &CallExpr,
+        ast_path: &AstNodePath<AstParentNodeRef<'_>>,
+        esm: bool,
+    ) {
+        let expr = if let Some(ExprOrSpread { spread: None, expr }) = call.args.first() {
+            expr
+        } else {
+            return;
+        };
+        let mut path = as_parent_path(ast_path);
+        path.push(AstParentKind::CallExpr(CallExprField::Args(0)));
+        path.push(AstParentKind::ExprOrSpread(ExprOrSpreadField::Expr));
+        match &**expr {
+            Expr::Lit(Lit::Str(str)) => {
+                self.hot_dependencies
+                    .push((str.value.to_string(), path, esm));
+            }
+            Expr::Array(array) => {
+                for (i, elem) in array.elems.iter().enumerate() {
+                    if let Some(ExprOrSpread {
+                        spread: None,
+                        expr: box Expr::Lit(Lit::Str(str)),
+                    }) = elem
+                    {
+                        let mut path = path.clone();
+                        path.push(AstParentKind::Expr(ExprField::Array));
+                        path.push(AstParentKind::ArrayLit(ArrayLitField::Elems(i)));
+                        path.push(AstParentKind::ExprOrSpread(ExprOrSpreadField::Expr));
+                        self.hot_dependencies
+                            .push((str.value.to_string(), path, esm));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
 }
 
 #[turbo_tasks::function]
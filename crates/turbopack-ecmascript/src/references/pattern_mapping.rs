@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use indexmap::IndexMap;
 use swc_core::{
     ecma::ast::{Expr, Lit},
@@ -6,8 +6,9 @@ use swc_core::{
 };
 use turbo_tasks::{debug::ValueDebug, primitives::StringVc, Value, ValueToString};
 use turbopack_core::{
+    asset::AssetVc,
     chunk::{ChunkableAssetVc, ChunkingContextVc, FromChunkableAsset, ModuleId},
-    issue::{code_gen::CodeGenerationIssue, IssueSeverity},
+    issue::{code_gen::CodeGenerationIssue, IssueSeverity, OptionIssueSourceVc},
     resolve::{
         origin::ResolveOriginVc, parse::RequestVc, ResolveResult, ResolveResultVc, SpecialType,
     },
@@ -45,6 +46,8 @@ pub(crate) enum PatternMapping {
     OriginalReferenceExternal,
     /// Original reference with different request
     OriginalReferenceTypeExternal(String),
+    /// External read from the global variable with the name
+    GlobalExternal(String),
 }
 
 #[derive(PartialOrd, Ord, Hash, Debug, Copy, Clone)]
@@ -63,7 +66,23 @@ impl PatternMapping {
             | PatternMapping::Single(_)
             | PatternMapping::Map(_) => true,
             PatternMapping::OriginalReferenceExternal
-            | PatternMapping::OriginalReferenceTypeExternal(_) => false,
+            | PatternMapping::OriginalReferenceTypeExternal(_)
+            | PatternMapping::GlobalExternal(_) => false,
+        }
+    }
+
+    /// The reason why the request is replaced with code throwing at runtime,
+    /// if it is.
+    pub fn invalid_reason(&self) -> Option<String> {
+        match self {
+            PatternMapping::Invalid => Some(
+                "the request doesn't resolve to a module which can be placed in a chunk"
+                    .to_string(),
+            ),
+            PatternMapping::Unresolveable(request) => {
+                Some(format!("the request '{request}' can't be resolved"))
+            }
+            _ => None,
         }
     }
 
@@ -84,9 +103,8 @@ impl PatternMapping {
             PatternMapping::OriginalReferenceExternal => {
                 todo!("emit an error for this case: apply need to be used");
             }
-            PatternMapping::OriginalReferenceTypeExternal(s) => {
-                Expr::Lit(Lit::Str(s.as_str().into()))
-            }
+            PatternMapping::OriginalReferenceTypeExternal(s)
+            | PatternMapping::GlobalExternal(s) => Expr::Lit(Lit::Str(s.as_str().into())),
         }
     }
 
@@ -111,6 +129,7 @@ impl PatternMappingVc {
         context: ChunkingContextVc,
         resolve_result: ResolveResultVc,
         resolve_type: Value<ResolveType>,
+        issue_source: OptionIssueSourceVc,
     ) -> Result<PatternMappingVc> {
         let result = resolve_result.await?;
         let asset = match &*result {
@@ -128,6 +147,9 @@ impl PatternMappingVc {
             ResolveResult::Special(SpecialType::OriginalReferenceTypeExternal(s), _) => {
                 return Ok(PatternMapping::OriginalReferenceTypeExternal(s.clone()).cell())
             }
+            ResolveResult::Special(SpecialType::GlobalExternal(s), _) => {
+                return Ok(PatternMapping::GlobalExternal(s.clone()).cell())
+            }
             ResolveResult::Special(SpecialType::Ignore, _) => {
                 return Ok(PatternMapping::Ignored.cell())
             }
@@ -150,6 +172,7 @@ impl PatternMappingVc {
                         resolve_result.dbg().await?
                     )),
                     path: origin.origin_path(),
+                    source: *issue_source.await?,
                 }
                 .cell()
                 .as_issue()
@@ -160,8 +183,13 @@ impl PatternMappingVc {
 
         if let Some(chunkable) = ChunkableAssetVc::resolve_from(asset).await? {
             if *resolve_type == ResolveType::EsmAsync {
+                // The loader must be the same as the one placed into the chunk,
+                // which is created for the importing module
+                let parent = AssetVc::resolve_from(origin)
+                    .await?
+                    .context("async imports must originate from a module")?;
                 if let Some((loader, _)) =
-                    EcmascriptChunkItemVc::from_async_asset(context, chunkable).await?
+                    EcmascriptChunkItemVc::from_async_asset(context, chunkable, parent).await?
                 {
                     return Ok(PatternMappingVc::cell(PatternMapping::Single(
                         loader.id().await?.clone_value(),
@@ -183,6 +211,7 @@ impl PatternMappingVc {
                 asset.path().to_string().await?
             )),
             path: origin.origin_path(),
+            source: *issue_source.await?,
         }
         .cell()
         .as_issue()
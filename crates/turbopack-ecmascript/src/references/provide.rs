@@ -0,0 +1,163 @@
+use anyhow::Result;
+use swc_core::{
+    ecma::{
+        ast::{Expr, KeyValueProp, Lit, Prop, PropName},
+        visit::{
+            fields::{ExprField, PropField},
+            AstParentKind,
+        },
+    },
+    quote,
+};
+use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
+use turbopack_core::{
+    chunk::{ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc},
+    issue::OptionIssueSourceVc,
+    reference::{AssetReference, AssetReferenceVc},
+    resolve::{origin::ResolveOriginVc, parse::RequestVc, ResolveResultVc},
+};
+
+use super::pattern_mapping::{PatternMapping, PatternMappingVc, ResolveType::Cjs};
+use crate::{
+    code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
+    create_visitor,
+    references::{
+        util::{ast_path_issue_source, global_external_expr},
+        AstPathVc,
+    },
+    resolve::cjs_resolve,
+};
+
+/// A read of a global which the environment doesn't have, but a module
+/// provides, e. g. `Buffer` from the `buffer` package. The global is replaced
+/// with the export of the required module.
+#[turbo_tasks::value]
+#[derive(Hash, Debug)]
+pub struct ProvideAssetReference {
+    pub origin: ResolveOriginVc,
+    pub request: RequestVc,
+    /// The export which is the global, or `None` when it's the module itself.
+    pub export: Option<String>,
+    pub path: AstPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ProvideAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        origin: ResolveOriginVc,
+        request: RequestVc,
+        export: Option<String>,
+        path: AstPathVc,
+    ) -> Self {
+        Self::cell(ProvideAssetReference {
+            origin,
+            request,
+            export,
+            path,
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for ProvideAssetReference {
+    #[turbo_tasks::function]
+    fn resolve_reference(&self) -> ResolveResultVc {
+        cjs_resolve(self.origin, self.request)
+    }
+
+    #[turbo_tasks::function]
+    fn issue_source(&self) -> OptionIssueSourceVc {
+        ast_path_issue_source(self.origin, self.path)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for ProvideAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "provided global from {}",
+            self.request.to_string().await?,
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAssetReference for ProvideAssetReference {}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for ProvideAssetReference {
+    #[turbo_tasks::function]
+    async fn code_generation(&self, context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        let pm = PatternMappingVc::resolve_request(
+            self.request,
+            self.origin,
+            context,
+            cjs_resolve(self.origin, self.request),
+            Value::new(Cjs),
+            ast_path_issue_source(self.origin, self.path),
+        )
+        .await?;
+
+        let mut path = self.path.await?.clone_value();
+        let invalid_references = pm
+            .invalid_reason()
+            .map(|reason| (path.clone(), reason))
+            .into_iter()
+            .collect();
+
+        let module = match &*pm {
+            PatternMapping::GlobalExternal(name) => global_external_expr(name),
+            PatternMapping::Invalid
+            | PatternMapping::Unresolveable(_)
+            | PatternMapping::Ignored => pm.create(),
+            _ if pm.is_internal_import() => {
+                quote!("__turbopack_require__($id)" as Expr, id: Expr = pm.create())
+            }
+            _ => quote!(
+                "__turbopack_external_require__($id)" as Expr,
+                id: Expr = pm.create()
+            ),
+        };
+        let replacement = match &self.export {
+            Some(export) => quote!(
+                "$module[$export]" as Expr,
+                module: Expr = module,
+                export: Expr = Expr::Lit(Lit::Str(export.as_str().into()))
+            ),
+            None => module,
+        };
+
+        let mut visitors = Vec::new();
+        match path.last() {
+            Some(AstParentKind::Expr(ExprField::Ident)) => {
+                path.pop();
+                visitors.push(
+                    create_visitor!(exact path, visit_mut_expr(expr: &mut Expr) {
+                        *expr = replacement.clone();
+                    }),
+                );
+            }
+            Some(AstParentKind::Prop(PropField::Shorthand)) => {
+                path.pop();
+                visitors.push(create_visitor!(path, visit_mut_prop(prop: &mut Prop) {
+                    if let Prop::Shorthand(ident) = prop {
+                        *prop = Prop::KeyValue(KeyValueProp {
+                            key: PropName::Ident(ident.clone()),
+                            value: box replacement.clone(),
+                        });
+                    }
+                }));
+            }
+            // Assignments to the global are left alone
+            _ => {}
+        }
+
+        Ok(CodeGeneration {
+            visitors,
+            invalid_references,
+        }
+        .into())
+    }
+}
@@ -0,0 +1,187 @@
+use std::mem::take;
+
+use anyhow::Result;
+use swc_core::{
+    common::DUMMY_SP,
+    ecma::ast::{ArrayLit, Callee, Expr, ExprOrSpread},
+    quote, quote_expr,
+};
+use turbo_tasks::{primitives::StringVc, TryJoinIterExt, Value, ValueToString, ValueToStringVc};
+use turbopack_core::{
+    chunk::{
+        ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc, ChunkingType,
+        ChunkingTypeOptionVc,
+    },
+    reference::{AssetReference, AssetReferenceKind, AssetReferenceKindVc, AssetReferenceVc},
+    resolve::{origin::ResolveOriginVc, parse::RequestVc, ResolveResultVc},
+};
+
+use super::pattern_mapping::{PatternMapping, PatternMappingVc, ResolveType::EsmAsync};
+use crate::{
+    code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
+    create_visitor,
+    references::{util::ast_path_issue_source, AstPathVc},
+    resolve::cjs_resolve,
+};
+
+/// A dependency of a `require.ensure([...], callback)` call, which is loaded
+/// in a separate chunk like a dynamic import.
+#[turbo_tasks::value]
+#[derive(Hash, Debug)]
+pub struct RequireEnsureAssetReference {
+    pub origin: ResolveOriginVc,
+    pub request: RequestVc,
+}
+
+#[turbo_tasks::value_impl]
+impl RequireEnsureAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(origin: ResolveOriginVc, request: RequestVc) -> Self {
+        Self::cell(RequireEnsureAssetReference { origin, request })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for RequireEnsureAssetReference {
+    #[turbo_tasks::function]
+    fn resolve_reference(&self) -> ResolveResultVc {
+        cjs_resolve(self.origin, self.request)
+    }
+
+    #[turbo_tasks::function]
+    fn kind(&self) -> AssetReferenceKindVc {
+        AssetReferenceKind::Async.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for RequireEnsureAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "require.ensure {}",
+            self.request.to_string().await?,
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAssetReference for RequireEnsureAssetReference {
+    #[turbo_tasks::function]
+    fn chunking_type(&self, _context: ChunkingContextVc) -> ChunkingTypeOptionVc {
+        ChunkingTypeOptionVc::cell(Some(ChunkingType::SeparateAsync))
+    }
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub struct RequireEnsureCodeGen {
+    requests: Vec<RequestVc>,
+    origin: ResolveOriginVc,
+    path: AstPathVc,
+}
+
+impl RequireEnsureCodeGenVc {
+    pub fn new(requests: Vec<RequestVc>, origin: ResolveOriginVc, path: AstPathVc) -> Self {
+        Self::cell(RequireEnsureCodeGen {
+            requests,
+            origin,
+            path,
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for RequireEnsureCodeGen {
+    #[turbo_tasks::function]
+    async fn code_generation(&self, context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        let pattern_mappings = self
+            .requests
+            .iter()
+            .map(|request| {
+                PatternMappingVc::resolve_request(
+                    *request,
+                    self.origin,
+                    context,
+                    cjs_resolve(self.origin, *request),
+                    Value::new(EsmAsync),
+                    ast_path_issue_source(self.origin, self.path),
+                )
+            })
+            .try_join()
+            .await?;
+
+        let path = self.path.await?;
+        let invalid_references = pattern_mappings
+            .iter()
+            .filter_map(|pm| pm.invalid_reason())
+            .map(|reason| (path.to_vec(), reason))
+            .collect();
+        let visitor =
+            // Transforms `require.ensure([dep1, dep2], callback, errorCallback)` into:
+            // ```js
+            // Promise.all([
+            //   __turbopack_require__(loader1)(__turbopack_import__),
+            //   __turbopack_require__(loader2)(__turbopack_import__),
+            // ]).then(() => callback(__turbopack_require__), errorCallback)
+            // ```
+            create_visitor!(exact path, visit_mut_call_expr(call_expr: &mut CallExpr) {
+                let mut args = take(&mut call_expr.args).into_iter();
+                let dependencies = match args.next() {
+                    Some(ExprOrSpread { expr: box Expr::Array(ArrayLit { elems, .. }), spread: None }) => elems,
+                    _ => Vec::new(),
+                };
+                let callback = match args.next() {
+                    Some(ExprOrSpread { expr, spread: None }) => expr,
+                    _ => quote_expr!("() => {}"),
+                };
+                let error_callback = match args.next() {
+                    Some(ExprOrSpread { expr, spread: None }) => expr,
+                    _ => quote_expr!("undefined"),
+                };
+                let loaders = pattern_mappings.iter().zip(dependencies.into_iter().chain(std::iter::repeat(None))).map(|(pm, dependency)| {
+                    let expr = match (&**pm, dependency) {
+                        (PatternMapping::Single(_), _) => quote!(
+                            "__turbopack_require__($loader)(__turbopack_import__)" as Expr,
+                            loader: Expr = pm.create()
+                        ),
+                        // Global variables are available without loading anything
+                        (PatternMapping::GlobalExternal(_), _) => quote!("Promise.resolve()" as Expr),
+                        (pm, Some(ExprOrSpread { expr, spread: None })) if !pm.is_internal_import() => quote!(
+                            "Promise.resolve().then(() => __turbopack_external_require__($request))" as Expr,
+                            request: Expr = pm.apply(*expr)
+                        ),
+                        (pm, _) if pm.is_internal_import() => quote!(
+                            "Promise.resolve().then(() => $error)" as Expr,
+                            error: Expr = pm.create()
+                        ),
+                        _ => quote!("Promise.resolve()" as Expr),
+                    };
+                    Some(ExprOrSpread { spread: None, expr: box expr })
+                }).collect();
+                call_expr.callee = Callee::Expr(quote_expr!(
+                    "Promise.all($loaders).then",
+                    loaders: Expr = Expr::Array(ArrayLit {
+                        span: DUMMY_SP,
+                        elems: loaders,
+                    })
+                ));
+                call_expr.args = vec![
+                    ExprOrSpread {
+                        spread: None,
+                        expr: quote_expr!(
+                            "() => $callback(__turbopack_require__)",
+                            callback: Expr = *callback
+                        ),
+                    },
+                    ExprOrSpread { spread: None, expr: error_callback },
+                ];
+            });
+
+        Ok(CodeGeneration {
+            visitors: vec![visitor],
+            invalid_references,
+        }
+        .into())
+    }
+}
@@ -1,7 +1,17 @@
 use anyhow::Result;
-use swc_core::{ecma::ast::Expr, quote};
-use turbo_tasks::primitives::StringVc;
-use turbopack_core::resolve::parse::RequestVc;
+use swc_core::{common::source_map::Pos, ecma::ast::Expr, quote};
+use turbo_tasks::{primitives::StringVc, Value};
+use turbopack_core::{
+    issue::{IssueSourceVc, OptionIssueSourceVc},
+    resolve::{origin::ResolveOriginVc, parse::RequestVc},
+};
+
+use super::AstPathVc;
+use crate::{
+    parse::{parse, ParseResult},
+    path_visitor::find_node_spans,
+    EcmascriptModuleAssetVc,
+};
 
 /// Creates a IIFE expression that throws a "Cannot find module" error for the
 /// given request string
@@ -14,6 +24,22 @@ pub fn throw_module_not_found_expr(request: &str) -> Expr {
     )
 }
 
+/// Creates an expression reading the global variable of an external, e. g.
+/// `globalThis["jQuery"]`.
+pub fn global_external_expr(name: &str) -> Expr {
+    quote!("globalThis[$name]" as Expr, name: Expr = name.into())
+}
+
+/// Creates an expression wrapping the global variable of an external in a
+/// namespace object, with the value as default export, as it's imported with
+/// `import()`.
+pub fn global_external_namespace_expr(name: &str) -> Expr {
+    quote!(
+        "((v) => Object.assign(Object.create(null), v, { default: v }))($value)" as Expr,
+        value: Expr = global_external_expr(name)
+    )
+}
+
 #[turbo_tasks::function]
 pub async fn request_to_string(request: RequestVc) -> Result<StringVc> {
     Ok(StringVc::cell(
@@ -24,3 +50,36 @@ pub async fn request_to_string(request: RequestVc) -> Result<StringVc> {
             .unwrap_or_else(|| "unknown".into()),
     ))
 }
+
+/// The location of the node at `path` in the code of `origin`, e. g. of a
+/// reference, so issues about it can be shown with a code frame. There is none
+/// when `origin` isn't an ecmascript module.
+#[turbo_tasks::function]
+pub async fn ast_path_issue_source(
+    origin: ResolveOriginVc,
+    path: AstPathVc,
+) -> Result<OptionIssueSourceVc> {
+    let module = match EcmascriptModuleAssetVc::resolve_from(origin).await? {
+        Some(module) => module.await?,
+        None => return Ok(OptionIssueSourceVc::cell(None)),
+    };
+    let parsed = parse(module.source, Value::new(module.ty), module.transforms).await?;
+    let (program, source_map) = match &*parsed {
+        ParseResult::Ok {
+            program,
+            source_map,
+            ..
+        } => (program, source_map),
+        _ => return Ok(OptionIssueSourceVc::cell(None)),
+    };
+    let path = path.await?;
+    Ok(OptionIssueSourceVc::cell(
+        find_node_spans(program, [&*path]).get(&*path).map(|span| {
+            IssueSourceVc::from_byte_offset(
+                module.source,
+                source_map.lookup_byte_offset(span.lo()).pos.to_usize(),
+                source_map.lookup_byte_offset(span.hi()).pos.to_usize(),
+            )
+        }),
+    ))
+}
@@ -24,6 +24,16 @@ pub async fn apply_esm_specific_options(options: ResolveOptionsVc) -> Result<Res
     Ok(options.into())
 }
 
+/// Like [apply_esm_specific_options], for requests imported with `import()`.
+#[turbo_tasks::function]
+pub async fn apply_esm_async_specific_options(
+    options: ResolveOptionsVc,
+) -> Result<ResolveOptionsVc> {
+    let mut options: ResolveOptions = apply_esm_specific_options(options).await?.clone_value();
+    options.async_import = true;
+    Ok(options.into())
+}
+
 #[turbo_tasks::function]
 pub async fn apply_cjs_specific_options(options: ResolveOptionsVc) -> Result<ResolveOptionsVc> {
     let mut options: ResolveOptions = options.await?.clone_value();
@@ -45,6 +55,15 @@ pub async fn esm_resolve(origin: ResolveOriginVc, request: RequestVc) -> Result<
     specific_resolve(origin, request, options, "esm request").await
 }
 
+#[turbo_tasks::function]
+pub async fn esm_async_resolve(
+    origin: ResolveOriginVc,
+    request: RequestVc,
+) -> Result<ResolveResultVc> {
+    let options = apply_esm_async_specific_options(origin.resolve_options());
+    specific_resolve(origin, request, options, "dynamic esm request").await
+}
+
 #[turbo_tasks::function]
 pub async fn cjs_resolve(origin: ResolveOriginVc, request: RequestVc) -> Result<ResolveResultVc> {
     let options = apply_cjs_specific_options(origin.resolve_options());
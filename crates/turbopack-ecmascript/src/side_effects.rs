@@ -0,0 +1,277 @@
+//! Support for the `sideEffects` field of package.json.
+//!
+//! Modules which only re-export from other modules and are declared free of
+//! side effects are skipped: imports of their exports are re-routed to the
+//! modules which define them, so the re-exporting module is not emitted.
+
+use std::collections::HashSet;
+
+use anyhow::{anyhow, bail, Result};
+use swc_core::ecma::ast::{ModuleDecl, ModuleItem, NamedExport, Program};
+use turbo_tasks::primitives::BoolVc;
+use turbo_tasks_fs::{glob::Glob, FileJsonContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::Asset,
+    issue::package_json::PackageJsonIssue,
+    resolve::{find_context_file, FindContextFileResult},
+};
+
+use crate::{
+    chunk::{EcmascriptChunkPlaceableVc, EcmascriptExports},
+    references::esm::{
+        base::ReferencedAsset,
+        export::{expand_star_exports, EsmExport},
+    },
+    EcmascriptModuleAssetVc,
+};
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+enum SideEffectsField {
+    /// The field is missing or `true`: every module might have side effects.
+    All,
+    /// `false`: no module has side effects.
+    None,
+    /// Only modules matching one of the globs have side effects.
+    Globs(Vec<Glob>),
+}
+
+#[turbo_tasks::function]
+async fn side_effects_field(package_json: FileSystemPathVc) -> Result<SideEffectsFieldVc> {
+    let content = package_json.read_json().await?;
+    let field = match &*content {
+        FileJsonContent::Content(content) => &content["sideEffects"],
+        FileJsonContent::Unparseable | FileJsonContent::NotFound => {
+            return Ok(SideEffectsField::All.cell())
+        }
+    };
+    let patterns = match field {
+        serde_json::Value::Bool(false) => return Ok(SideEffectsField::None.cell()),
+        serde_json::Value::Array(patterns) => patterns,
+        _ => return Ok(SideEffectsField::All.cell()),
+    };
+    let mut globs = Vec::new();
+    for pattern in patterns {
+        let result = match pattern.as_str() {
+            Some(pattern) => side_effects_globs(pattern),
+            None => Err(anyhow!("`sideEffects` must only contain strings")),
+        };
+        match result {
+            Ok(pattern_globs) => globs.extend(pattern_globs),
+            Err(err) => {
+                // Without knowing all patterns, every module might have side effects
+                PackageJsonIssue {
+                    path: package_json,
+                    error_message: err.to_string(),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                return Ok(SideEffectsField::All.cell());
+            }
+        }
+    }
+    Ok(SideEffectsField::Globs(globs).cell())
+}
+
+/// Converts a pattern of the `sideEffects` field into globs matching paths
+/// relative to the package. Like webpack, patterns without a `/` match files in
+/// any directory.
+fn side_effects_globs(pattern: &str) -> Result<Vec<Glob>> {
+    if pattern.contains('[') {
+        bail!("character classes are not supported in `sideEffects` pattern {pattern}");
+    }
+    let pattern = pattern.strip_prefix("./").unwrap_or(pattern);
+    expand_braces(pattern)
+        .into_iter()
+        .map(|pattern| {
+            if pattern.contains(['{', '}']) {
+                bail!("unbalanced braces in `sideEffects` pattern {pattern}");
+            }
+            if pattern.contains('/') {
+                Glob::parse(&pattern)
+            } else {
+                Glob::parse(&format!("**/{pattern}"))
+            }
+        })
+        .collect()
+}
+
+/// Expands `{a,b}` alternatives into one pattern per alternative.
+fn expand_braces(pattern: &str) -> Vec<String> {
+    if let Some(start) = pattern.find('{') {
+        if let Some(len) = pattern[start..].find('}') {
+            let end = start + len;
+            let (prefix, suffix) = (&pattern[..start], &pattern[end + 1..]);
+            return pattern[start + 1..end]
+                .split(',')
+                .flat_map(|alternative| expand_braces(&format!("{prefix}{alternative}{suffix}")))
+                .collect();
+        }
+    }
+    vec![pattern.to_string()]
+}
+
+/// Whether the module at `path` is declared to be free of side effects by the
+/// `sideEffects` field of the closest package.json.
+#[turbo_tasks::function]
+pub async fn is_marked_side_effect_free(path: FileSystemPathVc) -> Result<BoolVc> {
+    let package_json = match &*find_context_file(path.parent(), "package.json").await? {
+        FindContextFileResult::Found(package_json, _) => *package_json,
+        FindContextFileResult::NotFound(_) => return Ok(BoolVc::cell(false)),
+    };
+    let side_effect_free = match &*side_effects_field(package_json).await? {
+        SideEffectsField::All => false,
+        SideEffectsField::None => true,
+        SideEffectsField::Globs(globs) => {
+            let package_dir = package_json.parent().await?;
+            match package_dir.get_path_to(&*path.await?) {
+                Some(subpath) => !globs.iter().any(|glob| glob.execute(subpath)),
+                None => false,
+            }
+        }
+    };
+    Ok(BoolVc::cell(side_effect_free))
+}
+
+/// Whether the module only consists of re-exports (`export ... from "..."`).
+pub(crate) fn is_reexport_only(program: &Program) -> bool {
+    match program {
+        Program::Module(module) => {
+            !module.body.is_empty()
+                && module.body.iter().all(|item| {
+                    matches!(
+                        item,
+                        ModuleItem::ModuleDecl(
+                            ModuleDecl::ExportAll(_)
+                                | ModuleDecl::ExportNamed(NamedExport { src: Some(_), .. })
+                        )
+                    )
+                })
+        }
+        Program::Script(_) => false,
+    }
+}
+
+/// Whether imports of `module` can skip it and import from the modules it
+/// re-exports from instead.
+#[turbo_tasks::function]
+pub(crate) async fn is_skippable_reexport_module(
+    module: EcmascriptChunkPlaceableVc,
+) -> Result<BoolVc> {
+    let module = match EcmascriptModuleAssetVc::resolve_from(module).await? {
+        Some(module) => module,
+        None => return Ok(BoolVc::cell(false)),
+    };
+    Ok(BoolVc::cell(
+        *module.analyze().await?.reexport_only.await?
+            && *is_marked_side_effect_free(module.path()).await?,
+    ))
+}
+
+#[turbo_tasks::value(shared)]
+pub struct FollowExportsResult {
+    pub module: EcmascriptChunkPlaceableVc,
+    /// The name of the export in `module`. `None` means the namespace object of
+    /// the module.
+    pub export_name: Option<String>,
+}
+
+/// Follows the export `export_name` of `module` through skippable re-export
+/// modules to the module which defines it.
+#[turbo_tasks::function]
+pub(crate) async fn follow_reexports(
+    module: EcmascriptChunkPlaceableVc,
+    export_name: String,
+) -> Result<FollowExportsResultVc> {
+    let mut module = module.resolve().await?;
+    let mut export_name = export_name;
+    let mut visited = HashSet::new();
+    while visited.insert(module) && *is_skippable_reexport_module(module).await? {
+        let exports = match &*module.get_exports().await? {
+            EcmascriptExports::EsmExports(exports) => exports.await?,
+            _ => break,
+        };
+        match exports.exports.get(&export_name) {
+            Some(EsmExport::ImportedBinding(reference, name)) => {
+                match &*reference.get_referenced_asset().await? {
+                    ReferencedAsset::Some(asset) => {
+                        module = *asset;
+                        export_name = name.clone();
+                    }
+                    _ => break,
+                }
+            }
+            Some(EsmExport::ImportedNamespace(reference)) => {
+                if let ReferencedAsset::Some(asset) = &*reference.get_referenced_asset().await? {
+                    return Ok(FollowExportsResult {
+                        module: *asset,
+                        export_name: None,
+                    }
+                    .cell());
+                }
+                break;
+            }
+            Some(
+                EsmExport::LocalBinding(_)
+                | EsmExport::Constant(_)
+                | EsmExport::ConstantObject(_)
+                | EsmExport::ObjectProperty
+                | EsmExport::PropertiesObject
+                | EsmExport::Error,
+            ) => break,
+            None => {
+                let mut found = None;
+                for reference in exports.star_exports.iter() {
+                    if let ReferencedAsset::Some(asset) = &*reference.get_referenced_asset().await?
+                    {
+                        if expand_star_exports(*asset).await?.contains(&export_name) {
+                            found = Some(*asset);
+                            break;
+                        }
+                    }
+                }
+                match found {
+                    Some(asset) => module = asset,
+                    None => break,
+                }
+            }
+        }
+    }
+    Ok(FollowExportsResult {
+        module,
+        export_name: Some(export_name),
+    }
+    .cell())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_braces, side_effects_globs};
+
+    #[test]
+    fn braces() {
+        assert_eq!(
+            expand_braces("src/*.{css,scss}"),
+            vec!["src/*.css".to_string(), "src/*.scss".to_string()]
+        );
+        assert_eq!(expand_braces("*.js"), vec!["*.js".to_string()]);
+    }
+
+    #[test]
+    fn globs() {
+        let matches = |pattern: &str, path: &str| {
+            side_effects_globs(pattern)
+                .unwrap()
+                .iter()
+                .any(|glob| glob.execute(path))
+        };
+        assert!(matches("*.css", "index.css"));
+        assert!(matches("*.css", "dist/styles/index.css"));
+        assert!(matches("./src/polyfill.js", "src/polyfill.js"));
+        assert!(!matches("./src/polyfill.js", "lib/src/polyfill.js"));
+        assert!(matches("./dist/*.{css,js}", "dist/index.js"));
+        assert!(!matches("./dist/*.{css,js}", "dist/index.mjs"));
+        assert!(side_effects_globs("*.[jt]s").is_err());
+    }
+}
@@ -0,0 +1,312 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use swc_core::{
+    common::{Mark, DUMMY_SP},
+    ecma::{
+        ast::{
+            ArrayLit, Bool, ComputedPropName, Expr, ExprOrSpread, KeyValueProp, Lit, MemberExpr,
+            MemberProp, Null, Number, ObjectLit, Pat, PatOrExpr, Prop, PropName, PropOrSpread, Str,
+            UnaryExpr, UnaryOp, UpdateExpr,
+        },
+        visit::{VisitMut, VisitMutWith},
+    },
+    quote,
+};
+use turbo_tasks::trace::TraceRawVcs;
+
+/// The value an expression is replaced with at compile time. Values are
+/// inserted as literals of their type, e. g. a [CompileTimeDefineValue::String]
+/// becomes a string literal and not the code it contains.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub enum CompileTimeDefineValue {
+    Undefined,
+    Bool(bool),
+    String(String),
+    /// Any JSON value, e. g. a number or an object, which is inserted as
+    /// object literal.
+    Json(#[turbo_tasks(trace_ignore)] serde_json::Value),
+}
+
+impl From<bool> for CompileTimeDefineValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<&str> for CompileTimeDefineValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for CompileTimeDefineValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<serde_json::Value> for CompileTimeDefineValue {
+    fn from(value: serde_json::Value) -> Self {
+        Self::Json(value)
+    }
+}
+
+impl CompileTimeDefineValue {
+    fn to_expr(&self) -> Expr {
+        match self {
+            CompileTimeDefineValue::Undefined => quote!("void 0" as Expr),
+            CompileTimeDefineValue::Bool(value) => Expr::Lit(Lit::Bool(Bool {
+                span: DUMMY_SP,
+                value: *value,
+            })),
+            CompileTimeDefineValue::String(value) => Expr::Lit(Lit::Str(value.as_str().into())),
+            CompileTimeDefineValue::Json(value) => json_to_expr(value),
+        }
+    }
+}
+
+fn json_to_expr(value: &serde_json::Value) -> Expr {
+    match value {
+        serde_json::Value::Null => Expr::Lit(Lit::Null(Null { span: DUMMY_SP })),
+        serde_json::Value::Bool(value) => Expr::Lit(Lit::Bool(Bool {
+            span: DUMMY_SP,
+            value: *value,
+        })),
+        serde_json::Value::Number(value) => {
+            let value = value.as_f64().unwrap_or(f64::NAN);
+            let number = Expr::Lit(Lit::Num(Number {
+                span: DUMMY_SP,
+                value: value.abs(),
+                raw: None,
+            }));
+            if value.is_sign_negative() {
+                quote!("-$number" as Expr, number: Expr = number)
+            } else {
+                number
+            }
+        }
+        serde_json::Value::String(value) => Expr::Lit(Lit::Str(value.as_str().into())),
+        serde_json::Value::Array(values) => Expr::Array(ArrayLit {
+            span: DUMMY_SP,
+            elems: values
+                .iter()
+                .map(|value| {
+                    Some(ExprOrSpread {
+                        spread: None,
+                        expr: box json_to_expr(value),
+                    })
+                })
+                .collect(),
+        }),
+        serde_json::Value::Object(values) => Expr::Object(ObjectLit {
+            span: DUMMY_SP,
+            props: values
+                .iter()
+                .map(|(key, value)| {
+                    PropOrSpread::Prop(box Prop::KeyValue(KeyValueProp {
+                        key: PropName::Str(Str {
+                            span: DUMMY_SP,
+                            value: key.as_str().into(),
+                            raw: None,
+                        }),
+                        value: box json_to_expr(value),
+                    }))
+                })
+                .collect(),
+        }),
+    }
+}
+
+/// Expressions replaced with values at compile time, like `DefinePlugin` in
+/// webpack, by the expression. Expressions are free variables, e. g.
+/// `__DEV__`, static member reads of free variables, e. g. `MY_FLAG.nested`,
+/// or `typeof` of those, e. g. `typeof window`.
+#[turbo_tasks::value(transparent)]
+pub struct CompileTimeDefines(IndexMap<String, CompileTimeDefineValue>);
+
+#[turbo_tasks::value_impl]
+impl CompileTimeDefinesVc {
+    #[turbo_tasks::function]
+    pub fn empty() -> Self {
+        Self::cell(IndexMap::new())
+    }
+}
+
+/// Replaces the expressions of `defines` with their values, so the analyzer
+/// evaluates them as constants.
+pub fn compile_time_defines(
+    defines: &IndexMap<String, CompileTimeDefineValue>,
+    unresolved_mark: Mark,
+) -> impl VisitMut + '_ {
+    CompileTimeDefinesVisitor {
+        defines,
+        unresolved_mark,
+    }
+}
+
+struct CompileTimeDefinesVisitor<'a> {
+    defines: &'a IndexMap<String, CompileTimeDefineValue>,
+    unresolved_mark: Mark,
+}
+
+impl CompileTimeDefinesVisitor<'_> {
+    /// The name of a free variable or of a static member read of it, e. g.
+    /// `MY_FLAG.nested`.
+    fn name(&self, expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Ident(ident) if ident.span.ctxt.outer() == self.unresolved_mark => {
+                Some(ident.sym.to_string())
+            }
+            Expr::Member(MemberExpr { obj, prop, .. }) => {
+                let prop = match prop {
+                    MemberProp::Ident(prop) => &*prop.sym,
+                    MemberProp::Computed(ComputedPropName {
+                        expr: box Expr::Lit(Lit::Str(prop)),
+                        ..
+                    }) => &*prop.value,
+                    _ => return None,
+                };
+                Some(format!("{}.{prop}", self.name(obj)?))
+            }
+            _ => None,
+        }
+    }
+
+    fn lookup(&self, expr: &Expr) -> Option<&CompileTimeDefineValue> {
+        let name = match expr {
+            Expr::Unary(UnaryExpr {
+                op: UnaryOp::TypeOf,
+                arg,
+                ..
+            }) => format!("typeof {}", self.name(arg)?),
+            _ => self.name(expr)?,
+        };
+        self.defines.get(&name)
+    }
+}
+
+impl VisitMut for CompileTimeDefinesVisitor<'_> {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        if let Some(value) = self.lookup(expr) {
+            *expr = value.to_expr();
+            return;
+        }
+        expr.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_prop(&mut self, prop: &mut Prop) {
+        if let Prop::Shorthand(ident) = prop {
+            if ident.span.ctxt.outer() == self.unresolved_mark {
+                if let Some(value) = self.defines.get(&*ident.sym) {
+                    *prop = Prop::KeyValue(KeyValueProp {
+                        key: PropName::Ident(ident.clone()),
+                        value: box value.to_expr(),
+                    });
+                }
+            }
+            return;
+        }
+        prop.visit_mut_children_with(self);
+    }
+
+    // Defined expressions which are assigned to are left alone, a literal
+    // can't be assigned to
+    fn visit_mut_pat_or_expr(&mut self, target: &mut PatOrExpr) {
+        let expr = match target {
+            PatOrExpr::Expr(expr) | PatOrExpr::Pat(box Pat::Expr(expr)) => expr,
+            PatOrExpr::Pat(pat) => {
+                pat.visit_mut_with(self);
+                return;
+            }
+        };
+        if self.lookup(expr).is_none() {
+            expr.visit_mut_children_with(self);
+        }
+    }
+
+    fn visit_mut_update_expr(&mut self, update: &mut UpdateExpr) {
+        if self.lookup(&update.arg).is_none() {
+            update.arg.visit_mut_children_with(self);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+    use swc_core::{
+        common::{errors::HANDLER, FileName, Mark},
+        ecma::{
+            ast::EsVersion,
+            codegen::{text_writer::JsWriter, Emitter},
+            parser::parse_file_as_module,
+            transforms::base::resolver,
+            visit::VisitMutWith,
+        },
+        testing::run_test,
+    };
+
+    use super::{compile_time_defines, CompileTimeDefineValue};
+
+    fn transform(src: &str, defines: &IndexMap<String, CompileTimeDefineValue>) -> String {
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(FileName::Anon, src.to_string());
+            let mut module = parse_file_as_module(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .map_err(|err| HANDLER.with(|handler| err.into_diagnostic(handler).emit()))
+            .unwrap();
+            let unresolved_mark = Mark::new();
+            let top_level_mark = Mark::new();
+            module.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+            module.visit_mut_with(&mut compile_time_defines(defines, unresolved_mark));
+
+            let mut bytes = Vec::new();
+            let mut emitter = Emitter {
+                cfg: swc_core::ecma::codegen::Config {
+                    minify: true,
+                    ..Default::default()
+                },
+                cm: cm.clone(),
+                comments: None,
+                wr: JsWriter::new(cm, "\n", &mut bytes, None),
+            };
+            emitter.emit_module(&module).unwrap();
+            Ok(String::from_utf8(bytes).unwrap())
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn defines() {
+        let mut defines = IndexMap::new();
+        defines.insert("__DEV__".to_string(), true.into());
+        defines.insert("typeof window".to_string(), "object".into());
+        defines.insert(
+            "MY_FLAG.nested".to_string(),
+            serde_json::json!({ "list": [1, -2] }).into(),
+        );
+        let js = transform(
+            r#"
+            const dev = __DEV__;
+            const isServer = typeof window === "undefined";
+            const list = MY_FLAG.nested.list;
+            const other = MY_FLAG.other;
+            MY_FLAG.nested = null;
+            function f(__DEV__) { return __DEV__; }
+            "#,
+            &defines,
+        );
+        assert!(js.contains("dev=true"));
+        assert!(js.contains(r#"isServer="object"==="undefined""#));
+        assert!(js.contains(r#"{"list":[1,-2]}"#));
+        assert!(!js.contains("MY_FLAG.nested.list"));
+        assert!(js.contains("other=MY_FLAG.other"));
+        assert!(js.contains("MY_FLAG.nested=null"));
+        assert!(js.contains("return __DEV__"));
+    }
+}
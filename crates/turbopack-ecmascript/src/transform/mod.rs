@@ -1,4 +1,6 @@
+pub(crate) mod defines;
 mod server_to_client_proxy;
+mod webpack_compat;
 
 use std::{path::Path, sync::Arc};
 
@@ -16,10 +18,19 @@ use swc_core::{
         visit::{FoldWith, VisitMutWith},
     },
 };
-use turbo_tasks::primitives::StringVc;
+use turbo_tasks::primitives::{JsonValueVc, StringVc};
+use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::environment::EnvironmentVc;
 
-use self::server_to_client_proxy::{create_proxy_module, is_client_module};
+use self::{
+    defines::compile_time_defines,
+    server_to_client_proxy::{create_proxy_module, is_client_module},
+    webpack_compat::webpack_runtime_shims,
+};
+pub use self::{
+    defines::{CompileTimeDefineValue, CompileTimeDefines, CompileTimeDefinesVc},
+    webpack_compat::require_ensure_callbacks,
+};
 mod next_ssg;
 
 #[turbo_tasks::value(serialization = "auto_for_input")]
@@ -28,6 +39,9 @@ pub enum EcmascriptInputTransform {
     ClientDirective(StringVc),
     CommonJs,
     Custom,
+    /// Replaces expressions with values known at compile time, e. g.
+    /// `__DEV__` with `true`.
+    Defines(CompileTimeDefinesVc),
     Emotion,
     /// This enables the Next SSG transform, which will eliminate
     /// `getStaticProps`/`getServerSideProps`/etc. exports from the output, as
@@ -39,10 +53,46 @@ pub enum EcmascriptInputTransform {
     React {
         #[serde(default)]
         refresh: bool,
+        #[serde(default)]
+        runtime: JsxRuntime,
+        /// The package the automatic runtime imports the JSX functions from,
+        /// e. g. `preact` or `@emotion/react`. Defaults to `react`.
+        #[serde(default)]
+        import_source: Option<StringVc>,
     },
     StyledComponents,
     StyledJsx,
+    /// Runs the SWC Wasm plugin at `plugin` with the JSON `config` inside the
+    /// SWC pass of the module. Requires the `swc_ecma_transform_plugin`
+    /// feature.
+    SwcPlugin {
+        plugin: FileSystemPathVc,
+        config: JsonValueVc,
+    },
     TypeScript,
+    /// Replaces the free `__webpack_require__` and `__non_webpack_require__`
+    /// variables left in the output of libraries built with webpack with
+    /// runtime shims providing the same API.
+    WebpackRuntimeShims,
+}
+
+/// How JSX is compiled.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(PartialOrd, Ord, Hash, Debug, Copy, Clone)]
+pub enum JsxRuntime {
+    /// JSX is compiled to calls of the functions of the `jsx-runtime` module
+    /// of the import source, which is imported by the module. The import is a
+    /// regular ESM reference, so the runtime is chunked like any other module.
+    Automatic,
+    /// JSX is compiled to `React.createElement` calls, which requires `React`
+    /// to be in scope.
+    Classic,
+}
+
+impl Default for JsxRuntime {
+    fn default() -> Self {
+        JsxRuntime::Automatic
+    }
 }
 
 #[turbo_tasks::value(transparent, serialization = "auto_for_input")]
@@ -82,12 +132,25 @@ impl EcmascriptInputTransform {
         }: &TransformContext<'_>,
     ) -> Result<()> {
         match *self {
-            EcmascriptInputTransform::React { refresh } => {
+            EcmascriptInputTransform::React {
+                refresh,
+                runtime,
+                import_source,
+            } => {
+                let runtime = match runtime {
+                    JsxRuntime::Automatic => swc_core::ecma::transforms::react::Runtime::Automatic,
+                    JsxRuntime::Classic => swc_core::ecma::transforms::react::Runtime::Classic,
+                };
+                let import_source = match import_source {
+                    Some(import_source) => Some(import_source.await?.clone_value()),
+                    None => None,
+                };
                 program.visit_mut_with(&mut react(
                     source_map.clone(),
                     Some(comments.clone()),
                     swc_core::ecma::transforms::react::Options {
-                        runtime: Some(swc_core::ecma::transforms::react::Runtime::Automatic),
+                        runtime: Some(runtime),
+                        import_source,
                         refresh: if refresh {
                             Some(swc_core::ecma::transforms::react::RefreshOptions {
                                 ..Default::default()
@@ -114,6 +177,10 @@ impl EcmascriptInputTransform {
                     Some(comments.clone()),
                 ));
             }
+            EcmascriptInputTransform::Defines(defines) => {
+                program
+                    .visit_mut_with(&mut compile_time_defines(&*defines.await?, unresolved_mark));
+            }
             EcmascriptInputTransform::Emotion => {
                 let p = std::mem::replace(program, Program::Module(Module::dummy()));
                 *program = p.fold_with(&mut swc_emotion::emotion(
@@ -133,6 +200,10 @@ impl EcmascriptInputTransform {
 
                 let module_program = unwrap_module_program(program);
 
+                // Helpers are external (see `parse`), so `inject_helpers` imports them from
+                // `@swc/helpers` instead of inlining them into every module, and generators
+                // import `regenerator-runtime`. The imports are analyzed like the imports of
+                // the source, so both are resolved and chunked as modules of their own.
                 *program = module_program.fold_with(&mut chain!(
                     preset_env::preset_env(
                         top_level_mark,
@@ -160,6 +231,17 @@ impl EcmascriptInputTransform {
                     FileName::Anon,
                 ));
             }
+            EcmascriptInputTransform::SwcPlugin { plugin, config } => {
+                apply_swc_plugin(
+                    program,
+                    plugin,
+                    config,
+                    source_map,
+                    unresolved_mark,
+                    file_name_str,
+                )
+                .await?;
+            }
             EcmascriptInputTransform::TypeScript => {
                 use swc_core::ecma::transforms::typescript::strip;
                 program.visit_mut_with(&mut strip(top_level_mark));
@@ -179,12 +261,85 @@ impl EcmascriptInputTransform {
 
                 *program = module_program.fold_with(&mut next_ssg(eliminated_packages));
             }
+            EcmascriptInputTransform::WebpackRuntimeShims => {
+                program.visit_mut_with(&mut webpack_runtime_shims(unresolved_mark));
+            }
             EcmascriptInputTransform::Custom => todo!(),
         }
         Ok(())
     }
 }
 
+#[cfg(feature = "swc_ecma_transform_plugin")]
+async fn apply_swc_plugin(
+    program: &mut Program,
+    plugin: FileSystemPathVc,
+    config: JsonValueVc,
+    source_map: &Arc<SourceMap>,
+    unresolved_mark: Mark,
+    file_name_str: &str,
+) -> Result<()> {
+    use anyhow::Context;
+    use once_cell::sync::Lazy;
+    use swc_core::{
+        common::plugin::{
+            metadata::TransformPluginMetadataContext,
+            serialized::{PluginSerializedBytes, VersionedSerializable},
+        },
+        plugin_runner::{cache::PluginModuleCache, create_plugin_transform_executor},
+    };
+    use turbo_tasks::ValueToString;
+
+    /// Compiled plugin modules, shared by all modules the plugins run on.
+    static PLUGIN_MODULE_CACHE: Lazy<PluginModuleCache> = Lazy::new(Default::default);
+
+    // Reading the binary makes the transform depend on it, the runner loads
+    // it from disk.
+    plugin.read().await?;
+    let plugin_name = plugin.to_string().await?;
+    let path = turbo_tasks_fs::to_sys_path(plugin)
+        .await?
+        .with_context(|| format!("SWC plugin {plugin_name} must be on a disk file system"))?;
+    let metadata = Arc::new(TransformPluginMetadataContext::new(
+        Some(file_name_str.to_string()),
+        "development".to_string(),
+        None,
+    ));
+    let mut executor = create_plugin_transform_executor(
+        &path,
+        &PLUGIN_MODULE_CACHE,
+        source_map,
+        &metadata,
+        Some(config.await?.clone_value()),
+    )
+    .with_context(|| format!("failed to load SWC plugin {plugin_name}"))?;
+    let serialized =
+        PluginSerializedBytes::try_serialize(&VersionedSerializable::new(program.clone()))?;
+    let transformed = executor
+        .transform(&serialized, unresolved_mark, false)
+        .with_context(|| format!("SWC plugin {plugin_name} failed on {file_name_str}"))?;
+    *program = transformed.deserialize::<Program>()?.into_inner();
+    Ok(())
+}
+
+#[cfg(not(feature = "swc_ecma_transform_plugin"))]
+async fn apply_swc_plugin(
+    _program: &mut Program,
+    plugin: FileSystemPathVc,
+    _config: JsonValueVc,
+    _source_map: &Arc<SourceMap>,
+    _unresolved_mark: Mark,
+    _file_name_str: &str,
+) -> Result<()> {
+    use turbo_tasks::ValueToString;
+
+    anyhow::bail!(
+        "SWC plugin {} can't be run, turbopack was built without the `swc_ecma_transform_plugin` \
+         feature",
+        plugin.to_string().await?
+    )
+}
+
 fn unwrap_module_program(program: &mut Program) -> Program {
     match program {
         Program::Module(module) => Program::Module(module.take()),
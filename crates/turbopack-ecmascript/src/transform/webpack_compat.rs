@@ -0,0 +1,127 @@
+use swc_core::{
+    common::{Mark, SyntaxContext},
+    ecma::{
+        ast::{
+            BindingIdent, CallExpr, Callee, Expr, ExprOrSpread, Id, Ident, MemberExpr, MemberProp,
+            Pat,
+        },
+        visit::{VisitMut, VisitMutWith},
+    },
+};
+
+fn is_unresolved_ident(expr: &Expr, name: &str, unresolved_mark: Mark) -> bool {
+    matches!(expr, Expr::Ident(ident) if &*ident.sym == name && ident.span.ctxt.outer() == unresolved_mark)
+}
+
+/// Removes the `require` parameter of callbacks passed to
+/// `require.ensure([...], function (require) { ... })`, so the `require`
+/// calls in the callback refer to the free `require` and are analysed like
+/// any other `require` call.
+pub fn require_ensure_callbacks(unresolved_mark: Mark) -> impl VisitMut {
+    RequireEnsureCallbacks { unresolved_mark }
+}
+
+struct RequireEnsureCallbacks {
+    unresolved_mark: Mark,
+}
+
+impl VisitMut for RequireEnsureCallbacks {
+    fn visit_mut_call_expr(&mut self, call: &mut CallExpr) {
+        call.visit_mut_children_with(self);
+
+        let is_require_ensure = match &call.callee {
+            Callee::Expr(box Expr::Member(MemberExpr {
+                obj,
+                prop: MemberProp::Ident(prop),
+                ..
+            })) => {
+                &*prop.sym == "ensure" && is_unresolved_ident(obj, "require", self.unresolved_mark)
+            }
+            _ => false,
+        };
+        if !is_require_ensure {
+            return;
+        }
+        let callback = match call.args.get_mut(1) {
+            Some(ExprOrSpread {
+                expr: box callback,
+                spread: None,
+            }) => callback,
+            _ => return,
+        };
+        let id = match callback {
+            Expr::Fn(f) => match f.function.params.first() {
+                Some(param) => match require_param(&param.pat) {
+                    Some(id) => {
+                        f.function.params.remove(0);
+                        id
+                    }
+                    None => return,
+                },
+                None => return,
+            },
+            Expr::Arrow(arrow) => match arrow.params.first().and_then(require_param) {
+                Some(id) => {
+                    arrow.params.remove(0);
+                    id
+                }
+                None => return,
+            },
+            _ => return,
+        };
+        callback.visit_mut_with(&mut Unresolve {
+            id,
+            ctxt: SyntaxContext::empty().apply_mark(self.unresolved_mark),
+        });
+    }
+}
+
+fn require_param(pat: &Pat) -> Option<Id> {
+    match pat {
+        Pat::Ident(BindingIdent { id, .. }) if &*id.sym == "require" => Some(id.to_id()),
+        _ => None,
+    }
+}
+
+/// Makes all references to the binding `id` free references.
+struct Unresolve {
+    id: Id,
+    ctxt: SyntaxContext,
+}
+
+impl VisitMut for Unresolve {
+    fn visit_mut_ident(&mut self, ident: &mut Ident) {
+        if ident.sym == self.id.0 && ident.span.ctxt == self.id.1 {
+            ident.span.ctxt = self.ctxt;
+        }
+    }
+}
+
+/// Replaces the free `__webpack_require__` and `__non_webpack_require__`
+/// variables, which libraries built with webpack sometimes leave in their
+/// output, with the turbopack runtime functions providing the same API.
+pub fn webpack_runtime_shims(unresolved_mark: Mark) -> impl VisitMut {
+    WebpackRuntimeShims { unresolved_mark }
+}
+
+struct WebpackRuntimeShims {
+    unresolved_mark: Mark,
+}
+
+impl VisitMut for WebpackRuntimeShims {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        if let Expr::Ident(ident) = expr {
+            if ident.span.ctxt.outer() == self.unresolved_mark {
+                match &*ident.sym {
+                    "__webpack_require__" => ident.sym = "__turbopack_webpack_require__".into(),
+                    "__non_webpack_require__" => {
+                        ident.sym = "__turbopack_external_require__".into()
+                    }
+                    _ => {}
+                }
+            }
+            return;
+        }
+        expr.visit_mut_children_with(self);
+    }
+}
@@ -6,7 +6,7 @@ use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
 use turbo_tasks_fs::{FileJsonContent, FileJsonContentVc, FileSystemPathVc};
 use turbopack_core::{
     asset::AssetVc,
-    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    issue::{codes, Issue, IssueSeverity, IssueSeverityVc, IssueVc},
     reference::{AssetReference, AssetReferenceVc},
     resolve::{
         handle_resolve_error,
@@ -148,42 +148,42 @@ pub async fn tsconfig_resolve_options(
         None
     };
 
+    // Like all compiler options, `paths` of a tsconfig replace the ones of the
+    // tsconfig it extends instead of being merged with them. The targets are
+    // relative to the effective `baseUrl`, or to the tsconfig defining them.
     let mut all_paths = HashMap::new();
-    for (content, source) in configs.iter().rev() {
-        if let FileJsonContent::Content(json) = &*content.await? {
-            if let JsonValue::Object(paths) = &json["compilerOptions"]["paths"] {
-                let mut context = source.path().parent();
-                if let Some(base_url) = json["compilerOptions"]["baseUrl"].as_str() {
-                    if let Some(new_context) = *context.try_join(base_url).await? {
-                        context = new_context;
-                    }
-                };
-                for (key, value) in paths.iter() {
-                    if let JsonValue::Array(vec) = value {
-                        let entries = vec
-                            .iter()
-                            .filter_map(|entry| entry.as_str().map(|s| s.to_string()))
-                            .collect();
-                        all_paths.insert(
-                            key.to_string(),
-                            ImportMapping::primary_alternatives(entries, Some(context)),
-                        );
-                    } else {
-                        TsConfigIssue {
-                            severity: IssueSeverity::Warning.cell(),
-                            path: source.path(),
-                            message: StringVc::cell(format!(
-                                "compilerOptions.paths[{key}] doesn't contains an array as \
-                                 expected\n{key}: {value:#}",
-                                key = serde_json::to_string(key)?,
-                                value = value
-                            )),
-                        }
-                        .cell()
-                        .as_issue()
-                        .emit()
-                    }
+    if let Some((paths, source)) = read_from_tsconfigs(&configs, |json, source| {
+        json["compilerOptions"]["paths"]
+            .as_object()
+            .map(|paths| (paths.clone(), source))
+    })
+    .await?
+    {
+        let context = base_url.unwrap_or_else(|| source.path().parent());
+        for (key, value) in paths.iter() {
+            if let JsonValue::Array(vec) = value {
+                let entries = vec
+                    .iter()
+                    .filter_map(|entry| entry.as_str().map(relative_to_base_url))
+                    .collect();
+                all_paths.insert(
+                    key.to_string(),
+                    ImportMapping::primary_alternatives(entries, Some(context)),
+                );
+            } else {
+                TsConfigIssue {
+                    severity: IssueSeverity::Warning.cell(),
+                    path: source.path(),
+                    message: StringVc::cell(format!(
+                        "compilerOptions.paths[{key}] doesn't contains an array as \
+                         expected\n{key}: {value:#}",
+                        key = serde_json::to_string(key)?,
+                        value = value
+                    )),
                 }
+                .cell()
+                .as_issue()
+                .emit()
             }
         }
     }
@@ -205,6 +205,16 @@ pub async fn tsconfig_resolve_options(
     .cell())
 }
 
+/// Targets of `compilerOptions.paths` are paths, not module requests, even
+/// when they don't start with `./`.
+fn relative_to_base_url(target: &str) -> String {
+    if target.starts_with("./") || target.starts_with("../") || target.starts_with('/') {
+        target.to_string()
+    } else {
+        format!("./{target}")
+    }
+}
+
 #[turbo_tasks::function]
 pub async fn apply_tsconfig_resolve_options(
     resolve_options: ResolveOptionsVc,
@@ -348,6 +358,11 @@ impl Issue for TsConfigIssue {
         StringVc::cell("typescript".to_string())
     }
 
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::config::TSCONFIG.to_string())
+    }
+
     #[turbo_tasks::function]
     fn context(&self) -> FileSystemPathVc {
         self.path
@@ -0,0 +1,227 @@
+//! Export-level tree shaking.
+//!
+//! [compute_used_exports] walks a module graph and collects which exports of
+//! each module are actually imported. The result can be passed to a chunking
+//! context, which makes code generation omit the unused exports, so they can
+//! be removed as dead code.
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::trace::TraceRawVcs;
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    chunk::used_exports::{ExportUsage, UsedExportsVc},
+    reference::{AssetReference, AssetReferenceVc},
+};
+
+use crate::{
+    chunk::{EcmascriptChunkPlaceablesVc, EcmascriptExports},
+    references::esm::{
+        base::{ReferencedAsset, ReferencedAssetVc},
+        EsmAssetReferenceVc, EsmExport,
+    },
+    EcmascriptModuleAssetVc,
+};
+
+/// How a module uses the exports of a module it imports via ESM.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub struct EsmImportUsage {
+    pub reference: EsmAssetReferenceVc,
+    pub usage: ExportUsage,
+    /// `export * from "..."`. Exports which are used on the importing module,
+    /// but not defined by it, are used on the referenced module too.
+    pub star_reexport: bool,
+}
+
+impl EsmImportUsage {
+    pub fn new(reference: EsmAssetReferenceVc) -> Self {
+        EsmImportUsage {
+            reference,
+            usage: ExportUsage::Named(BTreeSet::new()),
+            star_reexport: false,
+        }
+    }
+
+    pub fn add_export(&mut self, name: &str) {
+        self.usage.extend(&ExportUsage::Named(
+            [name.to_string()].into_iter().collect(),
+        ));
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct EsmImportUsages(Vec<EsmImportUsage>);
+
+/// Computes which exports of the modules reachable from `entries` are used.
+/// Entries and modules which are referenced by anything other than a static
+/// ESM import use all of their exports.
+#[turbo_tasks::function]
+pub async fn compute_used_exports(entries: EcmascriptChunkPlaceablesVc) -> Result<UsedExportsVc> {
+    let mut usages: IndexMap<FileSystemPathVc, ExportUsage> = IndexMap::new();
+    let mut local_exports: HashMap<FileSystemPathVc, BTreeSet<String>> = HashMap::new();
+    let mut star_reexports = Vec::new();
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+
+    for entry in entries.await?.iter() {
+        let entry = entry.as_asset().resolve().await?;
+        usages.insert(entry.path().resolve().await?, ExportUsage::All);
+        queue.push_back(entry);
+    }
+
+    while let Some(asset) = queue.pop_front() {
+        if !visited.insert(asset) {
+            continue;
+        }
+        let path = asset.path().resolve().await?;
+
+        let module = EcmascriptModuleAssetVc::resolve_from(asset).await?;
+        // Modules a CommonJS module re-exports as a whole, e. g. with
+        // `module.exports = require("./impl")`
+        let mut cjs_reexports = HashSet::new();
+        let import_usages = if let Some(module) = module {
+            let analysis = module.analyze().await?;
+            match &*analysis.exports.await? {
+                EcmascriptExports::EsmExports(exports) => {
+                    local_exports.insert(path, exports.await?.exports.keys().cloned().collect());
+                }
+                EcmascriptExports::CommonJs(exports) => {
+                    let exports = exports.await?;
+                    local_exports.insert(path, exports.names.clone());
+                    for reexport in exports.reexports.iter() {
+                        for target in reexport.resolve_reference().primary_assets().await?.iter() {
+                            cjs_reexports.insert(target.path().resolve().await?);
+                        }
+                    }
+                }
+                EcmascriptExports::Value | EcmascriptExports::None => {}
+            }
+            Some(analysis.import_usage.await?)
+        } else {
+            None
+        };
+        let references = asset.references().await?;
+        // A re-exported module which is also required elsewhere in the module
+        // might be used in any way
+        if !cjs_reexports.is_empty() {
+            let mut required = HashMap::<_, usize>::new();
+            for reference in references.iter() {
+                for target in reference.resolve_reference().primary_assets().await?.iter() {
+                    *required.entry(target.path().resolve().await?).or_default() += 1;
+                }
+            }
+            cjs_reexports.retain(|target| required.get(target) == Some(&1));
+        }
+
+        for reference in references.iter() {
+            let reference = reference.resolve().await?;
+            // The references of the analysis are compared resolved, as they
+            // are created by calls which aren't resolved yet
+            let mut import_usage = None;
+            for usage in import_usages.iter().flat_map(|usages| usages.iter()) {
+                if AssetReferenceVc::from(usage.reference).resolve().await? == reference {
+                    import_usage = Some(usage);
+                    break;
+                }
+            }
+            // References to a single export only use that export, under the name
+            // it has in the module re-exports have been followed to
+            let mut export_usage = None;
+            if let Some(esm_reference) = EsmAssetReferenceVc::resolve_from(reference).await? {
+                let esm_reference_value = esm_reference.await?;
+                if esm_reference_value.export_name.is_some() {
+                    export_usage = Some(match &*esm_reference.get_referenced_export().await? {
+                        Some(name) => {
+                            let mut name = name;
+                            // Reading a member of an object of exports only uses the export
+                            // of the same name, e. g. a class of a CSS module or a key of a
+                            // JSON module
+                            if let Some(member) = &esm_reference_value.member {
+                                let asset = esm_reference.get_referenced_asset();
+                                if is_object_of_exports(asset, name).await? {
+                                    name = member;
+                                }
+                            }
+                            ExportUsage::Named(BTreeSet::from([name.clone()]))
+                        }
+                        None => ExportUsage::All,
+                    });
+                }
+            }
+            for target in reference.resolve_reference().primary_assets().await?.iter() {
+                let target = target.resolve().await?;
+                let target_path = target.path().resolve().await?;
+                let usage = usages
+                    .entry(target_path)
+                    .or_insert_with(|| ExportUsage::Named(BTreeSet::new()));
+                match (import_usage, &export_usage) {
+                    (_, Some(export_usage)) => {
+                        usage.extend(export_usage);
+                    }
+                    (Some(import_usage), None) => {
+                        usage.extend(&import_usage.usage);
+                        if import_usage.star_reexport {
+                            star_reexports.push((path, target_path, false));
+                        }
+                    }
+                    (None, None) if cjs_reexports.contains(&target_path) => {
+                        star_reexports.push((path, target_path, true));
+                    }
+                    (None, None) => {
+                        usage.extend(&ExportUsage::All);
+                    }
+                }
+                queue.push_back(target);
+            }
+        }
+    }
+
+    // Forward usages through `export * from "..."` and CommonJS re-exports
+    // until nothing changes
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (from, to, commonjs) in star_reexports.iter() {
+            let forwarded = match &usages[from] {
+                ExportUsage::All => ExportUsage::All,
+                // The default export of a CommonJS module is the whole exports
+                // object, which is the re-exported module
+                ExportUsage::Named(names) if *commonjs && names.contains("default") => {
+                    ExportUsage::All
+                }
+                ExportUsage::Named(names) => {
+                    let local = local_exports.get(from);
+                    ExportUsage::Named(
+                        names
+                            .iter()
+                            .filter(|name| local.map_or(true, |local| !local.contains(*name)))
+                            .cloned()
+                            .collect(),
+                    )
+                }
+            };
+            if let Some(usage) = usages.get_mut(to) {
+                changed |= usage.extend(&forwarded);
+            }
+        }
+    }
+
+    Ok(UsedExportsVc::cell(usages))
+}
+
+/// Whether the export `name` of `asset` is an object of its other exports, a
+/// [EsmExport::ConstantObject] or a [EsmExport::PropertiesObject].
+async fn is_object_of_exports(asset: ReferencedAssetVc, name: &str) -> Result<bool> {
+    if let ReferencedAsset::Some(asset) = &*asset.await? {
+        if let EcmascriptExports::EsmExports(exports) = &*asset.get_exports().await? {
+            return Ok(matches!(
+                exports.await?.exports.get(name),
+                Some(EsmExport::ConstantObject(_) | EsmExport::PropertiesObject)
+            ));
+        }
+    }
+    Ok(false)
+}
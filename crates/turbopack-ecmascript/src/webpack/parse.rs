@@ -223,7 +223,7 @@ pub async fn webpack_runtime(
                 }
             }
         }
-        ParseResult::Unparseable | ParseResult::NotFound => {}
+        ParseResult::Unparseable | ParseResult::Panicked | ParseResult::NotFound => {}
     }
     Ok(WebpackRuntime::None.into())
 }
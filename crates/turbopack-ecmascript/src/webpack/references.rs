@@ -57,7 +57,9 @@ pub async fn module_references(
             });
             Ok(AssetReferencesVc::cell(references))
         }
-        ParseResult::Unparseable | ParseResult::NotFound => Ok(AssetReferencesVc::cell(Vec::new())),
+        ParseResult::Unparseable | ParseResult::Panicked | ParseResult::NotFound => {
+            Ok(AssetReferencesVc::cell(Vec::new()))
+        }
     }
 }
 
@@ -0,0 +1,9 @@
+const { API_URL, NODE_ENV: mode } = process.env;
+const { env } = process;
+const { DEBUG } = env;
+
+const direct = process.env.API_URL;
+const computed = process.env["NODE_ENV"];
+const dynamic = process.env[mode];
+
+const url = `${API_URL}/users`;
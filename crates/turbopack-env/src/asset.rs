@@ -5,7 +5,7 @@ use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
 use turbo_tasks_env::ProcessEnvVc;
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
-    asset::{Asset, AssetContentVc, AssetVc},
+    asset::{Asset, AssetContentVc, AssetOptionVc, AssetVc},
     chunk::{ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContextVc},
     reference::AssetReferencesVc,
 };
@@ -104,6 +104,11 @@ impl ChunkItem for ProcessEnvChunkItem {
     fn references(&self) -> AssetReferencesVc {
         AssetReferencesVc::empty()
     }
+
+    #[turbo_tasks::function]
+    fn asset(&self) -> AssetOptionVc {
+        AssetOptionVc::cell(Some(self.inner.into()))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use turbo_tasks::primitives::StringVc;
 use turbo_tasks_fs::FileSystemPathVc;
-use turbopack_core::issue::{Issue, IssueVc};
+use turbopack_core::issue::{codes, Issue, IssueVc};
 
 #[turbo_tasks::value(shared)]
 pub struct JsonIssue {
@@ -21,6 +21,11 @@ impl Issue for JsonIssue {
         StringVc::cell("parse".to_string())
     }
 
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::parse::JSON.to_string())
+    }
+
     #[turbo_tasks::function]
     fn context(&self) -> FileSystemPathVc {
         self.path
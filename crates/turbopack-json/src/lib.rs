@@ -3,24 +3,38 @@
 //! JSON assets are parsed to ensure they contain valid JSON.
 //!
 //! When imported from ES modules, they produce a module that exports the
-//! JSON value as an object.
+//! JSON value as default export. The top-level keys of objects are named
+//! exports. When the importers only use some keys, by importing them by name,
+//! reading them from the default export with static member accesses or by
+//! destructuring it, the other keys are omitted.
 
 #![feature(min_specialization)]
 
 pub mod issue;
 
+use std::{collections::BTreeMap, fmt::Write};
+
 use anyhow::Result;
 use issue::{JsonIssue, JsonIssueVc};
+use serde_json::{Map, Value as JsonValue};
 use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
-use turbo_tasks_fs::FileSystemPathVc;
+use turbo_tasks_fs::{FileJsonContent, FileSystemPathVc};
 use turbopack_core::{
-    asset::{Asset, AssetContentVc, AssetVc},
-    chunk::{ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContextVc},
+    asset::{Asset, AssetContentVc, AssetOptionVc, AssetVc},
+    chunk::{
+        used_exports::ExportUsage, ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset,
+        ChunkableAssetVc, ChunkingContextVc,
+    },
     reference::AssetReferencesVc,
 };
-use turbopack_ecmascript::chunk::{
-    EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkItemContentVc,
-    EcmascriptChunkItemVc, EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc, EcmascriptChunkVc,
+use turbopack_ecmascript::{
+    chunk::{
+        EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkItemContentVc,
+        EcmascriptChunkItemVc, EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc,
+        EcmascriptChunkVc, EcmascriptExports, EcmascriptExportsVc,
+    },
+    utils::stringify_str,
+    EsmExport, EsmExports,
 };
 
 #[turbo_tasks::value]
@@ -75,6 +89,32 @@ impl EcmascriptChunkPlaceable for JsonModuleAsset {
         })
         .into()
     }
+
+    /// The value is the default export. The top-level keys of an object are
+    /// named exports too.
+    #[turbo_tasks::function]
+    async fn get_exports(self_vc: JsonModuleAssetVc) -> Result<EcmascriptExportsVc> {
+        let json = self_vc.path().read_json().await?;
+        let object = match &*json {
+            FileJsonContent::Content(JsonValue::Object(object)) => object,
+            _ => return Ok(EcmascriptExports::Value.cell()),
+        };
+        let mut exports = object
+            .keys()
+            .filter(|key| *key != "default")
+            .map(|key| (key.clone(), EsmExport::ObjectProperty))
+            .collect::<BTreeMap<_, _>>();
+        exports.insert("default".to_string(), EsmExport::PropertiesObject);
+        Ok(EcmascriptExports::EsmExports(
+            EsmExports {
+                path: self_vc.path(),
+                exports,
+                star_exports: Vec::new(),
+            }
+            .cell(),
+        )
+        .cell())
+    }
 }
 
 #[turbo_tasks::value]
@@ -100,6 +140,11 @@ impl ChunkItem for JsonChunkItem {
     fn references(&self) -> AssetReferencesVc {
         self.module.references()
     }
+
+    #[turbo_tasks::function]
+    fn asset(&self) -> AssetOptionVc {
+        AssetOptionVc::cell(Some(self.module.into()))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -115,8 +160,18 @@ impl EcmascriptChunkItem for JsonChunkItem {
         // JSON is valid.
         let inner_code = match self.module.path().read_json().to_string().await {
             Ok(content) => {
-                let js_str_content = serde_json::to_string(content.as_str())?;
-                format!("__turbopack_export_value__(JSON.parse({js_str_content}));",)
+                let usage = self.context.export_usage(self.module.path()).await?;
+                let properties = match &*self.module.path().read_json().await? {
+                    FileJsonContent::Content(value) => used_properties(value, &usage),
+                    _ => None,
+                };
+                match properties {
+                    Some(properties) => esm_code(&properties)?,
+                    None => {
+                        let js_str_content = serde_json::to_string(content.as_str())?;
+                        format!("__turbopack_export_value__(JSON.parse({js_str_content}));",)
+                    }
+                }
             }
             Err(error) => {
                 let error_message = format!("{:?}", error.to_string());
@@ -141,6 +196,39 @@ impl EcmascriptChunkItem for JsonChunkItem {
     }
 }
 
+/// Returns the properties of `value` which are used according to `usage`,
+/// when it's an object and its importers only use some of its keys.
+fn used_properties(value: &JsonValue, usage: &ExportUsage) -> Option<Map<String, JsonValue>> {
+    match (value, usage) {
+        (JsonValue::Object(object), ExportUsage::Named(used)) if !used.contains("default") => Some(
+            object
+                .iter()
+                .filter(|(key, _)| used.contains(*key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Generates an ES module exporting `properties` as named exports. The default
+/// export is an object of them too, as they might be read from it.
+fn esm_code(properties: &Map<String, JsonValue>) -> Result<String> {
+    let json = serde_json::to_string(properties)?;
+    let mut code = format!("const json = JSON.parse({});\n", stringify_str(&json));
+    code += "__turbopack_esm__({\n";
+    for key in properties.keys() {
+        writeln!(
+            code,
+            "  {}: () => json[{}],",
+            stringify_str(key),
+            stringify_str(key)
+        )?;
+    }
+    code += "  default: () => json,\n});\n";
+    Ok(code)
+}
+
 pub fn register() {
     turbo_tasks::register();
     turbo_tasks_fs::register();
@@ -148,3 +236,28 @@ pub fn register() {
     turbopack_ecmascript::register();
     include!(concat!(env!("OUT_DIR"), "/register.rs"));
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use serde_json::json;
+    use turbopack_core::chunk::used_exports::ExportUsage;
+
+    use super::used_properties;
+
+    #[test]
+    fn used_properties_of_objects() {
+        let value = json!({ "name": "app", "version": "1.0.0", "private": true });
+        let usage = ExportUsage::Named(BTreeSet::from(["name".to_string()]));
+        assert_eq!(
+            used_properties(&value, &usage)
+                .map(|properties| properties.into_iter().collect::<Vec<_>>()),
+            Some(vec![("name".to_string(), json!("app"))])
+        );
+        let usage = ExportUsage::Named(BTreeSet::from(["default".to_string()]));
+        assert_eq!(used_properties(&value, &usage), None);
+        assert_eq!(used_properties(&value, &ExportUsage::All), None);
+        assert_eq!(used_properties(&json!([1, 2]), &usage), None);
+    }
+}
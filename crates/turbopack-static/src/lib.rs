@@ -7,14 +7,21 @@
 //!
 //! When referred to from CSS assets, the reference is replaced with the asset's
 //! path.
+//!
+//! Depending on the [StaticAssetType], assets are inlined as `data:` URL into
+//! the importing modules and stylesheets instead of being copied, or their
+//! content is exported as string.
 
 #![feature(min_specialization)]
 
 use anyhow::{anyhow, Result};
-use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks::{
+    primitives::{OptionStringVc, StringVc},
+    Value, ValueToString, ValueToStringVc,
+};
 use turbo_tasks_fs::{FileContent, FileSystemPathVc};
 use turbopack_core::{
-    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    asset::{Asset, AssetContent, AssetContentVc, AssetOptionVc, AssetVc},
     chunk::{ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContextVc},
     context::AssetContextVc,
     reference::{AssetReferencesVc, SingleAssetReferenceVc},
@@ -23,24 +30,76 @@ use turbopack_css::embed::{CssEmbed, CssEmbedVc, CssEmbeddable, CssEmbeddableVc}
 use turbopack_ecmascript::{
     chunk::{
         EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkItemContentVc,
-        EcmascriptChunkItemVc, EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc,
-        EcmascriptChunkVc, EcmascriptExports, EcmascriptExportsVc,
+        EcmascriptChunkItemOptions, EcmascriptChunkItemVc, EcmascriptChunkPlaceable,
+        EcmascriptChunkPlaceableVc, EcmascriptChunkVc, EcmascriptExports, EcmascriptExportsVc,
     },
     utils::stringify_str,
 };
 
+/// How a static asset is exposed to the modules importing it.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(PartialOrd, Ord, Hash, Debug, Copy, Clone)]
+pub enum StaticAssetType {
+    /// Like [StaticAssetType::Inline] for assets with at most `inline_limit`
+    /// bytes, like [StaticAssetType::Url] otherwise. An `inline_limit` of `0`
+    /// never inlines.
+    Auto { inline_limit: usize },
+    /// The asset is emitted with a content hashed name. Its URL is exported.
+    Url,
+    /// The asset is inlined as `data:` URL, which is exported.
+    Inline,
+    /// The raw content of the asset is exported as string. Stylesheets
+    /// reference the asset like [StaticAssetType::Url].
+    Source,
+}
+
 #[turbo_tasks::value]
 #[derive(Clone)]
 pub struct StaticModuleAsset {
     pub source: AssetVc,
     pub context: AssetContextVc,
+    pub ty: StaticAssetType,
 }
 
 #[turbo_tasks::value_impl]
 impl StaticModuleAssetVc {
     #[turbo_tasks::function]
-    pub fn new(source: AssetVc, context: AssetContextVc) -> Self {
-        Self::cell(StaticModuleAsset { source, context })
+    pub fn new(source: AssetVc, context: AssetContextVc, ty: Value<StaticAssetType>) -> Self {
+        Self::cell(StaticModuleAsset {
+            source,
+            context,
+            ty: ty.into_value(),
+        })
+    }
+
+    /// The `data:` URL of the asset when it's inlined.
+    #[turbo_tasks::function]
+    async fn inline_url(self) -> Result<OptionStringVc> {
+        let this = self.await?;
+        let inline_limit = match this.ty {
+            StaticAssetType::Auto { inline_limit } if inline_limit > 0 => inline_limit,
+            StaticAssetType::Inline => usize::MAX,
+            _ => return Ok(OptionStringVc::cell(None)),
+        };
+        let file = if let AssetContent::File(file) = &*this.source.content().await? {
+            file.await?
+        } else {
+            return Ok(OptionStringVc::cell(None));
+        };
+        let file = match &*file {
+            FileContent::Content(file) if file.content().len() <= inline_limit => file,
+            _ => return Ok(OptionStringVc::cell(None)),
+        };
+        let mime = match file.content_type() {
+            Some(mime) => mime.to_string(),
+            None => mime_guess::from_path(&this.source.path().await?.path)
+                .first_or_octet_stream()
+                .to_string(),
+        };
+        Ok(OptionStringVc::cell(Some(format!(
+            "data:{mime};base64,{}",
+            base64::encode(file.content())
+        ))))
     }
 
     #[turbo_tasks::function]
@@ -107,6 +166,7 @@ impl CssEmbeddable for StaticModuleAsset {
     #[turbo_tasks::function]
     fn as_css_embed(self_vc: StaticModuleAssetVc, context: ChunkingContextVc) -> CssEmbedVc {
         StaticCssEmbedVc::cell(StaticCssEmbed {
+            module: self_vc,
             static_asset: self_vc.static_asset(context),
         })
         .into()
@@ -175,12 +235,22 @@ impl ValueToString for ModuleChunkItem {
 impl ChunkItem for ModuleChunkItem {
     #[turbo_tasks::function]
     async fn references(&self) -> Result<AssetReferencesVc> {
+        if matches!(self.module.await?.ty, StaticAssetType::Source)
+            || self.module.inline_url().await?.is_some()
+        {
+            return Ok(AssetReferencesVc::empty());
+        }
         Ok(AssetReferencesVc::cell(vec![SingleAssetReferenceVc::new(
             self.static_asset.into(),
             StringVc::cell(format!("static(url) {}", self.static_asset.path().await?)),
         )
         .into()]))
     }
+
+    #[turbo_tasks::function]
+    fn asset(&self) -> AssetOptionVc {
+        AssetOptionVc::cell(Some(self.module.into()))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -192,11 +262,47 @@ impl EcmascriptChunkItem for ModuleChunkItem {
 
     #[turbo_tasks::function]
     async fn content(&self) -> Result<EcmascriptChunkItemContentVc> {
+        let module = self.module.await?;
+        let mut options = EcmascriptChunkItemOptions::default();
+        let value = if let StaticAssetType::Source = module.ty {
+            match &*module.source.content().await? {
+                AssetContent::File(file) => match &*file.await? {
+                    FileContent::Content(file) => {
+                        stringify_str(&String::from_utf8_lossy(file.content()))
+                    }
+                    FileContent::NotFound => {
+                        return Err(anyhow!("StaticModuleAsset::content: not found"))
+                    }
+                },
+                _ => {
+                    return Err(anyhow!(
+                        "StaticModuleAsset::content: unsupported file content"
+                    ))
+                }
+            }
+        } else if let Some(url) = &*self.module.inline_url().await? {
+            stringify_str(url)
+        } else {
+            let path = self.static_asset.path().await?;
+            let output_root = self.context.output_root().await?;
+            match (
+                &*self.context.public_path().await?,
+                output_root.get_path_to(&path),
+            ) {
+                (Some(public_path), Some(relative)) => match public_path.fixed_prefix() {
+                    Some(prefix) => stringify_str(&format!("{prefix}{relative}")),
+                    None => {
+                        // The prefix is only known at runtime
+                        options.public_path = true;
+                        format!("__turbopack_public_path__ + {}", stringify_str(relative))
+                    }
+                },
+                _ => stringify_str(&format!("/{}", &*path)),
+            }
+        };
         Ok(EcmascriptChunkItemContent {
-            inner_code: format!(
-                "__turbopack_export_value__({path});",
-                path = stringify_str(&format!("/{}", &*self.static_asset.path().await?))
-            ),
+            inner_code: format!("__turbopack_export_value__({value});"),
+            options,
             ..Default::default()
         }
         .into())
@@ -205,6 +311,7 @@ impl EcmascriptChunkItem for ModuleChunkItem {
 
 #[turbo_tasks::value]
 struct StaticCssEmbed {
+    module: StaticModuleAssetVc,
     static_asset: StaticAssetVc,
 }
 
@@ -212,6 +319,9 @@ struct StaticCssEmbed {
 impl CssEmbed for StaticCssEmbed {
     #[turbo_tasks::function]
     async fn references(&self) -> Result<AssetReferencesVc> {
+        if self.module.inline_url().await?.is_some() {
+            return Ok(AssetReferencesVc::empty());
+        }
         Ok(AssetReferencesVc::cell(vec![SingleAssetReferenceVc::new(
             self.static_asset.into(),
             StringVc::cell(format!("static(url) {}", self.static_asset.path().await?)),
@@ -223,6 +333,11 @@ impl CssEmbed for StaticCssEmbed {
     fn embeddable_asset(&self) -> AssetVc {
         self.static_asset.as_asset()
     }
+
+    #[turbo_tasks::function]
+    fn inline_url(&self) -> OptionStringVc {
+        self.module.inline_url()
+    }
 }
 
 pub fn register() {
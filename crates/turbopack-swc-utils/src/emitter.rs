@@ -8,7 +8,7 @@ use swc_core::common::{
 use turbo_tasks::primitives::StringVc;
 use turbopack_core::{
     asset::AssetVc,
-    issue::{analyze::AnalyzeIssue, IssueSeverity, IssueSourceVc},
+    issue::{analyze::AnalyzeIssue, codes, IssueSeverity, IssueSourceVc},
 };
 
 pub struct IssueEmitter {
@@ -26,10 +26,10 @@ impl Emitter for IssueEmitter {
             .map(|(s, _)| s.as_ref())
             .collect::<Vec<_>>()
             .join("");
-        let code = db.code.as_ref().map(|d| match d {
-            DiagnosticId::Error(s) => format!("error {s}"),
-            DiagnosticId::Lint(s) => format!("lint {s}"),
-        });
+        let code = match db.code.as_ref() {
+            Some(DiagnosticId::Error(s) | DiagnosticId::Lint(s)) => s.clone(),
+            None => codes::parse::PARSE.to_string(),
+        };
 
         let title;
         if let Some(t) = self.title.as_ref() {
@@ -65,7 +65,7 @@ impl Emitter for IssueEmitter {
             path: self.source.path(),
             title: StringVc::cell(title),
             message: StringVc::cell(message),
-            code,
+            code: Some(code),
             source,
         }
         .cell();
@@ -0,0 +1,582 @@
+#![cfg(test)]
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use anyhow::{bail, Result};
+use turbo_tasks::{NothingVc, TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, FileContent, FileSystem, FileSystemPathVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    ecmascript::EcmascriptModuleAssetVc, module_options::ModuleOptionsContext, register,
+    resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetVc},
+    chunk::{
+        dev::{DevChunkingContextBuilder, DevChunkingContextVc},
+        vendor::VendorChunkGroupsVc,
+        ChunkGroupVc, ChunkableAssetVc, ChunkingContextVc, CommonChunkOptions, ModuleEvaluation,
+    },
+    context::AssetContextVc,
+    environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    issue::{IssueSeverity, IssueVc},
+    reference::{all_assets, all_referenced_assets},
+    source_asset::SourceAssetVc,
+};
+
+/// Returns the module of `tests/<fixture>/input/index.js`, the chunking
+/// context set up by `configure` and the directory chunks are placed in.
+async fn entry_module(
+    fixture: &str,
+    configure: impl FnOnce(DevChunkingContextBuilder) -> DevChunkingContextBuilder,
+) -> Result<(AssetVc, ChunkingContextVc, FileSystemPathVc)> {
+    let workspace_root = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../..")
+        .canonicalize()?;
+    let fs = DiskFileSystemVc::new(
+        "workspace".to_string(),
+        workspace_root.to_string_lossy().to_string(),
+    );
+    let root = fs.root();
+    let path: FileSystemPathVc = root.join(&format!("crates/turbopack-tests/tests/{fixture}"));
+
+    let env = EnvironmentVc::new(
+        Value::new(ExecutionEnvironment::Browser(
+            BrowserEnvironment {
+                dom: true,
+                web_worker: false,
+                service_worker: false,
+                browserslist_query: "Chrome 102".to_string(),
+            }
+            .into(),
+        )),
+        Value::new(EnvironmentIntention::Client),
+    );
+    let context: AssetContextVc = ModuleAssetContextVc::new(
+        TransitionsByNameVc::cell(HashMap::new()),
+        env,
+        ModuleOptionsContext::default().cell(),
+        ResolveOptionsContext {
+            enable_node_modules: true,
+            ..Default::default()
+        }
+        .cell(),
+    )
+    .into();
+    let chunk_root_path = path.join("output");
+    let chunking_context = configure(DevChunkingContextVc::builder(
+        root,
+        path,
+        chunk_root_path,
+        path.join("static"),
+    ))
+    .build();
+
+    let module = context.process(SourceAssetVc::new(path.join("input/index.js")).into());
+    Ok((module, chunking_context, chunk_root_path))
+}
+
+/// Returns the chunk group of `tests/<fixture>/input/index.js` and the
+/// directory its chunks are placed in. `configure` sets up the chunking
+/// context.
+async fn entry_chunk_group(
+    fixture: &str,
+    configure: impl FnOnce(DevChunkingContextBuilder) -> DevChunkingContextBuilder,
+) -> Result<(ChunkGroupVc, FileSystemPathVc)> {
+    let (module, chunking_context, chunk_root_path) = entry_module(fixture, configure).await?;
+    let chunkable = match ChunkableAssetVc::resolve_from(module).await? {
+        Some(chunkable) => chunkable,
+        None => bail!("the entry of {fixture} is not chunkable"),
+    };
+    Ok((
+        ChunkGroupVc::from_asset(chunkable, chunking_context),
+        chunk_root_path,
+    ))
+}
+
+/// Returns the code of the chunk which evaluates
+/// `tests/<fixture>/input/index.js`.
+async fn evaluated_entry_script(
+    fixture: &str,
+    configure: impl FnOnce(DevChunkingContextBuilder) -> DevChunkingContextBuilder,
+) -> Result<String> {
+    let (module, chunking_context, _) = entry_module(fixture, configure).await?;
+    let ecmascript = match EcmascriptModuleAssetVc::resolve_from(module).await? {
+        Some(ecmascript) => ecmascript,
+        None => bail!("the entry of {fixture} is not an ecmascript module"),
+    };
+    let chunk = ecmascript.as_evaluated_chunk(chunking_context, None);
+    if let AssetContent::File(file) = &*chunk.as_asset().content().await? {
+        if let FileContent::Content(file) = &*file.await? {
+            return Ok(String::from_utf8_lossy(file.content()).to_string());
+        }
+    }
+    bail!("the evaluated chunk of {fixture} has no content")
+}
+
+/// Returns the paths of the chunks in the chunk group of
+/// `tests/<fixture>/input/index.js`, relative to the output directory.
+async fn chunk_group_paths(fixture: &str) -> Result<Vec<String>> {
+    let (chunk_group, chunk_root_path) = entry_chunk_group(fixture, |builder| builder).await?;
+    let chunk_root_path = chunk_root_path.await?;
+    let mut paths = Vec::new();
+    for chunk in chunk_group.chunks().await?.iter() {
+        let path = chunk.path().await?;
+        paths.push(
+            chunk_root_path
+                .get_path_to(&path)
+                .unwrap_or_default()
+                .to_string(),
+        );
+    }
+    Ok(paths)
+}
+
+/// Returns the code of all JavaScript chunks emitted for
+/// `tests/<fixture>/input/index.js`, including the chunks which are loaded
+/// asynchronously, keyed by their path.
+async fn emitted_scripts(
+    fixture: &str,
+    configure: impl FnOnce(DevChunkingContextBuilder) -> DevChunkingContextBuilder,
+) -> Result<BTreeMap<String, String>> {
+    let (chunk_group, _) = entry_chunk_group(fixture, configure).await?;
+    let mut assets = Vec::new();
+    for chunk in chunk_group.chunks().await?.iter() {
+        assets.push(chunk.as_asset());
+        assets.extend(all_assets(chunk.as_asset()).await?.iter().copied());
+    }
+    let mut scripts = BTreeMap::new();
+    for asset in assets {
+        let path = asset.path().await?.path.clone();
+        if !path.ends_with(".js") || scripts.contains_key(&path) {
+            continue;
+        }
+        if let AssetContent::File(file) = &*asset.content().await? {
+            if let FileContent::Content(file) = &*file.await? {
+                scripts.insert(path, String::from_utf8_lossy(file.content()).to_string());
+            }
+        }
+    }
+    Ok(scripts)
+}
+
+/// The paths of the scripts containing the module factory of the module at
+/// `path`, relative to the fixture.
+fn scripts_with_module(
+    scripts: &BTreeMap<String, String>,
+    fixture: &str,
+    path: &str,
+) -> Vec<String> {
+    let factory =
+        format!("\"[workspace]/crates/turbopack-tests/tests/{fixture}/{path} (ecmascript)\": ");
+    scripts
+        .iter()
+        .filter(|(_, code)| code.contains(&factory))
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+/// Computes the content and the referenced assets of `asset`, like the dev
+/// server does to serve it, and returns the referenced assets and the titles
+/// of the issues reported meanwhile.
+async fn serve(asset: AssetVc) -> Result<(Vec<AssetVc>, Vec<String>)> {
+    let content = asset.content();
+    content.await?;
+    let referenced = all_referenced_assets(asset);
+    let mut issues = Vec::new();
+    for issue in IssueVc::peek_issues_with_path(content)
+        .await?
+        .strongly_consistent()
+        .await?
+        .get_plain_issues()
+        .await?
+        .iter()
+        .chain(
+            IssueVc::peek_issues_with_path(referenced)
+                .await?
+                .strongly_consistent()
+                .await?
+                .get_plain_issues()
+                .await?
+                .iter(),
+        )
+    {
+        issues.push(issue.title.clone());
+    }
+    Ok((referenced.await?.iter().copied().collect(), issues))
+}
+
+#[tokio::test]
+async fn css_chunks_in_import_order() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let paths = chunk_group_paths("chunk_group").await?;
+        let css = paths
+            .iter()
+            .filter(|path| path.ends_with(".css"))
+            .collect::<Vec<_>>();
+        // The stylesheets must be applied in the order they are imported in.
+        assert_eq!(css.len(), 3, "unexpected CSS chunks {css:?}");
+        assert!(css[0].ends_with("_first.css"), "{css:?}");
+        assert!(css[1].ends_with("_dep_style.css"), "{css:?}");
+        assert!(css[2].ends_with("_last.css"), "{css:?}");
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
+
+#[tokio::test]
+async fn extracts_common_chunks_once() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let scripts = emitted_scripts("common_chunks", |builder| {
+            builder.common_chunks(CommonChunkOptions {
+                min_size: 0,
+                ..Default::default()
+            })
+        })
+        .await?;
+        let paths = scripts.keys().collect::<Vec<_>>();
+        // The modules loaded by both async chunks are emitted in one common
+        // chunk, which isn't one of the async chunks
+        let shared = scripts_with_module(&scripts, "common_chunks", "input/shared.js");
+        assert_eq!(shared.len(), 1, "{paths:?}");
+        let a = scripts_with_module(&scripts, "common_chunks", "input/a.js");
+        let b = scripts_with_module(&scripts, "common_chunks", "input/b.js");
+        assert_eq!(a.len(), 1, "{paths:?}");
+        assert_eq!(b.len(), 1, "{paths:?}");
+        assert!(shared[0] != a[0] && shared[0] != b[0], "{paths:?}");
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
+
+#[tokio::test]
+async fn splits_chunks_above_max_size() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let scripts = emitted_scripts("chunk_sizes", |builder| builder.max_chunk_size(100)).await?;
+        let paths = scripts.keys().collect::<Vec<_>>();
+        // Every module exceeds the max size, so each is placed in a chunk of
+        // its own
+        let mut chunks = Vec::new();
+        for module in ["index", "a", "b", "c"] {
+            let module_chunks =
+                scripts_with_module(&scripts, "chunk_sizes", &format!("input/{module}.js"));
+            assert_eq!(module_chunks.len(), 1, "{module}: {paths:?}");
+            chunks.extend(module_chunks);
+        }
+        chunks.sort();
+        chunks.dedup();
+        assert_eq!(chunks.len(), 4, "{paths:?}");
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
+
+#[tokio::test]
+async fn merges_chunks_below_min_size() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let scripts = emitted_scripts("chunk_sizes", |builder| {
+            builder.max_chunk_size(100).min_chunk_size(100_000)
+        })
+        .await?;
+        let paths = scripts.keys().collect::<Vec<_>>();
+        // The chunks split for the max size are all smaller than the min size,
+        // so they are merged again
+        let index = scripts_with_module(&scripts, "chunk_sizes", "input/index.js");
+        assert_eq!(index.len(), 1, "{paths:?}");
+        for module in ["a", "b", "c"] {
+            let module_chunks =
+                scripts_with_module(&scripts, "chunk_sizes", &format!("input/{module}.js"));
+            assert_eq!(module_chunks, index, "{module}: {paths:?}");
+        }
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
+
+#[tokio::test]
+async fn splits_packages_into_vendor_chunks() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let scripts = emitted_scripts("vendor_chunks", |builder| {
+            builder.vendor_chunk_groups(VendorChunkGroupsVc::node_modules())
+        })
+        .await?;
+        let paths = scripts.keys().collect::<Vec<_>>();
+        // Every package is a chunk of its own, which doesn't contain modules of
+        // the app or of the other package
+        let a = scripts_with_module(
+            &scripts,
+            "vendor_chunks",
+            "input/node_modules/a/lib/index.js",
+        );
+        let a_helper = scripts_with_module(
+            &scripts,
+            "vendor_chunks",
+            "input/node_modules/a/lib/helper.js",
+        );
+        let b = scripts_with_module(&scripts, "vendor_chunks", "input/node_modules/b/index.js");
+        let app = scripts_with_module(&scripts, "vendor_chunks", "input/app.js");
+        let index = scripts_with_module(&scripts, "vendor_chunks", "input/index.js");
+        assert_eq!(a.len(), 1, "{paths:?}");
+        assert_eq!(a_helper, a, "{paths:?}");
+        assert_eq!(b.len(), 1, "{paths:?}");
+        assert_ne!(a, b, "{paths:?}");
+        assert_eq!(app, index, "{paths:?}");
+        assert!(
+            !index.contains(&a[0]) && !index.contains(&b[0]),
+            "{paths:?}"
+        );
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
+
+#[tokio::test]
+async fn evaluates_dependencies_before_dependents_eagerly() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let code = evaluated_entry_script("eager_evaluation", |builder| {
+            builder.module_evaluation(ModuleEvaluation::Eager)
+        })
+        .await?;
+        let instantiations = code
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim();
+                let (cached, instantiated) = line
+                    .strip_prefix("cache[")?
+                    .strip_suffix(");")?
+                    .split_once("] || instantiateRuntimeModule(")?;
+                assert_eq!(cached, instantiated, "{line}");
+                Some(instantiated.to_string())
+            })
+            .collect::<Vec<_>>();
+        // `a` and `b` import each other, so the cycle is ordered by the
+        // first import reaching it: `index` imports `a`, which imports `b`.
+        // Every module is evaluated once, after its dependencies.
+        let expected = ["b", "a", "c", "index"]
+            .iter()
+            .map(|module| {
+                let path =
+                    format!("crates/turbopack-tests/tests/eager_evaluation/input/{module}.js");
+                format!("\"[workspace]/{path} (ecmascript)\"")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(instantiations, expected, "{code}");
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
+
+#[tokio::test]
+async fn computes_async_chunk_groups_when_their_manifest_is_served() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let (chunk_group, _) = entry_chunk_group("lazy_import", |builder| builder).await?;
+        let mut manifests = Vec::new();
+        for chunk in chunk_group.chunks().await?.iter() {
+            let (referenced, issues) = serve(chunk.as_asset()).await?;
+            // The import of the missing module is only resolved once the chunk
+            // group of lazy.js is built
+            assert!(
+                !issues
+                    .iter()
+                    .any(|title| title.starts_with("Error resolving")),
+                "{issues:?}"
+            );
+            for asset in referenced {
+                if asset.path().await?.path.contains("manifest-chunk") {
+                    manifests.push(asset);
+                }
+            }
+        }
+        assert_eq!(manifests.len(), 1);
+
+        let (referenced, issues) = serve(manifests[0]).await?;
+        assert!(
+            issues
+                .iter()
+                .any(|title| title.starts_with("Error resolving")),
+            "{issues:?}"
+        );
+        let mut paths = Vec::new();
+        for asset in referenced {
+            paths.push(asset.path().await?.path.clone());
+        }
+        assert!(
+            paths
+                .iter()
+                .any(|path| path.contains("input_lazy") && !path.contains("manifest-chunk")),
+            "the chunks of lazy.js are served after the manifest chunk: {paths:?}"
+        );
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
+
+#[tokio::test]
+async fn emits_the_chunks_of_dynamic_imports() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        // The chunks of lazy.js are only referenced by the manifest chunk, but
+        // are still found by the traversal of the emitted assets
+        let scripts = emitted_scripts("lazy_import", |builder| builder).await?;
+        let paths = scripts.keys().collect::<Vec<_>>();
+        let index = scripts_with_module(&scripts, "lazy_import", "input/index.js");
+        let lazy = scripts_with_module(&scripts, "lazy_import", "input/lazy.js");
+        assert_eq!(index.len(), 1, "{paths:?}");
+        assert_eq!(lazy.len(), 1, "{paths:?}");
+        assert_ne!(index, lazy, "{paths:?}");
+
+        // They are not loaded in parallel with the manifest chunk, so they are
+        // not part of its chunk group
+        let (chunk_group, _) = entry_chunk_group("lazy_import", |builder| builder).await?;
+        for chunk in chunk_group.chunks().await?.iter() {
+            let path = chunk.path().await?.path.clone();
+            assert!(!path.contains("input_lazy"), "{path}");
+        }
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
+
+#[tokio::test]
+async fn fails_on_invalid_references_in_strict_mode() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let (chunk_group, _) = entry_chunk_group("invalid_reference", |builder| {
+            builder.strict_reference_validation()
+        })
+        .await?;
+        let chunks = chunk_group.chunks().await?;
+        let content = chunks[0].as_asset().content();
+        content.await?;
+        let issues = IssueVc::peek_issues_with_path(content)
+            .await?
+            .strongly_consistent()
+            .await?
+            .get_plain_issues()
+            .await?;
+        let issue = issues
+            .iter()
+            .find(|issue| issue.title == "Reference can't be generated")
+            .expect("the invalid reference is reported");
+        assert_eq!(issue.severity, IssueSeverity::Fatal);
+        assert!(
+            issue.description.contains("./missing"),
+            "{}",
+            issue.description
+        );
+        // The issue points to the `require` call
+        let source = issue.source.as_ref().expect("the issue has a source");
+        assert_eq!(source.start.line, 1);
+        assert_eq!(source.start.column, 16);
+        assert_eq!(source.end.line, 1);
+        assert_eq!(source.end.column, 36);
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
+
+#[tokio::test]
+async fn shows_code_generation_issues_with_code_frame() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let (chunk_group, _) = entry_chunk_group("code_gen_issue", |builder| builder).await?;
+        let chunks = chunk_group.chunks().await?;
+        let content = chunks[0].as_asset().content();
+        content.await?;
+        let issues = IssueVc::peek_issues_with_path(content)
+            .await?
+            .strongly_consistent()
+            .await?
+            .get_plain_issues()
+            .await?;
+        let issue = issues
+            .iter()
+            .find(|issue| issue.title == "non-ecmascript placeable asset")
+            .expect("the css reference is reported");
+        // The issue points to the `require` call
+        let source = issue.source.as_ref().expect("the issue has a source");
+        assert_eq!(source.start.line, 1);
+        assert_eq!(source.start.column, 15);
+        assert_eq!(source.end.line, 1);
+        assert_eq!(source.end.column, 37);
+        assert!(
+            source
+                .asset
+                .path
+                .path
+                .ends_with("code_gen_issue/input/index.js"),
+            "{}",
+            source.asset.path.path
+        );
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
+
+#[tokio::test]
+async fn reports_the_spans_of_references() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let (module, _, _) = entry_module("reference_spans", |builder| builder).await?;
+        let mut spans = BTreeMap::new();
+        for reference in module.references().await?.iter() {
+            let name = reference.to_string().await?.clone_value();
+            if let Some(source) = *reference.issue_source().await? {
+                let source = source.await?;
+                spans.insert(
+                    name,
+                    (
+                        (source.start.line, source.start.column),
+                        (source.end.line, source.end.column),
+                    ),
+                );
+            }
+        }
+        // The spans cover the `import()` and `require.resolve()` calls
+        assert_eq!(
+            spans.get("dynamic import relative \"./a.js\""),
+            Some(&((0, 10), (0, 26))),
+            "{spans:?}"
+        );
+        assert_eq!(
+            spans.get("require.resolve relative \"./b.js\""),
+            Some(&((1, 10), (1, 35))),
+            "{spans:?}"
+        );
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
@@ -0,0 +1,3 @@
+import "./first.css";
+import "dep/style.css";
+import "./last.css";
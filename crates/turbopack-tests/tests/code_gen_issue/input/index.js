@@ -0,0 +1,3 @@
+console.log("before");
+const styles = require("./style.css");
+console.log(styles);
@@ -0,0 +1,5 @@
+import { shared } from "./shared.js";
+
+export function b() {
+  return "b " + shared();
+}
@@ -0,0 +1,2 @@
+import("./a.js").then(({ a }) => console.log(a()));
+import("./b.js").then(({ b }) => console.log(b()));
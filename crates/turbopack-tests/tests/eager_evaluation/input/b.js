@@ -0,0 +1,9 @@
+import { a } from "./a.js";
+
+export function b() {
+  return "b";
+}
+
+export function ab() {
+  return a();
+}
@@ -0,0 +1,94 @@
+#![cfg(test)]
+
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::bail;
+use turbo_tasks::{NothingVc, TurboTasks, Value};
+use turbo_tasks_env::DotenvProcessEnvVc;
+use turbo_tasks_fs::{DiskFileSystemVc, FileSystem, FileSystemPathVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    ecmascript::{
+        chunk::{EcmascriptChunkPlaceableVc, EcmascriptChunkPlaceablesVc},
+        env_reads::check_undefined_env_reads,
+    },
+    module_options::ModuleOptionsContext,
+    register,
+    resolve_options_context::ResolveOptionsContext,
+    transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    context::AssetContextVc,
+    environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    issue::IssueVc,
+    source_asset::SourceAssetVc,
+};
+
+#[tokio::test]
+async fn reports_undefined_env_reads() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let workspace_root = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../..")
+            .canonicalize()?;
+        let fs = DiskFileSystemVc::new(
+            "workspace".to_string(),
+            workspace_root.to_string_lossy().to_string(),
+        );
+        let path: FileSystemPathVc = fs
+            .root()
+            .join("crates/turbopack-tests/tests/env_reads/input");
+
+        let process_env = DotenvProcessEnvVc::new(None, path.join(".env")).into();
+        let env = EnvironmentVc::new(
+            Value::new(ExecutionEnvironment::Browser(
+                BrowserEnvironment {
+                    dom: true,
+                    web_worker: false,
+                    service_worker: false,
+                    browserslist_query: "Chrome 102".to_string(),
+                }
+                .into(),
+            )),
+            Value::new(EnvironmentIntention::Client),
+        )
+        .with_defined_env(process_env);
+        let context: AssetContextVc = ModuleAssetContextVc::new(
+            TransitionsByNameVc::cell(HashMap::new()),
+            env,
+            ModuleOptionsContext::default().cell(),
+            ResolveOptionsContext::default().cell(),
+        )
+        .into();
+
+        let module = context.process(SourceAssetVc::new(path.join("index.js")).into());
+        let placeable = match EcmascriptChunkPlaceableVc::resolve_from(module).await? {
+            Some(placeable) => placeable,
+            None => bail!("the entry is not placeable"),
+        };
+        let completion =
+            check_undefined_env_reads(EcmascriptChunkPlaceablesVc::cell(vec![placeable]));
+        completion.await?;
+        let issues = IssueVc::peek_issues_with_path(completion)
+            .await?
+            .strongly_consistent()
+            .await?
+            .get_plain_issues()
+            .await?;
+
+        let titles = issues.iter().map(|issue| &issue.title).collect::<Vec<_>>();
+        assert_eq!(titles.len(), 1, "unexpected issues {titles:?}");
+        let description = &issues[0].description;
+        assert!(description.contains("- MISSING (read by "), "{description}");
+        assert!(
+            description.contains("- MISSING_IN_LIB (read by "),
+            "{description}"
+        );
+        assert!(!description.contains("DEFINED"), "{description}");
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
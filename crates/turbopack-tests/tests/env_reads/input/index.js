@@ -0,0 +1,3 @@
+import "./lib.js";
+
+console.log(process.env.DEFINED, process.env.MISSING);
@@ -0,0 +1 @@
+console.log(process.env.MISSING_IN_LIB);
@@ -0,0 +1,116 @@
+#![cfg(test)]
+
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use anyhow::Result;
+use turbo_tasks::{RawVc, TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, FileContent, FileSystem};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    module_options::ModuleOptionsContext, register, resolve_options_context::ResolveOptionsContext,
+    transition::TransitionsByNameVc, ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::AssetContent,
+    context::AssetContextVc,
+    environment::{EnvironmentIntention, EnvironmentVc, ExecutionEnvironment, NodeJsEnvironment},
+    issue::{IssueSeverity, IssueVc, PlainIssueReadRef},
+    resolve::parse::RequestVc,
+    source_asset::SourceAssetVc,
+};
+
+async fn peek_issues(source: impl Into<RawVc>) -> Result<Vec<PlainIssueReadRef>> {
+    IssueVc::peek_issues_with_path(source.into())
+        .await?
+        .strongly_consistent()
+        .await?
+        .get_plain_issues()
+        .await
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn reports_reads_outside_of_hermetic_root() {
+    register();
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("hermetic");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("root")).unwrap();
+    fs::create_dir_all(dir.join("outside")).unwrap();
+    fs::write(dir.join("outside/file.js"), "export default 42;").unwrap();
+    std::os::unix::fs::symlink(dir.join("outside"), dir.join("root/linked")).unwrap();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let fs = DiskFileSystemVc::new_hermetic(
+            "project".to_string(),
+            dir.join("root").to_string_lossy().to_string(),
+        );
+        let content = SourceAssetVc::new(fs.root().join("linked/file.js"))
+            .as_asset()
+            .content();
+        assert!(matches!(
+            &*content.await?,
+            AssetContent::File(file) if matches!(&*file.await?, FileContent::NotFound)
+        ));
+
+        let issues = peek_issues(content).await?;
+        assert_eq!(issues.len(), 1);
+        let issue = &issues[0];
+        assert_eq!(issue.severity, IssueSeverity::Fatal);
+        assert!(
+            issue.title.starts_with("file system attempted to access")
+                && issue.title.contains("outside of the root"),
+            "unexpected title {}",
+            issue.title
+        );
+        Ok(turbo_tasks::NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
+
+#[tokio::test]
+async fn reports_remote_modules_in_hermetic_mode() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests");
+        let fs = DiskFileSystemVc::new("tests".to_string(), root.to_string_lossy().to_string());
+        let context: AssetContextVc = ModuleAssetContextVc::new(
+            TransitionsByNameVc::cell(HashMap::new()),
+            EnvironmentVc::new(
+                Value::new(ExecutionEnvironment::NodeJsBuildTime(
+                    NodeJsEnvironment::default().into(),
+                )),
+                Value::new(EnvironmentIntention::Client),
+            ),
+            ModuleOptionsContext::default().cell(),
+            ResolveOptionsContext {
+                hermetic: true,
+                ..Default::default()
+            }
+            .cell(),
+        )
+        .into();
+
+        let origin_path = fs.root().join("index.js");
+        let result = context.resolve_asset(
+            origin_path,
+            RequestVc::parse_string("https://example.com/lib.js".to_string()),
+            context.resolve_options(origin_path),
+        );
+        assert!(*result.is_unresolveable().await?);
+
+        let issues = peek_issues(result).await?;
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, IssueSeverity::Fatal);
+        assert_eq!(
+            issues[0].title,
+            "resolve attempted to access the remote module https://example.com/lib.js in hermetic \
+             mode"
+        );
+        Ok(turbo_tasks::NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
@@ -0,0 +1,3 @@
+console.log("before");
+const missing = require("./missing");
+console.log(missing);
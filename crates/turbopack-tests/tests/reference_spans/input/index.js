@@ -0,0 +1,3 @@
+const a = import("./a.js");
+const b = require.resolve("./b.js");
+console.log(a, b);
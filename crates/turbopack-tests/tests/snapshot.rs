@@ -12,14 +12,19 @@ use serde::Deserialize;
 use similar::TextDiff;
 use test_generator::test_resources;
 use turbo_tasks::{NothingVc, TryJoinIterExt, TurboTasks, Value};
-use turbo_tasks_env::DotenvProcessEnvVc;
+use turbo_tasks_env::{DotenvProcessEnvVc, ProcessEnvVc};
 use turbo_tasks_fs::{
     util::sys_to_unix, DirectoryContent, DirectoryEntry, DiskFileSystemVc, File, FileContent,
     FileSystem, FileSystemEntryType, FileSystemPathVc, FileSystemVc,
 };
 use turbo_tasks_memory::MemoryBackend;
 use turbopack::{
-    ecmascript::{chunk::EcmascriptChunkPlaceablesVc, EcmascriptModuleAssetVc},
+    ecmascript::{
+        chunk::{EcmascriptChunkPlaceableVc, EcmascriptChunkPlaceablesVc},
+        concatenation::compute_concatenated_modules,
+        used_exports::compute_used_exports,
+        EcmascriptModuleAssetVc,
+    },
     module_options::ModuleOptionsContext,
     register,
     resolve_options_context::ResolveOptionsContext,
@@ -46,6 +51,12 @@ struct SnapshotOptions {
     browserslist: String,
     #[serde(default = "default_entry")]
     entry: String,
+    #[serde(default)]
+    hot_module_replacement: bool,
+    #[serde(default)]
+    used_exports: bool,
+    #[serde(default)]
+    concatenated_modules: bool,
 }
 
 impl Default for SnapshotOptions {
@@ -53,6 +64,9 @@ impl Default for SnapshotOptions {
         SnapshotOptions {
             browserslist: default_browserslist(),
             entry: default_entry(),
+            hot_module_replacement: false,
+            used_exports: false,
+            concatenated_modules: false,
         }
     }
 }
@@ -119,9 +133,10 @@ async fn run(resource: &'static str) -> Result<()> {
         let entry_asset = project_path.join(&options.entry);
         let entry_paths = vec![entry_asset];
 
-        let runtime_entries = maybe_load_env(project_fs.into(), fs_path).await?;
+        let process_env = maybe_load_env(project_fs.into(), fs_path).await?;
+        let runtime_entries = process_env.map(|(_, runtime_entries)| runtime_entries);
 
-        let env = EnvironmentVc::new(
+        let mut env = EnvironmentVc::new(
             Value::new(ExecutionEnvironment::Browser(
                 // TODO: load more from options.json
                 BrowserEnvironment {
@@ -134,6 +149,9 @@ async fn run(resource: &'static str) -> Result<()> {
             )),
             Value::new(EnvironmentIntention::Client),
         );
+        if let Some((process_env, _)) = process_env {
+            env = env.with_defined_env(process_env);
+        }
 
         let context: AssetContextVc = ModuleAssetContextVc::new(
             TransitionsByNameVc::cell(HashMap::new()),
@@ -156,11 +174,33 @@ async fn run(resource: &'static str) -> Result<()> {
         )
         .into();
 
+        let modules = entry_paths
+            .into_iter()
+            .map(|p| context.process(SourceAssetVc::new(p).into()))
+            .collect::<Vec<_>>();
+        let mut entries = Vec::new();
+        for &module in modules.iter() {
+            if let Some(placeable) = EcmascriptChunkPlaceableVc::resolve_from(module).await? {
+                entries.push(placeable);
+            }
+        }
+        let entries = EcmascriptChunkPlaceablesVc::cell(entries);
+
         let chunk_root_path = path.join("output");
         let asset_root_path = path.join("static");
-        let chunking_context =
-            DevChunkingContextVc::builder(project_root, path, chunk_root_path, asset_root_path)
-                .build();
+        let mut chunking_context =
+            DevChunkingContextVc::builder(project_root, path, chunk_root_path, asset_root_path);
+        if options.hot_module_replacement {
+            chunking_context = chunking_context.hot_module_replacement();
+        }
+        if options.used_exports {
+            chunking_context = chunking_context.used_exports(compute_used_exports(entries));
+        }
+        if options.concatenated_modules {
+            chunking_context =
+                chunking_context.concatenated_modules(compute_concatenated_modules(entries));
+        }
+        let chunking_context = chunking_context.build();
 
         let existing_dir = chunk_root_path.read_dir().await?;
         let mut expected_paths = HashMap::new();
@@ -179,11 +219,8 @@ async fn run(resource: &'static str) -> Result<()> {
             }
         };
 
-        let modules = entry_paths
-            .into_iter()
-            .map(|p| context.process(SourceAssetVc::new(p).into()));
-
         let chunks = modules
+            .into_iter()
             .map(|module| async move {
                 if let Some(ecmascript) = EcmascriptModuleAssetVc::resolve_from(module).await? {
                     // TODO: Load runtime entries from snapshots
@@ -313,10 +350,12 @@ fn trimmed_string(input: &[u8]) -> String {
     String::from_utf8_lossy(input).trim().to_string()
 }
 
+/// Loads the `input/.env` file of the test, if it exists. The variables are
+/// defined at compile time and injected by the returned runtime entries.
 async fn maybe_load_env(
     project_fs: FileSystemVc,
     path: &Path,
-) -> Result<Option<EcmascriptChunkPlaceablesVc>> {
+) -> Result<Option<(ProcessEnvVc, EcmascriptChunkPlaceablesVc)>> {
     let dotenv_path = path.join("input/.env");
     let dotenv_path = sys_to_unix(dotenv_path.to_str().unwrap());
     let dotenv_path = project_fs.root().join(&dotenv_path);
@@ -325,9 +364,10 @@ async fn maybe_load_env(
         return Ok(None);
     }
 
-    let env = DotenvProcessEnvVc::new(None, dotenv_path);
-    let asset = ProcessEnvAssetVc::new(dotenv_path, env.into());
-    Ok(Some(EcmascriptChunkPlaceablesVc::cell(vec![
-        asset.as_ecmascript_chunk_placeable()
-    ])))
+    let env = DotenvProcessEnvVc::new(None, dotenv_path).into();
+    let asset = ProcessEnvAssetVc::new(dotenv_path, env);
+    Ok(Some((
+        env,
+        EcmascriptChunkPlaceablesVc::cell(vec![asset.as_ecmascript_chunk_placeable()]),
+    )))
 }
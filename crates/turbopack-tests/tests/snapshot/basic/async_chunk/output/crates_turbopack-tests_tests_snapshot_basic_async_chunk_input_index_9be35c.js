@@ -110,6 +110,12 @@ __turbopack_export_value__((__turbopack_import__) => {
    * from chunk A, but still exists in chunk B.
    */
   const moduleChunksMap = new Map();
+  /**
+   * The URL prefix chunks are loaded from, see `resolvePublicPath`.
+   *
+   * @type {string}
+   */
+  const publicPath = resolvePublicPath();
   const hOP = Object.prototype.hasOwnProperty;
   const _process =
     typeof process !== "undefined"
@@ -206,6 +212,38 @@ __turbopack_export_value__((__turbopack_import__) => {
     return getOrInstantiateModuleFromParent(id, sourceModule).exports;
   }
 
+  /**
+   * Creates a `__webpack_require__` for libraries built with webpack, which
+   * requires modules like `require` and provides the helpers webpack attaches
+   * to it.
+   *
+   * @param {Module} sourceModule
+   */
+  function webpackRequire(sourceModule) {
+    const require = commonJsRequire.bind(null, sourceModule);
+    require.c = moduleCache;
+    require.d = (exports, getters) => {
+      for (const key in getters) {
+        defineProp(exports, key, { get: getters[key], enumerable: true });
+      }
+    };
+    require.n = (module) => {
+      const getter =
+        module && module.__esModule ? () => module["default"] : () => module;
+      require.d(getter, { a: getter });
+      return getter;
+    };
+    require.o = (obj, prop) => hOP.call(obj, prop);
+    require.r = (exports) => {
+      if (toStringTag) defineProp(exports, toStringTag, { value: "Module" });
+      defineProp(exports, "__esModule", { value: true });
+    };
+    require.g = globalThis;
+    // Chunks of webpack builds are bundled into the chunks of the importer.
+    require.e = () => Promise.resolve();
+    return require;
+  }
+
   function externalRequire(id) {
     let raw;
     try {
@@ -240,6 +278,105 @@ __turbopack_export_value__((__turbopack_import__) => {
     return chunkLoader.promise;
   }
 
+  /**
+   * Returns the URL a chunk is loaded from. When chunks are emitted with
+   * content hashed file names, the page maps the chunk paths to the emitted
+   * files in `TURBOPACK_CHUNK_FILES`.
+   *
+   * @param {ChunkPath} chunkPath
+   * @returns {string}
+   */
+  function getChunkUrl(chunkPath) {
+    return `${publicPath}${getChunkFile(chunkPath)}`;
+  }
+
+  /**
+   * Loads a chunk in the SystemJS or AMD format with the module loader of the
+   * host. We'll only mark the chunk as loaded once it has been executed, which
+   * happens in `registerChunk`.
+   *
+   * @param {"systemjs" | "amd"} chunkFormat
+   * @param {string} chunkUrl
+   * @param {() => void} onError
+   */
+  function loadChunkModule(chunkFormat, chunkUrl, onError) {
+    switch (chunkFormat) {
+      case "systemjs":
+        System.import(chunkUrl).catch(onError);
+        break;
+      case "amd":
+        require([chunkUrl], undefined, onError);
+        break;
+      default:
+        throw new Error(`unsupported chunk format ${chunkFormat}`);
+    }
+  }
+
+  /**
+   * Returns the file a chunk was emitted to, relative to the output root.
+   *
+   * @param {ChunkPath} chunkPath
+   * @returns {string}
+   */
+  function getChunkFile(chunkPath) {
+    const chunkFiles = self.TURBOPACK_CHUNK_FILES;
+    return (chunkFiles && chunkFiles[chunkPath]) || chunkPath;
+  }
+
+  /**
+   * Resolves the URL prefix chunks are loaded from, which is configured by
+   * `TURBOPACK_PUBLIC_PATH` and defaults to the root of the server.
+   *
+   * With `"auto"`, the prefix is derived from the URL of the script containing
+   * the runtime. Its chunk is the one which registered last, so the prefix is
+   * the script URL without the path of the chunk relative to the output root.
+   *
+   * @returns {string}
+   */
+  function resolvePublicPath() {
+    const publicPath = self.TURBOPACK_PUBLIC_PATH;
+    if (publicPath == null) {
+      return "/";
+    }
+    if (publicPath !== "auto") {
+      return publicPath === "" || publicPath.endsWith("/")
+        ? publicPath
+        : `${publicPath}/`;
+    }
+    const [chunkPath] = chunksToRegister[chunksToRegister.length - 1];
+    const file = getChunkFile(chunkPath);
+    const depth = file.split("/").length - 1;
+    return new URL(`./${"../".repeat(depth)}`, getScriptUrl(file)).href;
+  }
+
+  /**
+   * Returns the URL of the script which is executing, which was emitted to
+   * `file`.
+   *
+   * @param {string} file
+   * @returns {string}
+   */
+  function getScriptUrl(file) {
+    if (typeof document === "undefined") {
+      // Workers execute the script they were created with
+      return self.location.href;
+    }
+    if (document.currentScript != null) {
+      return document.currentScript.src;
+    }
+    // Module scripts have no `currentScript`, but they are loaded by a script
+    // tag of the page
+    const script = Array.from(document.querySelectorAll("script[src]")).find(
+      (script) => new URL(script.src).pathname.endsWith(`/${file}`)
+    );
+    if (script == null) {
+      throw new Error(
+        `can't derive the public path, the script of chunk ${file} is unknown`
+      );
+    }
+    return script.src;
+  }
+
   /**
    * @param {string} chunkPath
    * @returns {Loader}
@@ -273,6 +410,14 @@ __turbopack_export_value__((__turbopack_import__) => {
     };
     chunkLoaders.set(chunkPath, chunkLoader);
 
+    // Chunks in the SystemJS and AMD formats are loaded with the module loader
+    // of the host, which also works outside of the browser.
+    const chunkFormat = self.TURBOPACK_CHUNK_FORMAT;
+    if (chunkPath.endsWith(".js") && chunkFormat != null) {
+      loadChunkModule(chunkFormat, getChunkUrl(chunkPath), onError);
+      return chunkLoader;
+    }
+
     if (typeof document === "undefined") {
       throw new Error(
         "Loading chunks outside the browser is not currently supported. If using next/dynamic, try opting out of ssr for now: https://nextjs.org/docs/advanced-features/dynamic-import#with-no-ssr"
@@ -280,9 +425,21 @@ __turbopack_export_value__((__turbopack_import__) => {
     }
 
     if (chunkPath.endsWith(".css")) {
+      const chunkUrl = getChunkUrl(chunkPath);
+      // The stylesheet might already be linked by the page, when the chunk is
+      // also part of the chunk group the page was rendered with.
+      const existingLink = Array.from(
+        document.querySelectorAll("link[rel=stylesheet]")
+      ).find((link) => link.getAttribute("href") === chunkUrl);
+      if (existingLink != null) {
+        loadedChunks.add(chunkPath);
+        onLoad();
+        return chunkLoader;
+      }
+
       const link = document.createElement("link");
       link.rel = "stylesheet";
-      link.href = `/${chunkPath}`;
+      link.href = chunkUrl;
       link.onerror = onError;
       link.onload = () => {
         loadedChunks.add(chunkPath);
@@ -291,7 +448,7 @@ __turbopack_export_value__((__turbopack_import__) => {
       document.body.appendChild(link);
     } else if (chunkPath.endsWith(".js")) {
       const script = document.createElement("script");
-      script.src = `/${chunkPath}`;
+      script.src = getChunkUrl(chunkPath);
       // We'll only mark the chunk as loaded once the script has been executed,
       // which happens in `registerChunk`.
       script.onerror = onError;
@@ -387,9 +544,14 @@ __turbopack_export_value__((__turbopack_import__) => {
         s: esm.bind(null, module.exports),
         v: exportValue.bind(null, module),
         m: module,
+        h: module.hot,
+        get w() {
+          return webpackRequire(module);
+        },
         c: moduleCache,
         l: loadChunk,
         p: _process,
+        u: publicPath,
         __dirname: module.id.replace(/(^|\/)[\/]+$/, ""),
       });
     });
@@ -546,6 +708,8 @@ __turbopack_export_value__((__turbopack_import__) => {
   function computeOutdatedModules(update) {
     const outdatedModules = new Set();
     const newModuleFactories = new Map();
+    /** @type {Map<ModuleId, Set<ModuleId>>} */
+    const outdatedDependencies = new Map();
 
     for (const [moduleId, factory] of Object.entries(update.added)) {
       newModuleFactories.set(moduleId, _eval(factory));
@@ -567,17 +731,34 @@ __turbopack_export_value__((__turbopack_import__) => {
               effect.dependencyChain
             )}.`
           );
+        case "declined":
+          throw new Error(
+            `cannot apply update: declined dependency ${
+              effect.moduleId
+            } of module ${effect.parentId}. ${formatDependencyChain(
+              effect.dependencyChain
+            )}.`
+          );
         case "accepted":
           newModuleFactories.set(moduleId, _eval(factory));
           for (const outdatedModuleId of effect.outdatedModules) {
             outdatedModules.add(outdatedModuleId);
           }
+          for (const [parentId, dependencies] of effect.outdatedDependencies) {
+            let parentDependencies = outdatedDependencies.get(parentId);
+            if (!parentDependencies) {
+              parentDependencies = new Set();
+              outdatedDependencies.set(parentId, parentDependencies);
+            }
+            for (const dependencyId of dependencies) {
+              parentDependencies.add(dependencyId);
+            }
+          }
           break;
-        // TODO(alexkirsz) Dependencies: handle dependencies effects.
       }
     }
 
-    return { outdatedModules, newModuleFactories };
+    return { outdatedModules, newModuleFactories, outdatedDependencies };
   }
 
   /**
@@ -603,8 +784,14 @@ __turbopack_export_value__((__turbopack_import__) => {
    * @param {ChunkPath} chunkPath
    * @param {Iterable<ModuleId>} outdatedModules
    * @param {Iterable<ModuleId>} deletedModules
+   * @param {Map<ModuleId, Set<ModuleId>>} outdatedDependencies
    */
-  function disposePhase(chunkPath, outdatedModules, deletedModules) {
+  function disposePhase(
+    chunkPath,
+    outdatedModules,
+    deletedModules,
+    outdatedDependencies
+  ) {
     for (const moduleId of outdatedModules) {
       const module = moduleCache[moduleId];
       if (!module) {
@@ -631,8 +818,22 @@ __turbopack_export_value__((__turbopack_import__) => {
       }
     }
 
-    // TODO(alexkirsz) Dependencies: remove outdated dependency from module
-    // children.
+    // Remove the outdated dependencies from the children of the modules which
+    // accept them. They will be added back once the accept handlers import
+    // them again.
+    for (const [moduleId, dependencies] of outdatedDependencies) {
+      const module = moduleCache[moduleId];
+      if (!module) {
+        continue;
+      }
+
+      for (const dependencyId of dependencies) {
+        const idx = module.children.indexOf(dependencyId);
+        if (idx >= 0) {
+          module.children.splice(idx, 1);
+        }
+      }
+    }
   }
 
   /**
@@ -661,8 +862,6 @@ __turbopack_export_value__((__turbopack_import__) => {
     delete moduleCache[module.id];
     moduleHotState.delete(module);
 
-    // TODO(alexkirsz) Dependencies: delete the module from outdated deps.
-
     // Remove the disposed module from its children's parents list.
     // It will be added back once the module re-instantiates and imports its
     // children again.
@@ -686,11 +885,13 @@ __turbopack_export_value__((__turbopack_import__) => {
    * @param {ChunkPath} chunkPath
    * @param {{ moduleId: ModuleId, errorHandler: true | Function }[]} outdatedSelfAcceptedModules
    * @param {Map<string, ModuleFactory>} newModuleFactories
+   * @param {Map<ModuleId, Set<ModuleId>>} outdatedDependencies
    */
   function applyPhase(
     chunkPath,
     outdatedSelfAcceptedModules,
-    newModuleFactories
+    newModuleFactories,
+    outdatedDependencies
   ) {
     // Update module factories.
     for (const [moduleId, factory] of newModuleFactories.entries()) {
@@ -700,7 +901,44 @@ __turbopack_export_value__((__turbopack_import__) => {
 
     // TODO(alexkirsz) Run new runtime entries here.
 
-    // TODO(alexkirsz) Dependencies: call accept handlers for outdated deps.
+    // Call the accept handlers of the modules accepting outdated dependencies.
+    // Dependencies accepted with the same handler are passed to it at once.
+    for (const [moduleId, dependencies] of outdatedDependencies) {
+      const module = moduleCache[moduleId];
+      if (!module) {
+        continue;
+      }
+
+      const hotState = moduleHotState.get(module);
+      /** @type {Map<AcceptCallback, ModuleId[]>} */
+      const callbacks = new Map();
+      for (const dependencyId of dependencies) {
+        const callback = hotState.acceptedDependencies[dependencyId];
+        const callbackDependencies = callbacks.get(callback);
+        if (callbackDependencies) {
+          callbackDependencies.push(dependencyId);
+        } else {
+          callbacks.set(callback, [dependencyId]);
+        }
+      }
+
+      for (const [callback, callbackDependencies] of callbacks) {
+        try {
+          callback(callbackDependencies);
+        } catch (err) {
+          for (const dependencyId of callbackDependencies) {
+            const errorHandler = hotState.acceptedErrorHandlers[dependencyId];
+            if (typeof errorHandler === "function") {
+              try {
+                errorHandler(err, { moduleId, dependencyId });
+              } catch (_) {
+                // Ignore error.
+              }
+            }
+          }
+        }
+      }
+    }
 
     // Re-instantiate all outdated self-accepted modules.
     for (const { moduleId, errorHandler } of outdatedSelfAcceptedModules) {
@@ -724,7 +962,7 @@ __turbopack_export_value__((__turbopack_import__) => {
    * @param {EcmascriptChunkUpdate} update
    */
   function applyUpdate(chunkPath, update) {
-    const { outdatedModules, newModuleFactories } =
+    const { outdatedModules, newModuleFactories, outdatedDependencies } =
       computeOutdatedModules(update);
 
     const deletedModules = new Set(update.deleted);
@@ -732,8 +970,18 @@ __turbopack_export_value__((__turbopack_import__) => {
     const outdatedSelfAcceptedModules =
       computeOutdatedSelfAcceptedModules(outdatedModules);
 
-    disposePhase(chunkPath, outdatedModules, deletedModules);
-    applyPhase(chunkPath, outdatedSelfAcceptedModules, newModuleFactories);
+    disposePhase(
+      chunkPath,
+      outdatedModules,
+      deletedModules,
+      outdatedDependencies
+    );
+    applyPhase(
+      chunkPath,
+      outdatedSelfAcceptedModules,
+      newModuleFactories,
+      outdatedDependencies
+    );
   }
 
   /**
@@ -743,6 +991,8 @@ __turbopack_export_value__((__turbopack_import__) => {
    */
   function getAffectedModuleEffects(moduleId) {
     const outdatedModules = new Set();
+    /** @type {Map<ModuleId, Set<ModuleId>>} */
+    const outdatedDependencies = new Map();
 
     /** @typedef {{moduleId?: ModuleId, dependencyChain: ModuleId[]}} QueueItem */
 
@@ -805,8 +1055,28 @@ __turbopack_export_value__((__turbopack_import__) => {
           continue;
         }
 
-        // TODO(alexkirsz) Dependencies: check accepted and declined
-        // dependencies here.
+        const parentHotState = moduleHotState.get(parent);
+
+        if (parentHotState.declinedDependencies[moduleId]) {
+          return {
+            type: "declined",
+            dependencyChain: [...dependencyChain, moduleId],
+            moduleId,
+            parentId,
+          };
+        }
+
+        // The parent handles the update of this module in its accept
+        // handler, so the update doesn't propagate further.
+        if (parentHotState.acceptedDependencies[moduleId]) {
+          let dependencies = outdatedDependencies.get(parentId);
+          if (!dependencies) {
+            dependencies = new Set();
+            outdatedDependencies.set(parentId, dependencies);
+          }
+          dependencies.add(moduleId);
+          continue;
+        }
 
         queue.push({
           moduleId: parentId,
@@ -819,6 +1089,7 @@ __turbopack_export_value__((__turbopack_import__) => {
       type: "accepted",
       moduleId,
       outdatedModules,
+      outdatedDependencies,
     };
   }
 
@@ -850,22 +1121,29 @@ __turbopack_export_value__((__turbopack_import__) => {
       selfDeclined: false,
       selfInvalidated: false,
       disposeHandlers: [],
+      acceptedDependencies: {},
+      acceptedErrorHandlers: {},
+      declinedDependencies: {},
     };
 
     /**
-     * TODO(alexkirsz) Support full (dep, callback, errorHandler) form.
+     * The dependencies are module ids, as the requests passed to `accept` are
+     * replaced with the ids of the modules at compile time.
      *
-     * @param {string | string[] | AcceptErrorHandler} [dep]
-     * @param {AcceptCallback} [_callback]
-     * @param {AcceptErrorHandler} [_errorHandler]
+     * @param {ModuleId | ModuleId[] | AcceptErrorHandler} [dep]
+     * @param {AcceptCallback} [callback]
+     * @param {AcceptErrorHandler} [errorHandler]
      */
-    function accept(dep, _callback, _errorHandler) {
+    function accept(dep, callback, errorHandler) {
       if (dep === undefined) {
         hotState.selfAccepted = true;
       } else if (typeof dep === "function") {
         hotState.selfAccepted = dep;
       } else {
-        throw new Error("unsupported `accept` signature");
+        for (const dependencyId of Array.isArray(dep) ? dep : [dep]) {
+          hotState.acceptedDependencies[dependencyId] = callback ?? (() => {});
+          hotState.acceptedErrorHandlers[dependencyId] = errorHandler;
+        }
       }
     }
 
@@ -884,7 +1162,9 @@ __turbopack_export_value__((__turbopack_import__) => {
         if (dep === undefined) {
           hotState.selfDeclined = true;
         } else {
-          throw new Error("unsupported `decline` signature");
+          for (const dependencyId of Array.isArray(dep) ? dep : [dep]) {
+            hotState.declinedDependencies[dependencyId] = true;
+          }
         }
       },
 
@@ -0,0 +1,6 @@
+const value = "a";
+function helper() {
+  return value;
+}
+
+export { helper as value };
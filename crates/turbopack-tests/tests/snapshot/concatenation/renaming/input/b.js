@@ -0,0 +1,4 @@
+export const value = "b";
+export function helper() {
+  return { value };
+}
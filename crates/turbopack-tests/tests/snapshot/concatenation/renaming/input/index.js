@@ -0,0 +1,6 @@
+import { value as a } from "./a.js";
+import { value as b } from "./b.js";
+
+const value = "index";
+
+console.log(value, a, b);
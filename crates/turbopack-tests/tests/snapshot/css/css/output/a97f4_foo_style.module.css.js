@@ -2,8 +2,12 @@
 
 "[project]/crates/turbopack-tests/tests/snapshot/css/css/input/node_modules/foo/style.module.css (css module)": (({ r: __turbopack_require__, x: __turbopack_external_require__, i: __turbopack_import__, s: __turbopack_esm__, v: __turbopack_export_value__, c: __turbopack_cache__, l: __turbopack_load__, p: process, __dirname }) => (() => {
 
-__turbopack_export_value__({
-  "foo-module-style": "foo-module-style◽[project]/crates/turbopack-tests/tests/snapshot/css/css/input/node_modules/foo/style.module.css",
+const classes = {
+  "foo-module-style": "foo-module-style__e06af120",
+};
+__turbopack_esm__({
+  "foo-module-style": () => classes["foo-module-style"],
+  default: () => classes,
 });
 
 })()),
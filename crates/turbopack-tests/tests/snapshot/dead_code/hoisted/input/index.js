@@ -0,0 +1,9 @@
+// The declarations are hoisted out of the dead branch, so it must stay
+if (process.env.NODE_ENV !== "production") {
+  var development = require("./development");
+  function log() {
+    console.log(development);
+  }
+}
+
+console.log(typeof development, typeof log);
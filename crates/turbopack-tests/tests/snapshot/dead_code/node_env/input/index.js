@@ -0,0 +1,7 @@
+if (process.env.NODE_ENV !== "production") {
+  require("./development");
+} else {
+  console.log("production");
+}
+
+if (process.env.NODE_ENV === "development") require("./development");
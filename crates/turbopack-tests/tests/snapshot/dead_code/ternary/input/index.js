@@ -0,0 +1,6 @@
+const logger =
+  process.env.NODE_ENV === "production"
+    ? require("./production")
+    : require("./development");
+
+console.log(logger);
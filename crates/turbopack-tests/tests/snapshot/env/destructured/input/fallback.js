@@ -0,0 +1 @@
+console.log("API_URL is not defined");
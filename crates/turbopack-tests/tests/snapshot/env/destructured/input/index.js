@@ -0,0 +1,7 @@
+const { API_URL } = process.env;
+
+if (!API_URL) {
+  require("./fallback");
+}
+
+console.log(API_URL);
@@ -0,0 +1,5 @@
+let count = require("./count");
+
+module.hot.accept(["./count", "./message"], () => {
+  count = require("./count");
+});
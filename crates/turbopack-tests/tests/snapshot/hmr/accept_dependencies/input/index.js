@@ -0,0 +1,8 @@
+import { message } from "./message";
+import "./counter";
+
+console.log(message);
+
+import.meta.hot.accept("./message", () => {
+  console.log(message);
+});
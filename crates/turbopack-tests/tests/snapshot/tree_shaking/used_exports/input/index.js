@@ -0,0 +1,4 @@
+import { used } from "./lib";
+import * as utils from "./utils";
+
+console.log(used, utils);
@@ -0,0 +1,9 @@
+export function used() {
+  return "used";
+}
+
+export function unused() {
+  return "unused";
+}
+
+export default "unused default";
@@ -0,0 +1,3 @@
+export function app() {
+  return "app";
+}
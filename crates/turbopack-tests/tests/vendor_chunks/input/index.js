@@ -0,0 +1,5 @@
+import { a } from "a";
+import { b } from "b";
+import { app } from "./app.js";
+
+console.log(a(), b(), app());
@@ -0,0 +1,167 @@
+//! A minimal JavaScript API to bundle files from memory, e. g. for in-browser
+//! playgrounds.
+//!
+//! The crate is built for the `wasm32-unknown-unknown` target with
+//! `wasm-bindgen`. The turbopack crates are used without their default
+//! features, so neither file watching nor child processes are required:
+//! sources and outputs live in a
+//! [VirtualFileSystem](turbo_tasks_fs::virtual_fs::VirtualFileSystem).
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    mem::take,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Result};
+use js_sys::{Object, Reflect};
+use turbo_tasks::{NothingVc, TurboTasks, Value};
+use turbo_tasks_fs::{
+    virtual_fs::VirtualFileSystemVc, File, FileContent, FileSystemPathVc, FileSystemVc,
+};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    ecmascript::EcmascriptModuleAssetVc, module_options::ModuleOptionsContext, register,
+    resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::{AssetContent, AssetVc},
+    chunk::{dev::DevChunkingContextVc, ChunkableAssetVc},
+    context::AssetContextVc,
+    environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    reference::all_referenced_assets,
+    source_asset::SourceAssetVc,
+};
+use wasm_bindgen::prelude::*;
+
+/// Bundles `files`, an object mapping paths to their sources, starting at the
+/// file `entry`. Returns an object mapping the paths of the emitted files to
+/// their contents, or throws the error of the build.
+#[wasm_bindgen]
+pub fn bundle(files: JsValue, entry: String) -> Result<JsValue, JsValue> {
+    let mut sources = BTreeMap::new();
+    for key in Object::keys(&Object::from(files.clone())).iter() {
+        let path = key
+            .as_string()
+            .ok_or_else(|| JsValue::from_str("file paths must be strings"))?;
+        let source = Reflect::get(&files, &key)?
+            .as_string()
+            .ok_or_else(|| JsValue::from_str(&format!("the source of {path} must be a string")))?;
+        sources.insert(path, source);
+    }
+
+    let output =
+        bundle_sources(sources, entry).map_err(|err| JsValue::from_str(&format!("{err:?}")))?;
+
+    let result = Object::new();
+    for (path, content) in output {
+        Reflect::set(
+            &result,
+            &JsValue::from_str(&path),
+            &JsValue::from_str(&content),
+        )?;
+    }
+    Ok(result.into())
+}
+
+/// Bundles `sources`, mapping paths to their sources, starting at the file
+/// `entry`. Returns the contents of the emitted files by their path.
+pub fn bundle_sources(
+    sources: BTreeMap<String, String>,
+    entry: String,
+) -> Result<BTreeMap<String, String>> {
+    register();
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(async move {
+        let output = Arc::new(Mutex::new(BTreeMap::new()));
+        let tt = TurboTasks::new(MemoryBackend::new());
+        let task = {
+            let output = output.clone();
+            tt.spawn_once_task(async move {
+                let fs = VirtualFileSystemVc::new("playground".to_string());
+                {
+                    let fs = fs.await?;
+                    for (path, source) in sources {
+                        fs.set_file(&path, File::from(source));
+                    }
+                }
+                let root = FileSystemVc::from(fs).root();
+
+                let emitted = emit(root, &entry).await?;
+                *output.lock().unwrap() = emitted;
+                Ok(NothingVc::new().into())
+            })
+        };
+        tt.wait_task_completion(task, true).await?;
+        let output = take(&mut *output.lock().unwrap());
+        Ok(output)
+    })
+}
+
+/// Builds the chunks of `entry` into the `output` directory of `root` and
+/// returns the contents of all emitted files by their path.
+async fn emit(root: FileSystemPathVc, entry: &str) -> Result<BTreeMap<String, String>> {
+    let env = EnvironmentVc::new(
+        Value::new(ExecutionEnvironment::Browser(
+            BrowserEnvironment {
+                dom: true,
+                web_worker: false,
+                service_worker: false,
+                browserslist_query: "last 1 Chrome versions".to_string(),
+            }
+            .into(),
+        )),
+        Value::new(EnvironmentIntention::Client),
+    );
+    let context: AssetContextVc = ModuleAssetContextVc::new(
+        TransitionsByNameVc::cell(HashMap::new()),
+        env,
+        ModuleOptionsContext::default().cell(),
+        ResolveOptionsContext {
+            enable_typescript: true,
+            enable_react: true,
+            enable_node_modules: true,
+            ..Default::default()
+        }
+        .cell(),
+    )
+    .into();
+
+    let output_root = root.join("output");
+    let chunking_context =
+        DevChunkingContextVc::builder(root, output_root, output_root, output_root.join("static"))
+            .build();
+
+    let module = context.process(SourceAssetVc::new(root.join(entry)).into());
+    let chunk = if let Some(ecmascript) = EcmascriptModuleAssetVc::resolve_from(module).await? {
+        ecmascript.as_evaluated_chunk(chunking_context, None)
+    } else if let Some(chunkable) = ChunkableAssetVc::resolve_from(module).await? {
+        chunkable.as_chunk(chunking_context)
+    } else {
+        return Err(anyhow!("The entry {entry} can't be bundled"));
+    };
+
+    let mut emitted = BTreeMap::new();
+    let mut seen = HashSet::new();
+    let mut queue: VecDeque<AssetVc> = VecDeque::from([chunk.as_asset()]);
+    while let Some(asset) = queue.pop_front() {
+        let path = asset.path().await?.path.clone();
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+        if !path.starts_with("output/") {
+            continue;
+        }
+        if let AssetContent::File(file) = &*asset.content().await? {
+            if let FileContent::Content(file) = &*file.await? {
+                emitted.insert(path, String::from_utf8_lossy(file.content()).into_owned());
+            }
+        }
+        queue.extend(&*all_referenced_assets(asset).await?);
+    }
+    Ok(emitted)
+}
@@ -0,0 +1,175 @@
+use anyhow::{bail, Context, Result};
+
+const IMPORT_SECTION: u8 = 2;
+const EXPORT_SECTION: u8 = 7;
+
+/// The imports and exports of a WebAssembly module.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Default)]
+pub struct ModuleInterface {
+    /// The names of the modules the imports are read from, in the order of
+    /// their first import.
+    pub import_modules: Vec<String>,
+    pub exports: Vec<String>,
+}
+
+impl ModuleInterface {
+    /// Reads the import and export sections of a WebAssembly binary. All other
+    /// sections are skipped.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.take(4)? != b"\0asm" {
+            bail!("not a WebAssembly binary");
+        }
+        if reader.take(4)? != [1, 0, 0, 0] {
+            bail!("unsupported WebAssembly binary version");
+        }
+
+        let mut interface = ModuleInterface::default();
+        while !reader.is_empty() {
+            let id = reader.u8()?;
+            let size = reader.leb128()? as usize;
+            let mut section = Reader {
+                bytes: reader.take(size)?,
+                pos: 0,
+            };
+            match id {
+                IMPORT_SECTION => {
+                    for _ in 0..section.leb128()? {
+                        let module = section.name()?;
+                        section.name()?;
+                        section.import_description()?;
+                        if !interface.import_modules.contains(&module) {
+                            interface.import_modules.push(module);
+                        }
+                    }
+                }
+                EXPORT_SECTION => {
+                    for _ in 0..section.leb128()? {
+                        let name = section.name()?;
+                        // The kind and the index of the export.
+                        section.u8()?;
+                        section.leb128()?;
+                        interface.exports.push(name);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(interface)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .context("unexpected end of WebAssembly binary")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn leb128(&mut self) -> Result<u64> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift >= 64 {
+                bail!("invalid LEB128 integer in WebAssembly binary");
+            }
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let len = self.leb128()? as usize;
+        Ok(String::from_utf8(self.take(len)?.to_vec())?)
+    }
+
+    fn limits(&mut self) -> Result<()> {
+        let flags = self.u8()?;
+        self.leb128()?;
+        if flags & 1 != 0 {
+            self.leb128()?;
+        }
+        Ok(())
+    }
+
+    fn import_description(&mut self) -> Result<()> {
+        match self.u8()? {
+            // function: type index
+            0 => {
+                self.leb128()?;
+            }
+            // table: reference type and limits
+            1 => {
+                self.u8()?;
+                self.limits()?;
+            }
+            // memory: limits
+            2 => self.limits()?,
+            // global: value type and mutability
+            3 => {
+                self.u8()?;
+                self.u8()?;
+            }
+            // tag: attribute and type index
+            4 => {
+                self.u8()?;
+                self.leb128()?;
+            }
+            kind => bail!("unknown import kind {kind} in WebAssembly binary"),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ModuleInterface;
+
+    #[test]
+    fn parses_imports_and_exports() {
+        #[rustfmt::skip]
+        let bytes = [
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+            // type section: (func (param i32))
+            0x01, 0x05, 0x01, 0x60, 0x01, 0x7f, 0x00,
+            // import section: "./env.js" "log" (func 0), "./env.js" "memory"
+            // (memory 1), "wbg" "table" (table 1 2 funcref)
+            0x02, 0x32, 0x03,
+            0x08, b'.', b'/', b'e', b'n', b'v', b'.', b'j', b's',
+            0x03, b'l', b'o', b'g', 0x00, 0x00,
+            0x08, b'.', b'/', b'e', b'n', b'v', b'.', b'j', b's',
+            0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00, 0x01,
+            0x03, b'w', b'b', b'g',
+            0x05, b't', b'a', b'b', b'l', b'e', 0x01, 0x70, 0x01, 0x01, 0x02,
+            // export section: "run" (func 0)
+            0x07, 0x07, 0x01, 0x03, b'r', b'u', b'n', 0x00, 0x00,
+        ];
+        let interface = ModuleInterface::parse(&bytes).unwrap();
+        assert_eq!(interface.import_modules, ["./env.js", "wbg"]);
+        assert_eq!(interface.exports, ["run"]);
+
+        assert!(ModuleInterface::parse(b"\0asm").is_err());
+        assert!(ModuleInterface::parse(&bytes[..20]).is_err());
+    }
+}
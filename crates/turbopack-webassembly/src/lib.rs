@@ -0,0 +1,344 @@
+//! WebAssembly module support for turbopack.
+//!
+//! The modules a WebAssembly module imports from are referenced like ES
+//! module imports and passed as import object to its instantiation.
+//!
+//! In browsers, the binary is emitted as asset and instantiated with
+//! `WebAssembly.instantiateStreaming`. The module is an ES module facade which
+//! exports the exports of the instance as named exports, which are available
+//! once the instance is created. The default export is a promise of the
+//! exports of the instance, which importers need to await before calling the
+//! named exports.
+//!
+//! In Node.js, the binary is embedded into the module, which is compiled and
+//! instantiated synchronously and exports the exports of the instance like a
+//! CommonJS module.
+
+#![feature(min_specialization)]
+
+pub mod interface;
+
+use std::fmt::Write;
+
+use anyhow::{anyhow, Context, Result};
+use interface::{ModuleInterface, ModuleInterfaceVc};
+use turbo_tasks::{
+    primitives::{BoolVc, StringVc},
+    Value, ValueToString, ValueToStringVc,
+};
+use turbo_tasks_fs::{FileContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetOptionVc, AssetVc},
+    chunk::{ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContextVc},
+    context::AssetContextVc,
+    reference::{AssetReferenceVc, AssetReferencesVc, SingleAssetReferenceVc},
+    resolve::{
+        origin::{ResolveOrigin, ResolveOriginVc},
+        parse::RequestVc,
+    },
+};
+use turbopack_ecmascript::{
+    analyzer::imports::ImportAnnotations,
+    chunk::{
+        EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkItemContentVc,
+        EcmascriptChunkItemVc, EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc,
+        EcmascriptChunkVc, EcmascriptExports, EcmascriptExportsVc,
+    },
+    utils::{stringify_module_id, stringify_str},
+    EsmAssetReferenceVc,
+};
+
+#[turbo_tasks::value]
+#[derive(Clone)]
+pub struct WebAssemblyModuleAsset {
+    pub source: AssetVc,
+    pub context: AssetContextVc,
+}
+
+#[turbo_tasks::value_impl]
+impl WebAssemblyModuleAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(source: AssetVc, context: AssetContextVc) -> Self {
+        Self::cell(WebAssemblyModuleAsset { source, context })
+    }
+
+    /// The imports and exports of the binary.
+    #[turbo_tasks::function]
+    pub async fn interface(self) -> Result<ModuleInterfaceVc> {
+        let this = self.await?;
+        let path = this.source.path().to_string().await?;
+        let bytes = wasm_bytes(this.source).await?;
+        Ok(ModuleInterface::parse(&bytes)
+            .with_context(|| format!("failed to parse WebAssembly module {path}"))?
+            .cell())
+    }
+
+    #[turbo_tasks::function]
+    async fn wasm_asset(self, context: ChunkingContextVc) -> Result<WebAssemblyAssetVc> {
+        Ok(WebAssemblyAssetVc::cell(WebAssemblyAsset {
+            context,
+            source: self.await?.source,
+        }))
+    }
+}
+
+async fn wasm_bytes(source: AssetVc) -> Result<Vec<u8>> {
+    if let AssetContent::File(file) = &*source.content().await? {
+        if let FileContent::Content(file) = &*file.await? {
+            return Ok(file.content().to_vec());
+        }
+    }
+    Err(anyhow!("WebAssembly module not found"))
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for WebAssemblyModuleAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.source.path()
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        self.source.content()
+    }
+
+    /// References the modules the binary imports from, in the order of
+    /// [ModuleInterface::import_modules].
+    #[turbo_tasks::function]
+    async fn references(self_vc: WebAssemblyModuleAssetVc) -> Result<AssetReferencesVc> {
+        let origin = self_vc.as_resolve_origin();
+        Ok(AssetReferencesVc::cell(
+            self_vc
+                .interface()
+                .await?
+                .import_modules
+                .iter()
+                .map(|module| {
+                    EsmAssetReferenceVc::new(
+                        origin,
+                        RequestVc::parse_string(module.clone()),
+                        Value::new(ImportAnnotations::default()),
+                        false,
+                    )
+                    .into()
+                })
+                .collect(),
+        ))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ResolveOrigin for WebAssemblyModuleAsset {
+    #[turbo_tasks::function]
+    fn origin_path(&self) -> FileSystemPathVc {
+        self.source.path()
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> AssetContextVc {
+        self.context
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAsset for WebAssemblyModuleAsset {
+    #[turbo_tasks::function]
+    fn as_chunk(self_vc: WebAssemblyModuleAssetVc, context: ChunkingContextVc) -> ChunkVc {
+        EcmascriptChunkVc::new(context, self_vc.as_ecmascript_chunk_placeable()).into()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkPlaceable for WebAssemblyModuleAsset {
+    #[turbo_tasks::function]
+    fn as_chunk_item(
+        self_vc: WebAssemblyModuleAssetVc,
+        context: ChunkingContextVc,
+    ) -> EcmascriptChunkItemVc {
+        ModuleChunkItemVc::cell(ModuleChunkItem {
+            module: self_vc,
+            context,
+            wasm_asset: self_vc.wasm_asset(context),
+        })
+        .into()
+    }
+
+    #[turbo_tasks::function]
+    fn get_exports(&self) -> EcmascriptExportsVc {
+        EcmascriptExports::Value.into()
+    }
+}
+
+/// The binary of a WebAssembly module, emitted with a content hashed name.
+#[turbo_tasks::value]
+struct WebAssemblyAsset {
+    context: ChunkingContextVc,
+    source: AssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for WebAssemblyAsset {
+    #[turbo_tasks::function]
+    async fn path(&self) -> Result<FileSystemPathVc> {
+        let content_hash = turbo_tasks_hash::hash_md4(&wasm_bytes(self.source).await?);
+        let content_hash_b16 = turbo_tasks_hash::encode_base16(&content_hash);
+        Ok(self.context.asset_path(&content_hash_b16, "wasm"))
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        self.source.content()
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+#[turbo_tasks::value]
+struct ModuleChunkItem {
+    module: WebAssemblyModuleAssetVc,
+    context: ChunkingContextVc,
+    wasm_asset: WebAssemblyAssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ModuleChunkItemVc {
+    /// Whether the module is compiled synchronously from the embedded binary,
+    /// instead of being instantiated from the emitted binary.
+    #[turbo_tasks::function]
+    async fn is_sync(self) -> Result<BoolVc> {
+        Ok(self
+            .await?
+            .module
+            .await?
+            .context
+            .environment()
+            .node_externals())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for ModuleChunkItem {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "{} (wasm)",
+            self.module.await?.source.path().to_string().await?
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkItem for ModuleChunkItem {
+    #[turbo_tasks::function]
+    async fn references(self_vc: ModuleChunkItemVc) -> Result<AssetReferencesVc> {
+        let this = self_vc.await?;
+        let mut references = this.module.references().await?.clone_value();
+        if !*self_vc.is_sync().await? {
+            references.push(
+                SingleAssetReferenceVc::new(
+                    this.wasm_asset.into(),
+                    StringVc::cell(format!("wasm {}", this.wasm_asset.path().await?)),
+                )
+                .into(),
+            );
+        }
+        Ok(AssetReferencesVc::cell(references))
+    }
+
+    #[turbo_tasks::function]
+    fn asset(&self) -> AssetOptionVc {
+        AssetOptionVc::cell(Some(self.module.into()))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkItem for ModuleChunkItem {
+    #[turbo_tasks::function]
+    fn chunking_context(&self) -> ChunkingContextVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    async fn content(self_vc: ModuleChunkItemVc) -> Result<EcmascriptChunkItemContentVc> {
+        let this = self_vc.await?;
+        let interface = this.module.interface().await?;
+        let references = this.module.references().await?;
+
+        let mut code = "const imports = {\n".to_string();
+        for (module, reference) in interface.import_modules.iter().zip(references.iter()) {
+            let import = match imported_module(*reference).await? {
+                Some(placeable) => format!(
+                    "__turbopack_import__({})",
+                    stringify_module_id(&*placeable.as_chunk_item(this.context).id().await?)
+                ),
+                None => "{}".to_string(),
+            };
+            writeln!(code, "  {}: {import},", stringify_str(module))?;
+        }
+        code += "};\n";
+
+        if *self_vc.is_sync().await? {
+            let bytes = wasm_bytes(this.module.await?.source).await?;
+            writeln!(
+                code,
+                "const wasmModule = new WebAssembly.Module(Buffer.from({}, \
+                 \"base64\"));\n__turbopack_export_value__(new WebAssembly.Instance(wasmModule, \
+                 imports).exports);",
+                stringify_str(&base64::encode(bytes))
+            )?;
+        } else {
+            let url = format!("/{}", &*this.wasm_asset.path().await?);
+            writeln!(
+                code,
+                "const wasmExports = {{}};\n\
+                 const ready = (async () => {{\n  \
+                   const response = fetch({});\n  \
+                   const {{ instance }} = typeof WebAssembly.instantiateStreaming === \
+                 \"function\"\n    \
+                   ? await WebAssembly.instantiateStreaming(response, imports)\n    \
+                   : await WebAssembly.instantiate(await (await response).arrayBuffer(), \
+                 imports);\n  \
+                   return Object.assign(wasmExports, instance.exports);\n\
+                 }})();",
+                stringify_str(&url)
+            )?;
+            code += "__turbopack_esm__({\n";
+            for name in interface.exports.iter().filter(|name| *name != "default") {
+                let name = stringify_str(name);
+                writeln!(code, "  {name}: () => wasmExports[{name}],")?;
+            }
+            code += "  default: () => ready,\n});\n";
+        }
+
+        Ok(EcmascriptChunkItemContent {
+            inner_code: code,
+            ..Default::default()
+        }
+        .into())
+    }
+}
+
+/// The module a reference of [WebAssemblyModuleAsset::references] resolves
+/// to.
+async fn imported_module(
+    reference: AssetReferenceVc,
+) -> Result<Option<EcmascriptChunkPlaceableVc>> {
+    for asset in reference.resolve_reference().primary_assets().await?.iter() {
+        if let Some(placeable) = EcmascriptChunkPlaceableVc::resolve_from(asset).await? {
+            return Ok(Some(placeable));
+        }
+    }
+    Ok(None)
+}
+
+pub fn register() {
+    turbo_tasks::register();
+    turbo_tasks_fs::register();
+    turbopack_core::register();
+    turbopack_ecmascript::register();
+    include!(concat!(env!("OUT_DIR"), "/register.rs"));
+}
@@ -0,0 +1,865 @@
+//! A one-shot build of a project, for frameworks and command line tools which
+//! embed turbopack. The options and results are plain values, so callers
+//! don't need to set up turbo-tasks or deal with Vcs.
+
+use std::{
+    collections::{HashMap, HashSet},
+    env::current_dir,
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{trace::TraceRawVcs, CompletionsVc, TryJoinIterExt, TurboTasks, Value};
+use turbo_tasks_fs::{
+    DiskFileSystemVc, FileContent, FileJsonContent, FileSystemPathVc, FileSystemVc,
+};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack_core::{
+    asset::{AssetContent, AssetVc, AssetsVc},
+    chunk::{
+        content_hash::{ContentHashedAssetVc, OutputFilesVc, OutputManifestAssetVc},
+        resource_hints::EntryChunkGroupsVc,
+        size_report::{SizeReportAssetVc, SizeReportFormat},
+        ChunkFormat, ChunkGroupVc, ChunkVc, ChunkableAssetVc, ChunkingContextVc, MinifyOptions,
+        ModuleIdStrategy, PublicPath,
+    },
+    context::AssetContextVc,
+    environment::EcmascriptVersion,
+    issue::{IssueSeverity, IssueVc, PlainIssueReadRef},
+    reference::{all_assets, graph::ModuleGraphVc},
+    resolve::{
+        duplicates::{find_duplicate_packages, report_duplicate_packages},
+        externals::{ExternalType, Externals, ExternalsMatcher, ExternalsVc},
+    },
+    source_asset::SourceAssetVc,
+    source_map::SourceMapEmission,
+};
+use turbopack_ecmascript::{
+    chunk::{
+        entries::{EcmascriptEntries, EcmascriptEntry, SharedChunkOptions},
+        library::{
+            preserved_modules, EcmascriptLibraryAsset, EcmascriptLibraryAssetVc,
+            EcmascriptLibraryOptions, LibraryFormat,
+        },
+        module_ids::{compute_hashed_module_ids, compute_numeric_module_ids},
+        EcmascriptChunkPlaceableVc, EcmascriptChunkPlaceablesVc,
+    },
+    concatenation::compute_concatenated_modules,
+    used_exports::compute_used_exports,
+    EcmascriptModuleAssetVc,
+};
+
+use crate::{
+    content_hashed_output_files, emit_asset, html::HtmlEntryAsset, preset::Preset, register,
+    resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+
+/// The file in the output directory which maps the paths of the chunks to
+/// the content hashed files they were emitted to.
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// The file in the output directory the size report is written to, see
+/// [BuildOptions::size_report].
+pub const SIZE_REPORT_NAME: &str = "size-report.html";
+
+/// The page in the output directory loading the entries, see
+/// [BuildOptions::html].
+pub const HTML_NAME: &str = "index.html";
+
+/// The options of a [build].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BuildOptions {
+    /// The directory of the project, which entries are relative to.
+    /// Relative paths are relative to the current directory.
+    pub project_dir: PathBuf,
+    /// The directory the output is written to. Relative paths are relative
+    /// to the project directory.
+    pub output_dir: PathBuf,
+    /// The preset the environment, the transforms and the chunking are taken
+    /// from.
+    pub preset: Preset,
+    /// The browsers the output supports, for presets running in browsers.
+    pub browserslist_query: String,
+    /// Downlevels the syntax of the output to this ES version, e. g. `es5`,
+    /// in addition to the runtimes of the preset. Helpers are imported from
+    /// `@swc/helpers` and generators use `regenerator-runtime`, which have to
+    /// be installed in the project.
+    pub es_version: Option<EcmascriptVersion>,
+    /// The URL prefix the output is served from, e. g. `/assets/` or
+    /// `https://cdn.example.com/`. With `auto`, the prefix is derived at
+    /// runtime from the URL of the script containing the runtime.
+    pub public_path: String,
+    /// How the chunks register themselves, `global` for `<script>` tags, or
+    /// `systemjs` and `amd` for hosts loading the entries with SystemJS or an
+    /// AMD loader. The [HTML_NAME] page requires the `global` format.
+    pub chunk_format: ChunkFormat,
+    /// Minifies the code of the chunks.
+    pub minify: bool,
+    /// Emits the runtime as a chunk of its own instead of including it in the
+    /// chunk of every entry, so the chunks of the entries stay the same when
+    /// only the runtime changes.
+    pub runtime_chunk: bool,
+    /// Writes a treemap of the sizes of the chunks by package and module to
+    /// the [SIZE_REPORT_NAME] file.
+    pub size_report: bool,
+    /// Extracts the modules loaded by multiple entries into chunks shared
+    /// between them, instead of duplicating them in the chunk of every entry.
+    pub shared_chunks: bool,
+    /// Writes the [HTML_NAME] page loading the chunks of all entries, see
+    /// [HtmlEntryAsset]. Libraries are loaded as ES modules, which requires
+    /// the `esm` [LibraryFormat], and the files of their preserved modules
+    /// are preloaded.
+    pub html: bool,
+    /// The template of the [HTML_NAME] page, relative to the project
+    /// directory. Files it references are copied to the output directory.
+    pub html_template: Option<String>,
+    /// Builds the entries as libraries for other projects to import, instead
+    /// of chunks for pages to load. The shared chunks, the chunk format and
+    /// the size report only apply to apps.
+    pub library: Option<LibraryOptions>,
+    /// Replaces the modules of packages which are included in multiple
+    /// versions with the modules of the highest version, see
+    /// [DuplicatePackagesVc::dedupe_to_highest]. Packages relying on APIs of
+    /// older versions may break. Packages included in multiple versions are
+    /// reported as warnings without it.
+    ///
+    /// [DuplicatePackagesVc::dedupe_to_highest]: turbopack_core::resolve::duplicates::DuplicatePackagesVc::dedupe_to_highest
+    pub dedupe_packages: bool,
+    /// Omits the modules of async chunks which are already part of the chunk
+    /// importing them, as that chunk is always loaded before.
+    pub dedupe_async_chunks: bool,
+    /// Omits the exports no module imports from the code of the modules, so
+    /// they can be removed as dead code. Modules imported with a namespace
+    /// import, `require` or `import()` keep all of their exports.
+    pub tree_shaking: bool,
+    /// Concatenates ESM modules which are only imported by a single other ESM
+    /// module into the scope of their importer, see
+    /// [compute_concatenated_modules]. Their top level bindings are renamed
+    /// to avoid collisions.
+    pub concatenate_modules: bool,
+    /// How the module ids in the chunks are derived, see [ModuleIds].
+    /// Defaults to the module ids of the preset, see [Preset::module_ids].
+    pub module_ids: Option<ModuleIds>,
+}
+
+/// The options of library builds, see [BuildOptions::library].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LibraryOptions {
+    /// The module format of the libraries.
+    pub format: LibraryFormat,
+    /// The global variable UMD libraries assign their exports to.
+    pub name: Option<String>,
+    /// The global variables UMD libraries read external packages from, keyed
+    /// by the package name. Defaults to the package name.
+    pub globals: IndexMap<String, String>,
+    /// Packages which are not bundled, but imported by the libraries, in
+    /// addition to the `peerDependencies` of the package.json of the project.
+    pub externals: Vec<String>,
+    /// Emits every module of the project to a file of its own, which imports
+    /// the files of the modules it imports, instead of bundling the modules
+    /// into one file per entry. Not supported for UMD libraries.
+    pub preserve_modules: bool,
+    /// The directory relative to the project directory whose modules are
+    /// emitted to the output directory with preserved modules, e. g. `src`.
+    /// Other modules of the project keep their path relative to the project
+    /// directory.
+    pub preserve_modules_root: Option<String>,
+}
+
+/// How the module ids of a [build] are derived, see
+/// [BuildOptions::module_ids].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModuleIds {
+    /// The readable names of the modules, e. g. `[project]/src/index.js`.
+    Named,
+    /// Short hashes of the readable names, which are lengthened where they
+    /// collide. They stay the same across builds as long as the modules don't
+    /// move.
+    Hashed,
+    /// Numbers in the order of the readable names of all modules of the
+    /// build, which are the shortest ids, but change when modules are added
+    /// or removed.
+    Numeric,
+}
+
+impl ModuleIds {
+    pub const ALL: [ModuleIds; 3] = [ModuleIds::Named, ModuleIds::Hashed, ModuleIds::Numeric];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ModuleIds::Named => "named",
+            ModuleIds::Hashed => "hashed",
+            ModuleIds::Numeric => "numeric",
+        }
+    }
+}
+
+impl Display for ModuleIds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ModuleIds {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match ModuleIds::ALL.into_iter().find(|ids| ids.name() == s) {
+            Some(ids) => Ok(ids),
+            None => bail!(
+                "unknown module ids \"{s}\", expected one of {}",
+                ModuleIds::ALL.map(|ids| ids.name()).join(", ")
+            ),
+        }
+    }
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            project_dir: PathBuf::from("."),
+            output_dir: PathBuf::from("dist"),
+            preset: Preset::ReactApp,
+            browserslist_query: "last 1 Chrome versions, last 1 Firefox versions, last 1 Safari \
+                                 versions, last 1 Edge versions"
+                .to_string(),
+            es_version: None,
+            public_path: "/".to_string(),
+            chunk_format: ChunkFormat::Global,
+            minify: false,
+            runtime_chunk: false,
+            size_report: false,
+            shared_chunks: true,
+            html: false,
+            html_template: None,
+            library: None,
+            dedupe_packages: false,
+            dedupe_async_chunks: false,
+            tree_shaking: false,
+            concatenate_modules: false,
+            module_ids: None,
+        }
+    }
+}
+
+/// An entry of a [build].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", from = "BuildEntryConfig")]
+pub struct BuildEntry {
+    /// The name of the entry, which the `[name]` placeholder of `filename` is
+    /// replaced with. Names have to be unique.
+    pub name: String,
+    /// The path of the entry module, relative to the project directory.
+    pub request: String,
+    /// A template for the path of the chunk of the entry relative to the
+    /// output directory, e. g. `js/[name].js`. Only applies to JavaScript
+    /// entries. The path is derived from `request` when this is `None`, and
+    /// libraries are named `[name]` with the extension of their format.
+    pub filename: Option<String>,
+}
+
+impl BuildEntry {
+    /// Creates an entry for `request`, which is named after the file stem of
+    /// the request, e. g. `index` for `src/index.js`.
+    pub fn new(request: String) -> Self {
+        let name = Path::new(&request)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| request.clone());
+        BuildEntry {
+            name,
+            request,
+            filename: None,
+        }
+    }
+}
+
+/// A [BuildEntry] in a config file, which can also be just the request.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BuildEntryConfig {
+    Request(String),
+    Entry {
+        name: Option<String>,
+        request: String,
+        filename: Option<String>,
+    },
+}
+
+impl From<BuildEntryConfig> for BuildEntry {
+    fn from(config: BuildEntryConfig) -> Self {
+        match config {
+            BuildEntryConfig::Request(request) => BuildEntry::new(request),
+            BuildEntryConfig::Entry {
+                name,
+                request,
+                filename,
+            } => {
+                let mut entry = BuildEntry::new(request);
+                if let Some(name) = name {
+                    entry.name = name;
+                }
+                entry.filename = filename;
+                entry
+            }
+        }
+    }
+}
+
+/// A file written to the output directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub struct EmittedAsset {
+    /// The path of the file relative to the output directory.
+    pub path: String,
+    /// The size of the file in bytes.
+    pub size: usize,
+}
+
+/// The outcome of a [build].
+#[derive(Clone)]
+pub struct BuildResult {
+    /// The files written to the output directory, ordered by their path.
+    pub assets: Vec<EmittedAsset>,
+    /// Maps the paths of the chunks to the content hashed files they were
+    /// emitted to, like the [MANIFEST_NAME] file.
+    pub manifest: IndexMap<String, String>,
+    /// The issues which occurred while building, most severe first.
+    pub issues: Vec<PlainIssueReadRef>,
+    /// The time the build took.
+    pub duration: Duration,
+}
+
+impl BuildResult {
+    /// Whether an issue at or above `level` occurred, e. g.
+    /// [IssueSeverity::Error] to fail on errors.
+    pub fn has_issues_at(&self, level: IssueSeverity) -> bool {
+        self.issues.iter().any(|issue| issue.severity <= level)
+    }
+}
+
+/// Builds `entries` and writes the chunks and assets they need to the output
+/// directory. Modules loaded by several entries are placed in shared chunks,
+/// unless [BuildOptions::shared_chunks] is disabled. Chunks are
+/// written to content hashed file names, together with the [MANIFEST_NAME]
+/// file mapping them. With [BuildOptions::library], the entries are built as
+/// libraries instead, see [EcmascriptLibraryAsset].
+///
+/// Issues don't fail the build, they are returned in the result, so the
+/// caller can decide which of them to report and which are fatal.
+pub async fn build(entries: Vec<BuildEntry>, options: BuildOptions) -> Result<BuildResult> {
+    let start = Instant::now();
+    let project_dir = current_dir()?.join(&options.project_dir);
+    let output_dir = utf8_path(&project_dir.join(&options.output_dir))?;
+    let project_dir = utf8_path(&project_dir)?;
+
+    register();
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let result = tt
+        .run_once(async move {
+            let project_fs: FileSystemVc =
+                DiskFileSystemVc::new("project".to_string(), project_dir).into();
+            let output_fs: FileSystemVc =
+                DiskFileSystemVc::new("output".to_string(), output_dir).into();
+            let project_root = project_fs.root();
+            let output_root = output_fs.root();
+
+            let preset = options.preset;
+            let environment = preset.environment(&options.browserslist_query);
+            let mut resolve_options_context = preset.resolve_options_context(environment);
+            if let Some(library) = &options.library {
+                resolve_options_context.externals =
+                    Some(library_externals(project_root, library).await?);
+            }
+            let asset_context = |resolve_options_context: ResolveOptionsContext| {
+                AssetContextVc::from(ModuleAssetContextVc::new(
+                    TransitionsByNameVc::cell(HashMap::new()),
+                    environment,
+                    preset
+                        .module_options_context(environment, options.es_version)
+                        .cell(),
+                    resolve_options_context.cell(),
+                ))
+            };
+            let mut context = asset_context(resolve_options_context.clone());
+            if options.dedupe_packages {
+                // The duplicate packages are only known after resolving the
+                // modules, which are then resolved again with the lower
+                // versions replaced
+                let modules = process_entries(&entries, context, project_root).await?;
+                let graph = ModuleGraphVc::from_roots(AssetsVc::cell(
+                    modules.iter().map(|&(_, module)| module).collect(),
+                ));
+                resolve_options_context.resolved_map =
+                    Some(find_duplicate_packages(graph).dedupe_to_highest());
+                context = asset_context(resolve_options_context);
+            }
+            let runtime_entries = preset.runtime_entries(context, project_root);
+            let modules = process_entries(&entries, context, project_root).await?;
+            let mut chunking_context = preset.chunking_context(
+                project_root,
+                output_root,
+                output_root,
+                output_root.join("static"),
+            );
+            let public_path = options.public_path.parse::<PublicPath>()?;
+            chunking_context = chunking_context.public_path(public_path.clone());
+            if options.minify {
+                chunking_context = chunking_context.minify(MinifyOptions::default());
+            }
+            if options.runtime_chunk {
+                chunking_context = chunking_context.runtime_chunk();
+            }
+            if options.library.is_some() {
+                // The code of libraries is taken from chunks containing all
+                // modules, which are not emitted themselves
+                chunking_context = chunking_context
+                    .single_file_output()
+                    .source_map_emission(SourceMapEmission::Hidden);
+            } else {
+                if options.html && options.chunk_format != ChunkFormat::Global {
+                    bail!(
+                        "the {HTML_NAME} page loads chunks with <script> tags, which isn't \
+                         supported for the {} chunk format",
+                        options.chunk_format
+                    );
+                }
+                chunking_context = chunking_context.chunk_format(options.chunk_format);
+            }
+            if options.dedupe_async_chunks {
+                chunking_context = chunking_context.async_chunk_deduplication();
+            }
+            // Runtime entries are part of the module graph too
+            let mut placeables = runtime_entries.await?.clone_value();
+            for &(_, module) in modules.iter() {
+                if let Some(placeable) = EcmascriptChunkPlaceableVc::resolve_from(module).await? {
+                    placeables.push(placeable);
+                }
+            }
+            let placeables = EcmascriptChunkPlaceablesVc::cell(placeables);
+            if options.tree_shaking {
+                chunking_context = chunking_context.used_exports(compute_used_exports(placeables));
+            }
+            if options.concatenate_modules {
+                chunking_context =
+                    chunking_context.concatenated_modules(compute_concatenated_modules(placeables));
+            }
+            // The readable names of the modules don't depend on their ids, so
+            // they are taken from the chunking context without the ids
+            let module_id_strategy = match options.module_ids.unwrap_or_else(|| preset.module_ids())
+            {
+                ModuleIds::Named => ModuleIdStrategy::Named,
+                ModuleIds::Hashed => ModuleIdStrategy::Hashed(Some(compute_hashed_module_ids(
+                    chunking_context.clone().build(),
+                    placeables,
+                ))),
+                ModuleIds::Numeric => ModuleIdStrategy::Numeric(compute_numeric_module_ids(
+                    chunking_context.clone().build(),
+                    placeables,
+                )),
+            };
+            chunking_context = chunking_context.module_id_strategy(module_id_strategy);
+            let chunking_context = chunking_context.build();
+
+            let graph = ModuleGraphVc::from_roots(AssetsVc::cell(
+                modules.iter().map(|&(_, module)| module).collect(),
+            ));
+
+            if let Some(library) = &options.library {
+                let (libraries, chunks) = library_assets(
+                    &modules,
+                    library,
+                    chunking_context,
+                    project_root,
+                    output_root,
+                )
+                .await?;
+                let mut assets = libraries
+                    .iter()
+                    .map(|&library| library.into())
+                    .chain(chunks.iter().map(|chunk| chunk.as_asset()))
+                    .collect::<Vec<AssetVc>>();
+                if options.html {
+                    if library.format != LibraryFormat::Esm {
+                        bail!(
+                            "the {HTML_NAME} page loads libraries as ES modules, which isn't \
+                             supported for the {} library format",
+                            library.format
+                        );
+                    }
+                    assets.push(html_page(
+                        &options,
+                        project_root,
+                        output_root,
+                        context,
+                        chunking_context,
+                        chunks.into_iter().map(ChunkGroupVc::from_chunk).collect(),
+                        libraries,
+                        &public_path,
+                    ));
+                }
+                return emit(assets, output_root, graph).await;
+            }
+
+            // JavaScript entries are chunked together, so they can share chunks
+            let mut ecmascript_entries = Vec::new();
+            for &(entry, module) in modules.iter() {
+                if let Some(ecmascript) = EcmascriptModuleAssetVc::resolve_from(module).await? {
+                    ecmascript_entries.push(EcmascriptEntry {
+                        name: entry.name.clone(),
+                        module: ecmascript.into(),
+                        filename: entry.filename.clone(),
+                    });
+                }
+            }
+            let ecmascript_chunk_groups = EcmascriptEntries {
+                entries: ecmascript_entries,
+                shared_chunks: options.shared_chunks.then(SharedChunkOptions::default),
+            }
+            .cell()
+            .chunk_groups(chunking_context, Some(runtime_entries))
+            .await?;
+
+            let mut chunk_groups = IndexMap::new();
+            for (entry, module) in modules {
+                let chunk_group =
+                    if let Some(&chunk_group) = ecmascript_chunk_groups.get(&entry.name) {
+                        chunk_group
+                    } else if let Some(chunkable) = ChunkableAssetVc::resolve_from(module).await? {
+                        ChunkGroupVc::from_chunk(chunkable.as_chunk(chunking_context))
+                    } else {
+                        bail!(
+                            "entry {} is not chunkable, so it can't be built",
+                            entry.request
+                        );
+                    };
+                if chunk_groups
+                    .insert(entry.name.clone(), chunk_group)
+                    .is_some()
+                {
+                    bail!("there are multiple entries named {}", entry.name);
+                }
+            }
+
+            let mut assets = Vec::new();
+            for chunk_group in chunk_groups.values() {
+                assets.extend(
+                    chunk_group
+                        .chunks()
+                        .await?
+                        .iter()
+                        .map(|chunk| chunk.as_asset()),
+                );
+            }
+            if options.html {
+                assets.push(html_page(
+                    &options,
+                    project_root,
+                    output_root,
+                    context,
+                    chunking_context,
+                    chunk_groups.values().copied().collect(),
+                    Vec::new(),
+                    &public_path,
+                ));
+            }
+            if options.size_report {
+                assets.push(
+                    SizeReportAssetVc::new(
+                        output_root.join(SIZE_REPORT_NAME),
+                        output_root,
+                        EntryChunkGroupsVc::cell(chunk_groups),
+                        Value::new(SizeReportFormat::Html),
+                    )
+                    .into(),
+                );
+            }
+
+            emit(assets, output_root, graph).await
+        })
+        .await;
+    tt.stop_and_wait().await;
+    let (assets, manifest, issues) = result?;
+
+    Ok(BuildResult {
+        assets,
+        manifest,
+        issues,
+        duration: start.elapsed(),
+    })
+}
+
+/// The [HTML_NAME] page loading `chunk_groups` and `module_scripts`.
+#[allow(clippy::too_many_arguments)]
+fn html_page(
+    options: &BuildOptions,
+    project_root: FileSystemPathVc,
+    output_root: FileSystemPathVc,
+    context: AssetContextVc,
+    chunking_context: ChunkingContextVc,
+    chunk_groups: Vec<ChunkGroupVc>,
+    module_scripts: Vec<EcmascriptLibraryAssetVc>,
+    public_path: &PublicPath,
+) -> AssetVc {
+    let template = options
+        .html_template
+        .as_ref()
+        .map(|template| SourceAssetVc::new(project_root.join(template)).into());
+    HtmlEntryAsset {
+        path: output_root.join(HTML_NAME),
+        template,
+        context,
+        chunking_context,
+        chunk_groups,
+        legacy_chunk_groups: Vec::new(),
+        module_scripts,
+        // The page is in the output directory, so an automatic public path is
+        // the directory of the page
+        public_path: public_path.fixed_prefix().unwrap_or_default(),
+        content_hashes: true,
+    }
+    .cell()
+    .into()
+}
+
+/// Processes the modules of `entries` in `context`.
+async fn process_entries(
+    entries: &[BuildEntry],
+    context: AssetContextVc,
+    project_root: FileSystemPathVc,
+) -> Result<Vec<(&BuildEntry, AssetVc)>> {
+    entries
+        .iter()
+        .map(|entry| async move {
+            let source = SourceAssetVc::new(project_root.join(&entry.request));
+            Ok((entry, context.process(source.into()).resolve().await?))
+        })
+        .try_join()
+        .await
+}
+
+/// The packages libraries import instead of bundling them: the peer
+/// dependencies of the project and the configured externals, including
+/// their subpaths like `react/jsx-runtime`.
+async fn library_externals(
+    project_root: FileSystemPathVc,
+    library: &LibraryOptions,
+) -> Result<ExternalsVc> {
+    let mut packages: Vec<String> = match &*project_root.join("package.json").read_json().await? {
+        FileJsonContent::Content(package_json) => package_json["peerDependencies"]
+            .as_object()
+            .map(|dependencies| dependencies.keys().cloned().collect())
+            .unwrap_or_default(),
+        FileJsonContent::Unparseable | FileJsonContent::NotFound => Vec::new(),
+    };
+    packages.extend(library.externals.iter().cloned());
+    let mut externals = Externals::new();
+    for package in packages {
+        // Libraries import their externals, which the runtime loads like
+        // modules loaded with `require()`
+        externals.add(
+            ExternalsMatcher::Prefix(format!("{package}/")),
+            None,
+            Some(ExternalType::CommonJs),
+        );
+        externals.add(
+            ExternalsMatcher::Exact(package),
+            None,
+            Some(ExternalType::CommonJs),
+        );
+    }
+    Ok(externals.cell())
+}
+
+/// Creates the libraries of the JavaScript entries, and the chunks of the
+/// other entries, e. g. stylesheets.
+async fn library_assets(
+    modules: &[(&BuildEntry, AssetVc)],
+    library: &LibraryOptions,
+    chunking_context: ChunkingContextVc,
+    project_root: FileSystemPathVc,
+    output_root: FileSystemPathVc,
+) -> Result<(Vec<EcmascriptLibraryAssetVc>, Vec<ChunkVc>)> {
+    let library_options = EcmascriptLibraryOptions {
+        format: library.format,
+        name: library.name.clone(),
+        globals: library.globals.clone(),
+    }
+    .cell();
+    let mut chunks = Vec::new();
+    let mut entries: Vec<(&BuildEntry, EcmascriptChunkPlaceableVc)> = Vec::new();
+    for &(entry, module) in modules.iter() {
+        if let Some(ecmascript) = EcmascriptModuleAssetVc::resolve_from(module).await? {
+            entries.push((entry, ecmascript.into()));
+        } else if let Some(chunkable) = ChunkableAssetVc::resolve_from(module).await? {
+            chunks.push(chunkable.as_chunk(chunking_context));
+        } else {
+            bail!(
+                "entry {} is not chunkable, so it can't be built",
+                entry.request
+            );
+        }
+    }
+
+    let preserved = if library.preserve_modules {
+        let root = match &library.preserve_modules_root {
+            Some(root) => project_root.join(root),
+            None => project_root,
+        };
+        Some(preserved_modules(
+            chunking_context,
+            EcmascriptChunkPlaceablesVc::cell(entries.iter().map(|&(_, module)| module).collect()),
+            project_root,
+            root,
+            output_root,
+            library_options,
+        ))
+    } else {
+        None
+    };
+    let mut libraries = Vec::new();
+    for (entry, module) in entries {
+        let path = match preserved {
+            Some(preserved) => *preserved
+                .await?
+                .get(&module)
+                .with_context(|| format!("entry {} is not in the project", entry.request))?,
+            None => output_root.join(&match &entry.filename {
+                Some(filename) => filename.replace("[name]", &entry.name),
+                None => format!("{}{}", entry.name, library.format.extension()),
+            }),
+        };
+        libraries.push(
+            EcmascriptLibraryAsset {
+                path,
+                context: chunking_context,
+                entry: module,
+                options: library_options,
+                preserved_modules: preserved,
+            }
+            .cell(),
+        );
+    }
+    Ok((libraries, chunks))
+}
+
+/// Emits `assets` and returns the emitted files, the manifest and the issues
+/// of the build, including the duplicate packages of `graph`.
+async fn emit(
+    assets: Vec<AssetVc>,
+    output_root: FileSystemPathVc,
+    graph: ModuleGraphVc,
+) -> Result<(
+    Vec<EmittedAsset>,
+    IndexMap<String, String>,
+    Vec<PlainIssueReadRef>,
+)> {
+    let output = emit_build(AssetsVc::cell(assets), output_root, graph);
+    let output_value = output.strongly_consistent().await?;
+    let mut issues = IssueVc::peek_issues_with_path(output)
+        .await?
+        .strongly_consistent()
+        .await?
+        .get_plain_issues()
+        .await?;
+    issues.sort_by_key(|issue| issue.severity);
+    Ok((
+        output_value.assets.clone(),
+        output_value.manifest.clone(),
+        issues,
+    ))
+}
+
+fn utf8_path(path: &Path) -> Result<String> {
+    path.to_str()
+        .map(|path| path.to_string())
+        .with_context(|| format!("{} is not a valid UTF-8 path", path.display()))
+}
+
+#[turbo_tasks::value]
+struct BuildOutput {
+    assets: Vec<EmittedAsset>,
+    manifest: IndexMap<String, String>,
+}
+
+/// Emits the `entries` and all assets they reference which are in
+/// `output_dir`, and the manifest of the emitted chunks. The duplicate
+/// packages of `graph` are reported from here, so the issues are collected
+/// with the issues of the build.
+#[turbo_tasks::function]
+async fn emit_build(
+    entries: AssetsVc,
+    output_dir: FileSystemPathVc,
+    graph: ModuleGraphVc,
+) -> Result<BuildOutputVc> {
+    let dir = &*output_dir.await?;
+    let mut seen = HashSet::new();
+    let mut assets = Vec::new();
+    let mut manifest = IndexMap::new();
+    for entry in entries.await?.iter() {
+        for asset in all_assets(*entry).await?.iter() {
+            if !seen.insert(*asset) || !asset.path().await?.is_inside(dir) {
+                continue;
+            }
+            if ChunkVc::resolve_from(asset).await?.is_some() {
+                assets.push(ContentHashedAssetVc::new(*asset).into());
+            } else {
+                assets.push(*asset);
+            }
+        }
+        for (chunk, file) in content_hashed_output_files(*entry, output_dir)
+            .await?
+            .iter()
+        {
+            manifest.insert(chunk.clone(), file.clone());
+        }
+    }
+    assets.push(
+        OutputManifestAssetVc::new(
+            output_dir.join(MANIFEST_NAME),
+            OutputFilesVc::cell(manifest.clone()),
+        )
+        .into(),
+    );
+
+    let mut completions = Vec::new();
+    let mut emitted = Vec::new();
+    for asset in assets {
+        completions.push(emit_asset(asset));
+        // Assets without content are not written
+        if let Some(size) = content_size(asset).await? {
+            if let Some(path) = dir.get_path_to(&*asset.path().await?) {
+                emitted.push(EmittedAsset {
+                    path: path.to_string(),
+                    size,
+                });
+            }
+        }
+    }
+    completions.push(report_duplicate_packages(graph));
+    CompletionsVc::cell(completions).all().await?;
+    emitted.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(BuildOutput {
+        assets: emitted,
+        manifest,
+    }
+    .cell())
+}
+
+async fn content_size(asset: AssetVc) -> Result<Option<usize>> {
+    Ok(match &*asset.content().await? {
+        AssetContent::File(file) => match &*file.await? {
+            FileContent::Content(file) => Some(file.content().len()),
+            FileContent::NotFound => None,
+        },
+        AssetContent::Redirect { .. } => None,
+    })
+}
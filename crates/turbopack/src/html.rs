@@ -0,0 +1,642 @@
+//! An HTML page loading the chunks of entries, which is generated from a
+//! template.
+//!
+//! Images, icons and media referenced by the template with relative URLs are
+//! copied to the output with content hashed names, and referenced
+//! stylesheets are processed and chunked like any other stylesheet. The tags
+//! loading the chunks of the entries are injected into the template.
+//!
+//! ES module libraries are loaded with `<script type="module">`, and the
+//! files they and the chunks of the entries import are preloaded with
+//! `<link rel="modulepreload">`, see [module_preloads].
+
+use std::ops::Range;
+
+use anyhow::{anyhow, bail, Result};
+use indexmap::IndexSet;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContent, AssetContentVc, AssetVc},
+    chunk::{
+        content_hash::content_hash, ChunkGroupVc, ChunkReferenceVc, ChunkableAssetVc,
+        ChunkingContextVc,
+    },
+    context::AssetContextVc,
+    reference::{AssetReferencesVc, SingleAssetReferenceVc},
+    source_asset::SourceAssetVc,
+};
+use turbopack_ecmascript::chunk::library::{module_preloads, EcmascriptLibraryAssetVc};
+
+use crate::content_hashed_output_files;
+
+/// The template used when no template is passed.
+const DEFAULT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <meta charset="utf-8" />
+  </head>
+  <body></body>
+</html>
+"#;
+
+/// An HTML page loading the chunks of `chunk_groups`, generated from
+/// `template`.
+///
+/// Stylesheets are injected at the end of the `<head>`, scripts at the end of
+/// the `<body>`. When `legacy_chunk_groups` are passed, e. g. the chunks of
+/// the same entries compiled for older browsers, the scripts of `chunk_groups`
+/// are loaded as `type="module"` and the legacy ones as `nomodule`, so every
+/// browser only loads one of them.
+#[turbo_tasks::value(shared)]
+pub struct HtmlEntryAsset {
+    /// The path of the page, which should be in the output root of
+    /// `chunking_context`.
+    pub path: FileSystemPathVc,
+    /// The template of the page. A blank page is used when this is `None`.
+    pub template: Option<AssetVc>,
+    /// Processes the stylesheets the template references.
+    pub context: AssetContextVc,
+    /// Chunks the stylesheets and places the files the template references.
+    pub chunking_context: ChunkingContextVc,
+    pub chunk_groups: Vec<ChunkGroupVc>,
+    pub legacy_chunk_groups: Vec<ChunkGroupVc>,
+    /// Libraries in the ES module format which are loaded as module scripts.
+    /// All files they import, directly or through other files, are preloaded.
+    pub module_scripts: Vec<EcmascriptLibraryAssetVc>,
+    /// The URL prefix chunks and files are served from, e. g. `/`.
+    pub public_path: String,
+    /// Whether chunks are emitted to content hashed files, see
+    /// [emit_with_content_hashes](crate::emit_with_content_hashes). The page
+    /// loads the hashed files then, and passes their names to the runtime as
+    /// `TURBOPACK_CHUNK_FILES`.
+    pub content_hashes: bool,
+}
+
+/// A file referenced by the template.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+enum TemplateReferenceTarget {
+    /// Copied to the output, the URL is replaced with the URL of the copy.
+    File(AssetVc),
+    /// Chunked, the `<link>` tag is replaced with tags for the chunks.
+    Stylesheet(ChunkGroupVc),
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+struct TemplateReference {
+    start: usize,
+    end: usize,
+    target: TemplateReferenceTarget,
+}
+
+#[turbo_tasks::value(transparent)]
+struct TemplateReferences(Vec<TemplateReference>);
+
+#[turbo_tasks::value_impl]
+impl HtmlEntryAssetVc {
+    #[turbo_tasks::function]
+    async fn template_source(self) -> Result<StringVc> {
+        let this = self.await?;
+        let template = match this.template {
+            Some(template) => template,
+            None => return Ok(StringVc::cell(DEFAULT_TEMPLATE.to_string())),
+        };
+        let template_path = template.path().await?;
+        Ok(StringVc::cell(match &*template.content().await? {
+            AssetContent::File(file) => match &*file.await? {
+                FileContent::Content(file) => String::from_utf8(file.content().to_vec())
+                    .map_err(|_| anyhow!("template {} is not UTF-8", template_path.path))?,
+                FileContent::NotFound => {
+                    bail!("template {} doesn't exist", template_path.path)
+                }
+            },
+            AssetContent::Redirect { .. } => {
+                bail!("template {} is a redirect", template_path.path)
+            }
+        }))
+    }
+
+    /// The files referenced by the template which exist next to it.
+    #[turbo_tasks::function]
+    async fn template_references(self) -> Result<TemplateReferencesVc> {
+        let this = self.await?;
+        let template_dir = match this.template {
+            Some(template) => template.path().parent(),
+            None => return Ok(TemplateReferencesVc::cell(Vec::new())),
+        };
+        let source = self.template_source().await?;
+
+        let mut references = Vec::new();
+        for url in template_urls(&source) {
+            let path = template_dir.join(&url.path);
+            if !matches!(&*path.read().await?, FileContent::Content(_)) {
+                // Left as is, it might be served by other means
+                continue;
+            }
+            let source: AssetVc = SourceAssetVc::new(path).into();
+            let target = match url.kind {
+                TemplateUrlKind::File => TemplateReferenceTarget::File(
+                    HtmlFileAsset {
+                        context: this.chunking_context,
+                        source,
+                    }
+                    .cell()
+                    .into(),
+                ),
+                TemplateUrlKind::Stylesheet => {
+                    let module = this.context.process(source);
+                    match ChunkableAssetVc::resolve_from(module).await? {
+                        Some(chunkable) => TemplateReferenceTarget::Stylesheet(
+                            ChunkGroupVc::from_chunk(chunkable.as_chunk(this.chunking_context)),
+                        ),
+                        None => continue,
+                    }
+                }
+            };
+            references.push(TemplateReference {
+                start: url.range.start,
+                end: url.range.end,
+                target,
+            });
+        }
+        Ok(TemplateReferencesVc::cell(references))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for HtmlEntryAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn content(self_vc: HtmlEntryAssetVc) -> Result<AssetContentVc> {
+        let this = self_vc.await?;
+        let output_root = this.chunking_context.output_root();
+        let output_root_value = output_root.await?;
+        let files = if this.content_hashes {
+            Some(content_hashed_output_files(self_vc.into(), output_root).await?)
+        } else {
+            None
+        };
+        let public_path = if this.public_path.is_empty() || this.public_path.ends_with('/') {
+            this.public_path.clone()
+        } else {
+            format!("{}/", this.public_path)
+        };
+        let url = |path: &str| -> String {
+            let file = files
+                .as_ref()
+                .and_then(|files| files.get(path))
+                .map_or(path, |file| file.as_str());
+            format!("{public_path}{file}")
+        };
+
+        let source = self_vc.template_source().await?;
+        let mut html = String::with_capacity(source.len());
+        let mut position = 0;
+        for reference in self_vc.template_references().await?.iter() {
+            html.push_str(&source[position..reference.start]);
+            position = reference.end;
+            match reference.target {
+                TemplateReferenceTarget::File(asset) => {
+                    match output_root_value.get_path_to(&*asset.path().await?) {
+                        Some(path) => html.push_str(&escape_attribute(&url(path))),
+                        None => html.push_str(&source[reference.start..reference.end]),
+                    }
+                }
+                TemplateReferenceTarget::Stylesheet(chunk_group) => {
+                    for chunk in chunk_group.chunks().await?.iter() {
+                        let path = chunk.path().await?;
+                        if let Some(relative) = output_root_value.get_path_to(&path) {
+                            if path.extension() == Some("css") {
+                                html.push_str(&stylesheet_tag(&url(relative)));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        html.push_str(&source[position..]);
+
+        let mut styles = IndexSet::new();
+        let mut scripts = IndexSet::new();
+        if let Some(files) = &files {
+            // The runtime loads async chunks by their path, it needs to know
+            // which files they were emitted to
+            scripts.insert(format!(
+                "<script>self.TURBOPACK_CHUNK_FILES = {};</script>",
+                serde_json::to_string(&**files)?.replace("</", "<\\/")
+            ));
+        }
+        let legacy = !this.legacy_chunk_groups.is_empty();
+        let groups = this
+            .chunk_groups
+            .iter()
+            .map(|chunk_group| (chunk_group, false))
+            .chain(
+                this.legacy_chunk_groups
+                    .iter()
+                    .map(|chunk_group| (chunk_group, true)),
+            );
+        for (chunk_group, is_legacy) in groups {
+            let script_type = match (legacy, is_legacy) {
+                (false, _) => "",
+                (true, false) => " type=\"module\"",
+                (true, true) => " nomodule",
+            };
+            for chunk in chunk_group.chunks().await?.iter() {
+                let path = chunk.path().await?;
+                let relative = match output_root_value.get_path_to(&path) {
+                    Some(relative) => relative,
+                    None => continue,
+                };
+                match path.extension() {
+                    // The legacy chunks have the same styles
+                    Some("css") if !is_legacy => {
+                        styles.insert(stylesheet_tag(&url(relative)));
+                    }
+                    Some("js" | "mjs") => {
+                        scripts.insert(format!(
+                            "<script{} src=\"{}\"></script>",
+                            script_type,
+                            escape_attribute(&url(relative))
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        for &script in this.module_scripts.iter() {
+            let path = script.path().await?;
+            if let Some(relative) = output_root_value.get_path_to(&path) {
+                scripts.insert(format!(
+                    "<script type=\"module\" src=\"{}\"></script>",
+                    escape_attribute(&url(relative))
+                ));
+            }
+        }
+        let mut preloads = IndexSet::new();
+        let preloaded = module_preloads(this.chunk_groups.clone(), this.module_scripts.clone());
+        for library in preloaded.await?.iter() {
+            let path = library.path().await?;
+            if let Some(relative) = output_root_value.get_path_to(&path) {
+                preloads.insert(format!(
+                    "<link rel=\"modulepreload\" href=\"{}\" />",
+                    escape_attribute(&url(relative))
+                ));
+            }
+        }
+        preloads.extend(styles);
+
+        let html = inject(&html, "</head>", preloads);
+        let html = inject(&html, "</body>", scripts);
+        Ok(File::from(html).into())
+    }
+
+    #[turbo_tasks::function]
+    async fn references(self_vc: HtmlEntryAssetVc) -> Result<AssetReferencesVc> {
+        let this = self_vc.await?;
+        let mut references = Vec::new();
+        let mut chunk_groups = this
+            .chunk_groups
+            .iter()
+            .chain(this.legacy_chunk_groups.iter())
+            .copied()
+            .collect::<Vec<_>>();
+        for reference in self_vc.template_references().await?.iter() {
+            match reference.target {
+                TemplateReferenceTarget::File(asset) => {
+                    references.push(
+                        SingleAssetReferenceVc::new(
+                            asset,
+                            StringVc::cell("file referenced by the HTML template".to_string()),
+                        )
+                        .into(),
+                    );
+                }
+                TemplateReferenceTarget::Stylesheet(chunk_group) => {
+                    chunk_groups.push(chunk_group);
+                }
+            }
+        }
+        for chunk_group in chunk_groups {
+            for chunk in chunk_group.chunks().await?.iter() {
+                references.push(ChunkReferenceVc::new(*chunk).into());
+            }
+        }
+        for &script in this.module_scripts.iter() {
+            references.push(
+                SingleAssetReferenceVc::new(
+                    script.into(),
+                    StringVc::cell("module script of the HTML page".to_string()),
+                )
+                .into(),
+            );
+        }
+        Ok(AssetReferencesVc::cell(references))
+    }
+}
+
+/// A file referenced by the template, which is copied to the output with a
+/// content hashed name.
+#[turbo_tasks::value]
+struct HtmlFileAsset {
+    context: ChunkingContextVc,
+    source: AssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for HtmlFileAsset {
+    #[turbo_tasks::function]
+    async fn path(&self) -> Result<FileSystemPathVc> {
+        let hash = content_hash(self.source.content()).await?;
+        let extension = self
+            .source
+            .path()
+            .await?
+            .extension()
+            .unwrap_or("bin")
+            .to_string();
+        Ok(self.context.asset_path(&hash, &extension))
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        self.source.content()
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+fn stylesheet_tag(href: &str) -> String {
+    format!(
+        "<link rel=\"stylesheet\" href=\"{}\" />",
+        escape_attribute(href)
+    )
+}
+
+fn escape_attribute(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;")
+}
+
+/// Inserts `tags` before the last `closing_tag`, or at the end of `html` when
+/// the template has none.
+fn inject(html: &str, closing_tag: &str, tags: IndexSet<String>) -> String {
+    if tags.is_empty() {
+        return html.to_string();
+    }
+    // ASCII lowercasing keeps the byte offsets
+    let index = html
+        .to_ascii_lowercase()
+        .rfind(closing_tag)
+        .unwrap_or(html.len());
+    let mut injected = String::with_capacity(html.len());
+    injected.push_str(&html[..index]);
+    for tag in tags {
+        injected.push_str(&tag);
+        injected.push('\n');
+    }
+    injected.push_str(&html[index..]);
+    injected
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum TemplateUrlKind {
+    /// The URL is replaced with the URL of the copied file.
+    File,
+    /// The whole `<link>` tag is replaced with the tags of the chunks.
+    Stylesheet,
+}
+
+/// A relative URL in a template.
+#[derive(Debug, PartialEq, Eq)]
+struct TemplateUrl {
+    kind: TemplateUrlKind,
+    /// The URL without query and fragment.
+    path: String,
+    /// The bytes of the template which are replaced.
+    range: Range<usize>,
+}
+
+/// The attributes with URLs of files which are copied to the output.
+const FILE_ATTRIBUTES: [(&str, &str); 6] = [
+    ("img", "src"),
+    ("link", "href"),
+    ("source", "src"),
+    ("audio", "src"),
+    ("video", "src"),
+    ("video", "poster"),
+];
+
+/// Finds the relative URLs of files in `html`. This is a lenient scan of the
+/// tags rather than a full HTML parser, comments and the contents of
+/// `<script>` and `<style>` elements are skipped.
+fn template_urls(html: &str) -> Vec<TemplateUrl> {
+    let mut urls = Vec::new();
+    let mut position = 0;
+    while let Some(offset) = html[position..].find('<') {
+        let start = position + offset;
+        if html[start..].starts_with("<!--") {
+            position = html[start..]
+                .find("-->")
+                .map_or(html.len(), |end| start + end + 3);
+            continue;
+        }
+        let tag = match parse_tag(html, start) {
+            Some(tag) => tag,
+            None => {
+                position = start + 1;
+                continue;
+            }
+        };
+        position = tag.end;
+
+        if tag.name == "script" || tag.name == "style" {
+            let closing_tag = format!("</{}", tag.name);
+            position = html[position..]
+                .to_ascii_lowercase()
+                .find(&closing_tag)
+                .map_or(html.len(), |end| position + end);
+            continue;
+        }
+        let is_stylesheet = tag.name == "link"
+            && tag.attributes.iter().any(|attribute| {
+                attribute.name == "rel"
+                    && attribute
+                        .value
+                        .split_ascii_whitespace()
+                        .any(|rel| rel.eq_ignore_ascii_case("stylesheet"))
+            });
+        for attribute in tag.attributes.iter() {
+            if !FILE_ATTRIBUTES.contains(&(tag.name.as_str(), attribute.name.as_str())) {
+                continue;
+            }
+            let path = match relative_path(attribute.value) {
+                Some(path) => path,
+                None => continue,
+            };
+            urls.push(if is_stylesheet {
+                TemplateUrl {
+                    kind: TemplateUrlKind::Stylesheet,
+                    path,
+                    range: start..tag.end,
+                }
+            } else {
+                TemplateUrl {
+                    kind: TemplateUrlKind::File,
+                    path,
+                    range: attribute.range.clone(),
+                }
+            });
+        }
+    }
+    urls
+}
+
+/// Returns the path of a URL relative to the template, or `None` for absolute
+/// URLs, root-relative URLs and URLs with a scheme.
+fn relative_path(url: &str) -> Option<String> {
+    let url = url.trim();
+    let path = &url[..url.find(['?', '#']).unwrap_or(url.len())];
+    if path.is_empty() || path.starts_with('/') {
+        return None;
+    }
+    if let Some(colon) = path.find(':') {
+        if !path[..colon].contains('/') {
+            return None;
+        }
+    }
+    Some(path.strip_prefix("./").unwrap_or(path).to_string())
+}
+
+struct Tag<'a> {
+    /// The lowercase name of the tag.
+    name: String,
+    attributes: Vec<Attribute<'a>>,
+    /// The byte after the tag.
+    end: usize,
+}
+
+struct Attribute<'a> {
+    /// The lowercase name of the attribute.
+    name: String,
+    value: &'a str,
+    /// The bytes of the value, without quotes.
+    range: Range<usize>,
+}
+
+/// Parses the opening tag starting at `start`, or returns `None` when there is
+/// none.
+fn parse_tag(html: &str, start: usize) -> Option<Tag<'_>> {
+    let bytes = html.as_bytes();
+    let mut i = start + 1;
+    if !bytes.get(i)?.is_ascii_alphabetic() {
+        return None;
+    }
+    while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'-') {
+        i += 1;
+    }
+    let name = html[start + 1..i].to_ascii_lowercase();
+
+    let mut attributes = Vec::new();
+    loop {
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b'/') {
+            i += 1;
+        }
+        match bytes.get(i)? {
+            b'>' => {
+                return Some(Tag {
+                    name,
+                    attributes,
+                    end: i + 1,
+                })
+            }
+            b'<' => return None,
+            _ => {}
+        }
+        let name_start = i;
+        while i < bytes.len()
+            && !bytes[i].is_ascii_whitespace()
+            && !matches!(bytes[i], b'=' | b'>' | b'/')
+        {
+            i += 1;
+        }
+        let attribute_name = html[name_start..i].to_ascii_lowercase();
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if bytes.get(i) != Some(&b'=') {
+            attributes.push(Attribute {
+                name: attribute_name,
+                value: "",
+                range: i..i,
+            });
+            continue;
+        }
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        let range = match *bytes.get(i)? {
+            quote @ (b'"' | b'\'') => {
+                let value_start = i + 1;
+                let value_end = value_start + html[value_start..].find(quote as char)?;
+                i = value_end + 1;
+                value_start..value_end
+            }
+            _ => {
+                let value_start = i;
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>' {
+                    i += 1;
+                }
+                value_start..i
+            }
+        };
+        attributes.push(Attribute {
+            name: attribute_name,
+            value: &html[range.clone()],
+            range,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{template_urls, TemplateUrlKind};
+
+    #[test]
+    fn urls_in_templates() {
+        let html = r#"<html><head>
+<link rel="icon" href="./favicon.ico">
+<LINK REL=stylesheet HREF=styles/main.css>
+<link rel="stylesheet" href="https://example.com/font.css">
+<script>const img = '<img src="script.png">';</script>
+</head><body>
+<!-- <img src="comment.png"> -->
+<img alt='logo' src='logo.svg?v=2' />
+<img src="/public.png"><img src="data:image/png;base64,">
+</body></html>"#;
+        let urls = template_urls(html);
+        assert_eq!(
+            urls.iter()
+                .map(|url| (&url.kind, url.path.as_str(), &html[url.range.clone()]))
+                .collect::<Vec<_>>(),
+            vec![
+                (&TemplateUrlKind::File, "favicon.ico", "./favicon.ico"),
+                (
+                    &TemplateUrlKind::Stylesheet,
+                    "styles/main.css",
+                    "<LINK REL=stylesheet HREF=styles/main.css>"
+                ),
+                (&TemplateUrlKind::File, "logo.svg", "logo.svg?v=2"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,428 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{primitives::StringVc, trace::TraceRawVcs};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::environment::{EcmascriptVersion, EnvironmentVc};
+use turbopack_ecmascript::{CompileTimeDefinesVc, JsxRuntime};
+
+use crate::{
+    module_options::{ModuleOptionsContext, ModuleOptionsContextVc, ModuleRuleCondition},
+    preset::Preset,
+    resolve_options_context::{ResolveOptionsContext, ResolveOptionsContextVc},
+};
+
+/// A single transform or resolve option which can be set by a layer of
+/// [LayeredOptions].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub enum LayeredOption {
+    EnableEmotion(bool),
+    EnableReactRefresh(bool),
+    EnableStyledComponents(bool),
+    EnableStyledJsx(bool),
+    EnableTypescriptTransform(bool),
+    EnableWebpackRuntimeShims(bool),
+    JsxRuntime(JsxRuntime),
+    JsxImportSource(Option<String>),
+    PresetEnvVersions(Option<EnvironmentVc>),
+    EsVersion(Option<EcmascriptVersion>),
+    CompileTimeDefines(Option<CompileTimeDefinesVc>),
+    StaticAssetInlineLimit(usize),
+    EmulateEnvironment(Option<EnvironmentVc>),
+    EnableTypescript(bool),
+    EnableReact(bool),
+    EnableNodeModules(bool),
+    EnableNodeExternals(bool),
+    EnableNodeNativeModules(bool),
+    Browser(bool),
+    Module(bool),
+    CustomConditions(Vec<String>),
+    CustomExtensions(Option<Vec<String>>),
+}
+
+impl LayeredOption {
+    /// The name of the option, which is the name of the field it sets in
+    /// [ModuleOptionsContext] or [ResolveOptionsContext].
+    pub fn name(&self) -> &'static str {
+        match self {
+            LayeredOption::EnableEmotion(_) => "enable_emotion",
+            LayeredOption::EnableReactRefresh(_) => "enable_react_refresh",
+            LayeredOption::EnableStyledComponents(_) => "enable_styled_components",
+            LayeredOption::EnableStyledJsx(_) => "enable_styled_jsx",
+            LayeredOption::EnableTypescriptTransform(_) => "enable_typescript_transform",
+            LayeredOption::EnableWebpackRuntimeShims(_) => "enable_webpack_runtime_shims",
+            LayeredOption::JsxRuntime(_) => "jsx_runtime",
+            LayeredOption::JsxImportSource(_) => "jsx_import_source",
+            LayeredOption::PresetEnvVersions(_) => "preset_env_versions",
+            LayeredOption::EsVersion(_) => "es_version",
+            LayeredOption::CompileTimeDefines(_) => "compile_time_defines",
+            LayeredOption::StaticAssetInlineLimit(_) => "static_asset_inline_limit",
+            LayeredOption::EmulateEnvironment(_) => "emulate_environment",
+            LayeredOption::EnableTypescript(_) => "enable_typescript",
+            LayeredOption::EnableReact(_) => "enable_react",
+            LayeredOption::EnableNodeModules(_) => "enable_node_modules",
+            LayeredOption::EnableNodeExternals(_) => "enable_node_externals",
+            LayeredOption::EnableNodeNativeModules(_) => "enable_node_native_modules",
+            LayeredOption::Browser(_) => "browser",
+            LayeredOption::Module(_) => "module",
+            LayeredOption::CustomConditions(_) => "custom_conditions",
+            LayeredOption::CustomExtensions(_) => "custom_extensions",
+        }
+    }
+
+    fn describe_value(&self) -> String {
+        match self {
+            LayeredOption::EnableEmotion(value)
+            | LayeredOption::EnableReactRefresh(value)
+            | LayeredOption::EnableStyledComponents(value)
+            | LayeredOption::EnableStyledJsx(value)
+            | LayeredOption::EnableTypescriptTransform(value)
+            | LayeredOption::EnableWebpackRuntimeShims(value)
+            | LayeredOption::EnableTypescript(value)
+            | LayeredOption::EnableReact(value)
+            | LayeredOption::EnableNodeModules(value)
+            | LayeredOption::EnableNodeExternals(value)
+            | LayeredOption::EnableNodeNativeModules(value)
+            | LayeredOption::Browser(value)
+            | LayeredOption::Module(value) => value.to_string(),
+            LayeredOption::StaticAssetInlineLimit(limit) => limit.to_string(),
+            LayeredOption::JsxRuntime(runtime) => format!("{runtime:?}"),
+            LayeredOption::JsxImportSource(import_source) => match import_source {
+                Some(import_source) => import_source.clone(),
+                None => "none".to_string(),
+            },
+            LayeredOption::PresetEnvVersions(environment)
+            | LayeredOption::EmulateEnvironment(environment) => match environment {
+                Some(_) => "an environment".to_string(),
+                None => "none".to_string(),
+            },
+            LayeredOption::EsVersion(es_version) => match es_version {
+                Some(es_version) => es_version.to_string(),
+                None => "none".to_string(),
+            },
+            LayeredOption::CompileTimeDefines(defines) => match defines {
+                Some(_) => "defines".to_string(),
+                None => "none".to_string(),
+            },
+            LayeredOption::CustomConditions(conditions) => format!("[{}]", conditions.join(", ")),
+            LayeredOption::CustomExtensions(extensions) => match extensions {
+                Some(extensions) => format!("[{}]", extensions.join(", ")),
+                None => "none".to_string(),
+            },
+        }
+    }
+
+    fn apply(&self, module: &mut ModuleOptionsContext, resolve: &mut ResolveOptionsContext) {
+        match self {
+            LayeredOption::EnableEmotion(value) => module.enable_emotion = *value,
+            LayeredOption::EnableReactRefresh(value) => module.enable_react_refresh = *value,
+            LayeredOption::EnableStyledComponents(value) => {
+                module.enable_styled_components = *value
+            }
+            LayeredOption::EnableStyledJsx(value) => module.enable_styled_jsx = *value,
+            LayeredOption::EnableTypescriptTransform(value) => {
+                module.enable_typescript_transform = *value
+            }
+            LayeredOption::EnableWebpackRuntimeShims(value) => {
+                module.enable_webpack_runtime_shims = *value
+            }
+            LayeredOption::JsxRuntime(runtime) => module.jsx_runtime = *runtime,
+            LayeredOption::JsxImportSource(import_source) => {
+                module.jsx_import_source = import_source.clone()
+            }
+            LayeredOption::PresetEnvVersions(environment) => {
+                module.preset_env_versions = *environment
+            }
+            LayeredOption::EsVersion(es_version) => module.es_version = *es_version,
+            LayeredOption::CompileTimeDefines(defines) => module.compile_time_defines = *defines,
+            LayeredOption::StaticAssetInlineLimit(limit) => {
+                module.static_asset_inline_limit = *limit
+            }
+            LayeredOption::EmulateEnvironment(environment) => {
+                resolve.emulate_environment = *environment
+            }
+            LayeredOption::EnableTypescript(value) => resolve.enable_typescript = *value,
+            LayeredOption::EnableReact(value) => resolve.enable_react = *value,
+            LayeredOption::EnableNodeModules(value) => resolve.enable_node_modules = *value,
+            LayeredOption::EnableNodeExternals(value) => resolve.enable_node_externals = *value,
+            LayeredOption::EnableNodeNativeModules(value) => {
+                resolve.enable_node_native_modules = *value
+            }
+            LayeredOption::Browser(value) => resolve.browser = *value,
+            LayeredOption::Module(value) => resolve.module = *value,
+            LayeredOption::CustomConditions(conditions) => {
+                resolve.custom_conditions = conditions.clone()
+            }
+            LayeredOption::CustomExtensions(extensions) => {
+                resolve.custom_extensions = extensions.clone()
+            }
+        }
+    }
+
+    /// All layered options with their values in the given contexts.
+    fn all_from(module: &ModuleOptionsContext, resolve: &ResolveOptionsContext) -> Vec<Self> {
+        vec![
+            LayeredOption::EnableEmotion(module.enable_emotion),
+            LayeredOption::EnableReactRefresh(module.enable_react_refresh),
+            LayeredOption::EnableStyledComponents(module.enable_styled_components),
+            LayeredOption::EnableStyledJsx(module.enable_styled_jsx),
+            LayeredOption::EnableTypescriptTransform(module.enable_typescript_transform),
+            LayeredOption::EnableWebpackRuntimeShims(module.enable_webpack_runtime_shims),
+            LayeredOption::JsxRuntime(module.jsx_runtime),
+            LayeredOption::JsxImportSource(module.jsx_import_source.clone()),
+            LayeredOption::PresetEnvVersions(module.preset_env_versions),
+            LayeredOption::EsVersion(module.es_version),
+            LayeredOption::CompileTimeDefines(module.compile_time_defines),
+            LayeredOption::StaticAssetInlineLimit(module.static_asset_inline_limit),
+            LayeredOption::EmulateEnvironment(resolve.emulate_environment),
+            LayeredOption::EnableTypescript(resolve.enable_typescript),
+            LayeredOption::EnableReact(resolve.enable_react),
+            LayeredOption::EnableNodeModules(resolve.enable_node_modules),
+            LayeredOption::EnableNodeExternals(resolve.enable_node_externals),
+            LayeredOption::EnableNodeNativeModules(resolve.enable_node_native_modules),
+            LayeredOption::Browser(resolve.browser),
+            LayeredOption::Module(resolve.module),
+            LayeredOption::CustomConditions(resolve.custom_conditions.clone()),
+            LayeredOption::CustomExtensions(resolve.custom_extensions.clone()),
+        ]
+    }
+}
+
+/// A named set of options, e. g. the options of a preset or the options of a
+/// rule for some files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub struct OptionsLayer {
+    name: String,
+    condition: Option<ModuleRuleCondition>,
+    options: Vec<LayeredOption>,
+}
+
+impl OptionsLayer {
+    /// A layer setting `options` for all assets.
+    pub fn new(name: impl Into<String>, options: Vec<LayeredOption>) -> Self {
+        OptionsLayer {
+            name: name.into(),
+            condition: None,
+            options,
+        }
+    }
+
+    /// A layer setting the options of the given contexts which differ from
+    /// their defaults.
+    pub fn from_contexts(
+        name: impl Into<String>,
+        module: &ModuleOptionsContext,
+        resolve: &ResolveOptionsContext,
+    ) -> Self {
+        let defaults = LayeredOption::all_from(&Default::default(), &Default::default());
+        let options = LayeredOption::all_from(module, resolve)
+            .into_iter()
+            .zip(defaults)
+            .filter(|(option, default)| option != default)
+            .map(|(option, _)| option)
+            .collect();
+        OptionsLayer::new(name, options)
+    }
+
+    /// Restricts the layer to assets matching `condition`, e. g. a
+    /// [ModuleRuleCondition::ResourcePathGlob].
+    pub fn when(mut self, condition: ModuleRuleCondition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+}
+
+/// Options composed of layers, which are applied in order on top of the
+/// defaults, usually defaults → preset → environment → per-glob rules. The
+/// effective options of an asset can be explained, reporting which layer
+/// set each option.
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Default)]
+pub struct LayeredOptions {
+    layers: Vec<OptionsLayer>,
+}
+
+impl LayeredOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn layer(mut self, layer: OptionsLayer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// Adds the options of `preset` in `environment` as a layer.
+    pub fn preset(self, preset: Preset, environment: EnvironmentVc) -> Self {
+        self.layer(OptionsLayer::from_contexts(
+            format!("preset {preset}"),
+            &preset.module_options_context(environment, None),
+            &preset.resolve_options_context(environment),
+        ))
+    }
+
+    /// Adds a layer of options for all assets compiled for `environment`,
+    /// e. g. the server or the browser.
+    pub fn environment(
+        self,
+        name: &str,
+        environment: EnvironmentVc,
+        options: Vec<LayeredOption>,
+    ) -> Self {
+        let mut layer_options = vec![
+            LayeredOption::PresetEnvVersions(Some(environment)),
+            LayeredOption::EmulateEnvironment(Some(environment)),
+        ];
+        layer_options.extend(options);
+        self.layer(OptionsLayer::new(
+            format!("environment {name}"),
+            layer_options,
+        ))
+    }
+
+    /// Adds a layer of options for assets matching `condition`.
+    pub fn rule(
+        self,
+        name: &str,
+        condition: ModuleRuleCondition,
+        options: Vec<LayeredOption>,
+    ) -> Self {
+        self.layer(OptionsLayer::new(format!("rule {name}"), options).when(condition))
+    }
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Clone)]
+struct EffectiveOptions {
+    module: ModuleOptionsContext,
+    resolve: ResolveOptionsContext,
+    /// The effective value of every option with the name of the layer which
+    /// set it, in the order of [LayeredOption::all_from].
+    explanation: Vec<OptionExplanation>,
+}
+
+/// The effective value of an option and the layer which set it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub struct OptionExplanation {
+    pub option: String,
+    pub value: String,
+    pub layer: String,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionExplanations(Vec<OptionExplanation>);
+
+#[turbo_tasks::value_impl]
+impl LayeredOptionsVc {
+    #[turbo_tasks::function]
+    async fn effective(self, path: FileSystemPathVc) -> Result<EffectiveOptionsVc> {
+        let this = self.await?;
+        let path_value = path.await?;
+        let mut module = ModuleOptionsContext::default();
+        let mut resolve = ResolveOptionsContext::default();
+        let mut layers = IndexMap::new();
+        for option in LayeredOption::all_from(&module, &resolve) {
+            layers.insert(option.name(), ("defaults", option));
+        }
+        for layer in this.layers.iter() {
+            if let Some(condition) = &layer.condition {
+                if !condition.matches(&path_value, None) {
+                    continue;
+                }
+            }
+            for option in layer.options.iter() {
+                option.apply(&mut module, &mut resolve);
+                layers.insert(option.name(), (layer.name.as_str(), option.clone()));
+            }
+        }
+        let explanation = layers
+            .into_iter()
+            .map(|(name, (layer, option))| OptionExplanation {
+                option: name.to_string(),
+                value: option.describe_value(),
+                layer: layer.to_string(),
+            })
+            .collect();
+        Ok(EffectiveOptions {
+            module,
+            resolve,
+            explanation,
+        }
+        .cell())
+    }
+
+    /// The transform options of the asset at `path`.
+    #[turbo_tasks::function]
+    pub async fn module_options_context(
+        self,
+        path: FileSystemPathVc,
+    ) -> Result<ModuleOptionsContextVc> {
+        Ok(self.effective(path).await?.module.clone().cell())
+    }
+
+    /// The resolve options of the asset at `path`.
+    #[turbo_tasks::function]
+    pub async fn resolve_options_context(
+        self,
+        path: FileSystemPathVc,
+    ) -> Result<ResolveOptionsContextVc> {
+        Ok(self.effective(path).await?.resolve.clone().cell())
+    }
+
+    /// Reports the effective value of every option of the asset at `path`
+    /// and the layer which set it, which answers why a file is compiled the
+    /// way it is.
+    #[turbo_tasks::function]
+    pub async fn explain(self, path: FileSystemPathVc) -> Result<OptionExplanationsVc> {
+        Ok(OptionExplanationsVc::cell(
+            self.effective(path).await?.explanation.clone(),
+        ))
+    }
+
+    /// Renders [explain](LayeredOptionsVc::explain) as a list of options.
+    #[turbo_tasks::function]
+    pub async fn render_explanation(self, path: FileSystemPathVc) -> Result<StringVc> {
+        let mut rendered = String::new();
+        for OptionExplanation {
+            option,
+            value,
+            layer,
+        } in self.explain(path).await?.iter()
+        {
+            writeln!(rendered, "{option} = {value} (from {layer})")?;
+        }
+        Ok(StringVc::cell(rendered))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LayeredOption, OptionsLayer};
+    use crate::{
+        module_options::ModuleOptionsContext, resolve_options_context::ResolveOptionsContext,
+    };
+
+    #[test]
+    fn layers_only_options_differing_from_defaults() {
+        let layer = OptionsLayer::from_contexts(
+            "test",
+            &ModuleOptionsContext {
+                enable_styled_jsx: true,
+                ..Default::default()
+            },
+            &ResolveOptionsContext {
+                custom_conditions: vec!["development".to_string()],
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            layer.options,
+            vec![
+                LayeredOption::EnableStyledJsx(true),
+                LayeredOption::CustomConditions(vec!["development".to_string()]),
+            ]
+        );
+    }
+}
@@ -13,13 +13,14 @@ use std::{
     mem::swap,
 };
 
-use anyhow::Result;
-use css::{CssModuleAssetVc, ModuleCssModuleAssetVc};
+use anyhow::{bail, Result};
+use css::{CssModuleAssetVc, ModuleCssModuleAssetVc, SassAssetVc};
 use ecmascript::{
     typescript::resolve::TypescriptTypesAssetReferenceVc, EcmascriptModuleAssetType,
     EcmascriptModuleAssetVc,
 };
 use graph::{aggregate, AggregatedGraphNodeContent, AggregatedGraphVc};
+use indexmap::IndexMap;
 use lazy_static::lazy_static;
 use module_options::{
     ModuleOptionsContextVc, ModuleOptionsVc, ModuleRuleEffect, ModuleType, ModuleTypeVc,
@@ -27,26 +28,37 @@ use module_options::{
 pub use resolve::resolve_options;
 use turbo_tasks::{
     primitives::{BoolVc, StringVc},
-    CompletionVc, Value,
+    CompletionVc, CompletionsVc, Value,
 };
-use turbo_tasks_fs::FileSystemPathVc;
+use turbo_tasks_fs::{FileSystemPathOptionVc, FileSystemPathVc};
 use turbopack_core::{
     asset::AssetVc,
+    cache::RemoteCachedSourceVc,
+    chunk::{
+        content_hash::{ContentHashedAssetVc, OutputFilesVc, OutputManifestAssetVc},
+        ChunkVc,
+    },
     context::{AssetContext, AssetContextVc},
     environment::EnvironmentVc,
-    issue::{unsupported_module::UnsupportedModuleIssue, Issue, IssueVc},
-    reference::all_referenced_assets,
+    issue::{codes, unsupported_module::UnsupportedModuleIssue, Issue, IssueVc},
+    reference::{all_assets, all_referenced_assets},
     resolve::{
-        options::ResolveOptionsVc,
+        options::{ImportMapVc, ResolveOptionsVc},
         origin::PlainResolveOriginVc,
         parse::{Request, RequestVc},
         pattern::Pattern,
         resolve, ResolveResultVc,
     },
+    source_transform::SourceTransformsVc,
 };
 
+pub mod build;
 mod graph;
+pub mod html;
+pub mod layered_options;
 pub mod module_options;
+pub mod prebundle;
+pub mod preset;
 pub mod rebase;
 pub mod resolve;
 pub mod resolve_options_context;
@@ -56,6 +68,7 @@ pub use turbopack_css as css;
 pub use turbopack_ecmascript as ecmascript;
 use turbopack_json::JsonModuleAssetVc;
 use turbopack_static::StaticModuleAssetVc;
+use turbopack_webassembly::WebAssemblyModuleAssetVc;
 
 use self::{
     resolve_options_context::ResolveOptionsContextVc,
@@ -83,6 +96,11 @@ impl Issue for ModuleIssue {
         StringVc::cell("other".to_string())
     }
 
+    #[turbo_tasks::function]
+    fn code(&self) -> StringVc {
+        StringVc::cell(codes::resolve::MODULE_RULE.to_string())
+    }
+
     #[turbo_tasks::function]
     fn context(&self) -> FileSystemPathVc {
         self.path
@@ -100,10 +118,18 @@ impl Issue for ModuleIssue {
 }
 
 #[turbo_tasks::function]
-async fn get_module_type(path: FileSystemPathVc, options: ModuleOptionsVc) -> Result<ModuleTypeVc> {
+async fn get_module_type(
+    path: FileSystemPathVc,
+    issuer: FileSystemPathOptionVc,
+    options: ModuleOptionsVc,
+) -> Result<ModuleTypeVc> {
+    let issuer = match *issuer.await? {
+        Some(issuer) => Some(issuer.await?),
+        None => None,
+    };
     let mut current_module_type = None;
     for rule in options.await?.rules.iter() {
-        if rule.matches(&path.await?) {
+        if rule.matches(&path.await?, issuer.as_ref()) {
             for (_, effect) in rule.effects() {
                 match effect {
                     ModuleRuleEffect::ModuleType(module) => {
@@ -149,6 +175,7 @@ async fn get_module_type(path: FileSystemPathVc, options: ModuleOptionsVc) -> Re
                             }
                         };
                     }
+                    ModuleRuleEffect::SourceTransforms(_) => {}
                     ModuleRuleEffect::Custom => {
                         todo!("Custom module rule effects are not yet supported");
                     }
@@ -160,12 +187,66 @@ async fn get_module_type(path: FileSystemPathVc, options: ModuleOptionsVc) -> Re
     Ok(current_module_type.unwrap_or(ModuleType::Raw).cell())
 }
 
+#[turbo_tasks::function]
+async fn get_source_transforms(
+    path: FileSystemPathVc,
+    issuer: FileSystemPathOptionVc,
+    options: ModuleOptionsVc,
+) -> Result<SourceTransformsVc> {
+    let issuer = match *issuer.await? {
+        Some(issuer) => Some(issuer.await?),
+        None => None,
+    };
+    let mut transforms = Vec::new();
+    for rule in options.await?.rules.iter() {
+        if rule.matches(&path.await?, issuer.as_ref()) {
+            for (_, effect) in rule.effects() {
+                if let ModuleRuleEffect::SourceTransforms(rule_transforms) = effect {
+                    transforms.extend(rule_transforms.await?.iter().copied());
+                }
+            }
+        }
+    }
+    Ok(SourceTransformsVc::cell(transforms))
+}
+
 #[turbo_tasks::function]
 async fn module(source: AssetVc, context: ModuleAssetContextVc) -> Result<AssetVc> {
+    create_module(source, context, FileSystemPathOptionVc::cell(None)).await
+}
+
+/// Creates the module for `source` imported by the module at `issuer`. Falls
+/// back to [module] when no module rule has conditions on the issuer, so the
+/// module is shared by all issuers.
+#[turbo_tasks::function]
+async fn module_with_issuer(
+    source: AssetVc,
+    context: ModuleAssetContextVc,
+    issuer: FileSystemPathVc,
+) -> Result<AssetVc> {
+    let options = ModuleOptionsVc::new(source.path().parent(), context.module_options_context());
+    if !*options.depends_on_issuer().await? {
+        return Ok(module(source, context));
+    }
+    create_module(source, context, FileSystemPathOptionVc::cell(Some(issuer))).await
+}
+
+async fn create_module(
+    source: AssetVc,
+    context: ModuleAssetContextVc,
+    issuer: FileSystemPathOptionVc,
+) -> Result<AssetVc> {
     let path = source.path();
     let options = ModuleOptionsVc::new(path.parent(), context.module_options_context());
 
-    let current_module_type = get_module_type(path, options).await?;
+    let current_module_type = get_module_type(path, issuer, options).await?;
+    let transforms = get_source_transforms(path, issuer, options);
+    let source = match context.module_options_context().await?.remote_cache {
+        Some(cache) if !transforms.await?.is_empty() => {
+            RemoteCachedSourceVc::new(source, transforms.apply(source), cache).into()
+        }
+        _ => transforms.apply(source),
+    };
 
     Ok(match &*current_module_type {
         ModuleType::Ecmascript(transforms) => EcmascriptModuleAssetVc::new(
@@ -200,7 +281,20 @@ async fn module(source: AssetVc, context: ModuleAssetContextVc) -> Result<AssetV
         ModuleType::CssModule(transforms) => {
             ModuleCssModuleAssetVc::new(source, context.into(), *transforms).into()
         }
-        ModuleType::Static => StaticModuleAssetVc::new(source, context.into()).into(),
+        ModuleType::Sass(transforms) => {
+            CssModuleAssetVc::new(SassAssetVc::new(source).into(), context.into(), *transforms)
+                .into()
+        }
+        ModuleType::SassModule(transforms) => ModuleCssModuleAssetVc::new(
+            SassAssetVc::new(source).into(),
+            context.into(),
+            *transforms,
+        )
+        .into(),
+        ModuleType::Static(ty) => {
+            StaticModuleAssetVc::new(source, context.into(), Value::new(*ty)).into()
+        }
+        ModuleType::WebAssembly => WebAssemblyModuleAssetVc::new(source, context.into()).into(),
         ModuleType::Custom(_) => todo!(),
     })
 }
@@ -254,6 +348,34 @@ impl ModuleAssetContextVc {
         Ok(self.await?.module_options_context)
     }
 
+    /// Like [AssetContext::process], but for an asset imported by the module
+    /// at `issuer`, which module rules can match with
+    /// [ModuleRuleCondition::Issuer](module_options::ModuleRuleCondition::Issuer).
+    #[turbo_tasks::function]
+    pub async fn process_with_issuer(
+        self,
+        asset: AssetVc,
+        issuer: FileSystemPathVc,
+    ) -> Result<AssetVc> {
+        process_asset(&*self.await?, asset, Some(issuer)).await
+    }
+
+    #[turbo_tasks::function]
+    async fn process_resolve_result_with_issuer(
+        self,
+        result: ResolveResultVc,
+        issuer: FileSystemPathVc,
+    ) -> Result<ResolveResultVc> {
+        Ok(result
+            .await?
+            .map(
+                |a| self.process_with_issuer(a, issuer).resolve(),
+                |i| async move { Ok(i) },
+            )
+            .await?
+            .into())
+    }
+
     #[turbo_tasks::function]
     pub async fn is_typescript_resolving_enabled(self) -> Result<BoolVc> {
         Ok(BoolVc::cell(
@@ -279,6 +401,23 @@ impl ModuleAssetContextVc {
             resolve_options_context,
         ))
     }
+
+    /// Returns a new [ModuleAssetContextVc] whose import map is extended with
+    /// `import_map`, e. g. to redirect requests of the modules it processes.
+    #[turbo_tasks::function]
+    pub async fn with_extended_import_map(
+        self,
+        import_map: ImportMapVc,
+    ) -> Result<ModuleAssetContextVc> {
+        let this = self.await?;
+        Ok(ModuleAssetContextVc::new(
+            this.transitions,
+            this.environment,
+            this.module_options_context,
+            this.resolve_options_context
+                .with_extended_import_map(import_map),
+        ))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -308,7 +447,8 @@ impl AssetContext for ModuleAssetContext {
         let context_path = origin_path.parent().resolve().await?;
 
         let result = resolve(context_path, request, resolve_options);
-        let result = self_vc.process_resolve_result(result);
+        let result =
+            self_vc.process_resolve_result_with_issuer(result, origin_path.resolve().await?);
 
         if *self_vc.is_typescript_resolving_enabled().await? {
             let types_reference = TypescriptTypesAssetReferenceVc::new(
@@ -336,31 +476,7 @@ impl AssetContext for ModuleAssetContext {
 
     #[turbo_tasks::function]
     async fn process(self_vc: ModuleAssetContextVc, asset: AssetVc) -> Result<AssetVc> {
-        let this = self_vc.await?;
-        if let Some(transition) = this.transition {
-            let asset = transition.process_source(asset);
-            let environment = transition.process_environment(this.environment);
-            let module_options_context =
-                transition.process_module_options_context(this.module_options_context);
-            let resolve_options_context =
-                transition.process_resolve_options_context(this.resolve_options_context);
-            let context = ModuleAssetContextVc::new(
-                this.transitions,
-                environment,
-                module_options_context,
-                resolve_options_context,
-            );
-            let m = module(asset, context);
-            Ok(transition.process_module(m, context))
-        } else {
-            let context = ModuleAssetContextVc::new(
-                this.transitions,
-                this.environment,
-                this.module_options_context,
-                this.resolve_options_context,
-            );
-            Ok(module(asset, context))
-        }
+        process_asset(&*self_vc.await?, asset, None).await
     }
 
     #[turbo_tasks::function]
@@ -389,6 +505,42 @@ impl AssetContext for ModuleAssetContext {
     }
 }
 
+async fn process_asset(
+    this: &ModuleAssetContext,
+    asset: AssetVc,
+    issuer: Option<FileSystemPathVc>,
+) -> Result<AssetVc> {
+    let module_for = |asset, context| match issuer {
+        Some(issuer) => module_with_issuer(asset, context, issuer),
+        None => module(asset, context),
+    };
+    if let Some(transition) = this.transition {
+        let asset = transition.process_source(asset);
+        let environment = transition.process_environment(this.environment);
+        let module_options_context =
+            transition.process_module_options_context(this.module_options_context);
+        let resolve_options_context =
+            transition.process_resolve_options_context(this.resolve_options_context);
+        let context = ModuleAssetContextVc::new(
+            this.transitions,
+            environment,
+            module_options_context,
+            resolve_options_context,
+        );
+        // Resolved, so issuers sharing the module share the processed module
+        let m = module_for(asset, context).resolve().await?;
+        Ok(transition.process_module(m, context))
+    } else {
+        let context = ModuleAssetContextVc::new(
+            this.transitions,
+            this.environment,
+            this.module_options_context,
+            this.resolve_options_context,
+        );
+        Ok(module_for(asset, context))
+    }
+}
+
 #[turbo_tasks::function]
 pub async fn emit(asset: AssetVc) {
     emit_assets_recursive(asset);
@@ -449,6 +601,73 @@ pub async fn emit_asset_into_dir(
     })
 }
 
+/// Emits `asset` and all assets it references into `output_dir`, like
+/// [emit_with_completion], but chunks are written to content hashed file names
+/// (`[name].[contenthash].[ext]`). A manifest mapping the chunk paths to the
+/// emitted files is written to `manifest_path`.
+#[turbo_tasks::function]
+pub async fn emit_with_content_hashes(
+    asset: AssetVc,
+    output_dir: FileSystemPathVc,
+    manifest_path: FileSystemPathVc,
+) -> Result<CompletionVc> {
+    let dir = &*output_dir.await?;
+    let mut completions = Vec::new();
+    for asset in all_assets(asset).await?.iter() {
+        if !asset.path().await?.is_inside(dir) {
+            continue;
+        }
+        if ChunkVc::resolve_from(asset).await?.is_some() {
+            completions.push(emit_asset(ContentHashedAssetVc::new(*asset).into()));
+        } else {
+            completions.push(emit_asset(*asset));
+        }
+    }
+    let files = content_hashed_output_files(asset, output_dir);
+    let manifest = OutputManifestAssetVc::new(manifest_path, files);
+    completions.push(emit_asset(manifest.into()));
+    Ok(CompletionsVc::cell(completions).all())
+}
+
+/// Maps the paths of the chunks among `asset` and all assets it references,
+/// relative to `output_dir`, to the content hashed files
+/// [emit_with_content_hashes] writes them to. A
+/// [ResourceHintsManifestAsset](turbopack_core::chunk::resource_hints::ResourceHintsManifestAsset)
+/// can use them to preload the emitted files.
+#[turbo_tasks::function]
+pub async fn content_hashed_output_files(
+    asset: AssetVc,
+    output_dir: FileSystemPathVc,
+) -> Result<OutputFilesVc> {
+    let dir = &*output_dir.await?;
+    let mut chunks = Vec::new();
+    for asset in all_assets(asset).await?.iter() {
+        let path = asset.path().await?;
+        if path.is_inside(dir) && ChunkVc::resolve_from(asset).await?.is_some() {
+            chunks.push((path, *asset));
+        }
+    }
+    // `all_assets` has no stable order, but the manifest should
+    chunks.sort_by(|(a, _), (b, _)| a.path.cmp(&b.path));
+
+    let mut files = IndexMap::new();
+    for (path, chunk) in chunks {
+        let hashed: AssetVc = ContentHashedAssetVc::new(chunk).into();
+        let hashed_path = hashed.path().await?;
+        match (dir.get_path_to(&path), dir.get_path_to(&hashed_path)) {
+            (Some(chunk), Some(file)) => {
+                files.insert(chunk.to_string(), file.to_string());
+            }
+            _ => bail!(
+                "chunk {} is not in output directory {}",
+                path.path,
+                dir.path
+            ),
+        }
+    }
+    Ok(OutputFilesVc::cell(files))
+}
+
 #[turbo_tasks::function]
 pub fn print_most_referenced(asset: AssetVc) {
     let aggregated = aggregate(asset);
@@ -576,5 +795,6 @@ pub fn register() {
     turbopack_env::register();
     turbopack_json::register();
     turbopack_static::register();
+    turbopack_webassembly::register();
     include!(concat!(env!("OUT_DIR"), "/register.rs"));
 }
@@ -1,7 +1,12 @@
 use anyhow::Result;
+use turbo_tasks::{
+    primitives::{BoolVc, StringVc},
+    Value,
+};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_css::{CssInputTransform, CssInputTransformsVc};
 use turbopack_ecmascript::{EcmascriptInputTransform, EcmascriptInputTransformsVc};
+use turbopack_static::StaticAssetType;
 
 pub mod module_options_context;
 pub mod module_rule;
@@ -16,6 +21,18 @@ pub struct ModuleOptions {
 
 #[turbo_tasks::value_impl]
 impl ModuleOptionsVc {
+    /// Whether any rule has conditions on the importing module. Modules are
+    /// only created per importing module when they do.
+    #[turbo_tasks::function]
+    pub async fn depends_on_issuer(self) -> Result<BoolVc> {
+        Ok(BoolVc::cell(
+            self.await?
+                .rules
+                .iter()
+                .any(|rule| rule.depends_on_issuer()),
+        ))
+    }
+
     #[turbo_tasks::function]
     pub async fn new(
         _path: FileSystemPathVc,
@@ -27,12 +44,26 @@ impl ModuleOptionsVc {
             enable_styled_jsx,
             enable_styled_components,
             enable_typescript_transform,
+            enable_webpack_runtime_shims,
+            jsx_runtime,
+            ref jsx_import_source,
             preset_env_versions,
+            es_version,
+            compile_time_defines,
+            static_asset_inline_limit,
             ref custom_ecmascript_app_transforms,
             ref custom_ecmascript_transforms,
             ref custom_rules,
             ..
         } = *context.await?;
+        let mut custom_ecmascript_transforms = custom_ecmascript_transforms.clone();
+        if let Some(defines) = compile_time_defines {
+            // Replaced before other transforms, so they see the values
+            custom_ecmascript_transforms.insert(0, EcmascriptInputTransform::Defines(defines));
+        }
+        if enable_webpack_runtime_shims {
+            custom_ecmascript_transforms.push(EcmascriptInputTransform::WebpackRuntimeShims);
+        }
         let mut transforms = custom_ecmascript_app_transforms.clone();
         transforms.extend(custom_ecmascript_transforms.iter().cloned());
 
@@ -49,9 +80,15 @@ impl ModuleOptionsVc {
         }
         transforms.push(EcmascriptInputTransform::React {
             refresh: enable_react_refresh,
+            runtime: jsx_runtime,
+            import_source: jsx_import_source.clone().map(StringVc::cell),
         });
 
         if let Some(env) = preset_env_versions {
+            let env = match es_version {
+                Some(es_version) => env.with_es_version(Value::new(es_version)),
+                None => env,
+            };
             transforms.push(EcmascriptInputTransform::PresetEnv(env));
         }
 
@@ -94,6 +131,24 @@ impl ModuleOptionsVc {
                     css_transforms,
                 ))],
             ),
+            ModuleRule::new(
+                ModuleRuleCondition::any(vec![
+                    ModuleRuleCondition::ResourcePathEndsWith(".scss".to_string()),
+                    ModuleRuleCondition::ResourcePathEndsWith(".sass".to_string()),
+                ]),
+                vec![ModuleRuleEffect::ModuleType(ModuleType::Sass(
+                    css_transforms,
+                ))],
+            ),
+            ModuleRule::new(
+                ModuleRuleCondition::any(vec![
+                    ModuleRuleCondition::ResourcePathEndsWith(".module.scss".to_string()),
+                    ModuleRuleCondition::ResourcePathEndsWith(".module.sass".to_string()),
+                ]),
+                vec![ModuleRuleEffect::ModuleType(ModuleType::SassModule(
+                    css_transforms,
+                ))],
+            ),
             ModuleRule::new(
                 ModuleRuleCondition::any(vec![
                     ModuleRuleCondition::ResourcePathEndsWith(".js".to_string()),
@@ -170,6 +225,7 @@ impl ModuleOptionsVc {
                 ModuleRuleCondition::any(vec![
                     ModuleRuleCondition::ResourcePathEndsWith(".apng".to_string()),
                     ModuleRuleCondition::ResourcePathEndsWith(".avif".to_string()),
+                    ModuleRuleCondition::ResourcePathEndsWith(".bmp".to_string()),
                     ModuleRuleCondition::ResourcePathEndsWith(".gif".to_string()),
                     ModuleRuleCondition::ResourcePathEndsWith(".ico".to_string()),
                     ModuleRuleCondition::ResourcePathEndsWith(".jpg".to_string()),
@@ -177,9 +233,26 @@ impl ModuleOptionsVc {
                     ModuleRuleCondition::ResourcePathEndsWith(".png".to_string()),
                     ModuleRuleCondition::ResourcePathEndsWith(".svg".to_string()),
                     ModuleRuleCondition::ResourcePathEndsWith(".webp".to_string()),
+                    ModuleRuleCondition::ResourcePathEndsWith(".eot".to_string()),
+                    ModuleRuleCondition::ResourcePathEndsWith(".otf".to_string()),
+                    ModuleRuleCondition::ResourcePathEndsWith(".ttf".to_string()),
+                    ModuleRuleCondition::ResourcePathEndsWith(".woff".to_string()),
                     ModuleRuleCondition::ResourcePathEndsWith(".woff2".to_string()),
+                    ModuleRuleCondition::ResourcePathEndsWith(".mp3".to_string()),
+                    ModuleRuleCondition::ResourcePathEndsWith(".mp4".to_string()),
+                    ModuleRuleCondition::ResourcePathEndsWith(".ogg".to_string()),
+                    ModuleRuleCondition::ResourcePathEndsWith(".wav".to_string()),
+                    ModuleRuleCondition::ResourcePathEndsWith(".webm".to_string()),
                 ]),
-                vec![ModuleRuleEffect::ModuleType(ModuleType::Static)],
+                vec![ModuleRuleEffect::ModuleType(ModuleType::Static(
+                    StaticAssetType::Auto {
+                        inline_limit: static_asset_inline_limit,
+                    },
+                ))],
+            ),
+            ModuleRule::new(
+                ModuleRuleCondition::ResourcePathEndsWith(".wasm".to_string()),
+                vec![ModuleRuleEffect::ModuleType(ModuleType::WebAssembly)],
             ),
             ModuleRule::new(
                 ModuleRuleCondition::ResourcePathHasNoExtension,
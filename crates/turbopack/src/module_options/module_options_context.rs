@@ -1,5 +1,8 @@
-use turbopack_core::environment::EnvironmentVc;
-use turbopack_ecmascript::EcmascriptInputTransform;
+use turbopack_core::{
+    cache::RemoteCacheVc,
+    environment::{EcmascriptVersion, EnvironmentVc},
+};
+use turbopack_ecmascript::{CompileTimeDefinesVc, EcmascriptInputTransform, JsxRuntime};
 
 use super::ModuleRule;
 
@@ -11,11 +14,35 @@ pub struct ModuleOptionsContext {
     pub enable_styled_components: bool,
     pub enable_styled_jsx: bool,
     pub enable_typescript_transform: bool,
+    /// Provides `__webpack_require__` and `__non_webpack_require__` to
+    /// modules, which libraries built with webpack can reference.
+    pub enable_webpack_runtime_shims: bool,
+    pub jsx_runtime: JsxRuntime,
+    /// The package the automatic JSX runtime is imported from, e. g. `preact`
+    /// or `@emotion/react`. Defaults to `react`.
+    pub jsx_import_source: Option<String>,
     pub preset_env_versions: Option<EnvironmentVc>,
+    /// Downlevels the syntax of the app code to this ES version, in addition
+    /// to the runtime versions of [ModuleOptionsContext::preset_env_versions].
+    /// Helpers are imported from `@swc/helpers` and generators use
+    /// `regenerator-runtime`, so both need to be resolvable from the project.
+    pub es_version: Option<EcmascriptVersion>,
+    /// Expressions replaced with values at compile time in all modules, e. g.
+    /// `__DEV__` or `typeof window`.
+    pub compile_time_defines: Option<CompileTimeDefinesVc>,
+    /// Static assets, like images and fonts, with at most this many bytes
+    /// are inlined as `data:` URL into the modules and stylesheets
+    /// referencing them. `0` disables inlining.
+    pub static_asset_inline_limit: usize,
     pub custom_ecmascript_app_transforms: Vec<EcmascriptInputTransform>,
     pub custom_ecmascript_transforms: Vec<EcmascriptInputTransform>,
     /// Custom rules to be applied after all default rules.
     pub custom_rules: Vec<ModuleRule>,
+    /// Stores the output of source transforms in a cache shared between
+    /// machines, so sources are only transformed once, see [RemoteCache].
+    ///
+    /// [RemoteCache]: turbopack_core::cache::RemoteCache
+    pub remote_cache: Option<RemoteCacheVc>,
     pub placeholder_for_future_extensions: (),
 }
 
@@ -1,11 +1,13 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Not};
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use turbo_tasks::{primitives::Regex, trace::TraceRawVcs};
-use turbo_tasks_fs::FileSystemPathReadRef;
+use turbo_tasks_fs::{glob::GlobReadRef, FileSystemPathReadRef};
+use turbopack_core::source_transform::SourceTransformsVc;
 use turbopack_css::CssInputTransformsVc;
 use turbopack_ecmascript::EcmascriptInputTransformsVc;
+use turbopack_static::StaticAssetType;
 
 #[derive(Debug, Clone, Serialize, Deserialize, TraceRawVcs, PartialEq, Eq)]
 pub struct ModuleRule {
@@ -29,8 +31,21 @@ impl ModuleRule {
 }
 
 impl ModuleRule {
-    pub fn matches(&self, path: &FileSystemPathReadRef) -> bool {
-        self.condition.matches(path)
+    /// Whether the rule applies to the module at `path`, which is imported by
+    /// the module at `issuer`.
+    pub fn matches(
+        &self,
+        path: &FileSystemPathReadRef,
+        issuer: Option<&FileSystemPathReadRef>,
+    ) -> bool {
+        self.condition.matches(path, issuer)
+    }
+
+    /// Whether the rule has conditions on the importing module, so the same
+    /// file can become different modules depending on the module importing
+    /// it.
+    pub fn depends_on_issuer(&self) -> bool {
+        self.condition.depends_on_issuer()
     }
 }
 
@@ -38,11 +53,19 @@ impl ModuleRule {
 pub enum ModuleRuleCondition {
     All(Vec<ModuleRuleCondition>),
     Any(Vec<ModuleRuleCondition>),
+    Not(Box<ModuleRuleCondition>),
     ResourcePathHasNoExtension,
     ResourcePathEndsWith(String),
     ResourcePathInDirectory(String),
     ResourcePathInExactDirectory(FileSystemPathReadRef),
     ResourcePathRegex(#[turbo_tasks(trace_ignore)] Regex),
+    /// Matches the path of the module relative to the root of its filesystem
+    /// against a glob, e. g. `**/icons/*.svg`.
+    ResourcePathGlob(GlobReadRef),
+    /// Matches when the module importing the module matches the condition.
+    /// Never matches modules which aren't imported by another module, e. g.
+    /// entries.
+    Issuer(Box<ModuleRuleCondition>),
 }
 
 impl ModuleRuleCondition {
@@ -53,13 +76,34 @@ impl ModuleRuleCondition {
     pub fn any(conditions: Vec<ModuleRuleCondition>) -> ModuleRuleCondition {
         ModuleRuleCondition::Any(conditions)
     }
+
+    pub fn issuer(condition: ModuleRuleCondition) -> ModuleRuleCondition {
+        ModuleRuleCondition::Issuer(Box::new(condition))
+    }
+}
+
+impl Not for ModuleRuleCondition {
+    type Output = ModuleRuleCondition;
+
+    fn not(self) -> ModuleRuleCondition {
+        ModuleRuleCondition::Not(Box::new(self))
+    }
 }
 
 impl ModuleRuleCondition {
-    pub fn matches(&self, path: &FileSystemPathReadRef) -> bool {
+    pub fn matches(
+        &self,
+        path: &FileSystemPathReadRef,
+        issuer: Option<&FileSystemPathReadRef>,
+    ) -> bool {
         match self {
-            ModuleRuleCondition::All(conditions) => conditions.iter().all(|c| c.matches(path)),
-            ModuleRuleCondition::Any(conditions) => conditions.iter().any(|c| c.matches(path)),
+            ModuleRuleCondition::All(conditions) => {
+                conditions.iter().all(|c| c.matches(path, issuer))
+            }
+            ModuleRuleCondition::Any(conditions) => {
+                conditions.iter().any(|c| c.matches(path, issuer))
+            }
+            ModuleRuleCondition::Not(condition) => !condition.matches(path, issuer),
             ModuleRuleCondition::ResourcePathEndsWith(end) => path.path.ends_with(end),
             ModuleRuleCondition::ResourcePathHasNoExtension => {
                 if let Some(i) = path.path.rfind('.') {
@@ -78,7 +122,28 @@ impl ModuleRuleCondition {
             ModuleRuleCondition::ResourcePathInExactDirectory(parent_path) => {
                 path.is_inside(parent_path)
             }
-            _ => todo!("not implemented yet"),
+            ModuleRuleCondition::ResourcePathRegex(regex) => regex.is_match(&path.path),
+            ModuleRuleCondition::ResourcePathGlob(glob) => glob.execute(&path.path),
+            ModuleRuleCondition::Issuer(condition) => {
+                // The issuer of the issuer is unknown
+                issuer.map_or(false, |issuer| condition.matches(issuer, None))
+            }
+        }
+    }
+
+    pub fn depends_on_issuer(&self) -> bool {
+        match self {
+            ModuleRuleCondition::All(conditions) | ModuleRuleCondition::Any(conditions) => {
+                conditions.iter().any(|c| c.depends_on_issuer())
+            }
+            ModuleRuleCondition::Not(condition) => condition.depends_on_issuer(),
+            ModuleRuleCondition::Issuer(_) => true,
+            ModuleRuleCondition::ResourcePathHasNoExtension
+            | ModuleRuleCondition::ResourcePathEndsWith(_)
+            | ModuleRuleCondition::ResourcePathInDirectory(_)
+            | ModuleRuleCondition::ResourcePathInExactDirectory(_)
+            | ModuleRuleCondition::ResourcePathRegex(_)
+            | ModuleRuleCondition::ResourcePathGlob(_) => false,
         }
     }
 }
@@ -88,6 +153,10 @@ impl ModuleRuleCondition {
 pub enum ModuleRuleEffect {
     ModuleType(ModuleType),
     AddEcmascriptTransforms(EcmascriptInputTransformsVc),
+    /// Transforms the source before it's processed as its module type.
+    /// Transforms of multiple matching rules are applied in the order of the
+    /// rules.
+    SourceTransforms(SourceTransformsVc),
     Custom,
 }
 
@@ -101,7 +170,17 @@ pub enum ModuleType {
     Raw,
     Css(CssInputTransformsVc),
     CssModule(CssInputTransformsVc),
-    Static,
+    /// Sass stylesheets, which are compiled to CSS and processed like
+    /// [ModuleType::Css].
+    Sass(CssInputTransformsVc),
+    /// Like [ModuleType::Sass], but processed like [ModuleType::CssModule].
+    SassModule(CssInputTransformsVc),
+    /// Images, fonts and other files which are referenced by their URL or
+    /// imported as string.
+    Static(StaticAssetType),
+    /// WebAssembly binaries, which are instantiated with the modules they
+    /// import from.
+    WebAssembly,
     // TODO allow custom function when we support function pointers
     Custom(u8),
 }
@@ -113,6 +192,7 @@ impl ModuleRuleEffect {
             ModuleRuleEffect::AddEcmascriptTransforms(_) => {
                 ModuleRuleEffectKey::AddEcmascriptTransforms
             }
+            ModuleRuleEffect::SourceTransforms(_) => ModuleRuleEffectKey::SourceTransforms,
             ModuleRuleEffect::Custom => ModuleRuleEffectKey::Custom,
         }
     }
@@ -122,5 +202,6 @@ impl ModuleRuleEffect {
 pub enum ModuleRuleEffectKey {
     ModuleType,
     AddEcmascriptTransforms,
+    SourceTransforms,
     Custom,
 }
@@ -0,0 +1,187 @@
+//! Pre-bundling of the dependencies of an app, like the dependency
+//! optimization of other dev servers.
+//!
+//! Every package in `node_modules` which the app imports with a bare import is
+//! bundled into an ES module file of its own, see [EcmascriptLibraryAsset].
+//! The files are placed into a cache directory, and the bare imports of the
+//! app are redirected to them with an import map, see
+//! [PrebundledDependenciesVc::import_map]. So the app only processes a single
+//! module per package instead of all modules of the package, and the files of
+//! CommonJS packages have named exports for the statically detected exports.
+//!
+//! A package importing another pre-bundled package imports the file of it, so
+//! both share a single instance of the other package.
+
+use std::collections::{HashSet, VecDeque};
+
+use anyhow::Result;
+use indexmap::{IndexMap, IndexSet};
+use turbo_tasks::{primitives::StringsVc, CompletionVc, CompletionsVc, TryJoinIterExt};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::{Asset, AssetVc, AssetsVc},
+    chunk::{dev::DevChunkingContextVc, vendor::node_modules_package},
+    context::AssetContext,
+    reference::all_referenced_assets,
+    resolve::{
+        options::{ImportMap, ImportMapVc, ImportMapping},
+        origin::PlainResolveOriginVc,
+        parse::RequestVc,
+        ResolveResult,
+    },
+    source_asset::SourceAssetVc,
+    source_map::SourceMapEmission,
+};
+use turbopack_ecmascript::chunk::{
+    library::{
+        EcmascriptLibraryAsset, EcmascriptLibraryAssetVc, EcmascriptLibraryOptions, LibraryFormat,
+    },
+    EcmascriptChunkPlaceableVc,
+};
+
+use crate::{emit_asset, ModuleAssetContextVc};
+
+/// Returns the names of the packages in `node_modules` which the modules of the
+/// app reachable from `entries` import. The module graph is not followed into
+/// `node_modules`.
+#[turbo_tasks::function]
+pub async fn detect_dependencies(entries: AssetsVc) -> Result<StringsVc> {
+    let entries = entries.await?;
+    let mut visited = entries.iter().copied().collect::<HashSet<_>>();
+    let mut queue = entries.iter().copied().collect::<VecDeque<_>>();
+    let mut packages = IndexSet::new();
+    while let Some(asset) = queue.pop_front() {
+        for referenced in all_referenced_assets(asset).await?.iter() {
+            if !visited.insert(*referenced) {
+                continue;
+            }
+            match node_modules_package(&referenced.path().await?.path) {
+                Some(package) => {
+                    packages.insert(package.to_string());
+                }
+                None => queue.push_back(*referenced),
+            }
+        }
+    }
+    Ok(StringsVc::cell(packages.into_iter().collect()))
+}
+
+/// The files of the pre-bundled packages, keyed by the name of the package.
+#[turbo_tasks::value(transparent)]
+pub struct PrebundledDependencies(IndexMap<String, EcmascriptLibraryAssetVc>);
+
+#[turbo_tasks::value_impl]
+impl PrebundledDependenciesVc {
+    /// Pre-bundles the packages which the app at `entries` imports into files
+    /// in `cache_dir`. The modules of the packages are processed by `context`.
+    ///
+    /// Packages containing other assets than ECMAScript modules, e. g.
+    /// stylesheets, are not pre-bundled, as the files of the packages can't
+    /// load them.
+    #[turbo_tasks::function]
+    pub async fn new(
+        context: ModuleAssetContextVc,
+        entries: AssetsVc,
+        project_root: FileSystemPathVc,
+        cache_dir: FileSystemPathVc,
+    ) -> Result<Self> {
+        let packages = detect_dependencies(entries).await?;
+
+        // Bare imports of the other packages stay imports, which resolve to
+        // the files of the packages again
+        let mut externals = ImportMap::empty();
+        for name in packages.iter() {
+            externals.insert_exact_alias(name, ImportMapping::External(Some(name.clone())).cell());
+        }
+        let package_context = context.with_extended_import_map(externals.cell());
+
+        // The code of a file is taken from a chunk containing all modules of
+        // the package, which is not emitted itself
+        let chunking_context = DevChunkingContextVc::builder(
+            project_root,
+            cache_dir,
+            cache_dir.join("chunks"),
+            cache_dir.join("assets"),
+        )
+        .single_file_output()
+        .source_map_emission(SourceMapEmission::Hidden)
+        .build();
+        let options = EcmascriptLibraryOptions {
+            format: LibraryFormat::Esm,
+            name: None,
+            globals: IndexMap::new(),
+        }
+        .cell();
+
+        let origin =
+            PlainResolveOriginVc::new(context.into(), project_root.join("_")).as_resolve_origin();
+        let cache_dir_path = &*cache_dir.await?;
+        let libraries = packages
+            .iter()
+            .map(|name| async move {
+                let request = RequestVc::parse_string(name.clone());
+                let entry = match origin
+                    .resolve_asset(request, origin.resolve_options())
+                    .primary_assets()
+                    .await?
+                    .first()
+                {
+                    Some(entry) => entry.path(),
+                    None => return Ok(None),
+                };
+                // The entry is processed again, so the modules of the package
+                // are processed with the externals
+                let entry = package_context.process(SourceAssetVc::new(entry).into());
+                let entry = match EcmascriptChunkPlaceableVc::resolve_from(entry).await? {
+                    Some(entry) => entry,
+                    None => return Ok(None),
+                };
+                let library = EcmascriptLibraryAsset {
+                    path: cache_dir.join(&format!("{}.mjs", name.replace('/', "+"))),
+                    context: chunking_context,
+                    entry,
+                    options,
+                    preserved_modules: None,
+                }
+                .cell();
+                // Chunks and static assets of the package would be emitted
+                // next to the file
+                for asset in all_referenced_assets(library.into()).await?.iter() {
+                    if asset.path().await?.is_inside(cache_dir_path) {
+                        return Ok(None);
+                    }
+                }
+                Ok(Some((name.clone(), library)))
+            })
+            .try_join()
+            .await?;
+        Ok(Self::cell(libraries.into_iter().flatten().collect()))
+    }
+
+    /// An import map redirecting bare imports of the pre-bundled packages to
+    /// their files. Imports of subpaths of the packages are not redirected.
+    #[turbo_tasks::function]
+    pub async fn import_map(self) -> Result<ImportMapVc> {
+        let mut import_map = ImportMap::empty();
+        for (name, library) in self.await?.iter() {
+            let asset: AssetVc = (*library).into();
+            import_map.insert_exact_alias(
+                name,
+                ImportMapping::Direct(ResolveResult::Single(asset, Vec::new()).into()).cell(),
+            );
+        }
+        Ok(import_map.cell())
+    }
+
+    /// Writes the files of the pre-bundled packages into the cache directory.
+    #[turbo_tasks::function]
+    pub async fn emit(self) -> Result<CompletionVc> {
+        Ok(CompletionsVc::cell(
+            self.await?
+                .values()
+                .map(|library| emit_asset((*library).into()))
+                .collect(),
+        )
+        .all())
+    }
+}
@@ -0,0 +1,276 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{bail, Error};
+use serde::{Deserialize, Serialize};
+use turbo_tasks::Value;
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    chunk::{
+        dev::{DevChunkingContextBuilder, DevChunkingContextVc},
+        ModuleEvaluation, ModuleIdStrategy,
+    },
+    context::AssetContextVc,
+    environment::{
+        BrowserEnvironment, EcmascriptVersion, EdgeWorkerEnvironment, EnvironmentIntention,
+        EnvironmentVc, ExecutionEnvironment, NodeJsEnvironment,
+    },
+    source_map::SourceMapFormat,
+};
+use turbopack_ecmascript::{
+    chunk::EcmascriptChunkPlaceablesVc, react_refresh::react_refresh_runtime_entry,
+};
+
+use crate::{
+    build::ModuleIds, module_options::module_options_context::ModuleOptionsContext,
+    resolve_options_context::ResolveOptionsContext,
+};
+
+/// Named option presets for common project shapes. A preset bundles the
+/// environment, the module and resolve options and the chunking settings, so
+/// only the options which differ from it need to be configured.
+///
+/// The options are returned as plain values, so single fields can be
+/// overridden with struct update syntax before turning them into cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    /// A React app running in browsers, with TypeScript, JSX and Fast Refresh.
+    ReactApp,
+    /// A library running in Node.js. Node.js builtins and native modules are
+    /// external and all modules are evaluated eagerly, like Node.js does.
+    NodeLibrary,
+    /// A server running in Node.js. The app code is bundled, but Node.js
+    /// builtins and the packages in node_modules are loaded from there at
+    /// runtime.
+    NodeServer,
+    /// A function running in an edge runtime, which has web APIs but no
+    /// Node.js builtins. The output is a single file, as edge runtimes can't
+    /// load further chunks.
+    EdgeFunction,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 4] = [
+        Preset::ReactApp,
+        Preset::NodeLibrary,
+        Preset::NodeServer,
+        Preset::EdgeFunction,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::ReactApp => "react-app",
+            Preset::NodeLibrary => "node-library",
+            Preset::NodeServer => "node-server",
+            Preset::EdgeFunction => "edge-function",
+        }
+    }
+
+    /// The environment the output runs in. `browserslist_query` only applies
+    /// to presets running in browsers.
+    pub fn environment(self, browserslist_query: &str) -> EnvironmentVc {
+        match self {
+            Preset::ReactApp => EnvironmentVc::new(
+                Value::new(ExecutionEnvironment::Browser(
+                    BrowserEnvironment {
+                        dom: true,
+                        web_worker: false,
+                        service_worker: false,
+                        browserslist_query: browserslist_query.to_owned(),
+                    }
+                    .into(),
+                )),
+                Value::new(EnvironmentIntention::Client),
+            ),
+            Preset::NodeLibrary => EnvironmentVc::new(
+                Value::new(ExecutionEnvironment::NodeJsLambda(
+                    NodeJsEnvironment::default().into(),
+                )),
+                Value::new(EnvironmentIntention::Api),
+            ),
+            Preset::NodeServer => EnvironmentVc::new(
+                Value::new(ExecutionEnvironment::NodeJsLambda(
+                    NodeJsEnvironment::default().into(),
+                )),
+                Value::new(EnvironmentIntention::ServerRendering),
+            ),
+            Preset::EdgeFunction => EnvironmentVc::new(
+                Value::new(ExecutionEnvironment::EdgeFunction(
+                    EdgeWorkerEnvironment::default().into(),
+                )),
+                Value::new(EnvironmentIntention::Api),
+            ),
+        }
+    }
+
+    /// The transforms of the preset. The syntax of the app code is downleveled
+    /// to the runtime versions of `environment`, and further to `es_version`
+    /// when it's set.
+    pub fn module_options_context(
+        self,
+        environment: EnvironmentVc,
+        es_version: Option<EcmascriptVersion>,
+    ) -> ModuleOptionsContext {
+        match self {
+            Preset::ReactApp => ModuleOptionsContext {
+                enable_react_refresh: true,
+                enable_styled_jsx: true,
+                enable_typescript_transform: true,
+                preset_env_versions: Some(environment),
+                es_version,
+                static_asset_inline_limit: 4096,
+                ..Default::default()
+            },
+            Preset::NodeLibrary | Preset::NodeServer => ModuleOptionsContext {
+                enable_typescript_transform: true,
+                preset_env_versions: Some(environment),
+                es_version,
+                ..Default::default()
+            },
+            // The runtime versions of edge runtimes are not known, so their
+            // syntax is only downleveled to an explicit ES version.
+            Preset::EdgeFunction => ModuleOptionsContext {
+                enable_typescript_transform: true,
+                preset_env_versions: es_version.map(|_| environment),
+                es_version,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn resolve_options_context(self, environment: EnvironmentVc) -> ResolveOptionsContext {
+        match self {
+            Preset::ReactApp => ResolveOptionsContext {
+                enable_typescript: true,
+                enable_react: true,
+                enable_node_modules: true,
+                browser: true,
+                module: true,
+                custom_conditions: vec!["development".to_string()],
+                ..Default::default()
+            },
+            Preset::NodeLibrary => ResolveOptionsContext {
+                emulate_environment: Some(environment),
+                enable_typescript: true,
+                enable_node_modules: true,
+                enable_node_externals: true,
+                enable_node_native_modules: true,
+                custom_conditions: vec!["node".to_string()],
+                ..Default::default()
+            },
+            Preset::NodeServer => ResolveOptionsContext {
+                emulate_environment: Some(environment),
+                enable_typescript: true,
+                enable_react: true,
+                enable_node_modules: true,
+                enable_node_externals: true,
+                enable_node_native_modules: true,
+                externalize_node_modules: true,
+                custom_conditions: vec!["node".to_string()],
+                ..Default::default()
+            },
+            Preset::EdgeFunction => ResolveOptionsContext {
+                enable_typescript: true,
+                enable_node_modules: true,
+                browser: true,
+                module: true,
+                custom_conditions: vec!["edge-light".to_string(), "worker".to_string()],
+                ..Default::default()
+            },
+        }
+    }
+
+    /// The modules which are evaluated before the entries of the preset, e. g.
+    /// the setup of React Fast Refresh.
+    pub fn runtime_entries(
+        self,
+        context: AssetContextVc,
+        project_path: FileSystemPathVc,
+    ) -> EcmascriptChunkPlaceablesVc {
+        match self {
+            Preset::ReactApp => {
+                EcmascriptChunkPlaceablesVc::cell(vec![react_refresh_runtime_entry(
+                    context,
+                    project_path,
+                )])
+            }
+            Preset::NodeLibrary | Preset::NodeServer | Preset::EdgeFunction => {
+                EcmascriptChunkPlaceablesVc::cell(Vec::new())
+            }
+        }
+    }
+
+    /// How the module ids of builds with the preset are derived. Hashed ids
+    /// of [Preset::chunking_context] don't detect collisions, as the modules
+    /// aren't known upfront, which builds do with [ModuleIds::Hashed].
+    pub fn module_ids(self) -> ModuleIds {
+        match self {
+            Preset::ReactApp | Preset::NodeLibrary | Preset::NodeServer => ModuleIds::Named,
+            Preset::EdgeFunction => ModuleIds::Hashed,
+        }
+    }
+
+    /// The chunking and output settings of the preset, which can be extended
+    /// with further options before building the chunking context.
+    pub fn chunking_context(
+        self,
+        context_path: FileSystemPathVc,
+        output_root_path: FileSystemPathVc,
+        chunk_root_path: FileSystemPathVc,
+        asset_root_path: FileSystemPathVc,
+    ) -> DevChunkingContextBuilder {
+        let builder = DevChunkingContextVc::builder(
+            context_path,
+            output_root_path,
+            chunk_root_path,
+            asset_root_path,
+        );
+        match self {
+            Preset::ReactApp => builder.hot_module_replacement().error_tolerant_emit(),
+            Preset::NodeLibrary | Preset::NodeServer => builder
+                .module_evaluation(ModuleEvaluation::Eager)
+                .source_map_format(SourceMapFormat::Flattened),
+            Preset::EdgeFunction => builder
+                .single_file_output()
+                .module_id_strategy(ModuleIdStrategy::Hashed(None))
+                .source_map_format(SourceMapFormat::Flattened),
+        }
+    }
+}
+
+impl Display for Preset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Preset {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Preset::ALL.into_iter().find(|preset| preset.name() == s) {
+            Some(preset) => Ok(preset),
+            None => bail!(
+                "unknown preset \"{s}\", expected one of {}",
+                Preset::ALL.map(|preset| preset.name()).join(", ")
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Preset;
+
+    #[test]
+    fn parses_preset_names() {
+        for preset in Preset::ALL {
+            assert_eq!(preset.name().parse::<Preset>().unwrap(), preset);
+            assert_eq!(
+                serde_json::to_string(&preset).unwrap(),
+                format!("\"{}\"", preset.name())
+            );
+        }
+        assert!("react".parse::<Preset>().is_err());
+    }
+}
@@ -4,6 +4,7 @@ use anyhow::Result;
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::resolve::{
     find_context_file,
+    lockfile::find_lockfile,
     options::{
         ConditionValue, ImportMap, ImportMapping, ResolveInPackage, ResolveIntoPackage,
         ResolveModules, ResolveOptions, ResolveOptionsVc,
@@ -102,16 +103,42 @@ async fn base_resolve_options(
             );
         }
     }
+    if let Some(environment) = emulating {
+        for req in environment.node_compat_builtins().await?.iter() {
+            let external = format!("node:{req}");
+            direct_mappings.insert(
+                AliasPattern::exact(req.clone()),
+                ImportMapping::External(Some(external.clone())).into(),
+            );
+            direct_mappings.insert(
+                AliasPattern::exact(external.clone()),
+                ImportMapping::External(Some(external)).into(),
+            );
+        }
+    }
 
     let mut import_map = ImportMap::new(direct_mappings, Default::default());
     if let Some(additional_import_map) = opt.import_map {
         let additional_import_map = additional_import_map.await?;
         import_map.extend(&additional_import_map);
     }
+    for (alias, targets) in opt.aliases.iter() {
+        import_map.insert_user_alias(alias, targets, opt.alias_context);
+    }
     let import_map = import_map.cell();
 
+    if opt.enable_node_modules && opt.prewarm_lockfile_packages {
+        if let Some(lockfile) = *find_lockfile(context).await? {
+            // Start reading the package.json files of all installed packages, so
+            // resolving into them later hits a warm cache.
+            let _ = lockfile.prewarm();
+        }
+    }
+
     Ok(ResolveOptions {
-        extensions: if let Some(environment) = emulating {
+        extensions: if let Some(extensions) = &opt.custom_extensions {
+            extensions.clone()
+        } else if let Some(environment) = emulating {
             environment.resolve_extensions().await?.clone_value()
         } else {
             let mut ext = Vec::new();
@@ -221,6 +248,16 @@ async fn base_resolve_options(
         },
         import_map: Some(import_map),
         resolved_map: opt.resolved_map,
+        overrides: opt.module_overrides,
+        virtual_modules: opt.virtual_modules,
+        hermetic: opt.hermetic,
+        preserve_symlinks: opt.preserve_symlinks,
+        externals: match (opt.externals, emulating) {
+            (Some(externals), Some(environment)) => Some(externals.with_environment(environment)),
+            (externals, _) => externals,
+        },
+        externalize_node_modules: opt.externalize_node_modules,
+        polyfills: opt.polyfills,
         ..Default::default()
     }
     .into())
@@ -1,7 +1,14 @@
 use anyhow::Result;
+use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
     environment::EnvironmentVc,
-    resolve::options::{ImportMapVc, ResolvedMapVc},
+    resolve::{
+        externals::ExternalsVc,
+        options::{ImportMapVc, ResolvedMapVc},
+        overrides::ModuleOverridesVc,
+        polyfills::PolyfillsVc,
+        virtual_modules::VirtualModulesVc,
+    },
 };
 
 #[turbo_tasks::value(shared)]
@@ -20,6 +27,9 @@ pub struct ResolveOptionsContext {
     /// Enables the "module" field and export condition in package.json
     pub module: bool,
     pub custom_conditions: Vec<String>,
+    /// The extensions to try when resolving a request without one. Replaces
+    /// the extensions derived from the other options when set.
+    pub custom_extensions: Option<Vec<String>>,
     /// An additional import map to use when resolving modules.
     ///
     /// If set, this import map will be applied to `ResolveOption::import_map`.
@@ -32,8 +42,51 @@ pub struct ResolveOptionsContext {
     /// `ResolveOption::fallback_import_map`. It is always applied last, so
     /// any mapping defined within will take precedence over any other.
     pub fallback_import_map: Option<ImportMapVc>,
+    /// Aliases configured by the user, with the requests they redirect to,
+    /// e. g. `react` → `preact/compat`. See [ImportMap::insert_user_alias]
+    /// for how they match. They take precedence over
+    /// [import_map](Self::import_map) and are applied before looking up
+    /// node_modules.
+    ///
+    /// [ImportMap::insert_user_alias]: turbopack_core::resolve::options::ImportMap::insert_user_alias
+    pub aliases: Vec<(String, Vec<String>)>,
+    /// The directory relative targets of [aliases](Self::aliases) are
+    /// resolved from, e. g. the project directory. Defaults to the directory
+    /// of the importing module.
+    pub alias_context: Option<FileSystemPathVc>,
     /// An additional resolved map to use after modules have been resolved.
     pub resolved_map: Option<ResolvedMapVc>,
+    /// Modules replacing requests or resolved files, e. g. mocks in a test
+    /// bundle. They take precedence over all other options.
+    pub module_overrides: Option<ModuleOverridesVc>,
+    /// Modules generated in memory, e. g. entries or route manifests of a
+    /// framework, which requests resolve to by their specifier.
+    pub virtual_modules: Option<VirtualModulesVc>,
+    /// Forbids accessing the network while resolving, e. g. for remote
+    /// modules, and reports such requests as fatal issues.
+    pub hermetic: bool,
+    /// Keeps symlinked packages, e. g. of a pnpm store, at the path they are
+    /// linked to instead of their real path.
+    pub preserve_symlinks: bool,
+    /// Reads the package.json files of all packages of the lockfile in the
+    /// background, so resolving into them later hits a warm cache. Only pays
+    /// off for long running processes like dev servers, which import most of
+    /// the packages eventually.
+    pub prewarm_lockfile_packages: bool,
+    /// Requests which are loaded at runtime instead of being bundled, e. g.
+    /// `react` from a global variable. Externals without a type are loaded
+    /// the way the [emulated environment](Self::emulate_environment) loads
+    /// them.
+    pub externals: Option<ExternalsVc>,
+    /// Loads packages from node_modules at runtime instead of bundling them,
+    /// e. g. for a Node.js server, while the app code is still bundled.
+    /// Packages which can only be loaded as ES modules are external for
+    /// `import()` only.
+    pub externalize_node_modules: bool,
+    /// Replacements for Node.js builtins like `path` or `buffer`, e. g.
+    /// polyfill packages or empty modules in browsers. They are only used
+    /// when the builtin can't be resolved otherwise.
+    pub polyfills: Option<PolyfillsVc>,
     pub placeholder_for_future_extensions: (),
 }
 
@@ -51,6 +104,24 @@ impl ResolveOptionsContextVc {
         Ok(Self::cell(clone))
     }
 
+    /// Returns a new [ResolveOptionsContextVc] which resolves to the modules
+    /// of `overrides` instead, e. g. for building a test bundle with mocks.
+    #[turbo_tasks::function]
+    pub async fn with_module_overrides(self, overrides: ModuleOverridesVc) -> Result<Self> {
+        let mut clone = self.await?.clone_value();
+        clone.module_overrides = Some(overrides);
+        Ok(Self::cell(clone))
+    }
+
+    /// Returns a new [ResolveOptionsContextVc] which also resolves to the
+    /// modules of `virtual_modules`.
+    #[turbo_tasks::function]
+    pub async fn with_virtual_modules(self, virtual_modules: VirtualModulesVc) -> Result<Self> {
+        let mut clone = self.await?.clone_value();
+        clone.virtual_modules = Some(virtual_modules);
+        Ok(Self::cell(clone))
+    }
+
     /// Returns a new [ResolveOptionsContextVc] with its import map extended to
     /// include the given import map.
     #[turbo_tasks::function]
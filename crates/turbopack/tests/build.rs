@@ -0,0 +1,450 @@
+use std::{
+    env::temp_dir,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use indexmap::IndexMap;
+use turbopack::{
+    build::{
+        build, BuildEntry, BuildOptions, BuildResult, LibraryOptions, ModuleIds, HTML_NAME,
+        MANIFEST_NAME,
+    },
+    ecmascript::chunk::library::LibraryFormat,
+    preset::Preset,
+};
+use turbopack_core::{environment::EcmascriptVersion, issue::IssueSeverity};
+
+/// Builds `src/index.js` of the fixture `tests/build/<fixture>` into a
+/// temporary directory named after the fixture and `name`.
+async fn build_fixture(fixture: &str, name: &str, options: BuildOptions) -> (BuildResult, PathBuf) {
+    let project_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/build")
+        .join(fixture);
+    let output_dir = temp_dir().join(format!(
+        "turbopack-build-{fixture}-{name}-{}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&output_dir);
+    let result = build(
+        vec![BuildEntry::new("src/index.js".to_string())],
+        BuildOptions {
+            project_dir,
+            output_dir: output_dir.clone(),
+            ..options
+        },
+    )
+    .await
+    .unwrap();
+    (result, output_dir)
+}
+
+/// The code of all scripts in `dir`.
+fn read_scripts(dir: &Path) -> String {
+    read_files(dir, "js")
+}
+
+/// The contents of all files with the extension `extension` in `dir`.
+fn read_files(dir: &Path, extension: &str) -> String {
+    let mut contents = String::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().map_or(false, |ext| ext == extension) {
+            contents += &fs::read_to_string(path).unwrap();
+        }
+    }
+    contents
+}
+
+/// The ids of the module factories in the chunk `code`, without quotes.
+fn module_factory_ids(code: &str) -> Vec<String> {
+    code.lines()
+        .filter_map(|line| line.split_once(": ("))
+        .map(|(id, _)| id)
+        .filter(|id| {
+            id.len() > 1 && id.starts_with('"') && id.ends_with('"')
+                || !id.is_empty() && id.chars().all(|c| c.is_ascii_digit())
+        })
+        .map(|id| id.trim_matches('"').to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn builds_app() {
+    let (result, output_dir) = build_fixture("app", "app", BuildOptions::default()).await;
+
+    assert!(
+        !result.has_issues_at(IssueSeverity::Warning),
+        "unexpected issues {:?}",
+        result
+            .issues
+            .iter()
+            .map(|issue| &issue.title)
+            .collect::<Vec<_>>()
+    );
+    for asset in result.assets.iter() {
+        let metadata = fs::metadata(output_dir.join(&asset.path)).unwrap();
+        assert_eq!(
+            metadata.len() as usize,
+            asset.size,
+            "size of {}",
+            asset.path
+        );
+    }
+    assert!(result
+        .assets
+        .iter()
+        .any(|asset| asset.path == MANIFEST_NAME));
+    let manifest: IndexMap<String, String> =
+        serde_json::from_str(&fs::read_to_string(output_dir.join(MANIFEST_NAME)).unwrap()).unwrap();
+    assert_eq!(manifest, result.manifest);
+    assert!(manifest.keys().any(|chunk| chunk.ends_with(".js")));
+    assert!(manifest.keys().any(|chunk| chunk.ends_with(".css")));
+    for file in manifest.values() {
+        assert!(
+            result.assets.iter().any(|asset| &asset.path == file),
+            "{file} is emitted"
+        );
+    }
+    assert!(read_scripts(&output_dir).contains("Hello ${name}"));
+}
+
+#[tokio::test]
+async fn returns_issues() {
+    let (result, _) = build_fixture("resolve_error", "issues", BuildOptions::default()).await;
+
+    assert!(result.has_issues_at(IssueSeverity::Error));
+    assert!(result
+        .issues
+        .iter()
+        .any(|issue| issue.severity == IssueSeverity::Error
+            && issue.description.contains("./missing")));
+}
+
+const DUPLICATE_B: &str = "Package `b` is included in multiple versions";
+
+#[tokio::test]
+async fn reports_duplicate_packages() {
+    let (result, output_dir) =
+        build_fixture("duplicate_packages", "report", BuildOptions::default()).await;
+
+    let issue = result
+        .issues
+        .iter()
+        .find(|issue| issue.title == DUPLICATE_B)
+        .expect("the two versions of b are reported");
+    assert!(issue.description.contains("1.0.0 at "));
+    assert!(issue.description.contains("2.0.0 at "));
+    assert!(issue
+        .description
+        .contains("The lockfile installs the versions 1.0.0, 2.0.0 of the package."));
+    let code = read_scripts(&output_dir);
+    assert!(code.contains("b@1.0.0"));
+    assert!(code.contains("b@2.0.0"));
+}
+
+#[tokio::test]
+async fn dedupes_packages() {
+    let (result, output_dir) = build_fixture(
+        "duplicate_packages",
+        "dedupe",
+        BuildOptions {
+            dedupe_packages: true,
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert!(result.issues.iter().all(|issue| issue.title != DUPLICATE_B));
+    let code = read_scripts(&output_dir);
+    assert!(!code.contains("b@1.0.0"));
+    assert!(code.contains("b@2.0.0"));
+}
+
+#[tokio::test]
+async fn tree_shakes_unused_exports() {
+    let (result, output_dir) = build_fixture(
+        "tree_shaking",
+        "tree_shaking",
+        BuildOptions {
+            tree_shaking: true,
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert!(!result.has_issues_at(IssueSeverity::Error));
+    let code = read_scripts(&output_dir);
+    assert!(code.contains(r#""used": ()=>used"#));
+    assert!(!code.contains(r#""unused": ()=>unused"#));
+}
+
+#[tokio::test]
+async fn uses_detected_commonjs_exports() {
+    let (result, output_dir) = build_fixture(
+        "cjs_exports",
+        "cjs_exports",
+        BuildOptions {
+            tree_shaking: true,
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert!(!result.has_issues_at(IssueSeverity::Error));
+    let issue = result
+        .issues
+        .iter()
+        .find(|issue| issue.code == "TP1303")
+        .expect("the import of a missing export is reported");
+    assert_eq!(issue.severity, IssueSeverity::Warning);
+    assert!(issue.description.contains("`missing` is imported from"));
+    assert!(result
+        .issues
+        .iter()
+        .all(|issue| !issue.description.contains("`used` is imported from")));
+    // The usages of the CommonJS module are forwarded to the module it
+    // re-exports
+    let code = read_scripts(&output_dir);
+    assert!(code.contains(r#""used": ()=>used"#));
+    assert!(!code.contains(r#""unused": ()=>unused"#));
+}
+
+#[tokio::test]
+async fn minifies_chunks() {
+    let (result, output_dir) = build_fixture(
+        "tree_shaking",
+        "minify",
+        BuildOptions {
+            minify: true,
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert!(!result.has_issues_at(IssueSeverity::Error));
+    let code = read_scripts(&output_dir);
+    assert!(code.contains("used export"), "{code}");
+    // Functions are renamed and the code isn't indented anymore
+    assert!(!code.contains("function used()"), "{code}");
+    assert!(!code.contains("\n    "), "{code}");
+    // The source maps of the minified chunks still map to the modules
+    let maps = read_files(&output_dir, "map");
+    assert!(maps.contains("src/lib.js"), "{maps}");
+}
+
+#[tokio::test]
+async fn dedupes_async_chunks() {
+    let (result, output_dir) = build_fixture(
+        "async_chunks",
+        "dedupe_async_chunks",
+        BuildOptions {
+            dedupe_async_chunks: true,
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert!(!result.has_issues_at(IssueSeverity::Error));
+    let code = read_scripts(&output_dir);
+    // The async chunk uses the module factory of the entry chunk
+    assert_eq!(code.matches(r#"return "shared module";"#).count(), 1);
+    assert_eq!(code.matches(r#"return "lazy " + "#).count(), 1);
+}
+
+#[tokio::test]
+async fn builds_edge_functions_into_a_single_file() {
+    let (result, output_dir) = build_fixture(
+        "async_chunks",
+        "edge_function",
+        BuildOptions {
+            preset: Preset::EdgeFunction,
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert!(!result.has_issues_at(IssueSeverity::Error));
+    let scripts = fs::read_dir(&output_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "js"))
+        .collect::<Vec<_>>();
+    assert_eq!(scripts.len(), 1, "{scripts:?}");
+    // The imported module is in the same file and imported without loading a
+    // chunk
+    let code = read_scripts(&output_dir);
+    assert!(code.contains(r#"return "lazy " + "#), "{code}");
+    assert!(
+        code.contains("Promise.resolve().then(() => __turbopack_import__("),
+        "{code}"
+    );
+}
+
+#[tokio::test]
+async fn keeps_entry_chunks_when_the_runtime_changes() {
+    // The public path is only part of the code of the runtime
+    let build = |public_path: &'static str| async move {
+        let name = format!("runtime_chunk{}", public_path.replace('/', "_"));
+        build_fixture(
+            "tree_shaking",
+            &name,
+            BuildOptions {
+                runtime_chunk: true,
+                public_path: public_path.to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+    };
+    let (a, a_dir) = build("/a/").await;
+    let (b, b_dir) = build("/b/").await;
+
+    assert!(!a.has_issues_at(IssueSeverity::Error));
+    let (runtime, entries): (Vec<_>, Vec<_>) = a
+        .manifest
+        .keys()
+        .filter(|chunk| chunk.ends_with(".js"))
+        .partition(|chunk| chunk.ends_with("turbopack-runtime.js"));
+    assert_eq!(runtime.len(), 1, "{:?}", a.manifest);
+    assert!(!entries.is_empty(), "{:?}", a.manifest);
+    assert_ne!(
+        fs::read(a_dir.join(&a.manifest[runtime[0]])).unwrap(),
+        fs::read(b_dir.join(&b.manifest[runtime[0]])).unwrap()
+    );
+    for chunk in entries {
+        let file = &a.manifest[chunk];
+        assert_eq!(file, &b.manifest[chunk], "content hash of {chunk}");
+        assert_eq!(
+            fs::read(a_dir.join(file)).unwrap(),
+            fs::read(b_dir.join(file)).unwrap(),
+            "code of {chunk}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn preloads_imports_of_module_scripts() {
+    let (result, output_dir) = build_fixture(
+        "module_graph",
+        "modulepreload",
+        BuildOptions {
+            html: true,
+            library: Some(LibraryOptions {
+                format: LibraryFormat::Esm,
+                preserve_modules: true,
+                preserve_modules_root: Some("src".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert!(!result.has_issues_at(IssueSeverity::Error));
+    let html = fs::read_to_string(output_dir.join(HTML_NAME)).unwrap();
+    let head = &html[..html.find("</head>").unwrap()];
+    assert!(
+        html.contains(r#"<script type="module" src="/index.mjs"></script>"#),
+        "{html}"
+    );
+    // Every file in the static import graph is preloaded once, the files
+    // imported by the entry first
+    let preloads = head
+        .lines()
+        .filter(|line| line.contains("modulepreload"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        preloads,
+        vec![
+            r#"<link rel="modulepreload" href="/a.mjs" />"#,
+            r#"<link rel="modulepreload" href="/b.mjs" />"#,
+            r#"<link rel="modulepreload" href="/c.mjs" />"#,
+        ],
+        "{html}"
+    );
+}
+
+#[tokio::test]
+async fn lowers_syntax_to_es_version() {
+    let (result, output_dir) = build_fixture("es_version", "latest", BuildOptions::default()).await;
+
+    assert!(!result.has_issues_at(IssueSeverity::Error));
+    let code = read_scripts(&output_dir);
+    assert!(code.contains("class Counter"), "{code}");
+    assert!(code.contains("async function load"), "{code}");
+    assert!(!code.contains("swc-helpers"), "{code}");
+
+    let (result, output_dir) = build_fixture(
+        "es_version",
+        "es5",
+        BuildOptions {
+            es_version: Some(EcmascriptVersion::Es5),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert!(!result.has_issues_at(IssueSeverity::Error));
+    let code = read_scripts(&output_dir);
+    assert!(!code.contains("class Counter"), "{code}");
+    assert!(!code.contains("async function load"), "{code}");
+    // The helpers and the regenerator runtime are modules of their own, which
+    // the lowered code imports
+    assert!(code.contains("[project]/node_modules/@swc/helpers/src/_class_call_check.mjs"));
+    assert!(code.contains("[project]/node_modules/@swc/helpers/src/_async_to_generator.mjs"));
+    assert!(code.contains("[project]/node_modules/regenerator-runtime/runtime.js"));
+    assert!(code.contains("swc-helpers: Cannot call a class as a function"));
+    assert!(code.contains("regenerator-runtime: runtime"));
+}
+
+#[tokio::test]
+async fn hashes_module_ids() {
+    let (result, output_dir) = build_fixture(
+        "async_chunks",
+        "hashed_module_ids",
+        BuildOptions {
+            module_ids: Some(ModuleIds::Hashed),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert!(!result.has_issues_at(IssueSeverity::Error));
+    let code = read_scripts(&output_dir);
+    let ids = module_factory_ids(&code);
+    // The modules, the loader and the manifest of the async import
+    assert!(ids.len() >= 5, "{ids:?}");
+    for id in ids.iter() {
+        assert!(
+            id.len() >= 12 && id.chars().all(|c| c.is_ascii_hexdigit() || c == '-'),
+            "{id} is not hashed"
+        );
+    }
+    assert!(!code.contains(r#""[project]/"#), "{code}");
+    assert!(code.contains(r#"return "lazy " + "#));
+}
+
+#[tokio::test]
+async fn numbers_module_ids() {
+    let (result, output_dir) = build_fixture(
+        "async_chunks",
+        "numeric_module_ids",
+        BuildOptions {
+            module_ids: Some(ModuleIds::Numeric),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert!(!result.has_issues_at(IssueSeverity::Error));
+    let code = read_scripts(&output_dir);
+    let ids = module_factory_ids(&code);
+    assert!(ids.len() >= 5, "{ids:?}");
+    for id in ids.iter() {
+        assert!(id.parse::<u32>().is_ok(), "{id} is not a number");
+    }
+    // The loader of the async import refers to the manifest by its number too
+    assert!(!code.contains(r#""[project]/"#), "{code}");
+    assert!(code.contains(r#"return "lazy " + "#));
+}
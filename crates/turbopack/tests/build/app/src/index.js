@@ -0,0 +1,4 @@
+import "./style.css";
+import { greet } from "./greet";
+
+console.log(greet("turbopack"));
@@ -0,0 +1,4 @@
+import { shared } from "./shared";
+
+console.log(shared());
+import("./lazy").then(({ lazy }) => console.log(lazy()));
@@ -0,0 +1,5 @@
+import { shared } from "./shared";
+
+export function lazy() {
+  return "lazy " + shared();
+}
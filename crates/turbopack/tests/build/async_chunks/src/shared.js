@@ -0,0 +1,3 @@
+export function shared() {
+  return "shared module";
+}
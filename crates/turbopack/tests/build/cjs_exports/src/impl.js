@@ -0,0 +1,7 @@
+export function used() {
+  return "used export";
+}
+
+export function unused() {
+  return "unused export";
+}
@@ -0,0 +1,3 @@
+import { used, missing } from "./lib";
+
+console.log(used(), missing);
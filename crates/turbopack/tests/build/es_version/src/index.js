@@ -0,0 +1,12 @@
+class Counter {
+  constructor(count) {
+    this.count = count;
+  }
+}
+
+async function load() {
+  const count = await Promise.resolve(1);
+  return new Counter(count);
+}
+
+load();
@@ -0,0 +1,3 @@
+import { used } from "./lib";
+
+console.log(used());
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use anyhow::bail;
+use turbo_tasks::{TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, File, FileSystem};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    ecmascript::chunk::EcmascriptChunkPlaceableVc, module_options::ModuleOptionsContext, register,
+    resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    chunk::dev::DevChunkingContextVc,
+    context::AssetContext,
+    environment::{
+        EdgeWorkerEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment,
+    },
+    virtual_asset::VirtualAssetVc,
+};
+
+#[tokio::test]
+async fn loads_node_compat_builtins_from_the_runtime() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let code = tt
+        .run_once(async move {
+            let root = DiskFileSystemVc::new(
+                "project".to_string(),
+                env!("CARGO_MANIFEST_DIR").to_string(),
+            )
+            .root();
+            let environment = EnvironmentVc::new(
+                Value::new(ExecutionEnvironment::EdgeFunction(
+                    EdgeWorkerEnvironment {
+                        node_compat_builtins: vec!["buffer".to_string()],
+                    }
+                    .into(),
+                )),
+                Value::new(EnvironmentIntention::Api),
+            );
+            let context = ModuleAssetContextVc::new(
+                TransitionsByNameVc::cell(HashMap::new()),
+                environment,
+                ModuleOptionsContext::default().cell(),
+                ResolveOptionsContext {
+                    emulate_environment: Some(environment),
+                    ..Default::default()
+                }
+                .cell(),
+            );
+            let source = VirtualAssetVc::new(
+                root.join("index.js"),
+                File::from("import { Buffer } from \"buffer\";\nconsole.log(Buffer.from(\"a\"));")
+                    .into(),
+            );
+            let module =
+                match EcmascriptChunkPlaceableVc::resolve_from(context.process(source.into()))
+                    .await?
+                {
+                    Some(module) => module,
+                    None => bail!("the entry is not an ECMAScript module"),
+                };
+            let chunking_context = DevChunkingContextVc::builder(
+                root,
+                root,
+                root.join("_chunks"),
+                root.join("_assets"),
+            )
+            .build();
+            let content = module.as_chunk_item(chunking_context).content().await?;
+            Ok(content.inner_code.clone())
+        })
+        .await
+        .unwrap();
+
+    // The builtin is loaded with the `node:` prefix instead of being bundled
+    assert!(
+        code.contains(r#"__turbopack_external_require__("node:buffer")"#),
+        "{code}"
+    );
+}
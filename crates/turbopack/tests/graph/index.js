@@ -0,0 +1,5 @@
+import { a } from "./a.js";
+
+import("./lazy.js");
+
+console.log(a);
@@ -0,0 +1,3 @@
+const { shared } = require("./shared.js");
+
+console.log(shared + " lazy");
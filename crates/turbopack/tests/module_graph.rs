@@ -0,0 +1,150 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::PathBuf,
+};
+
+use serde_json::Value as JsonValue;
+use turbo_tasks::{TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, FileSystem};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    module_options::ModuleOptionsContext, preset::Preset, register,
+    resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::AssetsVc,
+    context::AssetContext,
+    reference::graph::{ModuleGraphFormat, ModuleGraphVc},
+    source_asset::SourceAssetVc,
+};
+
+/// The module graph of `index.js` of the `tests/graph` fixture.
+fn module_graph() -> ModuleGraphVc {
+    let root = DiskFileSystemVc::new(
+        "project".to_string(),
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/graph")
+            .to_string_lossy()
+            .to_string(),
+    )
+    .root();
+    let context = ModuleAssetContextVc::new(
+        TransitionsByNameVc::cell(HashMap::new()),
+        Preset::NodeLibrary.environment(""),
+        ModuleOptionsContext::default().cell(),
+        ResolveOptionsContext::default().cell(),
+    );
+    let module = context.process(SourceAssetVc::new(root.join("index.js")).into());
+    ModuleGraphVc::from_roots(AssetsVc::cell(vec![module]))
+}
+
+#[tokio::test]
+async fn renders_references_with_their_kinds() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let (json, dot) = tt
+        .run_once(async move {
+            let graph = module_graph();
+            let json = graph.render(Value::new(ModuleGraphFormat::Json)).await?;
+            let dot = graph.render(Value::new(ModuleGraphFormat::Dot)).await?;
+            Ok((json.clone_value(), dot.clone_value()))
+        })
+        .await
+        .unwrap();
+
+    let json: JsonValue = serde_json::from_str(&json).unwrap();
+    let modules = json["modules"].as_array().unwrap();
+    for (id, root) in [
+        ("[project]/index.js", true),
+        ("[project]/a.js", false),
+        ("[project]/lazy.js", false),
+        ("[project]/shared.js", false),
+    ] {
+        assert!(
+            modules
+                .iter()
+                .any(|module| module["id"] == id && module["root"] == root),
+            "{json:#}"
+        );
+    }
+    let references = json["references"].as_array().unwrap();
+    for (from, to, kind) in [
+        ("index.js", "a.js", "esm"),
+        ("index.js", "lazy.js", "async"),
+        ("a.js", "shared.js", "esm"),
+        ("lazy.js", "shared.js", "cjs"),
+    ] {
+        assert!(
+            references.iter().any(|reference| {
+                reference["from"] == format!("[project]/{from}").as_str()
+                    && reference["to"] == format!("[project]/{to}").as_str()
+                    && reference["kind"] == kind
+            }),
+            "{from} -> {to} ({kind}) in {json:#}"
+        );
+    }
+
+    assert!(dot.starts_with("digraph modules {\n"), "{dot}");
+    assert!(
+        dot.contains("  \"[project]/index.js\" [style=bold];\n"),
+        "{dot}"
+    );
+    // Async references are dashed
+    assert!(
+        dot.lines().any(|line| line
+            .starts_with("  \"[project]/index.js\" -> \"[project]/lazy.js\" [label=\"async\"")
+            && line.ends_with(", style=dashed];")),
+        "{dot}"
+    );
+}
+
+#[tokio::test]
+async fn finds_the_import_chains_of_a_module() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let (chains, text) = tt
+        .run_once(async move {
+            let graph = module_graph();
+            let shared = graph.modules_at_path("shared.js").await?;
+            assert_eq!(shared.len(), 1);
+            let import_chains = graph.import_chains(shared[0]);
+            // The paths of the modules along every chain, from the root
+            let mut chains = BTreeSet::new();
+            for chain in import_chains.await?.iter() {
+                let mut paths = Vec::new();
+                for reference in chain.iter() {
+                    paths.push(reference.from.path().await?.path.clone());
+                }
+                if let Some(last) = chain.last() {
+                    paths.push(last.to.path().await?.path.clone());
+                }
+                chains.insert(paths);
+            }
+            Ok((chains, import_chains.to_text().await?.clone_value()))
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        chains,
+        BTreeSet::from([
+            vec![
+                "index.js".to_string(),
+                "a.js".to_string(),
+                "shared.js".to_string()
+            ],
+            vec![
+                "index.js".to_string(),
+                "lazy.js".to_string(),
+                "shared.js".to_string()
+            ],
+        ])
+    );
+    assert!(text.starts_with("[project]/index.js\n"), "{text}");
+    assert!(text.contains("\n  async "), "{text}");
+    assert!(text.contains("\n  cjs "), "{text}");
+    assert!(text.contains("\n[project]/shared.js\n"), "{text}");
+}
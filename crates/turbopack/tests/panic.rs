@@ -0,0 +1,90 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use turbo_tasks::{NothingVc, TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, File, FileSystem};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    ecmascript::{
+        chunk::EcmascriptChunkPlaceableVc, EcmascriptInputTransform, EcmascriptInputTransformsVc,
+        EcmascriptModuleAssetType, EcmascriptModuleAssetVc,
+    },
+    module_options::ModuleOptionsContext,
+    preset::Preset,
+    register,
+    resolve_options_context::ResolveOptionsContext,
+    transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    chunk::dev::DevChunkingContextVc, issue::IssueVc, virtual_asset::VirtualAssetVc,
+};
+
+static CODE: Mutex<String> = Mutex::new(String::new());
+static ISSUES: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+#[tokio::test]
+async fn replaces_panicking_modules_with_errors() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let root = DiskFileSystemVc::new(
+            "project".to_string(),
+            env!("CARGO_MANIFEST_DIR").to_string(),
+        )
+        .root();
+        let environment = Preset::NodeLibrary.environment("");
+        let context = ModuleAssetContextVc::new(
+            TransitionsByNameVc::cell(HashMap::new()),
+            environment,
+            ModuleOptionsContext::default().cell(),
+            ResolveOptionsContext::default().cell(),
+        );
+        let source = VirtualAssetVc::new(
+            root.join("index.js"),
+            File::from("export const a = 1;").into(),
+        );
+        // The custom transform isn't implemented and panics while parsing
+        let module = EcmascriptModuleAssetVc::new(
+            source.into(),
+            context.into(),
+            Value::new(EcmascriptModuleAssetType::Ecmascript),
+            EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::Custom]),
+            environment,
+        );
+        let chunking_context =
+            DevChunkingContextVc::builder(root, root, root.join("_chunks"), root.join("_assets"))
+                .build();
+        let content = EcmascriptChunkPlaceableVc::from(module)
+            .as_chunk_item(chunking_context)
+            .content();
+        *CODE.lock().unwrap() = content.await?.inner_code.clone();
+        *ISSUES.lock().unwrap() = IssueVc::peek_issues_with_path(content)
+            .await?
+            .strongly_consistent()
+            .await?
+            .get_plain_issues()
+            .await?
+            .iter()
+            .map(|issue| (issue.code.clone(), issue.title.clone()))
+            .collect();
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+
+    let issues = ISSUES.lock().unwrap();
+    assert!(
+        issues.contains(&(
+            "TP3007".to_string(),
+            "Internal error while parsing the module".to_string()
+        )),
+        "{issues:?}"
+    );
+    // The module throws the error instead of being empty
+    let code = CODE.lock().unwrap();
+    assert!(code.contains("e.code = 'MODULE_FAILED';"), "{code}");
+    assert!(
+        code.contains("turbopack panicked while parsing it"),
+        "{code}"
+    );
+}
@@ -0,0 +1,121 @@
+use std::{collections::HashMap, env::temp_dir, fs, path::PathBuf};
+
+use turbo_tasks::{NothingVc, TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, FileSystem};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    module_options::ModuleOptionsContext, prebundle::PrebundledDependenciesVc, register,
+    resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::{Asset, AssetsVc},
+    context::AssetContext,
+    environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    reference::all_referenced_assets,
+    source_asset::SourceAssetVc,
+};
+
+#[tokio::test]
+async fn prebundles_dependencies() {
+    register();
+
+    let cache = temp_dir().join(format!("turbopack-prebundle-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&cache);
+    let cache_root = cache.to_string_lossy().to_string();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let project_root = DiskFileSystemVc::new(
+            "project".to_string(),
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/prebundle")
+                .to_string_lossy()
+                .to_string(),
+        )
+        .root();
+        let cache_dir = DiskFileSystemVc::new("cache".to_string(), cache_root).root();
+        let env = EnvironmentVc::new(
+            Value::new(ExecutionEnvironment::Browser(
+                BrowserEnvironment {
+                    dom: true,
+                    web_worker: false,
+                    service_worker: false,
+                    browserslist_query: "Chrome 102".to_string(),
+                }
+                .into(),
+            )),
+            Value::new(EnvironmentIntention::Client),
+        );
+        let context = ModuleAssetContextVc::new(
+            TransitionsByNameVc::cell(HashMap::new()),
+            env,
+            ModuleOptionsContext::default().cell(),
+            ResolveOptionsContext {
+                enable_node_modules: true,
+                ..Default::default()
+            }
+            .cell(),
+        );
+        let entry = context.process(SourceAssetVc::new(project_root.join("index.js")).into());
+
+        let prebundled = PrebundledDependenciesVc::new(
+            context,
+            AssetsVc::cell(vec![entry]),
+            project_root,
+            cache_dir,
+        );
+        // The packages the app imports are pre-bundled, but not the modules
+        // within them
+        assert_eq!(
+            prebundled.await?.keys().cloned().collect::<Vec<_>>(),
+            ["cjs", "esm"]
+        );
+        prebundled.emit().await?;
+
+        // The app imports the files of the packages instead of their modules
+        let app_context = context.with_extended_import_map(prebundled.import_map());
+        let app = app_context.process(SourceAssetVc::new(project_root.join("index.js")).into());
+        let mut paths = Vec::new();
+        for asset in all_referenced_assets(app).await?.iter() {
+            paths.push(asset.path().await?.path.clone());
+        }
+        assert!(paths.contains(&"cjs.mjs".to_string()), "{paths:?}");
+        assert!(paths.contains(&"esm.mjs".to_string()), "{paths:?}");
+        assert!(
+            !paths.iter().any(|path| path.starts_with("node_modules/")),
+            "{paths:?}"
+        );
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+
+    // The CommonJS package is bundled with the modules it requires, and
+    // exports `module.exports` as the default export and the detected exports
+    // as named exports
+    let cjs = fs::read_to_string(cache.join("cjs.mjs")).unwrap();
+    assert!(cjs.contains("cjs other"), "{cjs}");
+    assert!(
+        cjs.contains("export default __turbopack_exports__;"),
+        "{cjs}"
+    );
+    assert!(
+        cjs.contains(
+            "export { __turbopack_export_0__ as named, __turbopack_export_1__ as other };"
+        ),
+        "{cjs}"
+    );
+
+    // The ES module package imports the file of the other package instead of
+    // bundling it
+    let esm = fs::read_to_string(cache.join("esm.mjs")).unwrap();
+    assert!(
+        esm.contains(r#"import * as __turbopack_external_0__ from "cjs";"#),
+        "{esm}"
+    );
+    assert!(!esm.contains("cjs named"), "{esm}");
+    assert!(
+        esm.contains("export default __turbopack_exports__.default;"),
+        "{esm}"
+    );
+}
@@ -0,0 +1,4 @@
+import { named } from "cjs";
+import esm from "esm";
+
+console.log(named, esm);
@@ -0,0 +1,100 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::bail;
+use indexmap::IndexMap;
+use turbo_tasks::{TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, FileSystem};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    ecmascript::chunk::EcmascriptChunkPlaceableVc, module_options::ModuleOptionsContext, register,
+    resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    chunk::dev::DevChunkingContextVc,
+    context::AssetContext,
+    environment::{
+        BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment,
+        ProvidedGlobal, ProvidedGlobalsVc,
+    },
+    source_asset::SourceAssetVc,
+};
+
+#[tokio::test]
+async fn imports_provided_globals() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let code = tt
+        .run_once(async move {
+            let root = DiskFileSystemVc::new(
+                "project".to_string(),
+                PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                    .join("tests/provide")
+                    .to_string_lossy()
+                    .to_string(),
+            )
+            .root();
+            let environment = EnvironmentVc::new(
+                Value::new(ExecutionEnvironment::Browser(
+                    BrowserEnvironment {
+                        dom: true,
+                        web_worker: false,
+                        service_worker: false,
+                        browserslist_query: "Chrome 102".to_string(),
+                    }
+                    .into(),
+                )),
+                Value::new(EnvironmentIntention::Client),
+            )
+            .with_provided_globals(ProvidedGlobalsVc::cell(IndexMap::from([
+                (
+                    "Buffer".to_string(),
+                    ProvidedGlobal {
+                        request: "./shims/buffer.js".to_string(),
+                        export: Some("Buffer".to_string()),
+                    },
+                ),
+                (
+                    "process".to_string(),
+                    ProvidedGlobal {
+                        request: "./shims/process.js".to_string(),
+                        export: None,
+                    },
+                ),
+            ])));
+            let context = ModuleAssetContextVc::new(
+                TransitionsByNameVc::cell(HashMap::new()),
+                environment,
+                ModuleOptionsContext::default().cell(),
+                ResolveOptionsContext::default().cell(),
+            );
+            let module = context.process(SourceAssetVc::new(root.join("index.js")).into());
+            let module = match EcmascriptChunkPlaceableVc::resolve_from(module).await? {
+                Some(module) => module,
+                None => bail!("the entry is not an ECMAScript module"),
+            };
+            let chunking_context = DevChunkingContextVc::builder(
+                root,
+                root,
+                root.join("_chunks"),
+                root.join("_assets"),
+            )
+            .build();
+            let content = module.as_chunk_item(chunking_context).content().await?;
+            Ok(content.inner_code.clone())
+        })
+        .await
+        .unwrap();
+
+    let buffer = r#"__turbopack_require__("[project]/shims/buffer.js (ecmascript)")["Buffer"]"#;
+    assert!(code.contains(&format!("{buffer}.from(")), "{code}");
+    // Shorthand properties keep their name
+    assert!(code.contains(&format!("Buffer: {buffer}")), "{code}");
+    assert!(
+        code.contains(
+            r#"__turbopack_require__("[project]/shims/process.js (ecmascript)").nextTick("#
+        ),
+        "{code}"
+    );
+}
@@ -0,0 +1,2 @@
+console.log(Buffer.from("provided"), { Buffer });
+process.nextTick(() => {});
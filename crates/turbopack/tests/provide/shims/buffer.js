@@ -0,0 +1,5 @@
+exports.Buffer = class Buffer {
+  static from(value) {
+    return new Buffer(value);
+  }
+};
@@ -0,0 +1,5 @@
+module.exports = {
+  nextTick(callback) {
+    Promise.resolve().then(callback);
+  },
+};
@@ -0,0 +1,88 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::{bail, Result};
+use turbo_tasks::TurboTasks;
+use turbo_tasks_fs::{DiskFileSystemVc, FileContent, FileSystem};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    module_options::ModuleOptionsContext, preset::Preset, register,
+    resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::asset::{AssetContent, AssetContentVc};
+
+/// The code of the runtime entries of the React app preset for the fixture
+/// `tests/<fixture>`.
+async fn runtime_entries(fixture: &'static str) -> Vec<String> {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    tt.run_once(async move {
+        let root = DiskFileSystemVc::new(
+            "project".to_string(),
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests")
+                .join(fixture)
+                .to_string_lossy()
+                .to_string(),
+        )
+        .root();
+        let context = ModuleAssetContextVc::new(
+            TransitionsByNameVc::cell(HashMap::new()),
+            Preset::ReactApp.environment("Chrome 102"),
+            ModuleOptionsContext::default().cell(),
+            ResolveOptionsContext {
+                enable_node_modules: true,
+                ..Default::default()
+            }
+            .cell(),
+        );
+        let mut entries = Vec::new();
+        for entry in Preset::ReactApp
+            .runtime_entries(context.into(), root)
+            .await?
+            .iter()
+        {
+            entries.push(read(entry.as_asset().content()).await?);
+        }
+        Ok(entries)
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn sets_up_react_refresh_with_the_runtime_of_the_project() {
+    let entries = runtime_entries("react_refresh").await;
+
+    assert_eq!(entries.len(), 1);
+    assert!(
+        entries[0].contains(r#"import * as RefreshRuntime from "react-refresh/runtime";"#),
+        "{}",
+        entries[0]
+    );
+    assert!(
+        entries[0].contains("self.$RefreshInterceptModuleExecution$ = "),
+        "{}",
+        entries[0]
+    );
+}
+
+#[tokio::test]
+async fn stubs_react_refresh_without_the_runtime() {
+    let entries = runtime_entries("graph").await;
+
+    assert_eq!(
+        entries,
+        vec!["self.$RefreshReg$ = () => {};\nself.$RefreshSig$ = () => (type) => type;\n"]
+    );
+}
+
+async fn read(content: AssetContentVc) -> Result<String> {
+    if let AssetContent::File(file) = &*content.await? {
+        if let FileContent::Content(file) = &*file.await? {
+            return Ok(String::from_utf8(file.content().to_vec())?);
+        }
+    }
+    bail!("the content is not a file")
+}
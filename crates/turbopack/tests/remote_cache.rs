@@ -0,0 +1,92 @@
+use std::{env::temp_dir, fs, path::Path, sync::Arc};
+
+use anyhow::{bail, Result};
+use turbo_tasks::{NothingVc, TransientInstance, TurboTasks};
+use turbo_tasks_fs::{DiskFileSystemVc, File, FileContent, FileSystem};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::register;
+use turbopack_core::{
+    asset::{AssetContent, AssetContentVc},
+    cache::{CacheBackend, DiskCacheBackend, RemoteCacheVc, RemoteCachedSourceVc},
+    source_asset::SourceAssetVc,
+    virtual_asset::VirtualAssetVc,
+};
+
+#[tokio::test]
+async fn caches_transformed_sources() {
+    register();
+
+    let dir = temp_dir().join(format!("turbopack-remote-cache-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let source_dir = dir.join("project");
+    let cache_dir = dir.join("cache");
+    fs::create_dir_all(&source_dir).unwrap();
+    fs::write(source_dir.join("index.css"), "a { color: red }").unwrap();
+
+    // A miss runs the transforms and stores their output
+    assert_transformed(&source_dir, &cache_dir, "first", "first").await;
+    let entries = fs::read_dir(&cache_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    assert_eq!(entries.len(), 1, "{entries:?}");
+    let entry = entries[0].clone();
+
+    // A hit returns the stored output, even though the transforms would
+    // return something else now
+    assert_transformed(&source_dir, &cache_dir, "second", "first").await;
+
+    // A corrupted entry is ignored and replaced
+    let mut stored = fs::read(&entry).unwrap();
+    let last = stored.len() - 1;
+    stored[last] ^= 1;
+    fs::write(&entry, stored).unwrap();
+    assert_transformed(&source_dir, &cache_dir, "second", "second").await;
+    assert_transformed(&source_dir, &cache_dir, "third", "second").await;
+
+    // A changed source is a miss
+    fs::write(source_dir.join("index.css"), "a { color: blue }").unwrap();
+    assert_transformed(&source_dir, &cache_dir, "third", "third").await;
+    assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Checks that the source at `source_dir/index.css`, whose transforms return
+/// `transformed`, has the content `expected`. A new turbo tasks instance is
+/// used, so nothing but the cache directory is shared between the calls.
+async fn assert_transformed(
+    source_dir: &Path,
+    cache_dir: &Path,
+    transformed: &'static str,
+    expected: &'static str,
+) {
+    let source_dir = source_dir.to_string_lossy().to_string();
+    let cache_dir = cache_dir.to_path_buf();
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        let root = DiskFileSystemVc::new("project".to_string(), source_dir).root();
+        let backend: Arc<dyn CacheBackend> = Arc::new(DiskCacheBackend::new(cache_dir));
+        let cache = RemoteCacheVc::new(TransientInstance::new(backend), "test".to_string());
+        let source = SourceAssetVc::new(root.join("index.css"));
+        let transformed_source = VirtualAssetVc::new(
+            root.join("index.transformed.css"),
+            File::from(transformed).into(),
+        );
+        let content = RemoteCachedSourceVc::new(source.into(), transformed_source.into(), cache)
+            .as_asset()
+            .content();
+        assert_eq!(read(content).await?, expected);
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
+
+async fn read(content: AssetContentVc) -> Result<String> {
+    if let AssetContent::File(file) = &*content.await? {
+        if let FileContent::Content(file) = &*file.await? {
+            return Ok(String::from_utf8(file.content().to_vec())?);
+        }
+    }
+    bail!("the content is not a file")
+}
@@ -0,0 +1,600 @@
+use std::{env::temp_dir, fs, path::PathBuf, sync::Mutex};
+
+use anyhow::Result;
+use turbo_tasks::{NothingVc, TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, File, FileSystem, FileSystemPathVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{register, resolve_options, resolve_options_context::ResolveOptionsContext};
+use turbopack_core::{
+    asset::Asset,
+    issue::IssueVc,
+    resolve::{
+        overrides::ModuleOverrides,
+        parse::RequestVc,
+        polyfills::{Polyfill, Polyfills},
+        resolve,
+        trace::{trace_resolve, ResolveStep, ResolveTraceVc},
+        ResolveResult, ResolveResultVc, SpecialType,
+    },
+    virtual_asset::VirtualAssetVc,
+};
+
+/// The root directory of the fixture `tests/resolve/<fixture>`.
+fn fixture_root(fixture: &str) -> FileSystemPathVc {
+    let fixture_root = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/resolve")
+        .join(fixture);
+    DiskFileSystemVc::new(
+        "fixture".to_string(),
+        fixture_root.to_string_lossy().to_string(),
+    )
+    .root()
+}
+
+/// Resolves `request` from the directory `from` of the fixture
+/// `tests/resolve/<fixture>`.
+fn resolve_request(
+    fixture: &str,
+    from: &str,
+    request: &str,
+    options: ResolveOptionsContext,
+) -> ResolveResultVc {
+    let context = fixture_root(fixture).join(from);
+    resolve(
+        context,
+        RequestVc::parse(Value::new(request.to_string().into())),
+        resolve_options(context, options.cell()),
+    )
+}
+
+/// Resolves `request` like [resolve_request] and returns the paths of the
+/// resolved files relative to the fixture, see [resolved_paths].
+async fn resolve_in_fixture(
+    fixture: &str,
+    from: &str,
+    request: &str,
+    options: ResolveOptionsContext,
+) -> Result<Vec<String>> {
+    resolved_paths(resolve_request(fixture, from, request, options)).await
+}
+
+/// The paths of the files `result` resolved to, `<empty>` for an empty module,
+/// or nothing when the request is unresolveable.
+async fn resolved_paths(result: ResolveResultVc) -> Result<Vec<String>> {
+    let assets = match &*result.await? {
+        ResolveResult::Special(SpecialType::Empty, _) => return Ok(vec!["<empty>".to_string()]),
+        ResolveResult::Single(asset, _) => vec![*asset],
+        ResolveResult::Alternatives(assets, _) => assets.clone(),
+        ResolveResult::Keyed(assets, _) => assets.values().copied().collect(),
+        ResolveResult::Special(..) | ResolveResult::Unresolveable(_) => Vec::new(),
+    };
+    let mut paths = Vec::new();
+    for asset in assets {
+        paths.push(asset.path().await?.path.clone());
+    }
+    Ok(paths)
+}
+
+/// Resolves `request` like [resolve_request] and returns the titles of the
+/// issues reported meanwhile.
+async fn resolve_issues_in_fixture(
+    fixture: &str,
+    from: &str,
+    request: &str,
+    options: ResolveOptionsContext,
+) -> Result<Vec<String>> {
+    let result = resolve_request(fixture, from, request, options);
+    result.await?;
+    Ok(IssueVc::peek_issues_with_path(result)
+        .await?
+        .strongly_consistent()
+        .await?
+        .get_plain_issues()
+        .await?
+        .iter()
+        .map(|issue| issue.title.clone())
+        .collect())
+}
+
+/// Resolves `request` like [resolve_request] with tracing enabled and
+/// returns the steps it took.
+fn trace_in_fixture(
+    fixture: &str,
+    from: &str,
+    request: &str,
+    options: ResolveOptionsContext,
+) -> ResolveTraceVc {
+    let context = fixture_root(fixture).join(from);
+    trace_resolve(
+        context,
+        RequestVc::parse(Value::new(request.to_string().into())),
+        resolve_options(context, options.cell()),
+    )
+}
+
+/// Runs `test` in a fresh turbo tasks instance.
+async fn run(test: impl std::future::Future<Output = Result<()>> + Send + 'static) {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_once_task(async move {
+        test.await?;
+        Ok(NothingVc::new().into())
+    });
+    tt.wait_task_completion(task, true).await.unwrap();
+}
+
+#[tokio::test]
+async fn resolves_tsconfig_paths() {
+    run(async {
+        let options = || ResolveOptionsContext {
+            enable_typescript: true,
+            ..Default::default()
+        };
+        // The paths of a tsconfig replace the ones of the tsconfig it extends,
+        // and are relative to `baseUrl`, which is also a module directory
+        assert_eq!(
+            resolve_in_fixture("tsconfig_paths", "", "@app/page", options()).await?,
+            ["src/app/page.ts"]
+        );
+        assert!(
+            resolve_in_fixture("tsconfig_paths", "", "@lib/util", options())
+                .await?
+                .is_empty()
+        );
+        assert_eq!(
+            resolve_in_fixture("tsconfig_paths", "", "lib/util", options()).await?,
+            ["src/lib/util.ts"]
+        );
+        // The nearest tsconfig applies, with paths relative to it without
+        // `baseUrl`
+        assert_eq!(
+            resolve_in_fixture("tsconfig_paths", "nested", "@app/page", options()).await?,
+            ["nested/other/page.ts"]
+        );
+        Ok(())
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn resolves_exports_with_custom_conditions() {
+    run(async {
+        let options = |conditions: &[&str]| ResolveOptionsContext {
+            enable_node_modules: true,
+            custom_conditions: conditions.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        };
+        // The first condition of the exports field which is set applies
+        assert_eq!(
+            resolve_in_fixture("exports", "", "pkg", options(&["worker", "development"])).await?,
+            ["node_modules/pkg/worker.js"]
+        );
+        assert_eq!(
+            resolve_in_fixture("exports", "", "pkg", options(&["development"])).await?,
+            ["node_modules/pkg/dev.js"]
+        );
+        assert_eq!(
+            resolve_in_fixture("exports", "", "pkg/features/a", options(&[])).await?,
+            ["node_modules/pkg/src/features/a.js"]
+        );
+        // Subpaths which aren't exported can't be resolved, even though the
+        // file exists
+        assert!(
+            resolve_in_fixture("exports", "", "pkg/src/internal.js", options(&[]))
+                .await?
+                .is_empty()
+        );
+        assert_eq!(
+            resolve_issues_in_fixture("exports", "", "pkg/src/internal.js", options(&[])).await?,
+            ["Package subpath './src/internal.js' is not exported"]
+        );
+        Ok(())
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn resolves_package_imports() {
+    run(async {
+        let options = |conditions: &[&str]| ResolveOptionsContext {
+            enable_node_modules: true,
+            custom_conditions: conditions.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        };
+        // `#` requests are resolved with the imports field of the package
+        // containing the importing module, to its files or to other packages
+        assert_eq!(
+            resolve_in_fixture("imports", "src", "#internal/a", options(&[])).await?,
+            ["src/internal/a.js"]
+        );
+        assert_eq!(
+            resolve_in_fixture("imports", "src", "#env", options(&[])).await?,
+            ["src/env.js"]
+        );
+        assert_eq!(
+            resolve_in_fixture("imports", "src", "#env", options(&["worker"])).await?,
+            ["src/env-worker.js"]
+        );
+        assert_eq!(
+            resolve_in_fixture("imports", "src", "#dep", options(&[])).await?,
+            ["node_modules/dep/index.js"]
+        );
+        assert!(
+            resolve_in_fixture("imports", "src", "#missing", options(&[]))
+                .await?
+                .is_empty()
+        );
+        Ok(())
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn remaps_files_with_the_browser_field() {
+    run(async {
+        let options = |browser: bool| ResolveOptionsContext {
+            enable_node_modules: true,
+            browser,
+            ..Default::default()
+        };
+        assert_eq!(
+            resolve_in_fixture("browser_field", "", "pkg-main", options(true)).await?,
+            ["node_modules/pkg-main/browser.js"]
+        );
+        assert_eq!(
+            resolve_in_fixture("browser_field", "", "pkg", options(true)).await?,
+            ["node_modules/pkg/browser.js"]
+        );
+        assert_eq!(
+            resolve_in_fixture("browser_field", "", "pkg", options(false)).await?,
+            ["node_modules/pkg/node.js"]
+        );
+        assert_eq!(
+            resolve_in_fixture("browser_field", "", "pkg/lib/server", options(true)).await?,
+            ["node_modules/pkg/lib/client.js"]
+        );
+        // Files and modules mapped to `false` are replaced by an empty module
+        assert_eq!(
+            resolve_in_fixture("browser_field", "", "pkg/lib/fs-helper", options(true)).await?,
+            ["node_modules/pkg/__turbopack_empty_module__.js"]
+        );
+        assert_eq!(
+            resolve_in_fixture("browser_field", "node_modules/pkg", "fs", options(true)).await?,
+            ["node_modules/pkg/__turbopack_empty_module__.js"]
+        );
+        Ok(())
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn redirects_requests_with_aliases() {
+    run(async {
+        let options = || ResolveOptionsContext {
+            enable_node_modules: true,
+            aliases: vec![
+                ("react".to_string(), vec!["preact/compat".to_string()]),
+                ("lodash$".to_string(), vec!["./stub.js".to_string()]),
+                (
+                    "missing".to_string(),
+                    vec!["does-not-exist".to_string(), "./stub.js".to_string()],
+                ),
+            ],
+            ..Default::default()
+        };
+        // Aliases redirect subpaths, too, unless they end with `$`
+        assert_eq!(
+            resolve_in_fixture("aliases", "src", "react", options()).await?,
+            ["node_modules/preact/compat/index.js"]
+        );
+        assert_eq!(
+            resolve_in_fixture("aliases", "src", "react/jsx-runtime", options()).await?,
+            ["node_modules/preact/compat/jsx-runtime.js"]
+        );
+        assert_eq!(
+            resolve_in_fixture("aliases", "src", "lodash", options()).await?,
+            ["src/stub.js"]
+        );
+        assert_eq!(
+            resolve_in_fixture("aliases", "src", "lodash/fp", options()).await?,
+            ["node_modules/lodash/fp.js"]
+        );
+        // The first target which resolves applies
+        assert_eq!(
+            resolve_in_fixture("aliases", "src", "missing", options()).await?,
+            ["src/stub.js"]
+        );
+        Ok(())
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn replaces_modules_with_overrides() {
+    run(async {
+        let root = fixture_root("overrides");
+        let mut overrides = ModuleOverrides::new();
+        overrides.override_request(
+            "axios",
+            VirtualAssetVc::new(root.join("mocks/axios.js"), File::from("").into()).into(),
+        );
+        overrides.override_path(
+            root.join("src/api/client.js"),
+            VirtualAssetVc::new(root.join("mocks/client.js"), File::from("").into()).into(),
+        );
+        let overrides = overrides.cell();
+        let options = |with_overrides: bool| ResolveOptionsContext {
+            enable_node_modules: true,
+            module_overrides: with_overrides.then_some(overrides),
+            ..Default::default()
+        };
+        assert_eq!(
+            resolve_in_fixture("overrides", "src", "axios", options(true)).await?,
+            ["mocks/axios.js"]
+        );
+        // Every request resolving to an overridden file is replaced
+        assert_eq!(
+            resolve_in_fixture("overrides", "src", "./api/client", options(true)).await?,
+            ["mocks/client.js"]
+        );
+        assert_eq!(
+            resolve_in_fixture("overrides", "", "./src/api/client.js", options(true)).await?,
+            ["mocks/client.js"]
+        );
+        // Resolving without the overrides isn't affected by them
+        assert_eq!(
+            resolve_in_fixture("overrides", "src", "axios", options(false)).await?,
+            ["node_modules/axios/index.js"]
+        );
+        assert_eq!(
+            resolve_in_fixture("overrides", "src", "./api/client", options(false)).await?,
+            ["src/api/client.js"]
+        );
+        Ok(())
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn traces_resolve_steps() {
+    run(async {
+        let options = || ResolveOptionsContext {
+            enable_node_modules: true,
+            ..Default::default()
+        };
+        let trace = trace_in_fixture("exports", "", "pkg/features/a", options()).await?;
+        assert!(trace.resolved);
+        assert!(trace.steps.iter().any(|step| matches!(
+            step,
+            ResolveStep::ExportsField { subpath, targets, .. }
+                if subpath == "./features/a" && targets == &["./src/features/a.js"]
+        )));
+        let mut resolved = Vec::new();
+        for step in trace.steps.iter() {
+            if let ResolveStep::Resolved { path } = step {
+                resolved.push(path.await?.path.clone());
+            }
+        }
+        assert_eq!(resolved, ["node_modules/pkg/src/features/a.js"]);
+
+        // The trace explains why a request can't be resolved
+        let trace = trace_in_fixture("exports", "", "pkg/src/internal.js", options());
+        assert!(!trace.await?.resolved);
+        let rendered = trace.render().await?.clone_value();
+        assert!(rendered.contains("failed"), "{rendered}");
+        assert!(
+            rendered.contains("mapped ./src/internal.js to nothing"),
+            "{rendered}"
+        );
+        let trace = trace_in_fixture("exports", "", "missing", options()).await?;
+        assert!(!trace.resolved);
+        assert!(trace.steps.iter().any(|step| matches!(
+            step,
+            ResolveStep::PackageNotFound { module, .. } if module == "missing"
+        )));
+
+        // Tracing doesn't affect the results of resolving without it
+        assert_eq!(
+            resolve_in_fixture("exports", "", "pkg/features/a", options()).await?,
+            ["node_modules/pkg/src/features/a.js"]
+        );
+        Ok(())
+    })
+    .await;
+}
+
+/// The paths `pkg` resolved to in
+/// [resolves_again_when_resolving_fields_change], by execution.
+static RESOLVED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+#[tokio::test]
+async fn resolves_again_when_resolving_fields_change() {
+    register();
+
+    let root = temp_dir().join(format!(
+        "turbopack-resolve-package-json-{}",
+        std::process::id()
+    ));
+    let package_dir = root.join("node_modules/pkg");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&package_dir).unwrap();
+    for file in ["a.js", "b.js"] {
+        fs::write(package_dir.join(file), "").unwrap();
+    }
+    let write_package_json = |version: &str, main: &str| {
+        fs::write(
+            package_dir.join("package.json"),
+            format!(r#"{{ "name": "pkg", "version": "{version}", "main": "{main}" }}"#),
+        )
+        .unwrap();
+    };
+    write_package_json("1.0.0", "./a.js");
+    let root = root.to_string_lossy().to_string();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let task = tt.spawn_root_task({
+        let root = root.clone();
+        move || {
+            let root = root.clone();
+            Box::pin(async move {
+                let context = DiskFileSystemVc::new("package_json".to_string(), root).root();
+                let result = resolve(
+                    context,
+                    RequestVc::parse(Value::new("pkg".to_string().into())),
+                    resolve_options(
+                        context,
+                        ResolveOptionsContext {
+                            enable_node_modules: true,
+                            ..Default::default()
+                        }
+                        .cell(),
+                    ),
+                );
+                if let ResolveResult::Single(asset, _) = &*result.await? {
+                    RESOLVED
+                        .lock()
+                        .unwrap()
+                        .push(asset.path().await?.path.clone());
+                }
+                Ok(NothingVc::new().into())
+            })
+        }
+    });
+    let invalidate = || {
+        let root = root.clone();
+        tt.run_once(async move {
+            DiskFileSystemVc::new("package_json".to_string(), root)
+                .await?
+                .invalidate();
+            Ok(())
+        })
+    };
+    tt.wait_task_completion(task, true).await.unwrap();
+    assert_eq!(
+        RESOLVED.lock().unwrap().last().unwrap(),
+        "node_modules/pkg/a.js"
+    );
+
+    // Fields which resolving doesn't read don't change the result
+    write_package_json("2.0.0", "./a.js");
+    invalidate().await.unwrap();
+    tt.wait_task_completion(task, true).await.unwrap();
+    assert_eq!(
+        RESOLVED.lock().unwrap().last().unwrap(),
+        "node_modules/pkg/a.js"
+    );
+
+    // But the fields it reads do
+    write_package_json("2.0.0", "./b.js");
+    invalidate().await.unwrap();
+    tt.wait_task_completion(task, true).await.unwrap();
+    assert_eq!(
+        RESOLVED.lock().unwrap().last().unwrap(),
+        "node_modules/pkg/b.js"
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn resolves_symlinked_packages_to_their_real_path_unless_preserved() {
+    use std::os::unix::fs::symlink;
+
+    let root = temp_dir().join(format!("turbopack-resolve-symlinks-{}", std::process::id()));
+    let store_dir = root.join(".pnpm/pkg@1.0.0/node_modules/pkg");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&store_dir).unwrap();
+    fs::write(store_dir.join("index.js"), "").unwrap();
+    fs::create_dir_all(root.join("node_modules")).unwrap();
+    fs::create_dir_all(root.join("app/node_modules")).unwrap();
+    symlink(&store_dir, root.join("node_modules/pkg")).unwrap();
+    symlink(&store_dir, root.join("app/node_modules/pkg")).unwrap();
+    let root = root.to_string_lossy().to_string();
+
+    run(async move {
+        let root = DiskFileSystemVc::new("symlinks".to_string(), root).root();
+        let resolve_from = |from: &str, preserve_symlinks: bool| {
+            let context = root.join(from);
+            resolved_paths(resolve(
+                context,
+                RequestVc::parse(Value::new("pkg".to_string().into())),
+                resolve_options(
+                    context,
+                    ResolveOptionsContext {
+                        enable_node_modules: true,
+                        preserve_symlinks,
+                        ..Default::default()
+                    }
+                    .cell(),
+                ),
+            ))
+        };
+        // Both links resolve to the same module by default
+        assert_eq!(
+            resolve_from("", false).await?,
+            [".pnpm/pkg@1.0.0/node_modules/pkg/index.js"]
+        );
+        assert_eq!(
+            resolve_from("app", false).await?,
+            [".pnpm/pkg@1.0.0/node_modules/pkg/index.js"]
+        );
+        // And to a module for each link when symlinks are preserved
+        assert_eq!(resolve_from("", true).await?, ["node_modules/pkg/index.js"]);
+        assert_eq!(
+            resolve_from("app", true).await?,
+            ["app/node_modules/pkg/index.js"]
+        );
+        Ok(())
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn replaces_node_builtins_with_polyfills() {
+    run(async {
+        let mut polyfills = Polyfills::new();
+        polyfills.add("path", Polyfill::Request("path-browserify".to_string()));
+        polyfills.add("buffer", Polyfill::Request("path-browserify".to_string()));
+        polyfills.add("stream", Polyfill::Empty);
+        let polyfills = polyfills.cell();
+        let options = || ResolveOptionsContext {
+            enable_node_modules: true,
+            browser: true,
+            polyfills: Some(polyfills),
+            ..Default::default()
+        };
+        for request in ["path", "node:path"] {
+            assert_eq!(
+                resolve_in_fixture("polyfills", "", request, options()).await?,
+                ["node_modules/path-browserify/index.js"],
+                "{request}"
+            );
+        }
+        assert_eq!(
+            resolve_in_fixture("polyfills", "", "stream", options()).await?,
+            ["<empty>"]
+        );
+        // Packages with the name of a builtin take precedence
+        assert_eq!(
+            resolve_in_fixture("polyfills", "", "buffer", options()).await?,
+            ["node_modules/buffer/index.js"]
+        );
+
+        // Every replacement is reported
+        assert_eq!(
+            resolve_issues_in_fixture("polyfills", "", "path", options()).await?,
+            ["Node.js builtin `path` is polyfilled with `path-browserify`"]
+        );
+        assert_eq!(
+            resolve_issues_in_fixture("polyfills", "", "stream", options()).await?,
+            ["Node.js builtin `stream` is replaced with an empty module"]
+        );
+        assert!(
+            resolve_issues_in_fixture("polyfills", "", "buffer", options())
+                .await?
+                .is_empty()
+        );
+        Ok(())
+    })
+    .await;
+}
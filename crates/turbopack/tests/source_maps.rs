@@ -0,0 +1,118 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::{bail, Result};
+use turbo_tasks::TurboTasks;
+use turbo_tasks_fs::{DiskFileSystemVc, FileContent, FileSystem};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    ecmascript::EcmascriptModuleAssetVc, module_options::ModuleOptionsContext, preset::Preset,
+    register, resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::{AssetContent, AssetContentVc},
+    chunk::dev::DevChunkingContextVc,
+    context::AssetContext,
+    source_asset::SourceAssetVc,
+    source_map::SourceMapEmission,
+};
+
+/// The code of the chunk evaluating `entry` of the `tests/source_maps`
+/// fixture, and the content of the source map file it references.
+async fn chunk_with_source_map(
+    entry: &'static str,
+    emission: SourceMapEmission,
+) -> (String, Option<String>) {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    tt.run_once(async move {
+        let root = DiskFileSystemVc::new(
+            "project".to_string(),
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/source_maps")
+                .to_string_lossy()
+                .to_string(),
+        )
+        .root();
+        let context = ModuleAssetContextVc::new(
+            TransitionsByNameVc::cell(HashMap::new()),
+            Preset::NodeLibrary.environment(""),
+            ModuleOptionsContext::default().cell(),
+            ResolveOptionsContext::default().cell(),
+        );
+        let module = context.process(SourceAssetVc::new(root.join(entry)).into());
+        let module = match EcmascriptModuleAssetVc::resolve_from(module).await? {
+            Some(module) => module,
+            None => bail!("the entry is not an ECMAScript module"),
+        };
+        let chunking_context =
+            DevChunkingContextVc::builder(root, root, root.join("_chunks"), root.join("_assets"))
+                .source_map_emission(emission)
+                .build();
+        let chunk = module.as_evaluated_chunk(chunking_context, None).as_asset();
+
+        let code = read(chunk.content()).await?;
+        let mut source_map = None;
+        for reference in chunk.references().await?.iter() {
+            for asset in reference.resolve_reference().primary_assets().await?.iter() {
+                if asset.path().await?.path.ends_with(".map") {
+                    source_map = Some(read(asset.content()).await?);
+                }
+            }
+        }
+        Ok((code, source_map))
+    })
+    .await
+    .unwrap()
+}
+
+async fn read(content: AssetContentVc) -> Result<String> {
+    if let AssetContent::File(file) = &*content.await? {
+        if let FileContent::Content(file) = &*file.await? {
+            return Ok(String::from_utf8(file.content().to_vec())?);
+        }
+    }
+    bail!("the content is not a file")
+}
+
+#[tokio::test]
+async fn emits_external_source_maps() {
+    let (code, source_map) = chunk_with_source_map("index.js", SourceMapEmission::External).await;
+
+    assert!(code.contains("\n//# sourceMappingURL="), "{code}");
+    assert!(code.trim_end().ends_with(".js.map"), "{code}");
+    let source_map = source_map.expect("the source map is a file next to the chunk");
+    assert!(source_map.contains("index.js"), "{source_map}");
+}
+
+#[tokio::test]
+async fn inlines_source_maps() {
+    let (code, source_map) = chunk_with_source_map("index.js", SourceMapEmission::Inline).await;
+
+    assert!(
+        code.contains("\n//# sourceMappingURL=data:application/json;charset=utf-8;base64,"),
+        "{code}"
+    );
+    assert_eq!(source_map, None);
+}
+
+#[tokio::test]
+async fn hides_source_maps() {
+    let (code, source_map) = chunk_with_source_map("index.js", SourceMapEmission::Hidden).await;
+
+    // The source map is emitted, but the chunk doesn't refer to it
+    assert!(!code.contains("sourceMappingURL"), "{code}");
+    assert!(source_map.is_some());
+}
+
+#[tokio::test]
+async fn chains_input_source_maps() {
+    let (_, source_map) = chunk_with_source_map("compiled.js", SourceMapEmission::External).await;
+
+    // The modules are mapped to the sources they were compiled from, with a
+    // `.map` file and with an inline source map
+    let source_map = source_map.unwrap();
+    assert!(source_map.contains("greet.ts"), "{source_map}");
+    assert!(source_map.contains("farewell.ts"), "{source_map}");
+}
@@ -0,0 +1,4 @@
+import { greet } from "./greet.js";
+import { farewell } from "./farewell.js";
+
+console.log(greet("world"), farewell("world"));
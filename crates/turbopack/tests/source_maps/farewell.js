@@ -0,0 +1,4 @@
+export function farewell(name) {
+    return "Bye " + name;
+}
+//# sourceMappingURL=data:application/json;charset=utf-8;base64,eyJ2ZXJzaW9uIjogMywgImZpbGUiOiAiZmFyZXdlbGwuanMiLCAic291cmNlcyI6IFsiZmFyZXdlbGwudHMiXSwgInNvdXJjZXNDb250ZW50IjogWyJleHBvcnQgZnVuY3Rpb24gZmFyZXdlbGwobmFtZTogc3RyaW5nKSB7XG4gIHJldHVybiBcIkJ5ZSBcIiArIG5hbWU7XG59XG4iXSwgIm5hbWVzIjogW10sICJtYXBwaW5ncyI6ICJBQUFBO0FBQ0E7QUFDQSJ9
@@ -0,0 +1,4 @@
+export function greet(name) {
+    return "Hello " + name;
+}
+//# sourceMappingURL=greet.js.map
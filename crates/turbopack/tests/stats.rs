@@ -0,0 +1,121 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::{bail, Result};
+use indexmap::IndexMap;
+use serde_json::Value as JsonValue;
+use turbo_tasks::TurboTasks;
+use turbo_tasks_fs::{DiskFileSystemVc, FileContent, FileSystem};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    ecmascript::EcmascriptModuleAssetVc, module_options::ModuleOptionsContext, preset::Preset,
+    register, resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::{AssetContent, AssetContentVc},
+    chunk::{
+        dev::DevChunkingContextVc, resource_hints::EntryChunkGroupsVc, stats::StatsAssetVc,
+        ChunkGroupVc,
+    },
+    context::AssetContext,
+    source_asset::SourceAssetVc,
+};
+
+#[tokio::test]
+async fn describes_chunks_and_modules() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let stats = tt
+        .run_once(async move {
+            let root = DiskFileSystemVc::new(
+                "project".to_string(),
+                PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                    .join("tests/graph")
+                    .to_string_lossy()
+                    .to_string(),
+            )
+            .root();
+            let context = ModuleAssetContextVc::new(
+                TransitionsByNameVc::cell(HashMap::new()),
+                Preset::NodeLibrary.environment(""),
+                ModuleOptionsContext::default().cell(),
+                ResolveOptionsContext::default().cell(),
+            );
+            let module = context.process(SourceAssetVc::new(root.join("index.js")).into());
+            let module = match EcmascriptModuleAssetVc::resolve_from(module).await? {
+                Some(module) => module,
+                None => bail!("the entry is not an ECMAScript module"),
+            };
+            let output_root = root.join("dist");
+            let chunking_context = DevChunkingContextVc::builder(
+                root,
+                output_root,
+                output_root.join("_chunks"),
+                output_root.join("_assets"),
+            )
+            .build();
+            let chunk_group =
+                ChunkGroupVc::from_chunk(module.as_evaluated_chunk(chunking_context, None));
+            let stats = StatsAssetVc::new(
+                output_root.join("stats.json"),
+                output_root,
+                EntryChunkGroupsVc::cell(IndexMap::from([("main".to_string(), chunk_group)])),
+            );
+            read(stats.as_asset().content()).await
+        })
+        .await
+        .unwrap();
+    let stats: JsonValue = serde_json::from_str(&stats).unwrap();
+
+    let entry_chunks = stats["entrypoints"]["main"]["chunks"].as_array().unwrap();
+    assert!(!entry_chunks.is_empty(), "{stats:#}");
+    assert_eq!(
+        stats["assetsByChunkName"]["main"].as_array().unwrap().len(),
+        entry_chunks.len(),
+        "{stats:#}"
+    );
+    let chunks = stats["chunks"].as_array().unwrap();
+    let entry_chunk = chunks
+        .iter()
+        .find(|chunk| chunk["id"] == entry_chunks[0])
+        .unwrap();
+    assert_eq!(entry_chunk["entry"], true, "{stats:#}");
+    assert_eq!(entry_chunk["initial"], true, "{stats:#}");
+    assert_eq!(
+        entry_chunk["names"],
+        serde_json::json!(["main"]),
+        "{stats:#}"
+    );
+
+    let modules = stats["modules"].as_array().unwrap();
+    let module = |name: &str| {
+        modules
+            .iter()
+            .find(|module| module["name"] == name)
+            .unwrap_or_else(|| panic!("{name} is included in {stats:#}"))
+    };
+    let index = module("./index.js");
+    assert!(index["size"].as_u64().unwrap() > 0);
+    assert!(index["chunks"]
+        .as_array()
+        .unwrap()
+        .contains(&entry_chunks[0]));
+    // Modules are included for the modules referencing them
+    let reasons = module("./shared.js")["reasons"].as_array().unwrap();
+    assert!(
+        reasons
+            .iter()
+            .any(|reason| reason["moduleName"] == "./a.js"),
+        "{stats:#}"
+    );
+}
+
+async fn read(content: AssetContentVc) -> Result<String> {
+    if let AssetContent::File(file) = &*content.await? {
+        if let FileContent::Content(file) = &*file.await? {
+            return Ok(String::from_utf8(file.content().to_vec())?);
+        }
+    }
+    bail!("the content is not a file")
+}
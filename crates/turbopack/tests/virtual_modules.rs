@@ -0,0 +1,73 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use turbo_tasks::{TurboTasks, ValueToString};
+use turbo_tasks_fs::{DiskFileSystemVc, File, FileSystem};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    module_options::ModuleOptionsContext, preset::Preset, register,
+    resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    asset::AssetsVc, context::AssetContext, reference::graph::ModuleGraphVc,
+    resolve::virtual_modules::VirtualModules, virtual_asset::VirtualAssetVc,
+};
+
+#[tokio::test]
+async fn resolves_virtual_modules_by_their_specifier() {
+    register();
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let modules = tt
+        .run_once(async move {
+            let root = DiskFileSystemVc::new(
+                "project".to_string(),
+                PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                    .join("tests/graph")
+                    .to_string_lossy()
+                    .to_string(),
+            )
+            .root();
+            // The relative import is resolved from the directory of the path
+            // of the virtual module, which doesn't exist
+            let mut virtual_modules = VirtualModules::new();
+            virtual_modules.add(
+                "virtual:routes",
+                root.join(".virtual/routes.js"),
+                File::from("export { a } from \"../a.js\";").into(),
+            );
+            let context = ModuleAssetContextVc::new(
+                TransitionsByNameVc::cell(HashMap::new()),
+                Preset::NodeLibrary.environment(""),
+                ModuleOptionsContext::default().cell(),
+                ResolveOptionsContext {
+                    virtual_modules: Some(virtual_modules.cell()),
+                    ..Default::default()
+                }
+                .cell(),
+            );
+            let entry = VirtualAssetVc::new(
+                root.join("entry.js"),
+                File::from("import { a } from \"virtual:routes\";\nconsole.log(a);").into(),
+            );
+            let module = context.process(entry.into());
+            let graph = ModuleGraphVc::from_roots(AssetsVc::cell(vec![module])).await?;
+            let mut modules = Vec::new();
+            for module in graph.modules.iter() {
+                modules.push(module.path().to_string().await?.clone_value());
+            }
+            Ok(modules)
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        modules,
+        vec![
+            "[project]/entry.js",
+            "[project]/.virtual/routes.js",
+            "[project]/a.js",
+            "[project]/shared.js",
+        ]
+    );
+}